@@ -5,6 +5,7 @@ use figment::{
     Figment,
 };
 use probe_rs::WireProtocol;
+use probe_rs_cli_util::rtt_log::RotationConfig;
 use probe_rs_rtt::ChannelMode;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -34,6 +35,8 @@ pub struct Probe {
     pub usb_vid: Option<String>,
     pub usb_pid: Option<String>,
     pub serial: Option<String>,
+    pub usb_port_path: Option<String>,
+    pub alias: Option<String>,
     pub protocol: WireProtocol,
     pub speed: Option<u32>,
 }
@@ -86,10 +89,15 @@ pub struct Rtt {
     pub timeout: usize,
     /// Whether to show timestamps in RTTUI
     pub show_timestamps: bool,
-    /// Whether to save rtt history buffer on exit to file named history.txt
+    /// Whether to continuously write the data received on all channels to log files.
     pub log_enabled: bool,
-    /// Where to save rtt history buffer relative to manifest path.
+    /// Where to save the rtt log files relative to manifest path.
     pub log_path: PathBuf,
+    /// When to start a new log file.
+    #[serde(default)]
+    pub log_rotation: RotationConfig,
+    /// Whether to reconnect to the target when the connection to the probe or the target is lost.
+    pub reconnect: bool,
 }
 
 /// The gdb config struct holding all the possible gdb options.
@@ -181,6 +189,11 @@ impl Configs {
                         defined_profiles.join(", ")
                     );
                 }
+
+                for channel in &config.rtt.channels {
+                    channel.validate()?;
+                }
+
                 Ok(config)
             }
         }
@@ -204,6 +217,8 @@ impl Configs {
 #[cfg(test)]
 mod test {
     use super::Configs;
+    use figment::providers::{Format, Toml};
+    use probe_rs_rtt::framing::FrameFormat;
 
     #[test]
     fn default_profile() {
@@ -223,4 +238,37 @@ mod test {
         let configs = Configs::new_with_test_data(std::env::current_dir().unwrap());
         let _superfluous: anyhow::Error = configs.select_defined("default").unwrap_err();
     }
+    #[test]
+    fn framing_requires_binary_format() {
+        let mut configs = Configs::new(std::env::current_dir().unwrap());
+        configs.figment = configs.figment.merge(
+            Toml::string(
+                r#"
+            [default.rtt]
+               channels = [
+                   { up = 0, format = "String", framing = "Cobs" },
+               ]
+               "#,
+            )
+            .nested(),
+        );
+        let _framed_string: anyhow::Error = configs.select_defined("default").unwrap_err();
+    }
+    #[test]
+    fn framing_with_binary_format() {
+        let mut configs = Configs::new(std::env::current_dir().unwrap());
+        configs.figment = configs.figment.merge(
+            Toml::string(
+                r#"
+            [default.rtt]
+               channels = [
+                   { up = 0, format = "BinaryLE", framing = "Cobs" },
+               ]
+               "#,
+            )
+            .nested(),
+        );
+        let config = configs.select_defined("default").unwrap();
+        assert_eq!(config.rtt.channels[0].framing, Some(FrameFormat::Cobs));
+    }
 }
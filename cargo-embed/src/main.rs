@@ -8,7 +8,6 @@ use anyhow::{anyhow, Context, Result};
 use colored::*;
 use std::{
     env, fs,
-    fs::File,
     io::Write,
     panic,
     path::Path,
@@ -21,7 +20,7 @@ use time::OffsetDateTime;
 use probe_rs::{
     config::TargetSelector,
     flashing::{download_file_with_options, DownloadOptions, FlashProgress, Format, ProgressEvent},
-    DebugProbeSelector, Permissions, Probe,
+    Core, DebugProbeSelector, Permissions, Probe, Session,
 };
 #[cfg(feature = "sentry")]
 use probe_rs_cli_util::logging::{ask_to_log_crash, capture_anyhow, capture_panic};
@@ -32,6 +31,7 @@ use probe_rs_cli_util::{
     common_options::CargoOptions,
     indicatif::{MultiProgress, ProgressBar, ProgressStyle},
     logging::{self, Metadata},
+    probe_aliases,
 };
 
 use probe_rs_rtt::{Rtt, ScanRegion};
@@ -40,6 +40,9 @@ use probe_rs_gdb_server::GdbInstanceConfiguration;
 
 use crate::rttui::channel::DataFormat;
 
+/// How long to wait for the probe in a single attempt to reconnect to the target.
+const RECONNECT_ATTEMPT_TIMEOUT: Duration = Duration::from_millis(500);
+
 lazy_static::lazy_static! {
     static ref METADATA: Arc<Mutex<Metadata>> = Arc::new(Mutex::new(Metadata {
         release: meta::CARGO_VERSION.to_string(),
@@ -65,7 +68,9 @@ struct Opt {
     #[clap(
         long = "probe",
         help = "Use this flag to select a specific probe in the list.\n\
-        Use '--probe VID:PID' or '--probe VID:PID:Serial' if you have more than one probe with the same VID:PID."
+        Use '--probe VID:PID' or '--probe VID:PID:Serial' if you have more than one probe with the same VID:PID.\n\
+        Use '--probe VID:PID@Port' to select a probe by its USB port, or '--probe <alias>' to use a probe alias.",
+        value_parser = probe_aliases::parse_probe_selector
     )]
     probe_selector: Option<DebugProbeSelector>,
     #[clap(name = "list-chips", long = "list-chips")]
@@ -203,6 +208,8 @@ fn main_try() -> Result<()> {
     // If we got a probe selector in the config, open the probe matching the selector if possible.
     let mut probe = if let Some(selector) = opt.probe_selector {
         Probe::open(selector)?
+    } else if let Some(alias) = &config.probe.alias {
+        Probe::open(probe_aliases::resolve_alias(alias).map_err(|e| anyhow!(e))?)?
     } else {
         match (config.probe.usb_vid.as_ref(), config.probe.usb_pid.as_ref()) {
             (Some(vid), Some(pid)) => {
@@ -210,6 +217,7 @@ fn main_try() -> Result<()> {
                     vendor_id: u16::from_str_radix(vid, 16)?,
                     product_id: u16::from_str_radix(pid, 16)?,
                     serial_number: config.probe.serial.clone(),
+                    usb_port_path: config.probe.usb_port_path.clone(),
                 };
                 // if two probes with the same VID:PID pair exist we just choose one
                 Probe::open(selector)?
@@ -516,65 +524,30 @@ fn main_try() -> Result<()> {
 
         let mut i = 1;
 
+        let rtt_header_address = match fs::read(path)
+            .ok()
+            .and_then(|elf| probe_rs_rtt::find_rtt_symbol(&elf))
+        {
+            Some(address) => ScanRegion::Exact(address),
+            None => {
+                log::warn!(
+                    "No RTT header info was present in the ELF file. Does your firmware run RTT?"
+                );
+                ScanRegion::Ram
+            }
+        };
+
         while (t.elapsed().as_millis() as usize) < config.rtt.timeout {
             log::info!("Initializing RTT (attempt {})...", i);
             i += 1;
 
-            let rtt_header_address = if let Ok(mut file) = File::open(path) {
-                if let Some(address) = rttui::app::App::get_rtt_symbol(&mut file) {
-                    ScanRegion::Exact(address as u32)
-                } else {
-                    ScanRegion::Ram
-                }
-            } else {
-                ScanRegion::Ram
-            };
-
             let mut session_handle = session.lock().unwrap();
             let memory_map = session_handle.target().memory_map.clone();
             let mut core = session_handle.core(0)?;
 
             match Rtt::attach_region(&mut core, &memory_map, &rtt_header_address) {
                 Ok(mut rtt) => {
-                    // RTT supports three different "modes" for channels, which
-                    // describe how the firmware should handle writes that won't
-                    // fit in the available buffer.  The config file can
-                    // optionally specify a mode to use for all up channels,
-                    // and/or a mode for specific channels.
-                    let default_up_mode = config.rtt.up_mode;
-
-                    for up_channel in rtt.up_channels().iter() {
-                        let mut specific_mode = None;
-                        for channel_config in config
-                            .rtt
-                            .channels
-                            .iter()
-                            .filter(|ch_conf| ch_conf.up == Some(up_channel.number()))
-                        {
-                            if let Some(mode) = channel_config.up_mode {
-                                if specific_mode.is_some()
-                                    && specific_mode != channel_config.up_mode
-                                {
-                                    // Can't safely resolve this generally...
-                                    return Err(anyhow!("Conflicting modes specified for RTT up channel {}: {:?} and {:?}",
-                                        up_channel.number(), specific_mode.unwrap(), mode));
-                                }
-
-                                specific_mode = Some(mode);
-                            }
-                        }
-
-                        if let Some(mode) = specific_mode.or(default_up_mode) {
-                            // Only set the mode when the config file says to,
-                            // when not set explicitly, the firmware picks.
-                            log::debug!(
-                                "Setting RTT channel {} to {:?}",
-                                up_channel.number(),
-                                &mode
-                            );
-                            up_channel.set_mode(&mut core, mode)?;
-                        }
-                    }
+                    configure_rtt_modes(&mut core, &mut rtt, &config)?;
 
                     drop(core);
                     drop(session_handle);
@@ -602,15 +575,48 @@ fn main_try() -> Result<()> {
                         OffsetDateTime::now_local()?.unix_timestamp_nanos() / 1_000_000;
 
                     let logname = format!("{name}_{chip_name}_{timestamp_millis}");
+                    let rtt_ptr = rtt.ptr();
                     let mut app = rttui::app::App::new(rtt, &config, logname)?;
+                    let mut connected = true;
                     loop {
                         let mut session_handle = session.lock().unwrap();
-                        let mut core = session_handle.core(0)?;
 
-                        app.poll_rtt(&mut core)?;
+                        if !connected {
+                            connected = try_reconnect(&mut session_handle, rtt_ptr, &config)?;
+                        }
+
+                        let mut core = if connected {
+                            match session_handle.core(0) {
+                                Ok(core) => Some(core),
+                                Err(err) if config.rtt.reconnect && err.is_connection_lost() => {
+                                    log::warn!("Connection to the target lost, reconnecting...");
+                                    connected = false;
+                                    None
+                                }
+                                Err(err) => return Err(err.into()),
+                            }
+                        } else {
+                            None
+                        };
+
+                        if let Some(core) = core.as_mut() {
+                            if let Err(err) = app.poll_rtt(core, &defmt_state) {
+                                match err.downcast_ref::<probe_rs_rtt::Error>() {
+                                    Some(err)
+                                        if config.rtt.reconnect && err.is_connection_lost() =>
+                                    {
+                                        log::warn!(
+                                            "Connection to the target lost, reconnecting..."
+                                        );
+                                        connected = false;
+                                    }
+                                    _ => return Err(err),
+                                }
+                            }
+                        }
 
                         app.render(&defmt_state);
-                        if app.handle_event(&mut core) {
+                        if app.handle_event(core.as_mut()) {
                             logging::println("Shutting down.");
                             return Ok(());
                         };
@@ -642,6 +648,92 @@ fn main_try() -> Result<()> {
     Ok(())
 }
 
+/// Set the modes of the RTT up channels as specified in the config.
+fn configure_rtt_modes(core: &mut Core, rtt: &mut Rtt, config: &config::Config) -> Result<()> {
+    // RTT supports three different "modes" for channels, which
+    // describe how the firmware should handle writes that won't
+    // fit in the available buffer.  The config file can
+    // optionally specify a mode to use for all up channels,
+    // and/or a mode for specific channels.
+    let default_up_mode = config.rtt.up_mode;
+
+    for up_channel in rtt.up_channels().iter() {
+        let mut specific_mode = None;
+        for channel_config in config
+            .rtt
+            .channels
+            .iter()
+            .filter(|ch_conf| ch_conf.up == Some(up_channel.number()))
+        {
+            if let Some(mode) = channel_config.up_mode {
+                if specific_mode.is_some() && specific_mode != channel_config.up_mode {
+                    // Can't safely resolve this generally...
+                    return Err(anyhow!(
+                        "Conflicting modes specified for RTT up channel {}: {:?} and {:?}",
+                        up_channel.number(),
+                        specific_mode.unwrap(),
+                        mode
+                    ));
+                }
+
+                specific_mode = Some(mode);
+            }
+        }
+
+        if let Some(mode) = specific_mode.or(default_up_mode) {
+            // Only set the mode when the config file says to,
+            // when not set explicitly, the firmware picks.
+            log::debug!("Setting RTT channel {} to {:?}", up_channel.number(), &mode);
+            up_channel.set_mode(core, mode)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Try to reconnect to the target after the connection was lost, and restore the RTT channel modes.
+///
+/// Returns `false` if the target is not available yet. The RTT channels of the UI
+/// stay valid, as long as the firmware on the target is not changed.
+fn try_reconnect(session: &mut Session, rtt_ptr: u64, config: &config::Config) -> Result<bool> {
+    if !session.can_reconnect() {
+        return Err(anyhow!(
+            "The probe was not opened using a selector, and can not be reopened"
+        ));
+    }
+
+    if let Err(err) = session.reconnect(RECONNECT_ATTEMPT_TIMEOUT) {
+        log::debug!("Failed to reconnect: {}", err);
+        return Ok(false);
+    }
+
+    let memory_map = session.target().memory_map.clone();
+    let mut core = session.core(0)?;
+
+    // The target might have been reset, so wait for the firmware to initialize RTT again.
+    let start = Instant::now();
+    loop {
+        match Rtt::attach_region(&mut core, &memory_map, &ScanRegion::Exact(rtt_ptr)) {
+            Ok(mut rtt) => {
+                configure_rtt_modes(&mut core, &mut rtt, config)?;
+                break;
+            }
+            Err(err) if (start.elapsed().as_millis() as usize) < config.rtt.timeout => {
+                log::debug!("Failed to attach to RTT after reconnecting: {}", err);
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(err) => {
+                log::warn!("Failed to attach to RTT after reconnecting: {}", err);
+                break;
+            }
+        }
+    }
+
+    log::info!("Reconnected to the target.");
+
+    Ok(true)
+}
+
 fn print_families() -> Result<()> {
     logging::println("Available chips:");
     for family in
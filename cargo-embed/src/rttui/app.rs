@@ -5,12 +5,10 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use probe_rs::Core;
+use probe_rs_cli_util::rtt::hex_dump;
+use probe_rs_cli_util::rtt_log::ChannelLogger;
 use probe_rs_rtt::RttChannel;
-use std::{fmt::write, path::PathBuf, sync::mpsc::RecvTimeoutError};
-use std::{
-    io::{Read, Seek, Write},
-    time::Duration,
-};
+use std::{sync::mpsc::RecvTimeoutError, time::Duration};
 use tui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
@@ -34,8 +32,6 @@ pub struct App {
 
     terminal: Terminal<CrosstermBackend<std::io::Stdout>>,
     events: Events,
-    history_path: Option<PathBuf>,
-    logname: String,
 }
 
 fn pull_channel<C: RttChannel>(channels: &mut Vec<C>, n: usize) -> Option<C> {
@@ -66,6 +62,7 @@ impl App {
                     channel.name.clone(),
                     config.rtt.show_timestamps,
                     channel.format,
+                    channel.framing,
                 ))
             }
         } else {
@@ -79,6 +76,7 @@ impl App {
                     None,
                     config.rtt.show_timestamps,
                     DataFormat::String,
+                    None,
                 ));
             }
 
@@ -89,6 +87,7 @@ impl App {
                     None,
                     config.rtt.show_timestamps,
                     DataFormat::String,
+                    None,
                 ));
             }
         }
@@ -110,50 +109,29 @@ impl App {
         let mut terminal = Terminal::new(backend).unwrap();
         let _ = terminal.hide_cursor();
 
-        let history_path = {
-            if !config.rtt.log_enabled {
-                None
-            } else {
-                //when is the right time if ever to fail if the directory or file cant be created?
-                //should we create the path on startup or when we write
-                match std::fs::create_dir_all(&config.rtt.log_path) {
-                    Ok(_) => Some(config.rtt.log_path.clone()),
-                    Err(_) => {
-                        log::warn!("Could not create log directory");
-                        None
-                    }
+        if config.rtt.log_enabled {
+            for (i, tab) in tabs.iter_mut().enumerate() {
+                let name = format!("{logname}_channel{i}");
+                match ChannelLogger::new(
+                    &config.rtt.log_path,
+                    &name,
+                    tab.format().into(),
+                    &config.rtt.log_rotation,
+                ) {
+                    Ok(logger) => tab.set_logger(logger),
+                    Err(err) => log::warn!("Could not create log file for channel {}: {}", i, err),
                 }
             }
-        };
+        }
 
         Ok(Self {
             tabs,
             current_tab: 0,
             terminal,
             events,
-            history_path,
-            logname,
         })
     }
 
-    pub fn get_rtt_symbol<T: Read + Seek>(file: &mut T) -> Option<u64> {
-        let mut buffer = Vec::new();
-        if file.read_to_end(&mut buffer).is_ok() {
-            if let Ok(binary) = goblin::elf::Elf::parse(buffer.as_slice()) {
-                for sym in &binary.syms {
-                    if let Some(name) = binary.strtab.get_at(sym.st_name) {
-                        if name == "_SEGGER_RTT" {
-                            return Some(sym.st_value);
-                        }
-                    }
-                }
-            }
-        }
-
-        log::warn!("No RTT header info was present in the ELF file. Does your firmware run RTT?");
-        None
-    }
-
     pub fn render(
         &mut self,
         defmt_state: &Option<(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
@@ -163,6 +141,7 @@ impl App {
         let scroll_offset = self.current_tab().scroll_offset();
         let messages = self.current_tab().messages().clone();
         let data = self.current_tab().data().clone();
+        let is_framed = self.current_tab().is_framed();
 
         log::debug!("Data length: {}", data.len());
 
@@ -279,14 +258,11 @@ impl App {
 
                         // probably pretty bad
                         match binle_or_defmt {
+                            DataFormat::BinaryLE if is_framed => {
+                                messages_wrapped = messages.clone();
+                            }
                             DataFormat::BinaryLE => {
-                                messages_wrapped.push(data.iter().fold(
-                                    String::new(),
-                                    |mut output, byte| {
-                                        let _ = write(&mut output, format_args!("{byte:#04x}, "));
-                                        output
-                                    },
-                                ));
+                                messages_wrapped = hex_dump(0, &data);
                             }
                             DataFormat::Defmt => {
                                 let (table, locs) = defmt_state.as_ref().expect(
@@ -354,76 +330,13 @@ impl App {
     }
 
     /// Returns true if the application should exit.
-    pub fn handle_event(&mut self, core: &mut Core) -> bool {
+    pub fn handle_event(&mut self, core: Option<&mut Core>) -> bool {
         match self.events.next(Duration::from_millis(10)) {
             Ok(event) => match event.code {
                 KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => {
                     clean_up_terminal();
                     let _ = self.terminal.show_cursor();
 
-                    if let Some(path) = &self.history_path {
-                        for (i, tab) in self.tabs.iter().enumerate() {
-                            if tab.format() == DataFormat::Defmt {
-                                eprintln!("Not saving tab {} as saving defmt logs is currently unsupported.", i + 1);
-                                continue;
-                            }
-
-                            let extension = match tab.format() {
-                                DataFormat::String => "txt",
-                                DataFormat::BinaryLE => "dat",
-                                DataFormat::Defmt => unreachable!(),
-                            };
-
-                            let name = format!("{}_channel{}.{}", self.logname, i, extension);
-                            let sanitize_options = sanitize_filename::Options {
-                                replacement: "_",
-                                ..Default::default()
-                            };
-                            let sanitized_name =
-                                sanitize_filename::sanitize_with_options(name, sanitize_options);
-                            let final_path = path.join(sanitized_name);
-
-                            match std::fs::File::create(&final_path) {
-                                Ok(mut file) => {
-                                    match tab.format() {
-                                        DataFormat::String => {
-                                            for line in tab.messages() {
-                                                match writeln!(file, "{line}") {
-                                                    Ok(_) => {}
-                                                    Err(e) => {
-                                                        eprintln!(
-                                                            "\nError writing log channel {i}: {e}"
-                                                        );
-                                                        continue;
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        DataFormat::BinaryLE => match file.write(tab.data()) {
-                                            Ok(_) => {}
-                                            Err(e) => {
-                                                eprintln!("\nError writing log channel {i}: {e}");
-                                                continue;
-                                            }
-                                        },
-                                        DataFormat::Defmt => unreachable!(),
-                                    };
-
-                                    // Flush file
-                                    if let Err(e) = file.flush() {
-                                        eprintln!("Error writing log channel {i}: {e}")
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!(
-                                        "\nCould not create log file {}: {}",
-                                        final_path.display(),
-                                        e
-                                    );
-                                }
-                            }
-                        }
-                    }
                     true
                 }
                 KeyCode::Char('l') if event.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -438,7 +351,10 @@ impl App {
                     false
                 }
                 KeyCode::Enter => {
-                    self.push_rtt(core);
+                    // Input is kept until the target is connected again.
+                    if let Some(core) = core {
+                        self.push_rtt(core);
+                    }
                     false
                 }
                 KeyCode::Char(c) => {
@@ -479,11 +395,15 @@ impl App {
     /// Polls the RTT target for new data on all channels.
     ///
     /// # Errors
-    /// If getting the current time or formatting a timestamp fails,
-    /// this function will abort and return a [`time::Error`].
-    pub fn poll_rtt(&mut self, core: &mut Core) -> Result<(), time::Error> {
+    /// If getting the current time or formatting a timestamp fails, or the connection
+    /// to the target is lost, this function will abort and return the error.
+    pub fn poll_rtt(
+        &mut self,
+        core: &mut Core,
+        defmt_state: &Option<(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
+    ) -> Result<()> {
         for channel in self.tabs.iter_mut() {
-            channel.poll_rtt(core)?;
+            channel.poll_rtt(core, defmt_state)?;
         }
 
         Ok(())
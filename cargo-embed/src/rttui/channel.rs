@@ -1,6 +1,8 @@
 use std::fmt;
 
 use probe_rs::Core;
+use probe_rs_cli_util::rtt_log::ChannelLogger;
+use probe_rs_rtt::framing::{FrameFormat, Framing};
 use probe_rs_rtt::{ChannelMode, DownChannel, UpChannel};
 use time::{macros::format_description, OffsetDateTime};
 
@@ -11,6 +13,16 @@ pub enum DataFormat {
     Defmt,
 }
 
+impl From<DataFormat> for probe_rs_cli_util::rtt::DataFormat {
+    fn from(format: DataFormat) -> Self {
+        match format {
+            DataFormat::String => Self::String,
+            DataFormat::BinaryLE => Self::BinaryLE,
+            DataFormat::Defmt => Self::Defmt,
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ChannelConfig {
     pub up: Option<usize>,
@@ -18,6 +30,27 @@ pub struct ChannelConfig {
     pub name: Option<String>,
     pub up_mode: Option<ChannelMode>,
     pub format: DataFormat,
+    /// Splits binary data into frames, and frames the input sent to the down channel.
+    #[serde(default)]
+    pub framing: Option<FrameFormat>,
+}
+
+impl ChannelConfig {
+    /// Checks for options which can't be combined.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.framing.is_some() && self.format != DataFormat::BinaryLE {
+            anyhow::bail!(
+                "RTT channel {} uses framing, which is only supported for the BinaryLE format",
+                self.name
+                    .clone()
+                    .or_else(|| self.up.map(|up| up.to_string()))
+                    .or_else(|| self.down.map(|down| down.to_string()))
+                    .unwrap_or_default()
+            );
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -36,6 +69,11 @@ pub struct ChannelState {
     scroll_offset: usize,
     rtt_buffer: RttBuffer,
     show_timestamps: bool,
+    /// Writes all received data to disk when logging is enabled.
+    logger: Option<ChannelLogger>,
+    /// When set, [DataFormat::BinaryLE] data is split into frames, which are stored in
+    /// [ChannelState::messages].
+    framing: Option<Box<dyn Framing>>,
 }
 
 impl ChannelState {
@@ -45,6 +83,7 @@ impl ChannelState {
         name: Option<String>,
         show_timestamps: bool,
         format: DataFormat,
+        framing: Option<FrameFormat>,
     ) -> Self {
         let name = name
             .or_else(|| up_channel.as_ref().and_then(|up| up.name().map(Into::into)))
@@ -67,9 +106,15 @@ impl ChannelState {
             rtt_buffer: RttBuffer([0u8; 1024]),
             show_timestamps,
             data: Vec::new(),
+            logger: None,
+            framing: framing.map(FrameFormat::framing),
         }
     }
 
+    pub fn set_logger(&mut self, logger: ChannelLogger) {
+        self.logger = Some(logger);
+    }
+
     pub fn has_down_channel(&self) -> bool {
         self.down_channel.is_some()
     }
@@ -108,6 +153,10 @@ impl ChannelState {
         self.format
     }
 
+    pub fn is_framed(&self) -> bool {
+        self.framing.is_some()
+    }
+
     pub fn set_scroll_offset(&mut self, value: usize) {
         self.scroll_offset = value;
     }
@@ -128,11 +177,16 @@ impl ChannelState {
     ///
     /// # Errors
     /// This function can return a [`time::Error`] if getting the local time or formatting a timestamp fails.
-    pub fn poll_rtt(&mut self, core: &mut Core) -> Result<(), time::Error> {
+    pub fn poll_rtt(
+        &mut self,
+        core: &mut Core,
+        defmt_state: &Option<(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
+    ) -> anyhow::Result<()> {
         // TODO: Proper error handling.
         let count = if let Some(channel) = self.up_channel.as_mut() {
             match channel.read(core, self.rtt_buffer.0.as_mut()) {
                 Ok(count) => count,
+                Err(err) if err.is_connection_lost() => return Err(err.into()),
                 Err(err) => {
                     log::error!("\nError reading from RTT: {}", err);
                     return Ok(());
@@ -146,8 +200,14 @@ impl ChannelState {
             return Ok(());
         }
 
-        match self.format {
-            DataFormat::String => {
+        if let Some(logger) = self.logger.as_mut() {
+            if let Err(err) = logger.log(&self.rtt_buffer.0[..count], defmt_state.as_ref()) {
+                log::warn!("Failed to write RTT data of channel {}: {}", self.name, err);
+            }
+        }
+
+        match (self.format, self.framing.as_mut()) {
+            (DataFormat::String, _) => {
                 let now = OffsetDateTime::now_local()?;
 
                 // First, convert the incoming bytes to UTF8.
@@ -180,8 +240,22 @@ impl ChannelState {
                     }
                 }
             }
+            (DataFormat::BinaryLE, Some(framing)) => {
+                framing.received(&self.rtt_buffer.0[..count]);
+
+                while let Some(frame) = framing.decode() {
+                    let message = match frame {
+                        Ok(frame) => format!("[{} bytes] {}", frame.len(), format_hex(&frame)),
+                        Err(err) => format!("Invalid frame: {err}"),
+                    };
+                    self.messages.push(message);
+                    if self.scroll_offset != 0 {
+                        self.scroll_offset += 1;
+                    }
+                }
+            }
             // defmt output is later formatted into strings in [App::render].
-            DataFormat::BinaryLE | DataFormat::Defmt => {
+            (DataFormat::BinaryLE | DataFormat::Defmt, _) => {
                 self.data.extend_from_slice(&self.rtt_buffer.0[..count]);
             }
         };
@@ -190,14 +264,53 @@ impl ChannelState {
     }
 
     pub fn push_rtt(&mut self, core: &mut Core) {
+        if let (Some(down_channel), Some(framing)) = (self.down_channel.as_ref(), &self.framing) {
+            // The input of framed channels is a frame in hex, e.g. `01 02 ff`.
+            let payload = match parse_hex(&self.input) {
+                Some(payload) => payload,
+                None => {
+                    log::error!("\nInvalid frame, expected hex bytes: {}", self.input);
+                    return;
+                }
+            };
+
+            match down_channel.write_frame(core, framing.as_ref(), &payload) {
+                Ok(true) => self.input.clear(),
+                Ok(false) => log::warn!("\nRTT buffer full, frame not sent"),
+                Err(err) => log::error!("\nError writing to RTT: {}", err),
+            }
+            return;
+        }
+
         if let Some(down_channel) = self.down_channel.as_mut() {
             self.input += "\n";
-            down_channel.write(core, self.input.as_bytes()).unwrap();
+            if let Err(err) = down_channel.write(core, self.input.as_bytes()) {
+                log::error!("\nError writing to RTT: {}", err);
+            }
             self.input.clear();
         }
     }
 }
 
+/// Formats bytes as space separated hex.
+fn format_hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses space separated hex bytes, with or without `0x` prefix.
+fn parse_hex(input: &str) -> Option<Vec<u8>> {
+    input
+        .split_whitespace()
+        .map(|byte| {
+            let byte = byte.trim_start_matches("0x").trim_start_matches("0X");
+            u8::from_str_radix(byte, 16).ok()
+        })
+        .collect()
+}
+
 struct RttBuffer([u8; 1024]);
 
 impl fmt::Debug for RttBuffer {
@@ -172,6 +172,12 @@ pub(crate) fn render_diagnostics(error: OperationError) {
                 format!("Try specifying a speed lower than {speed} kHz")
             ],
         ),
+        OperationError::FailedToSelectJtagTap { .. } => (
+            error.to_string(),
+            vec![
+                "You can list the TAPs in the JTAG scan chain using `probe-rs-cli info --jtag-chain`.".into(),
+            ],
+        ),
         OperationError::AttachingFailed { source, connect_under_reset } => match source {
             ProbeRsError::ChipNotFound(RegistryError::ChipAutodetectFailed) => (
                 error.to_string(),
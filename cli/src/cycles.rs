@@ -0,0 +1,326 @@
+//! Measuring the cycles taken by code with the DWT counters.
+
+use super::{CoreOptions, ProbeOptions};
+use anyhow::Context;
+use probe_rs::architecture::arm::component::DwtCounters;
+use probe_rs::Session;
+use probe_rs_cli_util::clap;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Options for measuring the cycles taken by code.
+#[derive(clap::Parser)]
+pub(crate) struct CyclesOptions {
+    /// The ELF file of the firmware, used to look up the addresses of symbols.
+    #[clap(long, value_parser)]
+    elf: Option<PathBuf>,
+
+    /// Measure calls of this function, from its entry until it returns.
+    #[clap(long, requires = "elf", conflicts_with_all = ["start", "end"])]
+    function: Option<String>,
+
+    /// Start measuring when the core reaches this address or symbol.
+    #[clap(long, requires = "end")]
+    start: Option<String>,
+
+    /// Stop measuring when the core reaches this address or symbol.
+    #[clap(long, requires = "start")]
+    end: Option<String>,
+
+    /// The number of measurements.
+    #[clap(long, default_value = "1")]
+    runs: usize,
+
+    /// How long to wait for the core to reach a breakpoint, in milliseconds.
+    #[clap(long, default_value = "5000")]
+    timeout: u64,
+}
+
+/// The code to measure.
+enum Measurement {
+    /// From the entry of the function at the address until it returns.
+    Function(u64),
+    /// From the first address until the second one.
+    Range(u64, u64),
+}
+
+/// Measure the cycles taken by a function call or by the code between two addresses, using
+/// hardware breakpoints and the DWT counters.
+///
+/// The counters don't count while the core is halted, so halting at the breakpoints doesn't
+/// affect the measurement.
+///
+/// # Args
+/// * `shared_options` - Specifies information about which core to measure.
+/// * `common` - Specifies information about the probe to use.
+/// * `options` - Specifies what to measure.
+pub(crate) fn measure_cycles(
+    shared_options: &CoreOptions,
+    common: &ProbeOptions,
+    options: &CyclesOptions,
+) -> anyhow::Result<()> {
+    let elf = options
+        .elf
+        .as_deref()
+        .map(|path| {
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+        })
+        .transpose()?;
+    let symbols = elf
+        .as_deref()
+        .map(goblin::elf::Elf::parse)
+        .transpose()
+        .context("Failed to parse the ELF file")?;
+
+    let resolve = |location: &str| resolve_address(location, symbols.as_ref());
+
+    let measurement = match (&options.function, &options.start, &options.end) {
+        (Some(function), _, _) => Measurement::Function(resolve(function)?),
+        (None, Some(start), Some(end)) => Measurement::Range(resolve(start)?, resolve(end)?),
+        _ => anyhow::bail!("Either --function or --start and --end are required"),
+    };
+
+    let timeout = Duration::from_millis(options.timeout);
+
+    let mut session = common.simple_attach()?;
+    session.enable_dwt_counters(shared_options.core)?;
+
+    let mut results = Vec::with_capacity(options.runs);
+
+    for run in 1..=options.runs {
+        let (start, end) = match measurement {
+            Measurement::Function(address) => {
+                run_to(&mut session, shared_options.core, address, timeout)?;
+
+                // The function hasn't been entered yet, so LR still holds the return address.
+                let mut core = session.core(shared_options.core)?;
+                let return_address: u64 = core.read_core_reg(core.registers().return_address())?;
+
+                // Clear the Thumb bit.
+                (address, return_address & !1)
+            }
+            Measurement::Range(start, end) => {
+                run_to(&mut session, shared_options.core, start, timeout)?;
+                (start, end)
+            }
+        };
+
+        let before = session.read_dwt_counters()?;
+        run_to(&mut session, shared_options.core, end, timeout)?;
+        let counters = session.read_dwt_counters()?.since(&before);
+
+        println!(
+            "Run {run}: {start:#010x} to {end:#010x}: {}",
+            format_counters(&counters)
+        );
+
+        results.push(counters);
+    }
+
+    session.core(shared_options.core)?.run()?;
+
+    print_summary(&results);
+
+    Ok(())
+}
+
+/// Formats the cycles, exception overhead and sleep cycles of a run.
+///
+/// The exception overhead and sleep counters are only 8 bits wide. If the run took 256 cycles
+/// or more, they may have wrapped around, and are only known modulo 256.
+fn format_counters(counters: &DwtCounters) -> String {
+    let mut line = format!(
+        "{} cycles, {} exception overhead cycles, {} sleep cycles",
+        counters.cycles, counters.exception, counters.sleep
+    );
+
+    if counters.profiling_counters_may_have_wrapped() {
+        line.push_str(" (exception overhead and sleep cycles may have wrapped, modulo 256)");
+    }
+
+    line
+}
+
+/// Prints the minimum, mean and maximum of the measured counters.
+///
+/// The exception overhead and sleep cycles are only summarized over the runs in which they
+/// can't have wrapped around.
+fn print_summary(results: &[DwtCounters]) {
+    if results.len() < 2 {
+        return;
+    }
+
+    let cycles: Vec<u64> = results.iter().map(|c| c.cycles as u64).collect();
+    println!("Cycles: {}", format_summary(&cycles));
+
+    let exact: Vec<&DwtCounters> = results
+        .iter()
+        .filter(|c| !c.profiling_counters_may_have_wrapped())
+        .collect();
+
+    if exact.is_empty() {
+        println!(
+            "Exception overhead and sleep cycles: not summarized, they may have wrapped in every run"
+        );
+        return;
+    }
+
+    let exception: Vec<u64> = exact.iter().map(|c| c.exception as u64).collect();
+    let sleep: Vec<u64> = exact.iter().map(|c| c.sleep as u64).collect();
+    println!("Exception overhead cycles: {}", format_summary(&exception));
+    println!("Sleep cycles: {}", format_summary(&sleep));
+}
+
+/// Formats the minimum, mean and maximum of the values, which must not be empty.
+fn format_summary(values: &[u64]) -> String {
+    let min = values.iter().min().copied().unwrap_or_default();
+    let max = values.iter().max().copied().unwrap_or_default();
+    let mean = values.iter().sum::<u64>() as f64 / values.len() as f64;
+
+    format!(
+        "min {min}, mean {mean:.1}, max {max} over {} runs",
+        values.len()
+    )
+}
+
+/// Runs the core until it reaches `address`, using a hardware breakpoint.
+fn run_to(
+    session: &mut Session,
+    core_index: usize,
+    address: u64,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let mut core = session.core(core_index)?;
+
+    core.set_hw_breakpoint(address)?;
+    core.run()?;
+    let halted = core.wait_for_core_halted(timeout);
+    core.clear_hw_breakpoint(address)?;
+
+    halted.with_context(|| format!("The core did not reach {address:#010x} within {timeout:?}"))?;
+
+    // The core may also have halted for another reason, e.g. a breakpoint instruction.
+    let pc: u64 = core.read_core_reg(core.registers().program_counter())?;
+    if pc != address {
+        anyhow::bail!("The core halted at {pc:#010x} before reaching {address:#010x}");
+    }
+
+    Ok(())
+}
+
+/// Parses an address, or looks it up in the symbols of the ELF file.
+///
+/// Symbols can be given by their mangled name, or by their demangled name without the hash.
+fn resolve_address(location: &str, symbols: Option<&goblin::elf::Elf>) -> anyhow::Result<u64> {
+    if let Ok(address) = parse_int::parse::<u64>(location) {
+        return Ok(address);
+    }
+
+    let symbols = symbols.with_context(|| {
+        format!("'{location}' is not an address, and no ELF file was given to look it up")
+    })?;
+
+    for symbol in symbols.syms.iter() {
+        let name = match symbols.strtab.get_at(symbol.st_name) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        if name == location || format!("{:#}", rustc_demangle::demangle(name)) == location {
+            // Clear the Thumb bit of functions.
+            return Ok(if symbol.is_function() {
+                symbol.st_value & !1
+            } else {
+                symbol.st_value
+            });
+        }
+    }
+
+    anyhow::bail!("Symbol '{location}' not found in the ELF file")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resolve(location: &str) -> anyhow::Result<u64> {
+        let elf = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/symbols.elf"
+        ))
+        .unwrap();
+        let symbols = goblin::elf::Elf::parse(&elf).unwrap();
+
+        resolve_address(location, Some(&symbols))
+    }
+
+    #[test]
+    fn format_short_run() {
+        let counters = DwtCounters {
+            cycles: 200,
+            exception: 12,
+            sleep: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format_counters(&counters),
+            "200 cycles, 12 exception overhead cycles, 3 sleep cycles"
+        );
+    }
+
+    #[test]
+    fn format_long_run() {
+        let counters = DwtCounters {
+            cycles: 256,
+            exception: 12,
+            sleep: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format_counters(&counters),
+            "256 cycles, 12 exception overhead cycles, 3 sleep cycles \
+             (exception overhead and sleep cycles may have wrapped, modulo 256)"
+        );
+    }
+
+    #[test]
+    fn summary() {
+        assert_eq!(
+            format_summary(&[10, 20, 40]),
+            "min 10, mean 23.3, max 40 over 3 runs"
+        );
+    }
+
+    #[test]
+    fn resolve_plain_address() {
+        assert_eq!(resolve_address("0x08000400", None).unwrap(), 0x0800_0400);
+        assert_eq!(resolve_address("1024", None).unwrap(), 1024);
+        // Addresses are used as given, without clearing the Thumb bit.
+        assert_eq!(resolve("0x1001").unwrap(), 0x1001);
+    }
+
+    #[test]
+    fn resolve_symbol_names() {
+        assert_eq!(
+            resolve("_ZN7example7measure17h0123456789abcdefE").unwrap(),
+            0x1000
+        );
+        assert_eq!(resolve("example::measure").unwrap(), 0x1000);
+    }
+
+    #[test]
+    fn resolve_keeps_bit_0_of_variables() {
+        assert_eq!(resolve("COUNTER").unwrap(), 0x2000_0001);
+    }
+
+    #[test]
+    fn resolve_unknown_symbol() {
+        assert!(resolve("example::missing").is_err());
+        // The demangled name has to match completely.
+        assert!(resolve("measure").is_err());
+        assert!(resolve_address("example::measure", None).is_err());
+    }
+}
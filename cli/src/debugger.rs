@@ -102,6 +102,23 @@ impl DebugCli {
                             capstone::arch::riscv::ArchExtraMode::RiscVC,
                         ))
                         .build(),
+                    InstructionSet::RV64 => Capstone::new()
+                        .riscv()
+                        .mode(riscvArchMode::RiscV64)
+                        .endian(Endian::Little)
+                        .build(),
+                    InstructionSet::RV64C => Capstone::new()
+                        .riscv()
+                        .mode(riscvArchMode::RiscV64)
+                        .endian(Endian::Little)
+                        .extra_mode(std::iter::once(
+                            capstone::arch::riscv::ArchExtraMode::RiscVC,
+                        ))
+                        .build(),
+                    InstructionSet::Xtensa => {
+                        // Capstone has no Xtensa backend, so we can't disassemble here
+                        return Err(anyhow!("Disassembly is not supported for Xtensa cores").into());
+                    }
                 }
                 .map_err(|err| anyhow!("Error creating capstone: {:?}", err))?;
 
@@ -0,0 +1,124 @@
+//! Decoding of defmt logs received over other transports than RTT.
+
+use anyhow::Context;
+use probe_rs_cli_util::clap;
+use probe_rs_cli_util::defmt::{load_defmt_state, DefmtDecoder};
+use probe_rs_cli_util::defmt_decoder::{Locations, Table};
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Options for decoding defmt data sent by the target.
+#[derive(clap::Parser)]
+pub(crate) struct DefmtOptions {
+    /// Decode the data as defmt log frames, using the defmt table of this ELF file.
+    #[clap(long, value_parser)]
+    pub(crate) defmt: Option<PathBuf>,
+
+    /// Print the source location of every log frame.
+    #[clap(long, requires = "defmt")]
+    pub(crate) show_location: bool,
+}
+
+/// Reads the ELF file and loads its defmt table.
+pub(crate) fn load_table(elf: &Path) -> anyhow::Result<(Table, Option<Locations>)> {
+    let elf_data =
+        std::fs::read(elf).with_context(|| format!("Failed to read {}", elf.display()))?;
+
+    load_defmt_state(&elf_data)?
+        .with_context(|| format!("{} does not contain a defmt table", elf.display()))
+}
+
+/// Decode a defmt byte stream, e.g. from a file, a UART or stdin, and print the log frames.
+///
+/// Reads until the end of the input. Serial ports have to be configured beforehand, e.g. the
+/// baud rate with `stty`.
+pub(crate) fn decode_stream(
+    elf: &Path,
+    input: Option<&Path>,
+    show_location: bool,
+) -> anyhow::Result<()> {
+    let defmt_state = load_table(elf)?;
+    let mut decoder = DefmtDecoder::new(&defmt_state, show_location);
+
+    let input: Box<dyn Read> = match input {
+        Some(path) => Box::new(
+            std::fs::File::open(path)
+                .with_context(|| format!("Failed to open {}", path.display()))?,
+        ),
+        None => Box::new(std::io::stdin()),
+    };
+
+    decode(&mut decoder, input, std::io::stdout())
+}
+
+/// Decodes the data read from `input` until its end, and writes the log frames to `output`.
+fn decode(
+    decoder: &mut DefmtDecoder,
+    mut input: impl Read,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    let mut buffer = [0u8; 1024];
+
+    loop {
+        let count = match input.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(count) => count,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err).context("Failed to read the defmt data"),
+        };
+
+        decoder.received(&buffer[..count]);
+        output.write_all(decoder.decode()?.as_bytes())?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns the data in chunks of at most `chunk_size` bytes, like a slow serial port.
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk_size: usize,
+    }
+
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let count = self.data.len().min(self.chunk_size).min(buf.len());
+            buf[..count].copy_from_slice(&self.data[..count]);
+            self.data = &self.data[count..];
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn decode_split_rzcobs_stream() {
+        let elf = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../probe-rs-cli-util/tests/data/defmt/defmt_rzcobs.o");
+        let defmt_state = load_table(&elf).unwrap();
+
+        // `Hello, 42!` and `Goodbye`, with an additional separator in between
+        let data = [0x2a, 0x7b, 0x00, 0x00, 0x01, 0x7e, 0x00];
+
+        for chunk_size in 1..=data.len() {
+            let mut decoder = DefmtDecoder::new(&defmt_state, false);
+            let input = ChunkedReader {
+                data: &data,
+                chunk_size,
+            };
+
+            let mut output = Vec::new();
+            decode(&mut decoder, input, &mut output).unwrap();
+
+            let output = String::from_utf8(output).unwrap();
+            let lines: Vec<&str> = output.lines().collect();
+            assert_eq!(lines.len(), 2, "{output}");
+            assert!(lines[0].ends_with("Hello, 42!"), "{output}");
+            assert!(lines[1].ends_with("Goodbye"), "{output}");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Mutex;
 use std::time::Duration;
 
@@ -7,6 +8,7 @@ pub fn run_gdb_server(
     common: ProbeOptions,
     connection_string: Option<&str>,
     reset_halt: bool,
+    semihosting_root: Option<PathBuf>,
 ) -> anyhow::Result<()> {
     let mut session = common.simple_attach()?;
 
@@ -18,12 +20,14 @@ pub fn run_gdb_server(
 
     let gdb_connection_string = connection_string.unwrap_or("localhost:1337");
 
-    let instances = probe_rs_gdb_server::GdbInstanceConfiguration::from_session(
+    let mut instances = probe_rs_gdb_server::GdbInstanceConfiguration::from_session(
         &session,
         Some(gdb_connection_string.to_owned()),
     );
 
-    for instance in instances.iter() {
+    for instance in instances.iter_mut() {
+        instance.semihosting.root = semihosting_root.clone();
+
         println!(
             "Firing up GDB stub for {:?} cores at {:?}",
             instance.core_type, instance.socket_addrs
@@ -7,13 +7,14 @@ use probe_rs::{
             ap::{GenericAp, MemoryAp},
             armv6m::Demcr,
             component::Scs,
-            dp::{DPIDR, TARGETID},
+            dp::{targetsel_candidates, DPIDR, TARGETID},
             memory::{Component, CoresightComponent, PeripheralType},
             sequences::DefaultArmSequence,
-            ApAddress, ApInformation, ArmProbeInterface, DpAddress, MemoryApInformation, Register,
+            ApInformation, ArmProbeInterface, DpAddress, MemoryApInformation, Register,
         },
         riscv::communication_interface::RiscvCommunicationInterface,
     },
+    config::CoreAccessOptions,
     MemoryMappedRegister, Probe, WireProtocol,
 };
 
@@ -21,9 +22,21 @@ use anyhow::Result;
 use probe_rs_cli_util::common_options::ProbeOptions;
 use termtree::Tree;
 
-pub(crate) fn show_info_of_device(common: &ProbeOptions) -> Result<()> {
+pub(crate) fn show_info_of_device(
+    common: &ProbeOptions,
+    jtag_chain: bool,
+    swd_multidrop: bool,
+) -> Result<()> {
     let mut probe = common.attach_probe()?;
 
+    if jtag_chain {
+        return show_jtag_chain(probe);
+    }
+
+    if swd_multidrop {
+        return show_swd_multidrop(probe);
+    }
+
     let protocols = if let Some(protocol) = common.protocol {
         vec![protocol]
     } else {
@@ -46,6 +59,92 @@ pub(crate) fn show_info_of_device(common: &ProbeOptions) -> Result<()> {
     Ok(())
 }
 
+fn show_jtag_chain(mut probe: Probe) -> Result<()> {
+    probe.select_protocol(WireProtocol::Jtag)?;
+    probe.attach_to_unspecified()?;
+
+    let chain = probe.scan_jtag_chain();
+
+    probe.detach()?;
+
+    let chain = chain?;
+
+    let mut tree = Tree::new(format!("JTAG scan chain: {} TAP(s)", chain.len()));
+
+    for (index, tap) in chain.iter().enumerate() {
+        let description = match tap.idcode {
+            Some(idcode) => {
+                let manufacturer = jep106::JEP106Code::new(
+                    ((idcode >> 8) & 0xf) as u8,
+                    ((idcode >> 1) & 0x7f) as u8,
+                );
+
+                format!(
+                    "IDCODE: {:#010x}, Manufacturer: {}, Part: {:#06x}, Version: {:#x}",
+                    idcode,
+                    manufacturer.get().unwrap_or("<unknown>"),
+                    (idcode >> 12) & 0xffff,
+                    idcode >> 28
+                )
+            }
+            None => "No IDCODE (BYPASS)".to_string(),
+        };
+
+        tree.push(format!(
+            "TAP {index}: {description}, IR length: {}",
+            tap.irlen
+        ));
+    }
+
+    println!("{tree}");
+
+    Ok(())
+}
+
+fn show_swd_multidrop(mut probe: Probe) -> Result<()> {
+    probe.select_protocol(WireProtocol::Swd)?;
+    probe.attach_to_unspecified()?;
+
+    // Try all instances of the TARGETIDs used by the known targets.
+    let mut targetids = Vec::new();
+    for family in probe_rs::config::families()? {
+        for variant in family.variants() {
+            for core in &variant.cores {
+                if let CoreAccessOptions::Arm(options) = &core.core_access_options {
+                    targetids.extend(options.dp_targetsel());
+                }
+            }
+        }
+    }
+
+    let dps = probe.scan_swd_multidrop(&targetsel_candidates(targetids));
+
+    probe.detach()?;
+
+    let dps = dps?;
+
+    let mut tree = Tree::new(format!("Multidrop SWD bus: {} debug port(s)", dps.len()));
+
+    for dp in dps {
+        let designer_id = dp.targetid.tdesigner();
+        let designer =
+            jep106::JEP106Code::new((designer_id >> 7) as u8, (designer_id & 0x7f) as u8);
+
+        tree.push(format!(
+            "TARGETSEL {:#010x}: Debug Port Version {}, Designer: {}, Part: {:#x}, Instance: {}",
+            dp.targetsel,
+            dp.dpidr.version(),
+            designer.get().unwrap_or("<unknown>"),
+            dp.targetid.tpartno(),
+            dp.dlpidr.tinstance(),
+        ));
+    }
+
+    println!("{tree}");
+
+    Ok(())
+}
+
 fn try_show_info(
     mut probe: Probe,
     protocol: WireProtocol,
@@ -176,13 +275,9 @@ fn show_arm_info(interface: &mut dyn ArmProbeInterface) -> Result<()> {
     let mut tree = Tree::new(dp_node);
 
     let dp = DpAddress::Default;
-    let num_access_ports = interface.num_access_ports(dp).unwrap();
+    let access_ports = interface.access_ports(dp).unwrap();
 
-    for ap_index in 0..num_access_ports {
-        let ap = ApAddress {
-            ap: ap_index as u8,
-            dp,
-        };
+    for ap in access_ports {
         let access_port = GenericAp::new(ap);
 
         let ap_information = interface.ap_information(access_port).unwrap();
@@ -1,9 +1,13 @@
 mod benchmark;
 mod common;
+mod cycles;
 mod debugger;
+mod defmt;
 mod gdb;
 mod info;
+mod profile;
 mod run;
+mod sample;
 mod trace;
 
 include!(concat!(env!("OUT_DIR"), "/meta.rs"));
@@ -15,6 +19,7 @@ use probe_rs::{
     architecture::arm::{component::TraceSink, swo::SwoConfig},
     debug::debug_info::DebugInfo,
     flashing::{erase_all, BinOptions, FileDownloadError, Format},
+    semihosting::SemihostingOptions,
     MemoryInterface, Probe,
 };
 
@@ -23,6 +28,7 @@ use probe_rs_cli_util::{
     clap::Parser,
     common_options::{print_chip_info, print_families, CargoOptions, FlashOptions, ProbeOptions},
     flash::run_flash_download,
+    probe_aliases::ProbeAliases,
 };
 
 use rustyline::Editor;
@@ -54,6 +60,14 @@ enum Cli {
     Info {
         #[clap(flatten)]
         common: ProbeOptions,
+
+        /// Only scan the JTAG chain, and show the IDCODE and IR length of all TAPs on it.
+        #[clap(long)]
+        jtag_chain: bool,
+
+        /// Only scan for debug ports on a multidrop SWD bus, trying the TARGETSEL values of all known targets.
+        #[clap(long, conflicts_with = "jtag_chain")]
+        swd_multidrop: bool,
     },
     /// Resets the target attached to the selected debug probe
     Reset {
@@ -80,6 +94,11 @@ enum Cli {
             help = "Use this flag to reset and halt (instead of just a halt) the attached core after attaching to the target."
         )]
         reset_halt: bool,
+
+        /// Directory in which the target can access files using semihosting.
+        #[clap(long, value_parser)]
+        semihosting_root: Option<PathBuf>,
+
         #[clap(flatten)]
         common: ProbeOptions,
     },
@@ -162,8 +181,22 @@ enum Cli {
         /// Disable double-buffering when downloading flash.  If downloading times out, try this option.
         #[clap(long = "disable-double-buffering")]
         disable_double_buffering: bool,
+
+        /// Directory in which the target can access files using semihosting.
+        ///
+        /// Without this option, semihosting is limited to the console.
+        #[clap(long, value_parser)]
+        semihosting_root: Option<PathBuf>,
+
+        /// Directory to which the data received on all RTT channels is written continuously.
+        #[clap(long, value_parser)]
+        rtt_log: Option<PathBuf>,
+
+        /// Arguments passed to the target, which it can read using semihosting.
+        #[clap(last = true)]
+        args: Vec<String>,
     },
-    /// Trace a memory location on the target
+    /// Trace a memory location on the target, or the instructions executed by it
     #[clap(name = "trace")]
     Trace {
         #[clap(flatten)]
@@ -173,10 +206,16 @@ enum Cli {
         common: ProbeOptions,
 
         /// The address of the memory to dump from the target.
-        #[clap(value_parser = parse_u64)]
-        loc: u64,
+        #[clap(value_parser = parse_u64, required_unless_present = "elf")]
+        loc: Option<u64>,
+
+        #[clap(flatten)]
+        instructions: trace::InstructionTraceOptions,
     },
     /// Configure and monitor ITM trace packets from the target.
+    ///
+    /// Prints the text written to the stimulus ports, exception entry and exit, traced data
+    /// and a profile of the PC samples.
     #[clap(name = "itm")]
     Itm {
         #[clap(flatten)]
@@ -188,9 +227,83 @@ enum Cli {
         #[clap(value_parser = parse_u64)]
         duration_ms: u64,
 
+        /// The stimulus port on which the target sends defmt data, when decoding defmt.
+        #[clap(long, default_value = "0", requires = "defmt")]
+        defmt_port: u8,
+
+        #[clap(flatten)]
+        defmt: defmt::DefmtOptions,
+
+        #[clap(flatten)]
+        viewer: trace::ItmViewerOptions,
+
         #[clap(subcommand)]
         source: ItmSource,
     },
+    /// Decode a defmt byte stream from a file, a serial port or stdin.
+    ///
+    /// Serial ports have to be configured beforehand, e.g. the baud rate with `stty`.
+    #[clap(name = "defmt")]
+    Defmt {
+        /// The ELF file containing the defmt table of the firmware.
+        #[clap(value_parser)]
+        elf: PathBuf,
+
+        /// The file or serial port to read the defmt data from. Reads from stdin if omitted.
+        #[clap(value_parser)]
+        input: Option<PathBuf>,
+
+        /// Print the source location of every log frame.
+        #[clap(long)]
+        show_location: bool,
+    },
+    /// Profile the firmware by sampling the PC, and print the functions it spends the most time in.
+    #[clap(name = "profile")]
+    Profile {
+        #[clap(flatten)]
+        shared: CoreOptions,
+
+        #[clap(flatten)]
+        common: ProbeOptions,
+
+        /// How long to profile, in milliseconds.
+        #[clap(value_parser = parse_u64)]
+        duration_ms: u64,
+
+        #[clap(flatten)]
+        options: profile::ProfileOptions,
+    },
+    /// Measure the cycles taken by a function call or by the code between two addresses.
+    ///
+    /// Uses hardware breakpoints and the DWT cycle counter, exception overhead counter and
+    /// sleep counter.
+    #[clap(name = "cycles")]
+    Cycles {
+        #[clap(flatten)]
+        shared: CoreOptions,
+
+        #[clap(flatten)]
+        common: ProbeOptions,
+
+        #[clap(flatten)]
+        options: cycles::CyclesOptions,
+    },
+    /// Periodically read static variables while the core is running, and write the values as
+    /// CSV or plot them in the terminal.
+    ///
+    /// Variables are looked up by name in the debug info of the ELF file, and can be fields or
+    /// elements of statics, like `CONTROLLER.state.error`.
+    #[clap(name = "sample")]
+    Sample {
+        #[clap(flatten)]
+        shared: CoreOptions,
+
+        #[clap(flatten)]
+        common: ProbeOptions,
+
+        #[clap(flatten)]
+        options: sample::SampleOptions,
+    },
     #[clap(subcommand)]
     Chip(Chip),
     Benchmark {
@@ -288,12 +401,22 @@ fn main() -> Result<()> {
 
     let result = match matches {
         Cli::List {} => list_connected_devices(),
-        Cli::Info { common } => crate::info::show_info_of_device(&common),
+        Cli::Info {
+            common,
+            jtag_chain,
+            swd_multidrop,
+        } => crate::info::show_info_of_device(&common, jtag_chain, swd_multidrop),
         Cli::Gdb {
             gdb_connection_string,
             common,
             reset_halt,
-        } => gdb::run_gdb_server(common, gdb_connection_string.as_deref(), reset_halt),
+            semihosting_root,
+        } => gdb::run_gdb_server(
+            common,
+            gdb_connection_string.as_deref(),
+            reset_halt,
+            semihosting_root,
+        ),
         Cli::Reset {
             shared,
             common,
@@ -332,17 +455,46 @@ fn main() -> Result<()> {
             path,
             chip_erase,
             disable_double_buffering,
-        } => run::run(common, &path, chip_erase, disable_double_buffering),
+            semihosting_root,
+            rtt_log,
+            args,
+        } => {
+            // By convention, the command line starts with the name of the program.
+            let cmdline = std::iter::once(path.clone())
+                .chain(args)
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            run::run(
+                common,
+                &path,
+                chip_erase,
+                disable_double_buffering,
+                SemihostingOptions {
+                    root: semihosting_root,
+                    cmdline,
+                },
+                rtt_log,
+            )
+        }
         Cli::Erase { common } => erase(&common),
         Cli::Trace {
             shared,
             common,
             loc,
-        } => trace_u32_on_target(&shared, &common, loc),
+            instructions,
+        } => match (loc, &instructions.elf) {
+            (Some(loc), _) => trace_u32_on_target(&shared, &common, loc),
+            (None, Some(elf)) => trace::instruction_trace(&shared, &common, elf, &instructions),
+            (None, None) => unreachable!("clap requires either the location or the ELF file"),
+        },
         Cli::Itm {
             shared,
             common,
             duration_ms,
+            defmt_port,
+            defmt,
+            viewer,
             source,
         } => {
             let sink = match source {
@@ -354,8 +506,37 @@ fn main() -> Result<()> {
                 &common,
                 sink,
                 std::time::Duration::from_millis(duration_ms),
+                &defmt,
+                defmt_port,
+                &viewer,
             )
         }
+        Cli::Defmt {
+            elf,
+            input,
+            show_location,
+        } => defmt::decode_stream(&elf, input.as_deref(), show_location),
+        Cli::Cycles {
+            shared,
+            common,
+            options,
+        } => cycles::measure_cycles(&shared, &common, &options),
+        Cli::Sample {
+            shared,
+            common,
+            options,
+        } => sample::sample(&shared, &common, &options),
+        Cli::Profile {
+            shared,
+            common,
+            duration_ms,
+            options,
+        } => profile::profile(
+            &shared,
+            &common,
+            std::time::Duration::from_millis(duration_ms),
+            &options,
+        ),
         Cli::Chip(Chip::List) => print_families(io::stdout()).map_err(Into::into),
         Cli::Chip(Chip::Info { name }) => print_chip_info(name, io::stdout()),
         Cli::Benchmark { common, options } => benchmark(common, options),
@@ -368,13 +549,17 @@ fn main() -> Result<()> {
 
 fn list_connected_devices() -> Result<()> {
     let links = Probe::list_all();
+    let aliases = ProbeAliases::load()?;
 
     if !links.is_empty() {
         println!("The following devices were found:");
         links
             .iter()
             .enumerate()
-            .for_each(|(num, link)| println!("[{num}]: {link:?}"));
+            .for_each(|(num, link)| match aliases.alias_of(link) {
+                Some(alias) => println!("[{num}]: {link:?} (alias: {alias})"),
+                None => println!("[{num}]: {link:?}"),
+            });
     } else {
         println!("No devices were found.");
     }
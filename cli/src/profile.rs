@@ -0,0 +1,396 @@
+//! Statistical profiling of the executed functions by sampling the PC.
+
+use super::{CoreOptions, ProbeOptions};
+use anyhow::Context;
+use itm::{DecoderError, TracePacket};
+use probe_rs::architecture::arm::{
+    armv7m::DwtPcsr,
+    component::{enable_tracing, TraceSink},
+    SwoConfig,
+};
+use probe_rs::debug::debug_info::DebugInfo;
+use probe_rs::{Architecture, Core, MemoryInterface, MemoryMappedRegister};
+use probe_rs_cli_util::clap;
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How the PC is sampled.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SamplingMethod {
+    /// Use `pcsr` if the target supports it, and `halt` otherwise.
+    Auto,
+    /// Read the DWT PC sample register, without halting the core.
+    Pcsr,
+    /// Let the DWT emit PC samples over SWO, without halting the core.
+    Swo,
+    /// Halt the core, read the PC and resume it.
+    Halt,
+}
+
+/// Options for profiling the target.
+#[derive(clap::Parser)]
+pub(crate) struct ProfileOptions {
+    /// The ELF file of the firmware, used to resolve the sampled addresses to functions.
+    #[clap(long, value_parser)]
+    elf: PathBuf,
+
+    /// How the PC is sampled.
+    #[clap(long, value_enum, default_value = "auto")]
+    method: SamplingMethod,
+
+    /// The number of samples taken per second with the `pcsr` and `halt` methods. The actual
+    /// rate may be lower, depending on the speed of the probe.
+    #[clap(long, default_value = "1000")]
+    rate: u32,
+
+    /// Unwind the call stack of every sample, instead of only resolving inlined functions.
+    /// Only supported with the `halt` method, and keeps the core halted for longer.
+    #[clap(long)]
+    call_stacks: bool,
+
+    /// The speed of the clock feeding the TPIU/SWO module in Hz, for the `swo` method.
+    #[clap(long, requires = "swo_baud")]
+    swo_clk: Option<u32>,
+
+    /// The baud rate of the SWO output, for the `swo` method.
+    #[clap(long, requires = "swo_clk")]
+    swo_baud: Option<u32>,
+
+    /// Emit a PC sample about every this many cycles with the `swo` method.
+    #[clap(long, default_value = "1024")]
+    swo_interval: u32,
+
+    /// The number of functions shown in the flat profile.
+    #[clap(long, default_value = "20")]
+    functions: usize,
+
+    /// Write the sampled call stacks in the folded format used by flamegraph tools, like
+    /// `inferno-flamegraph` or `flamegraph.pl`, to this file.
+    #[clap(long, value_parser)]
+    folded: Option<PathBuf>,
+}
+
+/// Profile the application by sampling the PC, and print a flat profile of the executed
+/// functions.
+///
+/// # Args
+/// * `shared_options` - Specifies information about which core to profile.
+/// * `common` - Specifies information about the probe to use.
+/// * `duration` - Specifies the duration to profile for.
+/// * `options` - Specifies how to sample the PC and where to write the results.
+pub(crate) fn profile(
+    shared_options: &CoreOptions,
+    common: &ProbeOptions,
+    duration: Duration,
+    options: &ProfileOptions,
+) -> anyhow::Result<()> {
+    let debug_info = DebugInfo::from_file(&options.elf)
+        .with_context(|| format!("Failed to load the debug info of {}", options.elf.display()))?;
+    let mut profile = Profile::new(Some(debug_info));
+
+    let mut session = common.simple_attach()?;
+
+    let method = {
+        let mut core = session.core(shared_options.core)?;
+        if core.core_halted()? {
+            core.run()?;
+        }
+
+        match options.method {
+            SamplingMethod::Auto if supports_pcsr(&mut core)? => SamplingMethod::Pcsr,
+            SamplingMethod::Auto => SamplingMethod::Halt,
+            SamplingMethod::Pcsr if !supports_pcsr(&mut core)? => {
+                anyhow::bail!(
+                    "The target does not support sampling the PC with the DWT_PCSR register"
+                )
+            }
+            method => method,
+        }
+    };
+
+    if options.call_stacks && method != SamplingMethod::Halt {
+        anyhow::bail!("Unwinding the call stacks is only supported with the `halt` method");
+    }
+
+    let interval = Duration::from_secs(1) / options.rate.max(1);
+
+    match method {
+        SamplingMethod::Pcsr => {
+            println!("Sampling the PC with DWT_PCSR...");
+            let mut core = session.core(shared_options.core)?;
+            sample_periodically(duration, interval, || {
+                let pcsr = DwtPcsr(core.read_word_32(DwtPcsr::ADDRESS)?);
+                if let Some(pc) = pcsr.pc() {
+                    profile.add_sample(Some(pc));
+                }
+                Ok(())
+            })?;
+        }
+        SamplingMethod::Halt => {
+            println!("Sampling the PC by halting the core...");
+            let mut core = session.core(shared_options.core)?;
+            sample_periodically(duration, interval, || {
+                let pc = core.halt(Duration::from_millis(100))?.pc;
+                let result = if options.call_stacks {
+                    profile.add_call_stack(&mut core, pc)
+                } else {
+                    profile.add_sample(Some(pc as u32));
+                    Ok(())
+                };
+
+                // Resume the core before reporting an error, so the target isn't left halted.
+                core.run()?;
+                result
+            })?;
+        }
+        SamplingMethod::Swo => {
+            let (clk, baud) = match (options.swo_clk, options.swo_baud) {
+                (Some(clk), Some(baud)) => (clk, baud),
+                _ => anyhow::bail!("Sampling over SWO requires --swo-clk and --swo-baud"),
+            };
+
+            session.setup_tracing(
+                shared_options.core,
+                TraceSink::Swo(SwoConfig::new(clk).set_baud(baud)),
+            )?;
+            let interval = session.enable_pc_sampling(options.swo_interval)?;
+            println!("Sampling the PC every {interval} cycles over SWO...");
+
+            let result = session.swo_reader().map_err(Into::into).and_then(|reader| {
+                let decoder = itm::Decoder::new(reader, itm::DecoderOptions { ignore_eof: true });
+
+                let start = Instant::now();
+                for packet in decoder.singles() {
+                    if start.elapsed() > duration {
+                        break;
+                    }
+
+                    match packet {
+                        Ok(TracePacket::PCSample { pc }) => profile.add_sample(pc),
+                        Ok(_) => (),
+                        Err(DecoderError::MalformedPacket(malformed)) => {
+                            tracing::warn!("Malformed ITM packet: {malformed}");
+                        }
+                        Err(err) => return Err(err).context("Failed to read the ITM trace"),
+                    }
+                }
+
+                Ok(())
+            });
+
+            // Stop the PC sampling also after an error, so the target doesn't keep sending samples.
+            let disabled = session.disable_pc_sampling();
+            result?;
+            disabled?;
+        }
+        SamplingMethod::Auto => unreachable!("the sampling method has been selected above"),
+    }
+
+    if let Some(path) = &options.folded {
+        let mut file = std::io::BufWriter::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?,
+        );
+        profile.write_folded(&mut file)?;
+        file.flush()?;
+    }
+
+    profile.print(options.functions);
+
+    Ok(())
+}
+
+/// Checks whether the core can be sampled with the DWT_PCSR register, by reading it a few
+/// times while the core is running.
+fn supports_pcsr(core: &mut Core) -> anyhow::Result<bool> {
+    if core.architecture() != Architecture::Arm {
+        return Ok(false);
+    }
+
+    // The DWT registers can only be accessed with TRCENA set.
+    enable_tracing(core)?;
+
+    for _ in 0..8 {
+        if DwtPcsr(core.read_word_32(DwtPcsr::ADDRESS)?).pc().is_some() {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Calls `sample` every `interval` until `duration` has passed.
+///
+/// If sampling takes longer than `interval`, the next sample is taken right away.
+pub(crate) fn sample_periodically(
+    duration: Duration,
+    interval: Duration,
+    mut sample: impl FnMut() -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let start = Instant::now();
+    let mut next = start;
+
+    while start.elapsed() < duration {
+        sample()?;
+
+        next += interval;
+        let now = Instant::now();
+        if next > now {
+            std::thread::sleep(next - now);
+        } else {
+            next = now;
+        }
+    }
+
+    Ok(())
+}
+
+/// A statistical profile of the executed functions, built from PC samples.
+pub(crate) struct Profile {
+    debug_info: Option<DebugInfo>,
+    /// The functions at every sampled address, outermost first, looked up once per address.
+    functions: HashMap<u32, Vec<String>>,
+    /// The number of samples of every call stack, outermost function first.
+    stacks: HashMap<Vec<String>, u64>,
+    /// Samples taken while the core was sleeping.
+    sleeping: u64,
+}
+
+impl Profile {
+    /// Creates an empty profile. Without debug info, the samples are counted per address.
+    pub(crate) fn new(debug_info: Option<DebugInfo>) -> Self {
+        Self {
+            debug_info,
+            functions: HashMap::new(),
+            stacks: HashMap::new(),
+            sleeping: 0,
+        }
+    }
+
+    /// Adds a PC sample, or a sample taken while the core was sleeping if `pc` is `None`.
+    ///
+    /// The call stack of the sample only contains the function at `pc` and the functions
+    /// inlined into it.
+    pub(crate) fn add_sample(&mut self, pc: Option<u32>) {
+        match pc {
+            Some(pc) => {
+                let stack = self.functions_at(pc).to_vec();
+                *self.stacks.entry(stack).or_default() += 1;
+            }
+            None => self.sleeping += 1,
+        }
+    }
+
+    /// Adds a sample with the full call stack, unwound from the halted core.
+    pub(crate) fn add_call_stack(&mut self, core: &mut Core, pc: u64) -> anyhow::Result<()> {
+        let frames = match &self.debug_info {
+            Some(debug_info) => debug_info.unwind(core, pc)?,
+            None => Vec::new(),
+        };
+
+        if frames.is_empty() {
+            self.add_sample(Some(pc as u32));
+            return Ok(());
+        }
+
+        // The frames start with the innermost function.
+        let stack = frames
+            .into_iter()
+            .rev()
+            .map(|frame| frame.function_name)
+            .collect();
+        *self.stacks.entry(stack).or_default() += 1;
+
+        Ok(())
+    }
+
+    /// The functions at `pc`, outermost first, or the address itself if it is unknown.
+    fn functions_at(&mut self, pc: u32) -> &[String] {
+        let debug_info = &self.debug_info;
+        self.functions.entry(pc).or_insert_with(|| {
+            let names = debug_info
+                .as_ref()
+                .and_then(|debug_info| debug_info.inlined_function_names(pc as u64).ok())
+                .unwrap_or_default();
+
+            if names.is_empty() {
+                vec![format!("{pc:#010x}")]
+            } else {
+                names
+            }
+        })
+    }
+
+    /// Prints the `functions` functions with the most samples.
+    ///
+    /// For every function, the samples in the function itself and the samples in the function
+    /// including the functions it called or inlined are shown.
+    pub(crate) fn print(&self, functions: usize) {
+        let total = self.stacks.values().sum::<u64>() + self.sleeping;
+        if total == 0 {
+            println!("No PC samples received.");
+            return;
+        }
+
+        // (self samples, total samples) of every function.
+        let mut counts: HashMap<&str, (u64, u64)> = HashMap::new();
+        for (stack, count) in &self.stacks {
+            if let Some(function) = stack.last() {
+                counts.entry(function).or_default().0 += count;
+            }
+
+            // Recursive functions are only counted once per sample.
+            let unique: HashSet<&str> = stack.iter().map(|name| name.as_str()).collect();
+            for function in unique {
+                counts.entry(function).or_default().1 += count;
+            }
+        }
+        if self.sleeping > 0 {
+            counts.insert("<sleeping>", (self.sleeping, self.sleeping));
+        }
+
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+        let percent = |count: u64| 100.0 * count as f64 / total as f64;
+
+        println!("PC sampling profile, {total} samples:");
+        println!("    self%   total%   samples  function");
+        for (name, (self_count, total_count)) in counts.iter().take(functions) {
+            println!(
+                "  {:>6.2}%  {:>6.2}%  {self_count:>8}  {name}",
+                percent(*self_count),
+                percent(*total_count),
+            );
+        }
+    }
+
+    /// Writes the sampled call stacks in the folded format, one line per call stack with the
+    /// functions separated by `;`, followed by the number of samples.
+    pub(crate) fn write_folded(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let mut stacks: Vec<_> = self
+            .stacks
+            .iter()
+            .map(|(stack, count)| {
+                // `;` separates the functions, so it must not occur in the names, e.g. in
+                // array types like `[u8; 4]`.
+                let names: Vec<String> = stack.iter().map(|name| name.replace(';', ",")).collect();
+                (names.join(";"), *count)
+            })
+            .collect();
+        stacks.sort();
+
+        for (stack, count) in stacks {
+            writeln!(writer, "{stack} {count}")?;
+        }
+
+        if self.sleeping > 0 {
+            writeln!(writer, "<sleeping> {}", self.sleeping)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,17 +1,24 @@
 use anyhow::{Context, Result};
 use probe_rs::flashing::FileDownloadError;
+use probe_rs::semihosting::{Semihosting, SemihostingEvent, SemihostingOptions};
 use probe_rs_cli_util::common_options::{CargoOptions, FlashOptions, ProbeOptions};
 use probe_rs_cli_util::flash::run_flash_download;
 use probe_rs_cli_util::rtt;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long to wait for the firmware to initialize RTT after the reset.
+const RTT_ATTACH_TIMEOUT: Duration = Duration::from_secs(1);
 
 pub fn run(
     common: ProbeOptions,
     path: &str,
     chip_erase: bool,
     disable_double_buffering: bool,
+    semihosting_options: SemihostingOptions,
+    rtt_log: Option<PathBuf>,
 ) -> Result<()> {
     let mut session = common.simple_attach()?;
 
@@ -44,14 +51,26 @@ pub fn run(
         chip_erase,
     )?;
 
-    let rtt_config = rtt::RttConfig::default();
+    let rtt_config = rtt::RttConfig {
+        log_path: rtt_log,
+        ..Default::default()
+    };
 
     let memory_map = session.target().memory_map.clone();
 
     let mut core = session.core(0)?;
     core.reset()?;
 
-    let mut rtta = match rtt::attach_to_rtt(&mut core, &memory_map, Path::new(path), &rtt_config) {
+    // The reset clears `dcsr.ebreakm` on RISC-V, so semihosting traps would end up in the firmware.
+    core.debug_on_sw_breakpoint(true)?;
+
+    let mut rtta = match rtt::attach_to_rtt(
+        &mut core,
+        &memory_map,
+        Path::new(path),
+        &rtt_config,
+        RTT_ATTACH_TIMEOUT,
+    ) {
         Ok(target_rtt) => Some(target_rtt),
         Err(error) => {
             log::error!("{:?} Continuing without RTT... ", error);
@@ -59,14 +78,58 @@ pub fn run(
         }
     };
 
-    if let Some(rtta) = &mut rtta {
-        let mut stdout = std::io::stdout();
-        loop {
+    let mut semihosting =
+        Semihosting::new(semihosting_options).with_console_input(std::io::stdin());
+
+    let mut stdout = std::io::stdout();
+    let mut stderr = std::io::stderr();
+
+    loop {
+        let mut had_output = false;
+
+        if let Some(rtta) = &mut rtta {
             for (_ch, data) in rtta.poll_rtt_fallible(&mut core)? {
-                stdout.write_all(data.as_bytes()).unwrap();
+                had_output |= !data.is_empty();
+                stdout.write_all(data.as_bytes())?;
             }
         }
-    }
 
-    Ok(())
+        match semihosting.handle(&mut core)? {
+            Some(SemihostingEvent::Continued {
+                stdout: target_stdout,
+                stderr: target_stderr,
+            }) => {
+                stdout.write_all(&target_stdout)?;
+                stdout.flush()?;
+                stderr.write_all(&target_stderr)?;
+                continue;
+            }
+            Some(SemihostingEvent::Exited(exit)) => {
+                stdout.flush()?;
+
+                let exit_code = exit.exit_code();
+                log::info!("Target exited with code {}", exit_code);
+
+                // Make sure the probe is released before the process exits.
+                drop(core);
+                drop(session);
+
+                if exit_code != 0 {
+                    std::process::exit(exit_code);
+                }
+
+                return Ok(());
+            }
+            None => {}
+        }
+
+        if core.core_halted()? {
+            eprintln!("Core halted: {:?}", core.status()?);
+            return Ok(());
+        }
+
+        if !had_output {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
 }
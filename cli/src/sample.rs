@@ -0,0 +1,269 @@
+//! Periodic sampling of static variables while the core is running.
+
+use super::{CoreOptions, ProbeOptions};
+use crate::profile::sample_periodically;
+use anyhow::Context;
+use probe_rs::debug::debug_info::DebugInfo;
+use probe_rs::debug::{ScalarValue, StaticVariable};
+use probe_rs::MemoryInterface;
+use probe_rs_cli_util::clap;
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Variables which are at most this many bytes apart are read with a single memory access.
+const MAX_READ_GAP: u64 = 16;
+
+/// The width of the strip chart, in characters.
+const CHART_WIDTH: usize = 64;
+
+/// The markers used for the variables in the strip chart.
+const CHART_MARKERS: [char; 8] = ['*', '+', 'o', 'x', '#', '@', '%', '&'];
+
+/// Options for sampling variables.
+#[derive(clap::Parser)]
+pub(crate) struct SampleOptions {
+    /// The ELF file of the firmware, used to look up the addresses and types of the variables.
+    #[clap(long, value_parser)]
+    elf: PathBuf,
+
+    /// The variables to sample, e.g. `COUNTER`, `module::STATE.error` or `BUFFER[3]`. Only
+    /// scalar variables at a fixed address, like statics and their fields, can be sampled.
+    #[clap(required = true)]
+    variables: Vec<String>,
+
+    /// The number of samples taken per second. The actual rate may be lower, depending on the
+    /// speed of the probe.
+    #[clap(long, default_value = "100")]
+    rate: u32,
+
+    /// How long to sample, in milliseconds. Samples until interrupted if not given.
+    #[clap(long)]
+    duration_ms: Option<u64>,
+
+    /// Write the samples as CSV to this file, instead of to stdout.
+    #[clap(long, value_parser)]
+    csv: Option<PathBuf>,
+
+    /// Plot the samples as a strip chart in the terminal. Every variable is scaled to the range
+    /// of values sampled so far.
+    #[clap(long)]
+    chart: bool,
+}
+
+/// A block of memory containing one or more of the sampled variables.
+struct MemoryRange {
+    address: u64,
+    data: Vec<u8>,
+}
+
+/// Periodically read static variables without halting the core, and write the timestamped
+/// values as CSV or plot them in the terminal.
+///
+/// # Args
+/// * `shared_options` - Specifies information about which core to read the variables from.
+/// * `common` - Specifies information about the probe to use.
+/// * `options` - Specifies the variables, the sample rate and the outputs.
+pub(crate) fn sample(
+    shared_options: &CoreOptions,
+    common: &ProbeOptions,
+    options: &SampleOptions,
+) -> anyhow::Result<()> {
+    let debug_info = DebugInfo::from_file(&options.elf)
+        .with_context(|| format!("Failed to load the debug info of {}", options.elf.display()))?;
+
+    let variables = options
+        .variables
+        .iter()
+        .map(|path| debug_info.resolve_static_variable(path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for variable in &variables {
+        log::debug!(
+            "Sampling {} at {:#010x} as {:?}",
+            variable.path,
+            variable.address,
+            variable.scalar_type
+        );
+    }
+
+    let (mut ranges, locations) = group_reads(&variables);
+
+    let mut csv: Option<Box<dyn Write>> = match (&options.csv, options.chart) {
+        (Some(path), _) => Some(Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?,
+        ))),
+        (None, false) => Some(Box::new(std::io::stdout())),
+        (None, true) => None,
+    };
+
+    if let Some(csv) = &mut csv {
+        let header: Vec<String> = variables.iter().map(|v| csv_field(&v.path)).collect();
+        writeln!(csv, "time_s,{}", header.join(","))?;
+        csv.flush()?;
+    }
+
+    let mut chart = options.chart.then(|| StripChart::new(&variables));
+
+    let mut session = common.simple_attach()?;
+    let mut core = session.core(shared_options.core)?;
+
+    let duration = options
+        .duration_ms
+        .map(Duration::from_millis)
+        .unwrap_or(Duration::MAX);
+    let interval = Duration::from_secs(1) / options.rate.max(1);
+    let start = Instant::now();
+
+    sample_periodically(duration, interval, || {
+        for range in ranges.iter_mut() {
+            core.read(range.address, &mut range.data)?;
+        }
+        let time = start.elapsed().as_secs_f64();
+
+        let values: Vec<ScalarValue> = variables
+            .iter()
+            .zip(&locations)
+            .map(|(variable, &(range, offset))| {
+                variable.scalar_type.decode(&ranges[range].data[offset..])
+            })
+            .collect();
+
+        if let Some(csv) = &mut csv {
+            let row: Vec<String> = values.iter().map(ToString::to_string).collect();
+            writeln!(csv, "{time:.6},{}", row.join(","))?;
+            csv.flush()?;
+        }
+
+        if let Some(chart) = &mut chart {
+            println!("{}", chart.row(time, &values));
+        }
+
+        Ok(())
+    })?;
+
+    if let Some(chart) = &chart {
+        chart.print_ranges();
+    }
+
+    Ok(())
+}
+
+/// Groups the variables into blocks of memory, merging variables close to each other, so that
+/// they can be read with fewer memory accesses.
+///
+/// Returns the blocks and, for every variable, the index of its block and its offset in it.
+fn group_reads(variables: &[StaticVariable]) -> (Vec<MemoryRange>, Vec<(usize, usize)>) {
+    let mut order: Vec<usize> = (0..variables.len()).collect();
+    order.sort_by_key(|&index| variables[index].address);
+
+    let mut ranges: Vec<MemoryRange> = Vec::new();
+    let mut locations = vec![(0, 0); variables.len()];
+
+    for index in order {
+        let variable = &variables[index];
+        let end = variable.address + variable.scalar_type.size() as u64;
+
+        match ranges.last_mut() {
+            Some(range)
+                if variable.address <= range.address + range.data.len() as u64 + MAX_READ_GAP =>
+            {
+                let length = (end - range.address) as usize;
+                if length > range.data.len() {
+                    range.data.resize(length, 0);
+                }
+            }
+            _ => ranges.push(MemoryRange {
+                address: variable.address,
+                data: vec![0; variable.scalar_type.size()],
+            }),
+        }
+
+        let range = ranges.len() - 1;
+        locations[index] = (range, (variable.address - ranges[range].address) as usize);
+    }
+
+    (ranges, locations)
+}
+
+/// Quotes a CSV field if necessary, e.g. for paths containing generic parameters.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A text strip chart, printing a row with a marker for every variable per sample.
+struct StripChart {
+    paths: Vec<String>,
+    /// The minimum and maximum value of every variable sampled so far.
+    ranges: Vec<Option<(f64, f64)>>,
+}
+
+impl StripChart {
+    /// Creates the chart and prints the legend.
+    fn new(variables: &[StaticVariable]) -> Self {
+        for (variable, marker) in variables.iter().zip(CHART_MARKERS.iter().cycle()) {
+            println!("{marker} {}", variable.path);
+        }
+
+        Self {
+            paths: variables.iter().map(|v| v.path.clone()).collect(),
+            ranges: vec![None; variables.len()],
+        }
+    }
+
+    /// Formats a row of the chart, followed by the values.
+    fn row(&mut self, time: f64, values: &[ScalarValue]) -> String {
+        let mut line = [' '; CHART_WIDTH];
+
+        for ((value, range), marker) in values
+            .iter()
+            .zip(self.ranges.iter_mut())
+            .zip(CHART_MARKERS.iter().cycle())
+        {
+            let value = value.as_f64();
+            if !value.is_finite() {
+                continue;
+            }
+
+            let (min, max) = match *range {
+                Some((min, max)) => (min.min(value), max.max(value)),
+                None => (value, value),
+            };
+            *range = Some((min, max));
+
+            let position = if max > min {
+                ((value - min) / (max - min) * (CHART_WIDTH - 1) as f64).round() as usize
+            } else {
+                CHART_WIDTH / 2
+            };
+            line[position.min(CHART_WIDTH - 1)] = *marker;
+        }
+
+        let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+        format!(
+            "{time:>10.3}s |{}| {}",
+            line.iter().collect::<String>(),
+            values.join(" ")
+        )
+    }
+
+    /// Prints the range of values of every variable, which the chart is scaled to.
+    fn print_ranges(&self) {
+        for ((path, range), marker) in self
+            .paths
+            .iter()
+            .zip(&self.ranges)
+            .zip(CHART_MARKERS.iter().cycle())
+        {
+            if let Some((min, max)) = range {
+                println!("{marker} {path}: {min} to {max}");
+            }
+        }
+    }
+}
@@ -1,7 +1,80 @@
-//! Provides ITM tracing capabilities.
+//! Provides ITM and instruction tracing capabilities.
 
 use super::{CoreOptions, ProbeOptions};
-use probe_rs::architecture::arm::component::TraceSink;
+use crate::defmt::{load_table, DefmtOptions};
+use crate::profile::Profile;
+use anyhow::Context;
+use itm::cortex_m::VectActive;
+use itm::{
+    DecoderError, ExceptionAction, LocalTimestampOptions, MemoryAccessType, Timestamp,
+    TimestampsConfiguration, TracePacket,
+};
+use probe_rs::architecture::arm::{
+    armv6m::Demcr,
+    component::TraceSink,
+    trace::{InstructionTraceSource, TraceEvent},
+    SwoConfig,
+};
+use probe_rs::debug::debug_info::DebugInfo;
+use probe_rs::{MemoryInterface, MemoryMappedRegister, Session};
+use probe_rs_cli_util::clap;
+use probe_rs_cli_util::defmt::DefmtDecoder;
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Options for interpreting the ITM trace.
+#[derive(clap::Parser)]
+pub(crate) struct ItmViewerOptions {
+    /// Print the decoded packets as they are, without interpreting them.
+    #[clap(long, conflicts_with_all = ["defmt", "pc_sampling"])]
+    raw: bool,
+
+    /// The frequency of the ITM timestamp clock in Hz, used to convert the timestamps into
+    /// time. Defaults to the SWO clock. Without a clock, no timestamps are shown.
+    #[clap(long)]
+    timestamp_clk: Option<u32>,
+
+    /// Sample the PC about every this many cycles, and print a profile of the sampled
+    /// functions at the end. The DWT supports multiples of 64 cycles up to 1024 cycles, and
+    /// multiples of 1024 cycles up to 16384 cycles.
+    #[clap(long)]
+    pc_sampling: Option<u32>,
+
+    /// Resolve the PC samples to functions using the symbols of this ELF file.
+    #[clap(long, value_parser, requires = "pc_sampling")]
+    elf: Option<PathBuf>,
+
+    /// The number of functions shown in the PC sampling profile.
+    #[clap(long, default_value = "20")]
+    profile_functions: usize,
+
+    /// Trace the value written to or read from an address with a DWT comparator, given as
+    /// `<UNIT>=<ADDRESS>`, e.g. `0=0x20000010`. Can be used multiple times.
+    #[clap(long, value_parser = parse_data_trace)]
+    data_trace: Vec<DataTrace>,
+}
+
+/// An address traced by a DWT comparator.
+#[derive(Clone, Copy, Debug)]
+struct DataTrace {
+    unit: usize,
+    address: u32,
+}
+
+fn parse_data_trace(input: &str) -> Result<DataTrace, String> {
+    let (unit, address) = input
+        .split_once('=')
+        .ok_or_else(|| format!("Expected <UNIT>=<ADDRESS>, got '{input}'"))?;
+
+    Ok(DataTrace {
+        unit: parse_int::parse(unit).map_err(|e| format!("Invalid unit '{unit}': {e}"))?,
+        address: parse_int::parse(address)
+            .map_err(|e| format!("Invalid address '{address}': {e}"))?,
+    })
+}
 
 /// Trace the application using ITM.
 ///
@@ -10,33 +83,668 @@ use probe_rs::architecture::arm::component::TraceSink;
 /// * `common` - Specifies information about the probe to use for tracing.
 /// * `sink` - Specifies the destination for trace data.
 /// * `duration` - Specifies the duration to trace for.
-/// * `output_file` - An optionally specified filename to write ITM binary data into.
+/// * `defmt` - Specifies the ELF file to decode defmt data with, if any.
+/// * `defmt_port` - Specifies the stimulus port which carries the defmt data.
+/// * `options` - Specifies how to interpret the trace.
 pub(crate) fn itm_trace(
     shared_options: &CoreOptions,
     common: &ProbeOptions,
     sink: TraceSink,
-    duration: std::time::Duration,
+    duration: Duration,
+    defmt: &DefmtOptions,
+    defmt_port: u8,
+    options: &ItmViewerOptions,
 ) -> anyhow::Result<()> {
+    let defmt_state = defmt.defmt.as_deref().map(load_table).transpose()?;
+    let defmt_decoder = defmt_state
+        .as_ref()
+        .map(|defmt_state| DefmtDecoder::new(defmt_state, defmt.show_location));
+
+    let profile = match (options.pc_sampling, &options.elf) {
+        (Some(_), Some(elf)) => Some(Profile::new(Some(
+            DebugInfo::from_file(elf)
+                .with_context(|| format!("Failed to load the debug info of {}", elf.display()))?,
+        ))),
+        (Some(_), None) => Some(Profile::new(None)),
+        (None, _) => None,
+    };
+
+    let mut viewer = ItmViewer {
+        out: std::io::stdout(),
+        defmt: defmt_decoder,
+        defmt_port,
+        lines: HashMap::new(),
+        data_trace: options.data_trace.clone(),
+        profile,
+    };
+
+    let timestamp_clk = match &sink {
+        TraceSink::Swo(config) => options.timestamp_clk.or(Some(config.tpiu_clk())),
+        TraceSink::Tpiu(_) | TraceSink::TraceMemory => options.timestamp_clk,
+    };
+
     let mut session = common.simple_attach()?;
 
     session.setup_tracing(shared_options.core, sink)?;
 
-    let decoder = itm::Decoder::new(
-        session.swo_reader()?,
-        itm::DecoderOptions { ignore_eof: true },
-    );
+    let result = enable_trace_sources(&mut session, options).and_then(|()| {
+        let decoder = itm::Decoder::new(
+            session.swo_reader()?,
+            itm::DecoderOptions { ignore_eof: true },
+        );
+        decode_itm(decoder, &mut viewer, options.raw, timestamp_clk, duration)
+    });
+
+    // Stop the trace sources on every exit path, so the target doesn't keep sending trace data.
+    let disabled = disable_trace_sources(&mut session, options);
+    result?;
+    disabled?;
+
+    viewer.finish(options.profile_functions)?;
 
+    Ok(())
+}
+
+/// Enables the PC sampling and the data traces selected in `options`.
+fn enable_trace_sources(session: &mut Session, options: &ItmViewerOptions) -> anyhow::Result<()> {
+    if let Some(interval) = options.pc_sampling {
+        let interval = session.enable_pc_sampling(interval)?;
+        println!("Sampling the PC every {interval} cycles");
+    }
+
+    for trace in &options.data_trace {
+        session.add_swv_data_trace(trace.unit, trace.address)?;
+    }
+
+    Ok(())
+}
+
+/// Disables the trace sources enabled by [`enable_trace_sources`], also if enabling them failed
+/// halfway. Returns the first error after trying to disable all of them.
+fn disable_trace_sources(session: &mut Session, options: &ItmViewerOptions) -> anyhow::Result<()> {
+    let mut result = Ok(());
+
+    if options.pc_sampling.is_some() {
+        result = result.and(session.disable_pc_sampling());
+    }
+
+    for trace in &options.data_trace {
+        result = result.and(session.remove_swv_data_trace(trace.unit));
+    }
+
+    Ok(result?)
+}
+
+/// Decodes the ITM data for up to `duration`, and passes it to `viewer`.
+///
+/// The timestamps are converted into time since the start of the trace using `timestamp_clk`.
+/// Without a clock, the packets are shown without timestamps.
+fn decode_itm<W: Write>(
+    decoder: itm::Decoder<impl Read>,
+    viewer: &mut ItmViewer<W>,
+    raw: bool,
+    timestamp_clk: Option<u32>,
+    duration: Duration,
+) -> anyhow::Result<()> {
     let start = std::time::Instant::now();
-    let iter = decoder.singles();
 
     // Decode and print the ITM data for display.
-    for packet in iter {
-        if start.elapsed() > duration {
-            return Ok(());
+    match (raw, timestamp_clk) {
+        (true, _) => {
+            for packet in decoder.singles() {
+                if start.elapsed() > duration {
+                    break;
+                }
+
+                writeln!(viewer.out, "{packet:?}")?;
+            }
+        }
+        (false, Some(clock_frequency)) => {
+            let timestamps = decoder.timestamps(TimestampsConfiguration {
+                clock_frequency,
+                // Local timestamps are enabled without a prescaler, see `Itm::tx_enable`.
+                lts_prescaler: LocalTimestampOptions::Enabled,
+                expect_malformed: true,
+            });
+
+            for packets in timestamps {
+                if start.elapsed() > duration {
+                    break;
+                }
+
+                let packets = packets.context("Failed to read the ITM trace")?;
+                let time = match packets.timestamp {
+                    Timestamp::Sync(time) | Timestamp::AssocEventDelay(time) => time,
+                    Timestamp::UnknownDelay { curr, .. }
+                    | Timestamp::UnknownAssocEventDelay { curr, .. } => curr,
+                };
+
+                for malformed in packets.malformed_packets {
+                    tracing::warn!("Malformed ITM packet: {malformed}");
+                }
+
+                for packet in packets.packets {
+                    viewer.packet(Some(time), packet)?;
+                }
+            }
+        }
+        (false, None) => {
+            for packet in decoder.singles() {
+                if start.elapsed() > duration {
+                    break;
+                }
+
+                match packet {
+                    Ok(packet) => viewer.packet(None, packet)?,
+                    Err(DecoderError::MalformedPacket(malformed)) => {
+                        tracing::warn!("Malformed ITM packet: {malformed}");
+                    }
+                    Err(err) => return Err(err).context("Failed to read the ITM trace"),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats the time of a trace event, if the timestamps are known.
+fn format_time(time: Option<Duration>) -> String {
+    match time {
+        Some(time) => format!("[{:>12.6}] ", time.as_secs_f64()),
+        None => String::new(),
+    }
+}
+
+/// The name of an active exception reported by the exception trace.
+fn vect_active_name(exception: VectActive) -> String {
+    match exception {
+        VectActive::ThreadMode => "thread mode".to_string(),
+        VectActive::Exception(exception) => exception_name((exception.irqn() + 16) as u16),
+        VectActive::Interrupt { irqn } => exception_name(irqn + 16),
+    }
+}
+
+/// Interprets the decoded ITM packets and prints them to `out`.
+struct ItmViewer<'a, W: Write> {
+    out: W,
+    /// The decoder for the defmt data on `defmt_port`, if any.
+    defmt: Option<DefmtDecoder<'a>>,
+    defmt_port: u8,
+    /// Incomplete lines received on the stimulus ports, printed once the line ends.
+    lines: HashMap<u8, Vec<u8>>,
+    data_trace: Vec<DataTrace>,
+    profile: Option<Profile>,
+}
+
+impl<W: Write> ItmViewer<'_, W> {
+    fn packet(&mut self, time: Option<Duration>, packet: TracePacket) -> anyhow::Result<()> {
+        match packet {
+            TracePacket::Instrumentation { port, payload } => match self.defmt.as_mut() {
+                Some(decoder) if port == self.defmt_port => {
+                    decoder.received(&payload);
+                    write!(self.out, "{}", decoder.decode()?)?;
+                }
+                _ => {
+                    let line = self.lines.entry(port).or_default();
+                    for byte in payload {
+                        if byte == b'\n' {
+                            write_line(&mut self.out, time, port, line)?;
+                            line.clear();
+                        } else {
+                            line.push(byte);
+                        }
+                    }
+                }
+            },
+            TracePacket::ExceptionTrace { exception, action } => {
+                let action = match action {
+                    ExceptionAction::Entered => "entered",
+                    ExceptionAction::Exited => "exited",
+                    ExceptionAction::Returned => "returned to",
+                };
+                writeln!(
+                    self.out,
+                    "{}{action} {}",
+                    format_time(time),
+                    vect_active_name(exception)
+                )?;
+            }
+            TracePacket::PCSample { pc } => {
+                if let Some(profile) = self.profile.as_mut() {
+                    profile.add_sample(pc);
+                }
+            }
+            TracePacket::DataTraceValue {
+                comparator,
+                access_type,
+                value,
+            } => {
+                let access = match access_type {
+                    MemoryAccessType::Read => "read",
+                    MemoryAccessType::Write => "write",
+                };
+
+                // The value is sent with the least significant byte first.
+                let mut bytes = [0u8; 4];
+                let length = value.len().min(4);
+                bytes[..length].copy_from_slice(&value[..length]);
+                let value = u32::from_le_bytes(bytes);
+
+                writeln!(
+                    self.out,
+                    "{}{} {access} {value:#0width$x} ({value})",
+                    format_time(time),
+                    self.data_trace_name(comparator),
+                    width = 2 + 2 * length,
+                )?;
+            }
+            TracePacket::DataTracePC { comparator, pc } => {
+                writeln!(
+                    self.out,
+                    "{}{} accessed at {pc:#010x}",
+                    format_time(time),
+                    self.data_trace_name(comparator)
+                )?;
+            }
+            TracePacket::Overflow => {
+                writeln!(
+                    self.out,
+                    "{}<ITM overflow, some packets were lost>",
+                    format_time(time)
+                )?;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// The name of a data trace comparator, including the traced address if known.
+    fn data_trace_name(&self, comparator: u8) -> String {
+        match self
+            .data_trace
+            .iter()
+            .find(|trace| trace.unit == comparator as usize)
+        {
+            Some(trace) => format!("data trace {comparator} ({:#010x})", trace.address),
+            None => format!("data trace {comparator}"),
+        }
+    }
+
+    /// Prints the incomplete lines and the PC sampling profile.
+    fn finish(mut self, functions: usize) -> std::io::Result<()> {
+        let mut lines: Vec<_> = self.lines.into_iter().collect();
+        lines.sort_by_key(|(port, _)| *port);
+        for (port, line) in lines {
+            if !line.is_empty() {
+                write_line(&mut self.out, None, port, &line)?;
+            }
+        }
+
+        if let Some(profile) = self.profile {
+            profile.print(functions);
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a line received on a stimulus port.
+fn write_line(
+    out: &mut impl Write,
+    time: Option<Duration>,
+    port: u8,
+    line: &[u8],
+) -> std::io::Result<()> {
+    let line = String::from_utf8_lossy(line);
+    writeln!(
+        out,
+        "{}port {port}: {}",
+        format_time(time),
+        line.trim_end_matches('\r')
+    )
+}
+
+/// Options for recording an instruction trace.
+#[derive(clap::Parser)]
+pub(crate) struct InstructionTraceOptions {
+    /// Record the executed instructions until the core halts, e.g. on a HardFault, and print the
+    /// last executed functions using the symbols of this ELF file.
+    #[clap(long, value_parser, conflicts_with = "loc")]
+    pub(crate) elf: Option<PathBuf>,
+
+    /// The number of functions to print.
+    #[clap(long, default_value = "16")]
+    functions: usize,
+
+    /// Record the trace with the MTB of a Cortex-M0+ instead of the ETM.
+    #[clap(long)]
+    mtb: bool,
+
+    /// Collect the ETM trace over SWO instead of the trace memory, with the given speed of the
+    /// clock feeding the TPIU/SWO module in Hz.
+    #[clap(long, requires = "swo_baud", conflicts_with = "mtb")]
+    swo_clk: Option<u32>,
+
+    /// The baud rate of the SWO output.
+    #[clap(long, requires = "swo_clk")]
+    swo_baud: Option<u32>,
+}
+
+/// An entry in the list of executed functions.
+enum HistoryEntry {
+    Function { address: u32, name: Option<String> },
+    Exception(Option<u16>),
+    Overflow,
+}
+
+impl std::fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistoryEntry::Function { address, name } => {
+                write!(
+                    f,
+                    "{address:#010x}  {}",
+                    name.as_deref().unwrap_or("<unknown>")
+                )
+            }
+            HistoryEntry::Exception(Some(number)) => {
+                write!(f, "exception {number} ({})", exception_name(*number))
+            }
+            HistoryEntry::Exception(None) => write!(f, "exception"),
+            HistoryEntry::Overflow => write!(f, "<trace overflow, some functions are missing>"),
+        }
+    }
+}
+
+/// The name of an ARMv7-M/ARMv8-M exception.
+fn exception_name(number: u16) -> String {
+    match number {
+        1 => "Reset".to_string(),
+        2 => "NMI".to_string(),
+        3 => "HardFault".to_string(),
+        4 => "MemManage".to_string(),
+        5 => "BusFault".to_string(),
+        6 => "UsageFault".to_string(),
+        7 => "SecureFault".to_string(),
+        11 => "SVCall".to_string(),
+        12 => "DebugMonitor".to_string(),
+        14 => "PendSV".to_string(),
+        15 => "SysTick".to_string(),
+        number if number >= 16 => format!("IRQ {}", number - 16),
+        _ => "reserved".to_string(),
+    }
+}
+
+/// The last executed functions, resolved from the decoded trace.
+struct FunctionHistory {
+    debug_info: DebugInfo,
+    names: HashMap<u32, Option<String>>,
+    entries: VecDeque<HistoryEntry>,
+    length: usize,
+}
+
+impl FunctionHistory {
+    fn new(debug_info: DebugInfo, length: usize) -> Self {
+        Self {
+            debug_info,
+            names: HashMap::new(),
+            entries: VecDeque::with_capacity(length),
+            length,
+        }
+    }
+
+    fn extend(&mut self, events: Vec<TraceEvent>) {
+        for event in events {
+            let entry = match event {
+                TraceEvent::Branch { target, .. } => {
+                    let debug_info = &self.debug_info;
+                    let name = self
+                        .names
+                        .entry(target)
+                        .or_insert_with(|| {
+                            debug_info
+                                .function_name(target as u64, false)
+                                .ok()
+                                .flatten()
+                        })
+                        .clone();
+
+                    // Branches within the same function are collapsed into a single entry.
+                    if let Some(HistoryEntry::Function {
+                        name: previous @ Some(_),
+                        ..
+                    }) = self.entries.back()
+                    {
+                        if *previous == name {
+                            continue;
+                        }
+                    }
+
+                    HistoryEntry::Function {
+                        address: target,
+                        name,
+                    }
+                }
+                TraceEvent::Exception { number, .. } => HistoryEntry::Exception(number),
+                TraceEvent::ExceptionReturn => continue,
+                TraceEvent::Overflow => HistoryEntry::Overflow,
+            };
+
+            if self.entries.len() == self.length {
+                self.entries.pop_front();
+            }
+            self.entries.push_back(entry);
+        }
+    }
+}
+
+/// Record an instruction trace until the core halts, and print the last executed functions.
+///
+/// A HardFault halts the core, so the trace ends with the functions executed before the fault.
+pub(crate) fn instruction_trace(
+    shared_options: &CoreOptions,
+    common: &ProbeOptions,
+    elf: &Path,
+    options: &InstructionTraceOptions,
+) -> anyhow::Result<()> {
+    let debug_info = DebugInfo::from_file(elf)?;
+    let mut session = common.simple_attach()?;
+
+    let source = match (options.mtb, options.swo_clk, options.swo_baud) {
+        (true, _, _) => InstructionTraceSource::Mtb,
+        (false, Some(clk), Some(baud)) => {
+            InstructionTraceSource::Etm(TraceSink::Swo(SwoConfig::new(clk).set_baud(baud)))
+        }
+        _ => InstructionTraceSource::Etm(TraceSink::TraceMemory),
+    };
+
+    {
+        // Halt on a HardFault, so that the trace ends at the fault.
+        let mut core = session.core(shared_options.core)?;
+        let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS)?);
+        demcr.set_vc_harderr(true);
+        core.write_word_32(Demcr::ADDRESS, demcr.into())?;
+    }
+
+    session.setup_instruction_trace(shared_options.core, source)?;
+
+    {
+        let mut core = session.core(shared_options.core)?;
+        if core.core_halted()? {
+            core.run()?;
+        }
+    }
+
+    println!("Recording the instruction trace until the core halts...");
+
+    let mut history = FunctionHistory::new(debug_info, options.functions);
+    loop {
+        history.extend(session.read_instruction_trace()?);
+
+        if session.core(shared_options.core)?.core_halted()? {
+            // Read the trace recorded up to the halt.
+            history.extend(session.read_instruction_trace()?);
+            break;
         }
 
-        println!("{packet:?}");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    session.disable_instruction_trace()?;
+
+    println!("Last executed functions, oldest first:");
+    for entry in &history.entries {
+        println!("  {entry}");
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn viewer<'a>(
+        out: &'a mut Vec<u8>,
+        defmt: Option<DefmtDecoder<'a>>,
+    ) -> ItmViewer<'a, &'a mut Vec<u8>> {
+        ItmViewer {
+            out,
+            defmt,
+            defmt_port: 1,
+            lines: HashMap::new(),
+            data_trace: Vec::new(),
+            profile: None,
+        }
+    }
+
+    fn instrumentation(port: u8, payload: &[u8]) -> TracePacket {
+        TracePacket::Instrumentation {
+            port,
+            payload: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn defmt_on_stimulus_port() {
+        let elf = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../probe-rs-cli-util/tests/data/defmt/defmt_rzcobs.o");
+        let defmt_state = load_table(&elf).unwrap();
+        let mut out = Vec::new();
+        let mut viewer = viewer(&mut out, Some(DefmtDecoder::new(&defmt_state, false)));
+
+        // `Hello, 42!` and `Goodbye` on port 1, split into packets of different sizes and
+        // interleaved with text on port 0. Port 2 carries defmt-like data, which is shown as text.
+        let packets = [
+            instrumentation(1, &[0x2a]),
+            instrumentation(0, b"hi"),
+            instrumentation(2, &[0x01, 0x7e, 0x00, b'\n']),
+            instrumentation(1, &[0x7b, 0x00]),
+            instrumentation(0, b"!\n"),
+            instrumentation(1, &[0x01, 0x7e, 0x00, 0x00]),
+        ];
+        for packet in packets {
+            viewer.packet(None, packet).unwrap();
+        }
+        viewer.finish(0).unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4, "{output}");
+        assert_eq!(lines[0], "port 2: \u{1}~\0");
+        assert!(lines[1].ends_with("Hello, 42!"), "{output}");
+        assert_eq!(lines[2], "port 0: hi!");
+        assert!(lines[3].ends_with("Goodbye"), "{output}");
+    }
+
+    #[test]
+    fn text_on_stimulus_ports() {
+        let mut out = Vec::new();
+        let mut viewer = viewer(&mut out, None);
+
+        viewer
+            .packet(None, instrumentation(1, b"abc\r\nde"))
+            .unwrap();
+        viewer.packet(None, instrumentation(0, b"x")).unwrap();
+        // Incomplete lines are printed at the end
+        viewer.finish(0).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "port 1: abc\nport 0: x\nport 1: de\n"
+        );
+    }
+
+    fn decode(data: &[u8], timestamp_clk: Option<u32>) -> String {
+        let mut out = Vec::new();
+        let mut viewer = viewer(&mut out, None);
+        let decoder = itm::Decoder::new(data, itm::DecoderOptions { ignore_eof: false });
+        decode_itm(decoder, &mut viewer, false, timestamp_clk, Duration::MAX).unwrap();
+        viewer.finish(0).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    /// An exception trace packet for `number`, with the action encoded as in the DWT packet.
+    fn exception_trace(number: u16, action: u8) -> [u8; 3] {
+        [0x0e, number as u8, (action << 4) | (number >> 8) as u8]
+    }
+
+    #[test]
+    fn exception_trace_rendering() {
+        let data = [
+            exception_trace(15, 1),
+            exception_trace(18, 2),
+            exception_trace(0, 3),
+            exception_trace(3, 1),
+            exception_trace(16 + 300, 1),
+        ]
+        .concat();
+
+        assert_eq!(
+            decode(&data, None),
+            "entered SysTick\n\
+             exited IRQ 2\n\
+             returned to thread mode\n\
+             entered HardFault\n\
+             entered IRQ 300\n"
+        );
+    }
+
+    #[test]
+    fn timestamps() {
+        // Every local timestamp follows the packets it belongs to, and holds the number of
+        // timestamp clock cycles since the previous timestamp.
+        let data = [
+            &[0x01, b'a', 0x01, b'\n'][..],
+            // 5 cycles
+            &[0x50],
+            &exception_trace(15, 1),
+            // 1500 cycles, in two bytes
+            &[0xc0, 0xdc, 0x0b],
+            &exception_trace(15, 2),
+            // 1 cycle
+            &[0x10],
+        ]
+        .concat();
+
+        assert_eq!(
+            decode(&data, Some(1000)),
+            "[    0.005000] port 0: a\n\
+             [    1.505000] entered SysTick\n\
+             [    1.506000] exited SysTick\n"
+        );
+        assert_eq!(
+            decode(&data, Some(1_000_000)),
+            "[    0.000005] port 0: a\n\
+             [    0.001505] entered SysTick\n\
+             [    0.001506] exited SysTick\n"
+        );
+        assert_eq!(
+            decode(&data, None),
+            "port 0: a\nentered SysTick\nexited SysTick\n"
+        );
+    }
+}
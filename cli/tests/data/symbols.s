@@ -0,0 +1,11 @@
+# Minimal ELF file with a Thumb function and a variable, used by the address resolution tests.
+#
+# Built with `as symbols.s -o symbols.o && ld -e 0 symbols.o -o symbols.elf`.
+
+    .globl _ZN7example7measure17h0123456789abcdefE
+    .type _ZN7example7measure17h0123456789abcdefE, @function
+    .set _ZN7example7measure17h0123456789abcdefE, 0x1001
+
+    .globl COUNTER
+    .type COUNTER, @object
+    .set COUNTER, 0x20000001
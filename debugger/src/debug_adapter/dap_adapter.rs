@@ -852,7 +852,13 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, Ok(Some(instruction_breakpoint_body)))
     }
 
-    pub(crate) fn threads(&mut self, target_core: &mut CoreHandle, request: Request) -> Result<()> {
+    /// The `smp_threads` of the other cores of an SMP target are listed after the thread of `target_core`.
+    pub(crate) fn threads(
+        &mut self,
+        target_core: &mut CoreHandle,
+        smp_threads: Vec<Thread>,
+        request: Request,
+    ) -> Result<()> {
         // TODO: Implement actual thread resolution. For now, we just use the core id as the thread id.
         let current_core_status = target_core.core.status()?;
         let mut threads: Vec<Thread> = vec![];
@@ -864,6 +870,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     name: target_core.core_data.target_name.clone(),
                 };
                 threads.push(single_thread);
+                threads.extend(smp_threads);
                 // We do the actual stack trace here, because VSCode sometimes sends multiple StackTrace requests, which lead to unnecessary unwind processing.
                 // By doing it here, we do it once, and serve up the results when we get the StackTrace requests.
                 let regs = target_core.core.registers();
@@ -1264,13 +1271,32 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     capstone::arch::riscv::ArchExtraMode::RiscVC,
                 ))
                 .build(),
+            InstructionSet::RV64 => Capstone::new()
+                .riscv()
+                .mode(riscvArchMode::RiscV64)
+                .endian(Endian::Little)
+                .build(),
+            InstructionSet::RV64C => Capstone::new()
+                .riscv()
+                .mode(riscvArchMode::RiscV64)
+                .endian(Endian::Little)
+                .extra_mode(std::iter::once(
+                    capstone::arch::riscv::ArchExtraMode::RiscVC,
+                ))
+                .build(),
+            InstructionSet::Xtensa => {
+                // Capstone has no Xtensa backend.
+                return Err(DebuggerError::Other(anyhow!(
+                    "Disassembly is not supported for Xtensa cores"
+                )));
+            }
         }
         .map_err(|err| anyhow!("Error creating capstone: {:?}", err))?;
         let _ = cs.set_skipdata(true);
 
         // Adjust instruction offset as required for variable length instruction sets.
         let instruction_offset_as_bytes = match target_instruction_set {
-            InstructionSet::Thumb2 | InstructionSet::RV32C => {
+            InstructionSet::Thumb2 | InstructionSet::RV32C | InstructionSet::RV64C => {
                 // Since we cannot guarantee the size of individual instructions, let's assume we will read the 120% of the requested number of 16-bit instructions.
                 (instruction_offset
                     * target_core
@@ -1280,7 +1306,11 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     / 4
                     * 5
             }
-            InstructionSet::A32 | InstructionSet::A64 | InstructionSet::RV32 => {
+            InstructionSet::A32
+            | InstructionSet::A64
+            | InstructionSet::RV32
+            | InstructionSet::RV64
+            | InstructionSet::Xtensa => {
                 instruction_offset
                     * target_core
                         .core
@@ -1600,7 +1630,9 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             let mut variable_cache: Option<&mut probe_rs::debug::VariableCache> = None;
             let mut stack_frame_registers: Option<&DebugRegisters> = None;
             let mut frame_base: Option<u64> = None;
-            for stack_frame in target_core.core_data.stack_frames.iter_mut() {
+            for (frame_index, stack_frame) in
+                target_core.core_data.stack_frames.iter_mut().enumerate()
+            {
                 if let Some(search_cache) = &mut stack_frame.local_variables {
                     if let Some(search_variable) =
                         search_cache.get_variable_by_key(arguments.variables_reference)
@@ -1627,7 +1659,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 if stack_frame.id == arguments.variables_reference {
                     // This is a special case, where we just want to return the stack frame registers.
 
-                    let dap_variables: Vec<Variable> = stack_frame
+                    let mut dap_variables: Vec<Variable> = stack_frame
                         .registers
                         .0
                         .iter()
@@ -1643,6 +1675,43 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             variables_reference: 0,
                         })
                         .collect();
+
+                    // The banked registers of cores with a security extension are not part of
+                    // the unwound registers, so they are only shown with their live values for
+                    // the top frame.
+                    if frame_index == 0 {
+                        if let Some(security_state) = target_core.core.security_state()? {
+                            dap_variables.push(Variable {
+                                name: "Security State".to_string(),
+                                evaluate_name: None,
+                                memory_reference: None,
+                                indexed_variables: None,
+                                named_variables: None,
+                                presentation_hint: None,
+                                type_: Some(format!("{}", VariableName::RegistersRoot)),
+                                value: format!("{security_state:?}"),
+                                variables_reference: 0,
+                            });
+
+                            let registers = target_core.core.registers();
+                            for register in registers.other() {
+                                let value: RegisterValue =
+                                    target_core.core.read_core_reg(register)?;
+                                dap_variables.push(Variable {
+                                    name: register.name().to_string(),
+                                    evaluate_name: Some(register.name().to_string()),
+                                    memory_reference: None,
+                                    indexed_variables: None,
+                                    named_variables: None,
+                                    presentation_hint: None,
+                                    type_: Some(format!("{}", VariableName::RegistersRoot)),
+                                    value: value.to_string(),
+                                    variables_reference: 0,
+                                });
+                            }
+                        }
+                    }
+
                     return self.send_response(
                         request,
                         Ok(Some(VariablesResponseBody {
@@ -1986,6 +2055,24 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.adapter.log_to_console(message)
     }
 
+    /// Send output of the target application (e.g. using semihosting) to the client.
+    /// The `category` can be one of `stdout` or `stderr`.
+    pub fn send_output(&mut self, category: &str, output: &[u8]) -> Result<()> {
+        self.send_event(
+            "output",
+            Some(OutputEventBody {
+                output: String::from_utf8_lossy(output).into_owned(),
+                category: Some(category.to_owned()),
+                variables_reference: None,
+                source: None,
+                line: None,
+                column: None,
+                data: None,
+                group: None,
+            }),
+        )
+    }
+
     /// Send a custom "probe-rs-show-message" event to the MS DAP Client.
     /// The `severity` field can be one of `information`, `warning`, or `error`.
     pub fn show_message(&mut self, severity: MessageSeverity, message: impl Into<String>) -> bool {
@@ -94,6 +94,26 @@ impl SessionConfig {
                         None
                     }
                 };
+            // Update the `semihosting_root` and validate that the directory exists.
+            // If there is a problem with this directory, warn the user and limit semihosting to the console.
+            target_core_config.semihosting_root = match get_absolute_path(
+                self.cwd.clone(),
+                target_core_config.semihosting_root.as_ref(),
+            ) {
+                Ok(semihosting_root) => {
+                    if !semihosting_root.is_dir() {
+                        tracing::error!("Semihosting root {:?} not found.", semihosting_root);
+                        None
+                    } else {
+                        Some(semihosting_root)
+                    }
+                }
+                Err(error) => {
+                    // The semihosting root is not mandatory.
+                    tracing::debug!("Semihosting root not specified: {:?}", &error);
+                    None
+                }
+            };
         }
 
         Ok(())
@@ -188,6 +208,19 @@ pub struct CoreConfig {
     /// CMSIS-SVD file for the target. Relative to `cwd`, or fully qualified.
     pub(crate) svd_file: Option<PathBuf>,
 
+    /// Directory in which the target can access files using semihosting. Relative to `cwd`, or fully qualified.
+    pub(crate) semihosting_root: Option<PathBuf>,
+
+    /// Other cores of the target, which are halted and resumed together with `core_index`, e.g. the
+    /// second core of a dual-core microcontroller running its own program.
+    #[serde(default)]
+    pub(crate) halt_group_cores: Vec<usize>,
+
+    /// The other cores of an SMP target, which run the same program as `core_index`.
+    /// They are shown as additional threads, and are halted and resumed together with `core_index`.
+    #[serde(default)]
+    pub(crate) smp_cores: Vec<usize>,
+
     #[serde(flatten)]
     pub(crate) rtt_config: rtt::RttConfig,
 }
@@ -2,7 +2,10 @@ use super::session_data;
 use crate::{
     debug_adapter::{
         dap_adapter::{DapStatus, DebugAdapter},
-        dap_types::{ContinuedEventBody, MessageSeverity, StoppedEventBody},
+        dap_types::{
+            ContinuedEventBody, ExitedEventBody, MessageSeverity, StoppedEventBody,
+            TerminatedEventBody,
+        },
         protocol::ProtocolAdapter,
     },
     debugger::debug_rtt,
@@ -10,7 +13,11 @@ use crate::{
     DebuggerError,
 };
 use anyhow::{anyhow, Result};
-use probe_rs::{debug::debug_info::DebugInfo, Core, CoreStatus, Error};
+use probe_rs::{
+    debug::debug_info::DebugInfo,
+    semihosting::{Semihosting, SemihostingEvent},
+    Core, CoreStatus, Error,
+};
 use probe_rs_cli_util::rtt::{self, ChannelMode, DataFormat};
 
 /// [CoreData] is used to cache data needed by the debugger, on a per-core basis.
@@ -31,6 +38,8 @@ pub struct CoreData {
     pub(crate) stack_frames: Vec<probe_rs::debug::stack_frame::StackFrame>,
     pub(crate) breakpoints: Vec<session_data::ActiveBreakpoint>,
     pub(crate) rtt_connection: Option<debug_rtt::RttConnection>,
+    /// Services the semihosting requests of the core, and sends its console output to the client.
+    pub(crate) semihosting: Semihosting,
 }
 
 /// [CoreHandle] provides handles to various data structures required to debug a single instance of a core. The actual state is stored in [session_data::SessionData].
@@ -54,6 +63,49 @@ impl<'p> CoreHandle<'p> {
         debug_adapter.all_cores_halted = false;
     }
 
+    /// Service the semihosting request of a newly halted core, and send its console output to the client.
+    ///
+    /// The core is left halted, so that it can be resumed together with the other cores of its halt group.
+    /// Returns `true` if a request was serviced, and the core has to be resumed.
+    pub(crate) fn service_semihosting<P: ProtocolAdapter>(
+        &mut self,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> Result<bool, Error> {
+        let status = self.core.status()?;
+        if !status.is_halted() || self.core_data.last_known_status.is_halted() {
+            return Ok(false);
+        }
+
+        match self.core_data.semihosting.handle_halted(&mut self.core)? {
+            Some(SemihostingEvent::Continued { stdout, stderr }) => {
+                if !stdout.is_empty() {
+                    debug_adapter.send_output("stdout", &stdout)?;
+                }
+                if !stderr.is_empty() {
+                    debug_adapter.send_output("stderr", &stderr)?;
+                }
+                Ok(true)
+            }
+            Some(SemihostingEvent::Exited(exit)) => {
+                debug_adapter.log_to_console(format!(
+                    "The target application exited with code {}",
+                    exit.exit_code()
+                ));
+                debug_adapter.send_event(
+                    "exited",
+                    Some(ExitedEventBody {
+                        exit_code: exit.exit_code() as i64,
+                    }),
+                )?;
+                debug_adapter
+                    .send_event("terminated", Some(TerminatedEventBody { restart: None }))?;
+                self.core_data.last_known_status = status;
+                Ok(false)
+            }
+            None => Ok(false),
+        }
+    }
+
     /// - Whenever we check the status, we compare it against `last_known_status` and send the appropriate event to the client.
     /// - If we cannot determine the core status, then there is no sense in continuing the debug session, so please propogate the error.
     /// - If the core status has changed, then we update `last_known_status` to the new value, and return `true` as part of the Result<>.
@@ -161,6 +213,8 @@ impl<'p> CoreHandle<'p> {
             target_memory_map,
             program_binary,
             rtt_config,
+            // Attaching is retried on every poll of the debug session, so don't block here.
+            std::time::Duration::ZERO,
         ) {
             Ok(target_rtt) => {
                 for any_channel in target_rtt.active_channels.iter() {
@@ -112,25 +112,53 @@ impl Debugger {
                 let (core_statuses, _) = session_data.poll_cores(&self.config, debug_adapter)?;
                 // TODO: Currently, we only use `poll_cores()` results from the first core and need to expand to a multi-core implementation that understands which MS DAP requests are core specific.
                 if let (core_id, Some(new_status)) = (0_usize, core_statuses.first().cloned()) {
+                    let command = request.command.clone();
+
+                    // The SMP cores of the core are presented as threads, and requests referring to
+                    // one of their threads, stack frames or variables are handled by that core.
+                    let (core_index, smp_threads) =
+                        if let Some(target_core_config) = self.config.core_configs.get(core_id) {
+                            let core_index = session_data
+                                .core_index_for_request(&request, target_core_config.core_index);
+                            let smp_threads =
+                                if command == "threads" && debug_adapter.configuration_is_done() {
+                                    session_data.smp_threads(target_core_config)?
+                                } else {
+                                    vec![]
+                                };
+                            (core_index, smp_threads)
+                        } else {
+                            return Err(DebuggerError::Other(anyhow!(
+                                "No core configuration found for core id {}",
+                                core_id
+                            )));
+                        };
+
+                    // The other cores of the halt group are halted and resumed together with the core.
+                    let other_cores: Vec<usize> = session_data
+                        .halt_group_of(&self.config, core_index)
+                        .into_iter()
+                        .filter(|&other_core_index| other_core_index != core_index)
+                        .collect();
+                    if !other_cores.is_empty() {
+                        match command.as_ref() {
+                            "continue" => session_data.session.resume_cores(&other_cores)?,
+                            "pause" => session_data
+                                .session
+                                .halt_cores(&other_cores, Duration::from_millis(500))?,
+                            _ => {}
+                        }
+                    }
+
                     // Attach to the core. so that we have the handle available for processing the request.
-                    let mut target_core = if let Some(target_core_config) =
-                        self.config.core_configs.get_mut(core_id)
-                    {
-                        if let Ok(core_handle) =
-                            session_data.attach_core(target_core_config.core_index)
-                        {
+                    let mut target_core =
+                        if let Ok(core_handle) = session_data.attach_core(core_index) {
                             core_handle
                         } else {
                             return Err(DebuggerError::Other(anyhow!(
                                 "Unable to connect to target core"
                             )));
-                        }
-                    } else {
-                        return Err(DebuggerError::Other(anyhow!(
-                            "No core configuration found for core id {}",
-                            core_id
-                        )));
-                    };
+                        };
 
                     // For some operations, we need to make sure the core isn't sleeping, by calling `Core::halt()`.
                     // When we do this, we need to flag it (`unhalt_me = true`), and later call `Core::run()` again.
@@ -233,7 +261,7 @@ impl Debugger {
                             .configuration_done(&mut target_core, request)
                             .and(Ok(DebuggerStatus::ContinueSession)),
                         "threads" => debug_adapter
-                            .threads(&mut target_core, request)
+                            .threads(&mut target_core, smp_threads, request)
                             .and(Ok(DebuggerStatus::ContinueSession)),
                         "restart" => {
                             // Reset RTT so that the link can be re-established
@@ -286,6 +314,21 @@ impl Debugger {
                                     }
                                 }
                             }
+                            drop(target_core);
+
+                            // Breakpoints are set on the core, and are needed on its SMP cores as well.
+                            if matches!(
+                                command.as_ref(),
+                                "setBreakpoints" | "setInstructionBreakpoints"
+                            ) {
+                                if let Some(target_core_config) =
+                                    self.config.core_configs.get(core_id)
+                                {
+                                    if !target_core_config.smp_cores.is_empty() {
+                                        session_data.sync_smp_breakpoints(target_core_config)?;
+                                    }
+                                }
+                            }
                             Ok(debugger_status)
                         }
                         Err(e) => Err(DebuggerError::Other(e.context("Error executing request."))),
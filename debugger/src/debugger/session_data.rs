@@ -3,15 +3,27 @@ use super::{
     core_data::{CoreData, CoreHandle},
 };
 use crate::{
-    debug_adapter::{dap_adapter::DebugAdapter, dap_types::Source, protocol::ProtocolAdapter},
+    debug_adapter::{
+        dap_adapter::DebugAdapter,
+        dap_types::{Request, Source, Thread},
+        protocol::ProtocolAdapter,
+    },
     DebuggerError,
 };
 use anyhow::{anyhow, Result};
 use probe_rs::{
-    config::TargetSelector, debug::debug_info::DebugInfo, CoreStatus, DebugProbeError, Permissions,
-    Probe, ProbeCreationError, Session,
+    config::TargetSelector,
+    debug::debug_info::DebugInfo,
+    semihosting::{Semihosting, SemihostingOptions},
+    CoreStatus, DebugProbeError, Permissions, Probe, ProbeCreationError, Session,
 };
-use std::env::set_current_dir;
+use std::{env::set_current_dir, time::Duration};
+
+/// How long to wait for the probe and the target to come back, after the connection to them was lost.
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the connection may be lost again right after reconnecting, before giving up.
+const RECONNECT_ATTEMPTS: usize = 3;
 
 /// The supported breakpoint types
 #[derive(Clone, Debug, PartialEq)]
@@ -99,7 +111,7 @@ impl SessionData {
         }
 
         // Attach to the probe.
-        let target_session = if config.connect_under_reset {
+        let mut target_session = if config.connect_under_reset {
             target_probe.attach_under_reset(target_selector, permissions)?
         } else {
             target_probe
@@ -146,10 +158,8 @@ impl SessionData {
         let mut core_data_vec = vec![];
 
         for core_configuration in &valid_core_configs {
-            // Configure the [DebugInfo].
-            let debug_info = if let Some(binary_path) = &core_configuration.program_binary {
-                DebugInfo::from_file(binary_path)
-                    .map_err(|error| DebuggerError::Other(anyhow!(error)))?
+            let binary_path = if let Some(binary_path) = &core_configuration.program_binary {
+                binary_path
             } else {
                 return Err(anyhow!(
                     "Please provide a valid `program_binary` for debug core: {:?}",
@@ -158,20 +168,51 @@ impl SessionData {
                 .into());
             };
 
-            core_data_vec.push(CoreData {
-                core_index: core_configuration.core_index,
-                last_known_status: CoreStatus::Unknown,
-                target_name: format!(
-                    "{}-{}",
-                    core_configuration.core_index,
-                    target_session.target().name
-                ),
-                debug_info,
-                core_peripherals: None,
-                stack_frames: Vec::<probe_rs::debug::stack_frame::StackFrame>::new(),
-                breakpoints: Vec::<ActiveBreakpoint>::new(),
-                rtt_connection: None,
-            })
+            // The SMP cores run the same program, and each of them gets its own [CoreData].
+            let smp_group = smp_group(core_configuration);
+
+            for &core_index in &smp_group {
+                if !target_session
+                    .list_cores()
+                    .iter()
+                    .any(|(target_core_index, _)| *target_core_index == core_index)
+                {
+                    return Err(anyhow!(
+                        "The SMP core {} does not exist on the target.",
+                        core_index
+                    )
+                    .into());
+                }
+
+                // Configure the [DebugInfo].
+                let debug_info = DebugInfo::from_file(binary_path)
+                    .map_err(|error| DebuggerError::Other(anyhow!(error)))?;
+
+                core_data_vec.push(CoreData {
+                    core_index,
+                    last_known_status: CoreStatus::Unknown,
+                    target_name: format!("{}-{}", core_index, target_session.target().name),
+                    debug_info,
+                    core_peripherals: None,
+                    stack_frames: Vec::<probe_rs::debug::stack_frame::StackFrame>::new(),
+                    breakpoints: Vec::<ActiveBreakpoint>::new(),
+                    rtt_connection: None,
+                    semihosting: Semihosting::new(SemihostingOptions {
+                        root: core_configuration.semihosting_root.clone(),
+                        cmdline: String::new(),
+                    }),
+                })
+            }
+
+            let halt_group = halt_group(core_configuration);
+            if halt_group.len() > 1 {
+                let sync = target_session.configure_halt_group(&halt_group)?;
+                tracing::info!(
+                    "Cores {:?} are halted and resumed together ({:?})",
+                    halt_group,
+                    sync
+                );
+            }
         }
 
         Ok(SessionData {
@@ -214,19 +255,60 @@ impl SessionData {
     ///   - While the core is NOT halted, because core processing can generate new data at any time.
     ///   - The first time we have entered halted status, to ensure the buffers are drained. After that, for as long as we remain in halted state, we don't need to check RTT again.
     ///
+    /// If the connection to the probe or the target is lost while polling, the session is reconnected before polling again.
+    ///
     /// Return a Vec of [`CoreStatus`] (one entry per core) after this process has completed, as well as a boolean indicating whether we should consider a short delay before the next poll.
     pub(crate) fn poll_cores<P: ProtocolAdapter>(
         &mut self,
         session_config: &SessionConfig,
         debug_adapter: &mut DebugAdapter<P>,
     ) -> Result<(Vec<CoreStatus>, bool), DebuggerError> {
+        let mut attempt = 0;
+        loop {
+            if let Some(result) = self.poll_cores_once(session_config, debug_adapter)? {
+                return Ok(result);
+            }
+
+            attempt += 1;
+            let result = if attempt > RECONNECT_ATTEMPTS {
+                Err(DebuggerError::Other(anyhow!(
+                    "The connection to the target was lost {} times in a row, giving up.",
+                    RECONNECT_ATTEMPTS
+                )))
+            } else {
+                debug_adapter.log_to_console("Connection to the target lost, reconnecting ...");
+                self.session
+                    .reconnect(RECONNECT_TIMEOUT)
+                    .map_err(DebuggerError::ProbeRs)
+            };
+            if let Err(error) = result {
+                let _ = debug_adapter.send_error_response(&error);
+                return Err(error);
+            }
+            debug_adapter.log_to_console("Reconnected to the target.");
+        }
+    }
+
+    /// Poll all cores once, see [`SessionData::poll_cores`].
+    ///
+    /// Returns `None` if the connection to the probe or the target was lost.
+    fn poll_cores_once<P: ProtocolAdapter>(
+        &mut self,
+        session_config: &SessionConfig,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> Result<Option<(Vec<CoreStatus>, bool)>, DebuggerError> {
         // By default, we will have a small delay between polls, and will disable it if we know the last poll returned data, on the assumption that there might be at least one more batch of data.
         let mut suggest_delay_required = true;
         let mut status_of_cores: Vec<CoreStatus> = vec![];
         let target_memory_map = &self.session.target().memory_map.clone();
 
+        if debug_adapter.configuration_is_done() {
+            self.service_semihosting(session_config, debug_adapter)?;
+        }
+
         // Always set `all_cores_halted` to true, until one core is found to be running.
         debug_adapter.all_cores_halted = true;
+        let mut connection_lost = false;
         for core_config in session_config.core_configs.iter() {
             if let Ok(mut target_core) = self.attach_core(core_config.core_index) {
                 // We need to poll the core to determine its status.
@@ -270,6 +352,11 @@ impl SessionData {
                         }
                         status_of_cores.push(current_core_status);
                     }
+                    Err(error) if error.is_connection_lost() => {
+                        // The session is borrowed by the core handle, so reconnect after the loop.
+                        connection_lost = true;
+                        break;
+                    }
                     Err(error) => {
                         let error = DebuggerError::ProbeRs(error);
                         let _ = debug_adapter.send_error_response(&error);
@@ -283,6 +370,216 @@ impl SessionData {
                 );
             }
         }
-        Ok((status_of_cores, suggest_delay_required))
+
+        if connection_lost {
+            return Ok(None);
+        }
+
+        for core_config in session_config.core_configs.iter() {
+            if !core_config.smp_cores.is_empty() {
+                self.poll_smp_cores(core_config, debug_adapter)?;
+            }
+        }
+
+        Ok(Some((status_of_cores, suggest_delay_required)))
+    }
+
+    /// Service the semihosting requests of all cores, and resume them afterwards.
+    ///
+    /// This is done before the status of the cores is reported to the client, because the cores of
+    /// a halt group halt together with the core making the request, and are resumed together with it.
+    fn service_semihosting<P: ProtocolAdapter>(
+        &mut self,
+        session_config: &SessionConfig,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> Result<(), DebuggerError> {
+        let mut serviced = Vec::new();
+
+        for core_config in &session_config.core_configs {
+            for &core_index in
+                std::iter::once(&core_config.core_index).chain(&core_config.smp_cores)
+            {
+                let mut target_core = self.attach_core(core_index)?;
+                if target_core
+                    .service_semihosting(debug_adapter)
+                    .map_err(DebuggerError::ProbeRs)?
+                {
+                    serviced.push(core_index);
+                }
+            }
+        }
+
+        for core_index in serviced {
+            let halt_group = self.halt_group_of(session_config, core_index);
+            self.session.resume_cores(&halt_group)?;
+        }
+
+        Ok(())
+    }
+
+    /// Poll the SMP cores of `core_config`, which notifies the client when they halt or resume.
+    ///
+    /// When some cores of the SMP group halted, while others are still running, e.g. because
+    /// the cores have no hardware halt group, the running ones are halted as well.
+    fn poll_smp_cores<P: ProtocolAdapter>(
+        &mut self,
+        core_config: &CoreConfig,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> Result<(), DebuggerError> {
+        for &core_index in &core_config.smp_cores {
+            let mut target_core = self.attach_core(core_index)?;
+            let status = target_core
+                .poll_core(debug_adapter)
+                .map_err(DebuggerError::ProbeRs)?;
+            if !status.is_halted() {
+                debug_adapter.all_cores_halted = false;
+            }
+        }
+
+        let smp_group = smp_group(core_config);
+        let halted = self
+            .core_data
+            .iter()
+            .filter(|core_data| smp_group.contains(&core_data.core_index))
+            .filter(|core_data| core_data.last_known_status.is_halted())
+            .count();
+        if halted > 0 && halted < smp_group.len() {
+            self.session
+                .halt_cores(&smp_group, Duration::from_millis(100))?;
+        }
+
+        Ok(())
     }
+
+    /// The cores of the halt group `core_index` belongs to, or only `core_index` if it isn't part
+    /// of a halt group.
+    pub(crate) fn halt_group_of(
+        &self,
+        session_config: &SessionConfig,
+        core_index: usize,
+    ) -> Vec<usize> {
+        session_config
+            .core_configs
+            .iter()
+            .map(halt_group)
+            .find(|group| group.contains(&core_index))
+            .unwrap_or_else(|| vec![core_index])
+    }
+
+    /// Find the core a request is meant for, from the thread, stack frame, or variable it refers to.
+    ///
+    /// Requests which don't refer to a core of an SMP group are handled by `default_core_index`.
+    pub(crate) fn core_index_for_request(
+        &self,
+        request: &Request,
+        default_core_index: usize,
+    ) -> usize {
+        let arguments = match &request.arguments {
+            Some(arguments) => arguments,
+            None => return default_core_index,
+        };
+        let argument = |name: &str| arguments.get(name).and_then(|value| value.as_i64());
+
+        if let Some(thread_id) = argument("threadId") {
+            if let Some(core_data) = self
+                .core_data
+                .iter()
+                .find(|core_data| core_data.core_index as i64 == thread_id)
+            {
+                return core_data.core_index;
+            }
+        }
+
+        let reference = match argument("frameId").or_else(|| argument("variablesReference")) {
+            Some(reference) => reference,
+            None => return default_core_index,
+        };
+
+        self.core_data
+            .iter()
+            .find(|core_data| {
+                core_data.stack_frames.iter().any(|stack_frame| {
+                    stack_frame.id == reference
+                        || [&stack_frame.local_variables, &stack_frame.static_variables]
+                            .into_iter()
+                            .flatten()
+                            .any(|cache| cache.get_variable_by_key(reference).is_some())
+                })
+            })
+            .map_or(default_core_index, |core_data| core_data.core_index)
+    }
+
+    /// The threads of the SMP cores of `core_config`, which are listed next to the thread of the
+    /// core itself.
+    ///
+    /// The stack frames of the halted cores are refreshed, like [`DebugAdapter::threads`] does it
+    /// for the core itself.
+    pub(crate) fn smp_threads(
+        &mut self,
+        core_config: &CoreConfig,
+    ) -> Result<Vec<Thread>, DebuggerError> {
+        let mut threads = vec![];
+
+        for &core_index in &core_config.smp_cores {
+            let mut target_core = self.attach_core(core_index)?;
+            if !target_core.core.core_halted()? {
+                continue;
+            }
+
+            let pc = target_core
+                .core
+                .read_core_reg(target_core.core.registers().program_counter())?;
+            target_core.core_data.stack_frames = target_core
+                .core_data
+                .debug_info
+                .unwind(&mut target_core.core, pc)?;
+
+            threads.push(Thread {
+                id: core_index as i64,
+                name: target_core.core_data.target_name.clone(),
+            });
+        }
+
+        Ok(threads)
+    }
+
+    /// Set the same hardware breakpoints on the SMP cores of `core_config`, as on the core itself.
+    pub(crate) fn sync_smp_breakpoints(
+        &mut self,
+        core_config: &CoreConfig,
+    ) -> Result<(), DebuggerError> {
+        let breakpoints: Vec<u64> = self
+            .core_data
+            .iter()
+            .filter(|core_data| core_data.core_index == core_config.core_index)
+            .flat_map(|core_data| core_data.breakpoints.iter())
+            .map(|breakpoint| breakpoint.breakpoint_address)
+            .collect();
+
+        for &core_index in &core_config.smp_cores {
+            let mut core = self.session.core(core_index)?;
+            core.clear_all_hw_breakpoints()?;
+            for address in &breakpoints {
+                core.set_hw_breakpoint(*address)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The core of `core_config` and its SMP cores.
+fn smp_group(core_config: &CoreConfig) -> Vec<usize> {
+    std::iter::once(core_config.core_index)
+        .chain(core_config.smp_cores.iter().copied())
+        .collect()
+}
+
+/// The cores halted and resumed together with the core of `core_config`: its SMP cores, and the
+/// other cores of its halt group.
+fn halt_group(core_config: &CoreConfig) -> Vec<usize> {
+    smp_group(core_config)
+        .into_iter()
+        .chain(core_config.halt_group_cores.iter().copied())
+        .collect()
 }
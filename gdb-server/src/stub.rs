@@ -1,4 +1,5 @@
 use anyhow::Result;
+use probe_rs::semihosting::SemihostingOptions;
 use probe_rs::{CoreType, Error, Session};
 
 use std::net::{SocketAddr, ToSocketAddrs};
@@ -19,6 +20,8 @@ pub struct GdbInstanceConfiguration {
     pub cores: Vec<usize>,
     /// The list of [SocketAddr] addresses to bind to
     pub socket_addrs: Vec<SocketAddr>,
+    /// How semihosting requests of the cores are serviced
+    pub semihosting: SemihostingOptions,
 }
 
 impl GdbInstanceConfiguration {
@@ -65,6 +68,7 @@ impl GdbInstanceConfiguration {
                 core_type: *core_type,
                 cores: cores.to_vec(),
                 socket_addrs: adjust_addrs(&addrs, i),
+                semihosting: SemihostingOptions::default(),
             })
             .collect();
 
@@ -89,7 +93,12 @@ pub fn run<'a>(
     // Turn our group list into GDB targets
     let mut targets = instances
         .map(|instance| {
-            target::RuntimeTarget::new(session, instance.cores.to_vec(), &instance.socket_addrs[..])
+            target::RuntimeTarget::new(
+                session,
+                instance.cores.to_vec(),
+                &instance.socket_addrs[..],
+                instance.semihosting.clone(),
+            )
         })
         .collect::<Result<Vec<target::RuntimeTarget>, Error>>()?;
 
@@ -0,0 +1,57 @@
+use super::RuntimeTarget;
+
+use gdbstub::target::ext::monitor_cmd::outputln;
+use gdbstub::target::ext::monitor_cmd::MonitorCmd;
+
+const HELP_TEXT: &str = r#"Supported Commands:
+
+    info - print session information
+    translate <address> - translate a virtual address of the first core to a physical address
+"#;
+
+impl MonitorCmd for RuntimeTarget<'_> {
+    fn handle_monitor_cmd(
+        &mut self,
+        cmd: &[u8],
+        mut out: gdbstub::target::ext::monitor_cmd::ConsoleOutput<'_>,
+    ) -> Result<(), Self::Error> {
+        let cmd = String::from_utf8_lossy(cmd);
+        let mut args = cmd.split_whitespace();
+
+        match (args.next(), args.next(), args.next()) {
+            (Some("info"), None, None) => {
+                outputln!(
+                    out,
+                    "Target info:\n\n{:#?}",
+                    self.session.lock().unwrap().target()
+                );
+            }
+            (Some("translate"), Some(address), None) => match parse_address(address) {
+                Some(address) => {
+                    let mut session = self.session.lock().unwrap();
+                    match session
+                        .core(self.cores[0])
+                        .and_then(|mut core| core.translate_address(address))
+                    {
+                        Ok(physical) => outputln!(out, "{:#010x} -> {:#010x}", address, physical),
+                        Err(e) => outputln!(out, "Failed to translate {:#010x}: {}", address, e),
+                    }
+                }
+                None => outputln!(out, "Invalid address: {}", address),
+            },
+            _ => {
+                outputln!(out, "{}", HELP_TEXT);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a hexadecimal address with a `0x` prefix, or a decimal address.
+fn parse_address(address: &str) -> Option<u64> {
+    match address.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => address.parse().ok(),
+    }
+}
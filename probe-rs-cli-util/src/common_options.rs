@@ -40,8 +40,8 @@ use clap;
 use probe_rs::{
     config::{RegistryError, TargetSelector},
     flashing::{FileDownloadError, FlashError, FlashLoader},
-    DebugProbeError, DebugProbeSelector, FakeProbe, Permissions, Probe, Session, Target,
-    WireProtocol,
+    DebugProbeError, DebugProbeSelector, FakeProbe, JtagTapSelector, Permissions, Probe, Session,
+    Target, WireProtocol,
 };
 
 /// Common options when flashing a target device.
@@ -145,8 +145,14 @@ pub struct ProbeOptions {
 
     /// Use this flag to select a specific probe in the list.
     ///
-    /// Use '--probe VID:PID' or '--probe VID:PID:Serial' if you have more than one probe with the same VID:PID.",
-    #[structopt(long = "probe", help_heading = "PROBE CONFIGURATION")]
+    /// Use '--probe VID:PID' or '--probe VID:PID:Serial' if you have more than one probe with the same VID:PID.
+    /// Use '--probe VID:PID@Port' to select a probe by the USB port it is plugged into,
+    /// or '--probe <alias>' to use an alias defined in the probe aliases file.",
+    #[structopt(
+        long = "probe",
+        help_heading = "PROBE CONFIGURATION",
+        value_parser = crate::probe_aliases::parse_probe_selector
+    )]
     pub probe_selector: Option<DebugProbeSelector>,
     #[clap(
         long,
@@ -154,6 +160,12 @@ pub struct ProbeOptions {
         help_heading = "PROBE CONFIGURATION"
     )]
     pub speed: Option<u32>,
+    /// The TAP to debug if there are multiple TAPs in the JTAG scan chain.
+    ///
+    /// Use either the position of the TAP in the chain, starting at 0 for the TAP closest to TDO,
+    /// or its IDCODE in hexadecimal, e.g. '--jtag-tap 0x4ba00477'.
+    #[clap(long, help_heading = "PROBE CONFIGURATION")]
+    pub jtag_tap: Option<JtagTapSelector>,
     #[structopt(
         long = "connect-under-reset",
         help = "Use this flag to assert the nreset & ntrst pins during attaching the probe to the chip."
@@ -254,6 +266,12 @@ impl ProbeOptions {
             })?;
         }
 
+        if let Some(tap) = self.jtag_tap {
+            probe
+                .select_jtag_tap(tap)
+                .map_err(|error| OperationError::FailedToSelectJtagTap { source: error, tap })?;
+        }
+
         Ok(probe)
     }
 
@@ -498,6 +516,12 @@ pub enum OperationError {
         source: DebugProbeError,
         speed: u32,
     },
+    #[error("The JTAG TAP '{tap}' could not be selected.")]
+    FailedToSelectJtagTap {
+        #[source]
+        source: DebugProbeError,
+        tap: JtagTapSelector,
+    },
     #[error("Connecting to the chip was unsuccessful.")]
     AttachingFailed {
         #[source]
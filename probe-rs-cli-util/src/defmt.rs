@@ -0,0 +1,214 @@
+//! Decoding of defmt log frames, independent of the transport they were received over.
+//!
+//! The same table and formatting is used for RTT channels, ITM stimulus ports and raw byte
+//! streams, e.g. from a file or a UART.
+
+use anyhow::anyhow;
+use defmt_decoder::{DecodeError, Frame, Locations, StreamDecoder, Table};
+use std::fmt::Write;
+
+/// Loads the defmt table and the locations of the log statements from an ELF file.
+///
+/// Returns `None` if the ELF file doesn't contain a defmt table.
+pub fn load_defmt_state(elf: &[u8]) -> Result<Option<(Table, Option<Locations>)>, anyhow::Error> {
+    let table = match Table::parse(elf)? {
+        Some(table) => table,
+        None => {
+            log::warn!("No `Table` definition in DWARF info; compile your program with `debug = 2` to enable location info.");
+            return Ok(None);
+        }
+    };
+
+    let locs = table.get_locations(elf)?;
+
+    let locs = if !table.is_empty() && locs.is_empty() {
+        log::warn!("Insufficient DWARF info; compile your program with `debug = 2` to enable location info.");
+        None
+    } else if table.indices().all(|idx| locs.contains_key(&(idx as u64))) {
+        Some(locs)
+    } else {
+        log::warn!("Location info is incomplete; it will be omitted from the output.");
+        None
+    };
+
+    Ok(Some((table, locs)))
+}
+
+/// Formats a decoded frame as a line, followed by its source location if `show_location` is set.
+pub fn format_frame(
+    frame: &Frame,
+    locations: Option<&Locations>,
+    show_location: bool,
+    out: &mut String,
+) {
+    writeln!(out, "{}", frame.display(false)).map_or_else(
+        |err| log::error!("Failed to format defmt frame - {:?}", err),
+        |r| r,
+    );
+
+    if !show_location {
+        return;
+    }
+
+    match locations.and_then(|locs| locs.get(&frame.index())) {
+        Some(loc) => {
+            let relpath = match std::env::current_dir()
+                .ok()
+                .and_then(|dir| loc.file.strip_prefix(dir).ok().map(|p| p.to_owned()))
+            {
+                Some(relpath) => relpath,
+                // not relative; use full path
+                None => loc.file.clone(),
+            };
+            writeln!(out, "└─ {}:{}", relpath.display(), loc.line).map_or_else(
+                |err| log::error!("Failed to format defmt frame - {:?}", err),
+                |r| r,
+            );
+        }
+        None => {
+            writeln!(
+                out,
+                "└─ <invalid location: defmt frame-index: {}>",
+                frame.index()
+            )
+            .map_or_else(
+                |err| log::error!("Failed to format defmt frame - {:?}", err),
+                |r| r,
+            );
+        }
+    }
+}
+
+/// Decodes a stream of defmt data, received in chunks of any size, into formatted log lines.
+///
+/// Frames split across chunks are decoded once the rest of the frame has been received.
+pub struct DefmtDecoder<'a> {
+    table: &'a Table,
+    locations: Option<&'a Locations>,
+    decoder: Box<dyn StreamDecoder + 'a>,
+    show_location: bool,
+}
+
+impl<'a> DefmtDecoder<'a> {
+    /// Creates a decoder using the table and locations loaded with [`load_defmt_state`].
+    pub fn new(defmt_state: &'a (Table, Option<Locations>), show_location: bool) -> Self {
+        let (table, locations) = defmt_state;
+
+        Self {
+            table,
+            locations: locations.as_ref(),
+            decoder: table.new_stream_decoder(),
+            show_location,
+        }
+    }
+
+    /// Adds received data to the decoder.
+    pub fn received(&mut self, data: &[u8]) {
+        self.decoder.received(data);
+    }
+
+    /// Decodes all complete frames received so far and returns them formatted, one per line.
+    ///
+    /// Malformed frames are skipped if the encoding allows recovering from them. Otherwise an
+    /// error is returned, and some data may have been lost.
+    pub fn decode(&mut self) -> Result<String, anyhow::Error> {
+        let mut formatted_data = String::new();
+
+        loop {
+            match self.decoder.decode() {
+                Ok(frame) => {
+                    format_frame(
+                        &frame,
+                        self.locations,
+                        self.show_location,
+                        &mut formatted_data,
+                    );
+                }
+                Err(DecodeError::UnexpectedEof) => break,
+                Err(DecodeError::Malformed) => match self.table.encoding().can_recover() {
+                    // If recovery is impossible, break out of here and propagate the error.
+                    false => {
+                        return Err(anyhow!("Unrecoverable error while decoding Defmt data and some data may have been lost: {:?}", DecodeError::Malformed));
+                    }
+                    // If recovery is possible, skip the current frame and continue with new data.
+                    true => continue,
+                },
+            }
+        }
+
+        Ok(formatted_data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    fn defmt_state(elf: &str) -> (Table, Option<Locations>) {
+        let elf = std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join(elf)).unwrap();
+        load_defmt_state(&elf).unwrap().unwrap()
+    }
+
+    fn decode_chunks(decoder: &mut DefmtDecoder, chunks: &[&[u8]]) -> Vec<String> {
+        let mut output = String::new();
+        for chunk in chunks {
+            decoder.received(chunk);
+            output.push_str(&decoder.decode().unwrap());
+        }
+
+        output.lines().map(ToOwned::to_owned).collect()
+    }
+
+    #[test]
+    fn split_rzcobs_frames() {
+        let defmt_state = defmt_state("tests/data/defmt/defmt_rzcobs.o");
+        let mut decoder = DefmtDecoder::new(&defmt_state, false);
+
+        // `Hello, 42!` and `Goodbye`, split within and between the frames
+        let lines = decode_chunks(
+            &mut decoder,
+            &[&[0x2a], &[0x7b], &[0x00, 0x01, 0x7e], &[0x00]],
+        );
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("Hello, 42!"), "{}", lines[0]);
+        assert!(lines[1].ends_with("Goodbye"), "{}", lines[1]);
+    }
+
+    #[test]
+    fn skip_malformed_rzcobs_frame() {
+        let defmt_state = defmt_state("tests/data/defmt/defmt_rzcobs.o");
+        let mut decoder = DefmtDecoder::new(&defmt_state, false);
+
+        // A frame with an unknown index, followed by a valid frame
+        let lines = decode_chunks(&mut decoder, &[&[0x05, 0x7e, 0x00], &[0x01, 0x7e, 0x00]]);
+
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with("Goodbye"), "{}", lines[0]);
+    }
+
+    #[test]
+    fn split_raw_frames() {
+        let defmt_state = defmt_state("tests/data/defmt/defmt_raw.o");
+        let mut decoder = DefmtDecoder::new(&defmt_state, false);
+
+        let lines = decode_chunks(&mut decoder, &[&[0x00, 0x00], &[0x2a, 0x01], &[0x00]]);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("Hello, 42!"), "{}", lines[0]);
+        assert!(lines[1].ends_with("Goodbye"), "{}", lines[1]);
+    }
+
+    #[test]
+    fn show_location_without_locations() {
+        let defmt_state = defmt_state("tests/data/defmt/defmt_raw.o");
+        let mut decoder = DefmtDecoder::new(&defmt_state, true);
+
+        let lines = decode_chunks(&mut decoder, &[&[0x01, 0x00]]);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("Goodbye"), "{}", lines[0]);
+        assert_eq!(lines[1], "└─ <invalid location: defmt frame-index: 1>");
+    }
+}
@@ -1,8 +1,11 @@
 pub mod common_options;
+pub mod defmt;
 pub mod flash;
 pub mod logging;
 pub mod meta;
+pub mod probe_aliases;
 pub mod rtt;
+pub mod rtt_log;
 
 use cargo_toml::Manifest;
 use serde::Deserialize;
@@ -16,6 +19,7 @@ use std::{
 
 // Re-export crates to avoid version conflicts in the dependent crates.
 pub use clap;
+pub use defmt_decoder;
 pub use indicatif;
 pub use log;
 
@@ -0,0 +1,159 @@
+//! User defined names for debug probes.
+//!
+//! Aliases are read from the `probes.toml` file in the probe-rs configuration directory,
+//! e.g. `~/.config/probe-rs/probes.toml` on Linux:
+//!
+//! ```toml
+//! [aliases]
+//! bench-3 = "0483:3748@1-4.2"
+//! nrf-dk = "1366:1015:000683111111"
+//! ```
+//!
+//! An alias can then be used everywhere a probe selector is expected, e.g. `--probe bench-3`.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use once_cell::sync::Lazy;
+use probe_rs::{DebugProbeInfo, DebugProbeSelector};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// The name of the file containing the probe aliases.
+const ALIASES_FILE_NAME: &str = "probes.toml";
+
+/// The probe aliases of the current user, loaded when they are first used.
+static ALIASES: Lazy<Result<ProbeAliases, String>> = Lazy::new(|| {
+    ProbeAliases::load().map_err(|e| match std::error::Error::source(&e) {
+        Some(source) => format!("{e} {source}"),
+        None => e.to_string(),
+    })
+});
+
+#[derive(Debug, Error)]
+pub enum ProbeAliasError {
+    #[error("Failed to read the probe aliases from '{path}'.")]
+    Io {
+        #[source]
+        source: std::io::Error,
+        path: PathBuf,
+    },
+    #[error("Failed to parse the probe aliases in '{path}'.")]
+    Parse {
+        #[source]
+        source: toml::de::Error,
+        path: PathBuf,
+    },
+}
+
+/// A set of user defined names for debug probes.
+#[derive(Debug, Default, Deserialize)]
+pub struct ProbeAliases {
+    #[serde(default)]
+    aliases: BTreeMap<String, DebugProbeSelector>,
+}
+
+impl ProbeAliases {
+    /// The path of the file containing the probe aliases, if a configuration directory exists.
+    pub fn path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("rs", "probe-rs", "probe-rs")
+            .map(|dirs| dirs.config_dir().join(ALIASES_FILE_NAME))
+    }
+
+    /// Load the probe aliases of the current user.
+    ///
+    /// If the aliases file does not exist, no aliases are defined.
+    pub fn load() -> Result<Self, ProbeAliasError> {
+        match Self::path() {
+            Some(path) if path.exists() => {
+                let contents =
+                    std::fs::read_to_string(&path).map_err(|source| ProbeAliasError::Io {
+                        source,
+                        path: path.clone(),
+                    })?;
+
+                Self::parse(&contents).map_err(|source| ProbeAliasError::Parse { source, path })
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+
+    fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Get the selector for the probe with the given alias.
+    pub fn get(&self, alias: &str) -> Option<&DebugProbeSelector> {
+        self.aliases.get(alias)
+    }
+
+    /// Find the alias of the given probe, if it has one.
+    pub fn alias_of(&self, info: &DebugProbeInfo) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|(_, selector)| selector.matches(info))
+            .map(|(alias, _)| alias.as_str())
+    }
+}
+
+/// Parse a probe selector, which is either an alias or a selector
+/// in the form `VID:PID[:Serial][@Port]`.
+///
+/// This can be used as a `clap` value parser.
+pub fn parse_probe_selector(value: &str) -> Result<DebugProbeSelector, String> {
+    match ALIASES.as_ref()?.get(value) {
+        Some(selector) => Ok(selector.clone()),
+        None => value.parse().map_err(|e| format!("{e}")),
+    }
+}
+
+/// Get the selector for the probe with the given alias of the current user.
+pub fn resolve_alias(alias: &str) -> Result<DebugProbeSelector, String> {
+    ALIASES
+        .as_ref()?
+        .get(alias)
+        .cloned()
+        .ok_or_else(|| format!("The probe alias '{alias}' is not defined."))
+}
+
+#[cfg(test)]
+mod test {
+    use super::ProbeAliases;
+    use probe_rs::{DebugProbeInfo, DebugProbeType};
+
+    #[test]
+    fn parse_aliases() {
+        let aliases = ProbeAliases::parse(
+            r#"
+            [aliases]
+            bench-3 = "0483:3748@1-4.2"
+            nrf-dk = "1366:1015:000683111111"
+            "#,
+        )
+        .unwrap();
+
+        let selector = aliases.get("bench-3").unwrap();
+        assert_eq!(selector.vendor_id, 0x0483);
+        assert_eq!(selector.usb_port_path.as_deref(), Some("1-4.2"));
+
+        let mut info = DebugProbeInfo::new(
+            "STLink V2",
+            0x0483,
+            0x3748,
+            None,
+            DebugProbeType::StLink,
+            None,
+        );
+        info.usb_port_path = Some("1-4.2".to_owned());
+        assert_eq!(aliases.alias_of(&info), Some("bench-3"));
+
+        info.usb_port_path = Some("1-4.3".to_owned());
+        assert_eq!(aliases.alias_of(&info), None);
+    }
+
+    #[test]
+    fn empty_aliases() {
+        let aliases = ProbeAliases::parse("").unwrap();
+
+        assert!(aliases.get("bench-3").is_none());
+    }
+}
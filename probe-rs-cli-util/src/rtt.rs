@@ -1,41 +1,49 @@
+use crate::defmt::{load_defmt_state, DefmtDecoder};
+use crate::rtt_log::{ChannelLogger, RotationConfig};
 use crate::*;
 use anyhow::{anyhow, Result};
-use defmt_decoder::DecodeError;
 use num_traits::Zero;
 use probe_rs::config::MemoryRegion;
 use probe_rs::Core;
+use probe_rs_rtt::framing::{FrameFormat, Framing};
 pub use probe_rs_rtt::ChannelMode;
 use probe_rs_rtt::{DownChannel, Rtt, ScanRegion, UpChannel};
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::fs::File;
 use std::{
     fmt,
     fmt::Write,
     fs,
     io::{Read, Seek},
     str::FromStr,
+    time::Duration,
 };
 use time::OffsetDateTime;
 
+/// Attaches to RTT using the control block address from `elf_file`, see [`Rtt::attach_elf`].
+///
+/// If the firmware has not initialized RTT yet, attaching is retried for up to `timeout`.
 pub fn attach_to_rtt(
     core: &mut Core,
     memory_map: &[MemoryRegion],
     elf_file: &Path,
     rtt_config: &RttConfig,
+    timeout: Duration,
 ) -> Result<crate::rtt::RttActiveTarget, anyhow::Error> {
     log::info!("Initializing RTT");
-    let rtt_header_address = if let Ok(mut file) = File::open(elf_file) {
-        if let Some(address) = RttActiveTarget::get_rtt_symbol(&mut file) {
-            ScanRegion::Exact(address as u32)
-        } else {
-            ScanRegion::Ram
+    let attach_result = match fs::read(elf_file) {
+        Ok(elf) => Rtt::attach_elf(core, memory_map, &elf, timeout),
+        Err(err) => {
+            log::warn!(
+                "Failed to read {}, scanning RAM for RTT: {}",
+                elf_file.display(),
+                err
+            );
+            Rtt::attach_region(core, memory_map, &ScanRegion::Ram)
         }
-    } else {
-        ScanRegion::Ram
     };
 
-    match Rtt::attach_region(core, memory_map, &rtt_header_address) {
+    match attach_result {
         Ok(rtt) => {
             log::info!("RTT initialized.");
             let app = RttActiveTarget::new(rtt, elf_file, rtt_config)?;
@@ -92,6 +100,14 @@ pub struct RttConfig {
     #[structopt(skip)]
     #[serde(default = "default_channel_formats", rename = "rttChannelFormats")]
     pub channels: Vec<RttChannelConfig>,
+    /// Continuously write the data received on all up channels to log files in this directory.
+    #[structopt(skip)]
+    #[serde(default, rename = "rttLogPath")]
+    pub log_path: Option<PathBuf>,
+    /// Controls when a new log file is started, if `log_path` is set.
+    #[structopt(skip)]
+    #[serde(default, rename = "rttLogRotation")]
+    pub log_rotation: RotationConfig,
 }
 
 /// The User specified configuration for each active RTT Channel. The configuration is passed via a DAP Client configuration (`launch.json`). If no configuration is specified, the defaults will be `Dataformat::String` and `show_timestamps=false`.
@@ -110,6 +126,10 @@ pub struct RttChannelConfig {
     #[serde(default = "default_include_location")]
     // Control the inclusion of source location information for DataFormat::Defmt.
     pub show_location: bool,
+    #[structopt(skip)]
+    #[serde(default)]
+    // Split the data of DataFormat::BinaryLE channels into frames, and frame data written to the channel.
+    pub framing: Option<FrameFormat>,
 }
 
 /// This is the primary interface through which RTT channel data is read and written. Every actual RTT channel has a configuration and buffer that is used for this purpose.
@@ -124,6 +144,12 @@ pub struct RttActiveChannel {
     rtt_buffer: RttBuffer,
     show_timestamps: bool,
     show_location: bool,
+    /// Writes all data received on the channel to disk, see [`RttActiveTarget::enable_logging`].
+    logger: Option<ChannelLogger>,
+    /// Splits received data into frames, and frames data written with [`RttActiveChannel::write_frame`].
+    framing: Option<Box<dyn Framing>>,
+    /// Number of bytes received so far, used as offset in the hex dump of binary channels.
+    bytes_received: usize,
 }
 
 /// A fully configured RttActiveChannel. The configuration will always try to 'default' based on information read from the RTT control block in the binary. Where insufficient information is available, it will use the supplied configuration, with final hardcoded defaults where no other information was available.
@@ -188,6 +214,9 @@ impl RttActiveChannel {
             rtt_buffer: RttBuffer::new(buffer_size),
             show_timestamps: full_config.show_timestamps,
             show_location,
+            logger: None,
+            framing: full_config.framing.map(FrameFormat::framing),
+            bytes_received: 0,
         }
     }
 
@@ -235,6 +264,11 @@ impl RttActiveChannel {
         self
             .poll_rtt(core)
             .map(|bytes_read| {
+                if let Some(logger) = self.logger.as_mut() {
+                    if let Err(error) = logger.log(&self.rtt_buffer.0[..bytes_read], defmt_state) {
+                        log::warn!("Failed to write RTT data for channel {} to the log file: {}", self.channel_name, error);
+                    }
+                }
                 Ok((
                     self.number().unwrap_or(0).to_string(), // If the Channel doesn't have a number, then send the output to channel 0
                     {
@@ -251,55 +285,39 @@ impl RttActiveChannel {
                                 }
                             }
                             DataFormat::BinaryLE => {
-                                for element in &self.rtt_buffer.0[..bytes_read] {
-                                    // Width of 4 allows 0xFF to be printed.
-                                    write!(formatted_data, "{element:#04x}").map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                }
-                            }
-                            DataFormat::Defmt => {
-                                match defmt_state {
-                                    Some((table, locs)) => {
-                                        let mut stream_decoder = table.new_stream_decoder();
-                                        stream_decoder.received(&self.rtt_buffer.0[..bytes_read]);
-                                        loop {
-                                            match stream_decoder.decode() {
+                                let incoming = &self.rtt_buffer.0[..bytes_read];
+                                match self.framing.as_mut() {
+                                    Some(framing) => {
+                                        framing.received(incoming);
+                                        while let Some(frame) = framing.decode() {
+                                            match frame {
                                                 Ok(frame) => {
-                                                    let loc = locs.as_ref().and_then(|locs| locs.get(&frame.index()) );
-                                                    writeln!(formatted_data, "{}", frame.display(false)).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                                    if self.show_location {
-                                                        if let Some(loc) = loc {
-                                                            let relpath = if let Ok(relpath) =
-                                                                loc.file.strip_prefix(&std::env::current_dir().unwrap())
-                                                            {
-                                                                relpath
-                                                            } else {
-                                                                // not relative; use full path
-                                                                &loc.file
-                                                            };
-                                                            writeln!(formatted_data,
-                                                                "└─ {}:{}",
-                                                                relpath.display(),
-                                                                loc.line
-                                                            ).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                                        } else {
-                                                            writeln!(formatted_data, "└─ <invalid location: defmt frame-index: {}>", frame.index()).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                                        }
+                                                    writeln!(formatted_data, "Frame ({} bytes):", frame.len()).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
+                                                    for line in hex_dump(0, &frame) {
+                                                        writeln!(formatted_data, "{line}").map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
                                                     }
-                                                    continue;
-                                                },
-                                                Err(DecodeError::UnexpectedEof) => break,
-                                                Err(DecodeError::Malformed) => match table.encoding().can_recover() {
-                                                    // If recovery is impossible, break out of here and propagate the error.
-                                                    false => {
-                                                        return Err(anyhow!("Unrecoverable error while decoding Defmt data and some data may have been lost: {:?}", DecodeError::Malformed));
-                                                    },
-                                                    // If recovery is possible, skip the current frame and continue with new data.
-                                                    true => continue,
-                                                },
-
+                                                }
+                                                Err(err) => {
+                                                    writeln!(formatted_data, "Invalid frame: {err}").map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
+                                                }
                                             }
                                         }
                                     }
+                                    None => {
+                                        for line in hex_dump(self.bytes_received, incoming) {
+                                            writeln!(formatted_data, "{line}").map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
+                                        }
+                                    }
+                                }
+                                self.bytes_received += bytes_read;
+                            }
+                            DataFormat::Defmt => {
+                                match defmt_state {
+                                    Some(defmt_state) => {
+                                        let mut decoder = DefmtDecoder::new(defmt_state, self.show_location);
+                                        decoder.received(&self.rtt_buffer.0[..bytes_read]);
+                                        formatted_data.push_str(&decoder.decode()?);
+                                    }
                                     None => {
                                         write!(formatted_data, "Running rtt in defmt mode but table or locations could not be loaded.")
                                             .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
@@ -313,6 +331,25 @@ impl RttActiveChannel {
             }).transpose()
     }
 
+    /// Writes `payload` as a single frame to the down channel, using the framing configured for the channel.
+    ///
+    /// Returns `false` if there currently is not enough space in the channel buffer for the frame, in which case
+    /// nothing was written.
+    pub fn write_frame(&mut self, core: &mut Core, payload: &[u8]) -> Result<bool, anyhow::Error> {
+        let framing = self.framing.as_deref().ok_or_else(|| {
+            anyhow!(
+                "No framing is configured for RTT channel {}",
+                self.channel_name
+            )
+        })?;
+        let down_channel = self
+            .down_channel
+            .as_ref()
+            .ok_or_else(|| anyhow!("RTT channel {} has no down channel", self.channel_name))?;
+
+        Ok(down_channel.write_frame(core, framing, payload)?)
+    }
+
     pub fn _push_rtt(&mut self, core: &mut Core) {
         if let Some(down_channel) = self.down_channel.as_mut() {
             self._input_data += "\n";
@@ -379,48 +416,63 @@ impl RttActiveTarget {
                     err
                 )
             })?;
-            if let Some(table) = defmt_decoder::Table::parse(&elf)? {
-                let locs = {
-                    let locs = table.get_locations(&elf)?;
-
-                    if !table.is_empty() && locs.is_empty() {
-                        log::warn!("Insufficient DWARF info; compile your program with `debug = 2` to enable location info.");
-                        None
-                    } else if table.indices().all(|idx| locs.contains_key(&(idx as u64))) {
-                        Some(locs)
-                    } else {
-                        log::warn!(
-                            "Location info is incomplete; it will be omitted from the output."
-                        );
-                        None
-                    }
-                };
-                Some((table, locs))
-            } else {
-                log::warn!("No `Table` definition in DWARF info; compile your program with `debug = 2` to enable location info.");
-                None
-            }
+            load_defmt_state(&elf)?
         } else {
             None
         };
 
-        Ok(Self {
+        let mut active_target = Self {
             active_channels,
             defmt_state,
-        })
+        };
+
+        if let Some(log_path) = &rtt_config.log_path {
+            active_target
+                .enable_logging(log_path, &rtt_config.log_rotation)
+                .map_err(|err| {
+                    anyhow!(
+                        "Error creating RTT log files in {}: {}",
+                        log_path.display(),
+                        err
+                    )
+                })?;
+        }
+
+        Ok(active_target)
     }
 
+    /// Continuously writes the data received on all up channels to log files in `directory`.
+    ///
+    /// The files are named after the channel number and name, and are rotated according to `rotation`.
+    pub fn enable_logging(
+        &mut self,
+        directory: &Path,
+        rotation: &RotationConfig,
+    ) -> std::io::Result<()> {
+        for channel in self.active_channels.iter_mut() {
+            if let Some(number) = channel.number() {
+                let name = format!("channel{}-{}", number, channel.channel_name);
+                channel.logger = Some(ChannelLogger::new(
+                    directory,
+                    &name,
+                    channel.data_format,
+                    rotation,
+                )?);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[deprecated(
+        since = "0.15.0",
+        note = "This function is deprecated and will be removed in a future version. Please use `probe_rs_rtt::find_rtt_symbol` instead."
+    )]
     pub fn get_rtt_symbol<T: Read + Seek>(file: &mut T) -> Option<u64> {
         let mut buffer = Vec::new();
         if file.read_to_end(&mut buffer).is_ok() {
-            if let Ok(binary) = goblin::elf::Elf::parse(buffer.as_slice()) {
-                for sym in &binary.syms {
-                    if let Some(name) = binary.strtab.get_at(sym.st_name) {
-                        if name == "_SEGGER_RTT" {
-                            return Some(sym.st_value);
-                        }
-                    }
-                }
+            if let Some(address) = probe_rs_rtt::find_rtt_symbol(&buffer) {
+                return Some(address);
             }
         }
 
@@ -467,6 +519,34 @@ impl RttActiveTarget {
     // }
 }
 
+/// Formats `data` as a hex dump with 16 bytes per line, starting at `offset`.
+///
+/// Every line contains the offset, the bytes in hex and their printable ASCII characters.
+pub fn hex_dump(offset: usize, data: &[u8]) -> Vec<String> {
+    data.chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut line = format!("{:08x}:", offset + i * 16);
+            for byte in chunk {
+                let _ = write!(line, " {byte:02x}");
+            }
+            for _ in chunk.len()..16 {
+                line.push_str("   ");
+            }
+            line.push_str("  |");
+            line.extend(chunk.iter().map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            }));
+            line.push('|');
+            line
+        })
+        .collect()
+}
+
 struct RttBuffer(Vec<u8>);
 impl RttBuffer {
     /// Initialize the buffer and ensure it has enough capacity to match the size of the RTT channel on the target at the time of instantiation. Doing this now prevents later performance impact if the buffer capacity has to be grown dynamically.
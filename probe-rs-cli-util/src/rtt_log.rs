@@ -0,0 +1,624 @@
+//! Continuous capture of RTT channels to log files.
+//!
+//! A [`ChannelLogger`] writes everything received on an RTT channel to disk as soon as it
+//! arrives, so no data is lost if the host program exits or crashes. Text channels are written
+//! line by line with a host timestamp in front of every line, binary channels are written
+//! unmodified. For defmt channels, the decoded frames are written to a text log and additionally
+//! exported as [JSON Lines](https://jsonlines.org/) for log analysis tools.
+//!
+//! The log files can be rotated by size and age, see [`RotationConfig`].
+
+use crate::rtt::DataFormat;
+use anyhow::anyhow;
+use defmt_decoder::{DecodeError, Encoding, Frame, Locations, Table};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// Controls when a new log file is started.
+///
+/// Rotated files are numbered, the file with the highest number is the one currently written.
+/// Without any limits set, a single file is written for the whole session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RotationConfig {
+    /// Start a new file once the current one reaches this size in bytes.
+    pub max_size: Option<u64>,
+    /// Start a new file once the current one is older than this many seconds.
+    pub max_age: Option<u64>,
+    /// Number of files to keep. The oldest files are removed when a new file is started.
+    pub max_files: Option<usize>,
+}
+
+/// A log file which is split into multiple files according to a [`RotationConfig`].
+#[derive(Debug)]
+pub struct RotatingFile {
+    directory: PathBuf,
+    name: String,
+    extension: &'static str,
+    rotation: RotationConfig,
+    file: BufWriter<File>,
+    index: usize,
+    size: u64,
+    opened: Instant,
+}
+
+impl RotatingFile {
+    /// Creates a new file named `<name>-<index>.<extension>` in `directory`.
+    ///
+    /// Existing log files are kept, numbering continues after the highest index found in
+    /// `directory`.
+    pub fn create(
+        directory: &Path,
+        name: &str,
+        extension: &'static str,
+        rotation: &RotationConfig,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+
+        let index = Self::existing_indices(directory, name, extension)?
+            .into_iter()
+            .max()
+            .map_or(0, |index| index + 1);
+
+        let file = Self::open(directory, name, extension, index)?;
+
+        let file = Self {
+            directory: directory.to_owned(),
+            name: name.to_owned(),
+            extension,
+            rotation: rotation.clone(),
+            file,
+            index,
+            size: 0,
+            opened: Instant::now(),
+        };
+        file.remove_old_files()?;
+
+        Ok(file)
+    }
+
+    /// Indices of the log files with the given name and extension in `directory`.
+    fn existing_indices(directory: &Path, name: &str, extension: &str) -> io::Result<Vec<usize>> {
+        let prefix = format!("{name}-");
+        let suffix = format!(".{extension}");
+
+        let mut indices = Vec::new();
+        for entry in fs::read_dir(directory)? {
+            let file_name = entry?.file_name();
+            let index = file_name
+                .to_str()
+                .and_then(|file_name| file_name.strip_prefix(&prefix))
+                .and_then(|file_name| file_name.strip_suffix(&suffix))
+                .filter(|index| index.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|index| index.parse().ok());
+
+            if let Some(index) = index {
+                indices.push(index);
+            }
+        }
+
+        Ok(indices)
+    }
+
+    /// Path of the file with the given index.
+    fn path(directory: &Path, name: &str, extension: &str, index: usize) -> PathBuf {
+        directory.join(format!("{name}-{index:04}.{extension}"))
+    }
+
+    fn open(
+        directory: &Path,
+        name: &str,
+        extension: &str,
+        index: usize,
+    ) -> io::Result<BufWriter<File>> {
+        File::options()
+            .write(true)
+            .create_new(true)
+            .open(Self::path(directory, name, extension, index))
+            .map(BufWriter::new)
+    }
+
+    /// Path of the file currently written.
+    pub fn current_path(&self) -> PathBuf {
+        Self::path(&self.directory, &self.name, self.extension, self.index)
+    }
+
+    /// Writes a complete record, e.g. a line, starting a new file first if the current one is
+    /// full or too old. Records are never split across files.
+    pub fn write_record(&mut self, record: &[u8]) -> io::Result<()> {
+        if self.needs_rotation(record.len() as u64) {
+            self.rotate()?;
+        }
+
+        self.file.write_all(record)?;
+        self.size += record.len() as u64;
+
+        Ok(())
+    }
+
+    /// Writes the buffered data to disk.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    fn needs_rotation(&self, record_size: u64) -> bool {
+        // Always write at least one record to a file, even if it's larger than the limit
+        if self.size == 0 {
+            return false;
+        }
+
+        let too_large = self
+            .rotation
+            .max_size
+            .map(|max_size| self.size + record_size > max_size)
+            .unwrap_or(false);
+        let too_old = self
+            .rotation
+            .max_age
+            .map(|max_age| self.opened.elapsed() >= Duration::from_secs(max_age))
+            .unwrap_or(false);
+
+        too_large || too_old
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        self.index += 1;
+        self.file = Self::open(&self.directory, &self.name, self.extension, self.index)?;
+        self.size = 0;
+        self.opened = Instant::now();
+
+        self.remove_old_files()
+    }
+
+    /// Removes the oldest files, so at most `max_files` files are kept including the current one.
+    fn remove_old_files(&self) -> io::Result<()> {
+        let max_files = match self.rotation.max_files {
+            Some(max_files) => max_files.max(1),
+            None => return Ok(()),
+        };
+
+        for index in Self::existing_indices(&self.directory, &self.name, self.extension)? {
+            if index + max_files <= self.index {
+                let path = Self::path(&self.directory, &self.name, self.extension, index);
+
+                if let Err(error) = fs::remove_file(&path) {
+                    log::warn!(
+                        "Failed to remove old log file {}: {}",
+                        path.display(),
+                        error
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A decoded defmt frame, as exported to the JSON Lines log.
+#[derive(Debug, Serialize)]
+struct JsonFrame<'a> {
+    /// Time the frame was received by the host, in RFC 3339 format
+    host_timestamp: &'a str,
+    /// Timestamp of the frame, as formatted by the target's timestamp format
+    timestamp: Option<String>,
+    level: Option<&'static str>,
+    module: Option<&'a str>,
+    file: Option<String>,
+    line: Option<u64>,
+    message: String,
+    /// Index of the format string in the defmt table
+    index: u64,
+}
+
+/// Writes the data received on a single RTT channel to log files.
+#[derive(Debug)]
+pub struct ChannelLogger {
+    format: DataFormat,
+    log: RotatingFile,
+    /// JSON Lines export of decoded defmt frames
+    json: Option<RotatingFile>,
+    /// Text received after the last complete line
+    partial_line: Vec<u8>,
+    /// defmt data received after the last complete frame
+    partial_frame: Vec<u8>,
+}
+
+impl ChannelLogger {
+    /// Creates the log files for a channel in `directory`, named after `name`.
+    ///
+    /// Text channels are logged to `.log` files, binary channels to `.bin` files, and defmt
+    /// channels to `.log` and `.jsonl` files.
+    pub fn new(
+        directory: &Path,
+        name: &str,
+        format: DataFormat,
+        rotation: &RotationConfig,
+    ) -> io::Result<Self> {
+        let sanitize_options = sanitize_filename::Options {
+            replacement: "_",
+            ..Default::default()
+        };
+        let name = sanitize_filename::sanitize_with_options(name, sanitize_options);
+
+        let extension = match format {
+            DataFormat::String | DataFormat::Defmt => "log",
+            DataFormat::BinaryLE => "bin",
+        };
+
+        let json = match format {
+            DataFormat::Defmt => Some(RotatingFile::create(directory, &name, "jsonl", rotation)?),
+            DataFormat::String | DataFormat::BinaryLE => None,
+        };
+
+        Ok(Self {
+            format,
+            log: RotatingFile::create(directory, &name, extension, rotation)?,
+            json,
+            partial_line: Vec::new(),
+            partial_frame: Vec::new(),
+        })
+    }
+
+    /// Path of the log file currently written.
+    pub fn current_path(&self) -> PathBuf {
+        self.log.current_path()
+    }
+
+    /// Writes data received from the channel to the log files.
+    ///
+    /// `defmt_state` is required to decode defmt channels, their data is dropped otherwise.
+    pub fn log(
+        &mut self,
+        data: &[u8],
+        defmt_state: Option<&(Table, Option<Locations>)>,
+    ) -> anyhow::Result<()> {
+        match self.format {
+            DataFormat::String => self.log_lines(data)?,
+            DataFormat::BinaryLE => self.log.write_record(data)?,
+            DataFormat::Defmt => match defmt_state {
+                Some((table, locations)) => self.log_frames(data, table, locations.as_ref())?,
+                None => {
+                    return Err(anyhow!(
+                        "Unable to log defmt data, the defmt table could not be loaded."
+                    ))
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Writes the buffered data to disk.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if !self.partial_line.is_empty() {
+            // Write the incomplete line, so nothing is lost when the logger is dropped
+            let line = std::mem::take(&mut self.partial_line);
+            self.write_line(&line)?;
+        }
+
+        self.log.flush()?;
+
+        if let Some(json) = &mut self.json {
+            json.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn log_lines(&mut self, data: &[u8]) -> io::Result<()> {
+        self.partial_line.extend_from_slice(data);
+
+        while let Some(end) = self.partial_line.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial_line.drain(..=end).collect();
+            self.write_line(&line[..end])?;
+        }
+
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let mut record = format!("{} ", host_timestamp()).into_bytes();
+        record.extend_from_slice(line);
+        record.push(b'\n');
+
+        self.log.write_record(&record)
+    }
+
+    fn log_frames(
+        &mut self,
+        data: &[u8],
+        table: &Table,
+        locations: Option<&Locations>,
+    ) -> anyhow::Result<()> {
+        // The undecoded data is kept for the whole lifetime of the channel, so frames split
+        // across reads are decoded once the rest of the frame has been received.
+        self.partial_frame.extend_from_slice(data);
+
+        let host_timestamp = host_timestamp();
+
+        loop {
+            match table.encoding() {
+                Encoding::Raw => match table.decode(&self.partial_frame) {
+                    Ok((frame, consumed)) => {
+                        self.partial_frame.drain(..consumed);
+                        self.write_frame(&frame, locations, &host_timestamp)?;
+                    }
+                    Err(DecodeError::UnexpectedEof) => break,
+                    Err(DecodeError::Malformed) => {
+                        // Raw frames have no delimiter, so there's no way to find the next frame.
+                        self.partial_frame.clear();
+                        return Err(anyhow!(
+                            "Unrecoverable error while decoding defmt data for the log file, some data may have been lost."
+                        ));
+                    }
+                },
+                Encoding::Rzcobs => {
+                    // rzCOBS frames end with a zero byte, so only complete frames are decoded.
+                    let end = match self.partial_frame.iter().position(|&b| b == 0) {
+                        Some(end) => end,
+                        None => break,
+                    };
+                    let frame: Vec<u8> = self.partial_frame.drain(..=end).collect();
+
+                    let mut decoder = table.new_stream_decoder();
+                    decoder.received(&frame);
+
+                    match decoder.decode() {
+                        Ok(frame) => self.write_frame(&frame, locations, &host_timestamp)?,
+                        // Only separators, without any frame data
+                        Err(DecodeError::UnexpectedEof) => (),
+                        Err(DecodeError::Malformed) => {
+                            log::warn!("Skipping malformed defmt frame while logging RTT data");
+                        }
+                    }
+                }
+                encoding => {
+                    return Err(anyhow!(
+                        "Unable to log defmt data, the {:?} encoding is not supported.",
+                        encoding
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_frame(
+        &mut self,
+        frame: &Frame,
+        locations: Option<&Locations>,
+        host_timestamp: &str,
+    ) -> anyhow::Result<()> {
+        let location = locations.and_then(|locations| locations.get(&frame.index()));
+
+        let line = format!("{} {}\n", host_timestamp, frame.display(false));
+        self.log.write_record(line.as_bytes())?;
+
+        if let Some(json) = &mut self.json {
+            let record = JsonFrame {
+                host_timestamp,
+                timestamp: frame.display_timestamp().map(|ts| ts.to_string()),
+                level: frame.level().map(|level| level.as_str()),
+                module: location.map(|location| location.module.as_str()),
+                file: location.map(|location| location.file.display().to_string()),
+                line: location.map(|location| location.line),
+                message: frame.display_message().to_string(),
+                index: frame.index(),
+            };
+
+            let mut line = serde_json::to_vec(&record)?;
+            line.push(b'\n');
+            json.write_record(&line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for ChannelLogger {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            log::warn!("Failed to write RTT log file: {}", error);
+        }
+    }
+}
+
+/// The current time in RFC 3339 format, in the local time zone if it can be determined.
+fn host_timestamp() -> String {
+    let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+
+    now.format(&Rfc3339).unwrap_or_else(|_| now.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn log_files(directory: &Path) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn rotate_by_size() {
+        let directory = tempfile::tempdir().unwrap();
+        let rotation = RotationConfig {
+            max_size: Some(10),
+            max_age: None,
+            max_files: Some(2),
+        };
+
+        let mut file = RotatingFile::create(directory.path(), "test", "log", &rotation).unwrap();
+
+        for _ in 0..4 {
+            file.write_record(b"0123456789").unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(
+            log_files(directory.path()),
+            vec!["test-0002.log", "test-0003.log"]
+        );
+    }
+
+    #[test]
+    fn rotate_by_age() {
+        let directory = tempfile::tempdir().unwrap();
+        let rotation = RotationConfig {
+            max_size: None,
+            max_age: Some(1),
+            max_files: None,
+        };
+
+        let mut file = RotatingFile::create(directory.path(), "test", "log", &rotation).unwrap();
+
+        file.write_record(b"first").unwrap();
+        file.write_record(b"second").unwrap();
+        std::thread::sleep(Duration::from_millis(1100));
+        file.write_record(b"third").unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(
+            log_files(directory.path()),
+            vec!["test-0000.log", "test-0001.log"]
+        );
+        assert_eq!(
+            fs::read(directory.path().join("test-0000.log")).unwrap(),
+            b"firstsecond"
+        );
+        assert_eq!(
+            fs::read(directory.path().join("test-0001.log")).unwrap(),
+            b"third"
+        );
+    }
+
+    #[test]
+    fn keep_existing_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let rotation = RotationConfig {
+            max_size: None,
+            max_age: None,
+            max_files: Some(2),
+        };
+
+        for session in 0..3 {
+            let mut file =
+                RotatingFile::create(directory.path(), "test", "log", &rotation).unwrap();
+            file.write_record(format!("session {session}").as_bytes())
+                .unwrap();
+            file.flush().unwrap();
+        }
+
+        assert_eq!(
+            log_files(directory.path()),
+            vec!["test-0001.log", "test-0002.log"]
+        );
+        assert_eq!(
+            fs::read(directory.path().join("test-0001.log")).unwrap(),
+            b"session 1"
+        );
+    }
+
+    #[test]
+    fn log_lines_with_timestamps() {
+        let directory = tempfile::tempdir().unwrap();
+
+        let mut logger = ChannelLogger::new(
+            directory.path(),
+            "channel 0",
+            DataFormat::String,
+            &RotationConfig::default(),
+        )
+        .unwrap();
+
+        logger.log(b"first\nsec", None).unwrap();
+        logger.log(b"ond\nthird", None).unwrap();
+        let path = logger.current_path();
+        drop(logger);
+
+        let content = fs::read_to_string(path).unwrap();
+        let lines: Vec<&str> = content
+            .lines()
+            .map(|line| line.split_once(' ').unwrap().1)
+            .collect();
+
+        assert_eq!(lines, vec!["first", "second", "third"]);
+    }
+
+    fn log_defmt(elf: &str, chunks: &[&[u8]]) -> (Vec<String>, Vec<serde_json::Value>) {
+        let directory = tempfile::tempdir().unwrap();
+        let elf = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join(elf)).unwrap();
+        let defmt_state = crate::defmt::load_defmt_state(&elf).unwrap().unwrap();
+
+        let mut logger = ChannelLogger::new(
+            directory.path(),
+            "defmt",
+            DataFormat::Defmt,
+            &RotationConfig::default(),
+        )
+        .unwrap();
+
+        for chunk in chunks {
+            logger.log(chunk, Some(&defmt_state)).unwrap();
+        }
+        drop(logger);
+
+        let lines = fs::read_to_string(directory.path().join("defmt-0000.log"))
+            .unwrap()
+            .lines()
+            .map(|line| line.split_once(' ').unwrap().1.to_owned())
+            .collect();
+        let records = fs::read_to_string(directory.path().join("defmt-0000.jsonl"))
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        (lines, records)
+    }
+
+    #[test]
+    fn log_split_raw_defmt_frames() {
+        let elf = "tests/data/defmt/defmt_raw.o";
+
+        // `Hello, 42!` followed by `Goodbye`, split in the middle of both frames
+        let (lines, records) = log_defmt(elf, &[&[0x00], &[0x00, 0x2a, 0x01], &[0x00]]);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("Hello, 42!"), "{}", lines[0]);
+        assert!(lines[1].ends_with("Goodbye"), "{}", lines[1]);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["message"], "Hello, 42!");
+        assert_eq!(records[0]["level"], "info");
+        assert_eq!(records[0]["index"], 0);
+        assert_eq!(records[1]["message"], "Goodbye");
+        assert_eq!(records[1]["level"], "warn");
+        assert_eq!(records[1]["index"], 1);
+    }
+
+    #[test]
+    fn log_split_rzcobs_defmt_frames() {
+        let elf = "tests/data/defmt/defmt_rzcobs.o";
+
+        let (lines, records) = log_defmt(elf, &[&[0x2a], &[0x7b, 0x00, 0x01, 0x7e], &[0x00]]);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("Hello, 42!"), "{}", lines[0]);
+        assert!(lines[1].ends_with("Goodbye"), "{}", lines[1]);
+        assert_eq!(records[0]["message"], "Hello, 42!");
+        assert_eq!(records[1]["message"], "Goodbye");
+    }
+}
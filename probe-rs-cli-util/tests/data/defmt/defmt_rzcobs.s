@@ -0,0 +1,16 @@
+# Minimal defmt table with the rzcobs encoding, used by the defmt decoding tests.
+#
+# Assembled with `as defmt_rzcobs.s -o defmt_rzcobs.o`.
+
+    .section .defmt,"",@progbits
+    .globl "{\"package\":\"test\",\"tag\":\"defmt_info\",\"data\":\"Hello, {=u8}!\",\"disambiguator\":\"1\",\"crate_name\":\"test\"}"
+"{\"package\":\"test\",\"tag\":\"defmt_info\",\"data\":\"Hello, {=u8}!\",\"disambiguator\":\"1\",\"crate_name\":\"test\"}":
+    .byte 0
+    .globl "{\"package\":\"test\",\"tag\":\"defmt_warn\",\"data\":\"Goodbye\",\"disambiguator\":\"2\",\"crate_name\":\"test\"}"
+"{\"package\":\"test\",\"tag\":\"defmt_warn\",\"data\":\"Goodbye\",\"disambiguator\":\"2\",\"crate_name\":\"test\"}":
+    .byte 0
+
+    .globl "_defmt_version_ = 3"
+    .set "_defmt_version_ = 3", 1
+    .globl "_defmt_encoding_ = rzcobs"
+    .set "_defmt_encoding_ = rzcobs", 1
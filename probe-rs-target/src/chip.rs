@@ -27,6 +27,9 @@ pub struct Chip {
     /// [`ChipFamily::flash_algorithms`]: crate::ChipFamily::flash_algorithms
     #[serde(default)]
     pub flash_algorithms: Vec<String>,
+    /// JTAG-specific options, e.g. the description of the scan chain
+    /// if the chip shares it with other devices.
+    pub jtag: Option<Jtag>,
 }
 
 impl Chip {
@@ -44,10 +47,30 @@ impl Chip {
             }],
             memory_map: vec![],
             flash_algorithms: vec![],
+            jtag: None,
         }
     }
 }
 
+/// JTAG-specific options of a chip.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Jtag {
+    /// The TAPs of the JTAG scan chain, in the order in which they appear
+    /// in the IDCODE scan, i.e. starting with the TAP closest to TDO.
+    ///
+    /// If this is not set, the scan chain is detected automatically.
+    pub scan_chain: Option<Vec<ScanChainElement>>,
+}
+
+/// A single TAP in a JTAG scan chain.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ScanChainElement {
+    /// An optional, human readable name of the TAP.
+    pub name: Option<String>,
+    /// The length of the instruction register of the TAP.
+    pub ir_len: Option<u8>,
+}
+
 /// An individual core inside a chip
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Core {
@@ -70,6 +93,8 @@ pub enum CoreAccessOptions {
     Arm(ArmCoreAccessOptions),
     /// Riscv specific options
     Riscv(RiscvCoreAccessOptions),
+    /// Xtensa specific options
+    Xtensa(XtensaCoreAccessOptions),
 }
 
 /// The data required to access an ARM core
@@ -77,8 +102,22 @@ pub enum CoreAccessOptions {
 pub struct ArmCoreAccessOptions {
     /// The access port number to access the core
     pub ap: u8,
-    /// The port select number to access the core
+    /// The base address of the access port to access the core, for ADIv6 debug ports
+    /// which map their access ports into the address space of the debug port.
+    /// Takes precedence over `ap` if set.
+    #[serde(serialize_with = "hex_option")]
+    pub ap_address: Option<u64>,
+    /// The port select number to access the core.
+    ///
+    /// A non-zero value is used as the `TARGETSEL` value on a multidrop SWD bus.
+    /// Superseded by `targetsel`, which takes precedence if both are set.
+    #[serde(default)]
     pub psel: u32,
+    /// The `TARGETSEL` value which selects the debug port of the core on a
+    /// multidrop (SWDv2) SWD bus. If unset, the core is assumed to be the only
+    /// debug port on the bus.
+    #[serde(serialize_with = "hex_option")]
+    pub targetsel: Option<u32>,
     /// The base address of the debug registers for the core.
     /// Required for Cortex-A, optional for Cortex-M
     #[serde(serialize_with = "hex_option")]
@@ -87,8 +126,43 @@ pub struct ArmCoreAccessOptions {
     /// Required in ARMv8-A
     #[serde(serialize_with = "hex_option")]
     pub cti_base: Option<u64>,
+    /// The index of the TAP in the JTAG scan chain through which the core is accessed.
+    /// Only used for JTAG, defaults to the first TAP.
+    pub jtag_tap: Option<usize>,
+}
+
+impl ArmCoreAccessOptions {
+    /// The `TARGETSEL` value used to select the debug port of the core
+    /// on a multidrop SWD bus, if the core sits on one.
+    pub fn dp_targetsel(&self) -> Option<u32> {
+        self.targetsel.or(match self.psel {
+            0 => None,
+            psel => Some(psel),
+        })
+    }
 }
 
 /// The data required to access a Risc-V core
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RiscvCoreAccessOptions {}
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RiscvCoreAccessOptions {
+    /// The index of the TAP in the JTAG scan chain through which the core is accessed.
+    /// Defaults to the first TAP.
+    pub jtag_tap: Option<usize>,
+    /// The index of the hart on the debug module, as selected by `hartsel`.
+    #[serde(default)]
+    pub hart_index: u32,
+    /// The halt group of the hart, between 1 and 31.
+    ///
+    /// All harts in a halt group are halted together when one of them halts.
+    /// Only used if the debug module supports halt groups.
+    #[serde(default)]
+    pub halt_group: Option<u8>,
+}
+
+/// The data required to access an Xtensa core
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct XtensaCoreAccessOptions {
+    /// The index of the TAP in the JTAG scan chain through which the core is accessed.
+    /// Defaults to the first TAP.
+    pub jtag_tap: Option<usize>,
+}
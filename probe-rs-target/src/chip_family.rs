@@ -42,6 +42,10 @@ pub enum CoreType {
     Armv8m,
     /// RISC-V
     Riscv,
+    /// RISC-V with 64-bit registers (RV64)
+    Riscv64,
+    /// Xtensa LX: ESP32, ESP32-S2, ESP32-S3
+    Xtensa,
 }
 
 impl CoreType {
@@ -61,13 +65,16 @@ pub enum Architecture {
     Arm,
     /// A RISC-V core.
     Riscv,
+    /// An Xtensa core.
+    Xtensa,
 }
 
 impl CoreType {
     /// Returns the parent architecture family of this core type.
     pub fn architecture(&self) -> Architecture {
         match self {
-            CoreType::Riscv => Architecture::Riscv,
+            CoreType::Riscv | CoreType::Riscv64 => Architecture::Riscv,
+            CoreType::Xtensa => Architecture::Xtensa,
             _ => Architecture::Arm,
         }
     }
@@ -86,6 +93,12 @@ pub enum InstructionSet {
     RV32,
     /// RISC-V 32-bit compressed instruction sets (RV32C) - covers all ISA variants that allow compressed 16-bit instructions.
     RV32C,
+    /// RISC-V 64-bit uncompressed instruction sets (RV64) - covers all ISA variants that use 32-bit instructions.
+    RV64,
+    /// RISC-V 64-bit compressed instruction sets (RV64C) - covers all ISA variants that allow compressed 16-bit instructions.
+    RV64C,
+    /// Xtensa instruction set, with 24-bit instructions and 16-bit narrow instructions.
+    Xtensa,
 }
 
 impl InstructionSet {
@@ -100,6 +113,9 @@ impl InstructionSet {
             InstructionSet::A64 => 4,
             InstructionSet::RV32 => 4,
             InstructionSet::RV32C => 2,
+            InstructionSet::RV64 => 4,
+            InstructionSet::RV64C => 2,
+            InstructionSet::Xtensa => 2,
         }
     }
     /// Get the maximum instruction size in bytes. All supported architectures have a maximum instruction size of 4 bytes.
@@ -215,13 +231,28 @@ impl ChipFamily {
                             return Err(format!("Core {} requires setting cti_base", core.name));
                         }
                     }
-                    CoreAccessOptions::Riscv(_) => {
-                        if core.core_type != CoreType::Riscv {
+                    CoreAccessOptions::Riscv(options) => {
+                        if !matches!(core.core_type, CoreType::Riscv | CoreType::Riscv64) {
                             return Err(format!(
                                 "Riscv options don't match core type {:?} on core {}",
                                 core.core_type, core.name
                             ));
                         }
+
+                        if matches!(options.halt_group, Some(group) if !(1..=31).contains(&group)) {
+                            return Err(format!(
+                                "Invalid halt group on core {}, must be between 1 and 31",
+                                core.name
+                            ));
+                        }
+                    }
+                    CoreAccessOptions::Xtensa(_) => {
+                        if core.core_type != CoreType::Xtensa {
+                            return Err(format!(
+                                "Xtensa options don't match core type {:?} on core {}",
+                                core.core_type, core.name
+                            ));
+                        }
                     }
                 }
             }
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use probe_rs::{
-    architecture::arm::{ApAddress, DpAddress},
+    architecture::arm::{ApAddress, ApPort, DpAddress},
     Probe,
 };
 
@@ -24,19 +24,19 @@ fn main() -> Result<()> {
     // on an nRF5340 target.
 
     const APP_MEM: ApAddress = ApAddress {
-        ap: 0,
+        ap: ApPort::Index(0),
         dp: DpAddress::Default,
     };
     const NET_MEM: ApAddress = ApAddress {
-        ap: 1,
+        ap: ApPort::Index(1),
         dp: DpAddress::Default,
     };
     const APP_CTRL: ApAddress = ApAddress {
-        ap: 2,
+        ap: ApPort::Index(2),
         dp: DpAddress::Default,
     };
     const NET_CTRL: ApAddress = ApAddress {
-        ap: 3,
+        ap: ApPort::Index(3),
         dp: DpAddress::Default,
     };
 
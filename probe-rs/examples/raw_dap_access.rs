@@ -1,6 +1,6 @@
 use anyhow::Result;
 use probe_rs::{
-    architecture::arm::{sequences::DefaultArmSequence, ApAddress, DpAddress},
+    architecture::arm::{sequences::DefaultArmSequence, ApAddress, ApPort, DpAddress},
     Probe,
 };
 
@@ -25,7 +25,7 @@ fn main() -> Result<()> {
 
     let port = ApAddress {
         dp: DpAddress::Default,
-        ap: 1,
+        ap: ApPort::Index(1),
     };
 
     const RESET: u8 = 0;
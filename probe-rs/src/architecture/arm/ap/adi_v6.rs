@@ -0,0 +1,253 @@
+//! Discovery of the access ports of an ADIv6 debug port.
+//!
+//! A DPv3 debug port maps its access ports into its own address space. The access ports are
+//! found by walking the ROM tables starting at the root component, which is pointed to by the
+//! `BASEPTR0` and `BASEPTR1` registers of the debug port.
+
+use crate::architecture::arm::ArmError;
+
+/// Offset of the Component ID registers.
+const CIDR0: u64 = 0xFF0;
+/// Offset of the DEVARCH register.
+const DEVARCH: u64 = 0xFBC;
+/// Offset of the DEVID register.
+const DEVID: u64 = 0xFC8;
+
+/// The architect of ARM defined components, as found in DEVARCH.
+const ARCHITECT_ARM: u32 = 0x23B;
+/// The `ARCHID` of a class 0x9 ROM table.
+const ARCHID_ROM_TABLE: u32 = 0x0AF7;
+/// The `ARCHID` of a MEM-AP.
+const ARCHID_MEM_AP: u32 = 0x0A17;
+
+/// The maximum nesting depth of ROM tables, to protect against loops.
+const MAX_DEPTH: usize = 8;
+
+/// Find the base addresses of all access ports reachable from the component at `root`.
+///
+/// `read` reads a 32-bit word at an address in the address space of the debug port.
+pub(crate) fn discover_access_ports(
+    read: &mut dyn FnMut(u64) -> Result<u32, ArmError>,
+    root: u64,
+) -> Result<Vec<u64>, ArmError> {
+    let mut access_ports = Vec::new();
+
+    visit_component(read, root, 0, &mut access_ports)?;
+
+    Ok(access_ports)
+}
+
+fn visit_component(
+    read: &mut dyn FnMut(u64) -> Result<u32, ArmError>,
+    base: u64,
+    depth: usize,
+    access_ports: &mut Vec<u64>,
+) -> Result<(), ArmError> {
+    if depth > MAX_DEPTH {
+        tracing::warn!("ROM tables nested too deep at {:#x}, ignoring", base);
+        return Ok(());
+    }
+
+    let class = match component_class(read, base)? {
+        Some(class) => class,
+        None => {
+            tracing::debug!("No valid component at {:#x}", base);
+            return Ok(());
+        }
+    };
+
+    match class {
+        // Class 0x1 ROM table
+        0x1 => {
+            for index in 0..960 {
+                let entry = read(base + index * 4)?;
+
+                if entry == 0 {
+                    break;
+                }
+
+                if entry & 1 == 1 {
+                    visit_component(read, entry_address(base, entry), depth + 1, access_ports)?;
+                }
+            }
+        }
+        // CoreSight component, which can be a class 0x9 ROM table or an access port
+        0x9 => {
+            let devarch = read(base + DEVARCH)?;
+            let is_arm_architecture = devarch & (1 << 20) != 0 && devarch >> 21 == ARCHITECT_ARM;
+            let archid = devarch & 0xFFFF;
+
+            if is_arm_architecture && archid == ARCHID_ROM_TABLE {
+                let wide_entries = read(base + DEVID)? & 0xF == 1;
+                let (entry_size, entry_count) = if wide_entries { (8, 256) } else { (4, 512) };
+
+                for index in 0..entry_count {
+                    let address = base + index * entry_size;
+                    let mut entry = u64::from(read(address)?);
+                    if wide_entries {
+                        entry |= u64::from(read(address + 4)?) << 32;
+                    }
+
+                    match entry & 0b11 {
+                        // End of the table
+                        0b00 => break,
+                        // Entry is present
+                        0b11 => {
+                            let component = if wide_entries {
+                                base.wrapping_add(entry & !0xFFF)
+                            } else {
+                                entry_address(base, entry as u32)
+                            };
+                            visit_component(read, component, depth + 1, access_ports)?;
+                        }
+                        // Entry is not present, but the table continues
+                        _ => {}
+                    }
+                }
+            } else if is_arm_architecture && archid == ARCHID_MEM_AP {
+                tracing::debug!("Found access port at {:#x}", base);
+                access_ports.push(base);
+            } else {
+                tracing::debug!(
+                    "Ignoring component with DEVARCH {:#010x} at {:#x}",
+                    devarch,
+                    base
+                );
+            }
+        }
+        other => tracing::debug!("Ignoring component of class {:#x} at {:#x}", other, base),
+    }
+
+    Ok(())
+}
+
+/// Read the class of the component at `base`, if the Component ID registers are valid.
+fn component_class(
+    read: &mut dyn FnMut(u64) -> Result<u32, ArmError>,
+    base: u64,
+) -> Result<Option<u32>, ArmError> {
+    let mut cidr = [0; 4];
+    for (index, value) in cidr.iter_mut().enumerate() {
+        *value = read(base + CIDR0 + index as u64 * 4)? & 0xFF;
+    }
+
+    if cidr[0] != 0x0D || cidr[1] & 0x0F != 0x0 || cidr[2] != 0x05 || cidr[3] != 0xB1 {
+        return Ok(None);
+    }
+
+    Ok(Some(cidr[1] >> 4))
+}
+
+/// The address of the component referenced by a 32-bit entry of a class 0x1 or 0x9 ROM table.
+fn entry_address(base: u64, entry: u32) -> u64 {
+    // The offset is a signed value, relative to the base of the ROM table.
+    let offset = (entry & 0xFFFF_F000) as i32;
+    base.wrapping_add(offset as i64 as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn component(memory: &mut HashMap<u64, u32>, base: u64, class: u32) {
+        memory.insert(base + CIDR0, 0x0D);
+        memory.insert(base + CIDR0 + 4, class << 4);
+        memory.insert(base + CIDR0 + 8, 0x05);
+        memory.insert(base + CIDR0 + 12, 0xB1);
+    }
+
+    fn access_port(memory: &mut HashMap<u64, u32>, base: u64) {
+        component(memory, base, 0x9);
+        memory.insert(base + DEVARCH, 0x4770_0A17);
+    }
+
+    #[test]
+    fn discover_access_ports_in_nested_rom_tables() {
+        let mut memory = HashMap::new();
+
+        // Class 0x9 root ROM table, pointing to an access port and a nested class 0x1 ROM table
+        component(&mut memory, 0x0, 0x9);
+        memory.insert(DEVARCH, 0x4770_0AF7);
+        memory.insert(0x0, 0x1000 | 0b11);
+        memory.insert(0x4, 0x2000 | 0b10);
+        memory.insert(0x8, 0x3000 | 0b11);
+
+        access_port(&mut memory, 0x1000);
+
+        // Nested ROM table, pointing to two access ports
+        component(&mut memory, 0x3000, 0x1);
+        memory.insert(0x3000, 0x1000 | 1);
+        memory.insert(0x3004, 0xFFFF_F000 | 1);
+
+        access_port(&mut memory, 0x4000);
+        access_port(&mut memory, 0x2000);
+
+        let mut read = |address| Ok(memory.get(&address).copied().unwrap_or_default());
+
+        assert_eq!(
+            discover_access_ports(&mut read, 0x0).unwrap(),
+            vec![0x1000, 0x4000, 0x2000]
+        );
+    }
+
+    #[test]
+    fn negative_rom_table_offsets() {
+        let mut memory = HashMap::new();
+
+        // Class 0x9 ROM table with 32-bit entries, pointing below itself
+        component(&mut memory, 0x8000, 0x9);
+        memory.insert(0x8000 + DEVARCH, 0x4770_0AF7);
+        memory.insert(0x8000, 0xFFFF_F000 | 0b11);
+        memory.insert(0x8004, 0xFFFF_A000 | 0b11);
+
+        // Class 0x9 ROM table with 64-bit entries, pointing below itself
+        component(&mut memory, 0x2000, 0x9);
+        memory.insert(0x2000 + DEVARCH, 0x4770_0AF7);
+        memory.insert(0x2000 + DEVID, 1);
+        memory.insert(0x2000, 0xFFFF_F000 | 0b11);
+        memory.insert(0x2004, 0xFFFF_FFFF);
+
+        access_port(&mut memory, 0x7000);
+        access_port(&mut memory, 0x1000);
+
+        let mut read = |address| Ok(memory.get(&address).copied().unwrap_or_default());
+
+        assert_eq!(
+            discover_access_ports(&mut read, 0x8000).unwrap(),
+            vec![0x7000, 0x1000]
+        );
+    }
+
+    #[test]
+    fn only_mem_aps_are_access_ports() {
+        let mut memory = HashMap::new();
+
+        component(&mut memory, 0x0, 0x9);
+        memory.insert(DEVARCH, 0x4770_0AF7);
+        memory.insert(0x0, 0x1000 | 0b11);
+        memory.insert(0x4, 0x2000 | 0b11);
+        memory.insert(0x8, 0x3000 | 0b11);
+
+        access_port(&mut memory, 0x1000);
+
+        // A CTI, which has a non-zero value at the offset of the IDR register of an access port
+        component(&mut memory, 0x2000, 0x9);
+        memory.insert(0x2000 + DEVARCH, 0x4770_1A14);
+        memory.insert(0x2000 + 0xDFC, 0x0477_0004);
+
+        // A component without DEVARCH
+        component(&mut memory, 0x3000, 0x9);
+
+        let mut read = |address| Ok(memory.get(&address).copied().unwrap_or_default());
+
+        assert_eq!(discover_access_ports(&mut read, 0x0).unwrap(), vec![0x1000]);
+    }
+
+    #[test]
+    fn invalid_root_component() {
+        let mut read = |_| Ok(0);
+
+        assert!(discover_access_ports(&mut read, 0x0).unwrap().is_empty());
+    }
+}
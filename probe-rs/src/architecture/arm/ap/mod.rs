@@ -2,6 +2,7 @@
 
 #[macro_use]
 pub mod register_generation;
+pub(crate) mod adi_v6;
 pub(crate) mod generic_ap;
 pub(crate) mod memory_ap;
 
@@ -14,7 +15,7 @@ pub use memory_ap::{
 };
 
 use super::{
-    communication_interface::RegisterParseError, ApAddress, ArmError, DapAccess, DpAddress,
+    communication_interface::RegisterParseError, ApAddress, ApPort, ArmError, DapAccess, DpAddress,
     Register,
 };
 
@@ -215,13 +216,19 @@ where
 }
 
 /// Return a Vec of all valid access ports found that the target connected to the debug_probe.
+/// Only the access ports of ADIv5 debug ports, which are selected by their number, are found.
 /// Can fail silently under the hood testing an ap that doesnt exist and would require cleanup.
 pub(crate) fn valid_access_ports<AP>(debug_port: &mut AP, dp: DpAddress) -> Vec<GenericAp>
 where
     AP: ApAccess,
 {
     (0..=255)
-        .map(|ap| GenericAp::new(ApAddress { dp, ap }))
+        .map(|ap| {
+            GenericAp::new(ApAddress {
+                dp,
+                ap: ApPort::Index(ap),
+            })
+        })
         .take_while(|port| access_port_is_valid(debug_port, *port))
         .collect::<Vec<GenericAp>>()
 }
@@ -233,7 +240,12 @@ where
     P: Fn(IDR) -> bool,
 {
     (0..=255)
-        .map(|ap| GenericAp::new(ApAddress { dp, ap }))
+        .map(|ap| {
+            GenericAp::new(ApAddress {
+                dp,
+                ap: ApPort::Index(ap),
+            })
+        })
         .find(|ap| {
             if let Ok(idr) = debug_port.read_ap_register(*ap) {
                 f(idr)
@@ -1,15 +1,19 @@
 use super::{
     ap::{
-        valid_access_ports, AccessPort, ApAccess, ApClass, BaseaddrFormat, GenericAp, MemoryAp,
-        BASE, BASE2, CFG, CSW, IDR,
+        adi_v6, valid_access_ports, AccessPort, ApAccess, ApClass, BaseaddrFormat, GenericAp,
+        MemoryAp, BASE, BASE2, CFG, CSW, IDR,
+    },
+    dp::{
+        Abort, Ctrl, DebugPortError, DebugPortVersion, DpAccess, Select, Select1, SelectV3,
+        BASEPTR0, BASEPTR1, DPIDR,
     },
-    dp::{Abort, Ctrl, DebugPortVersion, DpAccess, Select, DPIDR},
     memory::{
         adi_v5_memory_interface::{ADIMemoryInterface, ArmProbe},
         Component,
     },
     sequences::{ArmDebugSequence, DefaultArmSequence},
-    ApAddress, ArmError, DapAccess, DpAddress, PortType, RawDapAccess, SwoAccess, SwoConfig,
+    ApAddress, ApPort, ArmError, DapAccess, DpAddress, PortType, RawDapAccess, SwoAccess,
+    SwoConfig,
 };
 use crate::{
     architecture::arm::ap::DataSize, DebugProbe, DebugProbeError, Error as ProbeRsError, Probe,
@@ -23,6 +27,9 @@ use std::{
     time::Duration,
 };
 
+/// Offset of the registers of an ADIv6 access port, relative to the ADIv5 register addresses.
+const ADI_V6_AP_REGISTER_OFFSET: u64 = 0xD00;
+
 /// An error in the communication with an access port or
 /// debug port.
 #[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
@@ -84,6 +91,19 @@ pub trait ArmProbeInterface: DapAccess + SwdSequence + SwoAccess + Send {
     /// if necessary. This will also  
     fn num_access_ports(&mut self, dp: DpAddress) -> Result<usize, ArmError>;
 
+    /// Returns the addresses of the access ports of the debug port.
+    ///
+    /// On an ADIv5 debug port, the access ports are numbered consecutively. On an ADIv6 debug port,
+    /// the access ports are found using the ROM table of the debug port, and identified by their base address.
+    fn access_ports(&mut self, dp: DpAddress) -> Result<Vec<ApAddress>, ArmError> {
+        Ok((0..self.num_access_ports(dp)?)
+            .map(|ap| ApAddress {
+                dp,
+                ap: ApPort::Index(ap as u8),
+            })
+            .collect())
+    }
+
     /// Reads the chip info from the romtable of given debug port.
     fn read_chip_info_from_rom_table(
         &mut self,
@@ -158,25 +178,30 @@ impl ArmDebugState for Initialized {}
 
 #[derive(Debug)]
 pub(crate) struct DpState {
-    pub _debug_port_version: DebugPortVersion,
+    pub debug_port_version: DebugPortVersion,
 
     pub current_dpbanksel: u8,
 
     pub current_apsel: u8,
     pub current_apbanksel: u8,
 
+    /// The address selected in SELECT and SELECT1 of a DPv3 debug port, if known.
+    pub current_address: Option<u64>,
+
     /// Information about the APs of the target.
-    /// APs are identified by a number, starting from zero.
+    /// On ADIv5 debug ports, APs are identified by a number, starting from zero.
+    /// On ADIv6 debug ports, APs are identified by their base address.
     pub ap_information: Vec<ApInformation>,
 }
 
 impl DpState {
     pub fn new() -> Self {
         Self {
-            _debug_port_version: DebugPortVersion::Unsupported(0xFF),
+            debug_port_version: DebugPortVersion::Unsupported(0xFF),
             current_dpbanksel: 0,
             current_apsel: 0,
             current_apbanksel: 0,
+            current_address: None,
             ap_information: Vec::new(),
         }
     }
@@ -191,7 +216,7 @@ pub enum ApInformation {
     MemoryAp(MemoryApInformation),
     /// Information about an AP with an unknown class.
     Other {
-        /// Zero-based port number of the access port. This is used in the debug port to select an AP.
+        /// The address of the access port. This is used in the debug port to select an AP.
         address: ApAddress,
         /// Content of the [`IDR`] register describing this AP.
         idr: IDR,
@@ -199,6 +224,14 @@ pub enum ApInformation {
 }
 
 impl ApInformation {
+    /// The address of the access port.
+    pub fn address(&self) -> ApAddress {
+        match self {
+            ApInformation::MemoryAp(information) => information.address,
+            ApInformation::Other { address, .. } => *address,
+        }
+    }
+
     /// Read information about an AP from its registers.
     ///
     /// This reads the IDR register of the AP, and parses
@@ -277,7 +310,7 @@ impl ApInformation {
 /// Useful for detecting supported memory access of a target.
 #[derive(Debug, Clone)]
 pub struct MemoryApInformation {
-    /// Zero-based port number of the access port. This is used in the debug port to select an AP.
+    /// The address of the access port. This is used in the debug port to select an AP.
     pub address: ApAddress,
 
     /// Some Memory APs only support 32 bit wide access to data, while others
@@ -353,6 +386,10 @@ impl ArmProbeInterface for ArmCommunicationInterface<Initialized> {
         ArmCommunicationInterface::num_access_ports(self, dp)
     }
 
+    fn access_ports(&mut self, dp: DpAddress) -> Result<Vec<ApAddress>, ArmError> {
+        ArmCommunicationInterface::access_ports(self, dp)
+    }
+
     fn close(self: Box<Self>) -> Probe {
         Probe::from_attached_probe(RawDapAccess::into_probe(self.probe))
     }
@@ -503,11 +540,24 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
             ctrl_reg.set_orun_detect(self.state.use_overrun_detect);
             self.write_dp_register(dp, ctrl_reg)?;
 
+            let dpidr: DPIDR = self.read_dp_register(dp)?;
+            let debug_port_version = DebugPortVersion::from(dpidr.version());
+            tracing::debug!("Debug port version: {}", debug_port_version);
+
+            // note(unwrap): we have inserted the state above, it must exist.
+            self.state.dps.get_mut(&dp).unwrap().debug_port_version = debug_port_version;
+
             /* determine the number and type of available APs */
             tracing::trace!("Searching valid APs");
 
             let ap_span = tracing::debug_span!("AP discovery").entered();
-            for ap in valid_access_ports(self, dp) {
+            let access_ports = if debug_port_version == DebugPortVersion::DPv3 {
+                self.discover_access_ports_v2(dp)?
+            } else {
+                valid_access_ports(self, dp)
+            };
+
+            for ap in access_ports {
                 let ap_state = ApInformation::read_from_target(self, ap)?;
                 tracing::debug!("AP {:x?}: {:?}", ap, ap_state);
 
@@ -536,25 +586,118 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
         let bank = dp_register_address >> 4;
         let addr = dp_register_address & 0xF;
 
-        if addr != 4 {
+        // On DPv3, address 0x0 is banked as well.
+        let is_banked =
+            addr == 4 || (addr == 0 && dp_state.debug_port_version == DebugPortVersion::DPv3);
+
+        if !is_banked {
             return Ok(());
         }
 
         if bank != dp_state.current_dpbanksel {
             dp_state.current_dpbanksel = bank;
 
-            let mut select = Select(0);
-
             tracing::debug!("Changing DP_BANK_SEL to {}", dp_state.current_dpbanksel);
 
+            self.write_select(dp)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the SELECT register of a debug port, using the cached selection.
+    fn write_select(&mut self, dp: DpAddress) -> Result<(), ArmError> {
+        let dp_state = self.select_dp(dp)?;
+
+        if dp_state.debug_port_version == DebugPortVersion::DPv3 {
+            let mut select = SelectV3(0);
+
+            select.set_addr((dp_state.current_address.unwrap_or_default() >> 4) as u32);
+            select.set_dp_bank_sel(dp_state.current_dpbanksel);
+
+            self.write_dp_register(dp, select)
+        } else {
+            let mut select = Select(0);
+
             select.set_ap_sel(dp_state.current_apsel);
             select.set_ap_bank_sel(dp_state.current_apbanksel);
             select.set_dp_bank_sel(dp_state.current_dpbanksel);
 
-            self.write_dp_register(dp, select)?;
+            self.write_dp_register(dp, select)
         }
+    }
 
-        Ok(())
+    /// Select an address in the address space of a DPv3 debug port, which is then
+    /// accessed with AP transactions.
+    fn select_dp_address(&mut self, dp: DpAddress, address: u64) -> Result<(), ArmError> {
+        let dp_state = self.select_dp(dp)?;
+
+        if dp_state.debug_port_version != DebugPortVersion::DPv3 {
+            return Err(DebugPortError::UnsupportedRegister {
+                register: "SELECT1",
+                version: dp_state.debug_port_version,
+            }
+            .into());
+        }
+
+        // Bits [3:2] of the address are part of the AP transaction.
+        let address = address & !0xF;
+        let previous_address = dp_state.current_address;
+
+        if previous_address == Some(address) {
+            return Ok(());
+        }
+
+        tracing::debug!("Changing selected address to {:#x}", address);
+
+        dp_state.current_address = Some(address);
+
+        if previous_address.map(|previous| previous >> 32) != Some(address >> 32) {
+            self.write_dp_register(dp, Select1((address >> 32) as u32))?;
+        }
+
+        self.write_select(dp)
+    }
+
+    /// Read a word from the address space of a DPv3 debug port.
+    fn read_dp_address_space(&mut self, dp: DpAddress, address: u64) -> Result<u32, ArmError> {
+        self.select_dp_address(dp, address)?;
+
+        let value = self
+            .probe
+            .raw_read_register(PortType::AccessPort, (address & 0xF) as u8)?;
+
+        Ok(value)
+    }
+
+    /// Find the access ports of a DPv3 debug port, using the ROM table pointed to by BASEPTR0 and BASEPTR1.
+    fn discover_access_ports_v2(&mut self, dp: DpAddress) -> Result<Vec<GenericAp>, ArmError> {
+        let baseptr0: BASEPTR0 = self.read_dp_register(dp)?;
+
+        if !baseptr0.valid() {
+            tracing::warn!("The debug port has no valid base pointer, no access ports were found.");
+            return Ok(Vec::new());
+        }
+
+        let baseptr1: BASEPTR1 = self.read_dp_register(dp)?;
+        let root = u64::from(baseptr1.0) << 32 | u64::from(baseptr0.ptr()) << 12;
+
+        tracing::debug!("Root component of the debug port at {:#x}", root);
+
+        let addresses = adi_v6::discover_access_ports(
+            &mut |address| self.read_dp_address_space(dp, address),
+            root,
+        )?;
+
+        Ok(addresses
+            .into_iter()
+            .map(|address| {
+                GenericAp::new(ApAddress {
+                    dp,
+                    ap: ApPort::Address(address),
+                })
+            })
+            .collect())
     }
 
     fn select_ap_and_ap_bank(
@@ -562,9 +705,25 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
         ap: ApAddress,
         ap_register_address: u8,
     ) -> Result<(), ArmError> {
+        let port = match ap.ap {
+            ApPort::Index(port) => port,
+            ApPort::Address(base) => {
+                // The registers of an ADIv6 access port are located at the same
+                // offsets as in ADIv5, plus 0xD00.
+                return self.select_dp_address(
+                    ap.dp,
+                    base + ADI_V6_AP_REGISTER_OFFSET + u64::from(ap_register_address),
+                );
+            }
+        };
+
         let dp_state = self.select_dp(ap.dp)?;
 
-        let port = ap.ap;
+        if dp_state.debug_port_version == DebugPortVersion::DPv3 {
+            // APs of a DPv3 debug port can only be selected by address.
+            return Err(ArmError::ApDoesNotExist(ap));
+        }
+
         let ap_bank = ap_register_address >> 4;
 
         let mut cache_changed = if dp_state.current_apsel != port {
@@ -580,19 +739,13 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
         }
 
         if cache_changed {
-            let mut select = Select(0);
-
             tracing::debug!(
                 "Changing AP to {}, AP_BANK_SEL to {}",
                 dp_state.current_apsel,
                 dp_state.current_apbanksel
             );
 
-            select.set_ap_sel(dp_state.current_apsel);
-            select.set_ap_bank_sel(dp_state.current_apbanksel);
-            select.set_dp_bank_sel(dp_state.current_dpbanksel);
-
-            self.write_dp_register(ap.dp, select)?;
+            self.write_select(ap.dp)?;
         }
 
         Ok(())
@@ -609,7 +762,10 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
 
         let state = self.select_dp(addr.dp)?;
 
-        Ok(state.ap_information.get(addr.ap as usize))
+        Ok(state
+            .ap_information
+            .iter()
+            .find(|information| information.address() == addr))
     }
 
     fn num_access_ports(&mut self, dp: DpAddress) -> Result<usize, ArmError> {
@@ -617,6 +773,16 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
 
         Ok(state.ap_information.len())
     }
+
+    fn access_ports(&mut self, dp: DpAddress) -> Result<Vec<ApAddress>, ArmError> {
+        let state = self.select_dp(dp)?;
+
+        Ok(state
+            .ap_information
+            .iter()
+            .map(|information| information.address())
+            .collect())
+    }
 }
 
 impl FlushableArmAccess for ArmCommunicationInterface<Initialized> {
@@ -749,7 +915,7 @@ impl ArmCommunicationInterface<Initialized> {
         dp: DpAddress,
     ) -> Result<Option<ArmChipInfo>, ArmError> {
         // faults on some chips need to be cleaned up.
-        let aps = valid_access_ports(self, dp);
+        let aps = self.access_ports(dp)?;
 
         // Check sticky error and cleanup if necessary
         let ctrl_reg: crate::architecture::arm::dp::Ctrl = self.read_dp_register(dp)?;
@@ -760,7 +926,7 @@ impl ArmCommunicationInterface<Initialized> {
             abort.set_stkerrclr(true);
             self.write_dp_register(dp, abort)?;
         }
-        for access_port in aps {
+        for access_port in aps.into_iter().map(GenericAp::new) {
             let idr: IDR = self.read_ap_register(access_port)?;
             tracing::debug!("{:#x?}", idr);
 
@@ -0,0 +1,254 @@
+//! Module for using the CTI.
+//!
+//! CTI = Cross Trigger Interface
+//!
+//! Every core has its own CTI, which connects the debug events of the core (trigger inputs) and its
+//! debug requests (trigger outputs) to the channels of the cross trigger matrix (CTM). All CTIs
+//! attached to the same CTM see the events on the channels, which is used to halt and restart a
+//! group of cores within a few cycles of each other.
+//!
+//! The trigger connections below are the ones of the Cortex-M7, Cortex-M33 and Cortex-M55, and
+//! of the Cortex-A cores: trigger input 0 signals that the core halted, trigger output 0 requests
+//! a halt (EDBGRQ) and trigger output 1 restarts the core (DBGRESTART).
+
+use crate::architecture::arm::{memory::adi_v5_memory_interface::ArmProbe, ArmError};
+
+use std::time::{Duration, Instant};
+
+const REGISTER_OFFSET_CTICONTROL: u64 = 0x000;
+const REGISTER_OFFSET_CTIINTACK: u64 = 0x010;
+const REGISTER_OFFSET_CTIAPPPULSE: u64 = 0x01C;
+const REGISTER_OFFSET_CTIINEN: u64 = 0x020;
+const REGISTER_OFFSET_CTIOUTEN: u64 = 0x0A0;
+const REGISTER_OFFSET_CTITRIGOUTSTATUS: u64 = 0x134;
+const REGISTER_OFFSET_CTIGATE: u64 = 0x140;
+const REGISTER_OFFSET_ACCESS: u64 = 0xFB0;
+
+/// Trigger input signalling that the core entered debug state.
+const TRIGGER_IN_HALTED: u64 = 0;
+/// Trigger output requesting the core to halt.
+const TRIGGER_OUT_HALT: u64 = 0;
+/// Trigger output requesting the core to leave debug state.
+const TRIGGER_OUT_RESTART: u64 = 1;
+
+const ACK_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The CTM channels used by a halt group.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CtiChannels {
+    /// The channel broadcasting halt events.
+    pub halt: u8,
+    /// The channel broadcasting restart requests.
+    pub restart: u8,
+}
+
+/// An interface to control the CTI (Cross Trigger Interface) of a Cortex-M or Cortex-A core.
+pub struct Cti<'a> {
+    memory: &'a mut dyn ArmProbe,
+    base_address: u64,
+}
+
+impl<'a> Cti<'a> {
+    /// Create a new CTI interface from a memory interface of the access port of the CTI and its
+    /// base address.
+    pub fn new(memory: &'a mut dyn ArmProbe, base_address: u64) -> Self {
+        Cti {
+            memory,
+            base_address,
+        }
+    }
+
+    fn write_reg(&mut self, offset: u64, value: u32) -> Result<(), ArmError> {
+        self.memory.write_word_32(self.base_address + offset, value)
+    }
+
+    fn read_reg(&mut self, offset: u64) -> Result<u32, ArmError> {
+        self.memory.read_word_32(self.base_address + offset)
+    }
+
+    fn modify_reg(&mut self, offset: u64, f: impl FnOnce(u32) -> u32) -> Result<(), ArmError> {
+        let value = self.read_reg(offset)?;
+        self.write_reg(offset, f(value))
+    }
+
+    /// Add the core of this CTI to the halt group using the given channels.
+    ///
+    /// Once all cores of a group are added, a halt of any of them is broadcast on the halt
+    /// channel and halts the others, and a pulse on the restart channel restarts all of them.
+    ///
+    /// Other channels mapped to the triggers, e.g. the ones a Cortex-A core uses to halt and
+    /// restart itself, are kept.
+    pub fn join_halt_group(&mut self, channels: CtiChannels) -> Result<(), ArmError> {
+        let halt = 1 << channels.halt;
+        let restart = 1 << channels.restart;
+
+        self.write_reg(REGISTER_OFFSET_ACCESS, 0xC5AC_CE55)?;
+        self.write_reg(REGISTER_OFFSET_CTICONTROL, 1)?;
+
+        self.modify_reg(REGISTER_OFFSET_CTIINEN + 4 * TRIGGER_IN_HALTED, |value| {
+            value | halt
+        })?;
+        self.modify_reg(REGISTER_OFFSET_CTIOUTEN + 4 * TRIGGER_OUT_HALT, |value| {
+            value | halt
+        })?;
+        self.modify_reg(
+            REGISTER_OFFSET_CTIOUTEN + 4 * TRIGGER_OUT_RESTART,
+            |value| value | restart,
+        )?;
+
+        // Pass the group channels to and from the CTM.
+        self.modify_reg(REGISTER_OFFSET_CTIGATE, |value| value | halt | restart)
+    }
+
+    /// Remove the core of this CTI from its halt group.
+    pub fn leave_halt_group(&mut self, channels: CtiChannels) -> Result<(), ArmError> {
+        let halt = 1 << channels.halt;
+        let restart = 1 << channels.restart;
+
+        self.modify_reg(REGISTER_OFFSET_CTIINEN + 4 * TRIGGER_IN_HALTED, |value| {
+            value & !halt
+        })?;
+        self.modify_reg(REGISTER_OFFSET_CTIOUTEN + 4 * TRIGGER_OUT_HALT, |value| {
+            value & !halt
+        })?;
+        self.modify_reg(
+            REGISTER_OFFSET_CTIOUTEN + 4 * TRIGGER_OUT_RESTART,
+            |value| value & !restart,
+        )?;
+
+        self.modify_reg(REGISTER_OFFSET_CTIGATE, |value| value & !(halt | restart))?;
+
+        self.acknowledge_halt()
+    }
+
+    /// Deassert the halt request of the core.
+    ///
+    /// The halt request stays asserted until it is acknowledged, and would halt the core again
+    /// right after it is resumed.
+    pub fn acknowledge_halt(&mut self) -> Result<(), ArmError> {
+        self.write_reg(REGISTER_OFFSET_CTIINTACK, 1 << TRIGGER_OUT_HALT)?;
+
+        let start = Instant::now();
+        while self.read_reg(REGISTER_OFFSET_CTITRIGOUTSTATUS)? & (1 << TRIGGER_OUT_HALT) != 0 {
+            if start.elapsed() > ACK_TIMEOUT {
+                return Err(ArmError::Timeout);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pulse the given channel, which is seen by all CTIs connected to the CTM.
+    pub fn pulse_channel(&mut self, channel: u8) -> Result<(), ArmError> {
+        self.write_reg(REGISTER_OFFSET_CTIAPPPULSE, 1 << channel)
+    }
+
+    /// Acknowledge the restart request after the core left debug state.
+    pub fn acknowledge_restart(&mut self) -> Result<(), ArmError> {
+        self.write_reg(REGISTER_OFFSET_CTIINTACK, 1 << TRIGGER_OUT_RESTART)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::architecture::arm::{
+        ap::memory_ap::mock::MockMemoryAp, memory::adi_v5_memory_interface::ADIMemoryInterface,
+        ApAddress, ApPort, DpAddress, MemoryApInformation,
+    };
+
+    const CTI_BASE: u64 = 0x1000;
+
+    fn memory_interface(mock: &mut MockMemoryAp) -> ADIMemoryInterface<'_, MockMemoryAp> {
+        let ap_information = MemoryApInformation {
+            address: ApAddress {
+                dp: DpAddress::Default,
+                ap: ApPort::Index(0),
+            },
+            supports_only_32bit_data_size: false,
+            supports_hnonsec: false,
+            debug_base_address: 0xf000_0000,
+            has_large_address_extension: false,
+            has_large_data_extension: false,
+            device_enabled: true,
+        };
+
+        ADIMemoryInterface::new(mock, ap_information).unwrap()
+    }
+
+    fn write_register(mock: &mut MockMemoryAp, offset: u64, value: u32) {
+        let address = (CTI_BASE + offset) as usize;
+        mock.memory[address..address + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn read_register(mock: &MockMemoryAp, offset: u64) -> u32 {
+        let address = (CTI_BASE + offset) as usize;
+        u32::from_le_bytes(mock.memory[address..address + 4].try_into().unwrap())
+    }
+
+    /// A CTI with channels 0 and 1 already mapped, as a Cortex-A core uses them.
+    fn mock_cti() -> MockMemoryAp {
+        let mut mock = MockMemoryAp::with_pattern();
+        write_register(&mut mock, REGISTER_OFFSET_CTICONTROL, 0);
+        write_register(&mut mock, REGISTER_OFFSET_CTIINEN, 0b01);
+        write_register(&mut mock, REGISTER_OFFSET_CTIOUTEN, 0b01);
+        write_register(&mut mock, REGISTER_OFFSET_CTIOUTEN + 4, 0b10);
+        write_register(&mut mock, REGISTER_OFFSET_CTITRIGOUTSTATUS, 0);
+        write_register(&mut mock, REGISTER_OFFSET_CTIGATE, 0b11);
+        mock
+    }
+
+    const CHANNELS: CtiChannels = CtiChannels {
+        halt: 2,
+        restart: 3,
+    };
+
+    #[test]
+    fn join_halt_group() {
+        let mut mock = mock_cti();
+
+        Cti::new(&mut memory_interface(&mut mock), CTI_BASE)
+            .join_halt_group(CHANNELS)
+            .unwrap();
+
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_ACCESS), 0xC5AC_CE55);
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTICONTROL), 1);
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIINEN), 0b0101);
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIOUTEN), 0b0101);
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIOUTEN + 4), 0b1010);
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIGATE), 0b1111);
+    }
+
+    #[test]
+    fn leave_halt_group_restores_registers() {
+        let mut mock = mock_cti();
+
+        let mut memory = memory_interface(&mut mock);
+        let mut cti = Cti::new(&mut memory, CTI_BASE);
+        cti.join_halt_group(CHANNELS).unwrap();
+        cti.leave_halt_group(CHANNELS).unwrap();
+
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIINEN), 0b01);
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIOUTEN), 0b01);
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIOUTEN + 4), 0b10);
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIGATE), 0b11);
+        // The pending halt request is acknowledged.
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIINTACK), 1);
+    }
+
+    #[test]
+    fn pulse_and_acknowledge_restart() {
+        let mut mock = mock_cti();
+
+        let mut memory = memory_interface(&mut mock);
+        let mut cti = Cti::new(&mut memory, CTI_BASE);
+        cti.pulse_channel(CHANNELS.restart).unwrap();
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIAPPPULSE), 0b1000);
+
+        Cti::new(&mut memory_interface(&mut mock), CTI_BASE)
+            .acknowledge_restart()
+            .unwrap();
+        assert_eq!(read_register(&mock, REGISTER_OFFSET_CTIINTACK), 0b10);
+    }
+}
@@ -93,6 +93,137 @@ impl<'a> Dwt<'a> {
         ctrl.set_exctrcena(false);
         ctrl.store(self.component, self.interface)
     }
+
+    /// Enable the cycle counter and the profiling counters, see [`DwtCounters`].
+    pub fn enable_counters(&mut self) -> Result<(), ArmError> {
+        let mut ctrl = Ctrl::load(self.component, self.interface)?;
+        ctrl.set_cyccntena(true);
+        ctrl.set_cpievtena(true);
+        ctrl.set_excevtena(true);
+        ctrl.set_sleepevtena(true);
+        ctrl.set_lsuevtena(true);
+        ctrl.set_foldevtena(true);
+        ctrl.store(self.component, self.interface)
+    }
+
+    /// Disable the profiling counters. The cycle counter is left running, as it is also used
+    /// for the synchronization packets and PC sampling.
+    pub fn disable_counters(&mut self) -> Result<(), ArmError> {
+        let mut ctrl = Ctrl::load(self.component, self.interface)?;
+        ctrl.set_cpievtena(false);
+        ctrl.set_excevtena(false);
+        ctrl.set_sleepevtena(false);
+        ctrl.set_lsuevtena(false);
+        ctrl.set_foldevtena(false);
+        ctrl.store(self.component, self.interface)
+    }
+
+    /// Read the current values of the cycle counter and the profiling counters.
+    pub fn read_counters(&mut self) -> Result<DwtCounters, ArmError> {
+        Ok(DwtCounters {
+            cycles: Cyccnt::load(self.component, self.interface)?.into(),
+            cpi: u32::from(Cpicnt::load(self.component, self.interface)?) as u8,
+            exception: u32::from(Exccnt::load(self.component, self.interface)?) as u8,
+            sleep: u32::from(Sleepcnt::load(self.component, self.interface)?) as u8,
+            lsu: u32::from(Lsucnt::load(self.component, self.interface)?) as u8,
+            fold: u32::from(Foldcnt::load(self.component, self.interface)?) as u8,
+        })
+    }
+
+    /// Reset the cycle counter and the profiling counters to zero.
+    pub fn reset_counters(&mut self) -> Result<(), ArmError> {
+        Cyccnt(0).store(self.component, self.interface)?;
+        Cpicnt(0).store(self.component, self.interface)?;
+        Exccnt(0).store(self.component, self.interface)?;
+        Sleepcnt(0).store(self.component, self.interface)?;
+        Lsucnt(0).store(self.component, self.interface)?;
+        Foldcnt(0).store(self.component, self.interface)
+    }
+
+    /// Enable periodic PC sampling, emitting a PC sample packet about every `interval` cycles.
+    ///
+    /// The DWT only supports intervals which are a multiple of 64 cycles up to 1024 cycles, or
+    /// a multiple of 1024 cycles up to 16384 cycles. The interval is rounded to the nearest
+    /// supported one, which is returned.
+    pub fn enable_pc_sampling(&mut self, interval: u32) -> Result<u32, ArmError> {
+        let (cyctap, tap) = if interval <= 16 * 64 {
+            (false, 64)
+        } else {
+            (true, 1024)
+        };
+        let postpreset = ((interval + tap / 2) / tap).clamp(1, 16) - 1;
+
+        // POSTPRESET and CYCTAP must not be changed while the cycle counter is running.
+        let mut ctrl = Ctrl::load(self.component, self.interface)?;
+        ctrl.set_cyccntena(false);
+        ctrl.set_pcsamplena(false);
+        ctrl.store(self.component, self.interface)?;
+
+        ctrl.set_cyctap(cyctap);
+        ctrl.set_postpreset(postpreset);
+        ctrl.set_postinit(postpreset as u8);
+        ctrl.store(self.component, self.interface)?;
+
+        // PC sampling is clocked by the cycle counter.
+        ctrl.set_cyccntena(true);
+        ctrl.set_pcsamplena(true);
+        ctrl.store(self.component, self.interface)?;
+
+        Ok((postpreset + 1) * tap)
+    }
+
+    /// Disable periodic PC sampling.
+    pub fn disable_pc_sampling(&mut self) -> Result<(), ArmError> {
+        let mut ctrl = Ctrl::load(self.component, self.interface)?;
+        ctrl.set_pcsamplena(false);
+        ctrl.store(self.component, self.interface)
+    }
+}
+
+/// The values of the DWT cycle counter and profiling counters.
+///
+/// Only the cycle counter is 32 bits wide. The profiling counters are 8 bits wide and wrap
+/// around every 256 cycles, so differences of them are only exact if less than 256 cycles
+/// were counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DwtCounters {
+    /// CYCCNT: The number of processor clock cycles.
+    pub cycles: u32,
+    /// CPICNT: The additional cycles taken by multi-cycle instructions and instruction fetch
+    /// stalls.
+    pub cpi: u8,
+    /// EXCCNT: The cycles spent on exception entry and exit.
+    pub exception: u8,
+    /// SLEEPCNT: The cycles spent sleeping.
+    pub sleep: u8,
+    /// LSUCNT: The additional cycles taken by load and store instructions.
+    pub lsu: u8,
+    /// FOLDCNT: The number of folded instructions, which took no cycles.
+    pub fold: u8,
+}
+
+impl DwtCounters {
+    /// The counts between `earlier` and `self`, taking a single wrap around of every counter
+    /// into account.
+    pub fn since(&self, earlier: &DwtCounters) -> DwtCounters {
+        DwtCounters {
+            cycles: self.cycles.wrapping_sub(earlier.cycles),
+            cpi: self.cpi.wrapping_sub(earlier.cpi),
+            exception: self.exception.wrapping_sub(earlier.exception),
+            sleep: self.sleep.wrapping_sub(earlier.sleep),
+            lsu: self.lsu.wrapping_sub(earlier.lsu),
+            fold: self.fold.wrapping_sub(earlier.fold),
+        }
+    }
+
+    /// Whether the 8 bit profiling counters of a difference returned by [`DwtCounters::since`]
+    /// may have wrapped around more than once, i.e. whether 256 or more cycles were counted.
+    ///
+    /// The profiling counters are incremented at most once per cycle, so they are exact as
+    /// long as less than 256 cycles were counted.
+    pub fn profiling_counters_may_have_wrapped(&self) -> bool {
+        self.cycles > u8::MAX as u32
+    }
 }
 
 bitfield! {
@@ -210,6 +341,75 @@ impl DebugRegister for Exccnt {
     const NAME: &'static str = "DWT/EXCCNT";
 }
 
+bitfield! {
+    #[derive(Clone, Default)]
+    pub struct Sleepcnt(u32);
+    impl Debug;
+}
+
+impl From<u32> for Sleepcnt {
+    fn from(raw: u32) -> Self {
+        Sleepcnt(raw)
+    }
+}
+
+impl From<Sleepcnt> for u32 {
+    fn from(raw: Sleepcnt) -> Self {
+        raw.0
+    }
+}
+
+impl DebugRegister for Sleepcnt {
+    const ADDRESS: u32 = 0x10;
+    const NAME: &'static str = "DWT/SLEEPCNT";
+}
+
+bitfield! {
+    #[derive(Clone, Default)]
+    pub struct Lsucnt(u32);
+    impl Debug;
+}
+
+impl From<u32> for Lsucnt {
+    fn from(raw: u32) -> Self {
+        Lsucnt(raw)
+    }
+}
+
+impl From<Lsucnt> for u32 {
+    fn from(raw: Lsucnt) -> Self {
+        raw.0
+    }
+}
+
+impl DebugRegister for Lsucnt {
+    const ADDRESS: u32 = 0x14;
+    const NAME: &'static str = "DWT/LSUCNT";
+}
+
+bitfield! {
+    #[derive(Clone, Default)]
+    pub struct Foldcnt(u32);
+    impl Debug;
+}
+
+impl From<u32> for Foldcnt {
+    fn from(raw: u32) -> Self {
+        Foldcnt(raw)
+    }
+}
+
+impl From<Foldcnt> for u32 {
+    fn from(raw: Foldcnt) -> Self {
+        raw.0
+    }
+}
+
+impl DebugRegister for Foldcnt {
+    const ADDRESS: u32 = 0x18;
+    const NAME: &'static str = "DWT/FOLDCNT";
+}
+
 bitfield! {
     #[derive(Clone, Default)]
     pub struct Comp(u32);
@@ -292,3 +492,85 @@ impl DebugRegister for Function {
     const ADDRESS: u32 = 0x28;
     const NAME: &'static str = "DWT/FUNCTION";
 }
+
+#[cfg(test)]
+mod test {
+    use super::DwtCounters;
+
+    #[test]
+    fn counters_since() {
+        let earlier = DwtCounters {
+            cycles: 1000,
+            cpi: 10,
+            exception: 20,
+            sleep: 30,
+            lsu: 40,
+            fold: 50,
+        };
+        let later = DwtCounters {
+            cycles: 1500,
+            cpi: 11,
+            exception: 22,
+            sleep: 33,
+            lsu: 44,
+            fold: 55,
+        };
+
+        assert_eq!(
+            later.since(&earlier),
+            DwtCounters {
+                cycles: 500,
+                cpi: 1,
+                exception: 2,
+                sleep: 3,
+                lsu: 4,
+                fold: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn counters_since_wrap_around() {
+        let earlier = DwtCounters {
+            cycles: u32::MAX - 99,
+            cpi: 250,
+            exception: 255,
+            sleep: 200,
+            lsu: 128,
+            fold: 1,
+        };
+        let later = DwtCounters {
+            cycles: 400,
+            cpi: 4,
+            exception: 0,
+            sleep: 100,
+            lsu: 127,
+            fold: 0,
+        };
+
+        assert_eq!(
+            later.since(&earlier),
+            DwtCounters {
+                cycles: 500,
+                cpi: 10,
+                exception: 1,
+                sleep: 156,
+                lsu: 255,
+                fold: 255,
+            }
+        );
+    }
+
+    #[test]
+    fn profiling_counters_may_have_wrapped() {
+        let counters = |cycles| DwtCounters {
+            cycles,
+            ..Default::default()
+        };
+
+        assert!(!counters(0).profiling_counters_may_have_wrapped());
+        assert!(!counters(255).profiling_counters_may_have_wrapped());
+        assert!(counters(256).profiling_counters_may_have_wrapped());
+        assert!(counters(u32::MAX).profiling_counters_may_have_wrapped());
+    }
+}
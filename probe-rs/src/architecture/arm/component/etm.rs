@@ -0,0 +1,203 @@
+//! Module for using the ETM.
+//!
+//! ETM = Embedded Trace Macrocell
+//!
+//! The ETM generates a compressed stream of the instructions executed by the core. The ETMv3
+//! found on Cortex-M3 and Cortex-M4 and the ETMv4 found on Cortex-M7 and Cortex-M33 are supported.
+//!
+//! The ETM is configured to emit an address packet for every taken branch (branch output on ETMv3,
+//! branch broadcasting on ETMv4), so the trace can be followed without walking the instructions of
+//! the traced program.
+
+use super::super::memory::romtable::CoresightComponent;
+use super::ComponentError;
+use crate::architecture::arm::{ArmError, ArmProbeInterface};
+
+use std::time::{Duration, Instant};
+
+const REGISTER_OFFSET_ACCESS: u32 = 0xFB0;
+const REGISTER_OFFSET_OSLAR: u32 = 0x300;
+/// ETMIDR on ETMv3, TRCIDR1 on ETMv4. Both hold the major architecture version in bits 11:8.
+const REGISTER_OFFSET_IDR: u32 = 0x1E4;
+
+// ETMv3 registers
+const ETMV3_CR: u32 = 0x000;
+const ETMV3_TRIGGER: u32 = 0x008;
+const ETMV3_SR: u32 = 0x010;
+const ETMV3_TEEVR: u32 = 0x020;
+const ETMV3_TECR1: u32 = 0x024;
+const ETMV3_FFLR: u32 = 0x02C;
+const ETMV3_SYNCFR: u32 = 0x1E0;
+const ETMV3_TRACEIDR: u32 = 0x200;
+
+// ETMv4 registers
+const TRCPRGCTLR: u32 = 0x004;
+const TRCSTATR: u32 = 0x00C;
+const TRCCONFIGR: u32 = 0x010;
+const TRCEVENTCTL0R: u32 = 0x020;
+const TRCEVENTCTL1R: u32 = 0x024;
+const TRCSTALLCTLR: u32 = 0x02C;
+const TRCTSCTLR: u32 = 0x030;
+const TRCSYNCPR: u32 = 0x034;
+const TRCCCCTLR: u32 = 0x038;
+const TRCBBCTLR: u32 = 0x03C;
+const TRCTRACEIDR: u32 = 0x040;
+const TRCVICTLR: u32 = 0x080;
+const TRCVIIECTLR: u32 = 0x084;
+const TRCVISSCTLR: u32 = 0x088;
+
+/// ETMv3 event encoding for "always true" (function A, resource hard-wired to true).
+const ETMV3_EVENT_ALWAYS: u32 = 0x6F;
+/// ETMv3 event encoding for "never true" (function NOT A, resource hard-wired to true).
+const ETMV3_EVENT_NEVER: u32 = 0x406F;
+
+const PROGRAMMING_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The architecture version of an ETM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EtmVersion {
+    /// ETMv3, found on Cortex-M3 and Cortex-M4.
+    V3,
+    /// ETMv4, found on Cortex-M7, Cortex-M23 and Cortex-M33.
+    V4,
+}
+
+/// An interface to control the ETM (Embedded Trace Macrocell) of a core.
+pub struct Etm<'a> {
+    component: &'a CoresightComponent,
+    interface: &'a mut dyn ArmProbeInterface,
+}
+
+impl<'a> Etm<'a> {
+    /// Create a new ETM interface from a probe and a ROM table component.
+    pub fn new(
+        interface: &'a mut dyn ArmProbeInterface,
+        component: &'a CoresightComponent,
+    ) -> Self {
+        Etm {
+            component,
+            interface,
+        }
+    }
+
+    /// Unlock the ETM registers for writing.
+    pub fn unlock(&mut self) -> Result<(), ArmError> {
+        self.component
+            .write_reg(self.interface, REGISTER_OFFSET_ACCESS, 0xC5AC_CE55)?;
+        self.component
+            .write_reg(self.interface, REGISTER_OFFSET_OSLAR, 0)
+    }
+
+    /// Determine the architecture version of the ETM.
+    pub fn version(&mut self) -> Result<EtmVersion, ArmError> {
+        let idr = self
+            .component
+            .read_reg(self.interface, REGISTER_OFFSET_IDR)?;
+
+        match ((idr >> 8) & 0xF) as u8 {
+            // ETMv3 reports its major version as "version - 1".
+            2 => Ok(EtmVersion::V3),
+            4 => Ok(EtmVersion::V4),
+            other => Err(ComponentError::UnsupportedEtmVersion(other).into()),
+        }
+    }
+
+    /// Configure the ETM to trace all executed instructions, and start tracing.
+    ///
+    /// The trace is tagged with the given trace bus ID, which is used to demultiplex the
+    /// formatted trace stream.
+    pub fn enable(&mut self, trace_id: u8) -> Result<EtmVersion, ArmError> {
+        self.unlock()?;
+
+        let version = self.version()?;
+        match version {
+            EtmVersion::V3 => self.enable_v3(trace_id)?,
+            EtmVersion::V4 => self.enable_v4(trace_id)?,
+        }
+
+        Ok(version)
+    }
+
+    /// Stop tracing.
+    pub fn disable(&mut self) -> Result<(), ArmError> {
+        match self.version()? {
+            EtmVersion::V3 => {
+                // Enter programming mode, which stops the trace, and power the ETM down.
+                self.component
+                    .write_reg(self.interface, ETMV3_CR, (1 << 10) | 1)
+            }
+            EtmVersion::V4 => self.component.write_reg(self.interface, TRCPRGCTLR, 0),
+        }
+    }
+
+    fn enable_v3(&mut self, trace_id: u8) -> Result<(), ArmError> {
+        // Power up the ETM and enter programming mode.
+        self.component
+            .write_reg(self.interface, ETMV3_CR, 1 << 10)?;
+        self.wait_for(ETMV3_SR, 1 << 1, true)?;
+
+        self.component
+            .write_reg(self.interface, ETMV3_TRIGGER, ETMV3_EVENT_NEVER)?;
+        self.component
+            .write_reg(self.interface, ETMV3_TEEVR, ETMV3_EVENT_ALWAYS)?;
+        // Exclude mode without any address range selected: trace everything.
+        self.component
+            .write_reg(self.interface, ETMV3_TECR1, 1 << 24)?;
+        self.component.write_reg(self.interface, ETMV3_FFLR, 0)?;
+        self.component
+            .write_reg(self.interface, ETMV3_SYNCFR, 0x400)?;
+        self.component
+            .write_reg(self.interface, ETMV3_TRACEIDR, trace_id as u32)?;
+
+        let mut cr = 0;
+        cr |= 1 << 8; // Branch output: emit an address packet for every taken branch
+        cr |= 1 << 11; // ETM port enable
+        self.component.write_reg(self.interface, ETMV3_CR, cr)?;
+        self.wait_for(ETMV3_SR, 1 << 1, false)
+    }
+
+    fn enable_v4(&mut self, trace_id: u8) -> Result<(), ArmError> {
+        self.component.write_reg(self.interface, TRCPRGCTLR, 0)?;
+        self.wait_for(TRCSTATR, 1 << 0, true)?;
+
+        // Branch broadcasting, no cycle counting, timestamps or context IDs.
+        self.component
+            .write_reg(self.interface, TRCCONFIGR, 1 << 3)?;
+        // Exclude mode without any address range selected: broadcast all branches.
+        self.component.write_reg(self.interface, TRCBBCTLR, 0)?;
+        self.component.write_reg(self.interface, TRCEVENTCTL0R, 0)?;
+        self.component.write_reg(self.interface, TRCEVENTCTL1R, 0)?;
+        self.component.write_reg(self.interface, TRCSTALLCTLR, 0)?;
+        self.component.write_reg(self.interface, TRCTSCTLR, 0)?;
+        self.component.write_reg(self.interface, TRCCCCTLR, 0)?;
+        // Trace synchronization every 2^12 bytes.
+        self.component.write_reg(self.interface, TRCSYNCPR, 0xC)?;
+        self.component
+            .write_reg(self.interface, TRCTRACEIDR, trace_id as u32)?;
+
+        // ViewInst: enabled by the always-true resource, start/stop logic in the started state.
+        self.component
+            .write_reg(self.interface, TRCVICTLR, (1 << 9) | 0x01)?;
+        self.component.write_reg(self.interface, TRCVIIECTLR, 0)?;
+        self.component.write_reg(self.interface, TRCVISSCTLR, 0)?;
+
+        self.component.write_reg(self.interface, TRCPRGCTLR, 1)?;
+        self.wait_for(TRCSTATR, 1 << 0, false)
+    }
+
+    /// Wait until the bits in `mask` of the register at `offset` are all set (or all cleared).
+    fn wait_for(&mut self, offset: u32, mask: u32, set: bool) -> Result<(), ArmError> {
+        let start = Instant::now();
+
+        loop {
+            let value = self.component.read_reg(self.interface, offset)?;
+            if (value & mask == mask) == set {
+                return Ok(());
+            }
+
+            if start.elapsed() > PROGRAMMING_TIMEOUT {
+                return Err(ArmError::Timeout);
+            }
+        }
+    }
+}
@@ -1,7 +1,10 @@
 //! Types and functions for interacting with CoreSight Components
 
+mod cti;
 mod dwt;
+mod etm;
 mod itm;
+mod mtb;
 mod scs;
 mod swo;
 mod tmc;
@@ -12,13 +15,19 @@ use super::ap::{GenericAp, MemoryAp};
 use super::memory::romtable::{CoresightComponent, PeripheralType, RomTableError};
 use super::memory::Component;
 use super::ArmError;
-use super::{ApAddress, ApInformation, DpAddress, MemoryApInformation};
+use super::{ApInformation, DpAddress, MemoryApInformation};
 use crate::architecture::arm::core::armv6m::Demcr;
+use crate::architecture::arm::trace::{
+    EtmDecoder, InstructionTrace, InstructionTraceSource, TraceEvent, ETM_TRACE_ID,
+};
 use crate::architecture::arm::{ArmProbeInterface, SwoConfig, SwoMode};
 use crate::{Core, Error, MemoryInterface, MemoryMappedRegister};
 
 pub use self::itm::Itm;
-pub use dwt::Dwt;
+pub use cti::{Cti, CtiChannels};
+pub use dwt::{Dwt, DwtCounters};
+pub use etm::{Etm, EtmVersion};
+pub use mtb::{Mtb, MtbEntry};
 pub use scs::Scs;
 pub use swo::Swo;
 pub use tmc::TraceMemoryController;
@@ -47,6 +56,10 @@ pub enum ComponentError {
     /// Nordic chips do not support setting all TPIU clocks. Try choosing another clock speed.
     #[error("Nordic does not support TPIU CLK value of {0}")]
     NordicUnsupportedTPUICLKValue(u32),
+
+    /// The ETM implements an architecture version which is not supported.
+    #[error("Unsupported ETM architecture version {0:#x}")]
+    UnsupportedEtmVersion(u8),
 }
 
 /// A trait to be implemented on debug register types for debug component interfaces.
@@ -109,9 +122,9 @@ pub fn get_arm_components(
 ) -> Result<Vec<CoresightComponent>, ArmError> {
     let mut components = Vec::new();
 
-    for ap_index in 0..(interface.num_access_ports(dp)? as u8) {
+    for ap_address in interface.access_ports(dp)? {
         let ap_information = interface
-            .ap_information(GenericAp::new(ApAddress { dp, ap: ap_index }))?
+            .ap_information(GenericAp::new(ap_address))?
             .clone();
 
         let component = match ap_information {
@@ -143,7 +156,7 @@ pub fn get_arm_components(
                 components.push(component);
             }
             Err(e) => {
-                tracing::info!("Not counting AP {} because of: {}", ap_index, e);
+                tracing::info!("Not counting AP {} because of: {}", ap_address.ap, e);
             }
         }
     }
@@ -218,7 +231,15 @@ pub(crate) fn setup_tracing(
     itm.unlock()?;
     itm.tx_enable()?;
 
-    // Configure the trace destination.
+    configure_trace_sink(interface, components, sink)
+}
+
+/// Configures the trace destination.
+fn configure_trace_sink(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+    sink: &TraceSink,
+) -> Result<(), Error> {
     match sink {
         TraceSink::Tpiu(config) => {
             configure_tpiu(
@@ -289,6 +310,39 @@ pub(crate) fn setup_tracing(
 pub(crate) fn read_trace_memory(
     interface: &mut dyn ArmProbeInterface,
     components: &[CoresightComponent],
+) -> Result<Vec<u8>, ArmError> {
+    let etf_trace = read_formatted_trace_memory(interface, components)?;
+
+    // The TMC formats data into frames, as it contains trace data from multiple data sources. We
+    // need to deserialize the frames and pull out only the data source of interest. For now, all
+    // we care about is the ITM data.
+
+    let mut id = 0.into();
+    let mut itm_trace = Vec::new();
+
+    // Process each formatted frame and extract the multiplexed trace data.
+    for frame_buffer in etf_trace.chunks_exact(16) {
+        let mut frame = tmc::Frame::new(frame_buffer, id);
+        for (id, data) in &mut frame {
+            match id.into() {
+                // ITM ATID, see Itm::tx_enable()
+                13 => itm_trace.push(data),
+                // ETM ATID, see setup_instruction_trace()
+                ETM_TRACE_ID => (),
+                0 => (),
+                id => tracing::warn!("Unexpected trace source ATID {id}: {data}, ignoring"),
+            }
+        }
+        id = frame.id();
+    }
+
+    Ok(itm_trace)
+}
+
+/// Read the formatted frames from internal trace memory.
+fn read_formatted_trace_memory(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
 ) -> Result<Vec<u8>, ArmError> {
     let mut tmc =
         TraceMemoryController::new(interface, find_component(components, PeripheralType::Tmc)?);
@@ -322,28 +376,146 @@ pub(crate) fn read_trace_memory(
         }
     }
 
-    // The TMC formats data into frames, as it contains trace data from multiple data sources. We
-    // need to deserialize the frames and pull out only the data source of interest. For now, all
-    // we care about is the ITM data.
+    Ok(etf_trace)
+}
 
-    let mut id = 0.into();
-    let mut itm_trace = Vec::new();
+/// Extracts the data of a single trace source from a stream of formatted frames.
+///
+/// The stream doesn't have to be split at frame boundaries, incomplete frames are kept until
+/// more data is pushed.
+#[derive(Debug)]
+pub(crate) struct TraceDeformatter {
+    id: u8,
+    current_id: tmc::Id,
+    pending: Vec<u8>,
+}
 
-    // Process each formatted frame and extract the multiplexed trace data.
-    for frame_buffer in etf_trace.chunks_exact(16) {
-        let mut frame = tmc::Frame::new(frame_buffer, id);
-        for (id, data) in &mut frame {
-            match id.into() {
-                // ITM ATID, see Itm::tx_enable()
-                13 => itm_trace.push(data),
-                0 => (),
-                id => tracing::warn!("Unexpected trace source ATID {id}: {data}, ignoring"),
+impl TraceDeformatter {
+    /// Create a deformatter for the trace source with the given ATID.
+    pub(crate) fn new(id: u8) -> Self {
+        Self {
+            id,
+            current_id: 0.into(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Push formatted data, and return the data of the trace source contained in all complete
+    /// frames.
+    pub(crate) fn push(&mut self, data: &[u8]) -> Vec<u8> {
+        self.pending.extend_from_slice(data);
+
+        let mut output = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let rest = &self.pending[offset..];
+
+            // The TPIU inserts full-word synchronization packets between frames.
+            if rest.starts_with(&[0xFF, 0xFF, 0xFF, 0x7F]) {
+                offset += 4;
+                continue;
             }
+
+            if rest.len() < 16 {
+                break;
+            }
+
+            let mut frame = tmc::Frame::new(&rest[..16], self.current_id);
+            for (id, data) in &mut frame {
+                if u8::from(id) == self.id {
+                    output.push(data);
+                }
+            }
+            self.current_id = frame.id();
+            offset += 16;
+        }
+
+        self.pending.drain(..offset);
+        output
+    }
+}
+
+/// Sets up instruction trace with the ETM or the MTB.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn setup_instruction_trace(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+    source: &InstructionTraceSource,
+) -> Result<InstructionTrace, Error> {
+    match source {
+        InstructionTraceSource::Etm(sink) => {
+            // The ETM trace has to be separated from the ITM trace, which requires formatting.
+            let sink = match *sink {
+                TraceSink::Swo(config) => TraceSink::Swo(config.set_continuous_formatting(true)),
+                TraceSink::Tpiu(config) => TraceSink::Tpiu(config.set_continuous_formatting(true)),
+                TraceSink::TraceMemory => TraceSink::TraceMemory,
+            };
+            configure_trace_sink(interface, components, &sink)?;
+
+            let mut etm = Etm::new(interface, find_component(components, PeripheralType::Etm)?);
+            let version = etm.enable(ETM_TRACE_ID)?;
+
+            Ok(InstructionTrace::Etm {
+                sink,
+                deformatter: TraceDeformatter::new(ETM_TRACE_ID),
+                decoder: EtmDecoder::new(version),
+            })
+        }
+        InstructionTraceSource::Mtb => {
+            let mut mtb = Mtb::new(interface, find_component(components, PeripheralType::Mtb)?);
+            mtb.enable()?;
+
+            Ok(InstructionTrace::Mtb)
         }
-        id = frame.id();
     }
+}
 
-    Ok(itm_trace)
+/// Reads and decodes the instruction trace recorded since the last read.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn read_instruction_trace(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+    trace: &mut InstructionTrace,
+) -> Result<Vec<TraceEvent>, ArmError> {
+    match trace {
+        InstructionTrace::Etm {
+            sink,
+            deformatter,
+            decoder,
+        } => {
+            let formatted = match sink {
+                TraceSink::Swo(_) | TraceSink::Tpiu(_) => interface.read_swo()?,
+                TraceSink::TraceMemory => read_formatted_trace_memory(interface, components)?,
+            };
+            Ok(decoder.decode(&deformatter.push(&formatted)))
+        }
+        InstructionTrace::Mtb => {
+            let mut mtb = Mtb::new(interface, find_component(components, PeripheralType::Mtb)?);
+            let entries = mtb.read_entries()?;
+            Ok(crate::architecture::arm::trace::decode_mtb(&entries))
+        }
+    }
+}
+
+/// Stops the instruction trace.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn disable_instruction_trace(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+    trace: &InstructionTrace,
+) -> Result<(), ArmError> {
+    match trace {
+        InstructionTrace::Etm { .. } => {
+            Etm::new(interface, find_component(components, PeripheralType::Etm)?).disable()
+        }
+        InstructionTrace::Mtb => {
+            Mtb::new(interface, find_component(components, PeripheralType::Mtb)?).disable()
+        }
+    }
 }
 
 /// Configures DWT trace unit `unit` to begin tracing `address`.
@@ -360,6 +532,62 @@ pub(crate) fn add_swv_data_trace(
     dwt.enable_data_trace(unit, address)
 }
 
+/// Enables the DWT cycle counter and profiling counters.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn enable_dwt_counters(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+) -> Result<(), ArmError> {
+    let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
+    dwt.enable_counters()
+}
+
+/// Reads the DWT cycle counter and profiling counters.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn read_dwt_counters(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+) -> Result<DwtCounters, ArmError> {
+    let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
+    dwt.read_counters()
+}
+
+/// Resets the DWT cycle counter and profiling counters to zero.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn reset_dwt_counters(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+) -> Result<(), ArmError> {
+    let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
+    dwt.reset_counters()
+}
+
+/// Enables periodic PC sampling in the DWT, see [`Dwt::enable_pc_sampling`].
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn enable_pc_sampling(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+    interval: u32,
+) -> Result<u32, ArmError> {
+    let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
+    dwt.enable_pc_sampling(interval)
+}
+
+/// Disables periodic PC sampling in the DWT.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn disable_pc_sampling(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+) -> Result<(), ArmError> {
+    let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
+    dwt.disable_pc_sampling()
+}
+
 /// Configures DWT trace unit `unit` to stop tracing `address`.
 ///
 ///
@@ -0,0 +1,164 @@
+//! Module for using the MTB.
+//!
+//! MTB = Micro Trace Buffer
+//!
+//! The MTB of the Cortex-M0+ records every non-sequential change of the program counter into a
+//! circular buffer in the on-chip SRAM. Each entry is a pair of the source and the destination
+//! address of the branch.
+//!
+//! The SRAM used by the MTB is shared with the application. The trace buffer starts at the
+//! address in the MTB `BASE` register, which the application has to leave unused.
+
+use bitfield::bitfield;
+
+use super::super::memory::romtable::CoresightComponent;
+use super::DebugRegister;
+use crate::architecture::arm::{ArmError, ArmProbeInterface};
+
+const REGISTER_OFFSET_POSITION: u32 = 0x000;
+const REGISTER_OFFSET_BASE: u32 = 0x00C;
+
+/// The size of a single entry in the trace buffer in bytes.
+const ENTRY_SIZE: u32 = 8;
+
+/// A single entry of the MTB trace buffer, as recorded by the hardware.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MtbEntry {
+    /// The source address of the branch. Bit 0 is set for exception entry and return.
+    pub source: u32,
+    /// The destination address of the branch. Bit 0 is set for the first entry after tracing started.
+    pub destination: u32,
+}
+
+/// An interface to control the MTB (Micro Trace Buffer) of a Cortex-M0+.
+pub struct Mtb<'a> {
+    component: &'a CoresightComponent,
+    interface: &'a mut dyn ArmProbeInterface,
+}
+
+impl<'a> Mtb<'a> {
+    /// Create a new MTB interface from a probe and a ROM table component.
+    pub fn new(
+        interface: &'a mut dyn ArmProbeInterface,
+        component: &'a CoresightComponent,
+    ) -> Self {
+        Mtb {
+            component,
+            interface,
+        }
+    }
+
+    /// The size of the trace buffer in bytes.
+    ///
+    /// The largest size supported by the implementation is used.
+    pub fn buffer_size(&mut self) -> Result<u32, ArmError> {
+        let master = Master::load(self.component, self.interface)?;
+        Ok(1 << (master.mask() + 4))
+    }
+
+    /// Start tracing into an empty trace buffer.
+    pub fn enable(&mut self) -> Result<(), ArmError> {
+        // The MASK field only keeps the bits supported by the implementation, so writing all ones
+        // selects the largest buffer.
+        let mut master = Master(0);
+        master.set_mask(0x1F);
+        master.store(self.component, self.interface)?;
+
+        self.component
+            .write_reg(self.interface, REGISTER_OFFSET_POSITION, 0)?;
+
+        let mut master = Master::load(self.component, self.interface)?;
+        master.set_en(true);
+        master.store(self.component, self.interface)
+    }
+
+    /// Stop tracing.
+    pub fn disable(&mut self) -> Result<(), ArmError> {
+        let mut master = Master::load(self.component, self.interface)?;
+        master.set_en(false);
+        master.store(self.component, self.interface)
+    }
+
+    /// Read all entries recorded since tracing was enabled or since the last read, oldest first.
+    ///
+    /// Tracing is paused while the buffer is read, and the buffer is emptied afterwards.
+    pub fn read_entries(&mut self) -> Result<Vec<MtbEntry>, ArmError> {
+        let master = Master::load(self.component, self.interface)?;
+        let mut stopped = master.clone();
+        stopped.set_en(false);
+        stopped.store(self.component, self.interface)?;
+
+        let size = 1 << (master.mask() + 4);
+        let position = self
+            .component
+            .read_reg(self.interface, REGISTER_OFFSET_POSITION)?;
+        let base = self
+            .component
+            .read_reg(self.interface, REGISTER_OFFSET_BASE)?;
+
+        let pointer = (position & !0x7) & (size - 1);
+        let wrapped = position & (1 << 2) != 0;
+
+        let mut words = vec![0; (size / 4) as usize];
+        {
+            let mut memory = self.interface.memory_interface(self.component.ap)?;
+            memory.read_32(base as u64, &mut words)?;
+        }
+
+        let to_entries = |words: &[u32]| -> Vec<MtbEntry> {
+            words
+                .chunks_exact(2)
+                .map(|entry| MtbEntry {
+                    source: entry[0],
+                    destination: entry[1],
+                })
+                .collect()
+        };
+
+        let split = (pointer / 4) as usize;
+        let mut entries = Vec::with_capacity((size / ENTRY_SIZE) as usize);
+        if wrapped {
+            // The oldest entry is the one the next write would overwrite.
+            entries.extend(to_entries(&words[split..]));
+        }
+        entries.extend(to_entries(&words[..split]));
+
+        self.component
+            .write_reg(self.interface, REGISTER_OFFSET_POSITION, 0)?;
+        master.store(self.component, self.interface)?;
+
+        Ok(entries)
+    }
+}
+
+bitfield! {
+    #[derive(Clone, Default)]
+    pub struct Master(u32);
+    impl Debug;
+
+    pub en, set_en: 31;
+    pub haltreq, set_haltreq: 9;
+    pub rampriv, set_rampriv: 8;
+    pub sfrwpriv, set_sfrwpriv: 7;
+    pub tstopen, set_tstopen: 6;
+    pub tstarten, set_tstarten: 5;
+    /// The size of the trace buffer is 2^(MASK + 4) bytes.
+    pub u8, mask, set_mask: 4, 0;
+}
+
+impl From<u32> for Master {
+    fn from(raw: u32) -> Self {
+        Master(raw)
+    }
+}
+
+impl From<Master> for u32 {
+    fn from(master: Master) -> u32 {
+        master.0
+    }
+}
+
+impl DebugRegister for Master {
+    const ADDRESS: u32 = 0x004;
+    const NAME: &'static str = "MTB_MASTER";
+}
@@ -542,6 +542,7 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
@@ -611,6 +612,7 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
 
         Ok(CoreInformation {
             pc: pc_after_step.try_into()?,
+            security_state: None,
         })
     }
 
@@ -644,6 +646,7 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
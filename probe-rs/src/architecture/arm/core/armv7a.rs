@@ -16,13 +16,14 @@ use crate::{Architecture, CoreInformation, CoreType, InstructionSet};
 use anyhow::Result;
 
 use super::instructions::aarch32::{
-    build_bx, build_ldc, build_mcr, build_mov, build_mrc, build_mrs, build_stc, build_vmov,
-    build_vmrs,
+    build_bx, build_dsb, build_isb, build_ldc, build_mcr, build_mov, build_mrc, build_mrs,
+    build_stc, build_vmov, build_vmrs,
 };
 use super::CortexAState;
 use super::{AARCH32_COMMON_REGS, AARCH32_FP_16_REGS, AARCH32_FP_32_REGS};
 
 use std::mem::size_of;
+use std::ops::Range;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
@@ -41,6 +42,10 @@ pub enum Armv7aError {
     /// Data Abort occurred
     #[error("A data abort occurred")]
     DataAbort,
+
+    /// The MMU could not translate an address
+    #[error("The address {0:#x} could not be translated by the MMU")]
+    TranslationFault(u64),
 }
 
 /// Interface for interacting with an ARMv7-A core
@@ -290,6 +295,120 @@ impl<'probe> Armv7a<'probe> {
 
         self.execute_instruction_with_input(instruction, value)
     }
+
+    /// Read the physical address register, PAR. This clobbers r0.
+    fn read_par(&mut self) -> Result<u32, Error> {
+        // Read PAR - MRC p15, 0, r0, c7, c4, 0
+        let instruction = build_mrc(15, 0, 0, 7, 4, 0);
+        self.execute_instruction(instruction)?;
+
+        // Move from r0 to transfer buffer - MCR p14, 0, r0, c0, c5, 0
+        let instruction = build_mcr(14, 0, 0, 0, 5, 0);
+        self.execute_instruction_with_result(instruction)
+    }
+
+    /// Write the physical address register, PAR. This clobbers r0.
+    fn write_par(&mut self, value: u32) -> Result<(), Error> {
+        self.set_r0(value)?;
+
+        // Write PAR - MCR p15, 0, r0, c7, c4, 0
+        let instruction = build_mcr(15, 0, 0, 7, 4, 0);
+        self.execute_instruction(instruction)?;
+
+        Ok(())
+    }
+
+    /// Translate an address with the ATS1CPR operation, and return the resulting value of PAR.
+    /// This clobbers r0 and PAR.
+    fn translate_to_par(&mut self, address: u32) -> Result<u32, Error> {
+        // Load r0 with the address to translate
+        self.set_r0(address)?;
+
+        // ATS1CPR - MCR p15, 0, r0, c7, c8, 0
+        let instruction = build_mcr(15, 0, 0, 7, 8, 0);
+        self.execute_instruction(instruction)?;
+        self.execute_instruction(build_isb())?;
+
+        self.read_par()
+    }
+
+    /// Size of the smallest data cache line in bytes
+    fn data_cache_line_size(&mut self) -> Result<u64, Error> {
+        // Save r0
+        self.prepare_r0_for_clobber()?;
+
+        // Read CTR - MRC p15, 0, r0, c0, c0, 1
+        let instruction = build_mrc(15, 0, 0, 0, 0, 1);
+        self.execute_instruction(instruction)?;
+
+        // Move from r0 to transfer buffer - MCR p14, 0, r0, c0, c5, 0
+        let instruction = build_mcr(14, 0, 0, 0, 5, 0);
+        let ctr = self.execute_instruction_with_result(instruction)?;
+
+        // DminLine is the log2 of the number of words in the smallest data cache line
+        Ok(4 << ((ctr >> 16) & 0xF))
+    }
+
+    /// Run the data cache maintenance operation `MCR p15, 0, r0, c7, <crm>, 1` for every
+    /// cache line covering the given memory range
+    fn data_cache_maintenance(&mut self, crm: u8, address: u64, length: u64) -> Result<(), Error> {
+        if length == 0 {
+            return Ok(());
+        }
+
+        let line_size = self.data_cache_line_size()?;
+        let range = address..address.saturating_add(length);
+
+        self.data_cache_maintenance_by_line(crm, range, line_size)?;
+        self.data_synchronization_barrier()
+    }
+
+    /// Run the data cache maintenance operation `MCR p15, 0, r0, c7, <crm>, 1` for every
+    /// cache line covering `range`, without a barrier afterwards
+    fn data_cache_maintenance_by_line(
+        &mut self,
+        crm: u8,
+        range: Range<u64>,
+        line_size: u64,
+    ) -> Result<(), Error> {
+        let mut line = range.start & !(line_size - 1);
+
+        while line < range.end {
+            self.set_r0(valid_32bit_address(line)?)?;
+            self.execute_instruction(build_mcr(15, 0, 0, 7, crm, 1))?;
+
+            line = line.saturating_add(line_size);
+        }
+
+        Ok(())
+    }
+
+    fn data_synchronization_barrier(&mut self) -> Result<(), Error> {
+        self.execute_instruction(build_dsb())?;
+
+        Ok(())
+    }
+
+    /// Make memory written through the core visible to its instruction fetches
+    fn synchronize_written_memory(&mut self) -> Result<(), Error> {
+        if self.state.written_memory.is_empty() {
+            return Ok(());
+        }
+
+        let line_size = self.data_cache_line_size()?;
+
+        for range in self.state.written_memory.line_ranges(line_size) {
+            // Clean to the point of unification - DCCMVAU
+            self.data_cache_maintenance_by_line(11, range, line_size)?;
+        }
+
+        self.data_synchronization_barrier()?;
+        self.invalidate_instruction_cache()?;
+
+        self.state.written_memory.clear();
+
+        Ok(())
+    }
 }
 
 impl<'probe> CoreInterface for Armv7a<'probe> {
@@ -338,6 +457,7 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
@@ -346,6 +466,9 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
             return Ok(());
         }
 
+        // make code written by the debugger visible to the core
+        self.synchronize_written_memory()?;
+
         // set writeback values
         self.writeback_registers()?;
 
@@ -381,6 +504,7 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
 
         // Reset our cached values
         self.reset_register_cache();
+        self.state.written_memory.clear();
 
         Ok(())
     }
@@ -418,6 +542,7 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
 
         // Reset our cached values
         self.reset_register_cache();
+        self.state.written_memory.clear();
 
         // try to read the program counter
         let pc_value = self.read_core_reg(register::PC.id)?;
@@ -425,6 +550,7 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
@@ -468,12 +594,17 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
         self.memory
             .write_word_32(bp_control_addr, saved_bp_control)?;
 
+        // Update core status, the step changed the registers
+        let _ = self.status()?;
+        self.reset_register_cache();
+
         // try to read the program counter
         let pc_value = self.read_core_reg(register::PC.id)?;
 
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
@@ -758,6 +889,58 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
             Ok(())
         }
     }
+
+    fn translate_address(&mut self, address: u64) -> Result<u64, Error> {
+        let virtual_address = valid_32bit_address(address)?;
+
+        // Save r0
+        self.prepare_r0_for_clobber()?;
+
+        // The translation overwrites PAR, which belongs to the code running on the core
+        let saved_par = self.read_par()?;
+        let result = self.translate_to_par(virtual_address);
+        self.write_par(saved_par)?;
+        let par = result?;
+
+        // PAR.F is set if the translation failed
+        if par & 1 != 0 {
+            return Err(Error::Arm(Armv7aError::TranslationFault(address).into()));
+        }
+
+        Ok(((par & 0xFFFF_F000) | (virtual_address & 0xFFF)).into())
+    }
+
+    fn prepare_for_restart(&mut self) -> Result<(), Error> {
+        if matches!(self.state.current_state, CoreStatus::Halted(_)) {
+            self.synchronize_written_memory()?;
+            self.writeback_registers()?;
+        }
+
+        Ok(())
+    }
+
+    fn clean_data_cache(&mut self, address: u64, length: u64) -> Result<(), Error> {
+        // DCCMVAC
+        self.data_cache_maintenance(10, address, length)
+    }
+
+    fn invalidate_data_cache(&mut self, address: u64, length: u64) -> Result<(), Error> {
+        // DCCIMVAC
+        self.data_cache_maintenance(14, address, length)
+    }
+
+    fn invalidate_instruction_cache(&mut self) -> Result<(), Error> {
+        // ICIALLU - MCR p15, 0, r0, c7, c5, 0
+        self.execute_instruction(build_mcr(15, 0, 0, 7, 5, 0))?;
+
+        // BPIALL - MCR p15, 0, r0, c7, c5, 6
+        self.execute_instruction(build_mcr(15, 0, 0, 7, 5, 6))?;
+
+        self.execute_instruction(build_dsb())?;
+        self.execute_instruction(build_isb())?;
+
+        Ok(())
+    }
 }
 
 impl<'probe> MemoryInterface for Armv7a<'probe> {
@@ -845,7 +1028,11 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
         self.set_r0(address)?;
 
         // Write to [r0]
-        self.execute_instruction_with_input(instr, data)
+        self.execute_instruction_with_input(instr, data)?;
+
+        self.state.record_memory_write(address.into(), 4);
+
+        Ok(())
     }
 
     fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
@@ -879,7 +1066,7 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
 
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
         for (i, byte) in data.iter().enumerate() {
-            self.write_word_8(address + ((i as u64) * 4), *byte)?;
+            self.write_word_8(address + (i as u64), *byte)?;
         }
 
         Ok(())
@@ -890,8 +1077,9 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
     }
 
     fn flush(&mut self) -> Result<(), Error> {
-        // Nothing to do - this runs through the CPU which automatically handles any caching
-        Ok(())
+        // Data accesses run through the CPU which automatically handles the data cache, but
+        // the instruction cache has to be updated with any code written
+        self.synchronize_written_memory()
     }
 }
 
@@ -1166,6 +1354,14 @@ mod test {
         probe.expected_read(Dbgdscr::get_mmio_address(TEST_BASE_ADDRESS), dbgdscr.into());
     }
 
+    fn add_execute_instruction_expectations(probe: &mut MockProbe, instruction: u32) {
+        let mut dbgdscr = Dbgdscr(0);
+        dbgdscr.set_instrcoml_l(true);
+
+        probe.expected_write(Dbgitr::get_mmio_address(TEST_BASE_ADDRESS), instruction);
+        probe.expected_read(Dbgdscr::get_mmio_address(TEST_BASE_ADDRESS), dbgdscr.into());
+    }
+
     fn add_read_memory_expectations(probe: &mut MockProbe, address: u64, value: u32) {
         add_set_r0_expectation(probe, address as u32);
 
@@ -1731,4 +1927,72 @@ mod test {
 
         assert_eq!(0xBA, armv7a.read_word_8(MEMORY_ADDRESS).unwrap());
     }
+
+    #[test]
+    fn armv7a_clean_data_cache() {
+        // 8 words per cache line
+        const CTR_VALUE: u32 = 3 << 16;
+        const MEMORY_ADDRESS: u64 = 0x12345678;
+
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        // Read CTR
+        add_execute_instruction_expectations(&mut probe, build_mrc(15, 0, 0, 0, 0, 1));
+        add_read_reg_expectations(&mut probe, 0, CTR_VALUE);
+
+        // Clean both cache lines covering the range
+        add_set_r0_expectation(&mut probe, 0x12345660);
+        add_execute_instruction_expectations(&mut probe, build_mcr(15, 0, 0, 7, 10, 1));
+        add_set_r0_expectation(&mut probe, 0x12345680);
+        add_execute_instruction_expectations(&mut probe, build_mcr(15, 0, 0, 7, 10, 1));
+        add_execute_instruction_expectations(&mut probe, build_dsb());
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        armv7a.clean_data_cache(MEMORY_ADDRESS, 16).unwrap();
+    }
+
+    #[test]
+    fn armv7a_invalidate_instruction_cache() {
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        add_execute_instruction_expectations(&mut probe, build_mcr(15, 0, 0, 7, 5, 0));
+        add_execute_instruction_expectations(&mut probe, build_mcr(15, 0, 0, 7, 5, 6));
+        add_execute_instruction_expectations(&mut probe, build_dsb());
+        add_execute_instruction_expectations(&mut probe, build_isb());
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        armv7a.invalidate_instruction_cache().unwrap();
+    }
 }
@@ -366,6 +366,40 @@ impl MemoryMappedRegister for Demcr {
     const NAME: &'static str = "DEMCR";
 }
 
+/// DWT Program Counter Sample Register, DWT_PCSR (see armv7-M Architecture Reference Manual C1.8.7)
+///
+/// Reading it samples the PC of the running core without halting it. It reads as
+/// `0xFFFF_FFFF` if the core is halted, or if PC sampling is not implemented.
+#[derive(Debug, Copy, Clone)]
+pub struct DwtPcsr(pub u32);
+
+impl DwtPcsr {
+    /// The sampled PC, or `None` if no sample could be taken.
+    pub fn pc(&self) -> Option<u32> {
+        match self.0 {
+            0xFFFF_FFFF => None,
+            pc => Some(pc),
+        }
+    }
+}
+
+impl From<u32> for DwtPcsr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<DwtPcsr> for u32 {
+    fn from(value: DwtPcsr) -> Self {
+        value.0
+    }
+}
+
+impl MemoryMappedRegister for DwtPcsr {
+    const ADDRESS: u64 = 0xE000_101C;
+    const NAME: &'static str = "DWT_PCSR";
+}
+
 bitfield! {
     /// Flash Patch Control Register, FP_CTRL (see armv7-M Architecture Reference Manual C1.11.3)
     #[derive(Copy,Clone)]
@@ -761,6 +795,7 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
@@ -848,6 +883,7 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
 
         Ok(CoreInformation {
             pc: pc_after_step.try_into()?,
+            security_state: None,
         })
     }
 
@@ -884,6 +920,7 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
@@ -20,9 +20,10 @@ use super::AARCH32_FP_32_REGS;
 
 use super::instructions::aarch64;
 use super::instructions::thumb2::{
-    build_ldr, build_mcr, build_mrc, build_str, build_vmov, build_vmrs,
+    build_dsb, build_isb, build_ldr, build_mcr, build_mrc, build_str, build_vmov, build_vmrs,
 };
 
+use std::ops::Range;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
@@ -41,6 +42,10 @@ pub enum Armv8aError {
     /// Data Abort occurred
     #[error("A data abort occurred")]
     DataAbort,
+
+    /// The MMU could not translate an address
+    #[error("The address {0:#x} could not be translated by the MMU")]
+    TranslationFault(u64),
 }
 
 /// When in 32-bit mode the two words have to be placed in swapped
@@ -398,6 +403,76 @@ impl<'probe> Armv8a<'probe> {
         }
     }
 
+    /// Read the physical address register, PAR or PAR_EL1. This clobbers r0/x0.
+    fn read_par(&mut self) -> Result<u64, Error> {
+        if self.state.is_64_bit {
+            // MRS X0, PAR_EL1
+            let instruction = aarch64::build_mrs(3, 0, 7, 4, 0, 0);
+            self.execute_instruction(instruction)?;
+
+            // MSR DBGDTR_EL0, X0
+            let instruction = aarch64::build_msr(2, 3, 0, 4, 0, 0);
+            self.execute_instruction_with_result_64(instruction)
+        } else {
+            // Read PAR - MRC p15, 0, r0, c7, c4, 0
+            let instruction = build_mrc(15, 0, 0, 7, 4, 0);
+            self.execute_instruction(instruction)?;
+
+            // Move from r0 to transfer buffer - MCR p14, 0, r0, c0, c5, 0
+            let instruction = build_mcr(14, 0, 0, 0, 5, 0);
+            Ok(self.execute_instruction_with_result_32(instruction)?.into())
+        }
+    }
+
+    /// Write the physical address register, PAR or PAR_EL1. This clobbers r0/x0.
+    fn write_par(&mut self, value: u64) -> Result<(), Error> {
+        self.set_reg_value(0, value)?;
+
+        let instruction = if self.state.is_64_bit {
+            // MSR PAR_EL1, X0
+            aarch64::build_msr(3, 0, 7, 4, 0, 0)
+        } else {
+            // Write PAR - MCR p15, 0, r0, c7, c4, 0
+            build_mcr(15, 0, 0, 7, 4, 0)
+        };
+        self.execute_instruction(instruction)?;
+
+        Ok(())
+    }
+
+    /// Translate an address with the address translation operations of the core, and return
+    /// the resulting value of PAR together with the mask of its physical address bits.
+    /// This clobbers r0/x0 and PAR.
+    fn translate_to_par(&mut self, address: u64) -> Result<(u64, u64), Error> {
+        // Load x0 with the address to translate
+        self.set_reg_value(0, address)?;
+
+        if self.state.is_64_bit {
+            // Translate in the regime of the exception level the core is halted in
+            let address = Edscr::get_mmio_address(self.base_address);
+            let edscr = Edscr(self.memory.read_word_32(address)?);
+            let op1 = match edscr.el() {
+                2 => 4,
+                3 => 6,
+                _ => 0,
+            };
+
+            // AT S1E<n>R, X0
+            let instruction = aarch64::build_sys(op1, 7, 8, 0, 0);
+            self.execute_instruction(instruction)?;
+            self.execute_instruction(aarch64::build_isb())?;
+
+            Ok((self.read_par()?, 0x000F_FFFF_FFFF_F000))
+        } else {
+            // ATS1CPR - MCR p15, 0, r0, c7, c8, 0
+            let instruction = build_mcr(15, 0, 0, 7, 8, 0);
+            self.execute_instruction(instruction)?;
+            self.execute_instruction(build_isb())?;
+
+            Ok((self.read_par()?, 0xFFFF_F000))
+        }
+    }
+
     fn ack_cti_halt(&mut self) -> Result<(), Error> {
         let mut ack = CtiIntack(0);
         ack.set_ack(0, 1);
@@ -678,6 +753,8 @@ impl<'probe> Armv8a<'probe> {
 
         self.execute_instruction(instruction)?;
 
+        self.state.record_memory_write(address.into(), 4);
+
         Ok(())
     }
 
@@ -695,6 +772,8 @@ impl<'probe> Armv8a<'probe> {
 
         self.execute_instruction(instruction)?;
 
+        self.state.record_memory_write(address, 4);
+
         Ok(())
     }
 
@@ -712,6 +791,108 @@ impl<'probe> Armv8a<'probe> {
 
         self.execute_instruction(instruction)?;
 
+        self.state.record_memory_write(address, 8);
+
+        Ok(())
+    }
+
+    /// Size of the smallest data cache line in bytes
+    fn data_cache_line_size(&mut self) -> Result<u64, Error> {
+        // Save x0
+        self.prepare_for_clobber(0)?;
+
+        let ctr = if self.state.is_64_bit {
+            // MRS CTR_EL0, X0
+            let instruction = aarch64::build_mrs(3, 3, 0, 0, 1, 0);
+            self.execute_instruction(instruction)?;
+
+            // MSR DBGDTR_EL0, X0
+            let instruction = aarch64::build_msr(2, 3, 0, 4, 0, 0);
+            self.execute_instruction_with_result_64(instruction)?
+        } else {
+            // Read CTR - MRC p15, 0, r0, c0, c0, 1
+            let instruction = build_mrc(15, 0, 0, 0, 0, 1);
+            self.execute_instruction(instruction)?;
+
+            // Move from r0 to transfer buffer - MCR p14, 0, r0, c0, c5, 0
+            let instruction = build_mcr(14, 0, 0, 0, 5, 0);
+            self.execute_instruction_with_result_32(instruction)?.into()
+        };
+
+        // DminLine is the log2 of the number of words in the smallest data cache line
+        Ok(4 << ((ctr >> 16) & 0xF))
+    }
+
+    /// Run a data cache maintenance operation by virtual address for every cache line covering
+    /// the given memory range.
+    ///
+    /// `crm` selects the operation, using the same encoding for `DC <op>, X0` in AArch64 and
+    /// `MCR p15, 0, r0, c7, <crm>, 1` in AArch32.
+    fn data_cache_maintenance(&mut self, crm: u8, address: u64, length: u64) -> Result<(), Error> {
+        if length == 0 {
+            return Ok(());
+        }
+
+        let line_size = self.data_cache_line_size()?;
+        let range = address..address.saturating_add(length);
+
+        self.data_cache_maintenance_by_line(crm, range, line_size)?;
+        self.data_synchronization_barrier()
+    }
+
+    /// Run a data cache maintenance operation by virtual address for every cache line covering
+    /// `range`, without a barrier afterwards.
+    fn data_cache_maintenance_by_line(
+        &mut self,
+        crm: u8,
+        range: Range<u64>,
+        line_size: u64,
+    ) -> Result<(), Error> {
+        let mut line = range.start & !(line_size - 1);
+
+        while line < range.end {
+            self.set_reg_value(0, line)?;
+
+            if self.state.is_64_bit {
+                self.execute_instruction(aarch64::build_sys(3, 7, crm, 1, 0))?;
+            } else {
+                self.execute_instruction(build_mcr(15, 0, 0, 7, crm, 1))?;
+            }
+
+            line = line.saturating_add(line_size);
+        }
+
+        Ok(())
+    }
+
+    fn data_synchronization_barrier(&mut self) -> Result<(), Error> {
+        if self.state.is_64_bit {
+            self.execute_instruction(aarch64::build_dsb())?;
+        } else {
+            self.execute_instruction(build_dsb())?;
+        }
+
+        Ok(())
+    }
+
+    /// Make memory written through the core visible to its instruction fetches
+    fn synchronize_written_memory(&mut self) -> Result<(), Error> {
+        if self.state.written_memory.is_empty() {
+            return Ok(());
+        }
+
+        let line_size = self.data_cache_line_size()?;
+
+        for range in self.state.written_memory.line_ranges(line_size) {
+            // Clean to the point of unification - DC CVAU / DCCMVAU
+            self.data_cache_maintenance_by_line(11, range, line_size)?;
+        }
+
+        self.data_synchronization_barrier()?;
+        self.invalidate_instruction_cache()?;
+
+        self.state.written_memory.clear();
+
         Ok(())
     }
 }
@@ -742,11 +923,11 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
 
     fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
         if !matches!(self.state.current_state, CoreStatus::Halted(_)) {
-            // Ungate halt CTI channel
-            let mut cti_gate = CtiGate(0);
+            // Ungate halt CTI channel, leaving the channels of a halt group untouched
+            let address = CtiGate::get_mmio_address(self.cti_address);
+            let mut cti_gate = CtiGate(self.memory.read_word_32(address)?);
             cti_gate.set_en(0, 1);
 
-            let address = CtiGate::get_mmio_address(self.cti_address);
             self.memory.write_word_32(address, cti_gate.into())?;
 
             // Pulse it
@@ -767,9 +948,10 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         let _ = self.status()?;
 
         // Gate halt channel
-        let cti_gate = CtiGate(0);
-
         let address = CtiGate::get_mmio_address(self.cti_address);
+        let mut cti_gate = CtiGate(self.memory.read_word_32(address)?);
+        cti_gate.set_en(0, 0);
+
         self.memory.write_word_32(address, cti_gate.into())?;
 
         // try to read the program counter
@@ -778,6 +960,7 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
@@ -786,16 +969,19 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
             return Ok(());
         }
 
+        // make code written by the debugger visible to the core
+        self.synchronize_written_memory()?;
+
         // set writeback values
         self.writeback_registers()?;
 
         self.ack_cti_halt()?;
 
         // Ungate restart CTI channel
-        let mut cti_gate = CtiGate(0);
+        let address = CtiGate::get_mmio_address(self.cti_address);
+        let mut cti_gate = CtiGate(self.memory.read_word_32(address)?);
         cti_gate.set_en(1, 1);
 
-        let address = CtiGate::get_mmio_address(self.cti_address);
         self.memory.write_word_32(address, cti_gate.into())?;
 
         // Pulse it
@@ -820,9 +1006,10 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         let _ = self.status()?;
 
         // Gate restart channel
-        let cti_gate = CtiGate(0);
-
         let address = CtiGate::get_mmio_address(self.cti_address);
+        let mut cti_gate = CtiGate(self.memory.read_word_32(address)?);
+        cti_gate.set_en(1, 0);
+
         self.memory.write_word_32(address, cti_gate.into())?;
 
         Ok(())
@@ -837,6 +1024,7 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
 
         // Reset our cached values
         self.reset_register_cache();
+        self.state.written_memory.clear();
 
         Ok(())
     }
@@ -867,6 +1055,7 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
 
         // Reset our cached values
         self.reset_register_cache();
+        self.state.written_memory.clear();
 
         // try to read the program counter
         let pc_value = self.read_core_reg(self.registers().program_counter().id)?;
@@ -874,6 +1063,7 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
@@ -895,12 +1085,17 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         edecr.set_ss(false);
         self.memory.write_word_32(edecr_address, edecr.into())?;
 
+        // Update core status, the step changed the registers
+        let _ = self.status()?;
+        self.reset_register_cache();
+
         // try to read the program counter
         let pc_value = self.read_core_reg(self.registers().program_counter().id)?;
 
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: None,
         })
     }
 
@@ -1085,6 +1280,10 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         Ok(true)
     }
 
+    fn is_64_bit(&self) -> bool {
+        self.state.is_64_bit
+    }
+
     fn on_session_stop(&mut self) -> Result<(), Error> {
         if matches!(self.state.current_state, CoreStatus::Halted(_)) {
             // We may have clobbered registers we wrote during debugging
@@ -1094,6 +1293,63 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
             Ok(())
         }
     }
+
+    fn translate_address(&mut self, address: u64) -> Result<u64, Error> {
+        // Save x0
+        self.prepare_for_clobber(0)?;
+
+        // The translation overwrites PAR, which belongs to the code running on the core
+        let saved_par = self.read_par()?;
+        let result = self.translate_to_par(address);
+        self.write_par(saved_par)?;
+        let (par, address_mask) = result?;
+
+        // PAR.F is set if the translation failed
+        if par & 1 != 0 {
+            return Err(Error::Arm(Armv8aError::TranslationFault(address).into()));
+        }
+
+        Ok((par & address_mask) | (address & 0xFFF))
+    }
+
+    fn prepare_for_restart(&mut self) -> Result<(), Error> {
+        if matches!(self.state.current_state, CoreStatus::Halted(_)) {
+            self.synchronize_written_memory()?;
+            self.writeback_registers()?;
+        }
+
+        Ok(())
+    }
+
+    fn clean_data_cache(&mut self, address: u64, length: u64) -> Result<(), Error> {
+        // DC CVAC / DCCMVAC
+        self.data_cache_maintenance(10, address, length)
+    }
+
+    fn invalidate_data_cache(&mut self, address: u64, length: u64) -> Result<(), Error> {
+        // DC CIVAC / DCCIMVAC
+        self.data_cache_maintenance(14, address, length)
+    }
+
+    fn invalidate_instruction_cache(&mut self) -> Result<(), Error> {
+        if self.state.is_64_bit {
+            // IC IALLU
+            self.execute_instruction(aarch64::build_sys(0, 7, 5, 0, 0))?;
+            self.execute_instruction(aarch64::build_dsb())?;
+            self.execute_instruction(aarch64::build_isb())?;
+        } else {
+            // ICIALLU - MCR p15, 0, r0, c7, c5, 0
+            self.execute_instruction(build_mcr(15, 0, 0, 7, 5, 0))?;
+
+            // BPIALL - MCR p15, 0, r0, c7, c5, 6
+            self.execute_instruction(build_mcr(15, 0, 0, 7, 5, 6))?;
+
+            self.execute_instruction(build_dsb())?;
+            self.execute_instruction(build_isb())?;
+        }
+
+        Ok(())
+    }
 }
 
 impl<'probe> MemoryInterface for Armv8a<'probe> {
@@ -1207,7 +1463,7 @@ impl<'probe> MemoryInterface for Armv8a<'probe> {
 
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
         for (i, byte) in data.iter().enumerate() {
-            self.write_word_8(address + ((i as u64) * 4), *byte)?;
+            self.write_word_8(address + (i as u64), *byte)?;
         }
 
         Ok(())
@@ -1218,8 +1474,9 @@ impl<'probe> MemoryInterface for Armv8a<'probe> {
     }
 
     fn flush(&mut self) -> Result<(), Error> {
-        // Nothing to do - this runs through the CPU which automatically handles any caching
-        Ok(())
+        // Data accesses run through the CPU which automatically handles the data cache, but
+        // the instruction cache has to be updated with any code written
+        self.synchronize_written_memory()
     }
 }
 
@@ -1278,6 +1535,18 @@ mod test {
         }
     }
 
+    impl Drop for MockProbe {
+        fn drop(&mut self) {
+            if !std::thread::panicking() {
+                assert!(
+                    self.expected_ops.is_empty(),
+                    "{} expected operations were not performed",
+                    self.expected_ops.len()
+                );
+            }
+        }
+    }
+
     impl ArmProbe for MockProbe {
         fn read_8(&mut self, _address: u64, _data: &mut [u8]) -> Result<(), ArmError> {
             todo!()
@@ -1491,6 +1760,8 @@ mod test {
     }
 
     fn add_halt_expectations(probe: &mut MockProbe) {
+        probe.expected_read(CtiGate::get_mmio_address(TEST_CTI_ADDRESS), 0);
+
         let mut cti_gate = CtiGate(0);
         cti_gate.set_en(0, 1);
 
@@ -1506,6 +1777,8 @@ mod test {
     }
 
     fn add_halt_cleanup_expectations(probe: &mut MockProbe) {
+        probe.expected_read(CtiGate::get_mmio_address(TEST_CTI_ADDRESS), 0);
+
         let cti_gate = CtiGate(0);
 
         probe.expected_write(CtiGate::get_mmio_address(TEST_CTI_ADDRESS), cti_gate.into());
@@ -1523,6 +1796,8 @@ mod test {
             status.into(),
         );
 
+        probe.expected_read(CtiGate::get_mmio_address(TEST_CTI_ADDRESS), 0);
+
         let mut cti_gate = CtiGate(0);
         cti_gate.set_en(1, 1);
         probe.expected_write(CtiGate::get_mmio_address(TEST_CTI_ADDRESS), cti_gate.into());
@@ -1540,6 +1815,8 @@ mod test {
     }
 
     fn add_resume_cleanup_expectations(probe: &mut MockProbe) {
+        probe.expected_read(CtiGate::get_mmio_address(TEST_CTI_ADDRESS), 0);
+
         let cti_gate = CtiGate(0);
         probe.expected_write(CtiGate::get_mmio_address(TEST_CTI_ADDRESS), cti_gate.into());
     }
@@ -2272,6 +2549,62 @@ mod test {
         assert_eq!(MEMORY_VALUE, armv8a.read_word_32(MEMORY_ADDRESS).unwrap());
     }
 
+    fn add_execute_expectations(probe: &mut MockProbe, instruction: u32) {
+        let mut edscr = Edscr(0);
+        edscr.set_ite(true);
+
+        probe.expected_write(Editr::get_mmio_address(TEST_BASE_ADDRESS), instruction);
+        probe.expected_read(Edscr::get_mmio_address(TEST_BASE_ADDRESS), edscr.into());
+    }
+
+    #[test]
+    fn armv8a_translate_address_aarch64() {
+        const VIRTUAL_ADDRESS: u64 = 0xFFFF_0000_1234_5678;
+        const SAVED_PAR: u64 = 0x0000_00AB_CDEF_0000;
+        const PAR: u64 = 0xFF00_0000_8765_4000;
+
+        let mut probe = MockProbe::new(true);
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+
+        // Save x0
+        add_read_reg_64_expectations(&mut probe, 0, 0);
+
+        // Save PAR_EL1
+        add_execute_expectations(&mut probe, aarch64::build_mrs(3, 0, 7, 4, 0, 0));
+        add_read_reg_64_expectations(&mut probe, 0, SAVED_PAR);
+
+        // Translate at EL1
+        add_set_x0_expectation(&mut probe, VIRTUAL_ADDRESS);
+        probe.expected_read(Edscr::get_mmio_address(TEST_BASE_ADDRESS), 1 << 8);
+        add_execute_expectations(&mut probe, aarch64::build_sys(0, 7, 8, 0, 0));
+        add_execute_expectations(&mut probe, aarch64::build_isb());
+        add_execute_expectations(&mut probe, aarch64::build_mrs(3, 0, 7, 4, 0, 0));
+        add_read_reg_64_expectations(&mut probe, 0, PAR);
+
+        // Restore PAR_EL1
+        add_set_x0_expectation(&mut probe, SAVED_PAR);
+        add_execute_expectations(&mut probe, aarch64::build_msr(3, 0, 7, 4, 0, 0));
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv8a = Armv8a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            TEST_CTI_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            0x8765_4678,
+            armv8a.translate_address(VIRTUAL_ADDRESS).unwrap()
+        );
+    }
+
     #[test]
     fn armv8a_read_word_8() {
         const MEMORY_VALUE: u32 = 0xBA5EBA11;
@@ -2333,4 +2666,168 @@ mod test {
 
         assert_eq!(0xBA, armv8a.read_word_8(MEMORY_ADDRESS).unwrap());
     }
+
+    #[test]
+    fn armv8a_clean_data_cache_aarch64() {
+        // 8 words per cache line
+        const CTR_VALUE: u64 = 3 << 16;
+        const MEMORY_ADDRESS: u64 = 0xFFFF_0000_1234_5678;
+
+        let mut probe = MockProbe::new(true);
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+
+        // Save x0
+        add_read_reg_64_expectations(&mut probe, 0, 0);
+
+        // Read CTR_EL0
+        add_execute_expectations(&mut probe, aarch64::build_mrs(3, 3, 0, 0, 1, 0));
+        add_read_reg_64_expectations(&mut probe, 0, CTR_VALUE);
+
+        // Clean both cache lines covering the range - DC CVAC, X0
+        add_set_x0_expectation(&mut probe, 0xFFFF_0000_1234_5660);
+        add_execute_expectations(&mut probe, aarch64::build_sys(3, 7, 10, 1, 0));
+        add_set_x0_expectation(&mut probe, 0xFFFF_0000_1234_5680);
+        add_execute_expectations(&mut probe, aarch64::build_sys(3, 7, 10, 1, 0));
+        add_execute_expectations(&mut probe, aarch64::build_dsb());
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv8a = Armv8a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            TEST_CTI_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        armv8a.clean_data_cache(MEMORY_ADDRESS, 16).unwrap();
+    }
+
+    /// Expectations for making the written memory visible to instruction fetches, with 8 words
+    /// per cache line. x0 is saved before it's clobbered, and written back afterwards.
+    fn add_synchronize_written_memory_aarch64_expectations(probe: &mut MockProbe, lines: &[u64]) {
+        // Save x0
+        add_read_reg_64_expectations(probe, 0, 0);
+
+        // Read CTR_EL0
+        add_execute_expectations(probe, aarch64::build_mrs(3, 3, 0, 0, 1, 0));
+        add_read_reg_64_expectations(probe, 0, 3 << 16);
+
+        // Clean to the point of unification - DC CVAU, X0
+        for line in lines {
+            add_set_x0_expectation(probe, *line);
+            add_execute_expectations(probe, aarch64::build_sys(3, 7, 11, 1, 0));
+        }
+        add_execute_expectations(probe, aarch64::build_dsb());
+
+        // IC IALLU
+        add_execute_expectations(probe, aarch64::build_sys(0, 7, 5, 0, 0));
+        add_execute_expectations(probe, aarch64::build_dsb());
+        add_execute_expectations(probe, aarch64::build_isb());
+
+        // Restore x0
+        add_set_x0_expectation(probe, 0);
+    }
+
+    #[test]
+    fn armv8a_run_synchronizes_written_memory() {
+        let mut probe = MockProbe::new(true);
+        let mut state = CortexAState::new();
+
+        // A write to code, and a distant one to the stack.
+        state.record_memory_write(0x8000_0004, 4);
+        state.record_memory_write(0xFFFF_0000_2000_0FF8, 8);
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+
+        // Only the written cache lines are cleaned, not the memory in between.
+        add_synchronize_written_memory_aarch64_expectations(
+            &mut probe,
+            &[0x8000_0000, 0xFFFF_0000_2000_0FE0],
+        );
+
+        // Write resume request
+        add_resume_expectations(&mut probe);
+
+        // Read status
+        add_status_expectations(&mut probe, false);
+
+        add_resume_cleanup_expectations(&mut probe);
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv8a = Armv8a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            TEST_CTI_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        armv8a.run().unwrap();
+        drop(armv8a);
+
+        assert!(state.written_memory.is_empty());
+    }
+
+    #[test]
+    fn armv8a_step_synchronizes_written_memory() {
+        const PC_VALUE: u64 = 0x8000_0004;
+
+        let mut probe = MockProbe::new(true);
+        let mut state = CortexAState::new();
+
+        state.record_memory_write(0x8000_0004, 4);
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+
+        // Enable single stepping
+        probe.expected_read(Edecr::get_mmio_address(TEST_BASE_ADDRESS), 0);
+        let mut edecr = Edecr(0);
+        edecr.set_ss(true);
+        probe.expected_write(Edecr::get_mmio_address(TEST_BASE_ADDRESS), edecr.into());
+
+        add_synchronize_written_memory_aarch64_expectations(&mut probe, &[0x8000_0000]);
+
+        // Write resume request
+        add_resume_expectations(&mut probe);
+        add_status_expectations(&mut probe, false);
+        add_resume_cleanup_expectations(&mut probe);
+
+        // Wait for halted
+        add_status_expectations(&mut probe, true);
+
+        // Disable single stepping
+        probe.expected_write(Edecr::get_mmio_address(TEST_BASE_ADDRESS), 0);
+
+        // Read status
+        add_status_expectations(&mut probe, true);
+
+        // Read PC
+        add_read_reg_64_expectations(&mut probe, 0, 0);
+        add_read_pc_64_expectations(&mut probe, PC_VALUE);
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv8a = Armv8a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            TEST_CTI_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        assert_eq!(PC_VALUE, armv8a.step().unwrap().pc);
+        drop(armv8a);
+
+        assert!(state.written_memory.is_empty());
+    }
 }
@@ -6,6 +6,7 @@ use crate::architecture::arm::ArmError;
 use crate::core::RegisterFile;
 use crate::error::Error;
 use crate::memory::valid_32bit_address;
+use crate::SecurityState;
 use crate::{architecture::arm::core::register, CoreStatus, HaltReason, MemoryInterface};
 use crate::{Architecture, CoreInformation};
 use crate::{CoreInterface, CoreType, InstructionSet, MemoryMappedRegister};
@@ -15,7 +16,10 @@ use anyhow::Result;
 use bitfield::bitfield;
 
 use super::cortex_m::Mvfr0;
-use super::{CortexMState, Dfsr, CORTEX_M_COMMON_REGS, CORTEX_M_WITH_FP_REGS};
+use super::{
+    CortexMState, Dfsr, ARMV8M_SECURITY_REGS, ARMV8M_SECURITY_WITH_FP_REGS, CORTEX_M_COMMON_REGS,
+    CORTEX_M_WITH_FP_REGS,
+};
 use std::sync::Arc;
 use std::{
     mem::size_of,
@@ -65,6 +69,8 @@ impl<'probe> Armv8m<'probe> {
 
             state.current_state = core_state;
             state.fp_present = Mvfr0(memory.read_word_32(Mvfr0::ADDRESS)?).fp_present();
+            state.security_extension =
+                IdPfr1(memory.read_word_32(IdPfr1::ADDRESS)?).security() != 0;
 
             state.initialize();
         }
@@ -75,6 +81,28 @@ impl<'probe> Armv8m<'probe> {
             sequence,
         })
     }
+
+    /// Run a memory access with the security state selected by
+    /// [`CoreInterface::set_memory_security_state`].
+    ///
+    /// Accesses to the debug registers of the core are done outside of this, and always
+    /// use the default security state of the access port.
+    fn with_memory_security_state<T>(
+        &mut self,
+        access: impl FnOnce(&mut (dyn ArmProbe + 'probe)) -> Result<T, ArmError>,
+    ) -> Result<T, Error> {
+        let security_state = self.state.memory_security_state;
+
+        if security_state.is_none() {
+            return Ok(access(&mut *self.memory)?);
+        }
+
+        self.memory.set_security_state(security_state)?;
+        let result = access(&mut *self.memory);
+        self.memory.set_security_state(None)?;
+
+        Ok(result?)
+    }
 }
 
 impl<'probe> CoreInterface for Armv8m<'probe> {
@@ -116,6 +144,7 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: self.security_state()?,
         })
     }
 
@@ -170,6 +199,7 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         // get pc
         Ok(CoreInformation {
             pc: pc_value.try_into()?,
+            security_state: self.security_state()?,
         })
     }
 
@@ -221,6 +251,7 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
 
         Ok(CoreInformation {
             pc: pc_after_step.try_into()?,
+            security_state: self.security_state()?,
         })
     }
 
@@ -282,10 +313,11 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
     }
 
     fn registers(&self) -> &'static RegisterFile {
-        if self.state.fp_present {
-            &CORTEX_M_WITH_FP_REGS
-        } else {
-            &CORTEX_M_COMMON_REGS
+        match (self.state.fp_present, self.state.security_extension) {
+            (true, true) => &ARMV8M_SECURITY_WITH_FP_REGS,
+            (true, false) => &CORTEX_M_WITH_FP_REGS,
+            (false, true) => &ARMV8M_SECURITY_REGS,
+            (false, false) => &CORTEX_M_COMMON_REGS,
         }
     }
 
@@ -407,6 +439,30 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
     fn fpu_support(&mut self) -> Result<bool, crate::error::Error> {
         Ok(self.state.fp_present)
     }
+
+    fn security_state(&mut self) -> Result<Option<SecurityState>, Error> {
+        if !self.state.security_extension {
+            return Ok(None);
+        }
+
+        let dscsr = Dscsr(self.memory.read_word_32(Dscsr::ADDRESS)?);
+
+        Ok(Some(if dscsr.cds() {
+            SecurityState::Secure
+        } else {
+            SecurityState::NonSecure
+        }))
+    }
+
+    fn set_memory_security_state(&mut self, state: Option<SecurityState>) -> Result<(), Error> {
+        // Check that the access port can issue accesses in the requested state.
+        self.memory.set_security_state(state)?;
+        self.memory.set_security_state(None)?;
+
+        self.state.memory_security_state = state;
+
+        Ok(())
+    }
 }
 
 impl<'probe> MemoryInterface for Armv8m<'probe> {
@@ -414,80 +470,54 @@ impl<'probe> MemoryInterface for Armv8m<'probe> {
         self.memory.supports_native_64bit_access()
     }
     fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
-        self.memory
-            .read_word_32(address)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.read_word_32(address))
     }
     fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
-        self.memory
-            .read_word_8(address)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.read_word_8(address))
     }
 
     fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), crate::error::Error> {
-        self.memory
-            .read_64(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.read_64(address, data))
     }
 
     fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
-        self.memory
-            .read_32(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.read_32(address, data))
     }
 
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
-        self.memory
-            .read_8(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.read_8(address, data))
     }
 
     fn read_word_64(&mut self, address: u64) -> Result<u64, crate::error::Error> {
-        self.memory
-            .read_word_64(address)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.read_word_64(address))
     }
 
     fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), crate::error::Error> {
-        self.memory
-            .write_word_64(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.write_word_64(address, data))
     }
 
     fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), Error> {
-        self.memory
-            .write_word_32(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.write_word_32(address, data))
     }
 
     fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
-        self.memory
-            .write_word_8(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.write_word_8(address, data))
     }
 
     fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), crate::error::Error> {
-        self.memory
-            .write_64(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.write_64(address, data))
     }
 
     fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), Error> {
-        self.memory
-            .write_32(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.write_32(address, data))
     }
 
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
-        self.memory
-            .write_8(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.write_8(address, data))
     }
 
     fn write(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
-        self.memory
-            .write(address, data)
-            .map_err(From::<ArmError>::from)
+        self.with_memory_security_state(|memory| memory.write(address, data))
     }
 
     fn supports_8bit_transfers(&self) -> Result<bool, Error> {
@@ -1026,6 +1056,70 @@ bitfield! {
     pub enable, set_enable: 0;
 }
 
+bitfield! {
+    /// ID_PFR1, Processor Feature Register 1 (see armv8-M Architecture Reference Manual D1.2.93)
+    #[derive(Copy, Clone)]
+    pub struct IdPfr1(u32);
+    impl Debug;
+    /// Security, bits[7:4] Identifies whether the Security Extension is implemented.
+    /// 0b0000 Security Extension not implemented.
+    /// 0b0001 Security Extension implemented.
+    /// 0b0011 Security Extension implemented with state handling instructions.
+    pub security, _: 7, 4;
+}
+
+impl MemoryMappedRegister for IdPfr1 {
+    const ADDRESS: u64 = 0xE000_ED44;
+    const NAME: &'static str = "ID_PFR1";
+}
+
+impl From<u32> for IdPfr1 {
+    fn from(value: u32) -> Self {
+        IdPfr1(value)
+    }
+}
+
+impl From<IdPfr1> for u32 {
+    fn from(value: IdPfr1) -> Self {
+        value.0
+    }
+}
+
+bitfield! {
+    /// DSCSR, Debug Security Control and Status Register (see armv8-M Architecture Reference Manual D1.2.49)
+    #[derive(Copy, Clone)]
+    pub struct Dscsr(u32);
+    impl Debug;
+    /// CDSKEY, bit[17] Writes to the CDS bit are ignored unless CDSKEY is concurrently written to zero.
+    pub cdskey, set_cdskey: 17;
+    /// CDS, bit[16] Current domain Secure. Indicates the current Security state of the PE.
+    /// 0 PE is in Non-secure state.
+    /// 1 PE is in Secure state.
+    pub cds, set_cds: 16;
+    /// SBRSEL, bit[1] Secure banked register select. Selects which banked registers are
+    /// accessed by DCRSR when SBRSELEN is set.
+    pub sbrsel, set_sbrsel: 1;
+    /// SBRSELEN, bit[0] Secure banked register select enable.
+    pub sbrselen, set_sbrselen: 0;
+}
+
+impl MemoryMappedRegister for Dscsr {
+    const ADDRESS: u64 = 0xE000_EE08;
+    const NAME: &'static str = "DSCSR";
+}
+
+impl From<u32> for Dscsr {
+    fn from(value: u32) -> Self {
+        Dscsr(value)
+    }
+}
+
+impl From<Dscsr> for u32 {
+    fn from(value: Dscsr) -> Self {
+        value.0
+    }
+}
+
 impl MemoryMappedRegister for FpCompN {
     const ADDRESS: u64 = 0xE000_2008;
     const NAME: &'static str = "FP_COMPn";
@@ -1042,3 +1136,257 @@ impl From<FpCompN> for u32 {
         value.0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        architecture::arm::{
+            ap::MemoryAp, communication_interface::SwdSequence, core::cortex_m::Dcrsr,
+            sequences::DefaultArmSequence,
+        },
+        DebugProbeError,
+    };
+
+    const TEST_ADDRESS: u64 = 0x2000_0000;
+
+    /// The registers of the mock, and the accesses to them.
+    #[derive(Default)]
+    struct MockState {
+        registers: HashMap<u64, u32>,
+        supports_hnonsec: bool,
+        security_state: Option<SecurityState>,
+        /// The security state of each access to [`TEST_ADDRESS`].
+        test_accesses: Vec<Option<SecurityState>>,
+    }
+
+    impl MockState {
+        fn new(security_extension: bool) -> Self {
+            let mut registers = HashMap::new();
+            // Halted, with the register transfers always ready.
+            registers.insert(Dhcsr::ADDRESS, 0x0003_0000);
+            registers.insert(
+                IdPfr1::ADDRESS,
+                if security_extension { 0b0011 << 4 } else { 0 },
+            );
+
+            MockState {
+                registers,
+                ..Default::default()
+            }
+        }
+    }
+
+    struct MockProbe<'state>(&'state mut MockState);
+
+    impl ArmProbe for MockProbe<'_> {
+        fn read_8(&mut self, _address: u64, _data: &mut [u8]) -> Result<(), ArmError> {
+            todo!()
+        }
+
+        fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), ArmError> {
+            assert_eq!(data.len(), 1);
+
+            if address == TEST_ADDRESS {
+                self.0.test_accesses.push(self.0.security_state);
+            }
+            data[0] = self.0.registers.get(&address).copied().unwrap_or(0);
+
+            Ok(())
+        }
+
+        fn write_8(&mut self, _address: u64, _data: &[u8]) -> Result<(), ArmError> {
+            todo!()
+        }
+
+        fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), ArmError> {
+            assert_eq!(data.len(), 1);
+
+            if address == TEST_ADDRESS {
+                self.0.test_accesses.push(self.0.security_state);
+            }
+            self.0.registers.insert(address, data[0]);
+
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), ArmError> {
+            Ok(())
+        }
+
+        fn ap(&mut self) -> MemoryAp {
+            todo!()
+        }
+
+        fn set_security_state(&mut self, state: Option<SecurityState>) -> Result<(), ArmError> {
+            if state.is_some() && !self.0.supports_hnonsec {
+                return Err(ArmError::SecurityStateNotSupported);
+            }
+
+            self.0.security_state = state;
+
+            Ok(())
+        }
+
+        fn get_arm_communication_interface(
+            &mut self,
+        ) -> Result<
+            &mut crate::architecture::arm::ArmCommunicationInterface<
+                crate::architecture::arm::communication_interface::Initialized,
+            >,
+            DebugProbeError,
+        > {
+            todo!()
+        }
+
+        fn read_64(&mut self, _address: u64, _data: &mut [u64]) -> Result<(), ArmError> {
+            todo!()
+        }
+
+        fn write_64(&mut self, _address: u64, _data: &[u64]) -> Result<(), ArmError> {
+            todo!()
+        }
+
+        fn supports_8bit_transfers(&self) -> Result<bool, ArmError> {
+            Ok(false)
+        }
+
+        fn supports_native_64bit_access(&mut self) -> bool {
+            false
+        }
+    }
+
+    impl SwdSequence for MockProbe<'_> {
+        fn swj_sequence(&mut self, _bit_len: u8, _bits: u64) -> Result<(), DebugProbeError> {
+            todo!()
+        }
+
+        fn swj_pins(
+            &mut self,
+            _pin_out: u32,
+            _pin_select: u32,
+            _pin_wait: u32,
+        ) -> Result<u32, DebugProbeError> {
+            todo!()
+        }
+    }
+
+    fn armv8m<'probe>(
+        mock: &'probe mut MockState,
+        state: &'probe mut CortexMState,
+    ) -> Armv8m<'probe> {
+        Armv8m::new(
+            Box::new(MockProbe(mock)),
+            state,
+            DefaultArmSequence::create(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn security_state_from_dscsr() {
+        let mut mock = MockState::new(true);
+        let mut state = CortexMState::new();
+
+        let mut dscsr = Dscsr(0);
+        dscsr.set_cds(true);
+        mock.registers.insert(Dscsr::ADDRESS, dscsr.0);
+        assert_eq!(
+            armv8m(&mut mock, &mut state).security_state().unwrap(),
+            Some(SecurityState::Secure)
+        );
+
+        dscsr.set_cds(false);
+        mock.registers.insert(Dscsr::ADDRESS, dscsr.0);
+        assert_eq!(
+            armv8m(&mut mock, &mut state).security_state().unwrap(),
+            Some(SecurityState::NonSecure)
+        );
+    }
+
+    #[test]
+    fn security_state_without_security_extension() {
+        let mut mock = MockState::new(false);
+        let mut state = CortexMState::new();
+
+        assert_eq!(
+            armv8m(&mut mock, &mut state).security_state().unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn memory_accesses_use_selected_security_state() {
+        let mut mock = MockState::new(true);
+        mock.supports_hnonsec = true;
+        let mut state = CortexMState::new();
+
+        let mut core = armv8m(&mut mock, &mut state);
+        core.write_word_32(TEST_ADDRESS, 1).unwrap();
+        core.set_memory_security_state(Some(SecurityState::NonSecure))
+            .unwrap();
+        core.read_word_32(TEST_ADDRESS).unwrap();
+        core.set_memory_security_state(Some(SecurityState::Secure))
+            .unwrap();
+        core.write_word_32(TEST_ADDRESS, 2).unwrap();
+        // Debug registers are accessed in the default state.
+        core.security_state().unwrap();
+        drop(core);
+
+        assert_eq!(
+            mock.test_accesses,
+            [
+                None,
+                Some(SecurityState::NonSecure),
+                Some(SecurityState::Secure)
+            ]
+        );
+        assert_eq!(mock.security_state, None);
+        assert_eq!(state.memory_security_state, Some(SecurityState::Secure));
+    }
+
+    #[test]
+    fn memory_security_state_requires_hnonsec() {
+        let mut mock = MockState::new(true);
+        let mut state = CortexMState::new();
+
+        let mut core = armv8m(&mut mock, &mut state);
+        assert!(matches!(
+            core.set_memory_security_state(Some(SecurityState::NonSecure)),
+            Err(Error::Arm(ArmError::SecurityStateNotSupported))
+        ));
+        core.write_word_32(TEST_ADDRESS, 1).unwrap();
+        drop(core);
+
+        assert_eq!(mock.test_accesses, [None]);
+        assert_eq!(state.memory_security_state, None);
+    }
+
+    #[test]
+    fn banked_registers() {
+        let mut mock = MockState::new(true);
+        let mut state = CortexMState::new();
+
+        let core = armv8m(&mut mock, &mut state);
+        let other = core.registers().other;
+        drop(core);
+
+        for (register, regsel) in [
+            (register::MSP_NS, 0b001_1000),
+            (register::PSP_NS, 0b001_1001),
+            (register::MSP_S, 0b001_1010),
+            (register::PSP_S, 0b001_1011),
+            (register::EXTRA_S, 0b010_0010),
+            (register::EXTRA_NS, 0b010_0011),
+        ] {
+            assert!(other.iter().any(|r| r.id == register.id));
+
+            armv8m(&mut mock, &mut state)
+                .read_core_reg(register.id)
+                .unwrap();
+            assert_eq!(mock.registers[&Dcrsr::ADDRESS], regsel);
+        }
+    }
+}
@@ -109,6 +109,16 @@ pub(crate) mod aarch32 {
         ret
     }
 
+    /// Build an ISB instruction
+    pub(crate) fn build_isb() -> u32 {
+        0b1111_0101_0111_1111_1111_0000_0110_1111
+    }
+
+    /// Build a DSB instruction
+    pub(crate) fn build_dsb() -> u32 {
+        0b1111_0101_0111_1111_1111_0000_0100_1111
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -184,6 +194,22 @@ pub(crate) mod aarch32 {
             // VMOV r1, r2, d3
             assert_eq!(0xEC521B13, instr);
         }
+
+        #[test]
+        fn gen_isb_instruction() {
+            let instr = build_isb();
+
+            // ISB SY
+            assert_eq!(0xF57FF06F, instr);
+        }
+
+        #[test]
+        fn gen_dsb_instruction() {
+            let instr = build_dsb();
+
+            // DSB SY
+            assert_eq!(0xF57FF04F, instr);
+        }
     }
 }
 
@@ -211,6 +237,16 @@ pub(crate) mod thumb2 {
         ret
     }
 
+    /// Build an ISB instruction
+    pub(crate) fn build_isb() -> u32 {
+        0b1111_0011_1011_1111_1000_1111_0110_1111
+    }
+
+    /// Build a DSB instruction
+    pub(crate) fn build_dsb() -> u32 {
+        0b1111_0011_1011_1111_1000_1111_0100_1111
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -230,6 +266,22 @@ pub(crate) mod thumb2 {
             // STR r2, [r3], #4
             assert_eq!(0xF8432B04, instr);
         }
+
+        #[test]
+        fn gen_isb_instruction() {
+            let instr = build_isb();
+
+            // ISB SY
+            assert_eq!(0xF3BF8F6F, instr);
+        }
+
+        #[test]
+        fn gen_dsb_instruction() {
+            let instr = build_dsb();
+
+            // DSB SY
+            assert_eq!(0xF3BF8F4F, instr);
+        }
     }
 }
 
@@ -280,6 +332,29 @@ pub(crate) mod aarch64 {
         ret
     }
 
+    /// Build a SYS instruction, e.g. for the address translation instructions
+    pub(crate) fn build_sys(op1: u8, crn: u8, crm: u8, op2: u8, reg: u16) -> u32 {
+        let mut ret = 0b1101_0101_0000_1000_0000_0000_0000_0000;
+
+        ret |= (op1 as u32) << 16;
+        ret |= (crn as u32) << 12;
+        ret |= (crm as u32) << 8;
+        ret |= (op2 as u32) << 5;
+        ret |= reg as u32;
+
+        ret
+    }
+
+    /// Build an ISB instruction
+    pub(crate) fn build_isb() -> u32 {
+        0b1101_0101_0000_0011_0011_1111_1101_1111
+    }
+
+    /// Build a DSB instruction
+    pub(crate) fn build_dsb() -> u32 {
+        0b1101_0101_0000_0011_0011_1111_1001_1111
+    }
+
     pub(crate) fn build_str(reg_target: u16, reg_source: u16, imm: u16) -> u32 {
         let mut ret = 0b1111_1000_0000_0000_0000_0100_0000_0000;
 
@@ -355,6 +430,38 @@ pub(crate) mod aarch64 {
             assert_eq!(0xD5334143, instr);
         }
 
+        #[test]
+        fn gen_sys_instruction() {
+            let instr = build_sys(0, 7, 8, 0, 2);
+
+            // AT S1E1R, x2
+            assert_eq!(0xD5087802, instr);
+        }
+
+        #[test]
+        fn gen_isb_instruction() {
+            let instr = build_isb();
+
+            // ISB SY
+            assert_eq!(0xD5033FDF, instr);
+        }
+
+        #[test]
+        fn gen_dsb_instruction() {
+            let instr = build_dsb();
+
+            // DSB SY
+            assert_eq!(0xD5033F9F, instr);
+        }
+
+        #[test]
+        fn gen_dc_civac_instruction() {
+            let instr = build_sys(3, 7, 14, 1, 0);
+
+            // DC CIVAC, x0
+            assert_eq!(0xD50B7E20, instr);
+        }
+
         #[test]
         fn gen_str_instruction() {
             let instr = build_str(2, 3, 4);
@@ -3,10 +3,11 @@ use crate::{
         BreakpointCause, MemoryMappedRegister, RegisterDataType, RegisterDescription, RegisterFile,
         RegisterId, RegisterKind, RegisterValue,
     },
-    CoreStatus, HaltReason,
+    CoreStatus, HaltReason, SecurityState,
 };
 
 use bitfield::bitfield;
+use std::ops::Range;
 
 pub mod armv6m;
 pub mod armv7a;
@@ -112,6 +113,58 @@ pub(crate) mod register {
         size_in_bits: 32,
     };
 
+    // Banked stack pointers and special registers of ARMv8-M cores with the
+    // security extension.
+    pub const MSP_NS: RegisterDescription = RegisterDescription {
+        name: "MSP_NS",
+        _kind: RegisterKind::General,
+        id: RegisterId(0b11000),
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    };
+
+    pub const PSP_NS: RegisterDescription = RegisterDescription {
+        name: "PSP_NS",
+        _kind: RegisterKind::General,
+        id: RegisterId(0b11001),
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    };
+
+    pub const MSP_S: RegisterDescription = RegisterDescription {
+        name: "MSP_S",
+        _kind: RegisterKind::General,
+        id: RegisterId(0b11010),
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    };
+
+    pub const PSP_S: RegisterDescription = RegisterDescription {
+        name: "PSP_S",
+        _kind: RegisterKind::General,
+        id: RegisterId(0b11011),
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    };
+
+    // CONTROL, FAULTMASK, BASEPRI and PRIMASK of the secure state, laid out as EXTRA.
+    pub const EXTRA_S: RegisterDescription = RegisterDescription {
+        name: "EXTRA_S",
+        _kind: RegisterKind::General,
+        id: RegisterId(0b10_0010),
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    };
+
+    // CONTROL, FAULTMASK, BASEPRI and PRIMASK of the non-secure state, laid out as EXTRA.
+    pub const EXTRA_NS: RegisterDescription = RegisterDescription {
+        name: "EXTRA_NS",
+        _kind: RegisterKind::General,
+        id: RegisterId(0b10_0011),
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    };
+
     pub const FP: RegisterDescription = RegisterDescription {
         name: "FP",
         _kind: RegisterKind::General,
@@ -682,6 +735,22 @@ static CORTEX_M_COMMON_REGS: RegisterFile = RegisterFile {
     ..ARM32_COMMON_REGS
 };
 
+static ARMV8M_SECURITY_OTHER_REGS: &[RegisterDescription] = &[
+    register::EXTRA,
+    register::MSP_NS,
+    register::PSP_NS,
+    register::MSP_S,
+    register::PSP_S,
+    register::EXTRA_NS,
+    register::EXTRA_S,
+];
+
+static ARMV8M_SECURITY_REGS: RegisterFile = RegisterFile {
+    other: ARMV8M_SECURITY_OTHER_REGS,
+
+    ..CORTEX_M_COMMON_REGS
+};
+
 static CORTEX_M_WITH_FP_REGS: RegisterFile = RegisterFile {
     fp_status: Some(&register::FPSCR),
     fp_registers: Some(&[
@@ -914,6 +983,12 @@ static CORTEX_M_WITH_FP_REGS: RegisterFile = RegisterFile {
     ..CORTEX_M_COMMON_REGS
 };
 
+static ARMV8M_SECURITY_WITH_FP_REGS: RegisterFile = RegisterFile {
+    other: ARMV8M_SECURITY_OTHER_REGS,
+
+    ..CORTEX_M_WITH_FP_REGS
+};
+
 bitfield! {
     #[derive(Copy, Clone)]
     pub struct Dfsr(u32);
@@ -997,6 +1072,12 @@ pub struct CortexMState {
     current_state: CoreStatus,
 
     fp_present: bool,
+
+    /// Whether the core implements the ARMv8-M security extension.
+    security_extension: bool,
+
+    /// The security state selected for memory accesses through the core.
+    memory_security_state: Option<SecurityState>,
 }
 
 impl CortexMState {
@@ -1006,6 +1087,8 @@ impl CortexMState {
             hw_breakpoints_enabled: false,
             current_state: CoreStatus::Unknown,
             fp_present: false,
+            security_extension: false,
+            memory_security_state: None,
         }
     }
 
@@ -1016,6 +1099,11 @@ impl CortexMState {
     fn initialized(&self) -> bool {
         self.initialized
     }
+
+    /// The security state selected for memory accesses through the core.
+    pub(crate) fn memory_security_state(&self) -> Option<SecurityState> {
+        self.memory_security_state
+    }
 }
 
 #[derive(Debug)]
@@ -1031,6 +1119,67 @@ pub struct CortexAState {
 
     // Number of floating point registers
     fp_reg_count: Option<usize>,
+
+    // Memory written through the core since it was halted, which has to be made visible to
+    // instruction fetches before the core is resumed
+    written_memory: WrittenMemory,
+}
+
+/// The memory written through a Cortex-A core.
+///
+/// Writes are kept as a sorted list of disjoint ranges, instead of a single range covering all
+/// of them, so that the cache maintenance for writes to distant regions, e.g. code and stack,
+/// doesn't have to cover the memory in between.
+#[derive(Debug, Default)]
+struct WrittenMemory {
+    ranges: Vec<Range<u64>>,
+}
+
+impl WrittenMemory {
+    /// Record a write, merging it with any overlapping or adjacent ranges.
+    fn record(&mut self, address: u64, length: u64) {
+        if length == 0 {
+            return;
+        }
+
+        let mut range = address..address.saturating_add(length);
+
+        // The ranges which overlap or touch the new one.
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+
+        if first < last {
+            range.start = range.start.min(self.ranges[first].start);
+            range.end = range.end.max(self.ranges[last - 1].end);
+        }
+
+        self.ranges.splice(first..last, [range]);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    /// The written memory, with the start of every range aligned to a cache line, and ranges
+    /// sharing a cache line merged.
+    fn line_ranges(&self, line_size: u64) -> Vec<Range<u64>> {
+        let mut lines: Vec<Range<u64>> = Vec::with_capacity(self.ranges.len());
+
+        for range in &self.ranges {
+            let start = range.start & !(line_size - 1);
+
+            match lines.last_mut() {
+                Some(last) if start <= last.end => last.end = range.end,
+                _ => lines.push(start..range.end),
+            }
+        }
+
+        lines
+    }
 }
 
 impl CortexAState {
@@ -1041,9 +1190,15 @@ impl CortexAState {
             is_64_bit: false,
             register_cache: vec![],
             fp_reg_count: None,
+            written_memory: WrittenMemory::default(),
         }
     }
 
+    /// Record that memory was written through the core.
+    fn record_memory_write(&mut self, address: u64, length: u64) {
+        self.written_memory.record(address, length);
+    }
+
     fn initialize(&mut self) {
         self.initialized = true;
     }
@@ -1052,3 +1207,63 @@ impl CortexAState {
         self.initialized
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::WrittenMemory;
+
+    #[test]
+    fn written_memory_merges_adjacent_writes() {
+        let mut memory = WrittenMemory::default();
+
+        memory.record(0x1004, 4);
+        memory.record(0x1000, 4);
+        memory.record(0x1008, 8);
+        memory.record(0x1002, 4);
+
+        assert_eq!(memory.ranges.len(), 1);
+        assert_eq!(memory.ranges[0], 0x1000..0x1010);
+    }
+
+    #[test]
+    fn written_memory_keeps_distant_writes_apart() {
+        let mut memory = WrittenMemory::default();
+
+        memory.record(0x2000_0ff8, 8);
+        memory.record(0x0800_0000, 4);
+        memory.record(0x1000, 4);
+        memory.record(0x0800_0004, 4);
+
+        assert_eq!(
+            memory.ranges,
+            [
+                0x1000..0x1004,
+                0x0800_0000..0x0800_0008,
+                0x2000_0ff8..0x2000_1000
+            ]
+        );
+
+        // A write bridging two ranges merges them.
+        memory.record(0x1004, 0x0800_0000 - 0x1004);
+        assert_eq!(
+            memory.ranges,
+            [0x1000..0x0800_0008, 0x2000_0ff8..0x2000_1000]
+        );
+    }
+
+    #[test]
+    fn written_memory_line_ranges() {
+        let mut memory = WrittenMemory::default();
+
+        memory.record(0x1004, 4);
+        memory.record(0x1010, 4);
+        memory.record(0x1044, 4);
+        memory.record(0x8000, 4);
+
+        // The first two writes share a cache line.
+        assert_eq!(
+            memory.line_ranges(32),
+            [0x1000..0x1014, 0x1040..0x1048, 0x8000..0x8004]
+        );
+    }
+}
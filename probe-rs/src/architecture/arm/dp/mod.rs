@@ -2,6 +2,7 @@
 
 #[macro_use]
 mod register_generation;
+mod multidrop;
 
 use super::{
     communication_interface::RegisterParseError, ArmError, DapAccess, DapError, DpAddress, Register,
@@ -12,6 +13,9 @@ use jep106::JEP106Code;
 use crate::DebugProbeError;
 use std::fmt::Display;
 
+pub(crate) use multidrop::{discover_multidrop_dps, select_multidrop_dp};
+pub use multidrop::{targetsel_candidates, MultidropDp};
+
 /// An error occurred when interacting with the debug port.
 #[derive(thiserror::Error, Debug)]
 pub enum DebugPortError {
@@ -363,6 +367,143 @@ impl Register for Select {
     const NAME: &'static str = "SELECT";
 }
 
+bitfield! {
+    /// SELECT, AP Select register of a DPv3 debug port (see ADI v6.0 B2.2.11)
+    ///
+    /// In DPv3, the access ports are mapped into the address space of the debug port,
+    /// and SELECT holds the lower bits of the address accessed by AP transactions.
+    #[derive(Clone)]
+    pub struct SelectV3(u32);
+    impl Debug;
+    /// Bits `[31:4]` of the address accessed by AP transactions. Bits `[3:2]` of the
+    /// address are taken from the AP transaction.
+    pub u32, addr, set_addr: 31, 4;
+    /// Debug Port address bank select, see [`Select::dp_bank_sel`].
+    ///
+    /// In DPv3, the DP registers at address 0x0 and 0x4 are banked.
+    pub u8, dp_bank_sel, set_dp_bank_sel: 3, 0;
+}
+
+impl TryFrom<u32> for SelectV3 {
+    type Error = RegisterParseError;
+
+    fn try_from(raw: u32) -> Result<Self, Self::Error> {
+        Ok(SelectV3(raw))
+    }
+}
+
+impl From<SelectV3> for u32 {
+    fn from(raw: SelectV3) -> Self {
+        raw.0
+    }
+}
+
+impl DpRegister for SelectV3 {
+    const VERSION: DebugPortVersion = DebugPortVersion::DPv3;
+}
+
+impl Register for SelectV3 {
+    const ADDRESS: u8 = 0x8;
+    const NAME: &'static str = "SELECT";
+}
+
+/// SELECT1, AP Select register 1 (see ADI v6.0 B2.2.12)
+///
+/// Holds bits `[63:32]` of the address accessed by AP transactions on a DPv3 debug port
+/// which supports addresses larger than 32 bits.
+#[derive(Debug, Clone)]
+pub struct Select1(pub u32);
+
+impl TryFrom<u32> for Select1 {
+    type Error = RegisterParseError;
+
+    fn try_from(raw: u32) -> Result<Self, Self::Error> {
+        Ok(Select1(raw))
+    }
+}
+
+impl From<Select1> for u32 {
+    fn from(raw: Select1) -> Self {
+        raw.0
+    }
+}
+
+impl DpRegister for Select1 {
+    const VERSION: DebugPortVersion = DebugPortVersion::DPv3;
+}
+
+impl Register for Select1 {
+    const ADDRESS: u8 = 0x54;
+    const NAME: &'static str = "SELECT1";
+}
+
+bitfield! {
+    /// BASEPTR0, Base Pointer register 0 (see ADI v6.0 B2.2.1)
+    ///
+    /// Points to the root component of the address space of a DPv3 debug port,
+    /// which is usually a ROM table describing the access ports.
+    #[derive(Clone)]
+    pub struct BASEPTR0(u32);
+    impl Debug;
+    /// Bits `[31:12]` of the address of the root component.
+    pub u32, ptr, _: 31, 12;
+    /// Indicates if the base pointer is valid.
+    pub valid, _: 0;
+}
+
+impl TryFrom<u32> for BASEPTR0 {
+    type Error = RegisterParseError;
+
+    fn try_from(raw: u32) -> Result<Self, Self::Error> {
+        Ok(BASEPTR0(raw))
+    }
+}
+
+impl From<BASEPTR0> for u32 {
+    fn from(raw: BASEPTR0) -> Self {
+        raw.0
+    }
+}
+
+impl DpRegister for BASEPTR0 {
+    const VERSION: DebugPortVersion = DebugPortVersion::DPv3;
+}
+
+impl Register for BASEPTR0 {
+    const ADDRESS: u8 = 0x20;
+    const NAME: &'static str = "BASEPTR0";
+}
+
+/// BASEPTR1, Base Pointer register 1 (see ADI v6.0 B2.2.2)
+///
+/// Holds bits `[63:32]` of the address of the root component, if the debug port
+/// supports addresses larger than 32 bits.
+#[derive(Debug, Clone)]
+pub struct BASEPTR1(pub u32);
+
+impl TryFrom<u32> for BASEPTR1 {
+    type Error = RegisterParseError;
+
+    fn try_from(raw: u32) -> Result<Self, Self::Error> {
+        Ok(BASEPTR1(raw))
+    }
+}
+
+impl From<BASEPTR1> for u32 {
+    fn from(raw: BASEPTR1) -> Self {
+        raw.0
+    }
+}
+
+impl DpRegister for BASEPTR1 {
+    const VERSION: DebugPortVersion = DebugPortVersion::DPv3;
+}
+
+impl Register for BASEPTR1 {
+    const ADDRESS: u8 = 0x30;
+    const NAME: &'static str = "BASEPTR1";
+}
+
 bitfield! {
     /// DPIDR, Debug Port Identification register (see ADI v5.2 B2.2.5)
     ///
@@ -383,7 +524,8 @@ bitfield! {
     ///
     /// `0x0`: Reserved. Implementations of DPv0 do not implement DPIDR.\
     /// `0x1`: DPv1 is implemented.\
-    /// `0x2`: DPv2 is implemented.
+    /// `0x2`: DPv2 is implemented.\
+    /// `0x3`: DPv3 is implemented.
     ///
     /// All remaining values are reserved.
     pub u8, version, _: 15, 12;
@@ -482,6 +624,42 @@ impl Register for TARGETID {
     const NAME: &'static str = "TARGETID";
 }
 
+bitfield! {
+    /// DLPIDR, Data Link Protocol Identification register (see ADI v5.2 B2.2.4)
+    ///
+    /// DLPIDR identifies the instance of a debug port on a multidrop SWD bus.
+    #[derive(Clone)]
+    pub struct DLPIDR(u32);
+    impl Debug;
+    /// Target instance. Together with TARGETID, this uniquely identifies a debug port on a multidrop bus.
+    pub u8, tinstance, _: 31, 28;
+    /// Protocol version. `0x1` indicates SWD protocol version 2.
+    pub u8, protvsn, _: 3, 0;
+}
+
+impl TryFrom<u32> for DLPIDR {
+    type Error = RegisterParseError;
+
+    fn try_from(raw: u32) -> Result<Self, Self::Error> {
+        Ok(Self(raw))
+    }
+}
+
+impl From<DLPIDR> for u32 {
+    fn from(raw: DLPIDR) -> Self {
+        raw.0
+    }
+}
+
+impl DpRegister for DLPIDR {
+    const VERSION: DebugPortVersion = DebugPortVersion::DPv2;
+}
+
+impl Register for DLPIDR {
+    const ADDRESS: u8 = 0x34;
+    const NAME: &'static str = "DLPIDR";
+}
+
 /// The ID of a debug port. Can be used to detect and select devices in a multidrop setup.
 #[derive(Debug)]
 pub struct DebugPortId {
@@ -581,6 +759,8 @@ pub enum DebugPortVersion {
     DPv1,
     /// Version 2 (**very** rare (only known example is the RP2040))
     DPv2,
+    /// Version 3, which maps the access ports into the address space of the debug port (ADIv6)
+    DPv3,
     /// Some unsupported value was encountered!
     Unsupported(u8),
 }
@@ -593,6 +773,7 @@ impl From<DebugPortVersion> for u8 {
             DPv0 => 0,
             DPv1 => 1,
             DPv2 => 2,
+            DPv3 => 3,
             Unsupported(val) => val,
         }
     }
@@ -615,6 +796,7 @@ impl Display for DebugPortVersion {
             DPv0 => write!(f, "DPv0"),
             DPv1 => write!(f, "DPv1"),
             DPv2 => write!(f, "DPv2"),
+            DPv3 => write!(f, "DPv3"),
             Unsupported(version) => write!(f, "<unsupported Debugport Version {version}>"),
         }
     }
@@ -626,6 +808,7 @@ impl From<u8> for DebugPortVersion {
             0 => DebugPortVersion::DPv0,
             1 => DebugPortVersion::DPv1,
             2 => DebugPortVersion::DPv2,
+            3 => DebugPortVersion::DPv3,
             value => DebugPortVersion::Unsupported(value),
         }
     }
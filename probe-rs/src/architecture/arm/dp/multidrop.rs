@@ -0,0 +1,273 @@
+//! Debug port selection on a multidrop SWD (SWDv2) bus.
+
+use super::{Select, DLPIDR, DPIDR, TARGETID};
+use crate::architecture::arm::{ArmError, DapError, PortType, RawDapAccess, Register};
+
+/// Dormant-to-SWD selection alert sequence, followed by a line reset.
+///
+/// See ADI v5.2 B5.3.4. The bits are sent LSB first.
+const DORMANT_TO_SWD_LINE_RESET: [u8; 28] = [
+    0xff, 0x92, 0xf3, 0x09, 0x62, 0x95, 0x2d, 0x85, 0x86, 0xe9, 0xaf, 0xdd, 0xe3, 0xa2, 0x0e, 0xbc,
+    0x19, 0xa0, 0xf1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00,
+];
+
+/// Number of attempts to select a debug port before giving up.
+const TARGETSEL_RETRIES: usize = 5;
+
+/// A debug port which responded on a multidrop SWD bus.
+#[derive(Debug, Clone)]
+pub struct MultidropDp {
+    /// The `TARGETSEL` value which selects this debug port.
+    pub targetsel: u32,
+    /// The value of the DPIDR register.
+    pub dpidr: DPIDR,
+    /// The value of the TARGETID register.
+    pub targetid: TARGETID,
+    /// The value of the DLPIDR register.
+    pub dlpidr: DLPIDR,
+}
+
+/// Select the debug port with the given `TARGETSEL` value on a multidrop SWD bus.
+///
+/// All other debug ports on the bus are deselected.
+pub(crate) fn select_multidrop_dp<P: RawDapAccess + ?Sized>(
+    probe: &mut P,
+    targetsel: u32,
+) -> Result<(), ArmError> {
+    for _ in 0..TARGETSEL_RETRIES {
+        match try_select(probe, targetsel) {
+            Ok(dpidr) => {
+                tracing::debug!("DPIDR read {:08x}", dpidr);
+                return Ok(());
+            }
+            Err(e) => {
+                tracing::debug!("DPIDR read failed, retrying. Error: {:?}", e);
+            }
+        }
+    }
+
+    tracing::warn!("Giving up on TARGETSEL, too many retries.");
+    Err(DapError::NoAcknowledge.into())
+}
+
+/// Enumerate the debug ports responding on a multidrop SWD bus.
+///
+/// Each of the `candidates` is tried as `TARGETSEL` value. A debug port is only
+/// reported if its TARGETID and DLPIDR registers match the `TARGETSEL` value it
+/// was selected with, which filters out SWDv1 debug ports that ignore `TARGETSEL`.
+pub(crate) fn discover_multidrop_dps<P: RawDapAccess + ?Sized>(
+    probe: &mut P,
+    candidates: &[u32],
+) -> Result<Vec<MultidropDp>, ArmError> {
+    let mut found = Vec::new();
+
+    for &targetsel in candidates {
+        let dpidr = match try_select(probe, targetsel) {
+            Ok(dpidr) => DPIDR(dpidr),
+            Err(e) => {
+                tracing::debug!("No response for TARGETSEL {:#010x}: {:?}", targetsel, e);
+                continue;
+            }
+        };
+
+        if dpidr.version() < 2 {
+            tracing::debug!(
+                "Debug port responding to TARGETSEL {:#010x} does not support multidrop",
+                targetsel
+            );
+            continue;
+        }
+
+        let targetid = TARGETID(read_banked_register(probe, TARGETID::ADDRESS)?);
+        let dlpidr = DLPIDR(read_banked_register(probe, DLPIDR::ADDRESS)?);
+
+        if targetid.0 & 0x0fff_ffff != targetsel & 0x0fff_ffff
+            || dlpidr.tinstance() as u32 != targetsel >> 28
+        {
+            tracing::debug!(
+                "Ignoring debug port with TARGETID {:#010x} and DLPIDR {:#010x} responding to TARGETSEL {:#010x}",
+                targetid.0,
+                dlpidr.0,
+                targetsel
+            );
+            continue;
+        }
+
+        found.push(MultidropDp {
+            targetsel,
+            dpidr,
+            targetid,
+            dlpidr,
+        });
+    }
+
+    Ok(found)
+}
+
+/// The `TARGETSEL` values for all 16 instances of each of the given TARGETID values.
+pub fn targetsel_candidates(targetids: impl IntoIterator<Item = u32>) -> Vec<u32> {
+    let mut candidates: Vec<u32> = targetids
+        .into_iter()
+        .flat_map(|targetid| {
+            (0..16).map(move |instance| (instance << 28) | (targetid & 0x0fff_ffff) | 1)
+        })
+        .collect();
+
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates
+}
+
+/// Send the `TARGETSEL` write and read back DPIDR, which is required by the protocol.
+fn try_select<P: RawDapAccess + ?Sized>(probe: &mut P, targetsel: u32) -> Result<u32, ArmError> {
+    // dormant-to-swd + line reset
+    for chunk in DORMANT_TO_SWD_LINE_RESET.chunks(8) {
+        let mut bits = [0u8; 8];
+        bits[..chunk.len()].copy_from_slice(chunk);
+
+        probe.swj_sequence(chunk.len() as u8 * 8, u64::from_le_bytes(bits))?;
+    }
+
+    // TARGETSEL write.
+    // The TARGETSEL write is not ACKed by design. We can't use a normal register write
+    // because many probes don't even send the data phase when NAK.
+    let parity = targetsel.count_ones() % 2;
+    let packet = (parity as u64) << 45 | (targetsel as u64) << 13 | 0x1f99;
+
+    probe.swj_sequence(46, packet)?;
+
+    // "A write to the TARGETSEL register must always be followed by a read of the DPIDR register or a line reset. If the
+    // response to the DPIDR read is incorrect, or there is no response, the host must start the sequence again."
+    probe.raw_read_register(PortType::DebugPort, DPIDR::ADDRESS)
+}
+
+/// Read a register in one of the banks of the DP register at address 0x4.
+fn read_banked_register<P: RawDapAccess + ?Sized>(
+    probe: &mut P,
+    address: u8,
+) -> Result<u32, ArmError> {
+    let mut select = Select(0);
+    select.set_dp_bank_sel(address >> 4);
+    probe.raw_write_register(PortType::DebugPort, Select::ADDRESS, select.into())?;
+
+    let value = probe.raw_read_register(PortType::DebugPort, address & 0xf);
+
+    probe.raw_write_register(PortType::DebugPort, Select::ADDRESS, Select(0).into())?;
+
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{architecture::arm::DpAddress, DebugProbe, DebugProbeError};
+
+    /// A multidrop SWD bus with a number of debug ports, identified by their `TARGETSEL` value.
+    #[derive(Default)]
+    struct MockBus {
+        dps: Vec<u32>,
+        selected: Option<u32>,
+        dp_bank: u8,
+        sequence_bits: u32,
+    }
+
+    impl RawDapAccess for MockBus {
+        fn select_dp(&mut self, dp: DpAddress) -> Result<(), ArmError> {
+            match dp {
+                DpAddress::Default => Ok(()),
+                DpAddress::Multidrop(targetsel) => select_multidrop_dp(self, targetsel),
+            }
+        }
+
+        fn raw_read_register(&mut self, port: PortType, addr: u8) -> Result<u32, ArmError> {
+            assert_eq!(port, PortType::DebugPort);
+
+            let targetsel = self.selected.ok_or(DapError::NoAcknowledge)?;
+
+            match (addr, self.dp_bank) {
+                (0x0, _) => Ok(0x0bc12477),
+                (0x4, 2) => Ok(targetsel & 0x0fff_ffff),
+                (0x4, 3) => Ok(targetsel & 0xf000_0000 | 1),
+                _ => Ok(0),
+            }
+        }
+
+        fn raw_write_register(
+            &mut self,
+            port: PortType,
+            addr: u8,
+            value: u32,
+        ) -> Result<(), ArmError> {
+            assert_eq!((port, addr), (PortType::DebugPort, Select::ADDRESS));
+
+            self.dp_bank = (value & 0xf) as u8;
+
+            Ok(())
+        }
+
+        fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), DebugProbeError> {
+            if bit_len == 46 {
+                // The line reset before the TARGETSEL write has to be complete.
+                assert_eq!(self.sequence_bits, 28 * 8);
+
+                assert_eq!(bits & 0x1fff, 0x1f99);
+                let targetsel = (bits >> 13) as u32;
+                assert_eq!((bits >> 45) as u32, targetsel.count_ones() % 2);
+
+                self.selected = self.dps.iter().copied().find(|&dp| dp == targetsel);
+                self.sequence_bits = 0;
+            } else {
+                self.selected = None;
+                self.sequence_bits += bit_len as u32;
+            }
+
+            Ok(())
+        }
+
+        fn swj_pins(
+            &mut self,
+            _pin_out: u32,
+            _pin_select: u32,
+            _pin_wait: u32,
+        ) -> Result<u32, DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn select_dp_on_multidrop_bus() {
+        let mut bus = MockBus {
+            dps: vec![0x01002927, 0x11002927],
+            ..Default::default()
+        };
+
+        bus.select_dp(DpAddress::Multidrop(0x11002927)).unwrap();
+        assert_eq!(bus.selected, Some(0x11002927));
+
+        assert!(bus.select_dp(DpAddress::Multidrop(0x21002927)).is_err());
+        assert_eq!(bus.selected, None);
+    }
+
+    #[test]
+    fn discover_dps_on_multidrop_bus() {
+        let mut bus = MockBus {
+            dps: vec![0x01002927, 0x11002927, 0xf1002927],
+            ..Default::default()
+        };
+
+        let candidates = targetsel_candidates([0x01002927, 0x11002927]);
+        assert_eq!(candidates.len(), 16);
+
+        let found = discover_multidrop_dps(&mut bus, &candidates).unwrap();
+
+        let targetsels: Vec<_> = found.iter().map(|dp| dp.targetsel).collect();
+        assert_eq!(targetsels, [0x01002927, 0x11002927, 0xf1002927]);
+
+        assert_eq!(found[1].dlpidr.tinstance(), 1);
+        assert_eq!(found[1].targetid.tpartno(), 0x1002);
+    }
+}
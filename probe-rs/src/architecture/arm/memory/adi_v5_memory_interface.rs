@@ -7,7 +7,7 @@ use crate::architecture::arm::{
     communication_interface::Initialized, dp::DpAccess, MemoryApInformation,
 };
 use crate::architecture::arm::{ArmCommunicationInterface, ArmError};
-use crate::DebugProbeError;
+use crate::{DebugProbeError, SecurityState};
 use std::convert::TryInto;
 use std::ops::Range;
 
@@ -137,6 +137,17 @@ pub trait ArmProbe: SwdSequence {
     /// Returns the underlying [`ApAddress`].
     fn ap(&mut self) -> MemoryAp;
 
+    /// Select the security state of the following memory accesses.
+    ///
+    /// `None` selects the default of the access port. Access ports which can not
+    /// issue secure and non-secure transfers only accept `None`.
+    fn set_security_state(&mut self, state: Option<SecurityState>) -> Result<(), ArmError> {
+        match state {
+            None => Ok(()),
+            Some(_) => Err(ArmError::SecurityStateNotSupported),
+        }
+    }
+
     fn get_arm_communication_interface(
         &mut self,
     ) -> Result<&mut ArmCommunicationInterface<Initialized>, DebugProbeError>;
@@ -158,6 +169,9 @@ where
     /// cached on a lower level, where the other Memory AP information is
     /// stored.
     cached_csw_value: Option<CSW>,
+
+    /// The security state selected for memory accesses, `None` for the default.
+    security_state: Option<SecurityState>,
 }
 
 impl<'interface, AP> ADIMemoryInterface<'interface, AP>
@@ -175,6 +189,7 @@ where
             ap_information,
             memory_ap: MemoryAp::new(address),
             cached_csw_value: None,
+            security_state: None,
         })
     }
 }
@@ -190,7 +205,9 @@ where
         // The CSW Register is set for an AMBA AHB Acccess, according to
         // the ARM Debug Interface Architecture Specification.
         //
-        // The HNONSEC bit is set according to [Self::supports_hnonsec]:
+        // The HNONSEC bit is set according to the selected security state. Without an
+        // explicit selection, secure accesses are used if the AP supports them.
+        //
         // The PROT bits are set as follows:
         //  MasterType, bit [29] = 1  - Access as default AHB Master
         //  HPROT[4]             = 0  - Non-allocating access
@@ -202,7 +219,11 @@ where
         //   HPROT[3] == 0   - non-bufferable access
 
         CSW {
-            HNONSEC: !self.ap_information.supports_hnonsec as u8,
+            HNONSEC: match self.security_state {
+                Some(SecurityState::Secure) => 0,
+                Some(SecurityState::NonSecure) => 1,
+                None => !self.ap_information.supports_hnonsec as u8,
+            },
             PROT: 0b10,
             CACHE: 0b11,
             AddrInc: AddressIncrement::Single,
@@ -955,6 +976,16 @@ where
         self.memory_ap
     }
 
+    fn set_security_state(&mut self, state: Option<SecurityState>) -> Result<(), ArmError> {
+        if state.is_some() && !self.ap_information.supports_hnonsec {
+            return Err(ArmError::SecurityStateNotSupported);
+        }
+
+        self.security_state = state;
+
+        Ok(())
+    }
+
     fn get_arm_communication_interface(
         &mut self,
     ) -> Result<&mut ArmCommunicationInterface<Initialized>, DebugProbeError> {
@@ -985,15 +1016,20 @@ fn aligned_range(address: u64, len: usize) -> Result<Range<u64>, ArmError> {
 mod tests {
     use scroll::Pread;
 
-    use crate::architecture::arm::{ap::AccessPort, ApAddress, DpAddress, MemoryApInformation};
+    use crate::architecture::arm::{
+        ap::AccessPort, ApAddress, ApPort, DpAddress, MemoryApInformation,
+    };
 
     use super::super::super::ap::memory_ap::mock::MockMemoryAp;
     use super::super::super::ap::memory_ap::MemoryAp;
-    use super::ADIMemoryInterface;
+    use super::super::super::ap::{ApAccess, CSW};
+    use super::{ADIMemoryInterface, ArmProbe};
+    use crate::architecture::arm::ArmError;
+    use crate::SecurityState;
 
     const DUMMY_AP: MemoryAp = MemoryAp::new(ApAddress {
         dp: DpAddress::Default,
-        ap: 0,
+        ap: ApPort::Index(0),
     });
 
     impl<'interface> ADIMemoryInterface<'interface, MockMemoryAp> {
@@ -1221,6 +1257,53 @@ mod tests {
         }
     }
 
+    /// The HNONSEC bit of the CSW register last written by a memory access.
+    fn written_hnonsec(mi: &mut ADIMemoryInterface<MockMemoryAp>) -> u8 {
+        let csw: CSW = mi.interface.read_ap_register(DUMMY_AP).unwrap();
+        csw.HNONSEC
+    }
+
+    #[test]
+    fn security_state_selects_hnonsec() {
+        let mut mock = MockMemoryAp::with_pattern();
+        let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+        mi.ap_information.supports_hnonsec = true;
+
+        // Secure accesses are the default of an AP which supports them.
+        mi.write_word_32(DUMMY_AP, 0, DATA32[0]).unwrap();
+        assert_eq!(written_hnonsec(&mut mi), 0);
+
+        ArmProbe::set_security_state(&mut mi, Some(SecurityState::NonSecure)).unwrap();
+        mi.write_word_32(DUMMY_AP, 0, DATA32[0]).unwrap();
+        assert_eq!(written_hnonsec(&mut mi), 1);
+
+        ArmProbe::set_security_state(&mut mi, Some(SecurityState::Secure)).unwrap();
+        mi.read_word_32(DUMMY_AP, 0).unwrap();
+        assert_eq!(written_hnonsec(&mut mi), 0);
+
+        ArmProbe::set_security_state(&mut mi, None).unwrap();
+        mi.read_word_32(DUMMY_AP, 0).unwrap();
+        assert_eq!(written_hnonsec(&mut mi), 0);
+    }
+
+    #[test]
+    fn security_state_without_hnonsec_support() {
+        let mut mock = MockMemoryAp::with_pattern();
+        let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+        for state in [SecurityState::Secure, SecurityState::NonSecure] {
+            assert!(matches!(
+                ArmProbe::set_security_state(&mut mi, Some(state)),
+                Err(ArmError::SecurityStateNotSupported)
+            ));
+        }
+        ArmProbe::set_security_state(&mut mi, None).unwrap();
+
+        // Accesses are non-secure, as the AP can't issue secure transfers.
+        mi.write_word_32(DUMMY_AP, 0, DATA32[0]).unwrap();
+        assert_eq!(written_hnonsec(&mut mi), 1);
+    }
+
     use super::aligned_range;
 
     #[test]
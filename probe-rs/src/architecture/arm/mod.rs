@@ -8,6 +8,7 @@ pub mod dp;
 pub mod memory;
 pub mod sequences;
 pub mod swo;
+pub mod trace;
 mod traits;
 
 pub use communication_interface::{
@@ -23,6 +24,7 @@ use self::ap::AccessPortError;
 use self::armv7a::Armv7aError;
 use self::armv8a::Armv8aError;
 use self::communication_interface::RegisterParseError;
+use self::component::ComponentError;
 pub use self::core::armv6m;
 pub use self::core::armv7a;
 pub use self::core::armv7m;
@@ -123,6 +125,9 @@ pub enum ArmError {
     /// Tracing has not been configured.
     TracingUnconfigured,
 
+    /// Error using a CoreSight component.
+    Component(#[from] ComponentError),
+
     /// Error parsing a register.
     RegisterParse(#[from] RegisterParseError),
 
@@ -131,6 +136,10 @@ pub enum ArmError {
 
     /// Failed to erase chip
     ChipEraseFailed,
+
+    /// The access port can not select the security state of memory accesses.
+    #[error("The access port does not support secure and non-secure memory accesses")]
+    SecurityStateNotSupported,
 }
 
 impl ArmError {
@@ -146,6 +155,29 @@ impl ArmError {
     pub fn alignment_error(address: u64, alignment: usize) -> Self {
         ArmError::MemoryNotAligned { address, alignment }
     }
+
+    /// Check if the error was caused by losing the connection to the probe or the debug port.
+    pub(crate) fn is_connection_lost(&self) -> bool {
+        fn debug_port_lost(err: &DebugPortError) -> bool {
+            match err {
+                DebugPortError::DebugProbe(err) => err.is_connection_lost(),
+                DebugPortError::Dap(err) => *err == DapError::NoAcknowledge,
+                _ => false,
+            }
+        }
+
+        match self {
+            ArmError::Probe(err) => err.is_connection_lost(),
+            ArmError::Dap(err) => *err == DapError::NoAcknowledge,
+            ArmError::DebugPort(err) => debug_port_lost(err),
+            ArmError::AccessPort { source, .. } => match source {
+                AccessPortError::DebugPort(err) => debug_port_lost(err),
+                AccessPortError::Flush(err) => err.is_connection_lost(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
 }
 
 impl From<RomTableError> for ArmError {
@@ -5,7 +5,7 @@ use crate::{
     architecture::{
         self,
         arm::{
-            ap::MemoryAp, memory::adi_v5_memory_interface::ArmProbe, ApAddress, ArmError,
+            ap::MemoryAp, memory::adi_v5_memory_interface::ArmProbe, ApAddress, ApPort, ArmError,
             ArmProbeInterface, DpAddress,
         },
     },
@@ -414,7 +414,7 @@ impl DebugEraseSequence for AtSAME5x {
     fn erase_all(&self, interface: &mut dyn ArmProbeInterface) -> Result<(), ArmError> {
         let mem_ap = MemoryAp::new(ApAddress {
             dp: DpAddress::Default,
-            ap: 0,
+            ap: ApPort::Index(0),
         });
 
         let mut memory = interface.memory_interface(mem_ap)?;
@@ -4,7 +4,7 @@ use std::sync::Arc;
 
 use super::{ArmDebugSequence, ArmDebugSequenceError};
 use crate::architecture::arm::{
-    ap::MemoryAp, component::TraceSink, memory::CoresightComponent, ApAddress, ArmError,
+    ap::MemoryAp, component::TraceSink, memory::CoresightComponent, ApAddress, ApPort, ArmError,
     ArmProbeInterface, DpAddress,
 };
 use crate::session::MissingPermissions;
@@ -88,7 +88,7 @@ impl ArmDebugSequence for Nrf52 {
         permissions: &crate::Permissions,
     ) -> Result<(), ArmError> {
         let ctrl_ap = ApAddress {
-            ap: 1,
+            ap: ApPort::Index(1),
             dp: DpAddress::Default,
         };
 
@@ -7,7 +7,7 @@ use crate::architecture::arm::ap::AccessPort;
 use crate::architecture::arm::memory::adi_v5_memory_interface::ArmProbe;
 use crate::architecture::arm::ArmError;
 use crate::architecture::arm::{
-    communication_interface::Initialized, ApAddress, ArmCommunicationInterface, DapAccess,
+    communication_interface::Initialized, ApAddress, ApPort, ArmCommunicationInterface, DapAccess,
 };
 
 /// The sequence handle for the nRF9160.
@@ -31,11 +31,11 @@ impl Nrf for Nrf9160 {
             .map(|(core_ahb_ap, core_ctrl_ap)| {
                 (
                     ApAddress {
-                        ap: core_ahb_ap,
+                        ap: ApPort::Index(core_ahb_ap),
                         ..ap_address
                     },
                     ApAddress {
-                        ap: core_ctrl_ap,
+                        ap: ApPort::Index(core_ctrl_ap),
                         ..ap_address
                     },
                 )
@@ -13,7 +13,7 @@ use crate::{
         core::armv7m::{Aircr, Demcr, Dhcsr},
         dp::{Abort, Ctrl, DpAccess, Select, DPIDR},
         memory::adi_v5_memory_interface::ArmProbe,
-        ApAddress, ArmCommunicationInterface, ArmError, DapAccess, DpAddress,
+        ApAddress, ApPort, ArmCommunicationInterface, ArmError, DapAccess, DpAddress,
     },
     core::MemoryMappedRegister,
 };
@@ -283,7 +283,10 @@ fn enable_debug_mailbox(
 ) -> Result<(), ArmError> {
     tracing::info!("LPC55xx connect srcipt start");
 
-    let ap = ApAddress { dp, ap: 2 };
+    let ap = ApAddress {
+        dp,
+        ap: ApPort::Index(2),
+    };
 
     let status: IDR = interface.read_ap_register(GenericAp::new(ap))?;
 
@@ -488,7 +491,10 @@ impl ArmDebugSequence for MIMXRT11xx {
         // See its documentation and TODOs.
         self::debug_port_start(interface, dp, Select(0))?;
 
-        let ap = ApAddress { dp, ap: 0 };
+        let ap = ApAddress {
+            dp,
+            ap: ApPort::Index(0),
+        };
         let ap = MemoryAp::new(ap);
 
         tracing::debug!("Prepare trap code for Cortex M7");
@@ -4,7 +4,7 @@ use std::sync::Arc;
 
 use super::ArmDebugSequence;
 use crate::architecture::arm::{
-    ap::MemoryAp, component::TraceSink, memory::CoresightComponent, ApAddress, ArmError,
+    ap::MemoryAp, component::TraceSink, memory::CoresightComponent, ApAddress, ApPort, ArmError,
     ArmProbeInterface, DpAddress,
 };
 
@@ -77,7 +77,7 @@ impl ArmDebugSequence for Stm32fSeries {
         // Power down the debug components
         let ap = MemoryAp::new(ApAddress {
             dp: DpAddress::Default,
-            ap: 0,
+            ap: ApPort::Index(0),
         });
 
         let mut memory = interface.memory_interface(ap)?;
@@ -10,7 +10,7 @@ use crate::architecture::arm::{
         adi_v5_memory_interface::ArmProbe, romtable::RomTableError, CoresightComponent,
         PeripheralType,
     },
-    ApAddress, ArmError, ArmProbeInterface, DpAddress,
+    ApAddress, ApPort, ArmError, ArmProbeInterface, DpAddress,
 };
 
 // Base address of the trace funnel that directs trace data to the SWO peripheral.
@@ -152,7 +152,7 @@ impl ArmDebugSequence for Stm32h7 {
         // Power up the debug components through AP2, which is the defualt AP debug port.
         let ap = MemoryAp::new(ApAddress {
             dp: DpAddress::Default,
-            ap: 2,
+            ap: ApPort::Index(2),
         });
 
         let mut memory = interface.memory_interface(ap)?;
@@ -165,7 +165,7 @@ impl ArmDebugSequence for Stm32h7 {
         // Power up the debug components through AP2, which is the defualt AP debug port.
         let ap = MemoryAp::new(ApAddress {
             dp: DpAddress::Default,
-            ap: 2,
+            ap: ApPort::Index(2),
         });
 
         let mut memory = interface.memory_interface(ap)?;
@@ -0,0 +1,272 @@
+//! Decoder for the ETMv3 trace protocol, as used by the ETM of the Cortex-M3 and Cortex-M4.
+//!
+//! See the "Embedded Trace Macrocell Architecture Specification" (ARM IHI 0014Q), chapter 7.
+//! The ETM has to be configured for branch output, so that every taken branch is reported with its
+//! target address. ARMv7-M cores always use the alternative branch address encoding.
+
+use super::{decode_packets, PacketReader, Parsed, TraceEvent};
+
+/// The minimum number of zero bytes in an A-sync packet.
+const ASYNC_ZEROS: usize = 5;
+
+/// A streaming decoder for ETMv3 trace data.
+#[derive(Debug, Default)]
+pub struct EtmV3Decoder {
+    pending: Vec<u8>,
+    synced: bool,
+    state: State,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The last traced address, or `None` before the first I-sync packet.
+    address: Option<u32>,
+}
+
+impl EtmV3Decoder {
+    /// Create a new decoder, which waits for the first synchronization packet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the next chunk of the trace stream.
+    ///
+    /// Incomplete packets at the end of `data` are kept until the next call.
+    pub fn decode(&mut self, data: &[u8]) -> Vec<TraceEvent> {
+        self.pending.extend_from_slice(data);
+
+        let state = &mut self.state;
+        decode_packets(
+            &mut self.pending,
+            &mut self.synced,
+            ASYNC_ZEROS,
+            |reader, events| state.parse(reader, events),
+        )
+    }
+}
+
+impl State {
+    fn parse(&mut self, reader: &mut PacketReader, events: &mut Vec<TraceEvent>) -> Option<Parsed> {
+        let header = reader.byte()?;
+
+        match header {
+            // Branch address
+            header if header & 0x01 == 0x01 => return self.branch(header, reader, events),
+            // A-sync
+            0x00 => {
+                let mut byte = reader.byte()?;
+                while byte == 0x00 {
+                    byte = reader.byte()?;
+                }
+                // The first non-zero byte has to complete the A-sync packet.
+                if byte != 0x80 || reader.consumed() < ASYNC_ZEROS + 1 {
+                    return Some(Parsed::Invalid(header));
+                }
+            }
+            // I-sync: information byte, followed by the address
+            0x08 => {
+                let info = reader.byte()?;
+                let mut address = 0;
+                for index in 0..4 {
+                    address |= (reader.byte()? as u32) << (8 * index);
+                }
+                if info & 0x80 != 0 {
+                    // Load/store in progress address, which is irrelevant for the instruction flow.
+                    while reader.byte()? & 0x80 != 0 {}
+                }
+                // Bit 0 holds the Thumb state.
+                let address = address & !1;
+                self.address = Some(address);
+                events.push(TraceEvent::Branch {
+                    source: None,
+                    target: address,
+                });
+            }
+            // Trigger, ignore
+            0x0C | 0x66 => {}
+            // VMID
+            0x3C => reader.skip(1)?,
+            // Timestamp, with an optional cycle count
+            0x42 | 0x46 => {
+                reader.continued(9)?;
+            }
+            // Context ID, which has been configured to be zero bytes long
+            0x6E => {}
+            // Exception exit
+            0x76 => events.push(TraceEvent::ExceptionReturn),
+            // P-header, which only reports the number of executed instructions
+            header if header & 0x81 == 0x80 => {}
+            header => return Some(Parsed::Invalid(header)),
+        }
+
+        Some(Parsed::Packet)
+    }
+
+    fn branch(
+        &mut self,
+        header: u8,
+        reader: &mut PacketReader,
+        events: &mut Vec<TraceEvent>,
+    ) -> Option<Parsed> {
+        // The first byte holds address bits [6:1], every following byte 7 more bits. A byte
+        // without continuation bit, other than the first one, only holds 6 address bits, and bit 6
+        // signals that exception information follows.
+        let mut value = ((header >> 1) & 0x3F) as u32;
+        let mut bits = 6;
+        let mut exception_follows = false;
+        let mut byte = header;
+
+        for index in 1..5 {
+            if byte & 0x80 == 0 {
+                break;
+            }
+            byte = reader.byte()?;
+
+            if index == 4 {
+                // The fifth byte holds address bits [31:28] in Thumb state.
+                value |= ((byte & 0x0F) as u32) << bits;
+                bits = 31;
+                exception_follows = byte & 0x40 != 0;
+            } else if byte & 0x80 != 0 {
+                value |= ((byte & 0x7F) as u32) << bits;
+                bits += 7;
+            } else {
+                value |= ((byte & 0x3F) as u32) << bits;
+                bits += 6;
+                exception_follows = byte & 0x40 != 0;
+            }
+        }
+
+        let mut exception = None;
+        if exception_follows {
+            let info = reader.byte()?;
+            let mut number = ((info >> 1) & 0x0F) as u16;
+            if info & 0x80 != 0 {
+                let info = reader.byte()?;
+                number |= ((info & 0x1F) as u16) << 4;
+                if info & 0x80 != 0 {
+                    // Resume information
+                    reader.skip(1)?;
+                }
+            }
+            exception = Some(number);
+        }
+
+        // Branches before the first I-sync packet can not be resolved.
+        let previous = match self.address {
+            Some(previous) => previous,
+            None => return Some(Parsed::Packet),
+        };
+
+        let mask = if bits >= 31 {
+            0xFFFF_FFFE
+        } else {
+            ((1u32 << bits) - 1) << 1
+        };
+        let address = (previous & !mask) | ((value << 1) & mask);
+        self.address = Some(address);
+
+        if let Some(number) = exception {
+            events.push(TraceEvent::Exception {
+                number: Some(number),
+                return_address: None,
+            });
+        }
+        events.push(TraceEvent::Branch {
+            source: None,
+            target: address,
+        });
+
+        Some(Parsed::Packet)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ASYNC: [u8; 6] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x80];
+
+    fn isync(address: u32) -> Vec<u8> {
+        let mut packet = vec![0x08, 0x00];
+        packet.extend_from_slice(&(address | 1).to_le_bytes());
+        packet
+    }
+
+    fn branch(source: Option<u32>, target: u32) -> TraceEvent {
+        TraceEvent::Branch { source, target }
+    }
+
+    #[test]
+    fn waits_for_sync() {
+        let mut decoder = EtmV3Decoder::new();
+
+        let mut data = vec![0x12, 0x34];
+        data.extend_from_slice(&isync(0x0800_0100));
+        assert!(decoder.decode(&data).is_empty());
+
+        let mut data = ASYNC.to_vec();
+        data.extend_from_slice(&isync(0x0800_0100));
+        assert_eq!(decoder.decode(&data), vec![branch(None, 0x0800_0100)]);
+    }
+
+    #[test]
+    fn branch_addresses() {
+        let mut decoder = EtmV3Decoder::new();
+
+        let mut data = ASYNC.to_vec();
+        data.extend_from_slice(&isync(0x0800_0100));
+        // Single byte: address bits [6:1]
+        data.push(0x21);
+        // Two bytes: address bits [6:1] and [12:7]
+        data.extend_from_slice(&[0xC5, 0x1F]);
+        // Five bytes: full address
+        data.extend_from_slice(&[0x81, 0x80, 0x80, 0x80, 0x02]);
+
+        assert_eq!(
+            decoder.decode(&data),
+            vec![
+                branch(None, 0x0800_0100),
+                branch(None, 0x0800_0120),
+                branch(None, 0x0800_0FC4),
+                branch(None, 0x2000_0000),
+            ]
+        );
+    }
+
+    #[test]
+    fn exception_on_branch() {
+        let mut decoder = EtmV3Decoder::new();
+
+        let mut data = ASYNC.to_vec();
+        data.extend_from_slice(&isync(0x0800_0100));
+        // Two byte branch with exception information, exception 3 (HardFault)
+        data.extend_from_slice(&[0x89, 0x42, 0x06]);
+        data.push(0x76);
+
+        assert_eq!(
+            decoder.decode(&data),
+            vec![
+                branch(None, 0x0800_0100),
+                TraceEvent::Exception {
+                    number: Some(3),
+                    return_address: None,
+                },
+                branch(None, 0x0800_0108),
+                TraceEvent::ExceptionReturn,
+            ]
+        );
+    }
+
+    #[test]
+    fn split_packets() {
+        let mut decoder = EtmV3Decoder::new();
+
+        let mut data = ASYNC.to_vec();
+        data.extend_from_slice(&isync(0x0800_0100));
+        let (first, second) = data.split_at(8);
+
+        assert!(decoder.decode(first).is_empty());
+        assert_eq!(decoder.decode(second), vec![branch(None, 0x0800_0100)]);
+    }
+}
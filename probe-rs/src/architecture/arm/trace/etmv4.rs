@@ -0,0 +1,378 @@
+//! Decoder for the ETMv4 instruction trace protocol, as used by the ETM of the Cortex-M7, Cortex-M23
+//! and Cortex-M33.
+//!
+//! See the "ARM Embedded Trace Macrocell Architecture Specification ETMv4" (ARM IHI 0064), chapter 6.
+//! The ETM has to be configured for branch broadcasting, so that every taken branch is reported
+//! with its target address. Only the 32-bit address packets used by M-profile cores are supported.
+
+use super::{decode_packets, PacketReader, Parsed, TraceEvent};
+
+/// The minimum number of zero bytes in an A-sync packet.
+const ASYNC_ZEROS: usize = 11;
+
+/// A streaming decoder for ETMv4 instruction trace data.
+#[derive(Debug, Default)]
+pub struct EtmV4Decoder {
+    pending: Vec<u8>,
+    synced: bool,
+    state: State,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The address history, most recent address first.
+    history: [u32; 3],
+    /// Whether a full address has been received since the last synchronization.
+    address_valid: bool,
+    /// The next address packet holds the return address of an exception.
+    exception_pending: Option<u16>,
+}
+
+/// The instruction set of an address packet.
+#[derive(Clone, Copy)]
+enum Isa {
+    /// IS0, i.e. A32, where bits [1:0] of the address are always zero.
+    Is0,
+    /// IS1, i.e. T32, where bit 0 of the address is always zero.
+    Is1,
+}
+
+impl EtmV4Decoder {
+    /// Create a new decoder, which waits for the first synchronization packet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the next chunk of the trace stream.
+    ///
+    /// Incomplete packets at the end of `data` are kept until the next call.
+    pub fn decode(&mut self, data: &[u8]) -> Vec<TraceEvent> {
+        self.pending.extend_from_slice(data);
+
+        let state = &mut self.state;
+        decode_packets(
+            &mut self.pending,
+            &mut self.synced,
+            ASYNC_ZEROS,
+            |reader, events| state.parse(reader, events),
+        )
+    }
+}
+
+impl State {
+    fn parse(&mut self, reader: &mut PacketReader, events: &mut Vec<TraceEvent>) -> Option<Parsed> {
+        let header = reader.byte()?;
+
+        match header {
+            // Extension: A-sync, discard or overflow
+            0x00 => match reader.byte()? {
+                0x00 => {
+                    let mut byte = reader.byte()?;
+                    while byte == 0x00 {
+                        byte = reader.byte()?;
+                    }
+                    if byte != 0x80 || reader.consumed() < ASYNC_ZEROS + 1 {
+                        return Some(Parsed::Invalid(header));
+                    }
+                }
+                0x03 => {}
+                0x05 => {
+                    self.address_valid = false;
+                    events.push(TraceEvent::Overflow);
+                }
+                _ => return Some(Parsed::Invalid(header)),
+            },
+            // Trace info
+            0x01 => {
+                let control = reader.continued(1)?;
+                // INFO, KEY, SPEC and CYCT sections
+                for (bit, max_bytes) in [(0, 2), (1, 5), (2, 5), (3, 2)] {
+                    if control & (1 << bit) != 0 {
+                        reader.continued(max_bytes)?;
+                    }
+                }
+                self.history = [0; 3];
+                self.address_valid = false;
+                self.exception_pending = None;
+            }
+            // Timestamp, with an optional cycle count
+            0x02 | 0x03 => {
+                reader.continued(9)?;
+                if header & 0x01 != 0 {
+                    reader.continued(3)?;
+                }
+            }
+            // Trace on, function return
+            0x04 | 0x05 => {}
+            // Exception
+            0x06 => {
+                let info = reader.byte()?;
+                let mut number = ((info >> 1) & 0x1F) as u16;
+                if info & 0x80 != 0 {
+                    let info = reader.byte()?;
+                    number |= ((info & 0x1F) as u16) << 5;
+                }
+                // The preferred return address follows in an address packet.
+                self.exception_pending = Some(number);
+            }
+            // Exception return
+            0x07 => events.push(TraceEvent::ExceptionReturn),
+            // Cycle count format 2, with the commit and count in one byte
+            0x0C | 0x0D => reader.skip(1)?,
+            // Cycle count format 1, with a known count. Commit elements are assumed to be implied,
+            // so there is no commit section.
+            0x0E => {
+                reader.continued(3)?;
+            }
+            // Cycle count format 1, with an unknown count
+            0x0F => {}
+            // Cycle count format 3
+            0x10..=0x1F => {}
+            // Ignore, events
+            0x70..=0x7F => {}
+            // Context, without payload
+            0x80 => {}
+            // Context, with payload
+            0x81 => Self::context(reader)?,
+            // Address with context, 32-bit
+            0x82 | 0x83 => {
+                let address = Self::long_address(reader, Self::isa(header))?;
+                Self::context(reader)?;
+                self.push_address(address, events);
+            }
+            // Exact match address
+            0x90..=0x92 => {
+                if self.address_valid {
+                    let address = self.history[(header & 0x3) as usize];
+                    self.push_address(address, events);
+                }
+            }
+            // Short address, IS0 or IS1
+            0x95 | 0x96 => {
+                let (value, mask) = match header {
+                    0x95 => {
+                        let byte = reader.byte()?;
+                        let mut value = ((byte & 0x7F) as u32) << 2;
+                        let mut mask = 0x1FC;
+                        if byte & 0x80 != 0 {
+                            value |= (reader.byte()? as u32) << 9;
+                            mask = 0x1_FFFC;
+                        }
+                        (value, mask)
+                    }
+                    _ => {
+                        let byte = reader.byte()?;
+                        let mut value = ((byte & 0x7F) as u32) << 1;
+                        let mut mask = 0xFE;
+                        if byte & 0x80 != 0 {
+                            value |= (reader.byte()? as u32) << 8;
+                            mask = 0xFFFE;
+                        }
+                        (value, mask)
+                    }
+                };
+                if self.address_valid {
+                    let address = (self.history[0] & !mask) | value;
+                    self.push_address(address, events);
+                }
+            }
+            // Long address, 32-bit, IS0 or IS1
+            0x9A | 0x9B => {
+                let address = Self::long_address(reader, Self::isa(header))?;
+                self.push_address(address, events);
+            }
+            // Atoms, which only report whether branches were taken
+            0xC0..=0xFF => {}
+            header => return Some(Parsed::Invalid(header)),
+        }
+
+        Some(Parsed::Packet)
+    }
+
+    fn isa(header: u8) -> Isa {
+        if header & 0x01 == 0 {
+            Isa::Is0
+        } else {
+            Isa::Is1
+        }
+    }
+
+    fn long_address(reader: &mut PacketReader, isa: Isa) -> Option<u32> {
+        let mut bytes = [0u8; 4];
+        for byte in bytes.iter_mut() {
+            *byte = reader.byte()?;
+        }
+
+        let address = match isa {
+            Isa::Is0 => {
+                ((bytes[0] & 0x7F) as u32) << 2
+                    | ((bytes[1] & 0x7F) as u32) << 9
+                    | (bytes[2] as u32) << 16
+                    | (bytes[3] as u32) << 24
+            }
+            Isa::Is1 => {
+                ((bytes[0] & 0x7F) as u32) << 1
+                    | (bytes[1] as u32) << 8
+                    | (bytes[2] as u32) << 16
+                    | (bytes[3] as u32) << 24
+            }
+        };
+
+        Some(address)
+    }
+
+    /// Skip the payload of a context packet.
+    fn context(reader: &mut PacketReader) -> Option<()> {
+        let info = reader.byte()?;
+        if info & 0x40 != 0 {
+            // VMID
+            reader.skip(1)?;
+        }
+        if info & 0x80 != 0 {
+            // Context ID
+            reader.skip(4)?;
+        }
+        Some(())
+    }
+
+    fn push_address(&mut self, address: u32, events: &mut Vec<TraceEvent>) {
+        self.history = [address, self.history[0], self.history[1]];
+        self.address_valid = true;
+
+        match self.exception_pending.take() {
+            Some(number) => events.push(TraceEvent::Exception {
+                number: Some(number),
+                return_address: Some(address),
+            }),
+            None => events.push(TraceEvent::Branch {
+                source: None,
+                target: address,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ASYNC: [u8; 12] = [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+    ];
+    /// Trace info without any sections, followed by trace on.
+    const TRACE_INFO: [u8; 3] = [0x01, 0x00, 0x04];
+
+    fn long_is1(address: u32) -> Vec<u8> {
+        vec![
+            0x9B,
+            ((address >> 1) & 0x7F) as u8,
+            (address >> 8) as u8,
+            (address >> 16) as u8,
+            (address >> 24) as u8,
+        ]
+    }
+
+    fn branch(target: u32) -> TraceEvent {
+        TraceEvent::Branch {
+            source: None,
+            target,
+        }
+    }
+
+    #[test]
+    fn addresses() {
+        let mut decoder = EtmV4Decoder::new();
+
+        let mut data = ASYNC.to_vec();
+        data.extend_from_slice(&TRACE_INFO);
+        data.extend_from_slice(&long_is1(0x0800_1234));
+        // Atoms
+        data.extend_from_slice(&[0xF7, 0xDB]);
+        // Short IS1 address, bits [7:1]
+        data.extend_from_slice(&[0x96, 0x40 >> 1]);
+        // Short IS1 address, bits [15:1]
+        data.extend_from_slice(&[0x96, 0x80 | (0x56 >> 1), 0x78]);
+        // Exact match with the address before the last one
+        data.push(0x91);
+
+        assert_eq!(
+            decoder.decode(&data),
+            vec![
+                branch(0x0800_1234),
+                branch(0x0800_1240),
+                branch(0x0800_7856),
+                branch(0x0800_1240),
+            ]
+        );
+    }
+
+    #[test]
+    fn exception() {
+        let mut decoder = EtmV4Decoder::new();
+
+        let mut data = ASYNC.to_vec();
+        data.extend_from_slice(&TRACE_INFO);
+        data.extend_from_slice(&long_is1(0x0800_1234));
+        // HardFault, with the return address in the following address packet
+        data.extend_from_slice(&[0x06, 3 << 1]);
+        data.extend_from_slice(&long_is1(0x0800_1300));
+        data.push(0x07);
+
+        assert_eq!(
+            decoder.decode(&data),
+            vec![
+                branch(0x0800_1234),
+                TraceEvent::Exception {
+                    number: Some(3),
+                    return_address: Some(0x0800_1300),
+                },
+                TraceEvent::ExceptionReturn,
+            ]
+        );
+    }
+
+    #[test]
+    fn cycle_counts() {
+        let mut decoder = EtmV4Decoder::new();
+
+        let mut data = ASYNC.to_vec();
+        data.extend_from_slice(&TRACE_INFO);
+        data.extend_from_slice(&long_is1(0x0800_1234));
+        // Cycle count format 2
+        data.extend_from_slice(&[0x0C, 0x21]);
+        // Cycle count format 1, with a two byte count
+        data.extend_from_slice(&[0x0E, 0x85, 0x01]);
+        // Cycle count format 1, with an unknown count
+        data.push(0x0F);
+        // Cycle count format 3
+        data.push(0x13);
+        data.extend_from_slice(&long_is1(0x0800_2000));
+
+        assert_eq!(
+            decoder.decode(&data),
+            vec![branch(0x0800_1234), branch(0x0800_2000)]
+        );
+    }
+
+    #[test]
+    fn overflow_resets_address() {
+        let mut decoder = EtmV4Decoder::new();
+
+        let mut data = ASYNC.to_vec();
+        data.extend_from_slice(&TRACE_INFO);
+        data.extend_from_slice(&long_is1(0x0800_1234));
+        data.extend_from_slice(&[0x00, 0x05]);
+        // Short addresses can't be resolved until the next full address.
+        data.extend_from_slice(&[0x96, 0x20]);
+        data.extend_from_slice(&long_is1(0x0800_2000));
+
+        assert_eq!(
+            decoder.decode(&data),
+            vec![
+                branch(0x0800_1234),
+                TraceEvent::Overflow,
+                branch(0x0800_2000),
+            ]
+        );
+    }
+}
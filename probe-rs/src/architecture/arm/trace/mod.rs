@@ -0,0 +1,310 @@
+//! Instruction trace capture and decoding.
+//!
+//! Instruction trace records the flow of the program executed by a core. It is generated either
+//! by the ETM (Embedded Trace Macrocell) of a Cortex-M3/M4/M7/M33, or by the MTB (Micro Trace
+//! Buffer) of a Cortex-M0+.
+//!
+//! The ETM trace is collected through one of the [`TraceSink`]s, while the MTB stores its trace in
+//! the on-chip SRAM. Either way, the trace is decoded into a sequence of [`TraceEvent`]s, which
+//! contain the addresses where execution continued after every taken branch.
+
+mod etmv3;
+mod etmv4;
+
+pub use etmv3::EtmV3Decoder;
+pub use etmv4::EtmV4Decoder;
+
+use super::component::{EtmVersion, MtbEntry, TraceDeformatter, TraceSink};
+
+/// The trace bus ID used for the ETM trace stream.
+pub(crate) const ETM_TRACE_ID: u8 = 1;
+
+/// The trace unit to record the instruction trace with.
+#[derive(Debug, Copy, Clone)]
+pub enum InstructionTraceSource {
+    /// Record the trace with the ETM, and collect it through the given sink.
+    ///
+    /// Serial sinks are switched to continuous formatting, because the ETM trace has to be
+    /// separated from the other trace sources.
+    Etm(TraceSink),
+    /// Record the trace into the SRAM with the MTB.
+    Mtb,
+}
+
+/// An event in the decoded instruction trace.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// Execution continued at `target`.
+    ///
+    /// The `source` of the branch is only known for the MTB. The first event after the trace
+    /// (re-)synchronized is reported as a branch without source as well.
+    Branch {
+        /// The address of the branch instruction, if known.
+        source: Option<u32>,
+        /// The address of the next executed instruction.
+        target: u32,
+    },
+    /// An exception was taken.
+    Exception {
+        /// The exception number, if known.
+        number: Option<u16>,
+        /// The address execution will return to from the exception, if known.
+        return_address: Option<u32>,
+    },
+    /// Returned from an exception.
+    ExceptionReturn,
+    /// The trace unit could not keep up, and some trace was lost.
+    Overflow,
+}
+
+impl TraceEvent {
+    /// The address of the instruction executed next, if the event has one.
+    pub fn target(&self) -> Option<u32> {
+        match self {
+            TraceEvent::Branch { target, .. } => Some(*target),
+            _ => None,
+        }
+    }
+}
+
+/// Convert the entries read from the MTB into trace events.
+pub fn decode_mtb(entries: &[MtbEntry]) -> Vec<TraceEvent> {
+    let mut events = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let target = entry.destination & !1;
+        // Bit 0 of the source (the A-bit) marks exception entry and return.
+        if entry.source & 1 != 0 {
+            let source = entry.source & !1;
+            if source >= 0xF000_0000 {
+                // Returning from an exception branches to an EXC_RETURN value.
+                events.push(TraceEvent::ExceptionReturn);
+            } else {
+                events.push(TraceEvent::Exception {
+                    number: None,
+                    return_address: Some(source),
+                });
+            }
+            events.push(TraceEvent::Branch {
+                source: None,
+                target,
+            });
+        } else {
+            events.push(TraceEvent::Branch {
+                source: Some(entry.source),
+                target,
+            });
+        }
+    }
+
+    events
+}
+
+/// A decoder for the trace stream of an ETM.
+#[derive(Debug)]
+pub(crate) enum EtmDecoder {
+    V3(EtmV3Decoder),
+    V4(EtmV4Decoder),
+}
+
+impl EtmDecoder {
+    pub(crate) fn new(version: EtmVersion) -> Self {
+        match version {
+            EtmVersion::V3 => EtmDecoder::V3(EtmV3Decoder::new()),
+            EtmVersion::V4 => EtmDecoder::V4(EtmV4Decoder::new()),
+        }
+    }
+
+    pub(crate) fn decode(&mut self, data: &[u8]) -> Vec<TraceEvent> {
+        match self {
+            EtmDecoder::V3(decoder) => decoder.decode(data),
+            EtmDecoder::V4(decoder) => decoder.decode(data),
+        }
+    }
+}
+
+/// The state of a configured instruction trace.
+#[derive(Debug)]
+pub(crate) enum InstructionTrace {
+    Etm {
+        sink: TraceSink,
+        deformatter: TraceDeformatter,
+        decoder: EtmDecoder,
+    },
+    Mtb,
+}
+
+/// A reader for a trace packet which might not have been received completely yet.
+///
+/// All accessors return `None` when the packet is incomplete.
+pub(super) struct PacketReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> PacketReader<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    /// The number of bytes consumed so far.
+    pub(super) fn consumed(&self) -> usize {
+        self.offset
+    }
+
+    pub(super) fn byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.offset)?;
+        self.offset += 1;
+        Some(byte)
+    }
+
+    pub(super) fn skip(&mut self, count: usize) -> Option<()> {
+        if self.offset + count > self.data.len() {
+            return None;
+        }
+        self.offset += count;
+        Some(())
+    }
+
+    /// Read a value encoded with a continuation bit in bit 7 of every byte, least significant
+    /// bits first. The last of `max_bytes` bytes has no continuation bit.
+    pub(super) fn continued(&mut self, max_bytes: usize) -> Option<u64> {
+        let mut value = 0;
+        for index in 0..max_bytes {
+            let byte = self.byte()?;
+            if index == max_bytes - 1 {
+                value |= (byte as u64) << (7 * index);
+                break;
+            }
+            value |= ((byte & 0x7F) as u64) << (7 * index);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Some(value)
+    }
+}
+
+/// Find the end of the first alignment synchronization sequence in `data`, which consists of at
+/// least `zeros` zero bytes followed by `0x80`.
+///
+/// If there is none, the number of bytes which can be discarded is returned as error. Trailing
+/// zero bytes might be the start of a synchronization sequence, and have to be kept.
+pub(super) fn find_alignment_sync(data: &[u8], zeros: usize) -> Result<usize, usize> {
+    let mut run = 0;
+    for (index, &byte) in data.iter().enumerate() {
+        match byte {
+            0x00 => run += 1,
+            0x80 if run >= zeros => return Ok(index + 1),
+            _ => run = 0,
+        }
+    }
+
+    Err(data.len() - run)
+}
+
+/// The result of parsing a single packet.
+pub(super) enum Parsed {
+    /// A complete packet was parsed.
+    Packet,
+    /// The packet header is unknown, or the packet is malformed.
+    Invalid(u8),
+}
+
+/// Decode packets from the `pending` buffer, and remove the consumed bytes from it.
+///
+/// The decoder only starts after an alignment synchronization sequence has been found, and
+/// returns to searching for one when an invalid packet is encountered.
+pub(super) fn decode_packets(
+    pending: &mut Vec<u8>,
+    synced: &mut bool,
+    sync_zeros: usize,
+    mut parse: impl FnMut(&mut PacketReader, &mut Vec<TraceEvent>) -> Option<Parsed>,
+) -> Vec<TraceEvent> {
+    let mut events = Vec::new();
+    let mut offset = 0;
+
+    while offset < pending.len() {
+        if !*synced {
+            match find_alignment_sync(&pending[offset..], sync_zeros) {
+                Ok(end) => {
+                    offset += end;
+                    *synced = true;
+                }
+                Err(discard) => {
+                    offset += discard;
+                    break;
+                }
+            }
+            continue;
+        }
+
+        let mut reader = PacketReader::new(&pending[offset..]);
+        let mut packet_events = Vec::new();
+        match parse(&mut reader, &mut packet_events) {
+            Some(Parsed::Packet) => {
+                offset += reader.consumed();
+                events.extend(packet_events);
+            }
+            Some(Parsed::Invalid(header)) => {
+                tracing::warn!(
+                    "Invalid trace packet with header {:#04x}, waiting for synchronization",
+                    header
+                );
+                *synced = false;
+                offset += 1;
+            }
+            // Wait for the rest of the packet.
+            None => break,
+        }
+    }
+
+    pending.drain(..offset);
+    events
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mtb_entries() {
+        let entries = [
+            MtbEntry {
+                source: 0x0000_0120,
+                destination: 0x0000_0200 | 1,
+            },
+            MtbEntry {
+                source: 0x0000_0204 | 1,
+                destination: 0x0000_00C0,
+            },
+            MtbEntry {
+                source: 0xFFFF_FFF9 | 1,
+                destination: 0x0000_0206,
+            },
+        ];
+
+        assert_eq!(
+            decode_mtb(&entries),
+            vec![
+                TraceEvent::Branch {
+                    source: Some(0x0000_0120),
+                    target: 0x0000_0200,
+                },
+                TraceEvent::Exception {
+                    number: None,
+                    return_address: Some(0x0000_0204),
+                },
+                TraceEvent::Branch {
+                    source: None,
+                    target: 0x0000_00C0,
+                },
+                TraceEvent::ExceptionReturn,
+                TraceEvent::Branch {
+                    source: None,
+                    target: 0x0000_0206,
+                },
+            ]
+        );
+    }
+}
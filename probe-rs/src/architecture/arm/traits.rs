@@ -1,4 +1,5 @@
 use crate::{DebugProbe, DebugProbeError};
+use probe_rs_target::ArmCoreAccessOptions;
 
 use super::ArmError;
 
@@ -41,13 +42,71 @@ pub enum DpAddress {
     Multidrop(u32),
 }
 
+impl From<&ArmCoreAccessOptions> for DpAddress {
+    fn from(options: &ArmCoreAccessOptions) -> Self {
+        match options.dp_targetsel() {
+            Some(targetsel) => DpAddress::Multidrop(targetsel),
+            None => DpAddress::Default,
+        }
+    }
+}
+
 /// Access port address.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct ApAddress {
     /// The address of the debug port this access port belongs to.
     pub dp: DpAddress,
-    /// The access port number.
-    pub ap: u8,
+    /// The access port on the debug port.
+    pub ap: ApPort,
+}
+
+impl From<&ArmCoreAccessOptions> for ApAddress {
+    fn from(options: &ArmCoreAccessOptions) -> Self {
+        ApAddress {
+            dp: options.into(),
+            ap: match options.ap_address {
+                Some(address) => ApPort::Address(address),
+                None => ApPort::Index(options.ap),
+            },
+        }
+    }
+}
+
+/// The location of an access port on its debug port.
+///
+/// ADIv5 debug ports select an access port by its number, while the access ports of
+/// an ADIv6 debug port are mapped into the address space of the debug port.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ApPort {
+    /// The access port number, as selected by `SELECT.APSEL` on an ADIv5 debug port.
+    Index(u8),
+    /// The base address of the access port in the address space of an ADIv6 debug port.
+    Address(u64),
+}
+
+impl ApPort {
+    /// The access port number, if the access port is selected by its number.
+    pub fn index(&self) -> Option<u8> {
+        match self {
+            ApPort::Index(index) => Some(*index),
+            ApPort::Address(_) => None,
+        }
+    }
+}
+
+impl From<u8> for ApPort {
+    fn from(index: u8) -> Self {
+        ApPort::Index(index)
+    }
+}
+
+impl std::fmt::Display for ApPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApPort::Index(index) => write!(f, "{index}"),
+            ApPort::Address(address) => write!(f, "{address:#x}"),
+        }
+    }
 }
 
 /// Low-level DAP register access.
@@ -20,6 +20,18 @@ pub const fn sw(offset: u32, base: u32, width: u32, source: u32) -> u32 {
     offset_upper << 25 | source << 20 | base << 15 | width << 12 | offset_lower << 7 | opcode
 }
 
+/// Assemble a `ld` instruction, which loads a doubleword on RV64.
+#[allow(dead_code)]
+pub fn ld(offset: u16, base: u8, destination: u8) -> u32 {
+    lw(offset, base, 0b011, destination)
+}
+
+/// Assemble a `sd` instruction, which stores a doubleword on RV64.
+#[allow(dead_code)]
+pub const fn sd(offset: u32, base: u32, source: u32) -> u32 {
+    sw(offset, base, 0b011, source)
+}
+
 /// Assemble a `addi` instruction.
 pub fn addi(source: u8, destination: u8, immediate: u16) -> u32 {
     let opcode = 0b001_0011;
@@ -77,7 +89,7 @@ fn i_type_instruction(opcode: u8, rs1: u8, funct3: u8, rd: u8, imm: u16) -> u32
 
 #[cfg(test)]
 mod test {
-    use super::{csrr, csrw, lw, sw};
+    use super::{csrr, csrw, ld, lw, sd, sw};
 
     #[test]
     fn assemble_csrr() {
@@ -126,4 +138,26 @@ mod test {
 
         assert_eq!(assembled, expected);
     }
+
+    #[test]
+    fn assemble_sd() {
+        // Assembly output of assembly 'sd      x1, 4(x2)'
+        //
+        let expected = 0x00113223;
+
+        let assembled = sd(4, 2, 1);
+
+        assert_eq!(assembled, expected);
+    }
+
+    #[test]
+    fn assemble_ld() {
+        // Assembly output of assembly 'ld      x3, 8(x4)'
+        //
+        let expected = 0x00823183;
+
+        let assembled = ld(8, 4, 3);
+
+        assert_eq!(assembled, expected);
+    }
 }
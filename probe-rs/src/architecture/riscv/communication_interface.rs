@@ -11,7 +11,7 @@ use super::{
 use crate::DebugProbeError;
 use crate::{
     architecture::riscv::*,
-    probe::{CommandResult, DeferredResultIndex},
+    probe::{ChainParams, CommandResult, DeferredResultIndex, JtagChainItem},
 };
 use crate::{MemoryInterface, Probe};
 
@@ -20,6 +20,7 @@ use crate::{probe::JTAGAccess, Error as ProbeRsError, RegisterId};
 use crate::memory::valid_32bit_address;
 
 use bitfield::bitfield;
+use probe_rs_target::ScanChainElement;
 use std::{
     collections::HashMap,
     time::{Duration, Instant},
@@ -70,6 +71,12 @@ pub enum RiscvError {
     /// The connected target is not a RISCV device.
     #[error("Connected target is not a RISCV device.")]
     NoRiscvTarget,
+    /// The hart with the given index does not exist on the debug module.
+    #[error("Hart {0} does not exist on the debug module.")]
+    HartUnavailable(u32),
+    /// The TAP with the given index does not exist in the JTAG scan chain.
+    #[error("JTAG TAP {0} does not exist in the scan chain.")]
+    TapUnavailable(usize),
 }
 
 impl From<RiscvError> for ProbeRsError {
@@ -207,6 +214,18 @@ pub struct RiscvCommunicationInterfaceState {
     /// Number of harts
     num_harts: u32,
 
+    /// Index of the selected hart
+    hart: u32,
+
+    /// Multiple harts can be selected using the hart array mask
+    supports_hart_array: bool,
+
+    /// Width of the general purpose registers of the hart
+    xlen: Xlen,
+
+    /// Width of system bus addresses in bits
+    sbasize: u8,
+
     memory_access_info: HashMap<RiscvBusAccess, MemoryAccessMethod>,
 
     /// describes, if the given register can be read / written with an
@@ -249,6 +268,15 @@ impl RiscvCommunicationInterfaceState {
             // We assume only a singe hart exisits initially
             num_harts: 1,
 
+            hart: 0,
+
+            supports_hart_array: false,
+
+            // Set by the core, depending on its type.
+            xlen: Xlen::X32,
+
+            sbasize: 0,
+
             memory_access_info: HashMap::new(),
 
             abstract_cmd_register_info: HashMap::new(),
@@ -278,6 +306,12 @@ pub struct RiscvCommunicationInterface {
     /// communicate with the Debug Module on the target chip.
     dtm: Dtm,
     state: RiscvCommunicationInterfaceState,
+
+    /// The JTAG scan chain, scanned when a TAP is selected the first time.
+    chain: Option<Vec<JtagChainItem>>,
+
+    /// The TAP of the selected debug module, `None` if the TAP selected by the probe is used.
+    tap: Option<usize>,
 }
 
 impl RiscvCommunicationInterface {
@@ -286,7 +320,12 @@ impl RiscvCommunicationInterface {
         let state = RiscvCommunicationInterfaceState::new();
         let dtm = Dtm::new(probe)?;
 
-        let mut s = Self { dtm, state };
+        let mut s = Self {
+            dtm,
+            state,
+            chain: None,
+            tap: None,
+        };
 
         if let Err(err) = s.enter_debug_mode() {
             return Err((s.dtm.probe, err));
@@ -305,6 +344,250 @@ impl RiscvCommunicationInterface {
         self.dtm.read_idcode()
     }
 
+    /// The width of the general purpose registers of the hart.
+    pub fn xlen(&self) -> Xlen {
+        self.state.xlen
+    }
+
+    /// Set the width of the general purpose registers of the hart.
+    ///
+    /// This determines the width of register accesses, and of the addresses used for memory accesses.
+    pub(crate) fn set_xlen(&mut self, xlen: Xlen) {
+        self.state.xlen = xlen;
+    }
+
+    /// The number of harts connected to the debug module.
+    pub fn num_harts(&self) -> u32 {
+        self.state.num_harts
+    }
+
+    /// The index of the selected hart.
+    pub fn selected_hart(&self) -> u32 {
+        self.state.hart
+    }
+
+    /// Select the debug module with the JTAG TAP at position `index` of the scan chain, counted
+    /// from TDO.
+    ///
+    /// IR lengths given in `description` are used instead of detecting them when scanning the chain.
+    /// All further operations are performed on the debug module of the selected TAP.
+    pub fn select_tap(
+        &mut self,
+        index: usize,
+        description: Option<&[ScanChainElement]>,
+    ) -> Result<(), RiscvError> {
+        if self.tap == Some(index) {
+            return Ok(());
+        }
+
+        let chain = match self.chain.take() {
+            Some(chain) => chain,
+            None => self.dtm.probe.scan_chain(description)?,
+        };
+
+        if index >= chain.len() {
+            self.chain = Some(chain);
+            return Err(RiscvError::TapUnavailable(index));
+        }
+
+        tracing::debug!("Selecting TAP {}", index);
+
+        let params = ChainParams::from_chain(&chain, index)?;
+        self.chain = Some(chain);
+        self.dtm.probe.set_chain_params(params)?;
+        self.tap = Some(index);
+
+        // The debug module behind the new TAP is set up from scratch.
+        let xlen = self.state.xlen;
+        self.state = RiscvCommunicationInterfaceState::new();
+        self.state.xlen = xlen;
+
+        self.dtm.configure()?;
+        self.enter_debug_mode()
+    }
+
+    /// Select the hart which is accessed by all further operations.
+    pub fn select_hart(&mut self, hart: u32) -> Result<(), RiscvError> {
+        if hart >= self.state.num_harts {
+            return Err(RiscvError::HartUnavailable(hart));
+        }
+
+        if hart == self.state.hart {
+            return Ok(());
+        }
+
+        tracing::debug!("Selecting hart {}", hart);
+
+        let mut control = Dmcontrol(0);
+        control.set_dmactive(true);
+        control.set_hartsel(hart);
+
+        self.write_dm_register(control)?;
+
+        self.state.hart = hart;
+
+        // Harts don't necessarily support the same registers
+        self.state.abstract_cmd_register_info.clear();
+
+        let hartinfo: Hartinfo = self.read_dm_register()?;
+        self.state.nscratch = hartinfo.nscratch() as u8;
+
+        Ok(())
+    }
+
+    /// An empty `dmcontrol` value, which keeps the selected hart selected when written.
+    pub(crate) fn dmcontrol(&self) -> Dmcontrol {
+        let mut control = Dmcontrol(0);
+        control.set_hartsel(self.state.hart);
+        control
+    }
+
+    /// Put the given harts into a halt group.
+    ///
+    /// When one hart of a halt group halts, the debug module halts all other harts of
+    /// the group as well. Returns `false` if the debug module doesn't support halt groups.
+    pub fn set_halt_group(&mut self, harts: &[u32], group: u8) -> Result<bool, RiscvError> {
+        let selected = self.state.hart;
+        let mut supported = true;
+
+        for &hart in harts {
+            self.select_hart(hart)?;
+
+            let mut dmcs2 = Dmcs2(0);
+            dmcs2.set_hgwrite(true);
+            dmcs2.set_haltgroup(group as u32);
+
+            self.write_dm_register(dmcs2)?;
+
+            // Without support for halt groups, the group always reads as 0.
+            let readback: Dmcs2 = self.read_dm_register()?;
+
+            if readback.haltgroup() != group as u32 {
+                supported = false;
+                break;
+            }
+        }
+
+        self.select_hart(selected)?;
+
+        Ok(supported)
+    }
+
+    /// Halt the given harts.
+    ///
+    /// If the debug module supports the hart array mask, all harts are halted at the
+    /// same time. Otherwise they are halted one after the other.
+    pub fn halt_harts(&mut self, harts: &[u32], timeout: Duration) -> Result<(), RiscvError> {
+        self.request_harts(
+            harts,
+            timeout,
+            |control| control.set_haltreq(true),
+            Dmstatus::allhalted,
+        )
+    }
+
+    /// Resume the given harts.
+    ///
+    /// If the debug module supports the hart array mask, all harts are resumed at the
+    /// same time. Otherwise they are resumed one after the other.
+    pub fn resume_harts(&mut self, harts: &[u32], timeout: Duration) -> Result<(), RiscvError> {
+        self.request_harts(
+            harts,
+            timeout,
+            |control| control.set_resumereq(true),
+            Dmstatus::allresumeack,
+        )
+    }
+
+    fn request_harts(
+        &mut self,
+        harts: &[u32],
+        timeout: Duration,
+        request: impl Fn(&mut Dmcontrol),
+        done: impl Fn(&Dmstatus) -> bool,
+    ) -> Result<(), RiscvError> {
+        if let Some(&hart) = harts.iter().find(|&&hart| hart >= self.state.num_harts) {
+            return Err(RiscvError::HartUnavailable(hart));
+        }
+
+        if harts.is_empty() {
+            return Ok(());
+        }
+
+        let selected = self.state.hart;
+
+        let result = if self.state.supports_hart_array && harts.iter().all(|&hart| hart < 32) {
+            // The hart selected by `hartsel` is always part of the request,
+            // so it has to be one of the requested harts.
+            self.state.hart = harts[0];
+
+            // The first window of the hart array covers harts 0 to 31
+            self.write_dm_register(Hawindowsel(0))?;
+            self.write_dm_register(Hawindow(
+                harts.iter().fold(0, |mask, hart| mask | 1 << hart),
+            ))?;
+
+            let result = self.request_selected_harts(true, timeout, &request, &done);
+            self.state.hart = selected;
+
+            result
+        } else {
+            harts.iter().try_for_each(|&hart| {
+                self.select_hart(hart)?;
+                self.request_selected_harts(false, timeout, &request, &done)
+            })
+        };
+
+        // Restore the selection, including the information about the selected hart.
+        self.select_hart(selected)?;
+
+        // This also clears the hart array mask.
+        let mut control = self.dmcontrol();
+        control.set_dmactive(true);
+        self.write_dm_register(control)?;
+
+        result
+    }
+
+    /// Send a request to the selected harts, and wait until it is completed.
+    fn request_selected_harts(
+        &mut self,
+        hasel: bool,
+        timeout: Duration,
+        request: impl Fn(&mut Dmcontrol),
+        done: impl Fn(&Dmstatus) -> bool,
+    ) -> Result<(), RiscvError> {
+        let mut control = self.dmcontrol();
+        control.set_dmactive(true);
+        control.set_hasel(hasel);
+        request(&mut control);
+
+        self.write_dm_register(control)?;
+
+        let start = Instant::now();
+
+        let result = loop {
+            let status: Dmstatus = self.read_dm_register()?;
+
+            if done(&status) {
+                break Ok(());
+            }
+
+            if start.elapsed() > timeout {
+                break Err(RiscvError::Timeout);
+            }
+        };
+
+        // Clear the request
+        let mut control = self.dmcontrol();
+        control.set_dmactive(true);
+        control.set_hasel(hasel);
+
+        self.write_dm_register(control)?;
+
+        result
+    }
+
     fn enter_debug_mode(&mut self) -> Result<(), RiscvError> {
         // We need a jtag interface
 
@@ -389,6 +672,21 @@ impl RiscvCommunicationInterface {
 
         self.state.num_harts = num_harts;
 
+        // Check if multiple harts can be selected at once
+        let mut control = Dmcontrol(0);
+        control.set_dmactive(true);
+        control.set_hasel(true);
+
+        self.write_dm_register(control)?;
+
+        let control: Dmcontrol = self.read_dm_register()?;
+
+        self.state.supports_hart_array = control.hasel();
+        tracing::debug!(
+            "Support for hart array mask: {}",
+            self.state.supports_hart_array
+        );
+
         // Select hart 0 again
         let mut control = Dmcontrol(0);
         control.set_hartsel(0);
@@ -438,6 +736,8 @@ impl RiscvCommunicationInterface {
         // the system bus access conforms to the debug
         // specification 13.2.
         if sbcs.sbversion() == 1 {
+            self.state.sbasize = sbcs.sbasize() as u8;
+
             // When possible, we use system bus access for memory access
 
             if sbcs.sbaccess8() {
@@ -595,7 +895,7 @@ impl RiscvCommunicationInterface {
     }
 
     /// Perform a single read from a memory location, using system bus access.
-    fn perform_memory_read_sysbus<V: RiscvValue>(&mut self, address: u32) -> Result<V, RiscvError> {
+    fn perform_memory_read_sysbus<V: RiscvValue>(&mut self, address: u64) -> Result<V, RiscvError> {
         let mut sbcs = Sbcs(0);
 
         sbcs.set_sbaccess(V::WIDTH as u32);
@@ -603,7 +903,7 @@ impl RiscvCommunicationInterface {
 
         self.write_dm_register(sbcs)?;
 
-        self.write_dm_register(Sbaddress0(address))?;
+        self.write_sbaddress(address)?;
         let data = self.read_large_dtm_register::<V, Sbdata>()?;
 
         // Check that the read was succesful
@@ -619,9 +919,9 @@ impl RiscvCommunicationInterface {
     /// Perform multiple reads from consecutive memory locations
     /// using system bus access.
     /// Only reads up to a width of 32 bits are currently supported.
-    fn perform_memory_read_multiple_sysbus<V: RiscvValue32>(
+    fn perform_memory_read_multiple_sysbus<V: RiscvValue64>(
         &mut self,
-        address: u32,
+        address: u64,
         data: &mut [V],
     ) -> Result<(), RiscvError> {
         let mut sbcs = Sbcs(0);
@@ -635,7 +935,7 @@ impl RiscvCommunicationInterface {
 
         self.schedule_write_dm_register(sbcs)?;
 
-        self.schedule_write_dm_register(Sbaddress0(address))?;
+        self.schedule_write_sbaddress(address)?;
 
         let data_len = data.len();
 
@@ -658,7 +958,7 @@ impl RiscvCommunicationInterface {
         let result = result?;
         for (out_index, &idx) in read_results.iter().enumerate() {
             data[out_index] = match result[idx] {
-                CommandResult::U32(data) => V::from_register_value(data),
+                CommandResult::U32(data) => V::from_register_value(data as u64),
                 _ => panic!("Internal error occurred."),
             };
         }
@@ -679,22 +979,22 @@ impl RiscvCommunicationInterface {
     }
 
     /// Perform memory read from a single location using the program buffer.
-    /// Only reads up to a width of 32 bits are currently supported.
-    fn perform_memory_read_progbuf<V: RiscvValue32>(
+    /// Reads of 64 bits are only supported on RV64 harts.
+    fn perform_memory_read_progbuf<V: RiscvValue64>(
         &mut self,
-        address: u32,
+        address: u64,
     ) -> Result<V, RiscvError> {
         // assemble
         //  lb s1, 0(s0)
 
         // Backup register s0
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
+        let s0 = self.abstract_cmd_register_read(register::S0)?;
 
         let lw_command: u32 = assembly::lw(0, 8, V::WIDTH as u8, 8);
 
         self.setup_program_buffer(&[lw_command])?;
 
-        self.write_dm_register(Data0(address))?;
+        self.write_arg0(address)?;
 
         // Write s0, then execute program buffer
         let mut command = AccessRegisterCommand(0);
@@ -702,12 +1002,12 @@ impl RiscvCommunicationInterface {
         command.set_transfer(true);
         command.set_write(true);
 
-        // registers are 32 bit, so we have size 2 here
-        command.set_aarsize(RiscvBusAccess::A32);
+        // transfer the whole register
+        command.set_aarsize(self.state.xlen.register_access());
         command.set_postexec(true);
 
         // register s0, ie. 0x1008
-        command.set_regno((register::S0).id.0 as u32);
+        command.set_regno(register::S0.0 as u32);
 
         self.write_dm_register(command)?;
 
@@ -720,22 +1020,22 @@ impl RiscvCommunicationInterface {
         }
 
         // Read back s0
-        let value = self.abstract_cmd_register_read(&register::S0)?;
+        let value = self.abstract_cmd_register_read(register::S0)?;
 
         // Restore s0 register
-        self.abstract_cmd_register_write(&register::S0, s0)?;
+        self.abstract_cmd_register_write(register::S0, s0)?;
 
         Ok(V::from_register_value(value))
     }
 
-    fn perform_memory_read_multiple_progbuf<V: RiscvValue32>(
+    fn perform_memory_read_multiple_progbuf<V: RiscvValue64>(
         &mut self,
-        address: u32,
+        address: u64,
         data: &mut [V],
     ) -> Result<(), RiscvError> {
         // Backup registers s0 and s1
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
-        let s1 = self.abstract_cmd_register_read(&register::S1)?;
+        let s0 = self.abstract_cmd_register_read(register::S0)?;
+        let s1 = self.abstract_cmd_register_read(register::S1)?;
 
         // Load a word from address in register 8 (S0), with offset 0, into register 9 (S9)
         let lw_command: u32 = assembly::lw(0, 8, V::WIDTH as u8, 9);
@@ -745,7 +1045,7 @@ impl RiscvCommunicationInterface {
             assembly::addi(8, 8, V::WIDTH.byte_width() as u16),
         ])?;
 
-        self.write_dm_register(Data0(address))?;
+        self.write_arg0(address)?;
 
         // Write s0, then execute program buffer
         let mut command = AccessRegisterCommand(0);
@@ -753,12 +1053,12 @@ impl RiscvCommunicationInterface {
         command.set_transfer(true);
         command.set_write(true);
 
-        // registers are 32 bit, so we have size 2 here
-        command.set_aarsize(RiscvBusAccess::A32);
+        // transfer the whole register
+        command.set_aarsize(self.state.xlen.register_access());
         command.set_postexec(true);
 
         // register s0, ie. 0x1008
-        command.set_regno((register::S0).id.0 as u32);
+        command.set_regno(register::S0.0 as u32);
 
         self.write_dm_register(command)?;
 
@@ -770,21 +1070,21 @@ impl RiscvCommunicationInterface {
             command.set_transfer(true);
             command.set_write(false);
 
-            // registers are 32 bit, so we have size 2 here
-            command.set_aarsize(RiscvBusAccess::A32);
+            // transfer the whole register
+            command.set_aarsize(self.state.xlen.register_access());
             command.set_postexec(true);
 
-            command.set_regno((register::S1).id.0 as u32);
+            command.set_regno(register::S1.0 as u32);
 
             self.write_dm_register(command)?;
 
             // Read back s1
-            let value: Data0 = self.read_dm_register()?;
+            let value = self.read_arg0()?;
 
-            *word = V::from_register_value(value.0);
+            *word = V::from_register_value(value);
         }
 
-        let last_value = self.abstract_cmd_register_read(&register::S1)?;
+        let last_value = self.abstract_cmd_register_read(register::S1)?;
 
         data[data.len() - 1] = V::from_register_value(last_value);
 
@@ -796,8 +1096,8 @@ impl RiscvCommunicationInterface {
             ));
         }
 
-        self.abstract_cmd_register_write(&register::S0, s0)?;
-        self.abstract_cmd_register_write(&register::S1, s1)?;
+        self.abstract_cmd_register_write(register::S0, s0)?;
+        self.abstract_cmd_register_write(register::S1, s1)?;
 
         Ok(())
     }
@@ -805,7 +1105,7 @@ impl RiscvCommunicationInterface {
     /// Memory write using system bus
     fn perform_memory_write_sysbus<V: RiscvValue>(
         &mut self,
-        address: u32,
+        address: u64,
         data: &[V],
     ) -> Result<(), RiscvError> {
         let mut sbcs = Sbcs(0);
@@ -816,7 +1116,7 @@ impl RiscvCommunicationInterface {
 
         self.schedule_write_dm_register(sbcs)?;
 
-        self.schedule_write_dm_register(Sbaddress0(address))?;
+        self.schedule_write_sbaddress(address)?;
 
         for value in data {
             self.schedule_write_large_dtm_register::<V, Sbdata>(*value)?;
@@ -843,10 +1143,10 @@ impl RiscvCommunicationInterface {
     }
 
     /// Perform memory write to a single location using the program buffer.
-    /// Only writes up to a width of 32 bits are currently supported.
-    fn perform_memory_write_progbuf<V: RiscvValue32>(
+    /// Writes of 64 bits are only supported on RV64 harts.
+    fn perform_memory_write_progbuf<V: RiscvValue64>(
         &mut self,
-        address: u32,
+        address: u64,
         data: V,
     ) -> Result<(), RiscvError> {
         tracing::debug!(
@@ -856,18 +1156,18 @@ impl RiscvCommunicationInterface {
         );
 
         // Backup registers s0 and s1
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
-        let s1 = self.abstract_cmd_register_read(&register::S1)?;
+        let s0 = self.abstract_cmd_register_read(register::S0)?;
+        let s1 = self.abstract_cmd_register_read(register::S1)?;
 
         let sw_command = assembly::sw(0, 8, V::WIDTH as u32, 9);
 
         self.setup_program_buffer(&[sw_command])?;
 
         // write address into s0
-        self.abstract_cmd_register_write(&register::S0, address)?;
+        self.abstract_cmd_register_write(register::S0, address)?;
 
         // write data into data 0
-        self.write_dm_register(Data0(data.into()))?;
+        self.write_arg0(data.into())?;
 
         // Write s1, then execute program buffer
         let mut command = AccessRegisterCommand(0);
@@ -875,12 +1175,12 @@ impl RiscvCommunicationInterface {
         command.set_transfer(true);
         command.set_write(true);
 
-        // registers are 32 bit, so we have size 2 here
-        command.set_aarsize(RiscvBusAccess::A32);
+        // transfer the whole register
+        command.set_aarsize(self.state.xlen.register_access());
         command.set_postexec(true);
 
         // register s1, ie. 0x1009
-        command.set_regno((register::S1).id.0 as u32);
+        command.set_regno(register::S1.0 as u32);
 
         self.write_dm_register(command)?;
 
@@ -900,21 +1200,21 @@ impl RiscvCommunicationInterface {
 
         // Restore register s0 and s1
 
-        self.abstract_cmd_register_write(&register::S0, s0)?;
-        self.abstract_cmd_register_write(&register::S1, s1)?;
+        self.abstract_cmd_register_write(register::S0, s0)?;
+        self.abstract_cmd_register_write(register::S1, s1)?;
 
         Ok(())
     }
 
     /// Perform multiple memory writes to consecutive locations using the program buffer.
-    /// Only writes up to a width of 32 bits are currently supported.
-    fn perform_memory_write_multiple_progbuf<V: RiscvValue32>(
+    /// Writes of 64 bits are only supported on RV64 harts.
+    fn perform_memory_write_multiple_progbuf<V: RiscvValue64>(
         &mut self,
-        address: u32,
+        address: u64,
         data: &[V],
     ) -> Result<(), RiscvError> {
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
-        let s1 = self.abstract_cmd_register_read(&register::S1)?;
+        let s0 = self.abstract_cmd_register_read(register::S0)?;
+        let s1 = self.abstract_cmd_register_read(register::S1)?;
 
         // Setup program buffer for multiple writes
         // Store value from register s9 into memory,
@@ -927,11 +1227,11 @@ impl RiscvCommunicationInterface {
         ])?;
 
         // write address into s0
-        self.abstract_cmd_register_write(&register::S0, address)?;
+        self.abstract_cmd_register_write(register::S0, address)?;
 
         for value in data {
-            // write address into data 0
-            self.write_dm_register(Data0((*value).into()))?;
+            // write value into data 0
+            self.write_arg0((*value).into())?;
 
             // Write s0, then execute program buffer
             let mut command = AccessRegisterCommand(0);
@@ -939,12 +1239,12 @@ impl RiscvCommunicationInterface {
             command.set_transfer(true);
             command.set_write(true);
 
-            // registers are 32 bit, so we have size 2 here
-            command.set_aarsize(RiscvBusAccess::A32);
+            // transfer the whole register
+            command.set_aarsize(self.state.xlen.register_access());
             command.set_postexec(true);
 
             // register s1
-            command.set_regno((register::S1).id.0 as u32);
+            command.set_regno(register::S1.0 as u32);
 
             self.write_dm_register(command)?;
         }
@@ -966,8 +1266,8 @@ impl RiscvCommunicationInterface {
 
         // Restore register s0 and s1
 
-        self.abstract_cmd_register_write(&register::S0, s0)?;
-        self.abstract_cmd_register_write(&register::S1, s1)?;
+        self.abstract_cmd_register_write(register::S0, s0)?;
+        self.abstract_cmd_register_write(register::S1, s1)?;
 
         Ok(())
     }
@@ -978,7 +1278,7 @@ impl RiscvCommunicationInterface {
         // resumereq    = 0
         // ackhavereset = 0
 
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.dmcontrol();
         dmcontrol.set_haltreq(false);
         dmcontrol.set_resumereq(false);
         dmcontrol.set_ackhavereset(false);
@@ -1063,7 +1363,7 @@ impl RiscvCommunicationInterface {
     pub(crate) fn abstract_cmd_register_read(
         &mut self,
         regno: impl Into<RegisterId>,
-    ) -> Result<u32, RiscvError> {
+    ) -> Result<u64, RiscvError> {
         let regno = regno.into();
 
         // Check if the register was already tried via abstract cmd
@@ -1077,7 +1377,7 @@ impl RiscvCommunicationInterface {
         let mut command = AccessRegisterCommand(0);
         command.set_cmd_type(0);
         command.set_transfer(true);
-        command.set_aarsize(RiscvBusAccess::A32);
+        command.set_aarsize(self.state.xlen.register_access());
 
         command.set_regno(regno.0 as u32);
 
@@ -1094,15 +1394,13 @@ impl RiscvCommunicationInterface {
             Err(e) => return Err(e),
         }
 
-        let register_value: Data0 = self.read_dm_register()?;
-
-        Ok(register_value.into())
+        self.read_arg0()
     }
 
-    pub(crate) fn abstract_cmd_register_write<V: RiscvValue>(
+    pub(crate) fn abstract_cmd_register_write(
         &mut self,
         regno: impl Into<RegisterId>,
-        value: V,
+        value: u64,
     ) -> Result<(), RiscvError> {
         let regno = regno.into();
 
@@ -1118,11 +1416,11 @@ impl RiscvCommunicationInterface {
         command.set_cmd_type(0);
         command.set_transfer(true);
         command.set_write(true);
-        command.set_aarsize(V::WIDTH);
+        command.set_aarsize(self.state.xlen.register_access());
 
         command.set_regno(regno.0 as u32);
 
-        self.write_large_dtm_register::<V, Arg0>(value)?;
+        self.write_arg0(value)?;
 
         match self.execute_abstract_command(command.0) {
             Ok(_) => Ok(()),
@@ -1139,7 +1437,7 @@ impl RiscvCommunicationInterface {
     }
 
     /// Read the CSR progbuf register.
-    pub fn read_csr_progbuf(&mut self, address: u16) -> Result<u32, RiscvError> {
+    pub fn read_csr_progbuf(&mut self, address: u16) -> Result<u64, RiscvError> {
         tracing::debug!("Reading CSR {:#04x}", address);
 
         // Validate that the CSR address is valid
@@ -1147,7 +1445,7 @@ impl RiscvCommunicationInterface {
             return Err(RiscvError::UnsupportedCsrAddress(address));
         }
 
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
+        let s0 = self.abstract_cmd_register_read(register::S0)?;
 
         // Read csr value into register 8 (s0)
         let csrr_cmd = assembly::csrr(8, address);
@@ -1161,16 +1459,16 @@ impl RiscvCommunicationInterface {
         self.execute_abstract_command(postexec_cmd.0)?;
 
         // read the s0 value
-        let reg_value = self.abstract_cmd_register_read(&register::S0)?;
+        let reg_value = self.abstract_cmd_register_read(register::S0)?;
 
         // restore original value in s0
-        self.abstract_cmd_register_write(&register::S0, s0)?;
+        self.abstract_cmd_register_write(register::S0, s0)?;
 
         Ok(reg_value)
     }
 
     /// Write the CSR progbuf register.
-    pub fn write_csr_progbuf(&mut self, address: u16, value: u32) -> Result<(), RiscvError> {
+    pub fn write_csr_progbuf(&mut self, address: u16, value: u64) -> Result<(), RiscvError> {
         tracing::debug!("Writing CSR {:#04x}={}", address, value);
 
         // Validate that the CSR address is valid
@@ -1179,10 +1477,10 @@ impl RiscvCommunicationInterface {
         }
 
         // Backup register s0
-        let s0 = self.abstract_cmd_register_read(&register::S0)?;
+        let s0 = self.abstract_cmd_register_read(register::S0)?;
 
         // Write value into s0
-        self.abstract_cmd_register_write(&register::S0, value)?;
+        self.abstract_cmd_register_write(register::S0, value)?;
 
         // Built the CSRW command to write into the program buffer
         let csrw_cmd = assembly::csrw(address, 8);
@@ -1196,7 +1494,7 @@ impl RiscvCommunicationInterface {
 
         // command: transfer, regno = 0x1008
         // restore original value in s0
-        self.abstract_cmd_register_write(&register::S0, s0)?;
+        self.abstract_cmd_register_write(register::S0, s0)?;
 
         Ok(())
     }
@@ -1217,7 +1515,50 @@ impl RiscvCommunicationInterface {
         V::write_to_register::<R>(self, value)
     }
 
-    fn read_word<V: RiscvValue32>(&mut self, address: u32) -> Result<V, crate::Error> {
+    /// Read the arg0 portion of the data registers, with the width of a general purpose register.
+    fn read_arg0(&mut self) -> Result<u64, RiscvError> {
+        match self.state.xlen {
+            Xlen::X32 => self.read_large_dtm_register::<u32, Arg0>().map(u64::from),
+            Xlen::X64 => self.read_large_dtm_register::<u64, Arg0>(),
+        }
+    }
+
+    /// Write the arg0 portion of the data registers, with the width of a general purpose register.
+    fn write_arg0(&mut self, value: u64) -> Result<(), RiscvError> {
+        match self.state.xlen {
+            Xlen::X32 => self.write_large_dtm_register::<u32, Arg0>(value as u32),
+            Xlen::X64 => self.write_large_dtm_register::<u64, Arg0>(value),
+        }
+    }
+
+    /// Write the address for a system bus access.
+    ///
+    /// A write to sbaddress0 can trigger a read, so the upper bits of the address are written first.
+    fn write_sbaddress(&mut self, address: u64) -> Result<(), RiscvError> {
+        if self.state.sbasize > 32 {
+            self.write_dm_register(Sbaddress1((address >> 32) as u32))?;
+        }
+
+        self.write_dm_register(Sbaddress0(address as u32))
+    }
+
+    fn schedule_write_sbaddress(&mut self, address: u64) -> Result<(), RiscvError> {
+        if self.state.sbasize > 32 {
+            self.schedule_write_dm_register(Sbaddress1((address >> 32) as u32))?;
+        }
+
+        self.schedule_write_dm_register(Sbaddress0(address as u32))
+    }
+
+    /// Check that the address can be accessed by the hart.
+    fn valid_address(&self, address: u64) -> Result<u64, crate::Error> {
+        match self.state.xlen {
+            Xlen::X32 => Ok(valid_32bit_address(address)?.into()),
+            Xlen::X64 => Ok(address),
+        }
+    }
+
+    fn read_word<V: RiscvValue64>(&mut self, address: u64) -> Result<V, crate::Error> {
         let result = match self.state.memory_access_method(V::WIDTH) {
             MemoryAccessMethod::ProgramBuffer => self.perform_memory_read_progbuf(address)?,
             MemoryAccessMethod::SystemBus => self.perform_memory_read_sysbus(address)?,
@@ -1229,17 +1570,24 @@ impl RiscvCommunicationInterface {
         Ok(result)
     }
 
-    fn read_multiple<V: RiscvValue32>(
+    fn read_multiple<V: RiscvValue64>(
         &mut self,
-        address: u32,
+        address: u64,
         data: &mut [V],
     ) -> Result<(), crate::Error> {
-        tracing::debug!("read_32 from {:#08x}", address);
+        tracing::debug!("read_multiple from {:#08x}", address);
 
-        match self.state.memory_access_method(RiscvBusAccess::A32) {
+        match self.state.memory_access_method(V::WIDTH) {
             MemoryAccessMethod::ProgramBuffer => {
                 self.perform_memory_read_multiple_progbuf(address, data)?;
             }
+            MemoryAccessMethod::SystemBus if V::WIDTH > RiscvBusAccess::A32 => {
+                // Batched reads return the data in 32-bit chunks, so wider values are read one by one.
+                for (i, word) in data.iter_mut().enumerate() {
+                    let address = address + (i * V::WIDTH.byte_width()) as u64;
+                    *word = self.perform_memory_read_sysbus(address)?;
+                }
+            }
             MemoryAccessMethod::SystemBus => {
                 self.perform_memory_read_multiple_sysbus(address, data)?;
             }
@@ -1251,7 +1599,7 @@ impl RiscvCommunicationInterface {
         Ok(())
     }
 
-    fn write_word<V: RiscvValue32>(&mut self, address: u32, data: V) -> Result<(), crate::Error> {
+    fn write_word<V: RiscvValue64>(&mut self, address: u64, data: V) -> Result<(), crate::Error> {
         match self.state.memory_access_method(V::WIDTH) {
             MemoryAccessMethod::ProgramBuffer => {
                 self.perform_memory_write_progbuf(address, data)?
@@ -1265,9 +1613,9 @@ impl RiscvCommunicationInterface {
         Ok(())
     }
 
-    fn write_multiple<V: RiscvValue32>(
+    fn write_multiple<V: RiscvValue64>(
         &mut self,
-        address: u32,
+        address: u64,
         data: &[V],
     ) -> Result<(), crate::Error> {
         match self.state.memory_access_method(V::WIDTH) {
@@ -1389,23 +1737,28 @@ impl LargeRegister for Arg0 {
     const R3_ADDRESS: u8 = Data3::ADDRESS;
 }
 
-/// Helper trait, limited to RiscvValue no larger than 32 bits
-pub(crate) trait RiscvValue32: RiscvValue + Into<u32> {
-    fn from_register_value(value: u32) -> Self;
+/// Helper trait, limited to RiscvValue no larger than 64 bits
+pub(crate) trait RiscvValue64: RiscvValue + Into<u64> {
+    fn from_register_value(value: u64) -> Self;
 }
 
-impl RiscvValue32 for u8 {
-    fn from_register_value(value: u32) -> Self {
+impl RiscvValue64 for u8 {
+    fn from_register_value(value: u64) -> Self {
         value as u8
     }
 }
-impl RiscvValue32 for u16 {
-    fn from_register_value(value: u32) -> Self {
+impl RiscvValue64 for u16 {
+    fn from_register_value(value: u64) -> Self {
         value as u16
     }
 }
-impl RiscvValue32 for u32 {
-    fn from_register_value(value: u32) -> Self {
+impl RiscvValue64 for u32 {
+    fn from_register_value(value: u64) -> Self {
+        value as u32
+    }
+}
+impl RiscvValue64 for u64 {
+    fn from_register_value(value: u64) -> Self {
         value
     }
 }
@@ -1710,11 +2063,16 @@ impl RiscvValue for u128 {
 
 impl MemoryInterface for RiscvCommunicationInterface {
     fn supports_native_64bit_access(&mut self) -> bool {
-        false
+        self.state.xlen == Xlen::X64
     }
 
     fn read_word_64(&mut self, address: u64) -> Result<u64, crate::error::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
+
+        if self.state.xlen == Xlen::X64 {
+            return self.read_word(address);
+        }
+
         let mut ret = self.read_word::<u32>(address)? as u64;
         ret |= (self.read_word::<u32>(address + 4)? as u64) << 32;
 
@@ -1722,47 +2080,52 @@ impl MemoryInterface for RiscvCommunicationInterface {
     }
 
     fn read_word_32(&mut self, address: u64) -> Result<u32, crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         self.read_word(address)
     }
 
     fn read_word_8(&mut self, address: u64) -> Result<u8, crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         tracing::debug!("read_word_8 from {:#08x}", address);
         self.read_word(address)
     }
 
     fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), crate::error::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         tracing::debug!("read_64 from {:#08x}", address);
 
         for (i, d) in data.iter_mut().enumerate() {
-            *d = self.read_word_64((address + (i as u32 * 8)).into())?;
+            *d = self.read_word_64(address + i as u64 * 8)?;
         }
 
         Ok(())
     }
 
     fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         tracing::debug!("read_32 from {:#08x}", address);
         self.read_multiple(address, data)
     }
 
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         tracing::debug!("read_8 from {:#08x}", address);
 
         self.read_multiple(address, data)
     }
 
     fn read(&mut self, address: u64, data: &mut [u8]) -> Result<(), crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         self.read_multiple(address, data)
     }
 
     fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), crate::error::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
+
+        if self.state.xlen == Xlen::X64 {
+            return self.write_word(address, data);
+        }
+
         let low_word = data as u32;
         let high_word = (data >> 32) as u32;
 
@@ -1771,42 +2134,42 @@ impl MemoryInterface for RiscvCommunicationInterface {
     }
 
     fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         self.write_word(address, data)
     }
 
     fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         self.write_word(address, data)
     }
 
     fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), crate::error::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         tracing::debug!("write_64 to {:#08x}", address);
 
         for (i, d) in data.iter().enumerate() {
-            self.write_word_64((address + (i as u32 * 8)).into(), *d)?;
+            self.write_word_64(address + i as u64 * 8, *d)?;
         }
 
         Ok(())
     }
 
     fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         tracing::debug!("write_32 to {:#08x}", address);
 
         self.write_multiple(address, data)
     }
 
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         tracing::debug!("write_8 to {:#08x}", address);
 
         self.write_multiple(address, data)
     }
 
     fn write(&mut self, address: u64, data: &[u8]) -> Result<(), crate::Error> {
-        let address = valid_32bit_address(address)?;
+        let address = self.valid_address(address)?;
         self.write_multiple(address, data)
     }
 
@@ -1819,6 +2182,25 @@ impl MemoryInterface for RiscvCommunicationInterface {
     }
 }
 
+/// Width of the general purpose registers of a RISC-V hart.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Xlen {
+    /// 32-bit registers (RV32)
+    X32,
+    /// 64-bit registers (RV64)
+    X64,
+}
+
+impl Xlen {
+    /// Access width needed to transfer a whole register.
+    const fn register_access(&self) -> RiscvBusAccess {
+        match self {
+            Xlen::X32 => RiscvBusAccess::A32,
+            Xlen::X64 => RiscvBusAccess::A64,
+        }
+    }
+}
+
 /// Access width for bus access.
 /// This is used both for system bus access (`sbcs` register),
 /// as well for abstract commands.
@@ -2124,3 +2506,226 @@ data_register! { Confstrptr0, 0x19, "confstrptr0" }
 data_register! { Confstrptr1, 0x1a, "confstrptr1" }
 data_register! { Confstrptr2, 0x1b, "confstrptr2" }
 data_register! { Confstrptr3, 0x1c, "confstrptr3" }
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::probe::{DebugProbe, DebugProbeSelector, WireProtocol};
+
+    /// The DM register writes, as address and value.
+    type Writes = Arc<Mutex<Vec<(u8, u32)>>>;
+
+    /// A debug module behind a DTM, which records all DM register writes.
+    #[derive(Debug, Default)]
+    struct MockDtm {
+        writes: Writes,
+        /// The result of the last read, returned by the next DMI access.
+        read_result: u32,
+        /// The hart selected by the last `dmcontrol` write.
+        hart: u32,
+    }
+
+    impl DebugProbe for MockDtm {
+        fn new_from_selector(
+            _selector: impl Into<DebugProbeSelector>,
+        ) -> Result<Box<Self>, DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn get_name(&self) -> &str {
+            "Mock DTM"
+        }
+
+        fn speed_khz(&self) -> u32 {
+            unimplemented!()
+        }
+
+        fn set_speed(&mut self, _speed_khz: u32) -> Result<u32, DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn attach(&mut self) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn detach(&mut self) -> Result<(), crate::Error> {
+            unimplemented!()
+        }
+
+        fn target_reset(&mut self) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn select_protocol(&mut self, _protocol: WireProtocol) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn active_protocol(&self) -> Option<WireProtocol> {
+            Some(WireProtocol::Jtag)
+        }
+
+        fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+            self
+        }
+    }
+
+    impl JTAGAccess for MockDtm {
+        fn read_register(&mut self, address: u32, _len: u32) -> Result<Vec<u8>, DebugProbeError> {
+            assert_eq!(address, 0x10, "Only dtmcs is read directly");
+
+            // Version 1, 7 address bits
+            Ok((7u32 << 4 | 1).to_le_bytes().to_vec())
+        }
+
+        fn set_idle_cycles(&mut self, _idle_cycles: u8) {}
+
+        fn get_idle_cycles(&self) -> u8 {
+            0
+        }
+
+        fn set_ir_len(&mut self, _len: u32) {}
+
+        fn write_register(
+            &mut self,
+            address: u32,
+            data: &[u8],
+            _len: u32,
+        ) -> Result<Vec<u8>, DebugProbeError> {
+            if address != 0x11 {
+                // dtmcs write to clear errors
+                return Ok(vec![0; 4]);
+            }
+
+            let request = data
+                .iter()
+                .rev()
+                .fold(0u128, |acc, &byte| acc << 8 | byte as u128);
+            let op = request & 0x3;
+            let value = (request >> 2) as u32;
+            let dm_address = (request >> 34) as u8;
+
+            let response = (self.read_result as u128) << 2;
+
+            match op {
+                // Read: all harts are always halted and have acknowledged the resume request.
+                1 if dm_address == Dmstatus::ADDRESS => self.read_result = 1 << 17 | 1 << 9,
+                // Every hart has a different number of dscratch registers.
+                1 if dm_address == Hartinfo::ADDRESS => self.read_result = (self.hart + 1) << 20,
+                1 => self.read_result = 0,
+                2 => {
+                    if dm_address == Dmcontrol::ADDRESS {
+                        self.hart = Dmcontrol(value).hartsel();
+                    }
+                    self.writes.lock().unwrap().push((dm_address, value));
+                }
+                _ => {}
+            }
+
+            Ok(response.to_le_bytes().to_vec())
+        }
+    }
+
+    fn interface(
+        num_harts: u32,
+        supports_hart_array: bool,
+    ) -> (RiscvCommunicationInterface, Writes) {
+        let probe = MockDtm::default();
+        let writes = probe.writes.clone();
+
+        let mut state = RiscvCommunicationInterfaceState::new();
+        state.num_harts = num_harts;
+        state.supports_hart_array = supports_hart_array;
+        // A hart which is not part of the requests below
+        state.hart = num_harts - 1;
+
+        let interface = RiscvCommunicationInterface {
+            dtm: Dtm::new(Box::new(probe)).unwrap(),
+            state,
+            chain: None,
+            tap: None,
+        };
+
+        (interface, writes)
+    }
+
+    /// The values written to `dmcontrol`, in order.
+    fn dmcontrol_writes(writes: &Mutex<Vec<(u8, u32)>>) -> Vec<Dmcontrol> {
+        writes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(address, _)| *address == Dmcontrol::ADDRESS)
+            .map(|&(_, value)| Dmcontrol(value))
+            .collect()
+    }
+
+    #[test]
+    fn halt_harts_with_hart_array() {
+        let (mut interface, writes) = interface(4, true);
+
+        interface
+            .halt_harts(&[1, 2], Duration::from_millis(100))
+            .unwrap();
+
+        let hawindow = writes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(address, _)| *address == Hawindow::ADDRESS)
+            .map(|&(_, value)| value)
+            .collect::<Vec<_>>();
+        assert_eq!(hawindow, vec![0b110]);
+
+        let controls = dmcontrol_writes(&writes);
+        let request = controls[0];
+        assert_eq!(request.0 >> 31, 1, "haltreq");
+        assert!(request.hasel());
+        assert_eq!(request.hartsel(), 1);
+
+        // The request is cleared, and the original hart is selected again.
+        let restored = controls.last().unwrap();
+        assert_eq!(restored.0 >> 31, 0);
+        assert!(!restored.hasel());
+        assert_eq!(restored.hartsel(), 3);
+        assert_eq!(interface.selected_hart(), 3);
+    }
+
+    #[test]
+    fn resume_harts_one_by_one() {
+        let (mut interface, writes) = interface(4, false);
+
+        interface
+            .resume_harts(&[0, 2], Duration::from_millis(100))
+            .unwrap();
+
+        assert!(writes
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(address, _)| *address != Hawindow::ADDRESS));
+
+        let requests = dmcontrol_writes(&writes)
+            .into_iter()
+            .filter(|control| control.0 >> 30 & 1 == 1)
+            .collect::<Vec<_>>();
+        assert_eq!(requests.len(), 2);
+        for (request, hart) in requests.iter().zip([0, 2]) {
+            assert!(!request.hasel());
+            assert_eq!(request.hartsel(), hart);
+        }
+
+        assert_eq!(interface.selected_hart(), 3);
+        // The information about the selected hart is read again.
+        assert_eq!(interface.state.nscratch, 4);
+    }
+}
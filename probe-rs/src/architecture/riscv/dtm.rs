@@ -29,43 +29,49 @@ pub struct Dtm {
 }
 
 impl Dtm {
-    pub fn new(mut probe: Box<dyn JTAGAccess>) -> Result<Self, (Box<dyn JTAGAccess>, RiscvError)> {
+    pub fn new(probe: Box<dyn JTAGAccess>) -> Result<Self, (Box<dyn JTAGAccess>, RiscvError)> {
+        let mut dtm = Self {
+            probe,
+            abits: 0,
+            queued_commands: Vec::new(),
+        };
+
+        match dtm.configure() {
+            Ok(()) => Ok(dtm),
+            Err(e) => Err((dtm.probe, e)),
+        }
+    }
+
+    /// Read the configuration of the DTM, e.g. again after another TAP was selected.
+    pub fn configure(&mut self) -> Result<(), RiscvError> {
         // Always 5 bits for now
-        probe.set_ir_len(5);
+        self.probe.set_ir_len(5);
 
-        let dtmcs_raw = match probe.read_register(DTMCS_ADDRESS, DTMCS_WIDTH) {
-            Ok(value) => value,
-            Err(e) => return Err((probe, e.into())),
-        };
+        let dtmcs_raw = self.probe.read_register(DTMCS_ADDRESS, DTMCS_WIDTH)?;
 
         let raw_dtmcs = u32::from_le_bytes((&dtmcs_raw[..]).try_into().unwrap());
 
         if raw_dtmcs == 0 {
-            return Err((probe, RiscvError::NoRiscvTarget));
+            return Err(RiscvError::NoRiscvTarget);
         }
 
         let dtmcs = Dtmcs(raw_dtmcs);
 
         tracing::debug!("Dtmcs: {:?}", dtmcs);
 
-        let abits = dtmcs.abits();
-        let idle_cycles = dtmcs.idle();
-
         if dtmcs.version() != 1 {
-            return Err((
-                probe,
-                RiscvError::UnsupportedDebugTransportModuleVersion(dtmcs.version() as u8),
+            return Err(RiscvError::UnsupportedDebugTransportModuleVersion(
+                dtmcs.version() as u8,
             ));
         }
 
         // Setup the number of idle cycles between JTAG accesses
-        probe.set_idle_cycles(idle_cycles as u8);
+        self.probe.set_idle_cycles(dtmcs.idle() as u8);
 
-        Ok(Self {
-            probe,
-            abits,
-            queued_commands: Vec::new(),
-        })
+        self.abits = dtmcs.abits();
+        self.queued_commands.clear();
+
+        Ok(())
     }
 
     pub fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
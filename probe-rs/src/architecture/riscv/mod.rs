@@ -6,7 +6,7 @@ use crate::core::{Architecture, BreakpointCause};
 use crate::{CoreInterface, CoreType, InstructionSet};
 use anyhow::{anyhow, Result};
 use communication_interface::{
-    AbstractCommandErrorKind, DebugRegister, RiscvCommunicationInterface, RiscvError,
+    AbstractCommandErrorKind, DebugRegister, RiscvCommunicationInterface, RiscvError, Xlen,
 };
 
 use crate::core::{CoreInformation, RegisterFile, RegisterValue};
@@ -14,7 +14,8 @@ use crate::memory::valid_32bit_address;
 use crate::{CoreStatus, Error, HaltReason, MemoryInterface, RegisterId};
 
 use bitfield::bitfield;
-use register::RISCV_REGISTERS;
+use register::{RISCV64_REGISTERS, RISCV_REGISTERS};
+use std::marker::PhantomData;
 use std::time::{Duration, Instant};
 
 #[macro_use]
@@ -25,22 +26,57 @@ mod dtm;
 pub mod communication_interface;
 pub mod sequences;
 
+/// The width of the general purpose registers of a RISC-V core, see [`Riscv`].
+pub trait RiscvXlen {
+    /// Width of the general purpose registers.
+    const XLEN: Xlen;
+}
+
+/// Marker for RISC-V cores with 32-bit registers.
+pub enum Rv32 {}
+
+/// Marker for RISC-V cores with 64-bit registers.
+pub enum Rv64 {}
+
+impl RiscvXlen for Rv32 {
+    const XLEN: Xlen = Xlen::X32;
+}
+
+impl RiscvXlen for Rv64 {
+    const XLEN: Xlen = Xlen::X64;
+}
+
 /// A interface to operate RISC-V cores.
-pub struct Riscv32<'probe> {
+///
+/// The register width of the core is selected with `X`, see [`Riscv32`] and [`Riscv64`].
+pub struct Riscv<'probe, X: RiscvXlen> {
     interface: &'probe mut RiscvCommunicationInterface,
     state: &'probe mut RiscVState,
+    _xlen: PhantomData<X>,
 }
 
-impl<'probe> Riscv32<'probe> {
+/// A interface to operate RV32 cores.
+pub type Riscv32<'probe> = Riscv<'probe, Rv32>;
+
+/// A interface to operate RV64 cores.
+pub type Riscv64<'probe> = Riscv<'probe, Rv64>;
+
+impl<'probe, X: RiscvXlen> Riscv<'probe, X> {
     /// Create a new RISC-V interface.
     pub fn new(
         interface: &'probe mut RiscvCommunicationInterface,
         state: &'probe mut RiscVState,
     ) -> Self {
-        Self { interface, state }
+        interface.set_xlen(X::XLEN);
+
+        Self {
+            interface,
+            state,
+            _xlen: PhantomData,
+        }
     }
 
-    fn read_csr(&mut self, address: u16) -> Result<u32, RiscvError> {
+    fn read_csr(&mut self, address: u16) -> Result<u64, RiscvError> {
         // We need to use the "Access Register Command",
         // which has cmdtype 0
 
@@ -60,7 +96,7 @@ impl<'probe> Riscv32<'probe> {
         }
     }
 
-    fn write_csr(&mut self, address: u16, value: u32) -> Result<(), RiscvError> {
+    fn write_csr(&mut self, address: u16, value: u64) -> Result<(), RiscvError> {
         tracing::debug!("Writing CSR {:#x}", address);
 
         match self.interface.abstract_cmd_register_write(address, value) {
@@ -72,10 +108,44 @@ impl<'probe> Riscv32<'probe> {
         }
     }
 
+    /// Read a CSR with fields at the top of the register, like `tdata1`.
+    ///
+    /// On RV64, these fields are moved down to their RV32 positions, so the
+    /// value can be used with the 32-bit register definitions.
+    fn read_xlen_csr(&mut self, address: u16) -> Result<u32, RiscvError> {
+        let value = self.read_csr(address)?;
+
+        Ok(match X::XLEN {
+            Xlen::X32 => value as u32,
+            Xlen::X64 => {
+                ((value >> 32) as u32 & XLEN_FIELDS_MASK) | (value as u32 & !XLEN_FIELDS_MASK)
+            }
+        })
+    }
+
+    /// Write a CSR with fields at the top of the register, see [`Self::read_xlen_csr`].
+    fn write_xlen_csr(&mut self, address: u16, value: u32) -> Result<(), RiscvError> {
+        let value = match X::XLEN {
+            Xlen::X32 => value as u64,
+            Xlen::X64 => {
+                ((value & XLEN_FIELDS_MASK) as u64) << 32 | (value & !XLEN_FIELDS_MASK) as u64
+            }
+        };
+
+        self.write_csr(address, value)
+    }
+
+    /// Read the `misa` CSR.
+    fn read_misa(&mut self) -> Result<Misa, RiscvError> {
+        let value = self.read_csr(0x301)?;
+
+        Ok(Misa::from_csr(X::XLEN, value))
+    }
+
     // Resume the core.
     fn resume_core(&mut self) -> Result<(), crate::Error> {
         // set resume request.
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_resumereq(true);
         dmcontrol.set_dmactive(true);
         self.interface.write_dm_register(dmcontrol)?;
@@ -87,7 +157,7 @@ impl<'probe> Riscv32<'probe> {
         };
 
         // clear resume request.
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_dmactive(true);
         self.interface.write_dm_register(dmcontrol)?;
 
@@ -95,7 +165,7 @@ impl<'probe> Riscv32<'probe> {
     }
 }
 
-impl<'probe> CoreInterface for Riscv32<'probe> {
+impl<'probe, X: RiscvXlen> CoreInterface for Riscv<'probe, X> {
     fn wait_for_core_halted(&mut self, timeout: Duration) -> Result<(), crate::Error> {
         let start = Instant::now();
 
@@ -127,7 +197,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             self.interface.read_dm_register::<Dmcontrol>()?
         );
 
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
 
         dmcontrol.set_haltreq(true);
         dmcontrol.set_dmactive(true);
@@ -137,15 +207,18 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         self.wait_for_core_halted(timeout)?;
 
         // clear the halt request
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
 
         dmcontrol.set_dmactive(true);
 
         self.interface.write_dm_register(dmcontrol)?;
 
-        let pc = self.read_core_reg(register::RISCV_REGISTERS.program_counter.id)?;
+        let pc = self.read_core_reg(self.registers().program_counter.id)?;
 
-        Ok(CoreInformation { pc: pc.try_into()? })
+        Ok(CoreInformation {
+            pc: pc.try_into()?,
+            security_state: None,
+        })
     }
 
     fn run(&mut self) -> Result<(), crate::Error> {
@@ -177,7 +250,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     ) -> Result<crate::core::CoreInformation, crate::Error> {
         tracing::debug!("Resetting core, setting hartreset bit");
 
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_dmactive(true);
         dmcontrol.set_hartreset(true);
         dmcontrol.set_haltreq(true);
@@ -190,7 +263,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         if readback.hartreset() {
             tracing::debug!("Clearing hartreset bit");
             // Reset is performed by setting the bit high, and then low again
-            let mut dmcontrol = Dmcontrol(0);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_dmactive(true);
             dmcontrol.set_haltreq(true);
             dmcontrol.set_hartreset(false);
@@ -201,7 +274,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             //
             // TODO: Cache this
             tracing::debug!("Hartreset bit not supported, using ndmreset");
-            let mut dmcontrol = Dmcontrol(0);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_dmactive(true);
             dmcontrol.set_ndmreset(true);
             dmcontrol.set_haltreq(true);
@@ -209,7 +282,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             self.interface.write_dm_register(dmcontrol)?;
 
             tracing::debug!("Clearing ndmreset bit");
-            let mut dmcontrol = Dmcontrol(0);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_dmactive(true);
             dmcontrol.set_ndmreset(false);
             dmcontrol.set_haltreq(true);
@@ -225,7 +298,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         }
 
         // acknowledge the reset, clear the halt request
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_dmactive(true);
         dmcontrol.set_ackhavereset(true);
 
@@ -233,7 +306,10 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
 
         let pc = self.read_core_reg(RegisterId(0x7b1))?;
 
-        Ok(CoreInformation { pc: pc.try_into()? })
+        Ok(CoreInformation {
+            pc: pc.try_into()?,
+            security_state: None,
+        })
     }
 
     fn step(&mut self) -> Result<crate::core::CoreInformation, crate::Error> {
@@ -246,15 +322,23 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             // If we are halted on a software breakpoint AND we have passed the flashing operation, we can skip the single step and manually advance the dpc.
             let mut debug_pc = self.read_core_reg(RegisterId(0x7b1))?;
             // Advance the dpc by the size of the EBREAK (ebreak or c.ebreak) instruction.
-            if matches!(self.instruction_set()?, InstructionSet::RV32C) {
+            // If the dpc doesn't point to an EBREAK, it was already moved, e.g. after servicing a semihosting request.
+            let address: u64 = debug_pc.try_into()?;
+            let mut instruction = [0u8; 4];
+            self.read_8(address, &mut instruction[..2])?;
+            if u16::from_le_bytes([instruction[0], instruction[1]]) == 0x9002 {
                 debug_pc.incremenet_address(2)?;
             } else {
-                debug_pc.incremenet_address(4)?;
+                self.read_8(address + 2, &mut instruction[2..])?;
+                if u32::from_le_bytes(instruction) == 0x0010_0073 {
+                    debug_pc.incremenet_address(4)?;
+                }
             }
 
             self.write_core_reg(RegisterId(0x7b1), debug_pc)?;
             return Ok(CoreInformation {
                 pc: debug_pc.try_into()?,
+                security_state: None,
             });
         } else if matches!(
             halt_reason,
@@ -270,7 +354,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         // Disable any interrupts during single step.
         dcsr.set_stepie(false);
         dcsr.set_stopcount(true);
-        self.write_csr(0x7b0, dcsr.0)?;
+        self.write_csr(0x7b0, dcsr.0.into())?;
 
         // Now we can resume the core for the single step.
         self.resume_core()?;
@@ -284,7 +368,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         //Re-enable interrupts for single step.
         dcsr.set_stepie(true);
         dcsr.set_stopcount(false);
-        self.write_csr(0x7b0, dcsr.0)?;
+        self.write_csr(0x7b0, dcsr.0.into())?;
 
         // Re-enable breakpoints before we continue.
         if matches!(
@@ -295,13 +379,19 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             self.enable_breakpoints(true)?;
         }
 
-        Ok(CoreInformation { pc: pc.try_into()? })
+        Ok(CoreInformation {
+            pc: pc.try_into()?,
+            security_state: None,
+        })
     }
 
     fn read_core_reg(&mut self, address: crate::RegisterId) -> Result<RegisterValue, crate::Error> {
-        self.read_csr(address.0)
-            .map(|v| v.into())
-            .map_err(|e| e.into())
+        let value = self.read_csr(address.0)?;
+
+        Ok(match X::XLEN {
+            Xlen::X32 => RegisterValue::U32(value as u32),
+            Xlen::X64 => RegisterValue::U64(value),
+        })
     }
 
     fn write_core_reg(
@@ -309,7 +399,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         address: crate::RegisterId,
         value: RegisterValue,
     ) -> Result<(), crate::Error> {
-        let value: u32 = value.try_into()?;
+        let value: u64 = value.try_into()?;
         self.write_csr(address.0, value).map_err(|e| e.into())
     }
 
@@ -327,7 +417,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         // These steps follow the debug specification 0.13, section 5.1 Enumeration
         loop {
             tracing::debug!("Trying tselect={}", tselect_index);
-            if let Err(e) = self.write_csr(tselect, tselect_index) {
+            if let Err(e) = self.write_csr(tselect, tselect_index.into()) {
                 match e {
                     RiscvError::AbstractCommand(AbstractCommandErrorKind::Exception) => break,
                     other_error => return Err(other_error.into()),
@@ -336,7 +426,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
 
             let readback = self.read_csr(tselect)?;
 
-            if readback != tselect_index {
+            if readback != u64::from(tselect_index) {
                 break;
             }
 
@@ -355,13 +445,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
                 }
                 Err(RiscvError::AbstractCommand(AbstractCommandErrorKind::Exception)) => {
                     // An exception means we have to read tdata1 to discover the type
-                    let tdata_val = self.read_csr(tdata1)?;
-
-                    // Read the mxl field from the misa register (see RISC-V Privileged Spec, 3.1.1)
-                    let misa_value = Misa(self.read_csr(0x301)?);
-                    let xlen = u32::pow(2, misa_value.mxl() + 4);
-
-                    let trigger_type = tdata_val >> (xlen - 4);
+                    let trigger_type = Mcontrol(self.read_xlen_csr(tdata1)?).type_();
 
                     if trigger_type == 0 {
                         break;
@@ -391,10 +475,10 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
 
         for bp_unit_index in 0..self.available_breakpoint_units()? as usize {
             // Select the trigger.
-            self.write_csr(tselect, bp_unit_index as u32)?;
+            self.write_csr(tselect, bp_unit_index as u64)?;
 
             // Read the trigger "configuration" data.
-            let mut tdata_value = Mcontrol(self.read_csr(tdata1)?);
+            let mut tdata_value = Mcontrol(self.read_xlen_csr(tdata1)?);
 
             // Only modify the trigger if it is for an execution debug action in all modes(probe-rs enabled it) or no modes (we previously disabled it).
             if tdata_value.type_() == 0b10
@@ -411,7 +495,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
                 );
                 tdata_value.set_m(state);
                 tdata_value.set_u(state);
-                self.write_csr(tdata1, tdata_value.0)?;
+                self.write_xlen_csr(tdata1, tdata_value.0)?;
             }
         }
 
@@ -420,7 +504,9 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     }
 
     fn set_hw_breakpoint(&mut self, bp_unit_index: usize, addr: u64) -> Result<(), crate::Error> {
-        let addr = valid_32bit_address(addr)?;
+        if X::XLEN == Xlen::X32 {
+            valid_32bit_address(addr)?;
+        }
 
         if !self.hw_breakpoints_enabled() {
             self.enable_breakpoints(true)?;
@@ -433,11 +519,11 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
 
         tracing::warn!("Setting breakpoint {}", bp_unit_index);
 
-        self.write_csr(tselect, bp_unit_index as u32)?;
+        self.write_csr(tselect, bp_unit_index as u64)?;
 
         // verify the trigger has the correct type
 
-        let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+        let tdata_value = Mcontrol(self.read_xlen_csr(tdata1)?);
 
         // This should not happen
         let trigger_type = tdata_value.type_();
@@ -467,7 +553,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         // Match address
         instruction_breakpoint.set_select(false);
 
-        self.write_csr(tdata1, instruction_breakpoint.0)?;
+        self.write_xlen_csr(tdata1, instruction_breakpoint.0)?;
         self.write_csr(tdata2, addr)?;
 
         Ok(())
@@ -478,7 +564,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         let tdata1 = 0x7a1;
         let tdata2 = 0x7a2;
 
-        self.write_csr(tselect, unit_index as u32)?;
+        self.write_csr(tselect, unit_index as u64)?;
         self.write_csr(tdata1, 0)?;
         self.write_csr(tdata2, 0)?;
 
@@ -486,7 +572,10 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     }
 
     fn registers(&self) -> &'static RegisterFile {
-        &RISCV_REGISTERS
+        match X::XLEN {
+            Xlen::X32 => &RISCV_REGISTERS,
+            Xlen::X64 => &RISCV64_REGISTERS,
+        }
     }
 
     fn hw_breakpoints_enabled(&self) -> bool {
@@ -498,18 +587,24 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     }
 
     fn core_type(&self) -> CoreType {
-        CoreType::Riscv
+        match X::XLEN {
+            Xlen::X32 => CoreType::Riscv,
+            Xlen::X64 => CoreType::Riscv64,
+        }
     }
 
     fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
-        let misa_value = Misa(self.read_csr(0x301)?);
+        let misa_value = self.read_misa()?;
 
         // Check if the Bit at position 2 (signifies letter C, for compressed) is set.
-        if misa_value.extensions() & (1 << 2) != 0 {
-            Ok(InstructionSet::RV32C)
-        } else {
-            Ok(InstructionSet::RV32)
-        }
+        let compressed = misa_value.extensions() & (1 << 2) != 0;
+
+        Ok(match (X::XLEN, compressed) {
+            (Xlen::X32, true) => InstructionSet::RV32C,
+            (Xlen::X32, false) => InstructionSet::RV32,
+            (Xlen::X64, true) => InstructionSet::RV64C,
+            (Xlen::X64, false) => InstructionSet::RV64,
+        })
     }
 
     fn status(&mut self) -> Result<crate::core::CoreStatus, crate::Error> {
@@ -533,6 +628,8 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
                 4 => HaltReason::Step,
                 // Core halted directly after reset
                 5 => HaltReason::Exception,
+                // Another hart of the halt group halted
+                6 => HaltReason::External,
                 // Reserved for future use in specification
                 _ => HaltReason::Unknown,
             };
@@ -559,10 +656,10 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         let num_hw_breakpoints = self.available_breakpoint_units()? as usize;
         for bp_unit_index in 0..num_hw_breakpoints {
             // Select the trigger.
-            self.write_csr(tselect, bp_unit_index as u32)?;
+            self.write_csr(tselect, bp_unit_index as u64)?;
 
             // Read the trigger "configuration" data.
-            let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+            let tdata_value = Mcontrol(self.read_xlen_csr(tdata1)?);
 
             tracing::warn!("Breakpoint {}: {:?}", bp_unit_index, tdata_value);
 
@@ -580,7 +677,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
                 && trigger_any_action_enabled
             {
                 let breakpoint = self.read_csr(tdata2)?;
-                breakpoints.push(Some(breakpoint as u64));
+                breakpoints.push(Some(breakpoint));
             } else {
                 breakpoints.push(None);
             }
@@ -595,6 +692,10 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         )))
     }
 
+    fn is_64_bit(&self) -> bool {
+        matches!(X::XLEN, Xlen::X64)
+    }
+
     fn debug_on_sw_breakpoint(&mut self, enabled: bool) -> Result<(), crate::error::Error> {
         let mut dcsr = Dcsr(self.read_core_reg(RegisterId(0x7b0))?.try_into()?);
 
@@ -602,11 +703,11 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         dcsr.set_ebreaks(enabled);
         dcsr.set_ebreaku(enabled);
 
-        self.write_csr(0x7b0, dcsr.0).map_err(|e| e.into())
+        self.write_csr(0x7b0, dcsr.0.into()).map_err(|e| e.into())
     }
 }
 
-impl<'probe> MemoryInterface for Riscv32<'probe> {
+impl<'probe, X: RiscvXlen> MemoryInterface for Riscv<'probe, X> {
     fn supports_native_64bit_access(&mut self) -> bool {
         self.interface.supports_native_64bit_access()
     }
@@ -859,6 +960,37 @@ impl From<u32> for Hartinfo {
     }
 }
 
+bitfield! {
+    /// Debug Module Control and Status 2 (see 3.12.20)
+    pub struct Dmcs2(u32);
+    impl Debug;
+
+    exttrigger, set_exttrigger: 10, 7;
+    haltgroup, set_haltgroup: 6, 2;
+    _, set_hgwrite: 1;
+    hgselect, set_hgselect: 0;
+}
+
+impl DebugRegister for Dmcs2 {
+    const ADDRESS: u8 = 0x32;
+    const NAME: &'static str = "dmcs2";
+}
+
+impl From<Dmcs2> for u32 {
+    fn from(register: Dmcs2) -> Self {
+        register.0
+    }
+}
+
+impl From<u32> for Dmcs2 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+data_register! { Hawindowsel, 0x14, "hawindowsel" }
+data_register! { Hawindow, 0x15, "hawindow" }
+
 data_register! { pub Data0, 0x04, "data0" }
 data_register! { pub Data1, 0x05, "data1" }
 data_register! { pub Data2, 0x06, "data2" }
@@ -891,6 +1023,9 @@ data_register! { pub Progbuf13, 0x2D, "progbuf13" }
 data_register! { pub Progbuf14, 0x2E, "progbuf14" }
 data_register! { pub Progbuf15, 0x2F, "progbuf15" }
 
+/// Bits of the 32-bit layout of `tdata1` which are at the top of the register on RV64.
+const XLEN_FIELDS_MASK: u32 = 0xffe0_0000;
+
 bitfield! {
     struct Mcontrol(u32);
     impl Debug;
@@ -923,3 +1058,40 @@ bitfield! {
     /// Standard RISC-V extensions
     extensions, _: 25, 0;
 }
+
+impl Misa {
+    /// Convert the value of the `misa` CSR to the 32-bit layout.
+    ///
+    /// Only MXL is at the top of the register, the extensions are at the bottom for every XLEN.
+    fn from_csr(xlen: Xlen, value: u64) -> Self {
+        match xlen {
+            Xlen::X32 => Misa(value as u32),
+            Xlen::X64 => Misa(((value >> 62) as u32) << 30 | (value as u32 & 0x03ff_ffff)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Misa, Xlen};
+
+    #[test]
+    fn misa_rv32() {
+        // RV32IMAC
+        let misa = Misa::from_csr(Xlen::X32, 0x4010_1105);
+
+        assert_eq!(misa.mxl(), 1);
+        assert_eq!(misa.extensions(), 0x10_1105);
+    }
+
+    #[test]
+    fn misa_rv64() {
+        // RV64GCV with custom extensions
+        let misa = Misa::from_csr(Xlen::X64, 0x8000_0000_0280_112d);
+
+        assert_eq!(misa.mxl(), 2);
+        // The extensions V (bit 21) and X (bit 23) are kept.
+        assert_eq!(misa.extensions(), 0x0280_112d);
+        assert_ne!(misa.extensions() & (1 << 2), 0);
+    }
+}
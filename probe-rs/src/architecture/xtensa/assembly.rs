@@ -0,0 +1,61 @@
+//! Encodings of the Xtensa instructions executed by the debugger.
+//!
+//! All instructions are encoded for little-endian cores, as 24-bit values
+//! which can be written to the `DIR0EXEC` register.
+
+/// `break 1, 15`, the breakpoint instruction used by the debugger.
+pub const BREAK_1_15: u32 = 0x0041F0;
+
+/// Mask of the bits of the 24-bit `break s, t` instruction which do not encode `s` and `t`.
+const BREAK_MASK: u32 = 0xFFF00F;
+
+/// The 24-bit `break s, t` instruction, with `s` and `t` set to zero.
+const BREAK: u32 = 0x004000;
+
+/// Mask of the bits of the 16-bit `break.n s` instruction which do not encode `s`.
+const BREAK_N_MASK: u32 = 0xF0FF;
+
+/// The 16-bit `break.n s` instruction, with `s` set to zero.
+const BREAK_N: u32 = 0xF02D;
+
+/// Assemble a `rsr` instruction, which reads special register `sr` into `at`.
+pub const fn rsr(sr: u8, at: u8) -> u32 {
+    0x030000 | (sr as u32) << 8 | (at as u32 & 0xF) << 4
+}
+
+/// Assemble a `wsr` instruction, which writes `at` to special register `sr`.
+pub const fn wsr(sr: u8, at: u8) -> u32 {
+    0x130000 | (sr as u32) << 8 | (at as u32 & 0xF) << 4
+}
+
+/// Assemble a `rfdo` instruction, which returns from debug mode.
+pub const fn rfdo() -> u32 {
+    0xF1E000
+}
+
+/// Assemble a `lddr32.p` instruction, which loads the word at the address in `as`
+/// into `DDR`, and increments `as` by four.
+pub const fn lddr32_p(at: u8) -> u32 {
+    0x0070E0 | (at as u32 & 0xF) << 8
+}
+
+/// Assemble a `sddr32.p` instruction, which stores `DDR` to the address in `as`,
+/// and increments `as` by four.
+pub const fn sddr32_p(at: u8) -> u32 {
+    0x0070F0 | (at as u32 & 0xF) << 8
+}
+
+/// Assemble a `rotw` instruction, which rotates the register window by `n` windows of four registers.
+pub const fn rotw(n: i8) -> u32 {
+    0x408000 | (n as u32 & 0xF) << 4
+}
+
+/// Returns the length of the breakpoint instruction at the start of `instruction`,
+/// or `None` if `instruction` is not a `break` or `break.n` instruction.
+pub fn breakpoint_len(instruction: &[u8]) -> Option<u32> {
+    match *instruction {
+        [b0, b1, b2, ..] if u32::from_le_bytes([b0, b1, b2, 0]) & BREAK_MASK == BREAK => Some(3),
+        [b0, b1, ..] if u32::from_le_bytes([b0, b1, 0, 0]) & BREAK_N_MASK == BREAK_N => Some(2),
+        _ => None,
+    }
+}
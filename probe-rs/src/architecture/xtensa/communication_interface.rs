@@ -0,0 +1,1101 @@
+//! Xtensa On-Chip Debug (OCD) Communication
+//!
+//! The OCD module of an Xtensa core is accessed through its Nexus registers,
+//! which are exposed by the JTAG TAP of the core. While the core is halted, the debugger
+//! can execute single instructions on it, which is used to access registers and memory.
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    time::{Duration, Instant},
+};
+
+use bitfield::bitfield;
+use probe_rs_target::ScanChainElement;
+
+use super::{
+    assembly,
+    register::{sr, NUM_AREGS},
+};
+use crate::{
+    memory::valid_32bit_address,
+    probe::{ChainParams, JTAGAccess, JtagChainItem},
+    DebugProbeError, Error as ProbeRsError, MemoryInterface, Probe,
+};
+
+/// An error which occurred while working with an Xtensa core.
+#[derive(thiserror::Error, Debug)]
+pub enum XtensaError {
+    /// An error with operating the debug probe occurred.
+    #[error("Debug Probe Error")]
+    DebugProbe(#[from] DebugProbeError),
+    /// A timeout occurred while waiting for the core.
+    #[error("Timeout while waiting for the core.")]
+    Timeout,
+    /// The connected target is not an Xtensa device.
+    #[error("Connected target is not an Xtensa device.")]
+    NoXtensaTarget,
+    /// The core raised an exception while executing an instruction for the debugger.
+    #[error("The core raised an exception while executing instruction {0:#08x}.")]
+    ExecutionException(u32),
+    /// An instruction was sent to the core while it was still executing the previous one.
+    #[error("The core was still busy when instruction {0:#08x} was sent.")]
+    ExecutionOverrun(u32),
+    /// The register is not supported.
+    #[error("Register {0:#x} is not supported.")]
+    UnsupportedRegister(u16),
+    /// The TAP with the given index does not exist in the JTAG scan chain.
+    #[error("JTAG TAP {0} does not exist in the scan chain.")]
+    TapUnavailable(usize),
+    /// The memory access extends past the end of the 32-bit address space.
+    #[error("Accessing {length} bytes at {address:#010x} exceeds the address space.")]
+    AddressOutOfRange {
+        /// The start address of the access.
+        address: u32,
+        /// The length of the access in bytes.
+        length: usize,
+    },
+}
+
+impl From<XtensaError> for ProbeRsError {
+    fn from(err: XtensaError) -> Self {
+        match err {
+            XtensaError::DebugProbe(e) => e.into(),
+            XtensaError::Timeout => ProbeRsError::Timeout,
+            other => ProbeRsError::Xtensa(other),
+        }
+    }
+}
+
+/// Length of the instruction register of the Xtensa TAP.
+const IR_LEN: u32 = 5;
+
+/// JTAG instruction selecting the 8 bit power control register.
+const PWRCTL: u32 = 0x08;
+
+/// JTAG instruction selecting the 8 bit power status register.
+const PWRSTAT: u32 = 0x09;
+
+/// JTAG instruction selecting the Nexus registers.
+///
+/// After selecting it, DR scans alternate between the 8 bit Nexus address register (NAR),
+/// and the 32 bit Nexus data register (NDR).
+const NARSEL: u32 = 0x1C;
+
+/// JTAG instruction selecting the IDCODE register.
+const IDCODE: u32 = 0x1E;
+
+/// The address register used as scratch register when accessing special registers and memory.
+const SCRATCH: u8 = 3;
+
+/// Timeout for the execution of a single instruction.
+const EXECUTION_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Nexus registers of the OCD module.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum NexusRegister {
+    /// OCD ID register
+    OcdId = 0x40,
+    /// Debug control register, writing a one clears the corresponding bit.
+    DcrClr = 0x42,
+    /// Debug control register, writing a one sets the corresponding bit.
+    DcrSet = 0x43,
+    /// Debug status register
+    Dsr = 0x44,
+    /// Debug data register
+    Ddr = 0x45,
+    /// Debug data register, executes the instruction in `DIR0` when accessed.
+    DdrExec = 0x46,
+    /// Debug instruction register, executes the instruction when written.
+    Dir0Exec = 0x47,
+    /// Debug instruction register
+    Dir0 = 0x48,
+}
+
+bitfield! {
+    /// Debug Control Register (DCR)
+    #[derive(Copy, Clone)]
+    struct DebugControl(u32);
+    impl Debug;
+
+    pub enable_ocd, set_enable_ocd: 0;
+    pub debug_interrupt, set_debug_interrupt: 1;
+    pub interrupt_all_conds, set_interrupt_all_conds: 2;
+}
+
+bitfield! {
+    /// Debug Status Register (DSR)
+    #[derive(Copy, Clone)]
+    struct DebugStatus(u32);
+    impl Debug;
+
+    pub exec_done, set_exec_done: 0;
+    pub exec_exception, set_exec_exception: 1;
+    pub exec_busy, _: 2;
+    pub exec_overrun, set_exec_overrun: 3;
+    pub stopped, _: 4;
+}
+
+bitfield! {
+    /// Power Control Register (PWRCTL)
+    #[derive(Copy, Clone)]
+    struct PowerControl(u8);
+    impl Debug;
+
+    pub core_wakeup, set_core_wakeup: 0;
+    pub mem_wakeup, set_mem_wakeup: 1;
+    pub debug_wakeup, set_debug_wakeup: 2;
+    pub core_reset, set_core_reset: 4;
+    pub debug_reset, set_debug_reset: 6;
+    pub jtag_debug_use, set_jtag_debug_use: 7;
+}
+
+bitfield! {
+    /// Power Status Register (PWRSTAT)
+    #[derive(Copy, Clone)]
+    struct PowerStatus(u8);
+    impl Debug;
+
+    pub core_domain_on, _: 0;
+    pub mem_domain_on, _: 1;
+    pub debug_domain_on, _: 2;
+    pub core_still_needed, _: 3;
+    pub core_was_reset, set_core_was_reset: 4;
+    pub debug_was_reset, set_debug_was_reset: 6;
+}
+
+/// A interface that implements controls for Xtensa cores.
+#[derive(Debug)]
+pub struct XtensaCommunicationInterface {
+    probe: Box<dyn JTAGAccess>,
+
+    /// The JTAG scan chain, scanned when the first core is selected.
+    chain: Option<Vec<JtagChainItem>>,
+
+    /// The TAP of the selected core, `None` if the TAP selected by the probe is used.
+    tap: Option<usize>,
+
+    /// Address registers of the current window which were overwritten by the debugger,
+    /// with their original values. They are restored before the core resumes.
+    saved_registers: HashMap<u8, u32>,
+}
+
+impl XtensaCommunicationInterface {
+    /// Create the Xtensa communication interface using the underlying probe driver
+    pub fn new(mut probe: Box<dyn JTAGAccess>) -> Result<Self, (Box<dyn JTAGAccess>, XtensaError)> {
+        probe.set_ir_len(IR_LEN);
+
+        let mut s = Self {
+            probe,
+            chain: None,
+            tap: None,
+            saved_registers: HashMap::new(),
+        };
+
+        if let Err(err) = s.enter_debug_mode() {
+            return Err((s.probe, err));
+        }
+
+        Ok(s)
+    }
+
+    /// Destruct the interface and return the stored probe driver.
+    pub fn close(self) -> Probe {
+        Probe::from_attached_probe(self.probe.into_probe())
+    }
+
+    /// Deassert the target reset.
+    pub fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
+        self.probe.target_reset_deassert()
+    }
+
+    /// Read the targets IDCODE.
+    pub fn read_idcode(&mut self) -> Result<u32, DebugProbeError> {
+        let value = self.probe.read_register(IDCODE, 32)?;
+
+        Ok(u32::from_le_bytes((&value[..]).try_into().unwrap()))
+    }
+
+    /// Select the core with the JTAG TAP at position `index` of the scan chain, counted from TDO.
+    ///
+    /// IR lengths given in `description` are used instead of detecting them when scanning the chain.
+    /// All further operations are performed on the selected core.
+    pub fn select_tap(
+        &mut self,
+        index: usize,
+        description: Option<&[ScanChainElement]>,
+    ) -> Result<(), XtensaError> {
+        if self.tap == Some(index) {
+            return Ok(());
+        }
+
+        // The saved registers belong to the currently selected core.
+        self.restore_registers()?;
+
+        let chain = match self.chain.take() {
+            Some(chain) => chain,
+            None => self.probe.scan_chain(description)?,
+        };
+
+        if index >= chain.len() {
+            self.chain = Some(chain);
+            return Err(XtensaError::TapUnavailable(index));
+        }
+
+        tracing::debug!("Selecting TAP {}", index);
+
+        self.probe
+            .set_chain_params(ChainParams::from_chain(&chain, index)?)?;
+        self.chain = Some(chain);
+        self.tap = Some(index);
+
+        self.enter_debug_mode()
+    }
+
+    /// Power up the debug domain of the core, and enable the OCD module.
+    fn enter_debug_mode(&mut self) -> Result<(), XtensaError> {
+        let mut control = PowerControl(0);
+        control.set_core_wakeup(true);
+        control.set_mem_wakeup(true);
+        control.set_debug_wakeup(true);
+        self.write_power_control(control)?;
+
+        // The debug module has to be powered up before it can be claimed by JTAG.
+        control.set_jtag_debug_use(true);
+        self.write_power_control(control)?;
+
+        let status = self.clear_power_status()?;
+        tracing::debug!("Power status: {:?}", status);
+
+        if !status.debug_domain_on() {
+            return Err(XtensaError::NoXtensaTarget);
+        }
+
+        let ocd_id = self.read_nexus(NexusRegister::OcdId)?;
+        tracing::debug!("OCDID: {:#010x}", ocd_id);
+
+        if ocd_id == 0 || ocd_id == u32::MAX {
+            return Err(XtensaError::NoXtensaTarget);
+        }
+
+        let mut dcr = DebugControl(0);
+        dcr.set_enable_ocd(true);
+        self.write_nexus(NexusRegister::DcrSet, dcr.0)
+    }
+
+    fn write_power_control(&mut self, control: PowerControl) -> Result<(), XtensaError> {
+        self.probe.write_register(PWRCTL, &[control.0], 8)?;
+
+        Ok(())
+    }
+
+    /// Read the power status, and clear the sticky reset bits.
+    fn clear_power_status(&mut self) -> Result<PowerStatus, XtensaError> {
+        let mut clear = PowerStatus(0);
+        clear.set_core_was_reset(true);
+        clear.set_debug_was_reset(true);
+
+        let status = self.probe.write_register(PWRSTAT, &[clear.0], 8)?;
+
+        Ok(PowerStatus(status[0]))
+    }
+
+    fn read_nexus(&mut self, register: NexusRegister) -> Result<u32, XtensaError> {
+        self.probe
+            .write_register(NARSEL, &[(register as u8) << 1], 8)?;
+        let value = self.probe.read_register(NARSEL, 32)?;
+
+        Ok(u32::from_le_bytes((&value[..]).try_into().unwrap()))
+    }
+
+    fn write_nexus(&mut self, register: NexusRegister, value: u32) -> Result<(), XtensaError> {
+        self.probe
+            .write_register(NARSEL, &[(register as u8) << 1 | 1], 8)?;
+        self.probe
+            .write_register(NARSEL, &value.to_le_bytes(), 32)?;
+
+        Ok(())
+    }
+
+    fn read_status(&mut self) -> Result<DebugStatus, XtensaError> {
+        Ok(DebugStatus(self.read_nexus(NexusRegister::Dsr)?))
+    }
+
+    /// Execute a single instruction on the halted core, and wait until it is done.
+    fn execute(&mut self, instruction: u32) -> Result<(), XtensaError> {
+        self.write_nexus(NexusRegister::Dir0Exec, instruction)?;
+        self.wait_for_execution(instruction)
+    }
+
+    fn wait_for_execution(&mut self, instruction: u32) -> Result<(), XtensaError> {
+        let start = Instant::now();
+
+        loop {
+            let status = self.read_status()?;
+
+            if status.exec_exception() || status.exec_overrun() {
+                // Clear the sticky error bits
+                let mut clear = DebugStatus(0);
+                clear.set_exec_done(true);
+                clear.set_exec_exception(true);
+                clear.set_exec_overrun(true);
+                self.write_nexus(NexusRegister::Dsr, clear.0)?;
+
+                return Err(if status.exec_exception() {
+                    XtensaError::ExecutionException(instruction)
+                } else {
+                    XtensaError::ExecutionOverrun(instruction)
+                });
+            }
+
+            if !status.exec_busy() {
+                return Ok(());
+            }
+
+            if start.elapsed() > EXECUTION_TIMEOUT {
+                return Err(XtensaError::Timeout);
+            }
+        }
+    }
+
+    /// Check if the core is halted.
+    pub(super) fn is_halted(&mut self) -> Result<bool, XtensaError> {
+        Ok(self.read_status()?.stopped())
+    }
+
+    /// Request the core to halt, without waiting for it.
+    pub(super) fn halt(&mut self) -> Result<(), XtensaError> {
+        let mut dcr = DebugControl(0);
+        dcr.set_enable_ocd(true);
+        dcr.set_debug_interrupt(true);
+        self.write_nexus(NexusRegister::DcrSet, dcr.0)
+    }
+
+    /// Wait until the core is halted.
+    pub(super) fn wait_for_halt(&mut self, timeout: Duration) -> Result<(), XtensaError> {
+        let start = Instant::now();
+
+        while !self.is_halted()? {
+            if start.elapsed() > timeout {
+                return Err(XtensaError::Timeout);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        // The debug interrupt has to be cleared, otherwise the core
+        // halts again immediately after resuming.
+        let mut dcr = DebugControl(0);
+        dcr.set_debug_interrupt(true);
+        self.write_nexus(NexusRegister::DcrClr, dcr.0)
+    }
+
+    /// Restore the registers overwritten by the debugger, and resume the core.
+    pub(super) fn resume(&mut self) -> Result<(), XtensaError> {
+        self.restore_registers()?;
+
+        // The core leaves debug mode, so there is no need to wait for the instruction.
+        self.write_nexus(NexusRegister::Dir0Exec, assembly::rfdo())
+    }
+
+    /// Reset the core. If `halt` is set, the core is halted before it executes the first instruction.
+    pub(super) fn reset(&mut self, halt: bool) -> Result<(), XtensaError> {
+        // The registers are lost with the reset.
+        self.saved_registers.clear();
+
+        if halt {
+            let mut dcr = DebugControl(0);
+            dcr.set_enable_ocd(true);
+            dcr.set_debug_interrupt(true);
+            self.write_nexus(NexusRegister::DcrSet, dcr.0)?;
+        }
+
+        let mut control = PowerControl(0);
+        control.set_core_wakeup(true);
+        control.set_mem_wakeup(true);
+        control.set_debug_wakeup(true);
+        control.set_jtag_debug_use(true);
+        control.set_core_reset(true);
+        self.write_power_control(control)?;
+
+        control.set_core_reset(false);
+        self.write_power_control(control)?;
+
+        let status = self.clear_power_status()?;
+        if !status.core_was_reset() {
+            tracing::warn!("The core did not report a reset: {:?}", status);
+        }
+
+        Ok(())
+    }
+
+    /// Read the address register `a{register}` of the current window.
+    pub(super) fn read_ar(&mut self, register: u8) -> Result<u32, XtensaError> {
+        match self.saved_registers.get(&register) {
+            Some(value) => Ok(*value),
+            None => self.read_ar_raw(register),
+        }
+    }
+
+    /// Write the address register `a{register}` of the current window.
+    pub(super) fn write_ar(&mut self, register: u8, value: u32) -> Result<(), XtensaError> {
+        self.saved_registers.remove(&register);
+        self.write_ar_raw(register, value)
+    }
+
+    fn read_ar_raw(&mut self, register: u8) -> Result<u32, XtensaError> {
+        self.execute(assembly::wsr(sr::DDR, register))?;
+        self.read_nexus(NexusRegister::Ddr)
+    }
+
+    fn write_ar_raw(&mut self, register: u8, value: u32) -> Result<(), XtensaError> {
+        self.write_nexus(NexusRegister::Ddr, value)?;
+        self.execute(assembly::rsr(sr::DDR, register))
+    }
+
+    /// Save the value of `a{register}`, so it can be used as scratch register.
+    fn save_register(&mut self, register: u8) -> Result<(), XtensaError> {
+        if !self.saved_registers.contains_key(&register) {
+            let value = self.read_ar_raw(register)?;
+            self.saved_registers.insert(register, value);
+        }
+
+        Ok(())
+    }
+
+    /// Restore the address registers which were used as scratch registers.
+    fn restore_registers(&mut self) -> Result<(), XtensaError> {
+        for (register, value) in std::mem::take(&mut self.saved_registers) {
+            self.write_ar_raw(register, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the special register with number `register`.
+    pub(super) fn read_sr(&mut self, register: u8) -> Result<u32, XtensaError> {
+        self.save_register(SCRATCH)?;
+        self.execute(assembly::rsr(register, SCRATCH))?;
+        self.read_ar_raw(SCRATCH)
+    }
+
+    /// Write the special register with number `register`.
+    pub(super) fn write_sr(&mut self, register: u8, value: u32) -> Result<(), XtensaError> {
+        self.save_register(SCRATCH)?;
+        self.write_ar_raw(SCRATCH, value)?;
+        self.execute(assembly::wsr(register, SCRATCH))
+    }
+
+    /// Returns the rotation of the register window which makes the physical register `ar{register}`
+    /// accessible, and the address register it is accessible as in the rotated window.
+    ///
+    /// The rotation is zero if the register is part of the current window.
+    fn physical_register_window(&mut self, register: u8) -> Result<(i8, u8), XtensaError> {
+        let window_base = self.read_sr(sr::WINDOWBASE)? as u8 % (NUM_AREGS / 4);
+        let logical = (register + NUM_AREGS - window_base * 4) % NUM_AREGS;
+
+        if logical < 16 {
+            return Ok((0, logical));
+        }
+
+        // The window is rotated in steps of four registers, in the range -8..=7.
+        let rotation = ((logical / 4) as i8 + 8) % 16 - 8;
+
+        Ok((rotation, logical % 4))
+    }
+
+    /// Read the physical address register `ar{register}`.
+    pub(super) fn read_physical_ar(&mut self, register: u8) -> Result<u32, XtensaError> {
+        match self.physical_register_window(register)? {
+            (0, logical) => self.read_ar(logical),
+            (rotation, logical) => {
+                self.execute(assembly::rotw(rotation))?;
+                let value = self.read_ar_raw(logical);
+                self.execute(assembly::rotw(-rotation))?;
+                value
+            }
+        }
+    }
+
+    /// Write the physical address register `ar{register}`.
+    pub(super) fn write_physical_ar(
+        &mut self,
+        register: u8,
+        value: u32,
+    ) -> Result<(), XtensaError> {
+        match self.physical_register_window(register)? {
+            (0, logical) => self.write_ar(logical, value),
+            (rotation, logical) => {
+                self.execute(assembly::rotw(rotation))?;
+                let result = self.write_ar_raw(logical, value);
+                self.execute(assembly::rotw(-rotation))?;
+                result
+            }
+        }
+    }
+
+    /// Run `f` with the core halted, halting it and resuming it afterwards if it is running.
+    fn halted_access<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<R, XtensaError>,
+    ) -> Result<R, XtensaError> {
+        let was_running = !self.is_halted()?;
+
+        if was_running {
+            self.halt()?;
+            self.wait_for_halt(Duration::from_millis(100))?;
+        }
+
+        let result = f(self);
+
+        if was_running {
+            self.resume()?;
+        }
+
+        result
+    }
+
+    fn read_words(&mut self, address: u32, data: &mut [u32]) -> Result<(), XtensaError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.halted_access(|this| {
+            this.save_register(SCRATCH)?;
+            this.write_ar_raw(SCRATCH, address)?;
+
+            // Each read of DDREXEC executes `lddr32.p` again, loading the next word.
+            this.execute(assembly::lddr32_p(SCRATCH))?;
+            this.write_nexus(NexusRegister::Dir0, assembly::lddr32_p(SCRATCH))?;
+
+            let (last, words) = data.split_last_mut().unwrap();
+            for word in words {
+                *word = this.read_nexus(NexusRegister::DdrExec)?;
+            }
+            this.wait_for_execution(assembly::lddr32_p(SCRATCH))?;
+
+            *last = this.read_nexus(NexusRegister::Ddr)?;
+
+            Ok(())
+        })
+    }
+
+    fn write_words(&mut self, address: u32, data: &[u32]) -> Result<(), XtensaError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.halted_access(|this| {
+            this.save_register(SCRATCH)?;
+            this.write_ar_raw(SCRATCH, address)?;
+
+            // Each write of DDREXEC executes `sddr32.p`, storing the word and advancing the address.
+            this.write_nexus(NexusRegister::Dir0, assembly::sddr32_p(SCRATCH))?;
+
+            for word in data {
+                this.write_nexus(NexusRegister::DdrExec, *word)?;
+            }
+            this.wait_for_execution(assembly::sddr32_p(SCRATCH))
+        })
+    }
+
+    /// Returns the addresses of the first and the last word containing `length` bytes at `address`.
+    fn word_range(address: u32, length: usize) -> Result<(u32, u32), XtensaError> {
+        let last_byte = u32::try_from(length - 1)
+            .ok()
+            .and_then(|offset| address.checked_add(offset))
+            .ok_or(XtensaError::AddressOutOfRange { address, length })?;
+
+        Ok((address & !3, last_byte & !3))
+    }
+
+    fn read_bytes(&mut self, address: u32, data: &mut [u8]) -> Result<(), XtensaError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        // The core only supports word accesses through DDR, so read the
+        // words containing the requested bytes.
+        let (start, last) = Self::word_range(address, data.len())?;
+        let mut words = vec![0u32; ((last - start) / 4 + 1) as usize];
+        self.read_words(start, &mut words)?;
+
+        let offset = (address - start) as usize;
+        let bytes = words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .skip(offset)
+            .take(data.len());
+
+        for (byte, value) in data.iter_mut().zip(bytes) {
+            *byte = value;
+        }
+
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, address: u32, data: &[u8]) -> Result<(), XtensaError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        // Read-modify-write the words containing the bytes.
+        let (start, last) = Self::word_range(address, data.len())?;
+        let mut words = vec![0u32; ((last - start) / 4 + 1) as usize];
+
+        let unaligned_start = address != start;
+        let unaligned_end = (address - start) as usize + data.len() != words.len() * 4;
+        if unaligned_start {
+            self.read_words(start, &mut words[..1])?;
+        }
+        if unaligned_end && !(unaligned_start && words.len() == 1) {
+            let index = words.len() - 1;
+            self.read_words(last, &mut words[index..])?;
+        }
+
+        let mut bytes = words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<_>>();
+        let offset = (address - start) as usize;
+        bytes[offset..offset + data.len()].copy_from_slice(data);
+
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+
+        self.write_words(start, &words)
+    }
+}
+
+impl MemoryInterface for XtensaCommunicationInterface {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        false
+    }
+
+    fn read_word_64(&mut self, address: u64) -> Result<u64, ProbeRsError> {
+        let mut words = [0u32; 2];
+        self.read_32(address, &mut words)?;
+
+        Ok(words[0] as u64 | (words[1] as u64) << 32)
+    }
+
+    fn read_word_32(&mut self, address: u64) -> Result<u32, ProbeRsError> {
+        let mut word = [0u32];
+        self.read_32(address, &mut word)?;
+
+        Ok(word[0])
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, ProbeRsError> {
+        let mut byte = [0u8];
+        self.read_8(address, &mut byte)?;
+
+        Ok(byte[0])
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), ProbeRsError> {
+        for (i, d) in data.iter_mut().enumerate() {
+            *d = self.read_word_64(address + i as u64 * 8)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), ProbeRsError> {
+        let address = valid_32bit_address(address)?;
+        if address % 4 != 0 {
+            return Err(ProbeRsError::MemoryNotAligned {
+                address: address as u64,
+                alignment: 4,
+            });
+        }
+
+        tracing::debug!("read_32 from {:#08x}", address);
+        Ok(self.read_words(address, data)?)
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), ProbeRsError> {
+        let address = valid_32bit_address(address)?;
+
+        tracing::debug!("read_8 from {:#08x}", address);
+        Ok(self.read_bytes(address, data)?)
+    }
+
+    fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), ProbeRsError> {
+        self.write_32(address, &[data as u32, (data >> 32) as u32])
+    }
+
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), ProbeRsError> {
+        self.write_32(address, &[data])
+    }
+
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), ProbeRsError> {
+        self.write_8(address, &[data])
+    }
+
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), ProbeRsError> {
+        for (i, d) in data.iter().enumerate() {
+            self.write_word_64(address + i as u64 * 8, *d)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), ProbeRsError> {
+        let address = valid_32bit_address(address)?;
+        if address % 4 != 0 {
+            return Err(ProbeRsError::MemoryNotAligned {
+                address: address as u64,
+                alignment: 4,
+            });
+        }
+
+        tracing::debug!("write_32 to {:#08x}", address);
+        Ok(self.write_words(address, data)?)
+    }
+
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), ProbeRsError> {
+        let address = valid_32bit_address(address)?;
+
+        tracing::debug!("write_8 to {:#08x}", address);
+        Ok(self.write_bytes(address, data)?)
+    }
+
+    fn supports_8bit_transfers(&self) -> Result<bool, ProbeRsError> {
+        Ok(true)
+    }
+
+    fn flush(&mut self) -> Result<(), ProbeRsError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::probe::{DebugProbe, DebugProbeSelector, WireProtocol};
+
+    /// The state of the simulated core.
+    #[derive(Debug, Default)]
+    struct CoreState {
+        /// Physical address registers
+        ar: Vec<u32>,
+        /// Special registers
+        sr: HashMap<u8, u32>,
+        /// Memory words, by address
+        memory: HashMap<u32, u32>,
+        ddr: u32,
+        dir0: u32,
+    }
+
+    impl CoreState {
+        /// The physical register which is accessible as `a{register}` in the current window.
+        fn ar_index(&self, register: u32) -> usize {
+            let window_base = self.sr.get(&sr::WINDOWBASE).copied().unwrap_or(0);
+            ((window_base * 4 + register) % NUM_AREGS as u32) as usize
+        }
+
+        /// Execute an instruction of the debugger.
+        fn execute(&mut self, instruction: u32) {
+            let at = instruction >> 4 & 0xF;
+            let special = (instruction >> 8 & 0xFF) as u8;
+
+            if instruction & 0xFFF0FF == assembly::lddr32_p(0) {
+                let index = self.ar_index(instruction >> 8 & 0xF);
+                self.ddr = self.memory.get(&self.ar[index]).copied().unwrap_or(0);
+                self.ar[index] = self.ar[index].wrapping_add(4);
+            } else if instruction & 0xFFF0FF == assembly::sddr32_p(0) {
+                let index = self.ar_index(instruction >> 8 & 0xF);
+                self.memory.insert(self.ar[index], self.ddr);
+                self.ar[index] = self.ar[index].wrapping_add(4);
+            } else if instruction & 0xFFFF0F == assembly::rotw(0) {
+                let rotation = (at as i8) << 4 >> 4;
+                let window_base = self.sr.entry(sr::WINDOWBASE).or_default();
+                *window_base = (*window_base as i32 + rotation as i32).rem_euclid(16) as u32;
+            } else if instruction & 0xFF000F == assembly::rsr(0, 0) {
+                let value = match special {
+                    sr::DDR => self.ddr,
+                    _ => self.sr.get(&special).copied().unwrap_or(0),
+                };
+                let index = self.ar_index(at);
+                self.ar[index] = value;
+            } else if instruction & 0xFF000F == assembly::wsr(0, 0) {
+                let value = self.ar[self.ar_index(at)];
+                match special {
+                    sr::DDR => self.ddr = value,
+                    _ => {
+                        self.sr.insert(special, value);
+                    }
+                }
+            } else {
+                panic!("Unexpected instruction {instruction:#08x}");
+            }
+        }
+    }
+
+    /// An Xtensa TAP, which executes the instructions of the debugger on a simulated core.
+    #[derive(Debug)]
+    struct MockTap {
+        core: Arc<Mutex<CoreState>>,
+        /// The Nexus register selected by the last NAR scan, and whether it is written.
+        nexus: Option<(u8, bool)>,
+    }
+
+    impl MockTap {
+        fn read_nexus(&mut self, register: u8) -> u32 {
+            let mut core = self.core.lock().unwrap();
+
+            match register {
+                r if r == NexusRegister::OcdId as u8 => 0x1234_5678,
+                // The core is always halted, and done executing.
+                r if r == NexusRegister::Dsr as u8 => 1 << 4 | 1,
+                r if r == NexusRegister::Ddr as u8 => core.ddr,
+                r if r == NexusRegister::DdrExec as u8 => {
+                    let value = core.ddr;
+                    let instruction = core.dir0;
+                    core.execute(instruction);
+                    value
+                }
+                _ => 0,
+            }
+        }
+
+        fn write_nexus(&mut self, register: u8, value: u32) {
+            let mut core = self.core.lock().unwrap();
+
+            match register {
+                r if r == NexusRegister::Ddr as u8 => core.ddr = value,
+                r if r == NexusRegister::DdrExec as u8 => {
+                    core.ddr = value;
+                    let instruction = core.dir0;
+                    core.execute(instruction);
+                }
+                r if r == NexusRegister::Dir0 as u8 => core.dir0 = value,
+                r if r == NexusRegister::Dir0Exec as u8 => {
+                    core.dir0 = value;
+                    core.execute(value);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    impl DebugProbe for MockTap {
+        fn new_from_selector(
+            _selector: impl Into<DebugProbeSelector>,
+        ) -> Result<Box<Self>, DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn get_name(&self) -> &str {
+            "Mock Xtensa TAP"
+        }
+
+        fn speed_khz(&self) -> u32 {
+            unimplemented!()
+        }
+
+        fn set_speed(&mut self, _speed_khz: u32) -> Result<u32, DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn attach(&mut self) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn detach(&mut self) -> Result<(), crate::Error> {
+            unimplemented!()
+        }
+
+        fn target_reset(&mut self) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn select_protocol(&mut self, _protocol: WireProtocol) -> Result<(), DebugProbeError> {
+            unimplemented!()
+        }
+
+        fn active_protocol(&self) -> Option<WireProtocol> {
+            Some(WireProtocol::Jtag)
+        }
+
+        fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+            self
+        }
+    }
+
+    impl JTAGAccess for MockTap {
+        fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
+            assert_eq!(
+                (address, len),
+                (NARSEL, 32),
+                "Only the NDR is read directly"
+            );
+
+            let value = match self.nexus.take() {
+                Some((register, false)) => self.read_nexus(register),
+                other => panic!("NDR read after NAR scan {other:?}"),
+            };
+
+            Ok(value.to_le_bytes().to_vec())
+        }
+
+        fn set_idle_cycles(&mut self, _idle_cycles: u8) {}
+
+        fn get_idle_cycles(&self) -> u8 {
+            0
+        }
+
+        fn set_ir_len(&mut self, len: u32) {
+            assert_eq!(len, IR_LEN);
+        }
+
+        fn write_register(
+            &mut self,
+            address: u32,
+            data: &[u8],
+            len: u32,
+        ) -> Result<Vec<u8>, DebugProbeError> {
+            match (address, len) {
+                // The debug domain is always powered.
+                (PWRSTAT, 8) => Ok(vec![1 << 2]),
+                (PWRCTL, 8) => Ok(vec![0]),
+                (NARSEL, 8) => {
+                    self.nexus = Some((data[0] >> 1, data[0] & 1 == 1));
+                    Ok(vec![0])
+                }
+                (NARSEL, 32) => {
+                    match self.nexus.take() {
+                        Some((register, true)) => {
+                            self.write_nexus(register, u32::from_le_bytes(data.try_into().unwrap()))
+                        }
+                        other => panic!("NDR write after NAR scan {other:?}"),
+                    }
+                    Ok(vec![0; 4])
+                }
+                other => panic!("Unexpected register scan {other:?}"),
+            }
+        }
+    }
+
+    fn interface() -> (XtensaCommunicationInterface, Arc<Mutex<CoreState>>) {
+        let core = Arc::new(Mutex::new(CoreState {
+            ar: vec![0; NUM_AREGS as usize],
+            ..Default::default()
+        }));
+
+        let probe = MockTap {
+            core: core.clone(),
+            nexus: None,
+        };
+        let interface = XtensaCommunicationInterface::new(Box::new(probe)).unwrap();
+
+        (interface, core)
+    }
+
+    #[test]
+    fn read_unaligned_bytes() {
+        let (mut interface, core) = interface();
+        {
+            let mut core = core.lock().unwrap();
+            core.memory.insert(0x1000, 0x0302_0100);
+            core.memory.insert(0x1004, 0x0706_0504);
+            core.memory.insert(0x1008, 0x0b0a_0908);
+        }
+
+        let mut data = [0; 7];
+        interface.read_8(0x1001, &mut data).unwrap();
+        assert_eq!(data, [1, 2, 3, 4, 5, 6, 7]);
+
+        let mut data = [0; 2];
+        interface.read_8(0x1005, &mut data).unwrap();
+        assert_eq!(data, [5, 6]);
+    }
+
+    #[test]
+    fn write_unaligned_bytes() {
+        let (mut interface, core) = interface();
+        {
+            let mut core = core.lock().unwrap();
+            core.memory.insert(0x1000, 0x0302_0100);
+            core.memory.insert(0x1004, 0x0706_0504);
+            core.memory.insert(0x1008, 0x0b0a_0908);
+        }
+
+        // Across word boundaries, the bytes around the written ones are kept.
+        interface
+            .write_8(0x1003, &[0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5])
+            .unwrap();
+        // Within a single word
+        interface.write_8(0x1009, &[0xb0, 0xb1]).unwrap();
+
+        let core = core.lock().unwrap();
+        assert_eq!(core.memory[&0x1000], 0xa002_0100);
+        assert_eq!(core.memory[&0x1004], 0xa4a3_a2a1);
+        assert_eq!(core.memory[&0x1008], 0x0bb1_b0a5);
+
+        // The original value of the scratch register is restored when the core resumes.
+        assert_eq!(interface.saved_registers.get(&SCRATCH), Some(&0));
+    }
+
+    #[test]
+    fn access_at_the_end_of_the_address_space() {
+        let (mut interface, core) = interface();
+        core.lock().unwrap().memory.insert(0xffff_fffc, 0x4433_2211);
+
+        let mut data = [0; 2];
+        interface.read_8(0xffff_fffe, &mut data).unwrap();
+        assert_eq!(data, [0x33, 0x44]);
+
+        interface.write_8(0xffff_ffff, &[0x55]).unwrap();
+        assert_eq!(core.lock().unwrap().memory[&0xffff_fffc], 0x5533_2211);
+
+        let mut data = [0; 4];
+        assert!(matches!(
+            interface.read_bytes(0xffff_fffe, &mut data),
+            Err(XtensaError::AddressOutOfRange {
+                address: 0xffff_fffe,
+                length: 4
+            })
+        ));
+        assert!(matches!(
+            interface.write_bytes(0xffff_fffe, &data),
+            Err(XtensaError::AddressOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn physical_registers_of_other_windows() {
+        let (mut interface, core) = interface();
+        {
+            let mut core = core.lock().unwrap();
+            core.sr.insert(sr::WINDOWBASE, 2);
+            for (index, register) in core.ar.iter_mut().enumerate() {
+                *register = 0x100 + index as u32;
+            }
+        }
+
+        // ar8 is a0 of the current window
+        assert_eq!(interface.read_physical_ar(8).unwrap(), 0x108);
+        // ar4 is only accessible by rotating the window back
+        assert_eq!(interface.read_physical_ar(4).unwrap(), 0x104);
+        assert_eq!(interface.read_physical_ar(63).unwrap(), 0x13f);
+
+        interface.write_physical_ar(30, 0xdead_beef).unwrap();
+
+        let core = core.lock().unwrap();
+        assert_eq!(core.ar[30], 0xdead_beef);
+        // The window is rotated back after the access.
+        assert_eq!(core.sr[&sr::WINDOWBASE], 2);
+    }
+}
@@ -0,0 +1,390 @@
+//! All the interface bits for Xtensa.
+
+use std::time::Duration;
+
+use bitfield::bitfield;
+
+use crate::core::{
+    Architecture, BreakpointCause, CoreInformation, CoreStatus, HaltReason, RegisterFile,
+    RegisterValue,
+};
+use crate::{CoreInterface, CoreType, Error, InstructionSet, MemoryInterface, RegisterId};
+
+use communication_interface::{XtensaCommunicationInterface, XtensaError};
+use register::{sr, Register, DEBUG_LEVEL, NUM_IBREAK, PC, XTENSA_REGISTERS};
+
+mod assembly;
+mod register;
+
+pub mod communication_interface;
+pub mod sequences;
+
+pub(crate) use assembly::BREAK_1_15;
+
+bitfield! {
+    /// The `DEBUGCAUSE` special register, which holds the reason for the last debug exception.
+    #[derive(Copy, Clone)]
+    struct DebugCause(u32);
+    impl Debug;
+
+    pub icount_exception, _: 0;
+    pub ibreak_exception, _: 1;
+    pub dbreak_exception, _: 2;
+    pub break_instruction, _: 3;
+    pub break_n_instruction, _: 4;
+    pub debug_interrupt, _: 5;
+}
+
+/// An interface to operate Xtensa cores.
+pub struct Xtensa<'probe> {
+    interface: &'probe mut XtensaCommunicationInterface,
+    state: &'probe mut XtensaState,
+}
+
+impl<'probe> Xtensa<'probe> {
+    /// Create a new Xtensa interface.
+    pub fn new(
+        interface: &'probe mut XtensaCommunicationInterface,
+        state: &'probe mut XtensaState,
+    ) -> Self {
+        Self { interface, state }
+    }
+
+    fn read_debug_cause(&mut self) -> Result<DebugCause, XtensaError> {
+        Ok(DebugCause(self.interface.read_sr(sr::DEBUGCAUSE)?))
+    }
+
+    /// Returns the length of the `break` instruction the core is halted on, if any.
+    fn halted_on_break_instruction(&mut self) -> Result<Option<u32>, Error> {
+        let cause = self.read_debug_cause()?;
+
+        if !(cause.break_instruction() || cause.break_n_instruction()) {
+            return Ok(None);
+        }
+
+        // The cause is not cleared by moving the PC, so check that the core is still
+        // halted on the instruction.
+        let pc = self.interface.read_sr(sr::epc(DEBUG_LEVEL))?;
+        let mut instruction = [0u8; 3];
+        self.interface.read_8(pc as u64, &mut instruction)?;
+
+        Ok(assembly::breakpoint_len(&instruction))
+    }
+}
+
+impl<'probe> CoreInterface for Xtensa<'probe> {
+    fn wait_for_core_halted(&mut self, timeout: Duration) -> Result<(), Error> {
+        Ok(self.interface.wait_for_halt(timeout)?)
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        Ok(self.interface.is_halted()?)
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        if !self.interface.is_halted()? {
+            return Ok(CoreStatus::Running);
+        }
+
+        let cause = self.read_debug_cause()?;
+
+        let reason = if cause.icount_exception() {
+            HaltReason::Step
+        } else if cause.ibreak_exception() {
+            HaltReason::Breakpoint(BreakpointCause::Hardware)
+        } else if cause.break_instruction() || cause.break_n_instruction() {
+            HaltReason::Breakpoint(BreakpointCause::Software)
+        } else if cause.dbreak_exception() {
+            HaltReason::Watchpoint
+        } else if cause.debug_interrupt() {
+            HaltReason::Request
+        } else {
+            HaltReason::Unknown
+        };
+
+        Ok(CoreStatus::Halted(reason))
+    }
+
+    fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+        self.interface.halt()?;
+        self.interface.wait_for_halt(timeout)?;
+
+        let pc = self.read_core_reg(PC)?;
+
+        Ok(CoreInformation {
+            pc: pc.try_into()?,
+            security_state: None,
+        })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        // Before we run, we always perform a single instruction step, to account for possible breakpoints that might get us stuck on the current instruction.
+        self.step()?;
+
+        Ok(self.interface.resume()?)
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Ok(self.interface.reset(false)?)
+    }
+
+    fn reset_and_halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+        self.interface.reset(true)?;
+        self.interface.wait_for_halt(timeout)?;
+
+        let pc = self.read_core_reg(PC)?;
+
+        Ok(CoreInformation {
+            pc: pc.try_into()?,
+            security_state: None,
+        })
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        // Stepping a `break` instruction would halt on it again, so skip it instead.
+        if let Some(len) = self.halted_on_break_instruction()? {
+            let pc = self.interface.read_sr(sr::epc(DEBUG_LEVEL))? + len;
+            self.interface.write_sr(sr::epc(DEBUG_LEVEL), pc)?;
+
+            return Ok(CoreInformation {
+                pc: pc as u64,
+                security_state: None,
+            });
+        }
+
+        // Disable the breakpoints, otherwise the core halts on the breakpoint
+        // at the current instruction again.
+        let breakpoints = self.interface.read_sr(sr::IBREAKENABLE)?;
+        if breakpoints != 0 {
+            self.interface.write_sr(sr::IBREAKENABLE, 0)?;
+        }
+
+        // ICOUNT raises a debug exception when it overflows after the next instruction.
+        self.interface
+            .write_sr(sr::ICOUNTLEVEL, DEBUG_LEVEL as u32)?;
+        self.interface.write_sr(sr::ICOUNT, -2i32 as u32)?;
+
+        self.interface.resume()?;
+        let halted = self.interface.wait_for_halt(Duration::from_millis(100));
+        if halted.is_err() {
+            // The core may still be running, halt it to restore the debug registers.
+            self.interface.halt()?;
+            self.interface.wait_for_halt(Duration::from_millis(100))?;
+        }
+
+        // Restore the registers before reporting an error, otherwise the core
+        // stops after every instruction once it is resumed.
+        self.interface.write_sr(sr::ICOUNTLEVEL, 0)?;
+        self.interface.write_sr(sr::ICOUNT, 0)?;
+        if breakpoints != 0 {
+            self.interface.write_sr(sr::IBREAKENABLE, breakpoints)?;
+        }
+        halted?;
+
+        let pc = self.read_core_reg(PC)?;
+
+        Ok(CoreInformation {
+            pc: pc.try_into()?,
+            security_state: None,
+        })
+    }
+
+    fn read_core_reg(&mut self, address: RegisterId) -> Result<RegisterValue, Error> {
+        let value = match Register::try_from(address) {
+            Ok(Register::Logical(register)) => self.interface.read_ar(register)?,
+            Ok(Register::Physical(register)) => self.interface.read_physical_ar(register)?,
+            Ok(Register::Special(register)) => self.interface.read_sr(register)?,
+            Err(id) => return Err(XtensaError::UnsupportedRegister(id.0).into()),
+        };
+
+        Ok(value.into())
+    }
+
+    fn write_core_reg(&mut self, address: RegisterId, value: RegisterValue) -> Result<(), Error> {
+        let value: u32 = value.try_into()?;
+
+        match Register::try_from(address) {
+            Ok(Register::Logical(register)) => self.interface.write_ar(register, value)?,
+            Ok(Register::Physical(register)) => {
+                self.interface.write_physical_ar(register, value)?
+            }
+            Ok(Register::Special(register)) => self.interface.write_sr(register, value)?,
+            Err(id) => return Err(XtensaError::UnsupportedRegister(id.0).into()),
+        }
+
+        Ok(())
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(NUM_IBREAK as u32)
+    }
+
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        let enabled = self.interface.read_sr(sr::IBREAKENABLE)? | self.state.disabled_breakpoints;
+
+        let mut breakpoints = Vec::with_capacity(NUM_IBREAK as usize);
+
+        for unit in 0..NUM_IBREAK {
+            if enabled & (1 << unit) != 0 {
+                let address = self.interface.read_sr(sr::IBREAKA0 + unit)?;
+                breakpoints.push(Some(address as u64));
+            } else {
+                breakpoints.push(None);
+            }
+        }
+
+        Ok(breakpoints)
+    }
+
+    fn enable_breakpoints(&mut self, state: bool) -> Result<(), Error> {
+        let enabled = self.interface.read_sr(sr::IBREAKENABLE)?;
+
+        if state {
+            self.interface
+                .write_sr(sr::IBREAKENABLE, enabled | self.state.disabled_breakpoints)?;
+            self.state.disabled_breakpoints = 0;
+        } else {
+            self.interface.write_sr(sr::IBREAKENABLE, 0)?;
+            self.state.disabled_breakpoints |= enabled;
+        }
+
+        self.state.hw_breakpoints_enabled = state;
+
+        Ok(())
+    }
+
+    fn set_hw_breakpoint(&mut self, unit_index: usize, addr: u64) -> Result<(), Error> {
+        let address = crate::memory::valid_32bit_address(addr)?;
+
+        if !self.hw_breakpoints_enabled() {
+            self.enable_breakpoints(true)?;
+        }
+
+        let unit = unit_index as u8;
+        self.interface.write_sr(sr::IBREAKA0 + unit, address)?;
+
+        let enabled = self.interface.read_sr(sr::IBREAKENABLE)?;
+        self.interface
+            .write_sr(sr::IBREAKENABLE, enabled | 1 << unit)?;
+
+        Ok(())
+    }
+
+    fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        let unit = unit_index as u8;
+
+        let enabled = self.interface.read_sr(sr::IBREAKENABLE)?;
+        self.interface
+            .write_sr(sr::IBREAKENABLE, enabled & !(1 << unit))?;
+        self.state.disabled_breakpoints &= !(1 << unit);
+
+        Ok(())
+    }
+
+    fn registers(&self) -> &'static RegisterFile {
+        &XTENSA_REGISTERS
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        self.state.hw_breakpoints_enabled
+    }
+
+    fn architecture(&self) -> Architecture {
+        Architecture::Xtensa
+    }
+
+    fn core_type(&self) -> CoreType {
+        CoreType::Xtensa
+    }
+
+    fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        Ok(InstructionSet::Xtensa)
+    }
+
+    fn fpu_support(&mut self) -> Result<bool, Error> {
+        // FPU registers are not supported yet.
+        Ok(false)
+    }
+}
+
+impl<'probe> MemoryInterface for Xtensa<'probe> {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        self.interface.supports_native_64bit_access()
+    }
+
+    fn read_word_64(&mut self, address: u64) -> Result<u64, Error> {
+        self.interface.read_word_64(address)
+    }
+
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        self.interface.read_word_32(address)
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        self.interface.read_word_8(address)
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), Error> {
+        self.interface.read_64(address, data)
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        self.interface.read_32(address, data)
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.interface.read_8(address, data)
+    }
+
+    fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), Error> {
+        self.interface.write_word_64(address, data)
+    }
+
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), Error> {
+        self.interface.write_word_32(address, data)
+    }
+
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
+        self.interface.write_word_8(address, data)
+    }
+
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), Error> {
+        self.interface.write_64(address, data)
+    }
+
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), Error> {
+        self.interface.write_32(address, data)
+    }
+
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
+        self.interface.write_8(address, data)
+    }
+
+    fn supports_8bit_transfers(&self) -> Result<bool, Error> {
+        self.interface.supports_8bit_transfers()
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.interface.flush()
+    }
+}
+
+/// The state of an Xtensa core, which is kept between attaches.
+#[derive(Debug)]
+pub struct XtensaState {
+    /// A flag to remember whether we want to use hw_breakpoints during stepping of the core.
+    hw_breakpoints_enabled: bool,
+
+    /// The breakpoint units which were enabled when the breakpoints were disabled,
+    /// as bit mask of `IBREAKENABLE`.
+    disabled_breakpoints: u32,
+}
+
+impl XtensaState {
+    pub(crate) fn new() -> Self {
+        Self {
+            hw_breakpoints_enabled: false,
+            disabled_breakpoints: 0,
+        }
+    }
+}
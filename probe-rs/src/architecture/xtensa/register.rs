@@ -0,0 +1,894 @@
+//! Register definitions of Xtensa LX cores.
+
+use crate::{
+    core::{RegisterDataType, RegisterDescription, RegisterFile, RegisterKind},
+    RegisterId,
+};
+
+/// The interrupt level of debug exceptions.
+///
+/// This is a configuration option of the core, all ESP32 variants use level 6.
+pub(super) const DEBUG_LEVEL: u8 = 6;
+
+/// Number of physical address registers, `ar0` to `ar63`.
+pub(super) const NUM_AREGS: u8 = 64;
+
+/// Number of instruction breakpoint units.
+pub(super) const NUM_IBREAK: u8 = 2;
+
+/// Register IDs of special registers start at this offset, followed by the special register number.
+const SPECIAL_REGISTER_OFFSET: u16 = 0x100;
+
+/// Register IDs of physical address registers start at this offset, followed by the register number.
+const PHYSICAL_REGISTER_OFFSET: u16 = 0x200;
+
+/// A register of an Xtensa core, identified by its [`RegisterId`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum Register {
+    /// Address register of the current register window, `a0` to `a15`.
+    Logical(u8),
+    /// Physical address register, `ar0` to `ar63`.
+    Physical(u8),
+    /// Special register, accessed with the `rsr` and `wsr` instructions.
+    Special(u8),
+}
+
+impl TryFrom<RegisterId> for Register {
+    type Error = RegisterId;
+
+    fn try_from(id: RegisterId) -> Result<Self, Self::Error> {
+        match id.0 {
+            0..=15 => Ok(Register::Logical(id.0 as u8)),
+            n if (SPECIAL_REGISTER_OFFSET..SPECIAL_REGISTER_OFFSET + 0x100).contains(&n) => {
+                Ok(Register::Special((n - SPECIAL_REGISTER_OFFSET) as u8))
+            }
+            n if (PHYSICAL_REGISTER_OFFSET..PHYSICAL_REGISTER_OFFSET + NUM_AREGS as u16)
+                .contains(&n) =>
+            {
+                Ok(Register::Physical((n - PHYSICAL_REGISTER_OFFSET) as u8))
+            }
+            _ => Err(id),
+        }
+    }
+}
+
+/// Special register numbers.
+pub(super) mod sr {
+    /// Loop begin address.
+    pub const LBEG: u8 = 0;
+    /// Loop end address.
+    pub const LEND: u8 = 1;
+    /// Loop count.
+    pub const LCOUNT: u8 = 2;
+    /// Shift amount register.
+    pub const SAR: u8 = 3;
+    /// Position of the current register window.
+    pub const WINDOWBASE: u8 = 72;
+    /// One bit per register window, set if the window is in use.
+    pub const WINDOWSTART: u8 = 73;
+    /// Enable bits of the instruction breakpoints.
+    pub const IBREAKENABLE: u8 = 96;
+    /// Debug data register, shared with the OCD module.
+    pub const DDR: u8 = 104;
+    /// Address of the first instruction breakpoint.
+    pub const IBREAKA0: u8 = 128;
+    /// Processor configuration ID.
+    pub const CONFIGID0: u8 = 176;
+    /// Processor configuration ID.
+    pub const CONFIGID1: u8 = 208;
+    /// Cause of the last exception.
+    pub const EXCCAUSE: u8 = 232;
+    /// Cause of the last debug exception.
+    pub const DEBUGCAUSE: u8 = 233;
+    /// Instruction counter, raises a debug exception when it overflows.
+    pub const ICOUNT: u8 = 236;
+    /// Interrupt level below which ICOUNT is incremented.
+    pub const ICOUNTLEVEL: u8 = 237;
+
+    /// Exception program counter of interrupt level `level`.
+    pub const fn epc(level: u8) -> u8 {
+        176 + level
+    }
+
+    /// Exception processor state of interrupt level `level`.
+    pub const fn eps(level: u8) -> u8 {
+        192 + level
+    }
+}
+
+/// The program counter of the halted core, which is saved in `EPC` of the debug level.
+pub(super) const PC: RegisterId = RegisterId(SPECIAL_REGISTER_OFFSET | sr::epc(DEBUG_LEVEL) as u16);
+
+/// The processor state of the halted core, which is saved in `EPS` of the debug level.
+pub(super) const PS: RegisterId = RegisterId(SPECIAL_REGISTER_OFFSET | sr::eps(DEBUG_LEVEL) as u16);
+
+pub(super) static XTENSA_REGISTERS: RegisterFile = RegisterFile {
+    platform_registers: &[
+        RegisterDescription {
+            name: "a0",
+            _kind: RegisterKind::General,
+            id: RegisterId(0),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a1",
+            _kind: RegisterKind::General,
+            id: RegisterId(1),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a2",
+            _kind: RegisterKind::General,
+            id: RegisterId(2),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a3",
+            _kind: RegisterKind::General,
+            id: RegisterId(3),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a4",
+            _kind: RegisterKind::General,
+            id: RegisterId(4),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a5",
+            _kind: RegisterKind::General,
+            id: RegisterId(5),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a6",
+            _kind: RegisterKind::General,
+            id: RegisterId(6),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a7",
+            _kind: RegisterKind::General,
+            id: RegisterId(7),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a8",
+            _kind: RegisterKind::General,
+            id: RegisterId(8),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a9",
+            _kind: RegisterKind::General,
+            id: RegisterId(9),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a10",
+            _kind: RegisterKind::General,
+            id: RegisterId(10),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a11",
+            _kind: RegisterKind::General,
+            id: RegisterId(11),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a12",
+            _kind: RegisterKind::General,
+            id: RegisterId(12),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a13",
+            _kind: RegisterKind::General,
+            id: RegisterId(13),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a14",
+            _kind: RegisterKind::General,
+            id: RegisterId(14),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a15",
+            _kind: RegisterKind::General,
+            id: RegisterId(15),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+    ],
+
+    program_counter: &RegisterDescription {
+        name: "pc",
+        _kind: RegisterKind::PC,
+        id: PC,
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    },
+
+    return_address: &RegisterDescription {
+        name: "a0",
+        _kind: RegisterKind::General,
+        id: RegisterId(0),
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    },
+
+    stack_pointer: &RegisterDescription {
+        name: "a1",
+        _kind: RegisterKind::General,
+        id: RegisterId(1),
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    },
+
+    frame_pointer: &RegisterDescription {
+        name: "a7",
+        _kind: RegisterKind::General,
+        id: RegisterId(7),
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    },
+
+    argument_registers: &[
+        RegisterDescription {
+            name: "a2",
+            _kind: RegisterKind::General,
+            id: RegisterId(2),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a3",
+            _kind: RegisterKind::General,
+            id: RegisterId(3),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a4",
+            _kind: RegisterKind::General,
+            id: RegisterId(4),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a5",
+            _kind: RegisterKind::General,
+            id: RegisterId(5),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a6",
+            _kind: RegisterKind::General,
+            id: RegisterId(6),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a7",
+            _kind: RegisterKind::General,
+            id: RegisterId(7),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+    ],
+
+    result_registers: &[
+        RegisterDescription {
+            name: "a2",
+            _kind: RegisterKind::General,
+            id: RegisterId(2),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a3",
+            _kind: RegisterKind::General,
+            id: RegisterId(3),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+    ],
+
+    psp: None,
+    msp: None,
+    psr: Some(&RegisterDescription {
+        name: "ps",
+        _kind: RegisterKind::General,
+        id: PS,
+        _type: RegisterDataType::UnsignedInteger,
+        size_in_bits: 32,
+    }),
+    fp_registers: None,
+    fp_status: None,
+    other: &[
+        RegisterDescription {
+            name: "lbeg",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::LBEG as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "lend",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::LEND as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "lcount",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::LCOUNT as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "sar",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::SAR as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "windowbase",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::WINDOWBASE as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "windowstart",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::WINDOWSTART as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "configid0",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::CONFIGID0 as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "configid1",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::CONFIGID1 as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "exccause",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::EXCCAUSE as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "debugcause",
+            _kind: RegisterKind::General,
+            id: RegisterId(SPECIAL_REGISTER_OFFSET | sr::DEBUGCAUSE as u16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar0",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar1",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 1),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar2",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 2),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar3",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 3),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar4",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 4),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar5",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 5),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar6",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 6),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar7",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 7),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar8",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 8),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar9",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 9),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar10",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 10),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar11",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 11),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar12",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 12),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar13",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 13),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar14",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 14),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar15",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 15),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar16",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 16),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar17",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 17),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar18",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 18),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar19",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 19),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar20",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 20),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar21",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 21),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar22",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 22),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar23",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 23),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar24",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 24),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar25",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 25),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar26",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 26),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar27",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 27),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar28",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 28),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar29",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 29),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar30",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 30),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar31",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 31),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar32",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 32),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar33",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 33),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar34",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 34),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar35",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 35),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar36",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 36),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar37",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 37),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar38",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 38),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar39",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 39),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar40",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 40),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar41",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 41),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar42",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 42),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar43",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 43),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar44",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 44),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar45",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 45),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar46",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 46),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar47",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 47),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar48",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 48),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar49",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 49),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar50",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 50),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar51",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 51),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar52",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 52),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar53",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 53),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar54",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 54),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar55",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 55),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar56",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 56),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar57",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 57),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar58",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 58),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar59",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 59),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar60",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 60),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar61",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 61),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar62",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 62),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "ar63",
+            _kind: RegisterKind::General,
+            id: RegisterId(PHYSICAL_REGISTER_OFFSET | 63),
+            _type: RegisterDataType::UnsignedInteger,
+            size_in_bits: 32,
+        },
+    ],
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn register_from_id() {
+        assert_eq!(Register::try_from(RegisterId(0)), Ok(Register::Logical(0)));
+        assert_eq!(
+            Register::try_from(RegisterId(15)),
+            Ok(Register::Logical(15))
+        );
+        assert_eq!(Register::try_from(RegisterId(16)), Err(RegisterId(16)));
+
+        assert_eq!(
+            Register::try_from(PC),
+            Ok(Register::Special(sr::epc(DEBUG_LEVEL)))
+        );
+        assert_eq!(
+            Register::try_from(RegisterId(0x1ff)),
+            Ok(Register::Special(0xff))
+        );
+
+        assert_eq!(
+            Register::try_from(RegisterId(0x200)),
+            Ok(Register::Physical(0))
+        );
+        assert_eq!(
+            Register::try_from(RegisterId(0x23f)),
+            Ok(Register::Physical(63))
+        );
+        assert_eq!(
+            Register::try_from(RegisterId(0x240)),
+            Err(RegisterId(0x240))
+        );
+    }
+
+    #[test]
+    fn register_file_ids_are_valid() {
+        for register in XTENSA_REGISTERS.platform_registers {
+            assert!(
+                Register::try_from(register.id).is_ok(),
+                "{} has an invalid ID",
+                register.name
+            );
+        }
+    }
+}
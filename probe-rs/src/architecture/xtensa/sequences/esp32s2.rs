@@ -0,0 +1,47 @@
+//! Sequences for the ESP32-S2.
+
+use std::sync::Arc;
+
+use super::XtensaDebugSequence;
+use crate::MemoryInterface;
+
+/// The debug sequence implementation for the ESP32-S2.
+pub struct ESP32S2(());
+
+impl ESP32S2 {
+    /// Creates a new debug sequence handle for the ESP32-S2.
+    pub fn create() -> Arc<dyn XtensaDebugSequence> {
+        Arc::new(Self(()))
+    }
+}
+
+impl XtensaDebugSequence for ESP32S2 {
+    fn on_connect(
+        &self,
+        interface: &mut crate::architecture::xtensa::communication_interface::XtensaCommunicationInterface,
+    ) -> Result<(), crate::Error> {
+        tracing::info!("Disabling esp32s2 watchdogs...");
+        // disable super wdt
+        interface.write_word_32(0x3f4080b4, 0x8F1D312Au32)?; // write protection off
+        let current = interface.read_word_32(0x3f4080b0)?;
+        interface.write_word_32(0x3f4080b0, current | 1 << 31)?; // set RTC_CNTL_SWD_AUTO_FEED_EN
+        interface.write_word_32(0x3f4080b4, 0x0)?; // write protection on
+
+        // tg0 wdg
+        interface.write_word_32(0x3f41f064, 0x50D83AA1u32)?; // write protection off
+        interface.write_word_32(0x3f41f048, 0x0)?;
+        interface.write_word_32(0x3f41f064, 0x0)?; // write protection on
+
+        // tg1 wdg
+        interface.write_word_32(0x3f420064, 0x50D83AA1u32)?; // write protection off
+        interface.write_word_32(0x3f420048, 0x0)?;
+        interface.write_word_32(0x3f420064, 0x0)?; // write protection on
+
+        // rtc wdg
+        interface.write_word_32(0x3f4080ac, 0x50D83AA1u32)?; // write protection off
+        interface.write_word_32(0x3f408094, 0x0)?;
+        interface.write_word_32(0x3f4080ac, 0x0)?; // write protection on
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+//! Sequences for the ESP32-S3.
+
+use std::sync::Arc;
+
+use super::XtensaDebugSequence;
+use crate::MemoryInterface;
+
+/// The debug sequence implementation for the ESP32-S3.
+pub struct ESP32S3(());
+
+impl ESP32S3 {
+    /// Creates a new debug sequence handle for the ESP32-S3.
+    pub fn create() -> Arc<dyn XtensaDebugSequence> {
+        Arc::new(Self(()))
+    }
+}
+
+impl XtensaDebugSequence for ESP32S3 {
+    fn on_connect(
+        &self,
+        interface: &mut crate::architecture::xtensa::communication_interface::XtensaCommunicationInterface,
+    ) -> Result<(), crate::Error> {
+        tracing::info!("Disabling esp32s3 watchdogs...");
+        // disable super wdt
+        interface.write_word_32(0x600080b8, 0x8F1D312Au32)?; // write protection off
+        let current = interface.read_word_32(0x600080b4)?;
+        interface.write_word_32(0x600080b4, current | 1 << 31)?; // set RTC_CNTL_SWD_AUTO_FEED_EN
+        interface.write_word_32(0x600080b8, 0x0)?; // write protection on
+
+        // tg0 wdg
+        interface.write_word_32(0x6001f064, 0x50D83AA1u32)?; // write protection off
+        interface.write_word_32(0x6001f048, 0x0)?;
+        interface.write_word_32(0x6001f064, 0x0)?; // write protection on
+
+        // tg1 wdg
+        interface.write_word_32(0x60020064, 0x50D83AA1u32)?; // write protection off
+        interface.write_word_32(0x60020048, 0x0)?;
+        interface.write_word_32(0x60020064, 0x0)?; // write protection on
+
+        // rtc wdg
+        interface.write_word_32(0x600080b0, 0x50D83AA1u32)?; // write protection off
+        interface.write_word_32(0x60008098, 0x0)?;
+        interface.write_word_32(0x600080b0, 0x0)?; // write protection on
+
+        Ok(())
+    }
+}
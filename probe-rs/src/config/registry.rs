@@ -102,10 +102,11 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 cores: vec![Core {
                     name: "core".to_owned(),
                     core_type: CoreType::Riscv,
-                    core_access_options: CoreAccessOptions::Riscv(RiscvCoreAccessOptions {}),
+                    core_access_options: CoreAccessOptions::Riscv(RiscvCoreAccessOptions::default()),
                 }],
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                jtag: None,
             }],
             flash_algorithms: vec![],
             source: TargetDescriptionSource::Generic,
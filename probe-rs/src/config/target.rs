@@ -1,4 +1,4 @@
-use probe_rs_target::{Architecture, ChipFamily};
+use probe_rs_target::{Architecture, ChipFamily, Jtag, ScanChainElement};
 
 use super::{Core, MemoryRegion, RawFlashAlgorithm, RegistryError, TargetDescriptionSource};
 use crate::architecture::arm::sequences::{
@@ -14,6 +14,9 @@ use crate::architecture::arm::sequences::{
 };
 use crate::architecture::riscv::sequences::esp32c3::ESP32C3;
 use crate::architecture::riscv::sequences::{DefaultRiscvSequence, RiscvDebugSequence};
+use crate::architecture::xtensa::sequences::{
+    esp32::ESP32, esp32s2::ESP32S2, esp32s3::ESP32S3, DefaultXtensaSequence, XtensaDebugSequence,
+};
 use crate::flashing::FlashLoader;
 use std::sync::Arc;
 
@@ -34,6 +37,8 @@ pub struct Target {
     pub(crate) source: TargetDescriptionSource,
     /// Debug sequences for the given target.
     pub debug_sequence: DebugSequence,
+    /// The JTAG scan chain the target is part of, if it was described.
+    pub jtag: Option<Jtag>,
 }
 
 impl std::fmt::Debug for Target {
@@ -93,6 +98,7 @@ impl Target {
         let mut debug_sequence = match chip.cores[0].core_type.architecture() {
             Architecture::Arm => DebugSequence::Arm(DefaultArmSequence::create()),
             Architecture::Riscv => DebugSequence::Riscv(DefaultRiscvSequence::create()),
+            Architecture::Xtensa => DebugSequence::Xtensa(DefaultXtensaSequence::create()),
         };
 
         if chip.name.starts_with("MIMXRT10") {
@@ -107,6 +113,15 @@ impl Target {
         } else if chip.name.starts_with("esp32c3") {
             tracing::warn!("Using custom sequence for ESP32c3");
             debug_sequence = DebugSequence::Riscv(ESP32C3::create());
+        } else if chip.name.starts_with("esp32s3") {
+            tracing::warn!("Using custom sequence for ESP32s3");
+            debug_sequence = DebugSequence::Xtensa(ESP32S3::create());
+        } else if chip.name.starts_with("esp32s2") {
+            tracing::warn!("Using custom sequence for ESP32s2");
+            debug_sequence = DebugSequence::Xtensa(ESP32S2::create());
+        } else if chip.name == "esp32" {
+            tracing::warn!("Using custom sequence for ESP32");
+            debug_sequence = DebugSequence::Xtensa(ESP32::create());
         } else if chip.name.starts_with("nRF5340") {
             tracing::warn!("Using custom sequence for nRF5340");
             debug_sequence = DebugSequence::Arm(Nrf5340::create());
@@ -141,6 +156,7 @@ impl Target {
             source: family.source.clone(),
             memory_map: chip.memory_map.clone(),
             debug_sequence,
+            jtag: chip.jtag.clone(),
         })
     }
 
@@ -190,6 +206,13 @@ impl Target {
             _ => false,
         })
     }
+
+    /// Gets the description of the JTAG scan chain, if the target has one
+    pub(crate) fn jtag_scan_chain(&self) -> Option<&[ScanChainElement]> {
+        self.jtag
+            .as_ref()
+            .and_then(|jtag| jtag.scan_chain.as_deref())
+    }
 }
 
 /// Selector for the debug target.
@@ -247,4 +270,6 @@ pub enum DebugSequence {
     Arm(Arc<dyn ArmDebugSequence>),
     /// A RISC-V debug sequence.
     Riscv(Arc<dyn RiscvDebugSequence>),
+    /// An Xtensa debug sequence.
+    Xtensa(Arc<dyn XtensaDebugSequence>),
 }
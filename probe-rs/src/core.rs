@@ -1,12 +1,15 @@
 use crate::architecture::arm::memory::adi_v5_memory_interface::ArmProbe;
 use crate::architecture::riscv::RiscVState;
+use crate::architecture::xtensa::XtensaState;
 use crate::{CoreType, InstructionSet};
 use num_traits::Zero;
+use probe_rs_target::XtensaCoreAccessOptions;
 pub use probe_rs_target::{Architecture, CoreAccessOptions};
 
 use crate::architecture::{
     arm::core::CortexAState, arm::core::CortexMState,
     riscv::communication_interface::RiscvCommunicationInterface,
+    xtensa::communication_interface::XtensaCommunicationInterface,
 };
 use crate::error;
 use crate::Target;
@@ -29,6 +32,18 @@ pub trait MemoryMappedRegister: Clone + From<u32> + Into<u32> + Sized + std::fmt
 pub struct CoreInformation {
     /// The current Program Counter.
     pub pc: u64,
+    /// The security state the core is executing in, for cores with a security extension.
+    pub security_state: Option<SecurityState>,
+}
+
+/// The security state of a core with the ARMv8-M Security Extension (TrustZone), or of a memory
+/// access.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SecurityState {
+    /// Secure state.
+    Secure,
+    /// Non-secure state.
+    NonSecure,
 }
 
 /// The type of data stored in a register
@@ -560,10 +575,72 @@ pub trait CoreInterface: MemoryInterface {
     /// decision for some core types.
     fn fpu_support(&mut self) -> Result<bool, error::Error>;
 
+    /// Returns `true` if the core is currently running with 64-bit wide addresses and pointers.
+    fn is_64_bit(&self) -> bool {
+        false
+    }
+
     /// Called during session stop to do any pending cleanup
     fn on_session_stop(&mut self) -> Result<(), Error> {
         Ok(())
     }
+
+    /// The security state the core is executing in.
+    ///
+    /// Returns `None` if the core doesn't implement a security extension.
+    fn security_state(&mut self) -> Result<Option<SecurityState>, Error> {
+        Ok(None)
+    }
+
+    /// Select the security state of the memory accesses through this core.
+    ///
+    /// With `None`, the default security state of the core's access port is used.
+    fn set_memory_security_state(&mut self, state: Option<SecurityState>) -> Result<(), Error> {
+        match state {
+            None => Ok(()),
+            Some(_) => Err(Error::Other(anyhow::anyhow!(
+                "The core does not support selecting the security state of memory accesses"
+            ))),
+        }
+    }
+
+    /// Translate a virtual address to a physical address using the MMU of the core.
+    ///
+    /// Cores without an MMU return the address unchanged.
+    fn translate_address(&mut self, address: u64) -> Result<u64, Error> {
+        Ok(address)
+    }
+
+    /// Prepare the halted core to be restarted by a request from outside of the core, e.g.
+    /// through a cross trigger interface, instead of [`CoreInterface::run`].
+    ///
+    /// Registers which were changed by the debugger have to be written back to the core here.
+    fn prepare_for_restart(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Clean the data cache lines covering `length` bytes starting at `address`, writing any
+    /// data cached by the core back to memory.
+    ///
+    /// Cores without caches which are bypassed by debug memory accesses don't need to do anything.
+    fn clean_data_cache(&mut self, _address: u64, _length: u64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Clean and invalidate the data cache lines covering `length` bytes starting at `address`,
+    /// so that the core sees data written to memory by other bus masters.
+    ///
+    /// Cores without caches which are bypassed by debug memory accesses don't need to do anything.
+    fn invalidate_data_cache(&mut self, _address: u64, _length: u64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Invalidate the instruction cache and branch predictor of the core.
+    ///
+    /// Cores without caches which are bypassed by debug memory accesses don't need to do anything.
+    fn invalidate_instruction_cache(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
 impl<'probe> MemoryInterface for Core<'probe> {
@@ -643,6 +720,10 @@ pub struct CoreState {
 
     /// Information needed to access the core
     core_access_options: CoreAccessOptions,
+
+    /// The addresses of the hardware breakpoints set through probe-rs,
+    /// which are restored when reconnecting to the target.
+    hw_breakpoints: Vec<u64>,
 }
 
 impl CoreState {
@@ -651,6 +732,7 @@ impl CoreState {
         Self {
             id,
             core_access_options,
+            hw_breakpoints: Vec::new(),
         }
     }
 
@@ -659,6 +741,11 @@ impl CoreState {
     pub fn id(&self) -> usize {
         self.id
     }
+
+    /// Returns the addresses of the hardware breakpoints set through probe-rs.
+    pub(crate) fn hw_breakpoints(&self) -> &[u64] {
+        &self.hw_breakpoints
+    }
 }
 
 /// The architecture specific core state.
@@ -678,6 +765,10 @@ pub enum SpecificCoreState {
     Armv8m(CortexMState),
     /// The state of an RISC-V core.
     Riscv(RiscVState),
+    /// The state of an RV64 RISC-V core.
+    Riscv64(RiscVState),
+    /// The state of an Xtensa core.
+    Xtensa(XtensaState),
 }
 
 impl SpecificCoreState {
@@ -690,6 +781,8 @@ impl SpecificCoreState {
             CoreType::Armv8a => SpecificCoreState::Armv8a(CortexAState::new()),
             CoreType::Armv8m => SpecificCoreState::Armv8m(CortexMState::new()),
             CoreType::Riscv => SpecificCoreState::Riscv(RiscVState::new()),
+            CoreType::Riscv64 => SpecificCoreState::Riscv64(RiscVState::new()),
+            CoreType::Xtensa => SpecificCoreState::Xtensa(XtensaState::new()),
         }
     }
 
@@ -702,6 +795,8 @@ impl SpecificCoreState {
             SpecificCoreState::Armv8a(_) => CoreType::Armv8a,
             SpecificCoreState::Armv8m(_) => CoreType::Armv8m,
             SpecificCoreState::Riscv(_) => CoreType::Riscv,
+            SpecificCoreState::Riscv64(_) => CoreType::Riscv64,
+            SpecificCoreState::Xtensa(_) => CoreType::Xtensa,
         }
     }
 
@@ -713,7 +808,7 @@ impl SpecificCoreState {
     ) -> Result<Core<'probe>, Error> {
         let debug_sequence = match &target.debug_sequence {
             crate::config::DebugSequence::Arm(sequence) => sequence.clone(),
-            crate::config::DebugSequence::Riscv(_) => {
+            crate::config::DebugSequence::Riscv(_) | crate::config::DebugSequence::Xtensa(_) => {
                 return Err(Error::UnableToOpenProbe(
                     "Core architecture and Probe mismatch.",
                 ))
@@ -722,7 +817,7 @@ impl SpecificCoreState {
 
         let options = match &state.core_access_options {
             CoreAccessOptions::Arm(options) => options,
-            CoreAccessOptions::Riscv(_) => {
+            CoreAccessOptions::Riscv(_) | CoreAccessOptions::Xtensa(_) => {
                 return Err(Error::UnableToOpenProbe(
                     "Core architecture and Probe mismatch.",
                 ))
@@ -773,12 +868,50 @@ impl SpecificCoreState {
         &'probe mut self,
         state: &'probe mut CoreState,
         interface: &'probe mut RiscvCommunicationInterface,
+        target: &Target,
     ) -> Result<Core<'probe>, Error> {
+        if let CoreAccessOptions::Riscv(options) = &state.core_access_options {
+            if let Some(tap) = options.jtag_tap {
+                interface.select_tap(tap, target.jtag_scan_chain())?;
+            }
+            interface.select_hart(options.hart_index)?;
+        }
+
         Ok(match self {
             SpecificCoreState::Riscv(s) => Core::new(
                 crate::architecture::riscv::Riscv32::new(interface, s),
                 state,
             ),
+            SpecificCoreState::Riscv64(s) => Core::new(
+                crate::architecture::riscv::Riscv64::new(interface, s),
+                state,
+            ),
+            _ => {
+                return Err(Error::UnableToOpenProbe(
+                    "Core architecture and Probe mismatch.",
+                ))
+            }
+        })
+    }
+
+    pub(crate) fn attach_xtensa<'probe>(
+        &'probe mut self,
+        state: &'probe mut CoreState,
+        interface: &'probe mut XtensaCommunicationInterface,
+        target: &Target,
+    ) -> Result<Core<'probe>, Error> {
+        if let CoreAccessOptions::Xtensa(XtensaCoreAccessOptions {
+            jtag_tap: Some(tap),
+        }) = &state.core_access_options
+        {
+            interface.select_tap(*tap, target.jtag_scan_chain())?;
+        }
+
+        Ok(match self {
+            SpecificCoreState::Xtensa(s) => Core::new(
+                crate::architecture::xtensa::Xtensa::new(interface, s),
+                state,
+            ),
             _ => {
                 return Err(Error::UnableToOpenProbe(
                     "Core architecture and Probe mismatch.",
@@ -984,6 +1117,11 @@ impl<'probe> Core<'probe> {
         // Actually set the breakpoint. Even if it has been set, set it again so it will be active.
         self.inner
             .set_hw_breakpoint(breakpoint_comparator_index, address)?;
+
+        if !self.state.hw_breakpoints.contains(&address) {
+            self.state.hw_breakpoints.push(address);
+        }
+
         Ok(())
     }
 
@@ -1007,6 +1145,7 @@ impl<'probe> Core<'probe> {
         match bp_position {
             Some(bp_position) => {
                 self.inner.clear_hw_breakpoint(bp_position)?;
+                self.state.hw_breakpoints.retain(|&bp| bp != address);
                 Ok(())
             }
             None => Err(error::Error::Other(anyhow!(
@@ -1053,6 +1192,79 @@ impl<'probe> Core<'probe> {
         self.inner.fpu_support()
     }
 
+    /// Returns `true` if the core is currently running with 64-bit wide addresses and pointers,
+    /// e.g. an ARMv8-A core in AArch64 state or a RV64 core.
+    pub fn is_64_bit(&self) -> bool {
+        self.inner.is_64_bit()
+    }
+
+    /// The security state the core is executing in, or `None` if the core doesn't implement a
+    /// security extension.
+    pub fn security_state(&mut self) -> Result<Option<SecurityState>, error::Error> {
+        self.inner.security_state()
+    }
+
+    /// Access memory through this core as secure or non-secure, e.g. to read the non-secure
+    /// alias of a memory region on an ARMv8-M core with TrustZone.
+    ///
+    /// The selection is kept for the core until it is reset to `None`, which restores the
+    /// default security state of the access port of the core.
+    #[tracing::instrument(skip(self))]
+    pub fn set_memory_security_state(
+        &mut self,
+        state: Option<SecurityState>,
+    ) -> Result<(), error::Error> {
+        self.inner.set_memory_security_state(state)
+    }
+
+    /// Translate a virtual address to the physical address it is mapped to by the MMU of the
+    /// core, in the translation regime the core is halted in.
+    ///
+    /// Memory accesses through a Cortex-A core use virtual addresses, like the code running on
+    /// the core. The physical address is needed to access the same memory through another path,
+    /// e.g. a memory access port on the system bus. Cores without an MMU return the address
+    /// unchanged.
+    #[tracing::instrument(skip(self))]
+    pub fn translate_address(&mut self, address: u64) -> Result<u64, error::Error> {
+        self.inner.translate_address(address)
+    }
+
+    /// Prepare the halted core to be restarted through its cross trigger interface.
+    #[tracing::instrument(skip(self))]
+    pub(crate) fn prepare_for_restart(&mut self) -> Result<(), Error> {
+        self.inner.prepare_for_restart()
+    }
+
+    /// Clean the data cache lines of the core covering `length` bytes starting at `address`.
+    ///
+    /// This has to be done before reading memory which was written by the core through another
+    /// path than the core itself, e.g. a memory access port on the system bus, as the data may
+    /// still be held in the cache of the core. The core has to be halted.
+    #[tracing::instrument(skip(self))]
+    pub fn clean_data_cache(&mut self, address: u64, length: u64) -> Result<(), error::Error> {
+        self.inner.clean_data_cache(address, length)
+    }
+
+    /// Clean and invalidate the data cache lines of the core covering `length` bytes starting at
+    /// `address`.
+    ///
+    /// This has to be done after writing memory through another path than the core itself, so
+    /// the core doesn't keep using stale cached data. The core has to be halted.
+    #[tracing::instrument(skip(self))]
+    pub fn invalidate_data_cache(&mut self, address: u64, length: u64) -> Result<(), error::Error> {
+        self.inner.invalidate_data_cache(address, length)
+    }
+
+    /// Invalidate the instruction cache of the core, e.g. after code was modified through another
+    /// path than the core itself. The core has to be halted.
+    ///
+    /// Memory written through the [`MemoryInterface`] of a Cortex-A core is synchronized with its
+    /// instruction cache automatically before the core is resumed.
+    #[tracing::instrument(skip(self))]
+    pub fn invalidate_instruction_cache(&mut self) -> Result<(), error::Error> {
+        self.inner.invalidate_instruction_cache()
+    }
+
     /// Called during session tear down to do any pending cleanup
     #[tracing::instrument(skip(self))]
     pub(crate) fn on_session_stop(&mut self) -> Result<(), Error> {
@@ -1,6 +1,7 @@
 use super::{
     function_die::FunctionDie, get_sequential_key, unit_info::UnitInfo, unit_info::UnitIter,
-    variable::*, DebugError, DebugRegisters, SourceLocation, StackFrame, VariableCache,
+    variable::*, DebugError, DebugRegisters, SourceLocation, StackFrame, StaticVariable,
+    VariableCache,
 };
 use crate::{
     core::Core,
@@ -114,6 +115,41 @@ impl DebugInfo {
         Ok(None)
     }
 
+    /// Get the names of all functions at the given address, including inlined functions.
+    ///
+    /// The outermost function comes first, followed by the functions inlined into it, ending
+    /// with the innermost function. If no function is found, the list is empty.
+    pub fn inlined_function_names(&self, address: u64) -> Result<Vec<String>, DebugError> {
+        let mut units = self.dwarf.units();
+
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let functions = unit_info.get_function_dies(address, None, true)?;
+
+            let names: Vec<String> = functions
+                .iter()
+                .filter_map(|die_cursor_state| die_cursor_state.function_name())
+                .collect();
+
+            if !names.is_empty() {
+                return Ok(names);
+            }
+        }
+
+        Ok(Vec::new())
+    }
+
+    /// Resolve the address and type of a static variable, or of a field or element of it.
+    ///
+    /// The path starts with the name of the variable, optionally with the namespaces it is
+    /// declared in, e.g. `NAME` or `module::NAME`. It can be followed by fields and array
+    /// indices, e.g. `CONTROLLER.state.error` or `SAMPLES[3]`. The resolved variable must
+    /// have a scalar type: an integer, a float, a bool, a C-like enum or a pointer.
+    ///
+    /// As the variable has a fixed address, it can be read while the core is running.
+    pub fn resolve_static_variable(&self, path: &str) -> Result<StaticVariable, DebugError> {
+        super::static_variable::resolve(&self.dwarf, path)
+    }
+
     /// Try get the [`SourceLocation`] for a given address.
     pub fn get_source_location(&self, address: u64) -> Option<SourceLocation> {
         let mut units = self.dwarf.units();
@@ -1219,6 +1255,15 @@ fn unwind_register(
                                         // NOTE: [ARMv7-M Architecture Reference Manual](https://developer.arm.com/documentation/ddi0403/ee), Section A5.1.2: We have to clear the last bit to ensure the PC is half-word aligned. (on ARM architecture, when in Thumb state for certain instruction types will set the LSB to 1)
                                         register_rule_string = "PC=(unwound LR & !0b1) (dwarf Undefined)".to_string();
                                         Some(RegisterValue::U32(return_address  & !0b1))
+                                    } else if matches!(core.instruction_set(), Ok(InstructionSet::Xtensa)) {
+                                        // NOTE: The Xtensa windowed ABI stores the window increment of the `CALLn` instruction in the top two bits of the return address. The actual address shares its top two bits with the callee.
+                                        register_rule_string = "PC=(unwound LR & 0x3FFF_FFFF | callee PC & 0xC000_0000) (dwarf Undefined)".to_string();
+                                        let callee_pc: u32 = callee_frame_registers
+                                            .get_program_counter()
+                                            .and_then(|pc| pc.value)
+                                            .and_then(|pc| pc.try_into().ok())
+                                            .unwrap_or(0);
+                                        Some(RegisterValue::U32((return_address & 0x3FFF_FFFF) | (callee_pc & 0xC000_0000)))
                                     } else{
                                         Some(RegisterValue::U32(return_address))
                                     }
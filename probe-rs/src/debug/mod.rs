@@ -20,6 +20,8 @@ pub mod registers;
 pub(crate) mod source_statement;
 /// The stack frame information used while unwinding the stack from a specific program counter.
 pub mod stack_frame;
+/// Variables at fixed addresses, which can be read while the core is running.
+pub mod static_variable;
 /// Information about a Unit in the debug information.
 pub mod unit_info;
 /// Variable information used during debug.
@@ -28,7 +30,12 @@ pub mod variable;
 pub mod variable_cache;
 
 pub use self::{
-    debug_info::*, debug_step::SteppingMode, registers::*, stack_frame::StackFrame, variable::*,
+    debug_info::*,
+    debug_step::SteppingMode,
+    registers::*,
+    stack_frame::StackFrame,
+    static_variable::{ScalarType, ScalarValue, StaticVariable},
+    variable::*,
     variable_cache::VariableCache,
 };
 use crate::{core::Core, MemoryInterface};
@@ -0,0 +1,595 @@
+use super::{debug_info::DwarfReader, DebugError};
+use gimli::{AttributeValue, DebuggingInformationEntry, EntriesTreeNode, Operation, UnitOffset};
+
+type Dwarf = gimli::Dwarf<DwarfReader>;
+type Unit = gimli::Unit<DwarfReader>;
+type Entry<'abbrev, 'unit> = DebuggingInformationEntry<'abbrev, 'unit, DwarfReader>;
+
+/// The type of a scalar value in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarType {
+    /// A boolean, stored in a single byte.
+    Bool,
+    /// An unsigned integer, with its size in bytes. Also used for enums and pointers.
+    Unsigned(u8),
+    /// A signed integer, with its size in bytes.
+    Signed(u8),
+    /// A floating point number, with its size in bytes, which is either 4 or 8.
+    Float(u8),
+}
+
+impl ScalarType {
+    /// The size of a value of this type in bytes.
+    pub fn size(&self) -> usize {
+        match *self {
+            ScalarType::Bool => 1,
+            ScalarType::Unsigned(size) | ScalarType::Signed(size) | ScalarType::Float(size) => {
+                size as usize
+            }
+        }
+    }
+
+    /// Decodes a little endian value of this type from the start of `bytes`.
+    ///
+    /// `bytes` must contain at least [`ScalarType::size`] bytes.
+    pub fn decode(&self, bytes: &[u8]) -> ScalarValue {
+        let mut raw = [0u8; 8];
+        let size = self.size().min(bytes.len());
+        raw[..size].copy_from_slice(&bytes[..size]);
+        let value = u64::from_le_bytes(raw);
+
+        match *self {
+            ScalarType::Bool => ScalarValue::Bool(value != 0),
+            ScalarType::Unsigned(_) => ScalarValue::Unsigned(value),
+            ScalarType::Signed(size) => {
+                // Sign extend the value to 64 bits.
+                let shift = 64 - 8 * size as u32;
+                ScalarValue::Signed(((value << shift) as i64) >> shift)
+            }
+            ScalarType::Float(4) => ScalarValue::Float(f32::from_bits(value as u32) as f64),
+            ScalarType::Float(_) => ScalarValue::Float(f64::from_bits(value)),
+        }
+    }
+}
+
+/// A scalar value read from memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScalarValue {
+    /// A boolean.
+    Bool(bool),
+    /// An unsigned integer.
+    Unsigned(u64),
+    /// A signed integer.
+    Signed(i64),
+    /// A floating point number.
+    Float(f64),
+}
+
+impl ScalarValue {
+    /// The value as a floating point number, e.g. to plot it.
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            ScalarValue::Bool(value) => value as u8 as f64,
+            ScalarValue::Unsigned(value) => value as f64,
+            ScalarValue::Signed(value) => value as f64,
+            ScalarValue::Float(value) => value,
+        }
+    }
+}
+
+impl std::fmt::Display for ScalarValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScalarValue::Bool(value) => write!(f, "{value}"),
+            ScalarValue::Unsigned(value) => write!(f, "{value}"),
+            ScalarValue::Signed(value) => write!(f, "{value}"),
+            ScalarValue::Float(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// A scalar variable at a fixed address, like a static or a field of a static.
+///
+/// It can be read with a single memory access while the core is running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticVariable {
+    /// The path the variable was resolved from, e.g. `CONTROLLER.state.error`.
+    pub path: String,
+    /// The address of the variable.
+    pub address: u64,
+    /// The type of the variable.
+    pub scalar_type: ScalarType,
+}
+
+/// An access into a variable, following its name in the path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Accessor {
+    /// A field of a struct or union, e.g. `.error`.
+    Field(String),
+    /// An element of an array, e.g. `[3]`.
+    Index(u64),
+}
+
+impl std::fmt::Display for Accessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Accessor::Field(field) => write!(f, ".{field}"),
+            Accessor::Index(index) => write!(f, "[{index}]"),
+        }
+    }
+}
+
+fn error(message: String) -> DebugError {
+    DebugError::Other(anyhow::anyhow!(message))
+}
+
+/// Splits a path like `module::NAME.field[3]` into the name and the accessors.
+fn parse_path(path: &str) -> Result<(&str, Vec<Accessor>), DebugError> {
+    let name_end = path.find(['.', '[']).unwrap_or(path.len());
+    let (name, mut rest) = path.split_at(name_end);
+
+    if name.is_empty() {
+        return Err(error(format!(
+            "'{path}' does not start with a variable name"
+        )));
+    }
+
+    let mut accessors = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(field) = rest.strip_prefix('.') {
+            let end = field.find(['.', '[']).unwrap_or(field.len());
+            if end == 0 {
+                return Err(error(format!("Missing field name in '{path}'")));
+            }
+            accessors.push(Accessor::Field(field[..end].to_string()));
+            rest = &field[end..];
+        } else if let Some(index) = rest.strip_prefix('[') {
+            let end = index
+                .find(']')
+                .ok_or_else(|| error(format!("Missing ']' in '{path}'")))?;
+            let value = index[..end].trim().parse().map_err(|_| {
+                error(format!(
+                    "Invalid array index '{}' in '{path}'",
+                    &index[..end]
+                ))
+            })?;
+            accessors.push(Accessor::Index(value));
+            rest = &index[end + 1..];
+        } else {
+            return Err(error(format!("Unexpected '{rest}' in '{path}'")));
+        }
+    }
+
+    Ok((name, accessors))
+}
+
+/// Resolves the address and type of a static variable, or a field or element of it.
+///
+/// See [`DebugInfo::resolve_static_variable`](super::DebugInfo::resolve_static_variable).
+pub(crate) fn resolve(dwarf: &Dwarf, path: &str) -> Result<StaticVariable, DebugError> {
+    let (name, accessors) = parse_path(path)?;
+
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+
+        let mut tree = unit.entries_tree(None)?;
+        let root = tree.root()?;
+
+        if let Some((address, type_offset)) =
+            find_variable(dwarf, &unit, root, &mut Vec::new(), name)?
+        {
+            let (address, scalar_type) =
+                resolve_type(dwarf, &unit, address, type_offset, &accessors)
+                    .map_err(|err| error(format!("Failed to resolve '{path}': {err}")))?;
+
+            return Ok(StaticVariable {
+                path: path.to_string(),
+                address,
+                scalar_type,
+            });
+        }
+    }
+
+    Err(error(format!(
+        "No static variable named '{name}' found in the debug info"
+    )))
+}
+
+/// Searches the variables in `node` and its namespaces for one named `name`, and returns its
+/// address and the offset of its type.
+///
+/// The name matches if it is the name of the variable, or a suffix of its path including the
+/// namespaces, e.g. `module::NAME` for the variable `NAME` in the namespace `crate::module`.
+fn find_variable(
+    dwarf: &Dwarf,
+    unit: &Unit,
+    node: EntriesTreeNode<DwarfReader>,
+    namespaces: &mut Vec<String>,
+    name: &str,
+) -> Result<Option<(u64, UnitOffset)>, DebugError> {
+    let mut children = node.children();
+
+    while let Some(child) = children.next()? {
+        match child.entry().tag() {
+            gimli::DW_TAG_namespace => {
+                let namespace = entry_name(dwarf, unit, child.entry())?.unwrap_or_default();
+
+                namespaces.push(namespace);
+                let found = find_variable(dwarf, unit, child, namespaces, name)?;
+                namespaces.pop();
+
+                if found.is_some() {
+                    return Ok(found);
+                }
+            }
+            gimli::DW_TAG_variable => {
+                if let Some(found) = static_variable(dwarf, unit, child.entry(), namespaces, name)?
+                {
+                    return Ok(Some(found));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the address and the offset of the type of the variable `entry`, if it is named
+/// `name` and has a fixed address.
+fn static_variable(
+    dwarf: &Dwarf,
+    unit: &Unit,
+    entry: &Entry,
+    namespaces: &[String],
+    name: &str,
+) -> Result<Option<(u64, UnitOffset)>, DebugError> {
+    // A definition may refer to a declaration for its name and type.
+    let declaration = match entry.attr_value(gimli::DW_AT_specification)? {
+        Some(AttributeValue::UnitRef(offset)) => Some(unit.entry(offset)?),
+        _ => None,
+    };
+
+    let variable_name = match entry_name(dwarf, unit, entry)? {
+        Some(variable_name) => variable_name,
+        None => match &declaration {
+            Some(declaration) => match entry_name(dwarf, unit, declaration)? {
+                Some(variable_name) => variable_name,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        },
+    };
+
+    let path = namespaces
+        .iter()
+        .map(|namespace| namespace.as_str())
+        .chain(std::iter::once(variable_name.as_str()))
+        .collect::<Vec<_>>()
+        .join("::");
+
+    if path != name && !path.ends_with(&format!("::{name}")) {
+        return Ok(None);
+    }
+
+    let expression = match entry.attr_value(gimli::DW_AT_location)? {
+        Some(AttributeValue::Exprloc(expression)) => expression,
+        _ => return Ok(None),
+    };
+
+    // Only variables at a fixed address can be read without halting the core.
+    let mut operations = expression.operations(unit.encoding());
+    let address = match operations.next()? {
+        Some(Operation::Address { address }) => address,
+        Some(Operation::AddressIndex { index }) => dwarf.address(unit, index)?,
+        _ => return Ok(None),
+    };
+    if operations.next()?.is_some() {
+        return Ok(None);
+    }
+
+    // Statics which have been optimized into constants are placed at address zero.
+    if address == 0 {
+        return Ok(None);
+    }
+
+    let type_offset = match type_of(entry)? {
+        Some(type_offset) => type_offset,
+        None => match &declaration {
+            Some(declaration) => match type_of(declaration)? {
+                Some(type_offset) => type_offset,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        },
+    };
+
+    Ok(Some((address, type_offset)))
+}
+
+/// Applies the accessors to the variable at `address` with the type at `type_offset`, and
+/// returns the address and type of the resulting scalar.
+fn resolve_type(
+    dwarf: &Dwarf,
+    unit: &Unit,
+    mut address: u64,
+    mut type_offset: UnitOffset,
+    accessors: &[Accessor],
+) -> Result<(u64, ScalarType), DebugError> {
+    let mut accessors = accessors.iter();
+
+    loop {
+        let entry = unit.entry(type_offset)?;
+
+        match entry.tag() {
+            gimli::DW_TAG_typedef
+            | gimli::DW_TAG_const_type
+            | gimli::DW_TAG_volatile_type
+            | gimli::DW_TAG_atomic_type => {
+                type_offset = type_of(&entry)?
+                    .ok_or_else(|| error("Type without an underlying type".to_string()))?;
+            }
+            gimli::DW_TAG_base_type
+            | gimli::DW_TAG_enumeration_type
+            | gimli::DW_TAG_pointer_type => {
+                if let Some(accessor) = accessors.next() {
+                    return Err(error(format!(
+                        "'{accessor}' can't be applied to the scalar type '{}'",
+                        entry_name(dwarf, unit, &entry)?.unwrap_or_default()
+                    )));
+                }
+
+                return Ok((address, scalar_type(unit, &entry)?));
+            }
+            gimli::DW_TAG_structure_type | gimli::DW_TAG_union_type | gimli::DW_TAG_class_type => {
+                let type_name = entry_name(dwarf, unit, &entry)?.unwrap_or_default();
+
+                match accessors.next() {
+                    Some(Accessor::Field(field)) => {
+                        let (offset, member_type) = find_member(dwarf, unit, type_offset, field)?
+                            .ok_or_else(|| {
+                            error(format!("'{type_name}' has no field named '{field}'"))
+                        })?;
+
+                        address = address.checked_add(offset).ok_or_else(|| {
+                            error(format!("The address of field '{field}' overflows"))
+                        })?;
+                        type_offset = member_type;
+                    }
+                    Some(Accessor::Index(_)) => {
+                        return Err(error(format!("'{type_name}' is not an array")));
+                    }
+                    None => {
+                        return Err(error(format!(
+                            "'{type_name}' is not a scalar type, select one of its fields"
+                        )));
+                    }
+                }
+            }
+            gimli::DW_TAG_array_type => {
+                let element_type = type_of(&entry)?
+                    .ok_or_else(|| error("Array without an element type".to_string()))?;
+
+                match accessors.next() {
+                    Some(Accessor::Index(index)) => {
+                        if let Some(count) = array_length(unit, type_offset)? {
+                            if *index >= count {
+                                return Err(error(format!(
+                                    "Index {index} is out of bounds for an array of length {count}"
+                                )));
+                            }
+                        }
+
+                        // The index is not checked for arrays of unknown length.
+                        address = type_size(unit, element_type)?
+                            .checked_mul(*index)
+                            .and_then(|offset| address.checked_add(offset))
+                            .ok_or_else(|| {
+                                error(format!("The address of element {index} overflows"))
+                            })?;
+                        type_offset = element_type;
+                    }
+                    Some(Accessor::Field(field)) => {
+                        return Err(error(format!("Arrays have no field named '{field}'")));
+                    }
+                    None => {
+                        return Err(error(
+                            "Arrays are not a scalar type, select one of their elements"
+                                .to_string(),
+                        ));
+                    }
+                }
+            }
+            tag => {
+                return Err(error(format!("Unsupported type {tag}")));
+            }
+        }
+    }
+}
+
+/// The scalar type of a base type, enum or pointer.
+fn scalar_type(unit: &Unit, entry: &Entry) -> Result<ScalarType, DebugError> {
+    let size = match entry.attr_value(gimli::DW_AT_byte_size)? {
+        Some(size) => size.udata_value().unwrap_or(0),
+        None if entry.tag() == gimli::DW_TAG_pointer_type => unit.encoding().address_size as u64,
+        None => 0,
+    };
+
+    let scalar_type = match entry.tag() {
+        gimli::DW_TAG_base_type => match entry.attr_value(gimli::DW_AT_encoding)? {
+            Some(AttributeValue::Encoding(gimli::DW_ATE_boolean)) => ScalarType::Bool,
+            Some(AttributeValue::Encoding(gimli::DW_ATE_float)) => ScalarType::Float(size as u8),
+            Some(AttributeValue::Encoding(gimli::DW_ATE_signed))
+            | Some(AttributeValue::Encoding(gimli::DW_ATE_signed_char)) => {
+                ScalarType::Signed(size as u8)
+            }
+            Some(AttributeValue::Encoding(gimli::DW_ATE_unsigned))
+            | Some(AttributeValue::Encoding(gimli::DW_ATE_unsigned_char))
+            | Some(AttributeValue::Encoding(gimli::DW_ATE_UTF)) => ScalarType::Unsigned(size as u8),
+            encoding => {
+                return Err(error(format!(
+                    "Unsupported base type encoding {encoding:?}"
+                )));
+            }
+        },
+        _ => ScalarType::Unsigned(size as u8),
+    };
+
+    let valid = match scalar_type {
+        ScalarType::Bool => true,
+        ScalarType::Float(size) => matches!(size, 4 | 8),
+        ScalarType::Unsigned(size) | ScalarType::Signed(size) => matches!(size, 1 | 2 | 4 | 8),
+    };
+
+    if !valid || size > 8 {
+        return Err(error(format!("Unsupported scalar size of {size} bytes")));
+    }
+
+    Ok(scalar_type)
+}
+
+/// Finds the member `field` of the struct or union at `type_offset`, and returns its offset
+/// and the offset of its type.
+///
+/// Fields of tuples and tuple structs can be given by their index, e.g. `0` for `__0`.
+fn find_member(
+    dwarf: &Dwarf,
+    unit: &Unit,
+    type_offset: UnitOffset,
+    field: &str,
+) -> Result<Option<(u64, UnitOffset)>, DebugError> {
+    let tuple_field = format!("__{field}");
+
+    let mut tree = unit.entries_tree(Some(type_offset))?;
+    let root = tree.root()?;
+    let mut children = root.children();
+
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        if entry.tag() != gimli::DW_TAG_member {
+            continue;
+        }
+
+        let name = entry_name(dwarf, unit, entry)?;
+        if name.as_deref() != Some(field) && name.as_deref() != Some(tuple_field.as_str()) {
+            continue;
+        }
+
+        if entry.attr_value(gimli::DW_AT_bit_size)?.is_some() {
+            return Err(error(format!(
+                "Field '{field}' is a bitfield member, which is not supported"
+            )));
+        }
+
+        let offset = match entry.attr_value(gimli::DW_AT_data_member_location)? {
+            Some(AttributeValue::Exprloc(expression)) => {
+                match expression.operations(unit.encoding()).next()? {
+                    Some(Operation::PlusConstant { value }) => value,
+                    _ => return Err(error(format!("Unsupported location of field '{field}'"))),
+                }
+            }
+            Some(value) => value
+                .udata_value()
+                .ok_or_else(|| error(format!("Unsupported location of field '{field}'")))?,
+            // Members of unions have no location.
+            None => 0,
+        };
+
+        let member_type =
+            type_of(entry)?.ok_or_else(|| error(format!("Field '{field}' has no type")))?;
+
+        return Ok(Some((offset, member_type)));
+    }
+
+    Ok(None)
+}
+
+/// The number of elements of the array type at `type_offset`, if known.
+fn array_length(unit: &Unit, type_offset: UnitOffset) -> Result<Option<u64>, DebugError> {
+    let mut tree = unit.entries_tree(Some(type_offset))?;
+    let root = tree.root()?;
+    let mut children = root.children();
+
+    let mut length = None;
+    let mut dimensions = 0;
+
+    while let Some(child) = children.next()? {
+        let entry = child.entry();
+        if entry.tag() != gimli::DW_TAG_subrange_type {
+            continue;
+        }
+
+        dimensions += 1;
+
+        length = match (
+            entry.attr_value(gimli::DW_AT_count)?,
+            entry.attr_value(gimli::DW_AT_upper_bound)?,
+        ) {
+            (Some(count), _) => count.udata_value(),
+            (None, Some(upper_bound)) => upper_bound.udata_value().map(|bound| bound + 1),
+            (None, None) => None,
+        };
+    }
+
+    if dimensions > 1 {
+        return Err(error(
+            "Multi-dimensional arrays are not supported".to_string(),
+        ));
+    }
+
+    Ok(length)
+}
+
+/// The size of the type at `type_offset` in bytes.
+fn type_size(unit: &Unit, mut type_offset: UnitOffset) -> Result<u64, DebugError> {
+    loop {
+        let entry = unit.entry(type_offset)?;
+
+        if let Some(size) = entry
+            .attr_value(gimli::DW_AT_byte_size)?
+            .and_then(|size| size.udata_value())
+        {
+            return Ok(size);
+        }
+
+        match entry.tag() {
+            gimli::DW_TAG_pointer_type => return Ok(unit.encoding().address_size as u64),
+            gimli::DW_TAG_array_type => {
+                let element_type = type_of(&entry)?
+                    .ok_or_else(|| error("Array without an element type".to_string()))?;
+                let length = array_length(unit, type_offset)?
+                    .ok_or_else(|| error("Array of unknown length".to_string()))?;
+
+                return length
+                    .checked_mul(type_size(unit, element_type)?)
+                    .ok_or_else(|| error("Array size overflows".to_string()));
+            }
+            _ => {
+                type_offset =
+                    type_of(&entry)?.ok_or_else(|| error("Type of unknown size".to_string()))?;
+            }
+        }
+    }
+}
+
+/// The offset of the type referenced by `entry`.
+fn type_of(entry: &Entry) -> Result<Option<UnitOffset>, DebugError> {
+    match entry.attr_value(gimli::DW_AT_type)? {
+        Some(AttributeValue::UnitRef(offset)) => Ok(Some(offset)),
+        Some(_) => Err(error("Types in other units are not supported".to_string())),
+        None => Ok(None),
+    }
+}
+
+/// The name of `entry`, if it has one.
+fn entry_name(dwarf: &Dwarf, unit: &Unit, entry: &Entry) -> Result<Option<String>, DebugError> {
+    match entry.attr_value(gimli::DW_AT_name)? {
+        Some(name) => {
+            let name = dwarf.attr_string(unit, name)?;
+            Ok(Some(std::str::from_utf8(&name)?.to_string()))
+        }
+        None => Ok(None),
+    }
+}
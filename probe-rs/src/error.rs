@@ -2,6 +2,7 @@
 
 use crate::architecture::arm::ArmError;
 use crate::architecture::riscv::communication_interface::RiscvError;
+use crate::architecture::xtensa::communication_interface::XtensaError;
 use crate::config::RegistryError;
 use crate::DebugProbeError;
 
@@ -17,6 +18,9 @@ pub enum Error {
     /// A RISCV specific error occured.
     #[error("A RISCV specific error occured.")]
     Riscv(#[source] RiscvError),
+    /// An Xtensa specific error occured.
+    #[error("An Xtensa specific error occured.")]
+    Xtensa(#[source] XtensaError),
     /// The probe could not be opened.
     #[error("Probe could not be opened: {0}")]
     UnableToOpenProbe(&'static str),
@@ -51,6 +55,23 @@ pub enum Error {
     },
 }
 
+impl Error {
+    /// Check if the error was caused by losing the connection to the probe or the target.
+    ///
+    /// This happens when the probe is unplugged, or when the target stops responding to the
+    /// probe, e.g. because it entered a low power mode. The connection can be restored
+    /// using [`Session::reconnect`](crate::Session::reconnect).
+    pub fn is_connection_lost(&self) -> bool {
+        match self {
+            Error::Probe(err) => err.is_connection_lost(),
+            Error::Arm(err) => err.is_connection_lost(),
+            Error::Riscv(RiscvError::DebugProbe(err)) => err.is_connection_lost(),
+            Error::Xtensa(XtensaError::DebugProbe(err)) => err.is_connection_lost(),
+            _ => false,
+        }
+    }
+}
+
 impl From<ArmError> for Error {
     fn from(value: ArmError) -> Self {
         match value {
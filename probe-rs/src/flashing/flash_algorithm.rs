@@ -2,7 +2,10 @@ use probe_rs_target::{FlashProperties, PageInfo, RamRegion, RawFlashAlgorithm, S
 
 use super::FlashError;
 use crate::core::Architecture;
-use crate::{architecture::riscv, Target};
+use crate::{
+    architecture::{riscv, xtensa},
+    Target,
+};
 use std::convert::TryInto;
 
 /// A flash algorithm, which has been assembled for a specific
@@ -165,6 +168,9 @@ impl FlashAlgorithm {
     // Header for RISCV Flash Algorithms
     const RISCV_FLASH_BLOB_HEADER: [u32; 2] = [riscv::assembly::EBREAK, riscv::assembly::EBREAK];
 
+    // Header for Xtensa Flash Algorithms
+    const XTENSA_FLASH_BLOB_HEADER: [u32; 2] = [xtensa::BREAK_1_15, xtensa::BREAK_1_15];
+
     const ARM_FLASH_BLOB_HEADER: [u32; 8] = [
         0xE00A_BE00,
         0x062D_780D,
@@ -180,6 +186,7 @@ impl FlashAlgorithm {
         match architecture {
             Architecture::Arm => &Self::ARM_FLASH_BLOB_HEADER,
             Architecture::Riscv => &Self::RISCV_FLASH_BLOB_HEADER,
+            Architecture::Xtensa => &Self::XTENSA_FLASH_BLOB_HEADER,
         }
     }
 
@@ -82,21 +82,23 @@ mod memory;
 #[warn(missing_docs)]
 mod probe;
 #[warn(missing_docs)]
+pub mod semihosting;
+#[warn(missing_docs)]
 mod session;
 
 pub use crate::config::{CoreType, InstructionSet, Target};
 pub use crate::core::{
     Architecture, BreakpointCause, BreakpointId, Core, CoreInformation, CoreInterface, CoreState,
     CoreStatus, HaltReason, MemoryMappedRegister, RegisterDescription, RegisterFile, RegisterId,
-    RegisterValue, SpecificCoreState,
+    RegisterValue, SecurityState, SpecificCoreState,
 };
 pub use crate::error::Error;
 pub use crate::memory::MemoryInterface;
 pub use crate::probe::{
     AttachMethod, DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeSelector, DebugProbeType,
-    Probe, ProbeCreationError, WireProtocol,
+    JtagChainItem, JtagTapSelector, Probe, ProbeCreationError, WireProtocol,
 };
-pub use crate::session::{Permissions, Session};
+pub use crate::session::{HaltGroupSync, Permissions, Session};
 
 // TODO: Hide behind feature
 pub use crate::probe::fake_probe::FakeProbe;
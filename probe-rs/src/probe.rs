@@ -1,3 +1,4 @@
+pub(crate) mod blackmagic;
 pub(crate) mod cmsisdap;
 pub(crate) mod espusbjtag;
 pub(crate) mod fake_probe;
@@ -18,14 +19,18 @@ use crate::{
     architecture::{
         arm::{
             communication_interface::DapProbe,
+            dp::{discover_multidrop_dps, MultidropDp},
             sequences::{ArmDebugSequence, DefaultArmSequence},
             PortType, SwoAccess,
         },
         riscv::communication_interface::RiscvCommunicationInterface,
+        xtensa::communication_interface::{XtensaCommunicationInterface, XtensaError},
     },
     Permissions,
 };
+use bitvec::{order::Lsb0, slice::BitSlice, vec::BitVec};
 use jlink::list_jlink_devices;
+use probe_rs_target::ScanChainElement;
 use std::{convert::TryFrom, fmt};
 
 /// Used to log warnings when the measured target voltage is
@@ -158,6 +163,56 @@ pub enum DebugProbeError {
     /// A timeout occured during probe operation.
     #[error("Timeout occured during probe operation.")]
     Timeout,
+
+    /// The JTAG scan chain could not be detected, or does not contain the selected TAP.
+    #[error("JTAG scan chain error: {0}")]
+    JtagScanChain(&'static str),
+}
+
+impl DebugProbeError {
+    /// Check if the error was caused by the probe being disconnected.
+    pub(crate) fn is_connection_lost(&self) -> bool {
+        match self {
+            DebugProbeError::Usb(Some(err)) | DebugProbeError::ProbeSpecific(err) => {
+                is_disconnect_error(err.as_ref())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Check if `error`, or any error in its source chain, is caused by a USB device which is gone.
+fn is_disconnect_error(mut error: &(dyn std::error::Error + 'static)) -> bool {
+    loop {
+        if let Some(err) = error.downcast_ref::<rusb::Error>() {
+            if matches!(
+                err,
+                rusb::Error::NoDevice | rusb::Error::NotFound | rusb::Error::Io
+            ) {
+                return true;
+            }
+        }
+
+        if let Some(err) = error.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind;
+
+            if matches!(
+                err.kind(),
+                ErrorKind::BrokenPipe
+                    | ErrorKind::NotConnected
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::UnexpectedEof
+            ) {
+                return true;
+            }
+        }
+
+        match error.source() {
+            Some(source) => error = source,
+            None => return false,
+        }
+    }
 }
 
 /// An error during probe creation accured.
@@ -208,6 +263,14 @@ pub enum ProbeCreationError {
 pub struct Probe {
     inner: Box<dyn DebugProbe>,
     attached: bool,
+    /// The TAP to use if the JTAG scan chain contains more than one TAP.
+    jtag_tap: Option<JtagTapSelector>,
+    /// The scan chain as described by the target, if any.
+    scan_chain: Option<Vec<ScanChainElement>>,
+    /// The selector used to open the probe, if it was opened using [`Probe::open`].
+    ///
+    /// This is boxed to keep the size of `Probe` small, as it's returned in errors.
+    selector: Option<Box<DebugProbeSelector>>,
 }
 
 impl Probe {
@@ -216,6 +279,9 @@ impl Probe {
         Self {
             inner: Box::new(probe),
             attached: false,
+            jtag_tap: None,
+            scan_chain: None,
+            selector: None,
         }
     }
 
@@ -223,6 +289,9 @@ impl Probe {
         Self {
             inner: probe,
             attached: true,
+            jtag_tap: None,
+            scan_chain: None,
+            selector: None,
         }
     }
 
@@ -231,6 +300,9 @@ impl Probe {
         Probe {
             inner: probe,
             attached: false,
+            jtag_tap: None,
+            scan_chain: None,
+            selector: None,
         }
     }
 
@@ -250,6 +322,8 @@ impl Probe {
 
         list.extend(list_espjtag_devices());
 
+        list.extend(blackmagic::list_blackmagic_devices());
+
         list
     }
 
@@ -258,6 +332,16 @@ impl Probe {
     /// about all probes available.
     #[tracing::instrument(skip_all)]
     pub fn open(selector: impl Into<DebugProbeSelector> + Clone) -> Result<Self, DebugProbeError> {
+        let selector = selector.into();
+
+        let mut probe = Self::open_driver(selector.clone())?;
+        probe.selector = Some(Box::new(selector));
+
+        Ok(probe)
+    }
+
+    /// Open the probe matching the selector, trying all supported drivers.
+    fn open_driver(selector: DebugProbeSelector) -> Result<Self, DebugProbeError> {
         match cmsisdap::CmsisDap::new_from_selector(selector.clone()) {
             Ok(link) => return Ok(Probe::from_specific_probe(link)),
             Err(DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound)) => {}
@@ -279,7 +363,12 @@ impl Probe {
             Err(DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound)) => {}
             Err(e) => return Err(e),
         };
-        match espusbjtag::EspUsbJtag::new_from_selector(selector) {
+        match espusbjtag::EspUsbJtag::new_from_selector(selector.clone()) {
+            Ok(link) => return Ok(Probe::from_specific_probe(link)),
+            Err(DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound)) => {}
+            Err(e) => return Err(e),
+        };
+        match blackmagic::BlackMagicProbe::new_from_selector(selector) {
             Ok(link) => return Ok(Probe::from_specific_probe(link)),
             Err(DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound)) => {}
             Err(e) => return Err(e),
@@ -290,6 +379,13 @@ impl Probe {
         ))
     }
 
+    /// The selector which was used to open the probe.
+    ///
+    /// This is `None` if the probe was not opened using [`Probe::open`].
+    pub fn selector(&self) -> Option<&DebugProbeSelector> {
+        self.selector.as_deref()
+    }
+
     /// Get the human readable name for the probe.
     pub fn get_name(&self) -> String {
         self.inner.get_name().to_string()
@@ -313,7 +409,7 @@ impl Probe {
     /// Attach to a target without knowing what target you have at hand.
     /// This can be used for automatic device discovery or performing operations on an unspecified target.
     pub fn attach_to_unspecified(&mut self) -> Result<(), Error> {
-        self.inner.attach()?;
+        self.inner_attach()?;
         self.attached = true;
         Ok(())
     }
@@ -358,7 +454,127 @@ impl Probe {
     }
 
     pub(crate) fn inner_attach(&mut self) -> Result<(), DebugProbeError> {
-        self.inner.attach()
+        self.inner.attach()?;
+
+        if self.inner.active_protocol() == Some(WireProtocol::Jtag) {
+            self.apply_jtag_tap_selection()?;
+        }
+
+        Ok(())
+    }
+
+    /// Select the TAP to debug if the JTAG scan chain contains multiple TAPs.
+    ///
+    /// The selection is applied when attaching, and takes precedence over the TAP
+    /// specified in the target description.
+    pub fn select_jtag_tap(&mut self, tap: JtagTapSelector) -> Result<(), DebugProbeError> {
+        if self.attached {
+            return Err(DebugProbeError::Attached);
+        }
+
+        self.jtag_tap = Some(tap);
+        Ok(())
+    }
+
+    /// The TAP which was selected using [`Probe::select_jtag_tap`], if any.
+    pub(crate) fn jtag_tap(&self) -> Option<JtagTapSelector> {
+        self.jtag_tap
+    }
+
+    /// Use the given TAP unless another one was explicitly selected
+    /// using [`Probe::select_jtag_tap`].
+    pub(crate) fn set_default_jtag_tap(&mut self, tap: JtagTapSelector) {
+        if self.jtag_tap.is_none() {
+            self.jtag_tap = Some(tap);
+        }
+    }
+
+    /// Use the given scan chain description instead of detecting the IR lengths.
+    pub(crate) fn set_scan_chain(&mut self, scan_chain: Vec<ScanChainElement>) {
+        self.scan_chain = Some(scan_chain);
+    }
+
+    /// Scan the JTAG chain and return all TAPs found on it, starting with the one closest to TDO.
+    ///
+    /// The probe has to be attached using the JTAG protocol.
+    pub fn scan_jtag_chain(&mut self) -> Result<Vec<JtagChainItem>, DebugProbeError> {
+        if !self.attached {
+            return Err(DebugProbeError::NotAttached);
+        }
+
+        let scan_chain = self.scan_chain.clone();
+        let jtag = self
+            .inner
+            .try_as_jtag_probe()
+            .ok_or(DebugProbeError::InterfaceNotAvailable("JTAG"))?;
+
+        jtag.scan_chain(scan_chain.as_deref())
+    }
+
+    /// Enumerate the debug ports on a multidrop SWD bus.
+    ///
+    /// Each of the `targetsels` is tried as `TARGETSEL` value, and all debug ports which
+    /// respond are returned. Use [`targetsel_candidates`](crate::architecture::arm::dp::targetsel_candidates)
+    /// to try all instances of known TARGETID values.
+    ///
+    /// The probe has to be attached using the SWD protocol.
+    pub fn scan_swd_multidrop(&mut self, targetsels: &[u32]) -> Result<Vec<MultidropDp>, Error> {
+        if !self.attached {
+            return Err(DebugProbeError::NotAttached.into());
+        }
+
+        if let Some(protocol) = self.protocol().filter(|p| *p != WireProtocol::Swd) {
+            return Err(DebugProbeError::UnsupportedProtocol(protocol).into());
+        }
+
+        let probe = self
+            .inner
+            .try_as_dap_probe()
+            .ok_or(DebugProbeError::InterfaceNotAvailable("ARM DAP"))?;
+
+        Ok(discover_multidrop_dps(probe, targetsels)?)
+    }
+
+    /// Scan the JTAG chain and select the configured TAP, or the TAP of a known target
+    /// if none was configured.
+    fn apply_jtag_tap_selection(&mut self) -> Result<(), DebugProbeError> {
+        let configured = self.jtag_tap.is_some() || self.scan_chain.is_some();
+        let scan_chain = self.scan_chain.clone();
+        let jtag = match self.inner.try_as_jtag_probe() {
+            Some(jtag) => jtag,
+            None if !configured => return Ok(()),
+            None => return Err(DebugProbeError::InterfaceNotAvailable("JTAG")),
+        };
+
+        let chain = match jtag.scan_chain(scan_chain.as_deref()) {
+            Ok(chain) => chain,
+            // Probes which can't scan the chain use their own defaults.
+            Err(DebugProbeError::NotImplemented(_)) if !configured => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        if chain.is_empty() {
+            tracing::warn!("No JTAG TAPs detected");
+            return Err(DebugProbeError::TargetNotFound);
+        }
+
+        let index = match self.jtag_tap {
+            Some(tap) => tap.find(&chain).ok_or(DebugProbeError::JtagScanChain(
+                "the selected TAP was not found in the scan chain",
+            ))?,
+            None => default_jtag_tap(&chain).ok_or_else(|| {
+                tracing::warn!(
+                    "Found {} JTAG TAPs, but none of them is known. Select the TAP to debug explicitly.",
+                    chain.len()
+                );
+                DebugProbeError::TargetNotFound
+            })?,
+        };
+
+        let params = ChainParams::from_chain(&chain, index)?;
+        tracing::debug!("Selected TAP {} of the JTAG chain: {:?}", index, params);
+
+        jtag.set_chain_params(params)
     }
 
     /// Selects the transport protocol to be used by the debug probe.
@@ -470,6 +686,29 @@ impl Probe {
         }
     }
 
+    /// Check if the probe has an interface to
+    /// debug Xtensa chips.
+    pub fn has_xtensa_interface(&self) -> bool {
+        self.inner.has_xtensa_interface()
+    }
+
+    /// Try to get a [`XtensaCommunicationInterface`], which can
+    /// can be used to communicate with chips using the Xtensa
+    /// architecture.
+    ///
+    /// If an error occurs while trying to connect, the probe is returned.
+    pub fn try_into_xtensa_interface(
+        self,
+    ) -> Result<XtensaCommunicationInterface, (Self, XtensaError)> {
+        if !self.attached {
+            Err((self, DebugProbeError::NotAttached.into()))
+        } else {
+            self.inner
+                .try_get_xtensa_interface()
+                .map_err(|(probe, err)| (Probe::from_attached_probe(probe), err))
+        }
+    }
+
     /// Gets a SWO interface from the debug probe.
     ///
     /// This does not work on all probes.
@@ -597,6 +836,22 @@ pub trait DebugProbe: Send + fmt::Debug {
         false
     }
 
+    /// Get the dedicated interface to debug Xtensa chips. Ensure that the
+    /// probe actually supports this by calling [DebugProbe::has_xtensa_interface] first.
+    fn try_get_xtensa_interface(
+        self: Box<Self>,
+    ) -> Result<XtensaCommunicationInterface, (Box<dyn DebugProbe>, XtensaError)> {
+        Err((
+            self.into_probe(),
+            DebugProbeError::InterfaceNotAvailable("Xtensa").into(),
+        ))
+    }
+
+    /// Check if the probe offers an interface to debug Xtensa chips.
+    fn has_xtensa_interface(&self) -> bool {
+        false
+    }
+
     /// Get a SWO interface from the debug probe.
     ///
     /// This is not available on all debug probes.
@@ -621,6 +876,13 @@ pub trait DebugProbe: Send + fmt::Debug {
         None
     }
 
+    /// Get raw JTAG access to the scan chain, e.g. to scan it or to select a TAP.
+    ///
+    /// This is not available on all probes.
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        None
+    }
+
     /// Reads the target voltage in Volts, if possible. Returns `Ok(None)`
     /// if the probe doesn’t support reading the target voltage.
     fn get_target_voltage(&mut self) -> Result<Option<f32>, DebugProbeError> {
@@ -641,6 +903,8 @@ pub enum DebugProbeType {
     JLink,
     /// Built in RISC-V ESP JTAG debug probe
     EspJtag,
+    /// Black Magic Probe
+    BlackMagicProbe,
 }
 
 /// Gathers some information about a debug probe which was found during a scan.
@@ -660,19 +924,28 @@ pub struct DebugProbeInfo {
     /// The USB HID interface which should be used.
     /// This is necessary for composite HID devices.
     pub hid_interface: Option<u8>,
+
+    /// The USB port path of the debug probe, in the form `<bus>-<port>[.<port>...]`.
+    ///
+    /// This identifies the probe by the port it is plugged into, and can be used to
+    /// distinguish probes which share the same serial number.
+    pub usb_port_path: Option<String>,
 }
 
 impl std::fmt::Debug for DebugProbeInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "{} (VID: {:04x}, PID: {:04x}, {}{:?})",
+            "{} (VID: {:04x}, PID: {:04x}, {}{}{:?})",
             self.identifier,
             self.vendor_id,
             self.product_id,
             self.serial_number
                 .clone()
                 .map_or("".to_owned(), |v| format!("Serial: {v}, ")),
+            self.usb_port_path
+                .clone()
+                .map_or("".to_owned(), |v| format!("Port: {v}, ")),
             self.probe_type
         )
     }
@@ -695,6 +968,7 @@ impl DebugProbeInfo {
             serial_number,
             probe_type,
             hid_interface: usb_hid_interface,
+            usb_port_path: None,
         }
     }
 
@@ -708,7 +982,7 @@ impl DebugProbeInfo {
 pub enum DebugProbeSelectorParseError {
     #[error("The VID or PID could not be parsed: {0}")]
     ParseInt(#[from] std::num::ParseIntError),
-    #[error("Please use a string in the form `VID:PID:<Serial>` where Serial is optional.")]
+    #[error("Please use a string in the form `VID:PID:<Serial>@<Port>` where Serial and Port are optional.")]
     Format,
 }
 
@@ -720,6 +994,9 @@ pub enum DebugProbeSelectorParseError {
 /// ```
 /// use std::convert::TryInto;
 /// let selector: probe_rs::DebugProbeSelector = "1337:1337:SERIAL".try_into().unwrap();
+///
+/// // Select the probe connected to port 2 of the hub on port 4 of bus 1.
+/// let selector: probe_rs::DebugProbeSelector = "1337:1337@1-4.2".try_into().unwrap();
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 // We need this so that serde will first convert from the string `PID:VID:<Serial>` to a struct before deserializing.
@@ -731,17 +1008,46 @@ pub struct DebugProbeSelector {
     pub product_id: u16,
     /// The the serial number of the debug probe to be used.
     pub serial_number: Option<String>,
+    /// The USB port path of the debug probe to be used, see [`DebugProbeInfo::usb_port_path`].
+    pub usb_port_path: Option<String>,
+}
+
+impl DebugProbeSelector {
+    /// Check if the given probe matches this selector.
+    pub fn matches(&self, info: &DebugProbeInfo) -> bool {
+        self.vendor_id == info.vendor_id
+            && self.product_id == info.product_id
+            && (self.serial_number.is_none() || self.serial_number == info.serial_number)
+            && (self.usb_port_path.is_none() || self.usb_port_path == info.usb_port_path)
+    }
+
+    /// Check if the given USB device is plugged into the port given in the selector.
+    ///
+    /// This is always true if the selector does not contain a port path.
+    pub(crate) fn matches_usb_port<T: rusb::UsbContext>(&self, device: &rusb::Device<T>) -> bool {
+        match &self.usb_port_path {
+            Some(path) => usb_port_path(device).as_ref() == Some(path),
+            None => true,
+        }
+    }
 }
 
 impl TryFrom<&str> for DebugProbeSelector {
     type Error = DebugProbeSelectorParseError;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (value, usb_port_path) = match value.split_once('@') {
+            Some((value, path)) if !path.is_empty() => (value, Some(path.to_string())),
+            Some(_) => return Err(DebugProbeSelectorParseError::Format),
+            None => (value, None),
+        };
+
         let split = value.split(':').collect::<Vec<_>>();
         let mut selector = if split.len() > 1 {
             DebugProbeSelector {
                 vendor_id: u16::from_str_radix(split[0], 16)?,
                 product_id: u16::from_str_radix(split[1], 16)?,
                 serial_number: None,
+                usb_port_path,
             }
         } else {
             return Err(DebugProbeSelectorParseError::Format);
@@ -775,6 +1081,7 @@ impl From<DebugProbeInfo> for DebugProbeSelector {
             vendor_id: selector.vendor_id,
             product_id: selector.product_id,
             serial_number: selector.serial_number,
+            usb_port_path: selector.usb_port_path,
         }
     }
 }
@@ -785,6 +1092,7 @@ impl From<&DebugProbeInfo> for DebugProbeSelector {
             vendor_id: selector.vendor_id,
             product_id: selector.product_id,
             serial_number: selector.serial_number.clone(),
+            usb_port_path: selector.usb_port_path.clone(),
         }
     }
 }
@@ -795,10 +1103,277 @@ impl fmt::Display for DebugProbeSelector {
         if let Some(ref sn) = self.serial_number {
             write!(f, ":{sn}")?;
         }
+        if let Some(ref path) = self.usb_port_path {
+            write!(f, "@{path}")?;
+        }
         Ok(())
     }
 }
 
+/// Get the USB port path of a device, in the form `<bus>-<port>[.<port>...]`.
+///
+/// This is the same format Linux uses to name USB devices in sysfs, e.g. `1-4.2` for a device
+/// connected to port 2 of a hub, which is connected to port 4 of the root hub of bus 1.
+pub(crate) fn usb_port_path<T: rusb::UsbContext>(device: &rusb::Device<T>) -> Option<String> {
+    let ports = device.port_numbers().ok()?;
+
+    if ports.is_empty() {
+        return None;
+    }
+
+    let ports = ports
+        .iter()
+        .map(|port| port.to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+
+    Some(format!("{}-{}", device.bus_number(), ports))
+}
+
+/// Get the USB port path of the device with the given bus number and address.
+pub(crate) fn usb_port_path_by_address(bus_number: u8, address: u8) -> Option<String> {
+    rusb::Context::new()
+        .and_then(|context| rusb::UsbContext::devices(&context))
+        .ok()?
+        .iter()
+        .find(|device| device.bus_number() == bus_number && device.address() == address)
+        .and_then(|device| usb_port_path(&device))
+}
+
+/// The maximum number of TAPs which are detected in a JTAG scan chain.
+const JTAG_MAX_CHAIN_LENGTH: usize = 16;
+
+/// The maximum total length of all instruction registers in a JTAG scan chain.
+const JTAG_MAX_IR_LENGTH: usize = 256;
+
+/// IDCODEs of TAPs which are selected by default in a scan chain with multiple TAPs.
+const KNOWN_JTAG_IDCODES: &[u32] = &[
+    0x1000563d, // GD32VF103
+];
+
+/// The TAP which is used if none was selected: the only TAP of the scan chain,
+/// or the first one with a known IDCODE.
+fn default_jtag_tap(chain: &[JtagChainItem]) -> Option<usize> {
+    if chain.len() == 1 {
+        return Some(0);
+    }
+
+    chain.iter().position(|tap| {
+        tap.idcode
+            .map(|idcode| KNOWN_JTAG_IDCODES.contains(&idcode))
+            .unwrap_or(false)
+    })
+}
+
+/// A TAP found while scanning a JTAG scan chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JtagChainItem {
+    /// The IDCODE of the TAP, or `None` if the TAP has no IDCODE register
+    /// and selects the BYPASS register after a reset.
+    pub idcode: Option<u32>,
+    /// The length of the instruction register of the TAP.
+    pub irlen: usize,
+}
+
+/// Selects a TAP in a JTAG scan chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JtagTapSelector {
+    /// The TAP at the given position, counted from TDO.
+    Index(usize),
+    /// The first TAP with the given IDCODE.
+    IdCode(u32),
+}
+
+impl JtagTapSelector {
+    /// Find the position of the selected TAP in the given scan chain.
+    pub fn find(&self, chain: &[JtagChainItem]) -> Option<usize> {
+        match *self {
+            JtagTapSelector::Index(index) => (index < chain.len()).then_some(index),
+            JtagTapSelector::IdCode(idcode) => {
+                chain.iter().position(|tap| tap.idcode == Some(idcode))
+            }
+        }
+    }
+}
+
+impl fmt::Display for JtagTapSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JtagTapSelector::Index(index) => write!(f, "{}", index),
+            JtagTapSelector::IdCode(idcode) => write!(f, "{:#010x}", idcode),
+        }
+    }
+}
+
+impl std::str::FromStr for JtagTapSelector {
+    type Err = String;
+
+    /// Parses either a decimal TAP index, or a hexadecimal IDCODE prefixed with `0x`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Some(idcode) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            u32::from_str_radix(idcode, 16)
+                .map(JtagTapSelector::IdCode)
+                .map_err(|e| format!("Invalid IDCODE '{}': {}", s, e))
+        } else {
+            s.parse()
+                .map(JtagTapSelector::Index)
+                .map_err(|e| format!("Invalid TAP index '{}': {}", s, e))
+        }
+    }
+}
+
+/// The position of the selected TAP in the JTAG scan chain.
+///
+/// All other TAPs are kept in BYPASS. When accessing the selected TAP, `irpre`/`drpre`
+/// bits have to be shifted before, and `irpost`/`drpost` bits after the actual data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChainParams {
+    /// Number of IR bits of the TAPs between TDO and the selected TAP.
+    pub irpre: usize,
+    /// Number of IR bits of the TAPs between the selected TAP and TDI.
+    pub irpost: usize,
+    /// Number of TAPs between TDO and the selected TAP.
+    pub drpre: usize,
+    /// Number of TAPs between the selected TAP and TDI.
+    pub drpost: usize,
+    /// IR length of the selected TAP.
+    pub irlen: usize,
+}
+
+impl ChainParams {
+    /// Calculate the parameters for the TAP at position `index` in the scan chain.
+    pub fn from_chain(chain: &[JtagChainItem], index: usize) -> Result<Self, DebugProbeError> {
+        if index >= chain.len() {
+            return Err(DebugProbeError::JtagScanChain(
+                "the selected TAP was not found in the scan chain",
+            ));
+        }
+
+        let (pre, rest) = chain.split_at(index);
+        let (selected, post) = (&rest[0], &rest[1..]);
+
+        Ok(ChainParams {
+            irpre: pre.iter().map(|tap| tap.irlen).sum(),
+            irpost: post.iter().map(|tap| tap.irlen).sum(),
+            drpre: pre.len(),
+            drpost: post.len(),
+            irlen: selected.irlen,
+        })
+    }
+}
+
+/// Extract the IDCODEs from the data shifted out of the DR chain after a TAP reset,
+/// while shifting in ones.
+///
+/// A TAP with an IDCODE register always shifts out a one first, while a TAP in BYPASS
+/// shifts out a single zero. The end of the chain is reached when only the ones
+/// shifted in are shifted out again.
+fn extract_idcodes(dr: &BitSlice<u8, Lsb0>) -> Result<Vec<Option<u32>>, DebugProbeError> {
+    let mut idcodes = Vec::new();
+    let mut bits = dr;
+
+    while let Some(first) = bits.first() {
+        let idcode = if !*first {
+            bits = &bits[1..];
+            None
+        } else if bits.len() >= 32 {
+            let idcode = bits[..32]
+                .iter()
+                .rev()
+                .fold(0u32, |acc, bit| (acc << 1) | *bit as u32);
+            if idcode == 0xffff_ffff {
+                return Ok(idcodes);
+            }
+            bits = &bits[32..];
+            Some(idcode)
+        } else {
+            break;
+        };
+
+        if idcodes.len() == JTAG_MAX_CHAIN_LENGTH {
+            return Err(DebugProbeError::JtagScanChain(
+                "Too many TAPs in the scan chain, check the wiring of TDO",
+            ));
+        }
+        idcodes.push(idcode);
+    }
+
+    Err(DebugProbeError::JtagScanChain(
+        "The end of the scan chain was not found",
+    ))
+}
+
+/// Determine the IR length of each TAP in the chain.
+///
+/// `captured` is the data shifted out of the IR chain after a TAP reset, `total`
+/// the combined length of all instruction registers. IR lengths from the target description
+/// are used where available. The remaining ones are derived from the capture pattern,
+/// which has to start with `0b01` for every TAP. If the chain consists of a single TAP,
+/// its length is simply `total`.
+fn extract_ir_lengths(
+    captured: &BitSlice<u8, Lsb0>,
+    total: usize,
+    expected: &[Option<usize>],
+) -> Result<Vec<usize>, DebugProbeError> {
+    if expected.len() == 1 {
+        return Ok(vec![total]);
+    }
+
+    if expected.iter().all(Option::is_some) {
+        let lengths: Vec<usize> = expected.iter().flatten().copied().collect();
+        return if lengths.iter().sum::<usize>() == total {
+            Ok(lengths)
+        } else {
+            Err(DebugProbeError::JtagScanChain(
+                "The IR lengths in the scan chain description do not match the detected chain",
+            ))
+        };
+    }
+
+    let captured = &captured[..total.min(captured.len())];
+    let mut lengths = Vec::with_capacity(expected.len());
+    let mut pos = 0;
+
+    for (i, expected_len) in expected.iter().enumerate() {
+        let is_last = i == expected.len() - 1;
+
+        let len = match expected_len {
+            Some(len) => *len,
+            None => {
+                // The IR capture value always ends in `0b01`.
+                if captured.len() < pos + 2 || !captured[pos] || captured[pos + 1] {
+                    return Err(DebugProbeError::JtagScanChain(
+                        "Invalid IR capture value during the chain scan",
+                    ));
+                }
+
+                if is_last {
+                    captured.len() - pos
+                } else {
+                    2 + captured[pos + 2..].iter().position(|bit| *bit).ok_or(
+                        DebugProbeError::JtagScanChain(
+                            "Invalid IR capture value during the chain scan",
+                        ),
+                    )?
+                }
+            }
+        };
+
+        lengths.push(len);
+        pos += len;
+    }
+
+    if pos != total {
+        return Err(DebugProbeError::JtagScanChain(
+            "Could not determine the IR lengths of the scan chain",
+        ));
+    }
+
+    Ok(lengths)
+}
+
 /// Low-Level Access to the JTAG protocol
 ///
 /// This trait should be implemented by all probes which offer low-level access to
@@ -818,6 +1393,89 @@ pub trait JTAGAccess: DebugProbe {
     /// Set the IR register length
     fn set_ir_len(&mut self, len: u32);
 
+    /// Reset the TAPs using TMS and go to Run-Test/Idle.
+    fn tap_reset(&mut self) -> Result<(), DebugProbeError> {
+        Err(DebugProbeError::NotImplemented("JTAG TAP reset"))
+    }
+
+    /// Shift `len` bits through the instruction registers of the whole scan chain,
+    /// starting and ending in Run-Test/Idle, and return the bits shifted out.
+    ///
+    /// Unlike [`JTAGAccess::write_register`], this ignores the selected TAP.
+    fn shift_raw_ir(&mut self, _data: &[u8], _len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        Err(DebugProbeError::NotImplemented("raw JTAG IR shift"))
+    }
+
+    /// Shift `len` bits through the data registers of the whole scan chain,
+    /// starting and ending in Run-Test/Idle, and return the bits shifted out.
+    ///
+    /// Unlike [`JTAGAccess::write_register`], this ignores the selected TAP.
+    fn shift_raw_dr(&mut self, _data: &[u8], _len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        Err(DebugProbeError::NotImplemented("raw JTAG DR shift"))
+    }
+
+    /// Select the TAP used by all further register accesses. All other
+    /// TAPs in the scan chain are kept in BYPASS.
+    fn set_chain_params(&mut self, _params: ChainParams) -> Result<(), DebugProbeError> {
+        Err(DebugProbeError::NotImplemented("JTAG TAP selection"))
+    }
+
+    /// Scan the JTAG chain, and return the IDCODE and IR length of all TAPs,
+    /// starting with the TAP closest to TDO.
+    ///
+    /// IR lengths given in `description` are used instead of detecting them, which is
+    /// necessary if a TAP does not follow the usual IR capture pattern.
+    fn scan_chain(
+        &mut self,
+        description: Option<&[ScanChainElement]>,
+    ) -> Result<Vec<JtagChainItem>, DebugProbeError> {
+        // Read the IDCODEs, shifting in ones.
+        let dr_len = (JTAG_MAX_CHAIN_LENGTH + 1) * 32;
+        self.tap_reset()?;
+        let dr = self.shift_raw_dr(&vec![0xff; dr_len / 8], dr_len)?;
+        let idcodes = extract_idcodes(&BitSlice::<u8, Lsb0>::from_slice(&dr)[..dr_len])?;
+
+        tracing::debug!("Found {} TAPs: {:x?}", idcodes.len(), idcodes);
+
+        if idcodes.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Detect the total IR length: Fill the IR chain with zeros, then shift in ones,
+        // and count the zeros coming out before the first one. This leaves all TAPs in BYPASS.
+        self.tap_reset()?;
+        let mut ir = BitVec::<u8, Lsb0>::repeat(false, JTAG_MAX_IR_LENGTH);
+        ir.resize(2 * JTAG_MAX_IR_LENGTH, true);
+        let ir = self.shift_raw_ir(ir.as_raw_slice(), ir.len())?;
+        let ir = BitSlice::<u8, Lsb0>::from_slice(&ir);
+
+        let total_irlen = ir[JTAG_MAX_IR_LENGTH..2 * JTAG_MAX_IR_LENGTH]
+            .iter()
+            .position(|bit| *bit)
+            .ok_or(DebugProbeError::JtagScanChain(
+                "Could not detect the IR length, check the wiring of TDI",
+            ))?;
+
+        let expected = (0..idcodes.len())
+            .map(|i| {
+                description
+                    .and_then(|chain| chain.get(i))
+                    .and_then(|tap| tap.ir_len)
+                    .map(usize::from)
+            })
+            .collect::<Vec<_>>();
+
+        let lengths = extract_ir_lengths(&ir[..JTAG_MAX_IR_LENGTH], total_irlen, &expected)?;
+
+        self.tap_reset()?;
+
+        Ok(idcodes
+            .into_iter()
+            .zip(lengths)
+            .map(|(idcode, irlen)| JtagChainItem { idcode, irlen })
+            .collect())
+    }
+
     /// Write to a JTAG register
     ///
     /// This function will perform a write to the IR register, if necessary,
@@ -905,3 +1563,208 @@ pub enum AttachMethod {
     /// This is required on targets that can remap SWD pins or disable the SWD interface in sleep.
     UnderReset,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn bits(data: &[u8]) -> &BitSlice<u8, Lsb0> {
+        BitSlice::from_slice(data)
+    }
+
+    #[test]
+    fn extract_idcodes_with_bypass_tap() {
+        // IDCODE 0x4ba00477, a TAP in BYPASS, then the ones shifted in.
+        let mut dr = BitVec::<u8, Lsb0>::new();
+        dr.extend_from_bitslice(bits(&0x4ba0_0477u32.to_le_bytes()));
+        dr.push(false);
+        dr.resize(dr.len() + 64, true);
+
+        let idcodes = extract_idcodes(&dr).unwrap();
+
+        assert_eq!(idcodes, vec![Some(0x4ba0_0477), None]);
+    }
+
+    #[test]
+    fn extract_idcodes_empty_chain() {
+        assert_eq!(extract_idcodes(bits(&[0xff; 8])).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn extract_idcodes_max_chain_length() {
+        let chain = |taps: usize| {
+            let mut dr = BitVec::<u8, Lsb0>::new();
+            for _ in 0..taps {
+                dr.extend_from_bitslice(bits(&0x4ba0_0477u32.to_le_bytes()));
+            }
+            dr.resize(dr.len() + 32, true);
+            dr
+        };
+
+        assert_eq!(
+            extract_idcodes(&chain(JTAG_MAX_CHAIN_LENGTH)).unwrap(),
+            vec![Some(0x4ba0_0477); JTAG_MAX_CHAIN_LENGTH]
+        );
+        assert!(extract_idcodes(&chain(JTAG_MAX_CHAIN_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn extract_idcodes_stuck_tdo() {
+        assert!(extract_idcodes(bits(&[0x00; 8])).is_err());
+    }
+
+    #[test]
+    fn extract_ir_lengths_from_capture() {
+        // A TAP with IR length 4, followed by one with IR length 5.
+        let captured = [0b0001_0001, 0b0];
+
+        let lengths = extract_ir_lengths(bits(&captured), 9, &[None, None]).unwrap();
+
+        assert_eq!(lengths, vec![4, 5]);
+    }
+
+    #[test]
+    fn extract_ir_lengths_with_description() {
+        // The first TAP captures `0b00101`, which would be detected as an IR length of 2.
+        let captured = [0b0010_0101, 0b0];
+
+        let lengths = extract_ir_lengths(bits(&captured), 9, &[Some(5), None]).unwrap();
+
+        assert_eq!(lengths, vec![5, 4]);
+    }
+
+    #[test]
+    fn extract_ir_lengths_single_tap() {
+        assert_eq!(
+            extract_ir_lengths(bits(&[0b101]), 5, &[None]).unwrap(),
+            vec![5]
+        );
+    }
+
+    #[test]
+    fn chain_params_for_middle_tap() {
+        let chain = [
+            JtagChainItem {
+                idcode: Some(1),
+                irlen: 4,
+            },
+            JtagChainItem {
+                idcode: None,
+                irlen: 5,
+            },
+            JtagChainItem {
+                idcode: Some(2),
+                irlen: 6,
+            },
+        ];
+
+        assert_eq!(
+            ChainParams::from_chain(&chain, 1).unwrap(),
+            ChainParams {
+                irpre: 4,
+                irpost: 6,
+                drpre: 1,
+                drpost: 1,
+                irlen: 5,
+            }
+        );
+        assert!(ChainParams::from_chain(&chain, 3).is_err());
+        assert_eq!(default_jtag_tap(&chain), None);
+        assert_eq!(default_jtag_tap(&chain[1..2]), Some(0));
+        assert_eq!(JtagTapSelector::IdCode(2).find(&chain), Some(2));
+        assert_eq!(JtagTapSelector::Index(3).find(&chain), None);
+    }
+
+    #[test]
+    fn parse_selector_with_port_path() {
+        let selector: DebugProbeSelector = "0483:3748:SERIAL@1-4.2".parse().unwrap();
+
+        assert_eq!(selector.vendor_id, 0x0483);
+        assert_eq!(selector.product_id, 0x3748);
+        assert_eq!(selector.serial_number.as_deref(), Some("SERIAL"));
+        assert_eq!(selector.usb_port_path.as_deref(), Some("1-4.2"));
+        assert_eq!(selector.to_string(), "0483:3748:SERIAL@1-4.2");
+
+        let selector: DebugProbeSelector = "0483:3748@2-1".parse().unwrap();
+        assert_eq!(selector.serial_number, None);
+        assert_eq!(selector.usb_port_path.as_deref(), Some("2-1"));
+
+        assert!("0483:3748@".parse::<DebugProbeSelector>().is_err());
+    }
+
+    #[test]
+    fn selector_matches_port_path() {
+        let mut info =
+            DebugProbeInfo::new("probe", 0x0483, 0x3748, None, DebugProbeType::StLink, None);
+        info.usb_port_path = Some("1-4.2".to_string());
+
+        assert!(DebugProbeSelector::from_str("0483:3748")
+            .unwrap()
+            .matches(&info));
+        assert!(DebugProbeSelector::from_str("0483:3748@1-4.2")
+            .unwrap()
+            .matches(&info));
+        assert!(!DebugProbeSelector::from_str("0483:3748@1-4.3")
+            .unwrap()
+            .matches(&info));
+        assert!(!DebugProbeSelector::from_str("0483:3748:SERIAL")
+            .unwrap()
+            .matches(&info));
+    }
+
+    #[test]
+    fn parse_tap_selector() {
+        assert_eq!("2".parse(), Ok(JtagTapSelector::Index(2)));
+        assert_eq!(
+            "0x1000563d".parse(),
+            Ok(JtagTapSelector::IdCode(0x1000_563d))
+        );
+        assert!("foo".parse::<JtagTapSelector>().is_err());
+    }
+
+    #[test]
+    fn detect_lost_connection() {
+        use crate::architecture::arm::{ArmError, DapError};
+
+        let unplugged = DebugProbeError::ProbeSpecific(Box::new(rusb::Error::NoDevice));
+        assert!(crate::Error::Probe(unplugged).is_connection_lost());
+
+        let stalled = DebugProbeError::ProbeSpecific(Box::new(rusb::Error::Pipe));
+        assert!(!crate::Error::Probe(stalled).is_connection_lost());
+
+        let no_ack = ArmError::Dap(DapError::NoAcknowledge);
+        assert!(crate::Error::Arm(no_ack).is_connection_lost());
+
+        let fault = ArmError::Dap(DapError::FaultResponse);
+        assert!(!crate::Error::Arm(fault).is_connection_lost());
+    }
+
+    #[test]
+    fn detect_lost_connection_in_source_chain() {
+        use crate::architecture::riscv::communication_interface::RiscvError;
+        use crate::architecture::xtensa::communication_interface::XtensaError;
+
+        #[derive(Debug, thiserror::Error)]
+        #[error("transfer failed")]
+        struct TransferError(#[source] std::io::Error);
+
+        let broken_pipe = || {
+            DebugProbeError::ProbeSpecific(Box::new(TransferError(std::io::Error::from(
+                std::io::ErrorKind::BrokenPipe,
+            ))))
+        };
+        assert!(crate::Error::Probe(broken_pipe()).is_connection_lost());
+        assert!(crate::Error::Riscv(RiscvError::DebugProbe(broken_pipe())).is_connection_lost());
+        assert!(crate::Error::Xtensa(XtensaError::DebugProbe(broken_pipe())).is_connection_lost());
+
+        let timed_out = DebugProbeError::ProbeSpecific(Box::new(TransferError(
+            std::io::Error::from(std::io::ErrorKind::TimedOut),
+        )));
+        assert!(!crate::Error::Probe(timed_out).is_connection_lost());
+
+        let unplugged = DebugProbeError::Usb(Some(Box::new(rusb::Error::Io)));
+        assert!(crate::Error::Probe(unplugged).is_connection_lost());
+        assert!(!crate::Error::Probe(DebugProbeError::Usb(None)).is_connection_lost());
+    }
+}
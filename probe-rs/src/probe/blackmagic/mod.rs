@@ -0,0 +1,816 @@
+//! Driver for the Black Magic Probe.
+//!
+//! The probe is accessed using its remote protocol, which exposes the low-level SWD and JTAG
+//! primitives of the probe. The GDB server running on the probe is not used.
+
+mod protocol;
+
+use std::time::Duration;
+
+use bitvec::{field::BitField, order::Lsb0, slice::BitSlice, vec::BitVec};
+
+use crate::{
+    architecture::{
+        arm::{
+            communication_interface::{DapProbe, UninitializedArmProbe},
+            dp::{select_multidrop_dp, Abort, RdBuff},
+            ArmCommunicationInterface, ArmError, DapError, DpAddress, Pins, PortType, RawDapAccess,
+            Register,
+        },
+        riscv::communication_interface::{RiscvCommunicationInterface, RiscvError},
+    },
+    DebugProbe, DebugProbeError, DebugProbeSelector, ProbeCreationError, WireProtocol,
+};
+
+use self::protocol::ProtocolHandler;
+
+use super::{ChainParams, JTAGAccess};
+
+pub use protocol::list_blackmagic_devices;
+
+/// How often a SWD or JTAG transfer is retried when a WAIT response is received.
+const DAP_WAIT_RETRIES: usize = 1000;
+
+// SWD acknowledge values
+const SWD_ACK_OK: u32 = 0b001;
+const SWD_ACK_WAIT: u32 = 0b010;
+const SWD_ACK_FAULT: u32 = 0b100;
+const SWD_ACK_NO_RESPONSE: u32 = 0b111;
+
+// IR values for the JTAG-DP registers
+const JTAG_ABORT_IR_VALUE: u32 = 0x8;
+const JTAG_DEBUG_PORT_IR_VALUE: u32 = 0xA;
+const JTAG_ACCESS_PORT_IR_VALUE: u32 = 0xB;
+
+// JTAG-DP acknowledge values
+const JTAG_STATUS_WAIT: u64 = 0x1;
+const JTAG_STATUS_OK: u64 = 0x2;
+
+// ARM DR accesses are always 35 bits wide
+const JTAG_DR_BIT_LENGTH: usize = 35;
+
+/// Errors specific to the Black Magic Probe.
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum BlackMagicProbeError {
+    #[error("The probe returned an error for command {0:?}.")]
+    ErrorResponse(String),
+    #[error("The probe does not support the command {0:?}. Please update the probe firmware.")]
+    NotSupported(String),
+    #[error("Incorrect parity on data read from the target.")]
+    IncorrectParity,
+    #[error("Unexpected response from the probe: {0:?}")]
+    MalformedResponse(String),
+    #[error("The GDB interface of the probe could not be found.")]
+    InterfaceNotFound,
+    #[error("USB Communication Error")]
+    Usb(#[from] rusb::Error),
+}
+
+impl From<BlackMagicProbeError> for DebugProbeError {
+    fn from(e: BlackMagicProbeError) -> Self {
+        DebugProbeError::ProbeSpecific(Box::new(e))
+    }
+}
+
+impl From<BlackMagicProbeError> for ProbeCreationError {
+    fn from(e: BlackMagicProbeError) -> Self {
+        ProbeCreationError::ProbeSpecific(Box::new(e))
+    }
+}
+
+impl From<BlackMagicProbeError> for ArmError {
+    fn from(e: BlackMagicProbeError) -> Self {
+        match e {
+            BlackMagicProbeError::IncorrectParity => DapError::IncorrectParity.into(),
+            other => DebugProbeError::from(other).into(),
+        }
+    }
+}
+
+/// A Black Magic Probe, driven through its remote protocol.
+#[derive(Debug)]
+pub(crate) struct BlackMagicProbe {
+    protocol: ProtocolHandler,
+
+    /// Firmware version reported by the probe.
+    version: String,
+
+    wire_protocol: Option<WireProtocol>,
+
+    speed_khz: u32,
+
+    /// Length of the IR register of the selected TAP.
+    jtag_ir_len: u32,
+
+    /// Position of the selected TAP in the JTAG scan chain.
+    jtag_chain: ChainParams,
+
+    /// Idle cycles inserted after each DR access.
+    jtag_idle_cycles: u8,
+
+    /// Last value written to the IR register, if known.
+    current_ir_reg: Option<u32>,
+}
+
+impl BlackMagicProbe {
+    fn protocol(&self) -> WireProtocol {
+        self.wire_protocol.unwrap_or(WireProtocol::Swd)
+    }
+
+    /// Perform a single JTAG-DP transfer, retrying on WAIT responses.
+    ///
+    /// The JTAG-DP returns the result of a read in the *next* transfer,
+    /// so reads are followed by a read of the RDBUFF register.
+    fn jtag_dap_transfer(
+        &mut self,
+        port: PortType,
+        address: u8,
+        value: Option<u32>,
+    ) -> Result<u32, ArmError> {
+        let is_abort = port == PortType::DebugPort && address == Abort::ADDRESS && value.is_some();
+
+        let ir = if is_abort {
+            JTAG_ABORT_IR_VALUE
+        } else {
+            match port {
+                PortType::DebugPort => JTAG_DEBUG_PORT_IR_VALUE,
+                PortType::AccessPort => JTAG_ACCESS_PORT_IR_VALUE,
+            }
+        };
+
+        self.jtag_dap_shift(ir, jtag_dap_payload(address, value))?;
+
+        if value.is_some() {
+            return Ok(0);
+        }
+
+        let result = self.jtag_dap_shift(
+            JTAG_DEBUG_PORT_IR_VALUE,
+            jtag_dap_payload(RdBuff::ADDRESS, None),
+        )?;
+
+        Ok((result >> 3) as u32)
+    }
+
+    /// Shift a 35 bit payload into the given JTAG-DP scan chain, retrying on WAIT responses.
+    fn jtag_dap_shift(&mut self, ir: u32, payload: u64) -> Result<u64, ArmError> {
+        for _ in 0..DAP_WAIT_RETRIES {
+            let response =
+                self.write_register(ir, &payload.to_le_bytes(), JTAG_DR_BIT_LENGTH as u32)?;
+
+            let mut result = [0u8; 8];
+            result[..response.len()].copy_from_slice(&response);
+            let result = u64::from_le_bytes(result);
+
+            // ABORT does not have a meaningful acknowledge.
+            if ir == JTAG_ABORT_IR_VALUE {
+                return Ok(result);
+            }
+
+            match result & 0b111 {
+                JTAG_STATUS_OK => return Ok(result),
+                JTAG_STATUS_WAIT => continue,
+                _ => return Err(DapError::NoAcknowledge.into()),
+            }
+        }
+
+        Err(DapError::WaitResponse.into())
+    }
+
+    /// Shift `len` bits through the currently selected JTAG register, and return the captured bits.
+    ///
+    /// The TAP has to be in the SHIFT-IR or SHIFT-DR state, and is in the EXIT1 state afterwards.
+    fn jtag_shift(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, BlackMagicProbeError> {
+        let input = &BitSlice::<u8, Lsb0>::from_slice(data)[..len];
+
+        let mut output = BitVec::<u8, Lsb0>::with_capacity(len);
+
+        for (index, chunk) in input.chunks(64).enumerate() {
+            let is_last = (index + 1) * 64 >= len;
+
+            let tdo = self.protocol.jtag_tdi_tdo_seq(
+                chunk.load_le::<u64>(),
+                chunk.len() as u8,
+                is_last,
+            )?;
+
+            output.extend_from_bitslice(&BitSlice::<u64, Lsb0>::from_element(&tdo)[..chunk.len()]);
+        }
+
+        Ok(output.into_vec())
+    }
+
+    /// Clock TMS low for the given amount of cycles.
+    fn jtag_idle(&mut self, mut cycles: usize) -> Result<(), BlackMagicProbeError> {
+        while cycles > 0 {
+            let chunk = cycles.min(32);
+            self.protocol.jtag_tms_seq(0, chunk as u8)?;
+            cycles -= chunk;
+        }
+
+        Ok(())
+    }
+
+    /// Write the IR register of the selected TAP, putting all other TAPs into BYPASS.
+    fn jtag_write_ir(&mut self, address: u32) -> Result<(), BlackMagicProbeError> {
+        if self.current_ir_reg == Some(address) {
+            return Ok(());
+        }
+
+        let chain = self.jtag_chain;
+
+        let mut ir = BitVec::<u8, Lsb0>::repeat(true, chain.irpre);
+        ir.extend_from_bitslice(
+            &BitSlice::<u32, Lsb0>::from_element(&address)[..self.jtag_ir_len as usize],
+        );
+        ir.resize(ir.len() + chain.irpost, true);
+
+        self.jtag_shift_ir(ir.as_raw_slice(), ir.len())?;
+
+        self.current_ir_reg = Some(address);
+
+        Ok(())
+    }
+
+    /// Shift data through the IR registers of the chain, starting and ending in RUN-TEST/IDLE.
+    fn jtag_shift_ir(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, BlackMagicProbeError> {
+        // RUN-TEST/IDLE -> SELECT-DR-SCAN -> SELECT-IR-SCAN -> CAPTURE-IR -> SHIFT-IR
+        self.protocol.jtag_tms_seq(0b0011, 4)?;
+        let response = self.jtag_shift(data, len)?;
+        // EXIT1-IR -> UPDATE-IR -> RUN-TEST/IDLE
+        self.protocol.jtag_tms_seq(0b01, 2)?;
+
+        Ok(response)
+    }
+
+    /// Shift data through the DR registers of the chain, starting and ending in RUN-TEST/IDLE.
+    fn jtag_transfer_dr(
+        &mut self,
+        data: &[u8],
+        len: usize,
+    ) -> Result<Vec<u8>, BlackMagicProbeError> {
+        // RUN-TEST/IDLE -> SELECT-DR-SCAN -> CAPTURE-DR -> SHIFT-DR
+        self.protocol.jtag_tms_seq(0b001, 3)?;
+        let response = self.jtag_shift(data, len)?;
+        // EXIT1-DR -> UPDATE-DR -> RUN-TEST/IDLE
+        self.protocol.jtag_tms_seq(0b01, 2)?;
+
+        self.jtag_idle(self.jtag_idle_cycles as usize)?;
+
+        Ok(response)
+    }
+}
+
+impl DebugProbe for BlackMagicProbe {
+    fn new_from_selector(
+        selector: impl Into<DebugProbeSelector>,
+    ) -> Result<Box<Self>, DebugProbeError>
+    where
+        Self: Sized,
+    {
+        let mut protocol = ProtocolHandler::new_from_selector(selector)?;
+
+        let version = protocol.start()?;
+
+        tracing::info!("Black Magic Probe firmware version: {}", version);
+
+        Ok(Box::new(BlackMagicProbe {
+            protocol,
+            version,
+            wire_protocol: None,
+            speed_khz: 0,
+            jtag_ir_len: 4,
+            jtag_chain: ChainParams::default(),
+            jtag_idle_cycles: 0,
+            current_ir_reg: None,
+        }))
+    }
+
+    fn get_name(&self) -> &str {
+        "Black Magic Probe"
+    }
+
+    fn speed_khz(&self) -> u32 {
+        self.speed_khz
+    }
+
+    fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
+        match self.protocol.set_frequency(speed_khz * 1_000) {
+            Ok(()) => {}
+            // Older firmware versions do not allow to set the frequency.
+            Err(BlackMagicProbeError::NotSupported(_)) => {
+                tracing::warn!(
+                    "The firmware of the Black Magic Probe ({}) does not support setting the speed.",
+                    self.version
+                );
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        self.speed_khz = speed_khz;
+
+        Ok(speed_khz)
+    }
+
+    fn attach(&mut self) -> Result<(), DebugProbeError> {
+        tracing::debug!("Attaching with protocol {}", self.protocol());
+
+        match self.protocol() {
+            WireProtocol::Swd => self.protocol.swd_init()?,
+            WireProtocol::Jtag => {
+                self.protocol.jtag_init()?;
+                self.protocol.jtag_reset()?;
+                self.current_ir_reg = None;
+            }
+        }
+
+        // Make sure the protocol is stored, in case the default was used.
+        self.wire_protocol = Some(self.protocol());
+
+        Ok(())
+    }
+
+    fn detach(&mut self) -> Result<(), crate::Error> {
+        tracing::debug!("Detaching from the target");
+
+        // Release the reset line, in case the target was held in reset.
+        self.protocol
+            .set_srst(false)
+            .map_err(DebugProbeError::from)?;
+
+        // Leave the TAPs in their reset state, with the IDCODE or BYPASS instruction selected.
+        if self.wire_protocol == Some(WireProtocol::Jtag) {
+            self.protocol.jtag_reset().map_err(DebugProbeError::from)?;
+        }
+
+        self.current_ir_reg = None;
+
+        Ok(())
+    }
+
+    fn target_reset(&mut self) -> Result<(), DebugProbeError> {
+        self.target_reset_assert()?;
+        std::thread::sleep(Duration::from_millis(10));
+        self.target_reset_deassert()
+    }
+
+    fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+        self.protocol.set_srst(true)?;
+        Ok(())
+    }
+
+    fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
+        self.protocol.set_srst(false)?;
+        Ok(())
+    }
+
+    fn select_protocol(&mut self, protocol: WireProtocol) -> Result<(), DebugProbeError> {
+        self.wire_protocol = Some(protocol);
+        Ok(())
+    }
+
+    fn active_protocol(&self) -> Option<WireProtocol> {
+        self.wire_protocol
+    }
+
+    fn has_arm_interface(&self) -> bool {
+        true
+    }
+
+    fn try_get_arm_interface<'probe>(
+        self: Box<Self>,
+    ) -> Result<Box<dyn UninitializedArmProbe + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)>
+    {
+        // WAIT responses are retried in the driver, so overrun detection is not needed.
+        Ok(Box::new(ArmCommunicationInterface::new(self, false)))
+    }
+
+    fn has_riscv_interface(&self) -> bool {
+        true
+    }
+
+    fn try_get_riscv_interface(
+        self: Box<Self>,
+    ) -> Result<RiscvCommunicationInterface, (Box<dyn DebugProbe>, RiscvError)> {
+        let protocol = self.protocol();
+        if protocol != WireProtocol::Jtag {
+            return Err((self, DebugProbeError::UnsupportedProtocol(protocol).into()));
+        }
+
+        match RiscvCommunicationInterface::new(self) {
+            Ok(interface) => Ok(interface),
+            Err((probe, err)) => Err((probe.into_probe(), err)),
+        }
+    }
+
+    fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+        self
+    }
+
+    fn try_as_dap_probe(&mut self) -> Option<&mut dyn DapProbe> {
+        Some(self)
+    }
+
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        Some(self)
+    }
+
+    fn get_target_voltage(&mut self) -> Result<Option<f32>, DebugProbeError> {
+        let voltage = self.protocol.target_voltage()?;
+
+        // The voltage is reported as a string like `3.3V`. If no voltage
+        // can be measured, some other text is returned instead.
+        Ok(voltage.trim_end_matches('V').parse().ok())
+    }
+}
+
+impl RawDapAccess for BlackMagicProbe {
+    fn select_dp(&mut self, dp: DpAddress) -> Result<(), ArmError> {
+        match dp {
+            DpAddress::Default => Ok(()), // nop
+            DpAddress::Multidrop(targetsel) => select_multidrop_dp(self, targetsel),
+        }
+    }
+
+    fn raw_read_register(&mut self, port: PortType, address: u8) -> Result<u32, ArmError> {
+        match self.protocol() {
+            WireProtocol::Swd => swd_transfer(&mut self.protocol, port, address, None),
+            WireProtocol::Jtag => self.jtag_dap_transfer(port, address, None),
+        }
+    }
+
+    fn raw_write_register(
+        &mut self,
+        port: PortType,
+        address: u8,
+        value: u32,
+    ) -> Result<(), ArmError> {
+        match self.protocol() {
+            WireProtocol::Swd => swd_transfer(&mut self.protocol, port, address, Some(value)),
+            WireProtocol::Jtag => self.jtag_dap_transfer(port, address, Some(value)),
+        }
+        .map(|_| ())
+    }
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), DebugProbeError> {
+        let mut remaining = bit_len;
+        let mut bits = bits;
+
+        while remaining > 0 {
+            let chunk = remaining.min(32);
+
+            match self.protocol() {
+                WireProtocol::Swd => self.protocol.swd_seq_out(bits as u32, chunk)?,
+                WireProtocol::Jtag => self.protocol.jtag_tms_seq(bits as u32, chunk)?,
+            }
+
+            bits = bits.checked_shr(chunk as u32).unwrap_or(0);
+            remaining -= chunk;
+        }
+
+        // The TAP state is unknown after an arbitrary TMS sequence.
+        self.current_ir_reg = None;
+
+        Ok(())
+    }
+
+    fn swj_pins(
+        &mut self,
+        pin_out: u32,
+        pin_select: u32,
+        pin_wait: u32,
+    ) -> Result<u32, DebugProbeError> {
+        let mut nreset = Pins(0);
+        nreset.set_nreset(true);
+        let nreset_mask = nreset.0 as u32;
+
+        // Only the reset pin can be controlled through the remote protocol.
+        if pin_select != nreset_mask {
+            return Err(DebugProbeError::CommandNotSupportedByProbe("swj_pins"));
+        }
+
+        self.protocol.set_srst(!Pins(pin_out as u8).nreset())?;
+
+        // The probe has no way to wait for the pins to settle,
+        // so we wait on the host instead.
+        std::thread::sleep(Duration::from_micros(pin_wait as u64));
+
+        let mut pins = Pins(0xff);
+        pins.set_nreset(!self.protocol.srst()?);
+
+        Ok(pins.0 as u32)
+    }
+
+    fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+        self
+    }
+}
+
+impl DapProbe for BlackMagicProbe {}
+
+impl JTAGAccess for BlackMagicProbe {
+    fn set_ir_len(&mut self, len: u32) {
+        if self.jtag_ir_len != len {
+            self.jtag_ir_len = len;
+            self.current_ir_reg = None;
+        }
+    }
+
+    /// Read the data register
+    fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
+        let data = vec![0u8; (len as usize + 7) / 8];
+
+        self.write_register(address, &data, len)
+    }
+
+    /// Write the data register
+    fn write_register(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        len: u32,
+    ) -> Result<Vec<u8>, DebugProbeError> {
+        if data.len() * 8 < len as usize {
+            return Err(DebugProbeError::Other(anyhow::anyhow!(
+                "Not enough data to write {} bits to register {:#x}",
+                len,
+                address
+            )));
+        }
+
+        self.jtag_write_ir(address)?;
+
+        // The other TAPs in the chain are in BYPASS, and add a single bit each.
+        let chain = self.jtag_chain;
+
+        let mut dr = BitVec::<u8, Lsb0>::repeat(false, chain.drpre);
+        dr.extend_from_bitslice(&BitSlice::<u8, Lsb0>::from_slice(data)[..len as usize]);
+        dr.resize(dr.len() + chain.drpost, false);
+
+        let response = self.jtag_transfer_dr(dr.as_raw_slice(), dr.len())?;
+
+        let mut response = BitVec::<u8, Lsb0>::from_vec(response);
+        let mut response = response.split_off(chain.drpre);
+        response.truncate(len as usize);
+
+        Ok(response.into_vec())
+    }
+
+    fn tap_reset(&mut self) -> Result<(), DebugProbeError> {
+        self.protocol.jtag_reset()?;
+        self.current_ir_reg = None;
+
+        Ok(())
+    }
+
+    fn shift_raw_ir(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        self.current_ir_reg = None;
+
+        Ok(self.jtag_shift_ir(data, len)?)
+    }
+
+    fn shift_raw_dr(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        Ok(self.jtag_transfer_dr(data, len)?)
+    }
+
+    fn set_chain_params(&mut self, params: ChainParams) -> Result<(), DebugProbeError> {
+        self.jtag_chain = params;
+        self.jtag_ir_len = params.irlen as u32;
+        self.current_ir_reg = None;
+
+        Ok(())
+    }
+
+    fn set_idle_cycles(&mut self, idle_cycles: u8) {
+        self.jtag_idle_cycles = idle_cycles;
+    }
+
+    fn get_idle_cycles(&self) -> u8 {
+        self.jtag_idle_cycles
+    }
+}
+
+/// The SWD bit sequences of the remote protocol, which are used to perform DAP transfers.
+trait SwdIo {
+    /// Clock out up to 32 bits on SWDIO, LSB first.
+    fn swd_seq_out(&mut self, value: u32, bits: u8) -> Result<(), BlackMagicProbeError>;
+
+    /// Clock out up to 32 bits on SWDIO, LSB first, followed by a parity bit.
+    fn swd_seq_out_parity(&mut self, value: u32, bits: u8) -> Result<(), BlackMagicProbeError>;
+
+    /// Clock in up to 32 bits from SWDIO, LSB first.
+    fn swd_seq_in(&mut self, bits: u8) -> Result<u32, BlackMagicProbeError>;
+
+    /// Clock in up to 32 bits from SWDIO, LSB first, followed by a parity bit.
+    fn swd_seq_in_parity(&mut self, bits: u8) -> Result<u32, BlackMagicProbeError>;
+}
+
+impl SwdIo for ProtocolHandler {
+    fn swd_seq_out(&mut self, value: u32, bits: u8) -> Result<(), BlackMagicProbeError> {
+        ProtocolHandler::swd_seq_out(self, value, bits)
+    }
+
+    fn swd_seq_out_parity(&mut self, value: u32, bits: u8) -> Result<(), BlackMagicProbeError> {
+        ProtocolHandler::swd_seq_out_parity(self, value, bits)
+    }
+
+    fn swd_seq_in(&mut self, bits: u8) -> Result<u32, BlackMagicProbeError> {
+        ProtocolHandler::swd_seq_in(self, bits)
+    }
+
+    fn swd_seq_in_parity(&mut self, bits: u8) -> Result<u32, BlackMagicProbeError> {
+        ProtocolHandler::swd_seq_in_parity(self, bits)
+    }
+}
+
+/// Perform a single SWD transfer, retrying on WAIT responses.
+///
+/// For writes, `value` contains the value to write. For reads, the read value is returned.
+///
+/// AP reads are posted, the data phase of an AP read returns the result of the *previous*
+/// AP read. AP reads are therefore followed by a read of the RDBUFF register.
+fn swd_transfer(
+    io: &mut impl SwdIo,
+    port: PortType,
+    address: u8,
+    value: Option<u32>,
+) -> Result<u32, ArmError> {
+    let request = swd_request(port, address, value.is_none());
+
+    for retry in 0..DAP_WAIT_RETRIES {
+        io.swd_seq_out(request as u32, 8)?;
+
+        match io.swd_seq_in(3)? {
+            SWD_ACK_OK => break,
+            SWD_ACK_WAIT => {
+                tracing::debug!(
+                    "DAP WAIT, retries remaining {}.",
+                    DAP_WAIT_RETRIES - retry - 1
+                );
+
+                if retry == DAP_WAIT_RETRIES - 1 {
+                    return Err(DapError::WaitResponse.into());
+                }
+            }
+            SWD_ACK_FAULT => return Err(DapError::FaultResponse.into()),
+            SWD_ACK_NO_RESPONSE => return Err(DapError::NoAcknowledge.into()),
+            ack => {
+                tracing::debug!(
+                    "Unexpected response from target, does not conform to SWD specfication (ack={:#05b})",
+                    ack
+                );
+                return Err(DapError::SwdProtocol.into());
+            }
+        }
+    }
+
+    match value {
+        Some(value) => {
+            io.swd_seq_out_parity(value, 32)?;
+
+            // Clock the data through the DP by adding some idle cycles.
+            io.swd_seq_out(0, 8)?;
+
+            Ok(0)
+        }
+        None => {
+            let value = io.swd_seq_in_parity(32)?;
+
+            match port {
+                PortType::AccessPort => {
+                    swd_transfer(io, PortType::DebugPort, RdBuff::ADDRESS, None)
+                }
+                PortType::DebugPort => Ok(value),
+            }
+        }
+    }
+}
+
+/// Build the 8 bit SWD request header.
+fn swd_request(port: PortType, address: u8, read: bool) -> u8 {
+    let ap_n_dp = port == PortType::AccessPort;
+    let a2 = (address >> 2) & 0x01 == 1;
+    let a3 = (address >> 3) & 0x01 == 1;
+
+    let parity = ap_n_dp ^ read ^ a2 ^ a3;
+
+    // Start bit (always 1), park bit (always 1) and stop bit (always 0).
+    let mut request = 0b1000_0001;
+
+    request |= u8::from(ap_n_dp) << 1;
+    request |= u8::from(read) << 2;
+    request |= u8::from(a2) << 3;
+    request |= u8::from(a3) << 4;
+    request |= u8::from(parity) << 5;
+
+    request
+}
+
+/// Build the 35 bit payload for a DPACC or APACC scan.
+fn jtag_dap_payload(address: u8, value: Option<u32>) -> u64 {
+    let mut payload = 0u64;
+
+    // 32-bit value, bits 35:3
+    payload |= (value.unwrap_or(0) as u64) << 3;
+    // A[3:2], bits 2:1
+    payload |= (address as u64 & 0b1100) >> 1;
+    // RnW, bit 0
+    payload |= u64::from(value.is_none());
+
+    payload
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use super::{jtag_dap_payload, swd_request, swd_transfer, BlackMagicProbeError, SwdIo};
+    use crate::architecture::arm::PortType;
+
+    /// Records the sequences clocked out, and returns the queued values for sequences clocked in.
+    #[derive(Default)]
+    struct MockSwd {
+        sent: Vec<(u32, u8)>,
+        responses: VecDeque<u32>,
+    }
+
+    impl SwdIo for MockSwd {
+        fn swd_seq_out(&mut self, value: u32, bits: u8) -> Result<(), BlackMagicProbeError> {
+            self.sent.push((value, bits));
+            Ok(())
+        }
+
+        fn swd_seq_out_parity(&mut self, value: u32, bits: u8) -> Result<(), BlackMagicProbeError> {
+            self.sent.push((value, bits));
+            Ok(())
+        }
+
+        fn swd_seq_in(&mut self, _bits: u8) -> Result<u32, BlackMagicProbeError> {
+            Ok(self.responses.pop_front().expect("unexpected SWD read"))
+        }
+
+        fn swd_seq_in_parity(&mut self, bits: u8) -> Result<u32, BlackMagicProbeError> {
+            self.swd_seq_in(bits)
+        }
+    }
+
+    #[test]
+    fn swd_request_dpidr_read() {
+        // Read of DPIDR, this is the request sent during a line reset.
+        assert_eq!(swd_request(PortType::DebugPort, 0x0, true), 0xa5);
+    }
+
+    #[test]
+    fn swd_request_ap_write() {
+        // Write to AP register 0x4 (TAR of a MEM-AP).
+        assert_eq!(swd_request(PortType::AccessPort, 0x4, false), 0x8b);
+    }
+
+    #[test]
+    fn swd_request_ap_read() {
+        // Read of AP register 0xC (DRW of a MEM-AP).
+        assert_eq!(swd_request(PortType::AccessPort, 0xC, true), 0x9f);
+    }
+
+    #[test]
+    fn jtag_dap_payload_read() {
+        assert_eq!(jtag_dap_payload(0xC, None), 0b111);
+    }
+
+    #[test]
+    fn jtag_dap_payload_write() {
+        assert_eq!(
+            jtag_dap_payload(0x4, Some(0x5000_0000)),
+            (0x5000_0000u64 << 3) | 0b010
+        );
+    }
+
+    #[test]
+    fn swd_ap_read_returns_rdbuff() {
+        let mut mock = MockSwd {
+            // ACK and stale data of the AP read, then ACK and data of the RDBUFF read.
+            responses: VecDeque::from([0b001, 0xdead_beef, 0b001, 0x1234_5678]),
+            ..Default::default()
+        };
+
+        let value = swd_transfer(&mut mock, PortType::AccessPort, 0xC, None).unwrap();
+
+        assert_eq!(value, 0x1234_5678);
+        // Request for DRW, followed by a request for RDBUFF.
+        assert_eq!(mock.sent, [(0x9f, 8), (0xbd, 8)]);
+        assert!(mock.responses.is_empty());
+    }
+
+    #[test]
+    fn swd_dp_read_is_not_posted() {
+        let mut mock = MockSwd {
+            responses: VecDeque::from([0b001, 0x2ba0_1477]),
+            ..Default::default()
+        };
+
+        let value = swd_transfer(&mut mock, PortType::DebugPort, 0x0, None).unwrap();
+
+        assert_eq!(value, 0x2ba0_1477);
+        assert_eq!(mock.sent, [(0xa5, 8)]);
+    }
+}
@@ -0,0 +1,592 @@
+use std::{collections::VecDeque, fmt, time::Duration};
+
+use rusb::{Context, Device, DeviceHandle, Direction, TransferType, UsbContext};
+
+use super::BlackMagicProbeError;
+use crate::{
+    probe::usb_port_path, DebugProbeInfo, DebugProbeSelector, DebugProbeType, ProbeCreationError,
+};
+
+/// The USB VendorID of the Black Magic Probe.
+const USB_VID: u16 = 0x1d50;
+/// The USB ProductID of the Black Magic Probe in application mode.
+const USB_PID: u16 = 0x6018;
+
+const USB_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Interface class of a CDC communication interface.
+const USB_CLASS_CDC: u8 = 0x02;
+/// Interface class of a CDC data interface.
+const USB_CLASS_CDC_DATA: u8 = 0x0a;
+
+/// Descriptor type of the class-specific functional descriptors of a CDC interface.
+const CDC_CS_INTERFACE: u8 = 0x24;
+/// Descriptor subtype of the union functional descriptor, which links the communication
+/// interface to its data interface.
+const CDC_UNION_DESCRIPTOR: u8 = 0x06;
+
+/// CDC class request used to signal DTR / RTS to the probe.
+const CDC_SET_CONTROL_LINE_STATE: u8 = 0x22;
+
+/// Maximum size of a single response packet.
+///
+/// Responses to the low-level remote commands are at most a
+/// few dozen bytes long, this limit only guards against a probe
+/// which never sends the end-of-message marker.
+const MAX_RESPONSE_LEN: usize = 1024;
+
+/// End of a remote protocol message.
+const REMOTE_EOM: u8 = b'#';
+/// Start of a response to a remote protocol message.
+const REMOTE_RESP: u8 = b'&';
+
+const REMOTE_RESP_OK: u8 = b'K';
+const REMOTE_RESP_PARERR: u8 = b'P';
+const REMOTE_RESP_ERR: u8 = b'E';
+const REMOTE_RESP_NOTSUP: u8 = b'N';
+
+/// Handler for the remote protocol of the Black Magic Probe.
+///
+/// The probe exposes a GDB server on the first CDC ACM interface. Any packet starting with `!`
+/// is handled by the remote protocol instead, which gives the host direct access to the SWD
+/// and JTAG primitives of the probe. All messages are ASCII, with numbers encoded as hex strings.
+pub(super) struct ProtocolHandler {
+    device_handle: DeviceHandle<Context>,
+
+    /// The claimed CDC data interface.
+    interface: u8,
+
+    ep_out: u8,
+    ep_in: u8,
+
+    /// Bytes which were received from the probe, but not yet consumed.
+    input_buffer: VecDeque<u8>,
+}
+
+impl fmt::Debug for ProtocolHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtocolHandler")
+            .field("interface", &self.interface)
+            .field("ep_out", &self.ep_out)
+            .field("ep_in", &self.ep_in)
+            .finish()
+    }
+}
+
+impl ProtocolHandler {
+    pub fn new_from_selector(
+        selector: impl Into<DebugProbeSelector>,
+    ) -> Result<Self, ProbeCreationError> {
+        let selector = selector.into();
+
+        let context = Context::new()?;
+
+        tracing::debug!("Acquired libusb context.");
+
+        let device = context
+            .devices()?
+            .iter()
+            .filter(is_blackmagic_device)
+            .find_map(|device| {
+                let descriptor = device.device_descriptor().ok()?;
+                // First match the VID & PID.
+                if selector.vendor_id == descriptor.vendor_id()
+                    && selector.product_id == descriptor.product_id()
+                    && selector.matches_usb_port(&device)
+                {
+                    // If the VID & PID match, match the serial if one was given.
+                    if let Some(serial) = &selector.serial_number {
+                        let sn_str = read_serial_number(&device, &descriptor).ok();
+                        if sn_str.as_ref() == Some(serial) {
+                            Some(device)
+                        } else {
+                            None
+                        }
+                    } else {
+                        // If no serial was given, the VID & PID match is enough; return the device.
+                        Some(device)
+                    }
+                } else {
+                    None
+                }
+            })
+            .ok_or(ProbeCreationError::NotFound)?;
+
+        let mut device_handle = device.open()?;
+
+        tracing::debug!("Aquired handle for probe");
+
+        let config = device.active_config_descriptor()?;
+
+        // The first CDC data interface carries the GDB server, the second one is the UART bridge.
+        let (interface, ep_out, ep_in) = config
+            .interfaces()
+            .flat_map(|interface| interface.descriptors())
+            .filter(|descriptor| descriptor.class_code() == USB_CLASS_CDC_DATA)
+            .find_map(|descriptor| {
+                let mut ep_out = None;
+                let mut ep_in = None;
+
+                for endpoint in descriptor.endpoint_descriptors() {
+                    if endpoint.transfer_type() != TransferType::Bulk {
+                        continue;
+                    }
+
+                    match endpoint.direction() {
+                        Direction::Out => ep_out = Some(endpoint.address()),
+                        Direction::In => ep_in = Some(endpoint.address()),
+                    }
+                }
+
+                Some((descriptor.interface_number(), ep_out?, ep_in?))
+            })
+            .ok_or(BlackMagicProbeError::InterfaceNotFound)?;
+
+        // The communication interface belonging to the data interface receives the control requests.
+        let control_interface = config
+            .interfaces()
+            .flat_map(|interface| interface.descriptors())
+            .filter(|descriptor| descriptor.class_code() == USB_CLASS_CDC)
+            .find(|descriptor| cdc_union_controls(descriptor.extra(), interface))
+            .map(|descriptor| descriptor.interface_number())
+            .ok_or(BlackMagicProbeError::InterfaceNotFound)?;
+
+        tracing::debug!(
+            "Using CDC data interface {} (out: {:#04x}, in: {:#04x}), control interface {}",
+            interface,
+            ep_out,
+            ep_in,
+            control_interface
+        );
+
+        // The CDC ACM kernel driver is usually bound to the interface, we have to
+        // detach it before we can claim the interface. This is not supported on all platforms.
+        if let Err(e) = device_handle.set_auto_detach_kernel_driver(true) {
+            tracing::debug!("Automatic kernel driver detachment is not available: {}", e);
+        }
+
+        device_handle.claim_interface(interface)?;
+
+        tracing::debug!("Claimed interface {} of USB device.", interface);
+
+        let mut protocol = ProtocolHandler {
+            device_handle,
+            interface,
+            ep_out,
+            ep_in,
+            input_buffer: VecDeque::new(),
+        };
+
+        // Assert DTR on the communication interface, otherwise the probe will not send any data.
+        if let Err(e) = protocol.device_handle.write_control(
+            rusb::request_type(
+                Direction::Out,
+                rusb::RequestType::Class,
+                rusb::Recipient::Interface,
+            ),
+            CDC_SET_CONTROL_LINE_STATE,
+            0b11,
+            control_interface as u16,
+            &[],
+            USB_TIMEOUT,
+        ) {
+            tracing::debug!("Failed to set control line state: {}", e);
+        }
+
+        protocol.flush_input();
+
+        Ok(protocol)
+    }
+
+    /// Discard any data which was still pending from the probe,
+    /// e.g. output from a previous GDB session.
+    fn flush_input(&mut self) {
+        let mut buffer = [0u8; 64];
+
+        self.input_buffer.clear();
+
+        while let Ok(n) =
+            self.device_handle
+                .read_bulk(self.ep_in, &mut buffer, Duration::from_millis(10))
+        {
+            if n == 0 {
+                break;
+            }
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<u8, BlackMagicProbeError> {
+        while self.input_buffer.is_empty() {
+            let mut buffer = [0u8; 64];
+
+            let n = self
+                .device_handle
+                .read_bulk(self.ep_in, &mut buffer, USB_TIMEOUT)?;
+
+            self.input_buffer.extend(&buffer[..n]);
+        }
+
+        // NOTE(unwrap): The loop above ensures that the buffer is not empty.
+        Ok(self.input_buffer.pop_front().unwrap())
+    }
+
+    /// Send a remote command to the probe and wait for the response.
+    ///
+    /// On success, the payload of the response is returned.
+    pub fn command(&mut self, command: &str) -> Result<String, BlackMagicProbeError> {
+        tracing::trace!("Sending remote command {:?}", command);
+
+        self.device_handle
+            .write_bulk(self.ep_out, command.as_bytes(), USB_TIMEOUT)?;
+
+        // Skip everything until the start of the response, e.g.
+        // acknowledgements from the GDB server.
+        while self.read_byte()? != REMOTE_RESP {}
+
+        let mut response = Vec::new();
+
+        loop {
+            match self.read_byte()? {
+                REMOTE_EOM => break,
+                byte => response.push(byte),
+            }
+
+            if response.len() > MAX_RESPONSE_LEN {
+                return Err(BlackMagicProbeError::MalformedResponse(
+                    String::from_utf8_lossy(&response).into_owned(),
+                ));
+            }
+        }
+
+        tracing::trace!("Received response {:?}", String::from_utf8_lossy(&response));
+
+        parse_response(command, &response)
+    }
+
+    /// Send a remote command to the probe, and parse the response
+    /// as a hexadecimal number.
+    pub fn command_value(&mut self, command: &str) -> Result<u64, BlackMagicProbeError> {
+        let response = self.command(command)?;
+
+        parse_hex(&response)
+    }
+
+    /// Switch the probe into remote mode and return its firmware version.
+    pub fn start(&mut self) -> Result<String, BlackMagicProbeError> {
+        // The leading `+#` terminates any unfinished GDB packet.
+        self.command("+#!GA#")
+    }
+
+    /// Initialize the SWD interface of the probe.
+    pub fn swd_init(&mut self) -> Result<(), BlackMagicProbeError> {
+        self.command("!SS#").map(|_| ())
+    }
+
+    /// Clock out up to 32 bits on SWDIO, LSB first.
+    pub fn swd_seq_out(&mut self, value: u32, bits: u8) -> Result<(), BlackMagicProbeError> {
+        assert!(bits > 0 && bits <= 32);
+        self.command(&format!("!So{bits:02x}{value:x}#"))
+            .map(|_| ())
+    }
+
+    /// Clock out up to 32 bits on SWDIO, LSB first, followed by a parity bit.
+    pub fn swd_seq_out_parity(&mut self, value: u32, bits: u8) -> Result<(), BlackMagicProbeError> {
+        assert!(bits > 0 && bits <= 32);
+        self.command(&format!("!SO{bits:02x}{value:x}#"))
+            .map(|_| ())
+    }
+
+    /// Clock in up to 32 bits from SWDIO, LSB first.
+    pub fn swd_seq_in(&mut self, bits: u8) -> Result<u32, BlackMagicProbeError> {
+        assert!(bits > 0 && bits <= 32);
+        self.command_value(&format!("!Si{bits:02x}#"))
+            .map(|value| value as u32)
+    }
+
+    /// Clock in up to 32 bits from SWDIO, LSB first, followed by a parity bit.
+    ///
+    /// If the parity is incorrect, [`BlackMagicProbeError::IncorrectParity`] is returned.
+    pub fn swd_seq_in_parity(&mut self, bits: u8) -> Result<u32, BlackMagicProbeError> {
+        assert!(bits > 0 && bits <= 32);
+        self.command_value(&format!("!SI{bits:02x}#"))
+            .map(|value| value as u32)
+    }
+
+    /// Initialize the JTAG interface of the probe.
+    pub fn jtag_init(&mut self) -> Result<(), BlackMagicProbeError> {
+        self.command("!JS#").map(|_| ())
+    }
+
+    /// Reset the JTAG TAP state machine and go to RUN-TEST/IDLE.
+    pub fn jtag_reset(&mut self) -> Result<(), BlackMagicProbeError> {
+        self.command("!JR#").map(|_| ())
+    }
+
+    /// Clock out up to 32 bits on TMS, LSB first.
+    pub fn jtag_tms_seq(&mut self, tms: u32, bits: u8) -> Result<(), BlackMagicProbeError> {
+        assert!(bits > 0 && bits <= 32);
+        self.command(&format!("!JT{bits:02x}{tms:x}#")).map(|_| ())
+    }
+
+    /// Clock out up to 64 bits on TDI, LSB first, and return the bits captured on TDO.
+    ///
+    /// If `final_tms` is set, TMS is asserted while clocking the last bit,
+    /// which leaves the SHIFT-IR or SHIFT-DR state.
+    pub fn jtag_tdi_tdo_seq(
+        &mut self,
+        tdi: u64,
+        bits: u8,
+        final_tms: bool,
+    ) -> Result<u64, BlackMagicProbeError> {
+        assert!(bits > 0 && bits <= 64);
+        let command = if final_tms { 'D' } else { 'd' };
+        self.command_value(&format!("!J{command}{bits:02x}{tdi:x}#"))
+    }
+
+    /// Read the target voltage as reported by the probe, e.g. `3.3V`.
+    pub fn target_voltage(&mut self) -> Result<String, BlackMagicProbeError> {
+        self.command("!GV#")
+    }
+
+    /// Set the state of the nRST line. `true` asserts the reset.
+    pub fn set_srst(&mut self, asserted: bool) -> Result<(), BlackMagicProbeError> {
+        self.command(&format!("!GZ{}#", u8::from(asserted)))
+            .map(|_| ())
+    }
+
+    /// Read the state of the nRST line. `true` means reset is asserted.
+    pub fn srst(&mut self) -> Result<bool, BlackMagicProbeError> {
+        self.command_value("!Gz#").map(|value| value != 0)
+    }
+
+    /// Set the maximum SWCLK / TCK frequency in Hz.
+    pub fn set_frequency(&mut self, frequency: u32) -> Result<(), BlackMagicProbeError> {
+        self.command(&format!("!GF{frequency:08x}#")).map(|_| ())
+    }
+}
+
+impl Drop for ProtocolHandler {
+    fn drop(&mut self) {
+        if let Err(e) = self.device_handle.release_interface(self.interface) {
+            tracing::debug!("Failed to release USB interface: {}", e);
+        }
+    }
+}
+
+/// Parse a response to `command`, consisting of the status and the payload, without the
+/// start and end markers.
+fn parse_response(command: &str, response: &[u8]) -> Result<String, BlackMagicProbeError> {
+    let (status, payload) = match response.split_first() {
+        Some((status, payload)) => (*status, String::from_utf8_lossy(payload).into_owned()),
+        None => return Err(BlackMagicProbeError::MalformedResponse(String::new())),
+    };
+
+    match status {
+        REMOTE_RESP_OK => Ok(payload),
+        REMOTE_RESP_PARERR => Err(BlackMagicProbeError::IncorrectParity),
+        REMOTE_RESP_ERR => Err(BlackMagicProbeError::ErrorResponse(command.to_string())),
+        REMOTE_RESP_NOTSUP => Err(BlackMagicProbeError::NotSupported(command.to_string())),
+        _ => Err(BlackMagicProbeError::MalformedResponse(
+            String::from_utf8_lossy(response).into_owned(),
+        )),
+    }
+}
+
+/// Check if the class-specific descriptors of a CDC communication interface contain a union
+/// functional descriptor, which lists `data_interface` as one of its subordinate interfaces.
+fn cdc_union_controls(extra: &[u8], data_interface: u8) -> bool {
+    let mut descriptors = extra;
+
+    // Every functional descriptor starts with its length, type and subtype.
+    while let [length, descriptor_type, subtype, ..] = *descriptors {
+        let length = length as usize;
+        if length < 3 || length > descriptors.len() {
+            break;
+        }
+
+        // The union descriptor contains the control interface, followed by the subordinate interfaces.
+        if descriptor_type == CDC_CS_INTERFACE
+            && subtype == CDC_UNION_DESCRIPTOR
+            && length > 4
+            && descriptors[4..length].contains(&data_interface)
+        {
+            return true;
+        }
+
+        descriptors = &descriptors[length..];
+    }
+
+    false
+}
+
+fn parse_hex(value: &str) -> Result<u64, BlackMagicProbeError> {
+    if value.is_empty() {
+        return Ok(0);
+    }
+
+    u64::from_str_radix(value, 16)
+        .map_err(|_| BlackMagicProbeError::MalformedResponse(value.to_string()))
+}
+
+fn read_serial_number<T: rusb::UsbContext>(
+    device: &rusb::Device<T>,
+    descriptor: &rusb::DeviceDescriptor,
+) -> Result<String, rusb::Error> {
+    let timeout = Duration::from_millis(100);
+
+    let handle = device.open()?;
+    let language = handle
+        .read_languages(timeout)?
+        .first()
+        .cloned()
+        .ok_or(rusb::Error::BadDescriptor)?;
+    handle.read_serial_number_string(language, descriptor, timeout)
+}
+
+fn read_product_string<T: rusb::UsbContext>(
+    device: &rusb::Device<T>,
+    descriptor: &rusb::DeviceDescriptor,
+) -> Result<String, rusb::Error> {
+    let timeout = Duration::from_millis(100);
+
+    let handle = device.open()?;
+    let language = handle
+        .read_languages(timeout)?
+        .first()
+        .cloned()
+        .ok_or(rusb::Error::BadDescriptor)?;
+    handle.read_product_string(language, descriptor, timeout)
+}
+
+pub(super) fn is_blackmagic_device<T: UsbContext>(device: &Device<T>) -> bool {
+    // Check the VID/PID.
+    if let Ok(descriptor) = device.device_descriptor() {
+        descriptor.vendor_id() == USB_VID && descriptor.product_id() == USB_PID
+    } else {
+        false
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub fn list_blackmagic_devices() -> Vec<DebugProbeInfo> {
+    rusb::Context::new()
+        .and_then(|context| context.devices())
+        .map_or(vec![], |devices| {
+            devices
+                .iter()
+                .filter(is_blackmagic_device)
+                .filter_map(|device| {
+                    let descriptor = device.device_descriptor().ok()?;
+
+                    let sn_str = match read_serial_number(&device, &descriptor) {
+                        Ok(serial_number) => Some(serial_number),
+                        Err(e) => {
+                            // Reading the serial number can fail, e.g. if the driver for the probe
+                            // is not installed. In this case we can still list the probe,
+                            // just without serial number.
+                            tracing::debug!(
+                                "Failed to read serial number of device {:04x}:{:04x} : {}",
+                                descriptor.vendor_id(),
+                                descriptor.product_id(),
+                                e
+                            );
+                            tracing::debug!("This might be happening because of a missing driver.");
+                            None
+                        }
+                    };
+
+                    let identifier = read_product_string(&device, &descriptor)
+                        .unwrap_or_else(|_| "Black Magic Probe".to_string());
+
+                    Some(DebugProbeInfo {
+                        usb_port_path: usb_port_path(&device),
+                        ..DebugProbeInfo::new(
+                            identifier,
+                            descriptor.vendor_id(),
+                            descriptor.product_id(),
+                            sn_str,
+                            DebugProbeType::BlackMagicProbe,
+                            None,
+                        )
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cdc_union_controls, parse_hex, parse_response, BlackMagicProbeError};
+
+    #[test]
+    fn parse_hex_response() {
+        assert_eq!(parse_hex("").unwrap(), 0);
+        assert_eq!(parse_hex("1").unwrap(), 1);
+        assert_eq!(parse_hex("2ba01477").unwrap(), 0x2ba0_1477);
+        assert_eq!(parse_hex("ffffffffffffffff").unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn parse_invalid_hex_response() {
+        assert!(parse_hex("3.3V").is_err());
+    }
+
+    #[test]
+    fn parse_ok_response() {
+        assert_eq!(parse_response("!Si20#", b"K2ba01477").unwrap(), "2ba01477");
+        assert_eq!(parse_response("!SS#", b"K").unwrap(), "");
+    }
+
+    #[test]
+    fn parse_error_response() {
+        assert!(matches!(
+            parse_response("!JS#", b"E"),
+            Err(BlackMagicProbeError::ErrorResponse(command)) if command == "!JS#"
+        ));
+    }
+
+    #[test]
+    fn parse_unsupported_response() {
+        assert!(matches!(
+            parse_response("!GF000f4240#", b"N"),
+            Err(BlackMagicProbeError::NotSupported(command)) if command == "!GF000f4240#"
+        ));
+    }
+
+    #[test]
+    fn parse_parity_error_response() {
+        assert!(matches!(
+            parse_response("!SI20#", b"P"),
+            Err(BlackMagicProbeError::IncorrectParity)
+        ));
+    }
+
+    #[test]
+    fn parse_malformed_response() {
+        assert!(matches!(
+            parse_response("!GA#", b""),
+            Err(BlackMagicProbeError::MalformedResponse(_))
+        ));
+        assert!(matches!(
+            parse_response("!GA#", b"X12"),
+            Err(BlackMagicProbeError::MalformedResponse(response)) if response == "X12"
+        ));
+    }
+
+    #[test]
+    fn cdc_union_descriptor() {
+        // Header, call management, ACM and union functional descriptors of the GDB interface.
+        let extra = [
+            0x05, 0x24, 0x00, 0x10, 0x01, // header
+            0x05, 0x24, 0x01, 0x00, 0x01, // call management
+            0x04, 0x24, 0x02, 0x02, // ACM
+            0x05, 0x24, 0x06, 0x00, 0x01, // union: control 0, data 1
+        ];
+
+        assert!(cdc_union_controls(&extra, 1));
+        assert!(!cdc_union_controls(&extra, 3));
+        assert!(!cdc_union_controls(&[], 1));
+        // A truncated descriptor is ignored.
+        assert!(!cdc_union_controls(&extra[..17], 1));
+    }
+}
@@ -1,6 +1,6 @@
 use super::CmsisDapDevice;
 use crate::{
-    probe::{DebugProbeInfo, DebugProbeType, ProbeCreationError},
+    probe::{usb_port_path, DebugProbeInfo, DebugProbeType, ProbeCreationError},
     DebugProbeSelector,
 };
 use hidapi::HidApi;
@@ -117,6 +117,7 @@ fn get_cmsisdap_info(device: &Device<rusb::Context>) -> Option<DebugProbeInfo> {
             serial_number: sn_str,
             probe_type: DebugProbeType::CmsisDap,
             hid_interface,
+            usb_port_path: usb_port_path(device),
         })
     } else {
         None
@@ -142,6 +143,7 @@ fn get_cmsisdap_hid_info(device: &hidapi::DeviceInfo) -> Option<DebugProbeInfo>
             serial_number: device.serial_number().map(|s| s.to_owned()),
             probe_type: DebugProbeType::CmsisDap,
             hid_interface: Some(device.interface_number() as u8),
+            usb_port_path: hid_usb_port_path(device),
         })
     } else {
         None
@@ -232,13 +234,42 @@ pub fn open_v2_device(device: Device<rusb::Context>) -> Option<CmsisDapDevice> {
     None
 }
 
+/// Determine the USB port path of a HID device.
+///
+/// hidapi does not report the port path directly. When using the libusb backend, the path of the
+/// device starts with the port path. On Linux, the port path can be found in sysfs.
+fn hid_usb_port_path(device: &hidapi::DeviceInfo) -> Option<String> {
+    let path = device.path().to_str().ok()?;
+
+    // libusb backend, e.g. `1-4.2:1.0`
+    if let Some((port_path, _interface)) = path.split_once(':') {
+        if port_path.contains('-') && !port_path.contains('/') {
+            return Some(port_path.to_owned());
+        }
+    }
+
+    // hidraw backend, e.g. `/dev/hidraw3`, which links to a sysfs path like
+    // `/sys/devices/pci0000:00/0000:00:14.0/usb1/1-4/1-4.2/1-4.2:1.0/0003:0D28:0204.0005/hidraw/hidraw3`
+    let name = path.strip_prefix("/dev/")?;
+    let sysfs_path = std::fs::canonicalize(format!("/sys/class/hidraw/{name}")).ok()?;
+
+    sysfs_path
+        .iter()
+        .filter_map(|component| component.to_str())
+        .find_map(|component| component.split_once(':').map(|(port_path, _)| port_path))
+        .filter(|port_path| port_path.contains('-'))
+        .map(|port_path| port_path.to_owned())
+}
+
 fn device_matches(
+    device: &Device<rusb::Context>,
     device_descriptor: DeviceDescriptor,
     selector: &DebugProbeSelector,
     serial_str: Option<String>,
 ) -> bool {
     if device_descriptor.vendor_id() == selector.vendor_id
         && device_descriptor.product_id() == selector.product_id
+        && selector.matches_usb_port(device)
     {
         if selector.serial_number.is_some() {
             serial_str == selector.serial_number
@@ -308,7 +339,7 @@ pub fn open_device_from_selector(
             // multiple open handles are not allowed on Windows.
             drop(handle);
 
-            if device_matches(d_desc, &selector, sn_str) {
+            if device_matches(&device, d_desc, &selector, sn_str) {
                 hid_device_info = get_cmsisdap_info(&device);
 
                 if hid_device_info.is_some() {
@@ -353,6 +384,10 @@ pub fn open_device_from_selector(
                 device_match &= Some(sn.as_ref()) == info.serial_number();
             }
 
+            if let Some(port_path) = &selector.usb_port_path {
+                device_match &= hid_usb_port_path(info).as_ref() == Some(port_path);
+            }
+
             if let Some(hid_interface) =
                 hid_device_info.as_ref().and_then(|info| info.hid_interface)
             {
@@ -6,6 +6,8 @@ use std::{
     time::{Duration, Instant},
 };
 
+use bitvec::{order::Lsb0, slice::BitSlice, vec::BitVec};
+
 use crate::{
     architecture::{
         arm::{
@@ -13,8 +15,9 @@ use crate::{
             SwoAccess,
         },
         riscv::communication_interface::{RiscvCommunicationInterface, RiscvError},
+        xtensa::communication_interface::{XtensaCommunicationInterface, XtensaError},
     },
-    probe::jlink::bits_to_byte,
+    probe::{jlink::bits_to_byte, ChainParams},
     DebugProbe, DebugProbeError, DebugProbeSelector, WireProtocol,
 };
 
@@ -32,6 +35,9 @@ pub(crate) struct EspUsbJtag {
     /// accesses to the DMI register
     jtag_idle_cycles: u8,
 
+    /// Position of the selected TAP in the JTAG scan chain
+    jtag_chain: ChainParams,
+
     current_ir_reg: u32,
 
     speed_khz: u32,
@@ -45,10 +51,13 @@ impl EspUsbJtag {
     fn read_dr(&mut self, register_bits: usize) -> Result<Vec<u8>, DebugProbeError> {
         tracing::debug!("Read {} bits from DR", register_bits);
 
+        let chain = self.jtag_chain;
+
         let tms_enter_shift = [true, false, false];
 
         // Last bit of data is shifted out when we exit the SHIFT-DR State.
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
+        let tms_shift_out_value =
+            iter::repeat(false).take(chain.drpre + register_bits + chain.drpost - 1);
 
         let tms_enter_idle = [true, true, false];
 
@@ -67,7 +76,8 @@ impl EspUsbJtag {
 
         tracing::trace!("Response: {:?}", response);
 
-        let _remainder = response.split_off(tms_enter_shift.len());
+        // Skip the bits of the TAPs in BYPASS which are closer to TDO
+        let _remainder = response.split_off(tms_enter_shift.len() + chain.drpre);
 
         let mut remaining_bits = register_bits;
 
@@ -107,12 +117,14 @@ impl EspUsbJtag {
             todo!("Proper error for incorrect length");
         }
 
+        let chain = self.jtag_chain;
+
         let tms_enter_ir_shift = [true, true, false, false];
 
         // The last bit will be transmitted when exiting the shift state,
         // so we need to stay in the shift state for one period less than
         // we have bits to transmit.
-        let tms_data = iter::repeat(false).take(len - 1);
+        let tms_data = iter::repeat(false).take(chain.irpre + len + chain.irpost - 1);
 
         let tms_enter_idle = [true, true, false];
 
@@ -132,6 +144,9 @@ impl EspUsbJtag {
 
         tdi.extend_from_slice(&tdi_enter_ir_shift);
 
+        // All other TAPs in the chain are put into BYPASS
+        tdi.extend(iter::repeat(true).take(chain.irpre));
+
         let num_bytes = len / 8;
 
         let num_bits = len - (num_bytes * 8);
@@ -155,6 +170,8 @@ impl EspUsbJtag {
             }
         }
 
+        tdi.extend(iter::repeat(true).take(chain.irpost));
+
         tdi.extend_from_slice(&tdi_enter_idle);
 
         tracing::trace!("tms: {:?}", tms);
@@ -181,10 +198,13 @@ impl EspUsbJtag {
     fn write_dr(&mut self, data: &[u8], register_bits: usize) -> Result<Vec<u8>, DebugProbeError> {
         tracing::debug!("Write DR: {:?}, len={}", data, register_bits);
 
+        let chain = self.jtag_chain;
+
         let tms_enter_shift = [true, false, false];
 
         // Last bit of data is shifted out when we exi the SHIFT-DR State
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
+        let tms_shift_out_value =
+            iter::repeat(false).take(chain.drpre + register_bits + chain.drpost - 1);
 
         let tms_enter_idle = [true, true, false];
 
@@ -204,6 +224,9 @@ impl EspUsbJtag {
 
         tdi.extend_from_slice(&tdi_enter_shift);
 
+        // The TAPs in BYPASS have a single bit DR
+        tdi.extend(iter::repeat(false).take(chain.drpre));
+
         let num_bytes = register_bits / 8;
 
         let num_bits = register_bits - (num_bytes * 8);
@@ -227,6 +250,8 @@ impl EspUsbJtag {
             }
         }
 
+        tdi.extend(iter::repeat(false).take(chain.drpost));
+
         tdi.extend_from_slice(&tdi_enter_idle);
 
         // We need to stay in the idle cycle a bit
@@ -237,7 +262,8 @@ impl EspUsbJtag {
 
         tracing::trace!("Response: {:?}", response);
 
-        let _remainder = response.split_off(tms_enter_shift.len());
+        // Skip the bits of the TAPs in BYPASS which are closer to TDO
+        let _remainder = response.split_off(tms_enter_shift.len() + chain.drpre);
 
         let mut remaining_bits = register_bits;
 
@@ -258,6 +284,34 @@ impl EspUsbJtag {
 
         Ok(result)
     }
+
+    /// Shift `len` bits through the IR or DR registers of the whole chain,
+    /// starting and ending in RUN-TEST/IDLE, and return the bits shifted out.
+    fn shift_raw(&mut self, ir: bool, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        let tms_enter_shift: &[bool] = if ir {
+            &[true, true, false, false]
+        } else {
+            &[true, false, false]
+        };
+
+        let mut tms = tms_enter_shift.to_vec();
+        tms.extend(iter::repeat(false).take(len - 1));
+        tms.extend_from_slice(&[true, true, false]);
+
+        let mut tdi = vec![false; tms_enter_shift.len()];
+        tdi.extend(
+            BitSlice::<u8, Lsb0>::from_slice(data)[..len]
+                .iter()
+                .by_vals(),
+        );
+        tdi.extend_from_slice(&[false, false]);
+
+        let response = self.protocol.jtag_io(tms, tdi, true)?;
+
+        let result: BitVec<u8, Lsb0> = response.skip(tms_enter_shift.len()).take(len).collect();
+
+        Ok(result.into_vec())
+    }
 }
 
 impl JTAGAccess for EspUsbJtag {
@@ -319,6 +373,42 @@ impl JTAGAccess for EspUsbJtag {
     fn get_idle_cycles(&self) -> u8 {
         self.jtag_idle_cycles
     }
+
+    fn tap_reset(&mut self) -> Result<(), DebugProbeError> {
+        // 5 times TMS high, and enter idle state afterwards
+        let tms = vec![true, true, true, true, true, false];
+        let tdi = iter::repeat(false).take(6);
+
+        self.protocol.jtag_io(tms, tdi, false)?;
+
+        // The IR register was reset, so it has to be written again.
+        self.current_ir_reg = u32::MAX;
+
+        Ok(())
+    }
+
+    fn shift_raw_ir(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        self.current_ir_reg = u32::MAX;
+
+        self.shift_raw(true, data, len)
+    }
+
+    fn shift_raw_dr(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        self.shift_raw(false, data, len)
+    }
+
+    fn set_chain_params(&mut self, params: ChainParams) -> Result<(), DebugProbeError> {
+        if params.irlen != 5 {
+            return Err(DebugProbeError::NotImplemented(
+                "JTAG TAPs with an IR length other than 5 bits",
+            ));
+        }
+
+        self.jtag_chain = params;
+        self.current_ir_reg = u32::MAX;
+
+        Ok(())
+    }
 }
 
 impl DebugProbe for EspUsbJtag {
@@ -330,6 +420,7 @@ impl DebugProbe for EspUsbJtag {
         Ok(Box::new(EspUsbJtag {
             protocol,
             jtag_idle_cycles: 0,
+            jtag_chain: ChainParams::default(),
             current_ir_reg: 1,
             speed_khz: 0,
         }))
@@ -428,6 +519,15 @@ impl DebugProbe for EspUsbJtag {
         }
     }
 
+    fn try_get_xtensa_interface(
+        self: Box<Self>,
+    ) -> Result<XtensaCommunicationInterface, (Box<dyn DebugProbe>, XtensaError)> {
+        match XtensaCommunicationInterface::new(self) {
+            Ok(interface) => Ok(interface),
+            Err((probe, err)) => Err((probe.into_probe(), err)),
+        }
+    }
+
     fn get_swo_interface(&self) -> Option<&dyn SwoAccess> {
         // This probe cannot debug ARM targets.
         None
@@ -448,6 +548,11 @@ impl DebugProbe for EspUsbJtag {
         true
     }
 
+    fn has_xtensa_interface(&self) -> bool {
+        // The ESP32-S3 exposes its Xtensa cores through the built-in USB JTAG.
+        true
+    }
+
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
@@ -457,6 +562,10 @@ impl DebugProbe for EspUsbJtag {
         None
     }
 
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        Some(self)
+    }
+
     fn try_get_arm_interface<'probe>(
         self: Box<Self>,
     ) -> Result<Box<dyn UninitializedArmProbe + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)>
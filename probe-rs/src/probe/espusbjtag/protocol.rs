@@ -4,7 +4,8 @@ use std::{fmt::Debug, time::Duration};
 use rusb::{request_type, Context, Device, Direction, TransferType, UsbContext};
 
 use crate::{
-    DebugProbeError, DebugProbeInfo, DebugProbeSelector, DebugProbeType, ProbeCreationError,
+    probe::usb_port_path, DebugProbeError, DebugProbeInfo, DebugProbeSelector, DebugProbeType,
+    ProbeCreationError,
 };
 
 const JTAG_PROTOCOL_CAPABILITIES_VERSION: u8 = 1;
@@ -76,6 +77,7 @@ impl ProtocolHandler {
                 // First match the VID & PID.
                 if selector.vendor_id == descriptor.vendor_id()
                     && selector.product_id == descriptor.product_id()
+                    && selector.matches_usb_port(&device)
                 {
                     // If the VID & PID match, match the serial if one was given.
                     if let Some(serial) = &selector.serial_number {
@@ -583,14 +585,17 @@ pub fn list_espjtag_devices() -> Vec<DebugProbeInfo> {
                         }
                     };
 
-                    Some(DebugProbeInfo::new(
-                        "ESP JTAG".to_string(),
-                        descriptor.vendor_id(),
-                        descriptor.product_id(),
-                        sn_str,
-                        DebugProbeType::EspJtag,
-                        None,
-                    ))
+                    Some(DebugProbeInfo {
+                        usb_port_path: usb_port_path(&device),
+                        ..DebugProbeInfo::new(
+                            "ESP JTAG".to_string(),
+                            descriptor.vendor_id(),
+                            descriptor.product_id(),
+                            sn_str,
+                            DebugProbeType::EspJtag,
+                            None,
+                        )
+                    })
                 })
                 .collect::<Vec<_>>()
         })
@@ -82,8 +82,17 @@ impl Builder {
         }
     }
 
-    pub fn usb_open(mut self, vendor: u16, product: u16) -> Result<Device> {
+    pub fn usb_open(self, vendor: u16, product: u16) -> Result<Device> {
         let result = unsafe { ffi::ftdi_usb_open(self.context, vendor as i32, product as i32) };
+        self.into_device(result)
+    }
+
+    pub fn usb_open_bus_addr(self, bus: u8, address: u8) -> Result<Device> {
+        let result = unsafe { ffi::ftdi_usb_open_bus_addr(self.context, bus, address) };
+        self.into_device(result)
+    }
+
+    fn into_device(mut self, result: i32) -> Result<Device> {
         match result {
             0 => Ok(Device {
                 context: mem::replace(&mut self.context, ptr::null_mut()),
@@ -2,8 +2,9 @@ use crate::architecture::riscv::communication_interface::RiscvError;
 use crate::architecture::{
     arm::communication_interface::UninitializedArmProbe,
     riscv::communication_interface::RiscvCommunicationInterface,
+    xtensa::communication_interface::{XtensaCommunicationInterface, XtensaError},
 };
-use crate::probe::{JTAGAccess, ProbeCreationError};
+use crate::probe::{usb_port_path, ChainParams, JTAGAccess, ProbeCreationError};
 use crate::{
     DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeSelector, DebugProbeType, WireProtocol,
 };
@@ -22,36 +23,42 @@ use self::commands::{JtagCommand, WriteRegisterCommand};
 
 use super::{BatchExecutionError, CommandResult};
 
-#[derive(Debug)]
-struct JtagChainItem {
-    idcode: u32,
-    irlen: usize,
-}
-
-#[derive(Clone, Copy, Debug)]
-pub(super) struct ChainParams {
-    irpre: usize,
-    irpost: usize,
-    drpre: usize,
-    drpost: usize,
-    irlen: usize,
-}
-
 #[derive(Debug)]
 pub struct JtagAdapter {
     device: ftdi::Device,
     chain_params: Option<ChainParams>,
+    /// The instruction currently selected in the IR of the target TAP, if known.
+    current_ir_reg: Option<u32>,
 }
 
 impl JtagAdapter {
-    pub fn open(vid: u16, pid: u16) -> Result<Self, ftdi::Error> {
+    pub fn open(selector: &DebugProbeSelector) -> Result<Self, ftdi::Error> {
         let mut builder = ftdi::Builder::new();
         builder.set_interface(ftdi::Interface::A)?;
-        let device = builder.usb_open(vid, pid)?;
+
+        let device = if selector.usb_port_path.is_some() {
+            // Find the device plugged into the selected port, and open it by its address.
+            let device = rusb::Context::new()
+                .and_then(|ctx| ctx.devices())
+                .map_err(|_| ftdi::Error::EnumerationFailed)?
+                .iter()
+                .find(|device| {
+                    device.device_descriptor().map_or(false, |desc| {
+                        desc.vendor_id() == selector.vendor_id
+                            && desc.product_id() == selector.product_id
+                    }) && selector.matches_usb_port(device)
+                })
+                .ok_or(ftdi::Error::DeviceNotFound)?;
+
+            builder.usb_open_bus_addr(device.bus_number(), device.address())?
+        } else {
+            builder.usb_open(selector.vendor_id, selector.product_id)?
+        };
 
         Ok(Self {
             device,
             chain_params: None,
+            current_ir_reg: None,
         })
     }
 
@@ -207,6 +214,7 @@ impl JtagAdapter {
 
     /// Reset and go to RUN-TEST/IDLE
     pub fn reset(&mut self) -> io::Result<()> {
+        self.current_ir_reg = None;
         self.shift_tms(&[0xff, 0xff, 0xff, 0xff, 0x7f], 40)
     }
 
@@ -230,6 +238,7 @@ impl JtagAdapter {
 
     /// Shift to IR and return to IDLE
     pub fn transfer_ir(&mut self, data: &[u8], bits: usize) -> io::Result<Vec<u8>> {
+        self.current_ir_reg = None;
         self.shift_tms(&[0b0011], 4)?;
         let r = self.tranfer_tdi(data, bits)?;
         self.shift_tms(&[0b01], 2)?;
@@ -244,115 +253,6 @@ impl JtagAdapter {
         Ok(r)
     }
 
-    fn scan(&mut self) -> io::Result<Vec<JtagChainItem>> {
-        let max_device_count = 8;
-
-        self.reset()?;
-
-        let cmd = vec![0xff; max_device_count * 4];
-        let r = self.transfer_dr(&cmd, cmd.len() * 8)?;
-        let mut targets = vec![];
-        for i in 0..max_device_count {
-            let idcode = u32::from_le_bytes(r[i * 4..(i + 1) * 4].try_into().unwrap());
-            if idcode != 0xffffffff {
-                tracing::debug!("tap found: {:08x}", idcode);
-                let target = JtagChainItem { idcode, irlen: 0 };
-                targets.push(target);
-            } else {
-                break;
-            }
-        }
-
-        self.reset()?;
-
-        // Autodetect the targets' IR lengths.
-        //
-        // For many targets, reading the IR right after a reset yields 0b00..001. This allows
-        // autodetecting the IR lengths even when we have multiple targets. For example,
-        // if we read `0b1111111111110001000001` (LSB first) we know the first target in the
-        // chain has an irlen of 6 and the next one has an irlen of 4.
-        //
-        // However, not all targets satisfy this. For example, the esp32c3 shifts out a fixed value
-        // of `0b00101`. This makes the above algorithm to incorrectly detect the IR len as 2.
-        //
-        // Fortunately, we can use a different autodetection algorithm when we only have one target
-        // in the chain, that doesn't rely on the target to shift out a particular value. The key is
-        // the fact that whatever we shift in gets shifted back out, but delayed by the number of bits
-        // in the IR shfit register. So, we shift in lots of `1` bits to fill the shift register with `1`s.
-        // Then we shift in lots of `0` bytes. The output will be something like `0b00000111`, and the
-        // number of ones is the IR length.
-        if targets.len() == 1 {
-            let r = self.transfer_ir(&[0xFF, 0x00], 16)?;
-
-            let irlen = r[1].count_ones() as usize;
-            targets[0].irlen = irlen;
-            tracing::debug!("tap irlen: {}", irlen);
-        } else {
-            let cmd = vec![0xff; max_device_count];
-            let mut r = self.transfer_ir(&cmd, cmd.len() * 8)?;
-
-            let mut ir = 0;
-            let mut irbits = 0;
-            for (i, target) in targets.iter_mut().enumerate() {
-                if (!r.is_empty()) && irbits < 8 {
-                    let byte = r[0];
-                    r.remove(0);
-                    ir |= (byte as u32) << irbits;
-                    irbits += 8;
-                }
-                if ir & 0b11 == 0b01 {
-                    ir &= !1;
-                    let irlen = ir.trailing_zeros();
-                    ir >>= irlen;
-                    irbits -= irlen;
-                    tracing::debug!("tap {} irlen: {}", i, irlen);
-                    target.irlen = irlen as usize;
-                } else {
-                    tracing::debug!("invalid irlen for tap {}", i);
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "Invalid IR sequence during the chain scan",
-                    ));
-                }
-            }
-        }
-
-        Ok(targets)
-    }
-
-    pub fn select_target(&mut self, idcode: u32) -> io::Result<()> {
-        let taps = self.scan()?;
-
-        let mut found = false;
-        let mut params = ChainParams {
-            irpre: 0,
-            irpost: 0,
-            drpre: 0,
-            drpost: 0,
-            irlen: 0,
-        };
-        for tap in taps {
-            if tap.idcode == idcode {
-                params.irlen = tap.irlen;
-                found = true;
-            } else if found {
-                params.irpost += tap.irlen;
-                params.drpost += 1;
-            } else {
-                params.irpre += tap.irlen;
-                params.drpre += 1;
-            }
-        }
-
-        if found {
-            tracing::debug!("Target chain params: {:?}", params);
-            self.chain_params = Some(params);
-            Ok(())
-        } else {
-            Err(io::Error::new(io::ErrorKind::NotFound, "target not found"))
-        }
-    }
-
     fn get_chain_params(&self) -> io::Result<ChainParams> {
         match &self.chain_params {
             Some(params) => Ok(*params),
@@ -378,13 +278,17 @@ impl JtagAdapter {
             ));
         }
 
-        // Write IR register
-        let irbits = params.irpre + params.irlen + params.irpost;
-        assert!(irbits <= 32);
-        let mut ir: u32 = (1 << params.irpre) - 1;
-        ir |= address << params.irpre;
-        ir |= ((1 << params.irpost) - 1) << (params.irpre + params.irlen);
-        self.shift_ir(&ir.to_le_bytes(), irbits)?;
+        // Write IR register, unless the register is already selected. Some TAPs, like the
+        // Xtensa OCD, rely on the IR not being rewritten between consecutive DR scans.
+        if self.current_ir_reg != Some(address) {
+            let irbits = params.irpre + params.irlen + params.irpost;
+            assert!(irbits <= 32);
+            let mut ir: u32 = (1 << params.irpre) - 1;
+            ir |= address << params.irpre;
+            ir |= ((1 << params.irpost) - 1) << (params.irpre + params.irlen);
+            self.shift_ir(&ir.to_le_bytes(), irbits)?;
+            self.current_ir_reg = Some(address);
+        }
 
         let drbits = params.drpre + len_bits + params.drpost;
         let request = if let Some(data_slice) = data {
@@ -438,7 +342,7 @@ impl DebugProbe for FtdiProbe {
             ));
         }
 
-        let adapter = JtagAdapter::open(selector.vendor_id, selector.product_id)
+        let adapter = JtagAdapter::open(&selector)
             .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
 
         let probe = FtdiProbe {
@@ -471,34 +375,6 @@ impl DebugProbe for FtdiProbe {
             .attach()
             .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
 
-        let taps = self
-            .adapter
-            .scan()
-            .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
-        if taps.is_empty() {
-            tracing::warn!("no JTAG taps detected");
-            return Err(DebugProbeError::TargetNotFound);
-        }
-        if taps.len() == 1 {
-            self.adapter
-                .select_target(taps[0].idcode)
-                .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
-        } else {
-            let known_idcodes = [
-                0x1000563d, // GD32VF103
-            ];
-            let idcode = taps
-                .iter()
-                .map(|tap| tap.idcode)
-                .find(|idcode| known_idcodes.iter().any(|v| v == idcode));
-            if let Some(idcode) = idcode {
-                self.adapter
-                    .select_target(idcode)
-                    .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
-            } else {
-                return Err(DebugProbeError::TargetNotFound);
-            }
-        }
         Ok(())
     }
 
@@ -547,10 +423,27 @@ impl DebugProbe for FtdiProbe {
         true
     }
 
+    fn try_get_xtensa_interface(
+        self: Box<Self>,
+    ) -> Result<XtensaCommunicationInterface, (Box<dyn DebugProbe>, XtensaError)> {
+        match XtensaCommunicationInterface::new(self) {
+            Ok(interface) => Ok(interface),
+            Err((probe, err)) => Err((probe.into_probe(), err)),
+        }
+    }
+
+    fn has_xtensa_interface(&self) -> bool {
+        true
+    }
+
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
 
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        Some(self)
+    }
+
     fn try_get_arm_interface<'probe>(
         self: Box<Self>,
     ) -> Result<Box<dyn UninitializedArmProbe + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)>
@@ -619,6 +512,9 @@ impl JTAGAccess for FtdiProbe {
         let mut index_offset = 0;
         let mut results = Vec::<CommandResult>::new();
 
+        // Every command in the batch writes the IR register.
+        self.adapter.current_ir_reg = None;
+
         let chain_params = self.adapter.get_chain_params().map_err(|e| {
             BatchExecutionError::new(
                 crate::Error::Probe(DebugProbeError::ProbeSpecific(Box::new(e))),
@@ -746,6 +642,31 @@ impl JTAGAccess for FtdiProbe {
     fn set_ir_len(&mut self, _len: u32) {
         // The FTDI implementation automatically sets this, so need need to act on this data
     }
+
+    fn tap_reset(&mut self) -> Result<(), DebugProbeError> {
+        self.adapter
+            .reset()
+            .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))
+    }
+
+    fn shift_raw_ir(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        self.adapter
+            .transfer_ir(data, len)
+            .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))
+    }
+
+    fn shift_raw_dr(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        self.adapter
+            .transfer_dr(data, len)
+            .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))
+    }
+
+    fn set_chain_params(&mut self, params: ChainParams) -> Result<(), DebugProbeError> {
+        tracing::debug!("Target chain params: {:?}", params);
+        self.adapter.chain_params = Some(params);
+        self.adapter.current_ir_reg = None;
+        Ok(())
+    }
 }
 
 /// (VendorId, ProductId)
@@ -783,6 +704,7 @@ fn get_device_info(device: &rusb::Device<rusb::Context>) -> Option<DebugProbeInf
         serial_number: sn_str,
         probe_type: DebugProbeType::Ftdi,
         hid_interface: None,
+        usb_port_path: usb_port_path(device),
     })
 }
 
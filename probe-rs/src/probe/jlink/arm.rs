@@ -2,7 +2,7 @@ use std::{iter, time::Duration};
 
 use crate::{
     architecture::arm::{
-        dp::{Abort, Ctrl, RdBuff, DPIDR},
+        dp::{select_multidrop_dp, Abort, Ctrl, RdBuff, DPIDR},
         ArmError, DapError, DpAddress, Pins, PortType, RawDapAccess, Register,
     },
     probe::JTAGAccess,
@@ -926,10 +926,7 @@ impl<Probe: DebugProbe + RawProtocolIo + JTAGAccess + 'static> RawDapAccess for
     fn select_dp(&mut self, dp: DpAddress) -> Result<(), ArmError> {
         match dp {
             DpAddress::Default => Ok(()), // nop
-            DpAddress::Multidrop(_) => Err(DebugProbeError::ProbeSpecific(
-                anyhow::anyhow!("JLink doesn't support multidrop SWD yet").into(),
-            )
-            .into()),
+            DpAddress::Multidrop(targetsel) => select_multidrop_dp(self, targetsel),
         }
     }
 
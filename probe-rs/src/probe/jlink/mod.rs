@@ -1,5 +1,6 @@
 //! Support for J-Link Debug probes
 
+use bitvec::{order::Lsb0, slice::BitSlice, vec::BitVec};
 use jaylink::{Capability, Interface, JayLink, SpeedConfig, SwoMode};
 
 use std::convert::{TryFrom, TryInto};
@@ -8,6 +9,9 @@ use std::time::{Duration, Instant};
 
 use crate::architecture::arm::{ArmError, RawDapAccess};
 use crate::architecture::riscv::communication_interface::RiscvError;
+use crate::architecture::xtensa::communication_interface::{
+    XtensaCommunicationInterface, XtensaError,
+};
 use crate::{
     architecture::{
         arm::{
@@ -17,7 +21,8 @@ use crate::{
         riscv::communication_interface::RiscvCommunicationInterface,
     },
     probe::{
-        DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeType, JTAGAccess, WireProtocol,
+        usb_port_path_by_address, ChainParams, DebugProbe, DebugProbeError, DebugProbeInfo,
+        DebugProbeType, JTAGAccess, WireProtocol,
     },
     DebugProbeSelector,
 };
@@ -40,6 +45,9 @@ pub(crate) struct JLink {
     // JTAG IR register length
     ir_len: usize,
 
+    /// Position of the selected TAP in the JTAG scan chain
+    jtag_chain: ChainParams,
+
     /// Currently selected protocol
     protocol: Option<WireProtocol>,
 
@@ -102,10 +110,13 @@ impl JLink {
     fn read_dr(&mut self, register_bits: usize) -> Result<Vec<u8>, DebugProbeError> {
         tracing::debug!("Read {} bits from DR", register_bits);
 
+        let chain = self.jtag_chain;
+
         let tms_enter_shift = [true, false, false];
 
         // Last bit of data is shifted out when we exi the SHIFT-DR State
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
+        let tms_shift_out_value =
+            iter::repeat(false).take(chain.drpre + register_bits + chain.drpost - 1);
 
         let tms_enter_idle = [true, true, false];
 
@@ -124,7 +135,8 @@ impl JLink {
 
         tracing::trace!("Response: {:?}", response);
 
-        let _remainder = response.split_off(tms_enter_shift.len());
+        // Skip the bits of the TAPs in BYPASS which are closer to TDO
+        let _remainder = response.split_off(tms_enter_shift.len() + chain.drpre);
 
         let mut remaining_bits = register_bits;
 
@@ -164,12 +176,14 @@ impl JLink {
             todo!("Proper error for incorrect length");
         }
 
+        let chain = self.jtag_chain;
+
         let tms_enter_ir_shift = [true, true, false, false];
 
         // The last bit will be transmitted when exiting the shift state,
         // so we need to stay in the shift stay for one period less than
         // we have bits to transmit
-        let tms_data = iter::repeat(false).take(len - 1);
+        let tms_data = iter::repeat(false).take(chain.irpre + len + chain.irpost - 1);
 
         let tms_enter_idle = [true, true, false];
 
@@ -189,6 +203,9 @@ impl JLink {
 
         tdi.extend_from_slice(&tdi_enter_ir_shift);
 
+        // All other TAPs in the chain are put into BYPASS
+        tdi.extend(iter::repeat(true).take(chain.irpre));
+
         let num_bytes = len / 8;
 
         let num_bits = len - (num_bytes * 8);
@@ -212,6 +229,8 @@ impl JLink {
             }
         }
 
+        tdi.extend(iter::repeat(true).take(chain.irpost));
+
         tdi.extend_from_slice(&tdi_enter_idle);
 
         tracing::trace!("tms: {:?}", tms);
@@ -237,10 +256,13 @@ impl JLink {
     fn write_dr(&mut self, data: &[u8], register_bits: usize) -> Result<Vec<u8>, DebugProbeError> {
         tracing::debug!("Write DR: {:?}, len={}", data, register_bits);
 
+        let chain = self.jtag_chain;
+
         let tms_enter_shift = [true, false, false];
 
         // Last bit of data is shifted out when we exi the SHIFT-DR State
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
+        let tms_shift_out_value =
+            iter::repeat(false).take(chain.drpre + register_bits + chain.drpost - 1);
 
         let tms_enter_idle = [true, true, false];
 
@@ -260,6 +282,9 @@ impl JLink {
 
         tdi.extend_from_slice(&tdi_enter_shift);
 
+        // The TAPs in BYPASS have a single bit DR
+        tdi.extend(iter::repeat(false).take(chain.drpre));
+
         let num_bytes = register_bits / 8;
 
         let num_bits = register_bits - (num_bytes * 8);
@@ -283,6 +308,8 @@ impl JLink {
             }
         }
 
+        tdi.extend(iter::repeat(false).take(chain.drpost));
+
         tdi.extend_from_slice(&tdi_enter_idle);
 
         // We need to stay in the idle cycle a bit
@@ -293,7 +320,8 @@ impl JLink {
 
         tracing::trace!("Response: {:?}", response);
 
-        let _remainder = response.split_off(tms_enter_shift.len());
+        // Skip the bits of the TAPs in BYPASS which are closer to TDO
+        let _remainder = response.split_off(tms_enter_shift.len() + chain.drpre);
 
         let mut remaining_bits = register_bits;
 
@@ -314,6 +342,36 @@ impl JLink {
 
         Ok(result)
     }
+
+    /// Shift `len` bits through the IR or DR registers of the whole chain,
+    /// starting and ending in RUN-TEST/IDLE, and return the bits shifted out.
+    fn shift_raw(&mut self, ir: bool, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        let tms_enter_shift: &[bool] = if ir {
+            &[true, true, false, false]
+        } else {
+            &[true, false, false]
+        };
+
+        let mut tms = tms_enter_shift.to_vec();
+        tms.extend(iter::repeat(false).take(len - 1));
+        tms.extend_from_slice(&[true, true, false]);
+
+        let mut tdi = vec![false; tms_enter_shift.len()];
+        tdi.extend(
+            BitSlice::<u8, Lsb0>::from_slice(data)[..len]
+                .iter()
+                .by_vals(),
+        );
+        tdi.extend_from_slice(&[false, false]);
+
+        let mut response = self.handle.jtag_io(tms, tdi)?;
+
+        let _remainder = response.split_off(tms_enter_shift.len());
+
+        let result: BitVec<u8, Lsb0> = response.split_off(len).collect();
+
+        Ok(result.into_vec())
+    }
 }
 
 impl DebugProbe for JLink {
@@ -323,7 +381,14 @@ impl DebugProbe for JLink {
         let selector = selector.into();
         let mut jlinks = jaylink::scan_usb()?
             .filter_map(|usb_info| {
-                if usb_info.vid() == selector.vendor_id && usb_info.pid() == selector.product_id {
+                let port_matches = selector.usb_port_path.is_none()
+                    || usb_port_path_by_address(usb_info.bus_number(), usb_info.address())
+                        == selector.usb_port_path;
+
+                if usb_info.vid() == selector.vendor_id
+                    && usb_info.pid() == selector.product_id
+                    && port_matches
+                {
                     let device = usb_info.open();
                     if let Some(serial_number) = selector.serial_number.as_deref() {
                         if device
@@ -394,6 +459,7 @@ impl DebugProbe for JLink {
             supported_protocols,
             jtag_idle_cycles: 0,
             ir_len: 0,
+            jtag_chain: ChainParams::default(),
             protocol: None,
             current_ir_reg: 1,
             speed_khz: 0,
@@ -597,6 +663,26 @@ impl DebugProbe for JLink {
         self.supported_protocols.contains(&WireProtocol::Jtag)
     }
 
+    fn try_get_xtensa_interface(
+        self: Box<Self>,
+    ) -> Result<XtensaCommunicationInterface, (Box<dyn DebugProbe>, XtensaError)> {
+        if self.supported_protocols.contains(&WireProtocol::Jtag) {
+            match XtensaCommunicationInterface::new(self) {
+                Ok(interface) => Ok(interface),
+                Err((probe, err)) => Err((probe.into_probe(), err)),
+            }
+        } else {
+            Err((
+                RawDapAccess::into_probe(self),
+                DebugProbeError::InterfaceNotAvailable("JTAG").into(),
+            ))
+        }
+    }
+
+    fn has_xtensa_interface(&self) -> bool {
+        self.supported_protocols.contains(&WireProtocol::Jtag)
+    }
+
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
@@ -605,6 +691,10 @@ impl DebugProbe for JLink {
         Some(self)
     }
 
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        Some(self)
+    }
+
     fn try_get_arm_interface<'probe>(
         self: Box<Self>,
     ) -> Result<Box<dyn UninitializedArmProbe + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)>
@@ -677,6 +767,37 @@ impl JTAGAccess for JLink {
     fn get_idle_cycles(&self) -> u8 {
         self.jtag_idle_cycles
     }
+
+    fn tap_reset(&mut self) -> Result<(), DebugProbeError> {
+        // 5 times TMS high, and enter idle state afterwards
+        let tms = vec![true, true, true, true, true, false];
+        let tdi = iter::repeat(false).take(6);
+
+        self.handle.jtag_io(tms, tdi)?;
+
+        // The IR register was reset, so it has to be written again.
+        self.current_ir_reg = u32::MAX;
+
+        Ok(())
+    }
+
+    fn shift_raw_ir(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        self.current_ir_reg = u32::MAX;
+
+        self.shift_raw(true, data, len)
+    }
+
+    fn shift_raw_dr(&mut self, data: &[u8], len: usize) -> Result<Vec<u8>, DebugProbeError> {
+        self.shift_raw(false, data, len)
+    }
+
+    fn set_chain_params(&mut self, params: ChainParams) -> Result<(), DebugProbeError> {
+        self.jtag_chain = params;
+        self.ir_len = params.irlen;
+        self.current_ir_reg = u32::MAX;
+
+        Ok(())
+    }
 }
 
 impl DapProbe for JLink {}
@@ -747,6 +868,8 @@ pub(crate) fn list_jlink_devices() -> Vec<DebugProbeInfo> {
             .map(|device_info| {
                 let vid = device_info.vid();
                 let pid = device_info.pid();
+                let port_path =
+                    usb_port_path_by_address(device_info.bus_number(), device_info.address());
                 let (serial, product) = if let Ok(device) = device_info.open() {
                     let serial = device.serial_string();
                     let serial = if serial.is_empty() {
@@ -764,17 +887,20 @@ pub(crate) fn list_jlink_devices() -> Vec<DebugProbeInfo> {
                 } else {
                     (None, None)
                 };
-                DebugProbeInfo::new(
-                    format!(
-                        "J-Link{}",
-                        product.map(|p| format!(" ({p})")).unwrap_or_default()
-                    ),
-                    vid,
-                    pid,
-                    serial,
-                    DebugProbeType::JLink,
-                    None,
-                )
+                DebugProbeInfo {
+                    usb_port_path: port_path,
+                    ..DebugProbeInfo::new(
+                        format!(
+                            "J-Link{}",
+                            product.map(|p| format!(" ({p})")).unwrap_or_default()
+                        ),
+                        vid,
+                        pid,
+                        serial,
+                        DebugProbeType::JLink,
+                        None,
+                    )
+                }
             })
             .collect(),
         Err(_) => Vec::new(),
@@ -1090,6 +1090,8 @@ pub(crate) enum StlinkError {
     ManchesterSwoNotSupported,
     #[error("Multidrop SWD not supported")]
     MultidropNotSupported,
+    #[error("ADIv6 access ports not supported")]
+    AdiV6NotSupported,
     #[error("Unaligned")]
     UnalignedAddress,
 }
@@ -1209,11 +1211,9 @@ impl DapAccess for StlinkArmDebug {
     }
 
     fn read_raw_ap_register(&mut self, ap: ApAddress, address: u8) -> Result<u32, ArmError> {
-        if ap.dp != DpAddress::Default {
-            return Err(DebugProbeError::from(StlinkError::MultidropNotSupported).into());
-        }
-
-        let value = self.probe.read_register(ap.ap as u16, address)?;
+        let value = self
+            .probe
+            .read_register(u16::from(ap_index(ap)?), address)?;
 
         Ok(value)
     }
@@ -1224,11 +1224,8 @@ impl DapAccess for StlinkArmDebug {
         address: u8,
         value: u32,
     ) -> Result<(), ArmError> {
-        if ap.dp != DpAddress::Default {
-            return Err(DebugProbeError::from(StlinkError::MultidropNotSupported).into());
-        }
-
-        self.probe.write_register(ap.ap as u16, address, value)?;
+        self.probe
+            .write_register(u16::from(ap_index(ap)?), address, value)?;
 
         Ok(())
     }
@@ -1239,9 +1236,12 @@ impl ArmProbeInterface for StlinkArmDebug {
         &mut self,
         access_port: MemoryAp,
     ) -> Result<Box<dyn ArmProbe + '_>, ArmError> {
+        let ap_index = ap_index(access_port.ap_address())?;
+
         let interface = StLinkMemoryInterface {
             probe: self,
             current_ap: access_port,
+            ap_index,
         };
 
         Ok(Box::new(interface) as _)
@@ -1252,11 +1252,8 @@ impl ArmProbeInterface for StlinkArmDebug {
         access_port: crate::architecture::arm::ap::GenericAp,
     ) -> Result<&crate::architecture::arm::communication_interface::ApInformation, ArmError> {
         let addr = access_port.ap_address();
-        if addr.dp != DpAddress::Default {
-            return Err(DebugProbeError::from(StlinkError::MultidropNotSupported).into());
-        }
 
-        match self.ap_information.get(addr.ap as usize) {
+        match self.ap_information.get(usize::from(ap_index(addr)?)) {
             Some(res) => Ok(res),
             None => Err(ArmError::ApDoesNotExist(addr)),
         }
@@ -1344,6 +1341,8 @@ impl SwoAccess for StlinkArmDebug {
 struct StLinkMemoryInterface<'probe> {
     probe: &'probe mut StlinkArmDebug,
     current_ap: MemoryAp,
+    /// Index of `current_ap`, as used by the ST-Link commands.
+    ap_index: u8,
 }
 
 impl SwdSequence for StLinkMemoryInterface<'_> {
@@ -1372,11 +1371,9 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
         for (i, d) in data.iter_mut().enumerate() {
             let mut buff = vec![0u8; 8];
 
-            self.probe.probe.read_mem_32bit(
-                address + (i * 8) as u32,
-                &mut buff,
-                self.current_ap.ap_address().ap,
-            )?;
+            self.probe
+                .probe
+                .read_mem_32bit(address + (i * 8) as u32, &mut buff, self.ap_index)?;
 
             *d = u64::from_le_bytes(buff.try_into().unwrap());
         }
@@ -1394,7 +1391,7 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
             self.probe.probe.read_mem_32bit(
                 address + (index * STLINK_MAX_READ_LEN) as u32,
                 &mut buff,
-                self.current_ap.ap_address().ap,
+                self.ap_index,
             )?;
 
             for (index, word) in buff.chunks_exact(4).enumerate() {
@@ -1423,7 +1420,7 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
             chunk.copy_from_slice(&self.probe.probe.read_mem_8bit(
                 address + (index * chunk_size) as u32,
                 chunk.len() as u16,
-                self.current_ap.ap_address().ap,
+                self.ap_index,
             )?);
         }
 
@@ -1447,7 +1444,7 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
             self.probe.probe.write_mem_32bit(
                 address + (index * STLINK_MAX_WRITE_LEN) as u32,
                 chunk,
-                self.current_ap.ap_address().ap,
+                self.ap_index,
             )?;
         }
 
@@ -1471,7 +1468,7 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
             self.probe.probe.write_mem_32bit(
                 address + (index * STLINK_MAX_WRITE_LEN) as u32,
                 chunk,
-                self.current_ap.ap_address().ap,
+                self.ap_index,
             )?;
         }
 
@@ -1495,7 +1492,7 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
             tracing::trace!("write_8: small - direct 8 bit write to {:08x}", address);
             self.probe
                 .probe
-                .write_mem_8bit(address, data, self.current_ap.ap_address().ap)?;
+                .write_mem_8bit(address, data, self.ap_index)?;
         } else {
             // Handle unaligned data in the beginning.
             let bytes_beginning = if address % 4 == 0 {
@@ -1515,7 +1512,7 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
                 self.probe.probe.write_mem_8bit(
                     current_address,
                     &data[..bytes_beginning],
-                    self.current_ap.ap_address().ap,
+                    self.ap_index,
                 )?;
 
                 current_address += bytes_beginning as u32;
@@ -1539,7 +1536,7 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
                 self.probe.probe.write_mem_32bit(
                     current_address + (index * STLINK_MAX_WRITE_LEN) as u32,
                     chunk,
-                    self.current_ap.ap_address().ap,
+                    self.ap_index,
                 )?;
             }
 
@@ -1553,11 +1550,9 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
                     bytes_beginning,
                     current_address,
                 );
-                self.probe.probe.write_mem_8bit(
-                    current_address,
-                    remaining_bytes,
-                    self.current_ap.ap_address().ap,
-                )?;
+                self.probe
+                    .probe
+                    .write_mem_8bit(current_address, remaining_bytes, self.ap_index)?;
             }
         }
         Ok(())
@@ -1587,6 +1582,19 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
     }
 }
 
+/// The index of an access port, which is how the ST-Link addresses it.
+///
+/// The ST-Link only supports ADIv5 access ports on the default debug port.
+fn ap_index(ap: ApAddress) -> Result<u8, ArmError> {
+    if ap.dp != DpAddress::Default {
+        return Err(DebugProbeError::from(StlinkError::MultidropNotSupported).into());
+    }
+
+    ap.ap
+        .index()
+        .ok_or_else(|| DebugProbeError::from(StlinkError::AdiV6NotSupported).into())
+}
+
 fn is_wait_error(e: &DebugProbeError) -> bool {
     if let DebugProbeError::ProbeSpecific(e) = e {
         matches!(
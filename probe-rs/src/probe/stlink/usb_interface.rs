@@ -117,6 +117,7 @@ impl StLinkUsbDevice {
                 // First match the VID & PID.
                 if selector.vendor_id == descriptor.vendor_id()
                     && selector.product_id == descriptor.product_id()
+                    && selector.matches_usb_port(&device)
                 {
                     // If the VID & PID match, match the serial if one was given.
                     if let Some(serial) = &selector.serial_number {
@@ -0,0 +1,747 @@
+//! Host side of semihosting.
+//!
+//! Semihosting allows a program running on the target to use the I/O facilities of the host.
+//! The target halts the core with a special trap instruction, after which the debugger services
+//! the request and resumes the core. The available operations are described in the
+//! [semihosting specification](https://github.com/ARM-software/abi-aa/blob/main/semihosting/semihosting.rst).
+//!
+//! On ARM cores, the trap is `BKPT 0xAB` (Cortex-M) or `HLT 0xF000` (Cortex-A). RISC-V uses the same
+//! operations, trapped by an `ebreak` instruction surrounded by the magic `slli x0, x0, 0x1f` and
+//! `srai x0, x0, 7` instructions.
+
+use crate::{
+    BreakpointCause, Core, CoreStatus, Error, HaltReason, InstructionSet, MemoryInterface,
+    RegisterValue,
+};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// `BKPT 0xAB` in the T32 instruction set.
+const T32_BKPT: u16 = 0xbeab;
+/// `HLT 0x3C` in the T32 instruction set.
+const T32_HLT: u16 = 0xbabc;
+/// `BKPT 0xAB` in the A32 instruction set.
+const A32_BKPT: u32 = 0xe120_0a7b;
+/// `HLT 0xF000` in the A32 instruction set.
+const A32_HLT: u32 = 0xe10f_0070;
+/// `HLT 0xF000` in the A64 instruction set.
+const A64_HLT: u32 = 0xd45e_0000;
+/// The RISC-V trap sequence: `slli x0, x0, 0x1f`, `ebreak`, `srai x0, x0, 7`.
+const RISCV_TRAP: [u32; 3] = [0x01f0_1013, 0x0010_0073, 0x4070_5013];
+
+const SYS_OPEN: u32 = 0x01;
+const SYS_CLOSE: u32 = 0x02;
+const SYS_WRITEC: u32 = 0x03;
+const SYS_WRITE0: u32 = 0x04;
+const SYS_WRITE: u32 = 0x05;
+const SYS_READ: u32 = 0x06;
+const SYS_ISTTY: u32 = 0x09;
+const SYS_SEEK: u32 = 0x0a;
+const SYS_FLEN: u32 = 0x0c;
+const SYS_CLOCK: u32 = 0x10;
+const SYS_TIME: u32 = 0x11;
+const SYS_ERRNO: u32 = 0x13;
+const SYS_GET_CMDLINE: u32 = 0x15;
+const SYS_EXIT: u32 = 0x18;
+const SYS_EXIT_EXTENDED: u32 = 0x20;
+
+/// Handle of the console input, opened as `:tt` for reading.
+const HANDLE_STDIN: u32 = 1;
+/// Handle of the console output, opened as `:tt` for writing.
+const HANDLE_STDOUT: u32 = 2;
+/// Handle of the console error output, opened as `:tt` for appending.
+const HANDLE_STDERR: u32 = 3;
+
+const EBADF: i32 = 9;
+const EACCES: i32 = 13;
+const EINVAL: i32 = 22;
+const ENAMETOOLONG: i32 = 36;
+
+/// The longest file name accepted by `SYS_OPEN`.
+const MAX_NAME_LENGTH: u64 = 4096;
+/// The most data transferred by a single `SYS_WRITE` or `SYS_READ`.
+///
+/// The lengths are read from the target, so they are limited to avoid huge allocations on the
+/// host. Longer requests are partial transfers, which the target has to continue.
+const MAX_TRANSFER_LENGTH: u64 = 64 * 1024;
+
+/// Configuration of the host side of semihosting.
+#[derive(Debug, Clone, Default)]
+pub struct SemihostingOptions {
+    /// Directory in which the target can open files, using relative paths.
+    ///
+    /// If this is not set, the target can only access the console.
+    pub root: Option<PathBuf>,
+    /// The command line returned to the target by `SYS_GET_CMDLINE`.
+    pub cmdline: String,
+}
+
+/// The outcome of servicing a semihosting request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SemihostingEvent {
+    /// The request was serviced, and the core continues after the trap.
+    Continued {
+        /// Data the target wrote to the console output.
+        stdout: Vec<u8>,
+        /// Data the target wrote to the console error output.
+        stderr: Vec<u8>,
+    },
+    /// The target exited. The core is left halted.
+    Exited(SemihostingExit),
+}
+
+/// The exit status reported by the target using `SYS_EXIT` or `SYS_EXIT_EXTENDED`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemihostingExit {
+    /// The reason for the exit, e.g. [`SemihostingExit::APPLICATION_EXIT`].
+    pub reason: u32,
+    /// The exit status of the application, if it was reported.
+    pub subcode: Option<u32>,
+}
+
+impl SemihostingExit {
+    /// The reason code `ADP_Stopped_ApplicationExit`, used for a regular exit of the application.
+    pub const APPLICATION_EXIT: u32 = 0x20026;
+
+    /// The exit code of the application, as reported to a test runner.
+    ///
+    /// A regular exit reports the exit status of the application, or 0 if there is none.
+    /// All other reasons are reported as 1.
+    pub fn exit_code(&self) -> i32 {
+        match self.reason {
+            Self::APPLICATION_EXIT => self.subcode.unwrap_or(0) as i32,
+            _ => 1,
+        }
+    }
+}
+
+/// A semihosting trap the core is halted on.
+struct Trap {
+    /// The address at which execution continues after the request.
+    resume_address: u64,
+    /// Whether the parameters are 64 bits wide.
+    wide: bool,
+}
+
+/// The result of a single semihosting operation.
+enum Outcome {
+    /// The value returned to the target.
+    Return(i64),
+    /// The target exited.
+    Exit(SemihostingExit),
+}
+
+/// Services the semihosting requests of a core.
+pub struct Semihosting {
+    options: SemihostingOptions,
+    files: HashMap<u32, File>,
+    next_handle: u32,
+    errno: i32,
+    start: Instant,
+    stdin: Option<Box<dyn Read + Send>>,
+}
+
+impl std::fmt::Debug for Semihosting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Semihosting")
+            .field("options", &self.options)
+            .field("open_files", &self.files.len())
+            .finish()
+    }
+}
+
+impl Semihosting {
+    /// Create a new semihosting handler.
+    pub fn new(options: SemihostingOptions) -> Self {
+        Self {
+            options,
+            files: HashMap::new(),
+            next_handle: HANDLE_STDERR + 1,
+            errno: 0,
+            start: Instant::now(),
+            stdin: None,
+        }
+    }
+
+    /// Use `stdin` as console input of the target.
+    ///
+    /// Without console input, reads from the console report the end of the file.
+    pub fn with_console_input(mut self, stdin: impl Read + Send + 'static) -> Self {
+        self.stdin = Some(Box::new(stdin));
+        self
+    }
+
+    /// Service a semihosting request, if the core is halted on one.
+    ///
+    /// After a request is serviced, the core is resumed, unless the target exited.
+    /// Returns `None` if the core is not halted on a semihosting trap.
+    pub fn handle(&mut self, core: &mut Core) -> Result<Option<SemihostingEvent>, Error> {
+        let event = self.handle_halted(core)?;
+
+        if let Some(SemihostingEvent::Continued { .. }) = event {
+            core.run()?;
+        }
+
+        Ok(event)
+    }
+
+    /// Service a semihosting request, if the core is halted on one, and leave the core halted.
+    ///
+    /// The core continues after the trap once it is resumed. This allows resuming the core
+    /// together with the other cores of its halt group, using [`Session::resume_cores`](crate::Session::resume_cores).
+    pub fn handle_halted(&mut self, core: &mut Core) -> Result<Option<SemihostingEvent>, Error> {
+        let trap = match find_trap(core)? {
+            Some(trap) => trap,
+            None => return Ok(None),
+        };
+
+        let registers = core.registers();
+        let operation: u64 = core.read_core_reg(registers.argument_register(0))?;
+        let parameter: u64 = core.read_core_reg(registers.argument_register(1))?;
+
+        tracing::debug!(
+            "Semihosting operation {:#x} with parameter {:#x}",
+            operation,
+            parameter
+        );
+
+        let mut request = Request {
+            core,
+            wide: trap.wide,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        };
+
+        match self.service(&mut request, operation as u32, parameter)? {
+            Outcome::Return(value) => {
+                let Request {
+                    core,
+                    wide,
+                    stdout,
+                    stderr,
+                } = request;
+
+                let value = if wide {
+                    RegisterValue::U64(value as u64)
+                } else {
+                    RegisterValue::U32(value as u32)
+                };
+
+                core.write_core_reg(registers.result_register(0).into(), value)?;
+                core.write_core_reg(registers.program_counter().into(), trap.resume_address)?;
+
+                Ok(Some(SemihostingEvent::Continued { stdout, stderr }))
+            }
+            Outcome::Exit(exit) => {
+                tracing::info!("Target exited: {:?}", exit);
+
+                Ok(Some(SemihostingEvent::Exited(exit)))
+            }
+        }
+    }
+
+    fn service(
+        &mut self,
+        request: &mut Request,
+        operation: u32,
+        parameter: u64,
+    ) -> Result<Outcome, Error> {
+        let value = match operation {
+            SYS_OPEN => {
+                let name = request.read_field(parameter, 0)?;
+                let mode = request.read_field(parameter, 1)?;
+                let length = request.read_field(parameter, 2)?;
+
+                if length > MAX_NAME_LENGTH {
+                    self.fail(ENAMETOOLONG)
+                } else {
+                    let mut name_bytes = vec![0; length as usize];
+                    request.core.read(name, &mut name_bytes)?;
+
+                    self.open(&String::from_utf8_lossy(&name_bytes), mode)
+                }
+            }
+            SYS_CLOSE => {
+                let handle = request.read_field(parameter, 0)? as u32;
+
+                if handle <= HANDLE_STDERR || self.files.remove(&handle).is_some() {
+                    0
+                } else {
+                    self.fail(EBADF)
+                }
+            }
+            SYS_WRITEC => {
+                let mut byte = [0];
+                request.core.read(parameter, &mut byte)?;
+                request.stdout.extend_from_slice(&byte);
+
+                0
+            }
+            SYS_WRITE0 => {
+                let string = request.read_string(parameter)?;
+                request.stdout.extend_from_slice(&string);
+
+                0
+            }
+            SYS_WRITE => {
+                let handle = request.read_field(parameter, 0)? as u32;
+                let buffer = request.read_field(parameter, 1)?;
+                let length = request.read_field(parameter, 2)?;
+
+                let mut data = vec![0; transfer_length(length)];
+                request.core.read(buffer, &mut data)?;
+
+                // Anything beyond the transferred data is not written.
+                self.write(request, handle, &data) + (length - data.len() as u64) as i64
+            }
+            SYS_READ => {
+                let handle = request.read_field(parameter, 0)? as u32;
+                let buffer = request.read_field(parameter, 1)?;
+                let length = request.read_field(parameter, 2)?;
+
+                let mut data = vec![0; transfer_length(length)];
+
+                match self.read(handle, &mut data) {
+                    Ok(count) => {
+                        request.core.write_8(buffer, &data[..count])?;
+
+                        // The number of bytes which were not read is returned.
+                        (length - count as u64) as i64
+                    }
+                    Err(errno) => self.fail(errno),
+                }
+            }
+            SYS_ISTTY => {
+                let handle = request.read_field(parameter, 0)? as u32;
+
+                if handle <= HANDLE_STDERR {
+                    1
+                } else if self.files.contains_key(&handle) {
+                    0
+                } else {
+                    self.fail(EBADF)
+                }
+            }
+            SYS_SEEK => {
+                let handle = request.read_field(parameter, 0)? as u32;
+                let position = request.read_field(parameter, 1)?;
+
+                match self.files.get_mut(&handle) {
+                    Some(file) => match file.seek(SeekFrom::Start(position)) {
+                        Ok(_) => 0,
+                        Err(e) => self.fail_io(&e),
+                    },
+                    None => self.fail(EBADF),
+                }
+            }
+            SYS_FLEN => {
+                let handle = request.read_field(parameter, 0)? as u32;
+
+                match self.files.get(&handle).map(|file| file.metadata()) {
+                    Some(Ok(metadata)) => metadata.len() as i64,
+                    Some(Err(e)) => self.fail_io(&e),
+                    None => self.fail(EBADF),
+                }
+            }
+            SYS_CLOCK => (self.start.elapsed().as_millis() / 10) as i64,
+            SYS_TIME => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs() as i64)
+                .unwrap_or_default(),
+            SYS_ERRNO => self.errno as i64,
+            SYS_GET_CMDLINE => {
+                let buffer = request.read_field(parameter, 0)?;
+                let length = request.read_field(parameter, 1)?;
+
+                let mut cmdline = self.options.cmdline.as_bytes().to_vec();
+
+                if cmdline.len() < length as usize {
+                    let cmdline_length = cmdline.len() as u64;
+                    cmdline.push(0);
+
+                    request.core.write_8(buffer, &cmdline)?;
+                    request.write_field(parameter, 1, cmdline_length)?;
+
+                    0
+                } else {
+                    self.fail(EINVAL)
+                }
+            }
+            SYS_EXIT => {
+                // On 64-bit targets, the parameter points to the reason and the exit status,
+                // on 32-bit targets it is the reason itself.
+                let exit = if request.wide {
+                    SemihostingExit {
+                        reason: request.read_field(parameter, 0)? as u32,
+                        subcode: Some(request.read_field(parameter, 1)? as u32),
+                    }
+                } else {
+                    SemihostingExit {
+                        reason: parameter as u32,
+                        subcode: None,
+                    }
+                };
+
+                return Ok(Outcome::Exit(exit));
+            }
+            SYS_EXIT_EXTENDED => {
+                return Ok(Outcome::Exit(SemihostingExit {
+                    reason: request.read_field(parameter, 0)? as u32,
+                    subcode: Some(request.read_field(parameter, 1)? as u32),
+                }));
+            }
+            other => {
+                tracing::warn!("Unsupported semihosting operation {:#x}", other);
+
+                self.fail(EINVAL)
+            }
+        };
+
+        Ok(Outcome::Return(value))
+    }
+
+    /// Open a file, and return its handle.
+    ///
+    /// The special name `:tt` refers to the console.
+    fn open(&mut self, name: &str, mode: u64) -> i64 {
+        if name == ":tt" {
+            return match mode {
+                0..=3 => HANDLE_STDIN,
+                4..=7 => HANDLE_STDOUT,
+                _ => HANDLE_STDERR,
+            } as i64;
+        }
+
+        let path = match self.sandboxed_path(name) {
+            Some(path) => path,
+            None => {
+                tracing::warn!(
+                    "The target tried to open '{}', which is not accessible",
+                    name
+                );
+                return self.fail(EACCES);
+            }
+        };
+
+        // The modes correspond to the ones of `fopen`, with and without 'b'.
+        let mut options = OpenOptions::new();
+        match mode {
+            0 | 1 => options.read(true),
+            2 | 3 => options.read(true).write(true),
+            4 | 5 => options.write(true).create(true).truncate(true),
+            6 | 7 => options.read(true).write(true).create(true).truncate(true),
+            8 | 9 => options.append(true).create(true),
+            10 | 11 => options.read(true).append(true).create(true),
+            _ => return self.fail(EINVAL),
+        };
+
+        match options.open(&path) {
+            Ok(file) => {
+                let handle = self.next_handle;
+                self.next_handle += 1;
+                self.files.insert(handle, file);
+
+                tracing::debug!("Opened {} as handle {}", path.display(), handle);
+
+                handle as i64
+            }
+            Err(e) => self.fail_io(&e),
+        }
+    }
+
+    /// Resolve a path relative to the root directory.
+    ///
+    /// Paths which could leave the root directory are rejected.
+    fn sandboxed_path(&self, name: &str) -> Option<PathBuf> {
+        let root = self.options.root.as_ref()?;
+        let path = Path::new(name);
+
+        path.components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            .then(|| root.join(path))
+    }
+
+    /// Write to a file or the console, and return the number of bytes which were not written.
+    fn write(&mut self, request: &mut Request, handle: u32, data: &[u8]) -> i64 {
+        match handle {
+            HANDLE_STDOUT => request.stdout.extend_from_slice(data),
+            HANDLE_STDERR => request.stderr.extend_from_slice(data),
+            _ => match self.files.get_mut(&handle) {
+                Some(file) => {
+                    if let Err(e) = file.write_all(data) {
+                        self.fail_io(&e);
+                        return data.len() as i64;
+                    }
+                }
+                None => {
+                    self.fail(EBADF);
+                    return data.len() as i64;
+                }
+            },
+        }
+
+        0
+    }
+
+    /// Read from a file or the console, and return the number of bytes read.
+    fn read(&mut self, handle: u32, data: &mut [u8]) -> Result<usize, i32> {
+        let reader: &mut dyn Read = match handle {
+            HANDLE_STDIN => match &mut self.stdin {
+                Some(stdin) => stdin,
+                None => return Ok(0),
+            },
+            _ => match self.files.get_mut(&handle) {
+                Some(file) => file,
+                None => return Err(EBADF),
+            },
+        };
+
+        let mut count = 0;
+
+        // Files are read until the buffer is full, the console only until input is available.
+        while count < data.len() {
+            match reader.read(&mut data[count..]) {
+                Ok(0) => break,
+                Ok(n) => count += n,
+                Err(e) => return Err(e.raw_os_error().unwrap_or(EINVAL)),
+            }
+
+            if handle == HANDLE_STDIN {
+                break;
+            }
+        }
+
+        Ok(count)
+    }
+
+    fn fail(&mut self, errno: i32) -> i64 {
+        self.errno = errno;
+        -1
+    }
+
+    fn fail_io(&mut self, error: &std::io::Error) -> i64 {
+        self.fail(error.raw_os_error().unwrap_or(EINVAL))
+    }
+}
+
+/// The state of a single semihosting request.
+struct Request<'a, 'probe> {
+    core: &'a mut Core<'probe>,
+    wide: bool,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl Request<'_, '_> {
+    fn field_size(&self) -> u64 {
+        if self.wide {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// Read a field of the parameter block.
+    fn read_field(&mut self, block: u64, index: u64) -> Result<u64, Error> {
+        let mut bytes = [0; 8];
+        let size = self.field_size() as usize;
+
+        self.core
+            .read(block + index * self.field_size(), &mut bytes[..size])?;
+
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Write a field of the parameter block.
+    fn write_field(&mut self, block: u64, index: u64, value: u64) -> Result<(), Error> {
+        let size = self.field_size() as usize;
+
+        self.core.write_8(
+            block + index * self.field_size(),
+            &value.to_le_bytes()[..size],
+        )
+    }
+
+    /// Read a null-terminated string.
+    fn read_string(&mut self, mut address: u64) -> Result<Vec<u8>, Error> {
+        const CHUNK_SIZE: u64 = 32;
+
+        let mut string = Vec::new();
+
+        loop {
+            // Don't read past the end of the chunk, in case it is the end of the memory.
+            let mut chunk = vec![0; (CHUNK_SIZE - address % CHUNK_SIZE) as usize];
+            self.core.read(address, &mut chunk)?;
+
+            match chunk.iter().position(|&byte| byte == 0) {
+                Some(end) => {
+                    string.extend_from_slice(&chunk[..end]);
+                    return Ok(string);
+                }
+                None => string.extend_from_slice(&chunk),
+            }
+
+            address += chunk.len() as u64;
+        }
+    }
+}
+
+/// The number of bytes transferred by a `SYS_WRITE` or `SYS_READ` of `length` bytes.
+fn transfer_length(length: u64) -> usize {
+    length.min(MAX_TRANSFER_LENGTH) as usize
+}
+
+/// Check if the core is halted on a semihosting trap.
+fn find_trap(core: &mut Core) -> Result<Option<Trap>, Error> {
+    if !matches!(
+        core.status()?,
+        CoreStatus::Halted(HaltReason::Breakpoint(
+            BreakpointCause::Software | BreakpointCause::Unknown
+        ))
+    ) {
+        return Ok(None);
+    }
+
+    let pc: u64 = core.read_core_reg(core.registers().program_counter())?;
+    let instruction_set = core.instruction_set()?;
+
+    // After a HLT instruction, some cores report the address of the next instruction.
+    let resume_address = match instruction_set {
+        InstructionSet::Thumb2 => match read_u16(core, pc)? {
+            T32_BKPT | T32_HLT => Some(pc + 2),
+            _ if pc >= 2 && read_u16(core, pc - 2)? == T32_HLT => Some(pc),
+            _ => None,
+        },
+        InstructionSet::A32 => match read_u32(core, pc)? {
+            A32_BKPT | A32_HLT => Some(pc + 4),
+            _ if pc >= 4 && read_u32(core, pc - 4)? == A32_HLT => Some(pc),
+            _ => None,
+        },
+        InstructionSet::A64 => match read_u32(core, pc)? {
+            A64_HLT => Some(pc + 4),
+            _ if pc >= 4 && read_u32(core, pc - 4)? == A64_HLT => Some(pc),
+            _ => None,
+        },
+        InstructionSet::RV32
+        | InstructionSet::RV32C
+        | InstructionSet::RV64
+        | InstructionSet::RV64C => {
+            is_riscv_trap(pc, |address| read_u32(core, address))?.then(|| pc + 4)
+        }
+        // Semihosting is not supported on Xtensa yet.
+        InstructionSet::Xtensa => None,
+    };
+
+    Ok(resume_address.map(|resume_address| Trap {
+        resume_address,
+        wide: matches!(
+            instruction_set,
+            InstructionSet::A64 | InstructionSet::RV64 | InstructionSet::RV64C
+        ),
+    }))
+}
+
+/// Check if `pc` points to the `ebreak` of the RISC-V trap sequence.
+fn is_riscv_trap(
+    pc: u64,
+    mut read_u32: impl FnMut(u64) -> Result<u32, Error>,
+) -> Result<bool, Error> {
+    Ok(pc >= 4
+        && read_u32(pc)? == RISCV_TRAP[1]
+        && read_u32(pc - 4)? == RISCV_TRAP[0]
+        && read_u32(pc + 4)? == RISCV_TRAP[2])
+}
+
+fn read_u16(core: &mut Core, address: u64) -> Result<u16, Error> {
+    let mut bytes = [0; 2];
+    core.read_8(address, &mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(core: &mut Core, address: u64) -> Result<u32, Error> {
+    let mut bytes = [0; 4];
+    core.read_8(address, &mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn exit_code() {
+        let exit = |reason, subcode| SemihostingExit { reason, subcode }.exit_code();
+
+        assert_eq!(exit(SemihostingExit::APPLICATION_EXIT, None), 0);
+        assert_eq!(exit(SemihostingExit::APPLICATION_EXIT, Some(3)), 3);
+        // ADP_Stopped_RunTimeErrorUnknown
+        assert_eq!(exit(0x20023, Some(0)), 1);
+    }
+
+    #[test]
+    fn paths_are_sandboxed() {
+        let semihosting = Semihosting::new(SemihostingOptions {
+            root: Some(PathBuf::from("/tmp/root")),
+            cmdline: String::new(),
+        });
+
+        assert_eq!(
+            semihosting.sandboxed_path("data/input.bin"),
+            Some(PathBuf::from("/tmp/root/data/input.bin"))
+        );
+        assert_eq!(semihosting.sandboxed_path("../secret"), None);
+        assert_eq!(semihosting.sandboxed_path("/etc/passwd"), None);
+
+        let console_only = Semihosting::new(SemihostingOptions::default());
+        assert_eq!(console_only.sandboxed_path("input.bin"), None);
+    }
+
+    #[test]
+    fn console_handles() {
+        let mut semihosting = Semihosting::new(SemihostingOptions::default());
+
+        assert_eq!(semihosting.open(":tt", 0), HANDLE_STDIN as i64);
+        assert_eq!(semihosting.open(":tt", 4), HANDLE_STDOUT as i64);
+        assert_eq!(semihosting.open(":tt", 8), HANDLE_STDERR as i64);
+
+        assert_eq!(semihosting.open("file.txt", 0), -1);
+        assert_eq!(semihosting.errno, EACCES);
+    }
+
+    #[test]
+    fn transfers_are_limited() {
+        assert_eq!(transfer_length(0), 0);
+        assert_eq!(transfer_length(512), 512);
+        assert_eq!(transfer_length(MAX_TRANSFER_LENGTH), 64 * 1024);
+        assert_eq!(transfer_length(u32::MAX as u64), 64 * 1024);
+        assert_eq!(transfer_length(u64::MAX), 64 * 1024);
+    }
+
+    #[test]
+    fn riscv_trap_sequence() {
+        // nop, slli x0, x0, 0x1f, ebreak, srai x0, x0, 7, followed by a plain ebreak
+        let memory = [
+            0x0000_0013,
+            0x01f0_1013,
+            0x0010_0073,
+            0x4070_5013,
+            0x0010_0073,
+            0x0000_0013,
+        ];
+        let is_trap =
+            |pc: u64| is_riscv_trap(pc, |address| Ok(memory[address as usize / 4])).unwrap();
+
+        assert!(is_trap(8));
+
+        // An ebreak without the magic instructions is a regular breakpoint.
+        assert!(!is_trap(16));
+        // Only the ebreak of the sequence is a trap.
+        assert!(!is_trap(4));
+        assert!(!is_trap(12));
+        // The sequence can't start before address 0.
+        assert!(!is_riscv_trap(0, |_| Ok(RISCV_TRAP[1])).unwrap());
+    }
+}
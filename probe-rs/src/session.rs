@@ -1,22 +1,132 @@
+use crate::architecture::arm::ap::AccessPort;
+use crate::architecture::arm::armv7m::Dhcsr;
 use crate::architecture::arm::component::get_arm_components;
+use crate::architecture::arm::memory::PeripheralType;
 use crate::architecture::arm::sequences::{ArmDebugSequence, DefaultArmSequence};
 use crate::architecture::arm::{ApAddress, ArmError, DpAddress};
 use crate::architecture::riscv::communication_interface::RiscvError;
 use crate::config::{ChipInfo, RegistryError, Target, TargetSelector};
-use crate::core::{Architecture, CoreState, SpecificCoreState};
+use crate::core::{Architecture, CoreState, CoreStatus, HaltReason, SpecificCoreState};
 use crate::{
     architecture::{
         arm::{
-            ap::MemoryAp, communication_interface::ArmProbeInterface, component::TraceSink,
-            memory::CoresightComponent, SwoReader,
+            ap::MemoryAp,
+            communication_interface::ArmProbeInterface,
+            component::{Cti, CtiChannels, DwtCounters, TraceSink},
+            memory::CoresightComponent,
+            trace::{InstructionTrace, InstructionTraceSource, TraceEvent},
+            SwoReader,
         },
         riscv::communication_interface::RiscvCommunicationInterface,
+        xtensa::communication_interface::{XtensaCommunicationInterface, XtensaError},
     },
     config::DebugSequence,
 };
-use crate::{AttachMethod, Core, CoreType, Error, FakeProbe, Probe};
+use crate::{
+    AttachMethod, Core, CoreType, DebugProbeError, DebugProbeSelector, Error, FakeProbe,
+    JtagTapSelector, MemoryInterface, MemoryMappedRegister, Probe, WireProtocol,
+};
+use probe_rs_target::{RiscvCoreAccessOptions, TargetDescriptionSource};
+use std::collections::BTreeMap;
 use std::ops::DerefMut;
-use std::{fmt, sync::Arc, time::Duration};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Put the harts of the target into the halt groups given in the target description.
+fn configure_halt_groups(
+    target: &Target,
+    interface: &mut RiscvCommunicationInterface,
+) -> Result<(), RiscvError> {
+    let mut halt_groups: BTreeMap<u8, Vec<u32>> = BTreeMap::new();
+
+    for core in &target.cores {
+        if let probe_rs_target::CoreAccessOptions::Riscv(RiscvCoreAccessOptions {
+            hart_index,
+            halt_group: Some(group),
+            ..
+        }) = core.core_access_options
+        {
+            halt_groups.entry(group).or_default().push(hart_index);
+        }
+    }
+
+    for (group, harts) in halt_groups {
+        if !interface.set_halt_group(&harts, group)? {
+            tracing::warn!(
+                "The debug module does not support halt groups, harts are halted individually."
+            );
+            break;
+        }
+
+        tracing::debug!("Harts {:?} are in halt group {}", harts, group);
+    }
+
+    Ok(())
+}
+
+/// The CTM channels available for CTI halt groups, one pair per group.
+const CTI_HALT_GROUP_CHANNELS: [CtiChannels; 2] = [
+    CtiChannels {
+        halt: 0,
+        restart: 1,
+    },
+    CtiChannels {
+        halt: 2,
+        restart: 3,
+    },
+];
+
+/// The time to wait for the cores of a halt group to restart.
+const HALT_GROUP_RESUME_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How the cores of a halt group are halted and resumed together.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HaltGroupSync {
+    /// The cores halt and restart each other in hardware, within a few cycles.
+    ///
+    /// This uses the cross trigger interface on ARM, and the halt groups of the debug
+    /// module on RISC-V.
+    Hardware,
+    /// The cores are halted and resumed one after the other by the probe.
+    Software,
+}
+
+/// A group of cores which are halted and resumed together.
+#[derive(Debug)]
+struct HaltGroup {
+    cores: Vec<usize>,
+    kind: HaltGroupKind,
+}
+
+#[derive(Debug)]
+enum HaltGroupKind {
+    /// The CTIs of the cores are connected through the given channels.
+    Cti {
+        channels: CtiChannels,
+        ctis: Vec<(MemoryAp, u64)>,
+    },
+    /// The harts are in the given halt group of the debug module.
+    Riscv {
+        harts: Vec<u32>,
+        group: u8,
+    },
+    Software,
+}
+
+impl HaltGroup {
+    fn sync(&self) -> HaltGroupSync {
+        match self.kind {
+            HaltGroupKind::Cti { .. } | HaltGroupKind::Riscv { .. } => HaltGroupSync::Hardware,
+            HaltGroupKind::Software => HaltGroupSync::Software,
+        }
+    }
+}
+
+/// The time to wait between attempts to reconnect to the probe.
+const RECONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(250);
 
 /// The `Session` struct represents an active debug session.
 ///
@@ -42,11 +152,69 @@ pub struct Session {
     interface: ArchitectureInterface,
     cores: Vec<(SpecificCoreState, CoreState)>,
     configured_trace_sink: Option<TraceSink>,
+    configured_instruction_trace: Option<InstructionTrace>,
+    halt_groups: Vec<HaltGroup>,
+    reconnect: Option<ReconnectInfo>,
 }
 
 enum ArchitectureInterface {
     Arm(Box<dyn ArmProbeInterface + 'static>),
     Riscv(Box<RiscvCommunicationInterface>),
+    Xtensa(Box<XtensaCommunicationInterface>),
+    /// The connection to the probe was closed to reconnect.
+    Disconnected,
+}
+
+/// Everything needed to open a [Session] again, after the connection to the probe was lost.
+#[derive(Debug, Clone)]
+struct ReconnectInfo {
+    selector: DebugProbeSelector,
+    protocol: Option<WireProtocol>,
+    speed_khz: u32,
+    jtag_tap: Option<JtagTapSelector>,
+    attach_method: AttachMethod,
+    permissions: Permissions,
+}
+
+impl ReconnectInfo {
+    fn from_probe(
+        probe: &Probe,
+        attach_method: AttachMethod,
+        permissions: &Permissions,
+    ) -> Option<Self> {
+        Some(Self {
+            selector: probe.selector()?.clone(),
+            protocol: probe.protocol(),
+            speed_khz: probe.speed_khz(),
+            jtag_tap: probe.jtag_tap(),
+            attach_method,
+            permissions: permissions.clone(),
+        })
+    }
+
+    /// Open the probe and attach to the given target, using the original settings.
+    fn open_session(&self, target: Target) -> Result<Session, Error> {
+        let mut probe = Probe::open(self.selector.clone())?;
+
+        if let Some(protocol) = self.protocol {
+            probe.select_protocol(protocol)?;
+        }
+        // Not all probes support setting the speed, so only do it if it's actually needed.
+        if probe.speed_khz() != self.speed_khz {
+            probe.set_speed(self.speed_khz)?;
+        }
+
+        if let Some(tap) = self.jtag_tap {
+            probe.select_jtag_tap(tap)?;
+        }
+
+        Session::new(
+            probe,
+            TargetSelector::Specified(target),
+            self.attach_method,
+            self.permissions.clone(),
+        )
+    }
 }
 
 impl fmt::Debug for ArchitectureInterface {
@@ -57,15 +225,13 @@ impl fmt::Debug for ArchitectureInterface {
                 .debug_tuple("ArchitectureInterface::Riscv")
                 .field(iface)
                 .finish(),
-        }
-    }
-}
-
-impl From<ArchitectureInterface> for Architecture {
-    fn from(value: ArchitectureInterface) -> Self {
-        match value {
-            ArchitectureInterface::Arm(_) => Architecture::Arm,
-            ArchitectureInterface::Riscv(_) => Architecture::Riscv,
+            ArchitectureInterface::Xtensa(iface) => f
+                .debug_tuple("ArchitectureInterface::Xtensa")
+                .field(iface)
+                .finish(),
+            ArchitectureInterface::Disconnected => {
+                f.write_str("ArchitectureInterface::Disconnected")
+            }
         }
     }
 }
@@ -85,25 +251,20 @@ impl ArchitectureInterface {
                     .ok_or_else(|| Error::CoreNotFound(core_state.id()))?;
                 let arm_core_access_options = match &config.core_access_options {
                     probe_rs_target::CoreAccessOptions::Arm(opt) => opt,
-                    probe_rs_target::CoreAccessOptions::Riscv(_) => {
+                    probe_rs_target::CoreAccessOptions::Riscv(_)
+                    | probe_rs_target::CoreAccessOptions::Xtensa(_) => {
                         unreachable!("This should never happen. Please file a bug if it does.")
                     }
                 };
 
-                let dp = match arm_core_access_options.psel {
-                    0 => DpAddress::Default,
-                    x => DpAddress::Multidrop(x),
-                };
-
-                let ap = ApAddress {
-                    dp,
-                    ap: arm_core_access_options.ap,
-                };
+                let ap = ApAddress::from(arm_core_access_options);
                 let memory = state.memory_interface(MemoryAp::new(ap))?;
 
                 core.attach_arm(core_state, memory, target)
             }
-            ArchitectureInterface::Riscv(state) => core.attach_riscv(core_state, state),
+            ArchitectureInterface::Riscv(state) => core.attach_riscv(core_state, state, target),
+            ArchitectureInterface::Xtensa(state) => core.attach_xtensa(core_state, state, target),
+            ArchitectureInterface::Disconnected => Err(DebugProbeError::NotAttached.into()),
         }
     }
 }
@@ -116,9 +277,20 @@ impl Session {
         attach_method: AttachMethod,
         permissions: Permissions,
     ) -> Result<Self, Error> {
-        let (mut probe, target) = get_target_from_selector(target, attach_method, probe)?;
+        let reconnect = ReconnectInfo::from_probe(&probe, attach_method, &permissions);
+
+        let (mut probe, mut target) = get_target_from_selector(target, attach_method, probe)?;
 
-        let cores = target
+        if let Some(scan_chain) = target.jtag_scan_chain() {
+            probe.set_scan_chain(scan_chain.to_vec());
+        }
+
+        let jtag_tap = core_jtag_tap(&target.cores[0]);
+        if let Some(index) = jtag_tap {
+            probe.set_default_jtag_tap(JtagTapSelector::Index(index));
+        }
+
+        let mut cores: Vec<_> = target
             .cores
             .iter()
             .enumerate()
@@ -133,24 +305,32 @@ impl Session {
         let mut session = match target.architecture() {
             Architecture::Arm => {
                 let config = target.cores[0].clone();
+
+                // All cores are accessed through the TAP selected by the probe.
+                if target
+                    .cores
+                    .iter()
+                    .any(|core| core_jtag_tap(core) != jtag_tap)
+                {
+                    return Err(Error::Other(anyhow::anyhow!(
+                        "The cores of {} use different JTAG TAPs, which is not supported for ARM targets.",
+                        target.name
+                    )));
+                }
+
                 let arm_core_access_options = match config.core_access_options {
                     probe_rs_target::CoreAccessOptions::Arm(opt) => opt,
-                    probe_rs_target::CoreAccessOptions::Riscv(_) => {
+                    probe_rs_target::CoreAccessOptions::Riscv(_)
+                    | probe_rs_target::CoreAccessOptions::Xtensa(_) => {
                         unreachable!("This should never happen. Please file a bug if it does.")
                     }
                 };
 
-                let default_memory_ap = MemoryAp::new(ApAddress {
-                    dp: match arm_core_access_options.psel {
-                        0 => DpAddress::Default,
-                        x => DpAddress::Multidrop(x),
-                    },
-                    ap: arm_core_access_options.ap,
-                });
+                let default_memory_ap = MemoryAp::new(ApAddress::from(&arm_core_access_options));
 
                 let sequence_handle = match &target.debug_sequence {
                     DebugSequence::Arm(sequence) => sequence.clone(),
-                    DebugSequence::Riscv(_) => {
+                    DebugSequence::Riscv(_) | DebugSequence::Xtensa(_) => {
                         panic!("Mismatch between architecture and sequence type!")
                     }
                 };
@@ -203,20 +383,15 @@ impl Session {
                     for core in &target.cores {
                         let arm_core_access_options = match &core.core_access_options {
                             probe_rs_target::CoreAccessOptions::Arm(opt) => opt,
-                            probe_rs_target::CoreAccessOptions::Riscv(_) => {
+                            probe_rs_target::CoreAccessOptions::Riscv(_)
+                            | probe_rs_target::CoreAccessOptions::Xtensa(_) => {
                                 unreachable!(
                                     "This should never happen. Please file a bug if it does."
                                 )
                             }
                         };
 
-                        let mem_ap = MemoryAp::new(ApAddress {
-                            dp: match arm_core_access_options.psel {
-                                0 => DpAddress::Default,
-                                x => DpAddress::Multidrop(x),
-                            },
-                            ap: arm_core_access_options.ap,
-                        });
+                        let mem_ap = MemoryAp::new(ApAddress::from(arm_core_access_options));
 
                         let mut memory_interface = interface.memory_interface(mem_ap)?;
 
@@ -269,6 +444,9 @@ impl Session {
                         interface: ArchitectureInterface::Arm(interface),
                         cores,
                         configured_trace_sink: None,
+                        configured_instruction_trace: None,
+                        halt_groups: Vec::new(),
+                        reconnect,
                     };
 
                     {
@@ -300,6 +478,9 @@ impl Session {
                         interface: ArchitectureInterface::Arm(interface),
                         cores,
                         configured_trace_sink: None,
+                        configured_instruction_trace: None,
+                        halt_groups: Vec::new(),
+                        reconnect,
                     }
                 }
             }
@@ -308,22 +489,49 @@ impl Session {
 
                 let sequence_handle = match &target.debug_sequence {
                     DebugSequence::Riscv(sequence) => sequence.clone(),
-                    DebugSequence::Arm(_) => {
+                    DebugSequence::Arm(_) | DebugSequence::Xtensa(_) => {
                         panic!("Mismatch between architecture and sequence type!")
                     }
                 };
 
                 probe.inner_attach()?;
 
-                let interface = probe
+                let mut interface = probe
                     .try_into_riscv_interface()
                     .map_err(|(_probe, err)| err)?;
 
+                if target.source == TargetDescriptionSource::Generic && target.cores.len() == 1 {
+                    // The generic target only describes the first hart, add the others found on the debug module.
+                    for hart_index in 1..interface.num_harts() {
+                        let core = probe_rs_target::Core {
+                            name: format!("hart{hart_index}"),
+                            core_type: target.cores[0].core_type,
+                            core_access_options: probe_rs_target::CoreAccessOptions::Riscv(
+                                RiscvCoreAccessOptions {
+                                    hart_index,
+                                    ..Default::default()
+                                },
+                            ),
+                        };
+
+                        cores.push((
+                            SpecificCoreState::from_core_type(core.core_type),
+                            Core::create_state(cores.len(), core.core_access_options.clone()),
+                        ));
+                        target.cores.push(core);
+                    }
+                }
+
+                configure_halt_groups(&target, &mut interface)?;
+
                 let mut session = Session {
                     target,
                     interface: ArchitectureInterface::Riscv(Box::new(interface)),
                     cores,
                     configured_trace_sink: None,
+                    configured_instruction_trace: None,
+                    halt_groups: Vec::new(),
+                    reconnect,
                 };
 
                 {
@@ -335,6 +543,40 @@ impl Session {
 
                 sequence_handle.on_connect(session.get_riscv_interface()?)?;
 
+                session
+            }
+            Architecture::Xtensa => {
+                let sequence_handle = match &target.debug_sequence {
+                    DebugSequence::Xtensa(sequence) => sequence.clone(),
+                    DebugSequence::Arm(_) | DebugSequence::Riscv(_) => {
+                        panic!("Mismatch between architecture and sequence type!")
+                    }
+                };
+
+                probe.inner_attach()?;
+
+                let interface = probe
+                    .try_into_xtensa_interface()
+                    .map_err(|(_probe, err)| err)?;
+
+                let mut session = Session {
+                    target,
+                    interface: ArchitectureInterface::Xtensa(Box::new(interface)),
+                    cores,
+                    configured_trace_sink: None,
+                    configured_instruction_trace: None,
+                    halt_groups: Vec::new(),
+                    reconnect,
+                };
+
+                {
+                    let mut core = session.core(0)?;
+
+                    core.halt(Duration::from_millis(100))?;
+                }
+
+                sequence_handle.on_connect(session.get_xtensa_interface()?)?;
+
                 session
             }
         };
@@ -442,6 +684,9 @@ impl Session {
     pub fn get_arm_interface(&mut self) -> Result<&mut dyn ArmProbeInterface, ArmError> {
         let interface = match &mut self.interface {
             ArchitectureInterface::Arm(state) => state.deref_mut(),
+            ArchitectureInterface::Disconnected => {
+                return Err(ArmError::Probe(DebugProbeError::NotAttached))
+            }
             _ => return Err(ArmError::NoArmTarget),
         };
 
@@ -451,12 +696,27 @@ impl Session {
     fn get_riscv_interface(&mut self) -> Result<&mut RiscvCommunicationInterface, RiscvError> {
         let interface = match &mut self.interface {
             ArchitectureInterface::Riscv(interface) => interface,
+            ArchitectureInterface::Disconnected => {
+                return Err(RiscvError::DebugProbe(DebugProbeError::NotAttached))
+            }
             _ => return Err(RiscvError::NoRiscvTarget),
         };
 
         Ok(interface)
     }
 
+    fn get_xtensa_interface(&mut self) -> Result<&mut XtensaCommunicationInterface, XtensaError> {
+        let interface = match &mut self.interface {
+            ArchitectureInterface::Xtensa(interface) => interface,
+            ArchitectureInterface::Disconnected => {
+                return Err(XtensaError::DebugProbe(DebugProbeError::NotAttached))
+            }
+            _ => return Err(XtensaError::NoXtensaTarget),
+        };
+
+        Ok(interface)
+    }
+
     #[tracing::instrument(skip_all)]
     fn reattach_arm_interface(
         interface: &mut Box<dyn ArmProbeInterface>,
@@ -496,7 +756,7 @@ impl Session {
     pub fn has_sequence_erase_all(&self) -> bool {
         match &self.target.debug_sequence {
             DebugSequence::Arm(seq) => seq.debug_erase_sequence().is_some(),
-            DebugSequence::Riscv(_) => false,
+            DebugSequence::Riscv(_) | DebugSequence::Xtensa(_) => false,
         }
     }
 
@@ -513,7 +773,7 @@ impl Session {
             ArchitectureInterface::Arm(interface) => {
                 let debug_sequence = match &self.target.debug_sequence {
                     DebugSequence::Arm(seq) => seq.clone(),
-                    DebugSequence::Riscv(_) => {
+                    DebugSequence::Riscv(_) | DebugSequence::Xtensa(_) => {
                         unreachable!("This should never happen. Please file a bug if it does.")
                     }
                 };
@@ -532,20 +792,16 @@ impl Session {
                                 let config = self.target.cores[i].clone();
                                 let arm_core_access_options = match config.core_access_options {
                                     probe_rs_target::CoreAccessOptions::Arm(opt) => opt,
-                                    probe_rs_target::CoreAccessOptions::Riscv(_) => {
+                                    probe_rs_target::CoreAccessOptions::Riscv(_)
+                                    | probe_rs_target::CoreAccessOptions::Xtensa(_) => {
                                         unreachable!(
                                     "This should never happen. Please file a bug if it does."
                                 )
                                     }
                                 };
 
-                                let mem_ap = MemoryAp::new(ApAddress {
-                                    dp: match arm_core_access_options.psel {
-                                        0 => DpAddress::Default,
-                                        x => DpAddress::Multidrop(x),
-                                    },
-                                    ap: arm_core_access_options.ap,
-                                });
+                                let mem_ap =
+                                    MemoryAp::new(ApAddress::from(&arm_core_access_options));
 
                                 let mut memory_interface = interface.memory_interface(mem_ap)?;
 
@@ -568,9 +824,12 @@ impl Session {
                     )))
                 }
             }
-            ArchitectureInterface::Riscv(_) => Err(Error::Probe(
-                crate::DebugProbeError::NotImplemented("Debug Erase Sequence"),
-            )),
+            ArchitectureInterface::Riscv(_) | ArchitectureInterface::Xtensa(_) => {
+                Err(Error::Probe(crate::DebugProbeError::NotImplemented(
+                    "Debug Erase Sequence",
+                )))
+            }
+            ArchitectureInterface::Disconnected => Err(DebugProbeError::NotAttached.into()),
         }
     }
 
@@ -606,7 +865,7 @@ impl Session {
 
         let sequence_handle = match &self.target.debug_sequence {
             DebugSequence::Arm(sequence) => sequence.clone(),
-            DebugSequence::Riscv(_) => {
+            DebugSequence::Riscv(_) | DebugSequence::Xtensa(_) => {
                 panic!("Mismatch between architecture and sequence type!")
             }
         };
@@ -659,11 +918,520 @@ impl Session {
         crate::architecture::arm::component::remove_swv_data_trace(interface, &components, unit)
     }
 
+    /// Enable the DWT cycle counter and profiling counters of a core.
+    ///
+    /// The counters only count while the core is running, so they can be read with
+    /// [Session::read_dwt_counters] while the core is halted to measure the cycles spent
+    /// between two halts.
+    pub fn enable_dwt_counters(&mut self, core_index: usize) -> Result<(), Error> {
+        {
+            let mut core = self.core(core_index)?;
+            crate::architecture::arm::component::enable_tracing(&mut core)?;
+        }
+
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::enable_dwt_counters(interface, &components)?;
+
+        Ok(())
+    }
+
+    /// Read the DWT cycle counter and profiling counters.
+    pub fn read_dwt_counters(&mut self) -> Result<DwtCounters, ArmError> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::read_dwt_counters(interface, &components)
+    }
+
+    /// Reset the DWT cycle counter and profiling counters to zero.
+    pub fn reset_dwt_counters(&mut self) -> Result<(), ArmError> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::reset_dwt_counters(interface, &components)
+    }
+
+    /// Emit a PC sample about every `interval` cycles over SWV.
+    ///
+    /// The interval is rounded to one supported by the DWT, which is returned. Tracing has to be
+    /// set up with [Session::setup_tracing] first.
+    pub fn enable_pc_sampling(&mut self, interval: u32) -> Result<u32, ArmError> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::enable_pc_sampling(interface, &components, interval)
+    }
+
+    /// Stop emitting PC samples over SWV.
+    pub fn disable_pc_sampling(&mut self) -> Result<(), ArmError> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::disable_pc_sampling(interface, &components)
+    }
+
+    /// Configure the target to record an instruction trace, using the ETM or the MTB.
+    ///
+    /// The recorded trace can be read with [Session::read_instruction_trace]. This method is only
+    /// supported for ARM-based targets, and will return [ArmError::ArchitectureRequired] otherwise.
+    pub fn setup_instruction_trace(
+        &mut self,
+        core_index: usize,
+        source: InstructionTraceSource,
+    ) -> Result<(), Error> {
+        // The ETM and the MTB are only available on Cortex-M cores.
+        let sequence_handle = match &self.target.debug_sequence {
+            DebugSequence::Arm(sequence) => sequence.clone(),
+            DebugSequence::Riscv(_) | DebugSequence::Xtensa(_) => {
+                return Err(
+                    ArmError::ArchitectureRequired(&["ARMv6-M", "ARMv7-M", "ARMv8-M"]).into(),
+                )
+            }
+        };
+
+        // Enable tracing on the target
+        {
+            let mut core = self.core(core_index)?;
+            crate::architecture::arm::component::enable_tracing(&mut core)?;
+        }
+
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+
+        if let InstructionTraceSource::Etm(sink) = &source {
+            match sink {
+                TraceSink::Swo(config) | TraceSink::Tpiu(config) => {
+                    interface.enable_swo(config)?;
+                }
+                TraceSink::TraceMemory => {}
+            }
+
+            sequence_handle.trace_start(interface, &components, sink)?;
+        }
+
+        let trace = crate::architecture::arm::component::setup_instruction_trace(
+            interface,
+            &components,
+            &source,
+        )?;
+        self.configured_instruction_trace.replace(trace);
+
+        Ok(())
+    }
+
+    /// Read and decode the instruction trace recorded since the last call.
+    ///
+    /// This method is only supported for ARM-based targets, and requires the trace to be
+    /// configured with [Session::setup_instruction_trace].
+    pub fn read_instruction_trace(&mut self) -> Result<Vec<TraceEvent>, ArmError> {
+        let mut trace = self
+            .configured_instruction_trace
+            .take()
+            .ok_or(ArmError::TracingUnconfigured)?;
+
+        let result = self
+            .get_arm_components(DpAddress::Default)
+            .and_then(|components| {
+                let interface = self.get_arm_interface()?;
+                crate::architecture::arm::component::read_instruction_trace(
+                    interface,
+                    &components,
+                    &mut trace,
+                )
+            });
+
+        self.configured_instruction_trace = Some(trace);
+        result
+    }
+
+    /// Stop recording the instruction trace.
+    pub fn disable_instruction_trace(&mut self) -> Result<(), ArmError> {
+        let trace = self
+            .configured_instruction_trace
+            .take()
+            .ok_or(ArmError::TracingUnconfigured)?;
+
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::disable_instruction_trace(
+            interface,
+            &components,
+            &trace,
+        )
+    }
+
+    /// Halt and resume the given cores together.
+    ///
+    /// On ARM, Cortex-M and Cortex-A cores with a cross trigger interface (CTI) are connected
+    /// through the cross trigger matrix, so that a halt of any core of the group, e.g. on a
+    /// breakpoint, halts the others within a few cycles. This keeps the cores of an SMP target
+    /// consistent with each other while they are debugged. The CTI is taken from the `cti_base`
+    /// of the core in the target description, or found in the ROM table of the access port of
+    /// the core. On RISC-V, the harts
+    /// are put into a halt group of the debug module. Otherwise, the cores are halted and resumed
+    /// one after the other by [Session::halt_cores] and [Session::resume_cores].
+    ///
+    /// A core can only be in one halt group, and is removed from its previous group.
+    ///
+    /// Cores in a hardware halt group should be resumed with [Session::resume_cores]. When they
+    /// are resumed one at a time, the first core to hit a breakpoint halts the others again.
+    pub fn configure_halt_group(&mut self, cores: &[usize]) -> Result<HaltGroupSync, Error> {
+        for &core_index in cores {
+            // Make sure the core is initialized for debugging.
+            self.core(core_index)?;
+        }
+        self.remove_halt_group(cores)?;
+
+        let kind = if cores.len() < 2 {
+            HaltGroupKind::Software
+        } else {
+            match &self.interface {
+                ArchitectureInterface::Arm(_) => self.configure_cti_halt_group(cores)?,
+                ArchitectureInterface::Riscv(_) => self.configure_riscv_halt_group(cores)?,
+                _ => HaltGroupKind::Software,
+            }
+        };
+
+        let group = HaltGroup {
+            cores: cores.to_vec(),
+            kind,
+        };
+        let sync = group.sync();
+        tracing::debug!("Cores {:?} are in a halt group ({:?})", cores, sync);
+
+        self.halt_groups.push(group);
+
+        Ok(sync)
+    }
+
+    /// Remove the given cores from their halt groups, so that they are halted and resumed
+    /// independently again.
+    ///
+    /// The other cores of the affected groups are removed from the groups as well.
+    pub fn remove_halt_group(&mut self, cores: &[usize]) -> Result<(), Error> {
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.halt_groups)
+            .into_iter()
+            .partition(|group| group.cores.iter().any(|core| cores.contains(core)));
+        self.halt_groups = kept;
+
+        for group in removed {
+            match group.kind {
+                HaltGroupKind::Cti { channels, ctis } => {
+                    let interface = self.get_arm_interface()?;
+                    for (ap, base_address) in ctis {
+                        let mut memory = interface.memory_interface(ap)?;
+                        Cti::new(&mut *memory, base_address).leave_halt_group(channels)?;
+                    }
+                }
+                HaltGroupKind::Riscv { harts, .. } => {
+                    // Group 0 means that the hart is in no group.
+                    self.get_riscv_interface()?.set_halt_group(&harts, 0)?;
+                }
+                HaltGroupKind::Software => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn configure_cti_halt_group(&mut self, cores: &[usize]) -> Result<HaltGroupKind, Error> {
+        let used: Vec<CtiChannels> = self
+            .halt_groups
+            .iter()
+            .filter_map(|group| match group.kind {
+                HaltGroupKind::Cti { channels, .. } => Some(channels),
+                _ => None,
+            })
+            .collect();
+
+        // Cortex-A cores halt and restart themselves through channels 0 and 1 of their CTI,
+        // which can't be shared with a halt group.
+        let cortex_a = cores.iter().any(|&core_index| {
+            matches!(
+                self.target.cores[core_index].core_type,
+                CoreType::Armv7a | CoreType::Armv8a
+            )
+        });
+
+        let channels = match CTI_HALT_GROUP_CHANNELS.into_iter().find(|channels| {
+            !used.contains(channels) && !(cortex_a && (channels.halt < 2 || channels.restart < 2))
+        }) {
+            Some(channels) => channels,
+            None => {
+                tracing::warn!("All CTI channels are in use, cores are halted individually.");
+                return Ok(HaltGroupKind::Software);
+            }
+        };
+
+        let mut ctis = Vec::with_capacity(cores.len());
+        for &core_index in cores {
+            match self.find_cti(core_index)? {
+                Some(cti) => ctis.push(cti),
+                None => {
+                    tracing::warn!(
+                        "No cross trigger interface found for core {}, cores are halted individually.",
+                        core_index
+                    );
+                    return Ok(HaltGroupKind::Software);
+                }
+            }
+        }
+
+        let interface = self.get_arm_interface()?;
+        for &(ap, base_address) in &ctis {
+            let mut memory = interface.memory_interface(ap)?;
+            Cti::new(&mut *memory, base_address).join_halt_group(channels)?;
+        }
+
+        Ok(HaltGroupKind::Cti { channels, ctis })
+    }
+
+    /// Find the access port and base address of the CTI of a Cortex-M or Cortex-A core.
+    fn find_cti(&mut self, core_index: usize) -> Result<Option<(MemoryAp, u64)>, Error> {
+        let core = &self.target.cores[core_index];
+        if !matches!(
+            core.core_type,
+            CoreType::Armv7m
+                | CoreType::Armv7em
+                | CoreType::Armv8m
+                | CoreType::Armv7a
+                | CoreType::Armv8a
+        ) {
+            return Ok(None);
+        }
+        let options = match &core.core_access_options {
+            probe_rs_target::CoreAccessOptions::Arm(options) => options,
+            _ => return Ok(None),
+        };
+
+        let ap_address = ApAddress::from(options);
+        let ap = MemoryAp::new(ap_address);
+        if let Some(cti_base) = options.cti_base {
+            return Ok(Some((ap, cti_base)));
+        }
+
+        // Without an explicit base address, the CTI has to be the only one on the access port.
+        let components = self.get_arm_components(ap_address.dp)?;
+        let ctis: Vec<u64> = components
+            .iter()
+            .filter(|component| component.ap.ap_address() == ap_address)
+            .flat_map(|component| component.iter())
+            .filter(|component| {
+                component
+                    .component
+                    .id()
+                    .peripheral_id()
+                    .is_of_type(PeripheralType::Cti)
+            })
+            .map(|component| component.component.id().component_address())
+            .collect();
+
+        match ctis.as_slice() {
+            [base_address] => Ok(Some((ap, *base_address))),
+            _ => Ok(None),
+        }
+    }
+
+    fn configure_riscv_halt_group(&mut self, cores: &[usize]) -> Result<HaltGroupKind, Error> {
+        let mut harts = Vec::with_capacity(cores.len());
+        for &core_index in cores {
+            match &self.target.cores[core_index].core_access_options {
+                probe_rs_target::CoreAccessOptions::Riscv(options) => {
+                    harts.push(options.hart_index)
+                }
+                _ => return Ok(HaltGroupKind::Software),
+            }
+        }
+
+        // Use a group which isn't used by the target description or another halt group.
+        let mut used: Vec<u8> = self
+            .target
+            .cores
+            .iter()
+            .filter_map(|core| match core.core_access_options {
+                probe_rs_target::CoreAccessOptions::Riscv(RiscvCoreAccessOptions {
+                    halt_group,
+                    ..
+                }) => halt_group,
+                _ => None,
+            })
+            .collect();
+        used.extend(
+            self.halt_groups
+                .iter()
+                .filter_map(|group| match group.kind {
+                    HaltGroupKind::Riscv { group, .. } => Some(group),
+                    _ => None,
+                }),
+        );
+        let group = match (1..32).find(|group| !used.contains(group)) {
+            Some(group) => group,
+            None => return Ok(HaltGroupKind::Software),
+        };
+
+        if !self.get_riscv_interface()?.set_halt_group(&harts, group)? {
+            tracing::warn!(
+                "The debug module does not support halt groups, harts are halted individually."
+            );
+            return Ok(HaltGroupKind::Software);
+        }
+
+        Ok(HaltGroupKind::Riscv { harts, group })
+    }
+
+    /// Halt the given cores.
+    ///
+    /// Cores in a hardware halt group halt together with the first one of them. Cores which are
+    /// already halted are left alone.
+    pub fn halt_cores(&mut self, cores: &[usize], timeout: Duration) -> Result<(), Error> {
+        for &core_index in cores {
+            let mut core = self.core(core_index)?;
+            if !core.core_halted()? {
+                core.halt(timeout)?;
+            }
+        }
+
+        // Release the halt requests of the groups, so that the cores can also be run individually.
+        self.acknowledge_cti_halts(cores)
+    }
+
+    /// Deassert the halt requests of the CTI halt groups of the given cores.
+    fn acknowledge_cti_halts(&mut self, cores: &[usize]) -> Result<(), Error> {
+        for index in self.halt_groups_of(cores) {
+            if let HaltGroupKind::Cti { ctis, .. } = &self.halt_groups[index].kind {
+                let ctis = ctis.clone();
+                let interface = self.get_arm_interface()?;
+                for (ap, base_address) in ctis {
+                    let mut memory = interface.memory_interface(ap)?;
+                    Cti::new(&mut *memory, base_address).acknowledge_halt()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resume the given cores.
+    ///
+    /// Hardware halt groups which are completely contained in `cores` are restarted together, all
+    /// other cores are resumed one after the other.
+    pub fn resume_cores(&mut self, cores: &[usize]) -> Result<(), Error> {
+        let mut remaining = cores.to_vec();
+
+        for index in self.halt_groups_of(cores) {
+            let group = &self.halt_groups[index];
+            if !group.cores.iter().all(|core| cores.contains(core)) {
+                continue;
+            }
+
+            let group_cores = group.cores.clone();
+            match &group.kind {
+                HaltGroupKind::Cti { channels, ctis } => {
+                    let (channels, ctis) = (*channels, ctis.clone());
+                    self.resume_cti_halt_group(&group_cores, channels, &ctis)?;
+                }
+                HaltGroupKind::Riscv { harts, .. } => {
+                    let harts = harts.clone();
+                    self.get_riscv_interface()?
+                        .resume_harts(&harts, HALT_GROUP_RESUME_TIMEOUT)?;
+                    for &core_index in &group_cores {
+                        self.core(core_index)?.status()?;
+                    }
+                }
+                HaltGroupKind::Software => continue,
+            }
+
+            remaining.retain(|core| !group_cores.contains(core));
+        }
+
+        // A group which halted through its CTI still requests the cores to halt, which would
+        // halt them again right after they are resumed one at a time.
+        self.acknowledge_cti_halts(&remaining)?;
+
+        for core_index in remaining {
+            let mut core = self.core(core_index)?;
+            if core.core_halted()? {
+                core.run()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resume_cti_halt_group(
+        &mut self,
+        cores: &[usize],
+        channels: CtiChannels,
+        ctis: &[(MemoryAp, u64)],
+    ) -> Result<(), Error> {
+        for &core_index in cores {
+            let mut core = self.core(core_index)?;
+
+            if matches!(core.core_type(), CoreType::Armv7a | CoreType::Armv8a) {
+                core.prepare_for_restart()?;
+                continue;
+            }
+
+            // Step over the breakpoint the core is halted on, like `Core::run` does.
+            if let CoreStatus::Halted(HaltReason::Breakpoint(_)) = core.status()? {
+                core.step()?;
+            }
+
+            // Stepping masks the interrupts, which has to be undone before running again.
+            let mut dhcsr = Dhcsr(core.read_word_32(Dhcsr::ADDRESS)?);
+            if dhcsr.c_maskints() {
+                dhcsr.set_c_maskints(false);
+                dhcsr.enable_write();
+                core.write_word_32(Dhcsr::ADDRESS, dhcsr.into())?;
+            }
+        }
+
+        let interface = self.get_arm_interface()?;
+        for &(ap, base_address) in ctis {
+            let mut memory = interface.memory_interface(ap)?;
+            Cti::new(&mut *memory, base_address).acknowledge_halt()?;
+        }
+
+        // The restart request reaches all cores of the group through the CTM.
+        let (ap, base_address) = ctis[0];
+        let mut memory = interface.memory_interface(ap)?;
+        Cti::new(&mut *memory, base_address).pulse_channel(channels.restart)?;
+        drop(memory);
+
+        let start = Instant::now();
+        for &core_index in cores {
+            let mut core = self.core(core_index)?;
+            while core.core_halted()? {
+                if start.elapsed() > HALT_GROUP_RESUME_TIMEOUT {
+                    return Err(Error::Timeout);
+                }
+            }
+            core.status()?;
+        }
+
+        let interface = self.get_arm_interface()?;
+        for &(ap, base_address) in ctis {
+            let mut memory = interface.memory_interface(ap)?;
+            Cti::new(&mut *memory, base_address).acknowledge_restart()?;
+        }
+
+        Ok(())
+    }
+
+    /// The indices of the halt groups containing any of the given cores.
+    fn halt_groups_of(&self, cores: &[usize]) -> Vec<usize> {
+        self.halt_groups
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| group.cores.iter().any(|core| cores.contains(core)))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
     /// Return the `Architecture` of the currently connected chip.
     pub fn architecture(&self) -> Architecture {
         match self.interface {
             ArchitectureInterface::Arm(_) => Architecture::Arm,
             ArchitectureInterface::Riscv(_) => Architecture::Riscv,
+            ArchitectureInterface::Xtensa(_) => Architecture::Xtensa,
+            ArchitectureInterface::Disconnected => self.target.architecture(),
         }
     }
 
@@ -674,6 +1442,97 @@ impl Session {
                 .and_then(|mut core| core.clear_all_hw_breakpoints())
         })
     }
+
+    /// Check if the session can be restored using [Session::reconnect].
+    ///
+    /// This requires the probe to be opened using a [DebugProbeSelector].
+    pub fn can_reconnect(&self) -> bool {
+        self.reconnect.is_some()
+    }
+
+    /// Reconnect to the target after the connection to the probe or the target was lost.
+    ///
+    /// The probe is opened again using the selector it was originally opened with, waiting up to
+    /// `timeout` for it to show up again, e.g. after it was unplugged. The same protocol, speed
+    /// and JTAG TAP are used, and the target is attached using the original [AttachMethod] and [Permissions].
+    ///
+    /// Hardware breakpoints, halt groups and the memory security state of ARMv8-M cores which
+    /// were configured before the connection was lost are restored. SWV tracing is not restored,
+    /// and has to be configured again using [Session::setup_tracing].
+    ///
+    /// If reconnecting fails, the session stays disconnected, and `reconnect` can be called again.
+    /// Use [Error::is_connection_lost] to check if an error was caused by a lost connection.
+    #[tracing::instrument(skip(self))]
+    pub fn reconnect(&mut self, timeout: Duration) -> Result<(), Error> {
+        let reconnect = self.reconnect.clone().ok_or(Error::UnableToOpenProbe(
+            "The probe was not opened using a selector, and can not be reopened",
+        ))?;
+
+        let breakpoints = self
+            .cores
+            .iter()
+            .map(|(_, state)| state.hw_breakpoints().to_vec())
+            .collect::<Vec<_>>();
+        let security_states = self
+            .cores
+            .iter()
+            .map(|(state, _)| match state {
+                SpecificCoreState::Armv8m(state) => state.memory_security_state(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let halt_groups = self
+            .halt_groups
+            .iter()
+            .map(|group| group.cores.clone())
+            .collect::<Vec<_>>();
+
+        // The probe can only be opened once, so the old connection has to be closed first.
+        self.interface = ArchitectureInterface::Disconnected;
+
+        let start = Instant::now();
+        let mut session = loop {
+            match reconnect.open_session(self.target.clone()) {
+                Ok(session) => break session,
+                Err(err) if start.elapsed() < timeout => {
+                    tracing::debug!("Failed to reconnect, retrying: {}", err);
+                    std::thread::sleep(RECONNECT_RETRY_INTERVAL);
+                }
+                Err(err) => return Err(err),
+            }
+        };
+
+        // Take over the connection of the new session. This leaves the new
+        // session disconnected, so dropping it doesn't touch the target.
+        self.interface =
+            std::mem::replace(&mut session.interface, ArchitectureInterface::Disconnected);
+        self.cores = std::mem::take(&mut session.cores);
+        self.configured_trace_sink = None;
+        self.configured_instruction_trace = None;
+        self.halt_groups.clear();
+
+        for (core_index, (breakpoints, security_state)) in
+            breakpoints.into_iter().zip(security_states).enumerate()
+        {
+            let mut core = self.core(core_index)?;
+
+            for address in breakpoints {
+                core.set_hw_breakpoint(address)?;
+            }
+
+            if security_state.is_some() {
+                core.set_memory_security_state(security_state)?;
+            }
+        }
+
+        for cores in halt_groups {
+            self.configure_halt_group(&cores)?;
+        }
+
+        tracing::info!("Reconnected to the target");
+
+        Ok(())
+    }
 }
 
 // This test ensures that [Session] is fully [Send] + [Sync].
@@ -683,6 +1542,10 @@ static_assertions::assert_impl_all!(Session: Send);
 impl Drop for Session {
     #[tracing::instrument(name = "session_drop", skip(self))]
     fn drop(&mut self) {
+        if let ArchitectureInterface::Disconnected = self.interface {
+            return;
+        }
+
         if let Err(err) = { 0..self.cores.len() }.try_for_each(|i| {
             self.core(i)
                 .and_then(|mut core| core.clear_all_hw_breakpoints())
@@ -733,6 +1596,15 @@ impl Drop for Session {
     }
 }
 
+/// The JTAG TAP the core is accessed through, if the target description specifies it.
+fn core_jtag_tap(core: &probe_rs_target::Core) -> Option<usize> {
+    match &core.core_access_options {
+        probe_rs_target::CoreAccessOptions::Arm(options) => options.jtag_tap,
+        probe_rs_target::CoreAccessOptions::Riscv(options) => options.jtag_tap,
+        probe_rs_target::CoreAccessOptions::Xtensa(options) => options.jtag_tap,
+    }
+}
+
 /// Determine the [Target] from a [TargetSelector].
 ///
 /// If the selector is [TargetSelector::Unspecified], the target will be looked up in the registry.
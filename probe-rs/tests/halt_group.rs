@@ -0,0 +1,137 @@
+use probe_rs::{
+    config::add_target_from_yaml, FakeProbe, HaltGroupSync, MemoryInterface, Permissions, Probe,
+    Session,
+};
+use std::path::Path;
+
+/// The base addresses of the CTIs of the cores of the test target.
+const CTI_BASES: [u64; 6] = [0x1000, 0x2000, 0x3000, 0x4000, 0x5000, 0x6000];
+
+const CTIINEN0: u64 = 0x020;
+const CTIOUTEN0: u64 = 0x0A0;
+const CTIOUTEN1: u64 = 0x0A4;
+const CTITRIGOUTSTATUS: u64 = 0x134;
+const CTIGATE: u64 = 0x140;
+
+fn attach() -> Session {
+    add_target_from_yaml(Path::new("tests/halt-group/target.yaml"))
+        .expect("Failed to add the test target.");
+
+    let probe = Probe::from_specific_probe(Box::new(FakeProbe::new()));
+    let mut session = probe
+        .attach("halt_group_test", Permissions::default())
+        .expect("Failed to attach with 'fake' probe.");
+
+    // Start with CTIs without any channels mapped.
+    let mut core = session.core(0).unwrap();
+    for base in CTI_BASES {
+        for offset in [CTIINEN0, CTIOUTEN0, CTIOUTEN1, CTITRIGOUTSTATUS, CTIGATE] {
+            core.write_word_32(base + offset, 0).unwrap();
+        }
+    }
+    drop(core);
+
+    session
+}
+
+/// Read the channels mapped to the halt input, the halt output, the restart output and the gate
+/// of the CTI of a core.
+fn cti_channels(session: &mut Session, core_index: usize) -> [u32; 4] {
+    let base = CTI_BASES[core_index];
+    let mut core = session.core(0).unwrap();
+    [CTIINEN0, CTIOUTEN0, CTIOUTEN1, CTIGATE]
+        .map(|offset| core.read_word_32(base + offset).unwrap())
+}
+
+#[test]
+fn halt_groups_use_separate_channels() {
+    let mut session = attach();
+
+    assert_eq!(
+        session.configure_halt_group(&[0, 1]).unwrap(),
+        HaltGroupSync::Hardware
+    );
+    assert_eq!(
+        session.configure_halt_group(&[2, 3]).unwrap(),
+        HaltGroupSync::Hardware
+    );
+
+    for core_index in [0, 1] {
+        assert_eq!(
+            cti_channels(&mut session, core_index),
+            [0b01, 0b01, 0b10, 0b11]
+        );
+    }
+    for core_index in [2, 3] {
+        assert_eq!(
+            cti_channels(&mut session, core_index),
+            [0b0100, 0b0100, 0b1000, 0b1100]
+        );
+    }
+}
+
+#[test]
+fn halt_group_without_free_channels_is_software() {
+    let mut session = attach();
+
+    session.configure_halt_group(&[0, 1]).unwrap();
+    session.configure_halt_group(&[2, 3]).unwrap();
+
+    assert_eq!(
+        session.configure_halt_group(&[4, 5]).unwrap(),
+        HaltGroupSync::Software
+    );
+    for core_index in [4, 5] {
+        assert_eq!(cti_channels(&mut session, core_index), [0; 4]);
+    }
+
+    // Regrouping cores 1 and 2 removes both groups, which frees their channels.
+    assert_eq!(
+        session.configure_halt_group(&[1, 2]).unwrap(),
+        HaltGroupSync::Hardware
+    );
+    for core_index in [0, 3] {
+        assert_eq!(cti_channels(&mut session, core_index), [0; 4]);
+    }
+    for core_index in [1, 2] {
+        assert_eq!(
+            cti_channels(&mut session, core_index),
+            [0b01, 0b01, 0b10, 0b11]
+        );
+    }
+
+    // A single core is never in a hardware group.
+    assert_eq!(
+        session.configure_halt_group(&[3]).unwrap(),
+        HaltGroupSync::Software
+    );
+}
+
+#[test]
+fn remove_halt_group_restores_ctis() {
+    let mut session = attach();
+
+    session.configure_halt_group(&[0, 1]).unwrap();
+    session.configure_halt_group(&[2, 3]).unwrap();
+
+    // Removing one core removes the whole group.
+    session.remove_halt_group(&[1]).unwrap();
+    for core_index in [0, 1] {
+        assert_eq!(cti_channels(&mut session, core_index), [0; 4]);
+    }
+    for core_index in [2, 3] {
+        assert_eq!(
+            cti_channels(&mut session, core_index),
+            [0b0100, 0b0100, 0b1000, 0b1100]
+        );
+    }
+
+    // The freed channels are used for the next group.
+    session.configure_halt_group(&[0, 1]).unwrap();
+    for core_index in [0, 1] {
+        assert_eq!(
+            cti_channels(&mut session, core_index),
+            [0b01, 0b01, 0b10, 0b11]
+        );
+    }
+}
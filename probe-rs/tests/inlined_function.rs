@@ -80,3 +80,17 @@ fn function_name_of_non_inlined_function() -> TestResult {
 
     Ok(())
 }
+
+#[test]
+fn all_names_of_inlined_function() -> TestResult {
+    let di = DebugInfo::from_file("tests/inlined-function").unwrap();
+
+    let address = 0x15e;
+
+    let names = di.inlined_function_names(address)?;
+
+    assert_eq!(names.first().unwrap(), "__cortex_m_rt_main");
+    assert_eq!(names.last().unwrap(), "blink_on");
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use probe_rs::{Error, FakeProbe, Permissions, Probe};
+use std::time::Duration;
+
+/// A probe which was not opened using a selector can't be opened again.
+#[test]
+fn reconnect_without_selector() {
+    let probe = Probe::from_specific_probe(Box::new(FakeProbe::new()));
+
+    let mut session = probe
+        .attach("stm32wb55ccux", Permissions::default())
+        .expect("Failed to attach with 'fake' probe.");
+
+    assert!(!session.can_reconnect());
+    assert!(matches!(
+        session.reconnect(Duration::from_millis(10)),
+        Err(Error::UnableToOpenProbe(_))
+    ));
+
+    // The session is still connected after the failed attempt.
+    session.core(0).expect("Failed to attach to the core.");
+}
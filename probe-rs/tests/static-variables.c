@@ -0,0 +1,17 @@
+/* Source of the `static-variables` binary, used by `static_variable.rs`.
+ *
+ * Built with `gcc -g -O0 -nostdlib -static -Wl,-e,0 static-variables.c -o static-variables` */
+
+struct flags {
+    unsigned int ready : 1;
+    unsigned int mode : 3;
+    unsigned int count;
+};
+
+struct buffer {
+    unsigned int length;
+    unsigned int words[];
+};
+
+struct flags FLAGS = {1, 2, 3};
+struct buffer BUFFER = {0};
@@ -0,0 +1,110 @@
+use probe_rs::debug::{debug_info::DebugInfo, DebugError, ScalarType};
+
+type TestResult = Result<(), DebugError>;
+
+#[test]
+fn static_integer() -> TestResult {
+    let di = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+
+    let variable = di.resolve_static_variable("U32")?;
+
+    assert_eq!(variable.address, 0x2000_0020);
+    assert_eq!(variable.scalar_type, ScalarType::Unsigned(4));
+
+    let variable = di.resolve_static_variable("I8")?;
+
+    assert_eq!(variable.address, 0x2000_0008);
+    assert_eq!(variable.scalar_type, ScalarType::Signed(1));
+
+    Ok(())
+}
+
+#[test]
+fn static_float_with_namespace() -> TestResult {
+    let di = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+
+    let variable = di.resolve_static_variable("probe_rs_debugger_test::F64")?;
+
+    assert_eq!(variable.address, 0x2000_0038);
+    assert_eq!(variable.scalar_type, ScalarType::Float(8));
+
+    Ok(())
+}
+
+#[test]
+fn static_struct_field() -> TestResult {
+    let di = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+
+    // A `&str`, which consists of a pointer and a length.
+    let variable = di.resolve_static_variable("LOCAL_STATIC.length")?;
+
+    assert_eq!(variable.address, 0x2000_0044);
+    assert_eq!(variable.scalar_type, ScalarType::Unsigned(4));
+
+    Ok(())
+}
+
+#[test]
+fn static_array_element() -> TestResult {
+    let di = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+
+    let variable = di.resolve_static_variable("_RTT_CHANNEL_BUFFER.value.value[3]")?;
+
+    assert_eq!(variable.address, 0x2000_0097);
+    assert_eq!(variable.scalar_type, ScalarType::Unsigned(1));
+
+    Ok(())
+}
+
+#[test]
+fn static_struct_is_not_scalar() {
+    let di = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+
+    assert!(di.resolve_static_variable("LOCAL_STATIC").is_err());
+    assert!(di
+        .resolve_static_variable("_RTT_CHANNEL_BUFFER.value.value[1024]")
+        .is_err());
+    assert!(di.resolve_static_variable("DOES_NOT_EXIST").is_err());
+}
+
+#[test]
+fn index_into_array_of_unknown_length() -> TestResult {
+    let di = DebugInfo::from_file("tests/static-variables").unwrap();
+
+    let variable = di.resolve_static_variable("BUFFER.words[2]")?;
+
+    assert_eq!(variable.address, 0x40_100c + 2 * 4);
+    assert_eq!(variable.scalar_type, ScalarType::Unsigned(4));
+
+    Ok(())
+}
+
+#[test]
+fn overflowing_array_index() {
+    let di = DebugInfo::from_file("tests/static-variables").unwrap();
+
+    // The offset of the element overflows.
+    assert!(di
+        .resolve_static_variable("BUFFER.words[4611686018427387904]")
+        .is_err());
+    // The offset fits, but the address of the element overflows.
+    assert!(di
+        .resolve_static_variable("BUFFER.words[4611686018427387903]")
+        .is_err());
+}
+
+#[test]
+fn bitfield_members() -> TestResult {
+    let di = DebugInfo::from_file("tests/static-variables").unwrap();
+
+    let error = di.resolve_static_variable("FLAGS.mode").unwrap_err();
+    assert!(error.to_string().contains("bitfield"), "{error}");
+
+    // Regular members of a struct with bitfields can be used.
+    let variable = di.resolve_static_variable("FLAGS.count")?;
+
+    assert_eq!(variable.address, 0x40_1004);
+    assert_eq!(variable.scalar_type, ScalarType::Unsigned(4));
+
+    Ok(())
+}
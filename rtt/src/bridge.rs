@@ -0,0 +1,567 @@
+//! Bridge between RTT channels and sockets.
+//!
+//! A [`Bridge`] exposes every up/down channel pair of an [`Rtt`] instance as a TCP port or Unix
+//! socket, so other programs can talk to the target while the host program owns the probe.
+//! Data read from an up channel is sent to every connected client, and data received from any
+//! client is written to the down channel with the same number.
+//!
+//! ```no_run
+//! use probe_rs::{Probe, Permissions};
+//! use probe_rs_rtt::{Bridge, BridgeAddress, Rtt};
+//!
+//! let probe = Probe::list_all()[0].open()?;
+//! let mut session = probe.attach("somechip", Permissions::default())?;
+//! let memory_map = session.target().memory_map.clone();
+//! let mut core = session.core(0)?;
+//!
+//! let mut rtt = Rtt::attach(&mut core, &memory_map)?;
+//!
+//! // Channel 0 is available on port 19021, channel 1 on port 19022, and so on.
+//! let mut bridge = Bridge::new(&mut rtt, &"127.0.0.1:19021".parse()?)?;
+//!
+//! loop {
+//!     bridge.poll(&mut core)?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{DownChannel, Error, Rtt, UpChannel};
+use probe_rs::Core;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::{
+    fs::FileTypeExt,
+    net::{UnixListener, UnixStream},
+};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Maximum number of bytes buffered for a client which doesn't read its data. The client is
+/// disconnected if it falls further behind.
+const MAX_PENDING_BYTES: usize = 1024 * 1024;
+
+/// The address a [`Bridge`] listens on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BridgeAddress {
+    /// Listen on TCP ports, starting with this address for channel 0. The port number is
+    /// incremented by the channel number for the other channels.
+    ///
+    /// With port 0, every channel listens on a port assigned by the operating system, see
+    /// [`Bridge::addresses`].
+    Tcp(SocketAddr),
+
+    /// Listen on Unix sockets. Channel `n` uses this path with `.n` appended.
+    Unix(PathBuf),
+}
+
+impl BridgeAddress {
+    /// Returns the address used for the channel with the given number.
+    ///
+    /// Fails if the TCP port of the channel is beyond the last port number.
+    pub fn for_channel(&self, number: usize) -> Result<BridgeAddress, Error> {
+        match self {
+            // Let the operating system pick a port for every channel.
+            BridgeAddress::Tcp(address) if address.port() == 0 => Ok(self.clone()),
+            BridgeAddress::Tcp(address) => {
+                let port = u16::try_from(number)
+                    .ok()
+                    .and_then(|number| address.port().checked_add(number))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "No TCP port for RTT channel {number} after port {}",
+                                address.port()
+                            ),
+                        )
+                    })?;
+
+                let mut address = *address;
+                address.set_port(port);
+                Ok(BridgeAddress::Tcp(address))
+            }
+            BridgeAddress::Unix(path) => {
+                let mut path = path.clone().into_os_string();
+                path.push(format!(".{number}"));
+                Ok(BridgeAddress::Unix(path.into()))
+            }
+        }
+    }
+}
+
+impl FromStr for BridgeAddress {
+    type Err = String;
+
+    /// Parses `unix:<path>` as a Unix socket path, and anything else as a TCP socket address,
+    /// optionally prefixed with `tcp:`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            return Ok(BridgeAddress::Unix(path.into()));
+        }
+
+        let address = s.strip_prefix("tcp:").unwrap_or(s);
+
+        address
+            .parse()
+            .map(BridgeAddress::Tcp)
+            .map_err(|_| format!("Invalid bridge address '{s}'"))
+    }
+}
+
+impl fmt::Display for BridgeAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeAddress::Tcp(address) => write!(f, "tcp:{address}"),
+            BridgeAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Exposes all channels of an RTT instance as sockets.
+///
+/// See the [module documentation](self) for more information.
+#[derive(Debug)]
+pub struct Bridge {
+    channels: Vec<ChannelBridge>,
+}
+
+impl Bridge {
+    /// Takes all up and down channels of `rtt` and starts listening for clients on `address`.
+    ///
+    /// Up and down channels with the same number share a socket.
+    pub fn new(rtt: &mut Rtt, address: &BridgeAddress) -> Result<Bridge, Error> {
+        let mut numbers: BTreeSet<usize> = rtt.up_channels().iter().map(|c| c.number()).collect();
+        numbers.extend(rtt.down_channels().iter().map(|c| c.number()));
+
+        let mut channels = Vec::with_capacity(numbers.len());
+
+        for number in numbers {
+            channels.push(ChannelBridge::new(
+                rtt.up_channels().take(number),
+                rtt.down_channels().take(number),
+                &address.for_channel(number)?,
+            )?);
+        }
+
+        Ok(Bridge { channels })
+    }
+
+    /// Returns the channel numbers and the addresses they are available on.
+    pub fn addresses(&self) -> impl Iterator<Item = (usize, &BridgeAddress)> {
+        self.channels.iter().map(|c| (c.number, &c.address))
+    }
+
+    /// Accepts new clients and transfers pending data in both directions for all channels.
+    ///
+    /// Returns the number of bytes transferred, so the caller can back off polling when idle.
+    pub fn poll(&mut self, core: &mut Core) -> Result<usize, Error> {
+        let mut total = 0;
+
+        for channel in self.channels.iter_mut() {
+            total += channel.poll(core)?;
+        }
+
+        Ok(total)
+    }
+}
+
+/// A single up/down channel pair and the clients connected to it.
+#[derive(Debug)]
+struct ChannelBridge {
+    number: usize,
+    address: BridgeAddress,
+    up: Option<UpChannel>,
+    down: Option<DownChannel>,
+    listener: Listener,
+    clients: Vec<Client>,
+    // Data received from clients, which didn't fit into the down channel yet
+    down_buf: Vec<u8>,
+}
+
+impl ChannelBridge {
+    fn new(
+        up: Option<UpChannel>,
+        down: Option<DownChannel>,
+        address: &BridgeAddress,
+    ) -> Result<ChannelBridge, Error> {
+        let number = match (&up, &down) {
+            (Some(up), _) => up.number(),
+            (None, Some(down)) => down.number(),
+            (None, None) => unreachable!("A bridge needs at least one channel"),
+        };
+
+        let listener = Listener::bind(address)?;
+
+        Ok(ChannelBridge {
+            number,
+            address: listener.address()?,
+            up,
+            down,
+            listener,
+            clients: Vec::new(),
+            down_buf: Vec::new(),
+        })
+    }
+
+    fn poll(&mut self, core: &mut Core) -> Result<usize, Error> {
+        while let Some(stream) = self.listener.accept()? {
+            tracing::info!("Client connected to RTT channel {}", self.number);
+            self.clients.push(Client {
+                stream: Some(stream),
+                pending: Vec::new(),
+            });
+        }
+
+        let mut total = 0;
+
+        // Receive from the clients, but only if the down channel has caught up, so a client
+        // can't make us buffer unlimited amounts of data.
+        if self.down_buf.len() < MAX_PENDING_BYTES {
+            let mut buf = [0u8; 1024];
+
+            for client in self.clients.iter_mut() {
+                client.receive(&mut buf, &mut self.down_buf);
+            }
+        }
+
+        match &self.down {
+            Some(down) if !self.down_buf.is_empty() => {
+                let count = down.write(core, &self.down_buf)?;
+                self.down_buf.drain(..count);
+                total += count;
+            }
+            Some(_) => {}
+            // There is no down channel to send received data to
+            None => self.down_buf.clear(),
+        }
+
+        if let Some(up) = &self.up {
+            let mut buf = [0u8; 1024];
+            let count = up.read(core, &mut buf)?;
+
+            // Data is dropped if nobody is listening, like on a terminal which isn't open.
+            for client in self.clients.iter_mut() {
+                client.pending.extend_from_slice(&buf[..count]);
+            }
+
+            total += count;
+        }
+
+        for client in self.clients.iter_mut() {
+            client.send();
+        }
+
+        let number = self.number;
+        self.clients.retain(|client| {
+            let connected = client.is_connected();
+            if !connected {
+                tracing::info!("Client disconnected from RTT channel {}", number);
+            }
+            connected
+        });
+
+        Ok(total)
+    }
+}
+
+#[derive(Debug)]
+struct Client {
+    stream: Option<Stream>,
+    // Data read from the up channel, which the client didn't accept yet
+    pending: Vec<u8>,
+}
+
+impl Client {
+    fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Appends all data available from the client to `down_buf`.
+    fn receive(&mut self, buf: &mut [u8], down_buf: &mut Vec<u8>) {
+        if let Some(stream) = &mut self.stream {
+            loop {
+                match stream.read(buf) {
+                    // The client closed the connection
+                    Ok(0) => {
+                        self.stream = None;
+                        break;
+                    }
+                    Ok(count) => down_buf.extend_from_slice(&buf[..count]),
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                    Err(error) => {
+                        tracing::warn!("Failed to receive from RTT bridge client: {}", error);
+                        self.stream = None;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends as much of the pending data as the client accepts.
+    fn send(&mut self) {
+        if let Some(stream) = &mut self.stream {
+            while !self.pending.is_empty() {
+                match stream.write(&self.pending) {
+                    Ok(0) => {
+                        self.stream = None;
+                        break;
+                    }
+                    Ok(count) => {
+                        self.pending.drain(..count);
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                    Err(error) => {
+                        tracing::warn!("Failed to send to RTT bridge client: {}", error);
+                        self.stream = None;
+                        break;
+                    }
+                }
+            }
+
+            if self.pending.len() > MAX_PENDING_BYTES {
+                tracing::warn!("RTT bridge client is not reading its data, disconnecting it");
+                self.stream = None;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    fn bind(address: &BridgeAddress) -> Result<Listener, Error> {
+        let listener = match address {
+            BridgeAddress::Tcp(address) => {
+                let listener = TcpListener::bind(address)?;
+                listener.set_nonblocking(true)?;
+                Listener::Tcp(listener)
+            }
+            #[cfg(unix)]
+            BridgeAddress::Unix(path) => {
+                // Remove a stale socket left behind by a previous run, but never anything else
+                match std::fs::symlink_metadata(path) {
+                    Ok(metadata) if metadata.file_type().is_socket() => {
+                        std::fs::remove_file(path)?
+                    }
+                    Ok(_) => {
+                        return Err(Error::Io(io::Error::new(
+                            io::ErrorKind::AlreadyExists,
+                            format!(
+                                "Can't create the socket '{}', a file which is not a socket exists there",
+                                path.display()
+                            ),
+                        )))
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+                    Err(error) => return Err(error.into()),
+                }
+
+                let listener = UnixListener::bind(path)?;
+                listener.set_nonblocking(true)?;
+                Listener::Unix(listener, path.clone())
+            }
+            #[cfg(not(unix))]
+            BridgeAddress::Unix(_) => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Unix sockets are not supported on this platform",
+                )))
+            }
+        };
+
+        Ok(listener)
+    }
+
+    /// Returns the address the listener is bound to, which includes the actual port if it was
+    /// bound to TCP port 0.
+    fn address(&self) -> Result<BridgeAddress, Error> {
+        match self {
+            Listener::Tcp(listener) => Ok(BridgeAddress::Tcp(listener.local_addr()?)),
+            #[cfg(unix)]
+            Listener::Unix(_, path) => Ok(BridgeAddress::Unix(path.clone())),
+        }
+    }
+
+    /// Accepts a pending client, if there is one.
+    fn accept(&self) -> Result<Option<Stream>, Error> {
+        let result = match self {
+            Listener::Tcp(listener) => listener.accept().and_then(|(stream, _)| {
+                stream.set_nonblocking(true)?;
+                stream.set_nodelay(true)?;
+                Ok(Stream::Tcp(stream))
+            }),
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => listener.accept().and_then(|(stream, _)| {
+                stream.set_nonblocking(true)?;
+                Ok(Stream::Unix(stream))
+            }),
+        };
+
+        match result {
+            Ok(stream) => Ok(Some(stream)),
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Listener::Unix(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rtt::test::{fake_session, memory_map, write_control_block};
+    use crate::ScanRegion;
+    use probe_rs::MemoryInterface;
+    use std::time::{Duration, Instant};
+
+    /// Polls the bridge until `done` returns true.
+    fn poll_until(bridge: &mut Bridge, core: &mut Core, mut done: impl FnMut(&mut Core) -> bool) {
+        let start = Instant::now();
+        while !done(core) {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "Timeout polling the bridge"
+            );
+            bridge.poll(core).unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn tcp_round_trip() {
+        let mut session = fake_session();
+        let mut core = session.core(0).unwrap();
+        // Channel 0 has "hello" in its up buffer, and an empty down buffer at 0x1300.
+        write_control_block(&mut core, 0x1000, 4);
+        let mut rtt =
+            Rtt::attach_region(&mut core, &memory_map(), &ScanRegion::Exact(0x1000)).unwrap();
+
+        let mut bridge = Bridge::new(&mut rtt, &"127.0.0.1:0".parse().unwrap()).unwrap();
+        let address = match bridge.addresses().collect::<Vec<_>>().as_slice() {
+            [(0, BridgeAddress::Tcp(address))] => *address,
+            addresses => panic!("Unexpected bridge addresses {addresses:?}"),
+        };
+        assert_ne!(address.port(), 0);
+
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_millis(1)))
+            .unwrap();
+
+        // Up channel to client
+        let mut received = Vec::new();
+        poll_until(&mut bridge, &mut core, |_| {
+            let mut buf = [0u8; 16];
+            if let Ok(count) = client.read(&mut buf) {
+                received.extend_from_slice(&buf[..count]);
+            }
+            received.len() >= 5
+        });
+        assert_eq!(received, b"hello");
+
+        // Client to down channel
+        client.write_all(b"hi").unwrap();
+        const DOWN_WRITE_OFFSET: u64 = 0x1000 + 24 + 24 + 12;
+        poll_until(&mut bridge, &mut core, |core| {
+            core.read_word_32(DOWN_WRITE_OFFSET).unwrap() == 2
+        });
+        let mut data = [0u8; 2];
+        core.read_8(0x1300, &mut data).unwrap();
+        assert_eq!(&data, b"hi");
+    }
+
+    #[test]
+    fn tcp_port_per_channel() {
+        let address: BridgeAddress = "tcp:127.0.0.1:19021".parse().unwrap();
+        assert_eq!(
+            address.for_channel(2).unwrap(),
+            "127.0.0.1:19023".parse().unwrap()
+        );
+
+        let address: BridgeAddress = "127.0.0.1:65534".parse().unwrap();
+        assert_eq!(
+            address.for_channel(1).unwrap(),
+            "127.0.0.1:65535".parse().unwrap()
+        );
+        assert!(matches!(address.for_channel(2), Err(Error::Io(_))));
+        assert!(matches!(address.for_channel(1 << 16), Err(Error::Io(_))));
+
+        // Port 0 is kept, so every channel gets a port assigned by the operating system.
+        let address: BridgeAddress = "127.0.0.1:0".parse().unwrap();
+        assert_eq!(address.for_channel(3).unwrap(), address);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket_replaces_only_sockets() {
+        let path = std::env::temp_dir().join(format!("probe-rs-rtt-bridge-{}", std::process::id()));
+        let address = BridgeAddress::Unix(path.clone());
+
+        // A stale socket is replaced.
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        let listener = Listener::bind(&address).unwrap();
+        drop(listener);
+        assert!(!path.exists());
+
+        // Any other file is kept.
+        std::fs::write(&path, b"data").unwrap();
+        assert!(matches!(Listener::bind(&address), Err(Error::Io(_))));
+        assert_eq!(std::fs::read(&path).unwrap(), b"data");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+use crate::framing::{FrameError, Framing};
 use crate::Error;
 use probe_rs::{config::MemoryRegion, Core, MemoryInterface};
 use scroll::{Pread, LE};
@@ -20,10 +21,11 @@ pub trait RttChannel {
 pub(crate) struct Channel {
     number: usize,
     core_id: usize,
-    ptr: u32,
+    ptr: u64,
     name: Option<String>,
-    buffer_ptr: u32,
+    buffer_ptr: u64,
     size: u32,
+    pointer_width: usize,
 }
 
 // Chanels must follow this data layout when reading/writing memory in order to be compatible with
@@ -38,27 +40,48 @@ pub(crate) struct Channel {
 //     // The low 2 bits of flags are used for blocking/non blocking modes, the rest are ignored.
 //     unsigned int flags;
 // }
+//
+// The pointers are 4 bytes wide on 32-bit targets and 8 bytes wide on 64-bit targets, which
+// moves all the following fields.
 
 impl Channel {
-    // Size of the Channel struct in target memory in bytes
-    pub(crate) const SIZE: usize = 24;
-
     // Offsets of fields in target memory in bytes
     const O_NAME: usize = 0;
-    const O_BUFFER_PTR: usize = 4;
-    const O_SIZE: usize = 8;
-    const O_WRITE: usize = 12;
-    const O_READ: usize = 16;
-    const O_FLAGS: usize = 20;
+
+    /// Size of the Channel struct in target memory in bytes
+    pub(crate) fn size(pointer_width: usize) -> usize {
+        2 * pointer_width + 16
+    }
+
+    fn o_buffer_ptr(pointer_width: usize) -> usize {
+        pointer_width
+    }
+
+    fn o_size(pointer_width: usize) -> usize {
+        2 * pointer_width
+    }
+
+    fn o_write(pointer_width: usize) -> usize {
+        2 * pointer_width + 4
+    }
+
+    fn o_read(pointer_width: usize) -> usize {
+        2 * pointer_width + 8
+    }
+
+    fn o_flags(pointer_width: usize) -> usize {
+        2 * pointer_width + 12
+    }
 
     pub(crate) fn from(
         core: &mut Core,
         number: usize,
         memory_map: &[MemoryRegion],
-        ptr: u32,
+        ptr: u64,
         mem: &[u8],
+        pointer_width: usize,
     ) -> Result<Option<Channel>, Error> {
-        let buffer_ptr: u32 = match mem.pread_with(Self::O_BUFFER_PTR, LE) {
+        let buffer_ptr = match read_pointer(mem, Self::o_buffer_ptr(pointer_width), pointer_width) {
             Ok(buffer_ptr) => buffer_ptr,
             Err(_error) => return Err(Error::MemoryRead("RTT channel address".to_string())),
         };
@@ -68,7 +91,7 @@ impl Channel {
             return Ok(None);
         }
 
-        let name_ptr: u32 = match mem.pread_with(Self::O_NAME, LE) {
+        let name_ptr = match read_pointer(mem, Self::O_NAME, pointer_width) {
             Ok(name_ptr) => name_ptr,
             Err(_error) => return Err(Error::MemoryRead("RTT channel name".to_string())),
         };
@@ -85,10 +108,26 @@ impl Channel {
             ptr,
             name,
             buffer_ptr,
-            size: mem.pread_with(Self::O_SIZE, LE).unwrap(),
+            size: mem.pread_with(Self::o_size(pointer_width), LE).unwrap(),
+            pointer_width,
         }))
     }
 
+    /// Address of the write offset in target memory
+    fn write_address(&self) -> u64 {
+        self.ptr + Self::o_write(self.pointer_width) as u64
+    }
+
+    /// Address of the read offset in target memory
+    fn read_address(&self) -> u64 {
+        self.ptr + Self::o_read(self.pointer_width) as u64
+    }
+
+    /// Address of the flags in target memory
+    fn flags_address(&self) -> u64 {
+        self.ptr + Self::o_flags(self.pointer_width) as u64
+    }
+
     /// Validate that the Core id of a request is the same as the Core id against which the Channel was created.
     pub(crate) fn validate_core_id(&self, core: &mut Core) -> Result<(), Error> {
         if core.id() == self.core_id {
@@ -109,7 +148,7 @@ impl Channel {
     fn read_pointers(&self, core: &mut Core, dir: &'static str) -> Result<(u32, u32), Error> {
         self.validate_core_id(core)?;
         let mut block = [0u32; 2];
-        core.read_32((self.ptr + Self::O_WRITE as u32).into(), block.as_mut())?;
+        core.read_32(self.write_address(), block.as_mut())?;
 
         let write: u32 = block[0];
         let read: u32 = block[1];
@@ -164,7 +203,7 @@ impl UpChannel {
     pub fn mode(&self, core: &mut Core) -> Result<ChannelMode, Error> {
         self.0.validate_core_id(core)?;
 
-        let flags = core.read_word_32((self.0.ptr + Channel::O_FLAGS as u32).into())?;
+        let flags = core.read_word_32(self.0.flags_address())?;
 
         match flags & 0x3 {
             0 => Ok(ChannelMode::NoBlockSkip),
@@ -181,10 +220,10 @@ impl UpChannel {
     /// See [`ChannelMode`] for more information on what the modes mean.
     pub fn set_mode(&self, core: &mut Core, mode: ChannelMode) -> Result<(), Error> {
         self.0.validate_core_id(core)?;
-        let flags = core.read_word_32((self.0.ptr + Channel::O_FLAGS as u32).into())?;
+        let flags = core.read_word_32(self.0.flags_address())?;
 
         let new_flags = (flags & !3) | (mode as u32);
-        core.write_word_32((self.0.ptr + Channel::O_FLAGS as u32).into(), new_flags)?;
+        core.write_word_32(self.0.flags_address(), new_flags)?;
 
         Ok(())
     }
@@ -202,7 +241,7 @@ impl UpChannel {
                 break;
             }
 
-            core.read((self.0.buffer_ptr + read).into(), &mut buf[..count])?;
+            core.read(self.0.buffer_ptr + u64::from(read), &mut buf[..count])?;
 
             total += count;
             read += count as u32;
@@ -229,7 +268,7 @@ impl UpChannel {
 
         if total > 0 {
             // Write read pointer back to target if something was read
-            core.write_word_32((self.0.ptr + Channel::O_READ as u32).into(), read)?;
+            core.write_word_32(self.0.read_address(), read)?;
         }
 
         Ok(total)
@@ -312,7 +351,7 @@ impl DownChannel {
                 break;
             }
 
-            core.write_8((self.0.buffer_ptr + write).into(), &buf[..count])?;
+            core.write_8(self.0.buffer_ptr + u64::from(write), &buf[..count])?;
 
             total += count;
             write += count as u32;
@@ -326,11 +365,56 @@ impl DownChannel {
         }
 
         // Write write pointer back to target
-        core.write_word_32((self.0.ptr + Channel::O_WRITE as u32).into(), write)?;
+        core.write_word_32(self.0.write_address(), write)?;
 
         Ok(total)
     }
 
+    /// Writes a complete frame for `payload`, encoded with `framing`, into the channel buffer.
+    ///
+    /// Frames are never written partially, so the target always receives complete frames. If
+    /// there is currently not enough space in the channel buffer, nothing is written and `false`
+    /// is returned, and the write can be retried later.
+    pub fn write_frame(
+        &self,
+        core: &mut Core,
+        framing: &dyn Framing,
+        payload: &[u8],
+    ) -> Result<bool, Error> {
+        let mut frame = Vec::new();
+        framing.encode(payload, &mut frame)?;
+
+        // One byte of the ring buffer always stays unused.
+        let capacity = self.buffer_size().saturating_sub(1);
+        if frame.len() > capacity {
+            return Err(FrameError::TooLarge {
+                size: frame.len(),
+                max: capacity,
+            }
+            .into());
+        }
+
+        self.0.validate_core_id(core)?;
+        let (write, read) = self.0.read_pointers(core, "down")?;
+
+        if self.writable(write, read) < frame.len() {
+            return Ok(false);
+        }
+
+        let written = self.write(core, &frame)?;
+
+        Ok(written == frame.len())
+    }
+
+    /// Calculates the total amount of space available for writing
+    fn writable(&self, write: u32, read: u32) -> usize {
+        (if read > write {
+            read - write - 1
+        } else {
+            self.0.size - write + read - 1
+        }) as usize
+    }
+
     /// Calculates amount of contiguous space available for writing
     fn writable_contiguous(&self, write: u32, read: u32) -> usize {
         (if read > write {
@@ -357,11 +441,24 @@ impl RttChannel for DownChannel {
     }
 }
 
+/// Reads a pointer of `pointer_width` bytes from memory read from the target.
+pub(crate) fn read_pointer(
+    mem: &[u8],
+    offset: usize,
+    pointer_width: usize,
+) -> Result<u64, scroll::Error> {
+    if pointer_width == 8 {
+        mem.pread_with::<u64>(offset, LE)
+    } else {
+        mem.pread_with::<u32>(offset, LE).map(u64::from)
+    }
+}
+
 /// Reads a null-terminated string from target memory. Lossy UTF-8 decoding is used.
 fn read_c_string(
     core: &mut Core,
     memory_map: &[MemoryRegion],
-    ptr: u32,
+    ptr: u64,
 ) -> Result<Option<String>, Error> {
     // Find out which memory range contains the pointer
     let range = memory_map
@@ -371,7 +468,7 @@ fn read_c_string(
             MemoryRegion::Ram(r) => Some(&r.range),
             _ => None,
         })
-        .find(|r| r.contains(&(ptr as u64)));
+        .find(|r| r.contains(&ptr));
 
     // If the pointer is not within any valid range, return None.
     let range = match range {
@@ -380,8 +477,8 @@ fn read_c_string(
     };
 
     // Read up to 128 bytes not going past the end of the region
-    let mut bytes = vec![0u8; min(128, (range.end - ptr as u64) as usize)];
-    core.read(ptr.into(), bytes.as_mut())?;
+    let mut bytes = vec![0u8; min(128, (range.end - ptr) as usize)];
+    core.read(ptr, bytes.as_mut())?;
 
     let return_value = bytes
         .iter()
@@ -411,3 +508,37 @@ pub enum ChannelMode {
     /// is not read by the host.
     BlockIfFull = 2,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_pointer_widths() {
+        let mem = [0x78, 0x56, 0x34, 0x12, 0xf0, 0xde, 0xbc, 0x9a];
+
+        assert_eq!(read_pointer(&mem, 0, 4).unwrap(), 0x1234_5678);
+        assert_eq!(read_pointer(&mem, 4, 4).unwrap(), 0x9abc_def0);
+        assert_eq!(read_pointer(&mem, 0, 8).unwrap(), 0x9abc_def0_1234_5678);
+
+        assert!(read_pointer(&mem, 6, 4).is_err());
+        assert!(read_pointer(&mem, 4, 8).is_err());
+    }
+
+    #[test]
+    fn channel_layout() {
+        assert_eq!(Channel::size(4), 24);
+        assert_eq!(Channel::o_buffer_ptr(4), 4);
+        assert_eq!(Channel::o_size(4), 8);
+        assert_eq!(Channel::o_write(4), 12);
+        assert_eq!(Channel::o_read(4), 16);
+        assert_eq!(Channel::o_flags(4), 20);
+
+        assert_eq!(Channel::size(8), 32);
+        assert_eq!(Channel::o_buffer_ptr(8), 8);
+        assert_eq!(Channel::o_size(8), 16);
+        assert_eq!(Channel::o_write(8), 20);
+        assert_eq!(Channel::o_read(8), 24);
+        assert_eq!(Channel::o_flags(8), 28);
+    }
+}
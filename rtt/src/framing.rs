@@ -0,0 +1,531 @@
+//! Framing of binary messages exchanged over RTT channels.
+//!
+//! RTT channels are plain byte streams. To exchange structured binary messages, e.g. serialized
+//! with [postcard](https://docs.rs/postcard), the messages have to be framed so the receiver can
+//! find the start and end of each message. A [`Framing`] encodes payloads into frames and
+//! decodes a stream of received bytes back into payloads.
+//!
+//! Two framings are built in:
+//!
+//! - [`Cobs`]: Consistent Overhead Byte Stuffing, with every frame terminated by a zero byte.
+//!   This is compatible with postcard's `to_slice_cobs` / `from_bytes_cobs`.
+//! - [`LengthPrefixed`]: Every frame starts with its length as a little endian integer.
+//!
+//! Other framings can be supported by implementing the [`Framing`] trait.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use probe_rs::{Probe, Permissions};
+//! use probe_rs_rtt::{framing::{Cobs, Framing}, Rtt};
+//!
+//! let probe = Probe::list_all()[0].open()?;
+//! let mut session = probe.attach("somechip", Permissions::default())?;
+//! let memory_map = session.target().memory_map.clone();
+//! let mut core = session.core(0)?;
+//! let mut rtt = Rtt::attach(&mut core, &memory_map)?;
+//!
+//! let mut framing = Cobs::default();
+//!
+//! // Send a command to the target
+//! if let Some(output) = rtt.down_channels().take(0) {
+//!     output.write_frame(&mut core, &framing, &[0x01, 0x00, 0x02])?;
+//! }
+//!
+//! // Receive the responses
+//! if let Some(input) = rtt.up_channels().take(0) {
+//!     let mut buf = [0u8; 1024];
+//!     let count = input.read(&mut core, &mut buf[..])?;
+//!     framing.received(&buf[..count]);
+//!
+//!     while let Some(frame) = framing.decode() {
+//!         println!("Received frame: {:?}", frame?);
+//!     }
+//! }
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt;
+use thiserror::Error;
+
+/// The default for the largest frame accepted by the built-in framings, in bytes.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 64 * 1024;
+
+/// Error type for encoding and decoding frames.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    /// A received frame is not valid COBS. The frame has been dropped.
+    #[error("Received frame is not valid COBS")]
+    InvalidCobs,
+
+    /// A frame is larger than the framing or the channel allows.
+    #[error("Frame of {size} bytes exceeds the maximum frame size of {max} bytes")]
+    TooLarge {
+        /// Size of the frame in bytes.
+        size: usize,
+        /// Largest allowed frame size in bytes.
+        max: usize,
+    },
+}
+
+/// Encodes payloads into frames and decodes received data back into payloads.
+///
+/// Decoding works like a stream decoder: received data is passed to [`Framing::received`] in
+/// chunks of any size, and complete payloads are then taken out with [`Framing::decode`] until
+/// it returns `None`.
+pub trait Framing: fmt::Debug + Send {
+    /// Appends the frame for `payload` to `out`.
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<(), FrameError>;
+
+    /// Adds data received from the channel to the decoder.
+    fn received(&mut self, data: &[u8]);
+
+    /// Returns the next complete payload, or `None` if more data is needed.
+    ///
+    /// After an error, decoding continues with the next frame if possible.
+    fn decode(&mut self) -> Option<Result<Vec<u8>, FrameError>>;
+}
+
+/// The framings built into this crate, e.g. to select one in a configuration file.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum FrameFormat {
+    /// See [`Cobs`].
+    Cobs,
+    /// See [`LengthPrefixed`], with an 8 bit length.
+    LengthPrefixedU8,
+    /// See [`LengthPrefixed`], with a 16 bit length.
+    LengthPrefixedU16,
+    /// See [`LengthPrefixed`], with a 32 bit length.
+    LengthPrefixedU32,
+}
+
+impl FrameFormat {
+    /// Creates the framing for this format, using [`DEFAULT_MAX_FRAME_SIZE`].
+    pub fn framing(self) -> Box<dyn Framing> {
+        match self {
+            FrameFormat::Cobs => Box::<Cobs>::default(),
+            FrameFormat::LengthPrefixedU8 => Box::new(LengthPrefixed::new(1)),
+            FrameFormat::LengthPrefixedU16 => Box::new(LengthPrefixed::new(2)),
+            FrameFormat::LengthPrefixedU32 => Box::new(LengthPrefixed::new(4)),
+        }
+    }
+}
+
+/// Consistent Overhead Byte Stuffing, with every frame terminated by a zero byte.
+///
+/// Because zero bytes only occur at the end of a frame, the decoder can always resynchronize
+/// on the next frame after data has been lost.
+#[derive(Debug)]
+pub struct Cobs {
+    buffer: Vec<u8>,
+    max_frame_size: usize,
+    /// Set after dropping the start of an oversized frame, until the end of that frame is received.
+    discarding: bool,
+}
+
+impl Cobs {
+    /// Creates a COBS framing which drops received frames larger than `max_frame_size` bytes.
+    pub fn new(max_frame_size: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            max_frame_size,
+            discarding: false,
+        }
+    }
+}
+
+impl Default for Cobs {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FRAME_SIZE)
+    }
+}
+
+impl Framing for Cobs {
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<(), FrameError> {
+        let mut code_index = out.len();
+        let mut code = 1u8;
+        out.push(0);
+
+        for (index, &byte) in payload.iter().enumerate() {
+            if byte != 0 {
+                out.push(byte);
+                code += 1;
+            }
+
+            // A full block at the end of the payload doesn't need an empty block after it.
+            let block_full = code == 0xFF && index + 1 < payload.len();
+
+            if byte == 0 || block_full {
+                out[code_index] = code;
+                code_index = out.len();
+                code = 1;
+                out.push(0);
+            }
+        }
+
+        out[code_index] = code;
+        out.push(0);
+
+        Ok(())
+    }
+
+    fn received(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    fn decode(&mut self) -> Option<Result<Vec<u8>, FrameError>> {
+        loop {
+            if self.discarding {
+                // Skip the rest of the oversized frame, up to and including its terminating zero.
+                match self.buffer.iter().position(|&byte| byte == 0) {
+                    Some(end) => {
+                        self.buffer.drain(..=end);
+                        self.discarding = false;
+                    }
+                    None => {
+                        self.buffer.clear();
+                        return None;
+                    }
+                }
+            }
+
+            let end = match self.buffer.iter().position(|&byte| byte == 0) {
+                Some(end) => end,
+                None if self.buffer.len() > self.max_frame_size => {
+                    // The frame can't be decoded anyway, so skip everything up to the next frame.
+                    let size = self.buffer.len();
+                    self.buffer.clear();
+                    self.discarding = true;
+                    return Some(Err(FrameError::TooLarge {
+                        size,
+                        max: self.max_frame_size,
+                    }));
+                }
+                None => return None,
+            };
+
+            let frame: Vec<u8> = self.buffer.drain(..=end).take(end).collect();
+
+            // Consecutive zero bytes are used by some encoders to flush the receiver.
+            if frame.is_empty() {
+                continue;
+            }
+
+            if frame.len() > self.max_frame_size {
+                return Some(Err(FrameError::TooLarge {
+                    size: frame.len(),
+                    max: self.max_frame_size,
+                }));
+            }
+
+            return Some(cobs_decode(&frame).ok_or(FrameError::InvalidCobs));
+        }
+    }
+}
+
+/// Decodes a single COBS frame, without the terminating zero byte.
+fn cobs_decode(frame: &[u8]) -> Option<Vec<u8>> {
+    let mut payload = Vec::with_capacity(frame.len());
+    let mut index = 0;
+
+    while index < frame.len() {
+        let code = frame[index] as usize;
+        if code == 0 {
+            return None;
+        }
+
+        let start = index + 1;
+        let end = index + code;
+        if end > frame.len() {
+            return None;
+        }
+
+        payload.extend_from_slice(&frame[start..end]);
+        index = end;
+
+        if code < 0xFF && index < frame.len() {
+            payload.push(0);
+        }
+    }
+
+    Some(payload)
+}
+
+/// Every frame starts with the length of the payload as a little endian integer.
+///
+/// Unlike [`Cobs`], this framing can't resynchronize after data has been lost, so it should only
+/// be used on channels in [`ChannelMode::BlockIfFull`](crate::ChannelMode::BlockIfFull).
+#[derive(Debug)]
+pub struct LengthPrefixed {
+    buffer: Vec<u8>,
+    width: usize,
+    max_frame_size: usize,
+}
+
+impl LengthPrefixed {
+    /// Creates a framing with a length prefix of `width` bytes, which must be 1, 2 or 4.
+    ///
+    /// The maximum frame size is [`DEFAULT_MAX_FRAME_SIZE`], or less if the length prefix is
+    /// too small for it.
+    pub fn new(width: usize) -> Self {
+        assert!(
+            matches!(width, 1 | 2 | 4),
+            "Length prefix must be 1, 2 or 4 bytes wide"
+        );
+
+        let max_length = (1u64 << (8 * width)) - 1;
+
+        Self {
+            buffer: Vec::new(),
+            width,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE.min(max_length as usize),
+        }
+    }
+
+    /// Sets the largest frame which is accepted when decoding.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+}
+
+impl Framing for LengthPrefixed {
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<(), FrameError> {
+        let max = ((1u64 << (8 * self.width)) - 1) as usize;
+        if payload.len() > max {
+            return Err(FrameError::TooLarge {
+                size: payload.len(),
+                max,
+            });
+        }
+
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes()[..self.width]);
+        out.extend_from_slice(payload);
+
+        Ok(())
+    }
+
+    fn received(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    fn decode(&mut self) -> Option<Result<Vec<u8>, FrameError>> {
+        if self.buffer.len() < self.width {
+            return None;
+        }
+
+        let mut length = [0u8; 4];
+        length[..self.width].copy_from_slice(&self.buffer[..self.width]);
+        let length = u32::from_le_bytes(length) as usize;
+
+        if length > self.max_frame_size {
+            // There is no way to find the start of the next frame, so drop everything received.
+            self.buffer.clear();
+            return Some(Err(FrameError::TooLarge {
+                size: length,
+                max: self.max_frame_size,
+            }));
+        }
+
+        if self.buffer.len() < self.width + length {
+            return None;
+        }
+
+        let frame = self
+            .buffer
+            .drain(..self.width + length)
+            .skip(self.width)
+            .collect();
+
+        Some(Ok(frame))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode(framing: &dyn Framing, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        framing.encode(payload, &mut frame).unwrap();
+        frame
+    }
+
+    fn decode_all(framing: &mut dyn Framing) -> Vec<Result<Vec<u8>, FrameError>> {
+        std::iter::from_fn(|| framing.decode()).collect()
+    }
+
+    #[test]
+    fn cobs_encoding() {
+        let framing = Cobs::default();
+
+        assert_eq!(encode(&framing, &[]), [0x01, 0x00]);
+        assert_eq!(encode(&framing, &[0x00]), [0x01, 0x01, 0x00]);
+        assert_eq!(
+            encode(&framing, &[0x11, 0x00, 0x22]),
+            [0x02, 0x11, 0x02, 0x22, 0x00]
+        );
+    }
+
+    #[test]
+    fn cobs_round_trip() {
+        let block: Vec<u8> = (1..=254).collect();
+        let payloads = [
+            vec![],
+            vec![0x00],
+            vec![0x00, 0x00],
+            vec![0x01, 0x00, 0x02, 0x00],
+            // One byte less than, exactly, and one byte more than a full block of 254 bytes
+            block[..253].to_vec(),
+            block.clone(),
+            [&block[..], &[0x01]].concat(),
+            [&block[..], &[0x00]].concat(),
+            [&block[..], &[0x00], &block[..]].concat(),
+        ];
+
+        let mut framing = Cobs::default();
+        for payload in &payloads {
+            let frame = encode(&framing, payload);
+            assert_eq!(frame.iter().position(|&b| b == 0), Some(frame.len() - 1));
+
+            // Feed the frame byte by byte, so it's decoded incrementally
+            for byte in &frame {
+                framing.received(&[*byte]);
+            }
+
+            assert_eq!(decode_all(&mut framing), vec![Ok(payload.clone())]);
+        }
+    }
+
+    #[test]
+    fn cobs_full_block() {
+        let block: Vec<u8> = (1..=254).collect();
+        let frame = encode(&Cobs::default(), &block);
+
+        assert_eq!(frame.len(), 256);
+        assert_eq!(frame[0], 0xFF);
+        assert_eq!(&frame[1..255], &block[..]);
+        assert_eq!(frame[255], 0x00);
+    }
+
+    #[test]
+    fn cobs_oversized_frame() {
+        let mut framing = Cobs::new(8);
+
+        // The start of an oversized frame is dropped as soon as it exceeds the limit
+        framing.received(&[0x0C; 10]);
+        assert_eq!(
+            decode_all(&mut framing),
+            vec![Err(FrameError::TooLarge { size: 10, max: 8 })]
+        );
+
+        // The rest of it is discarded as well, instead of being decoded as a frame
+        framing.received(&[0x0C; 3]);
+        assert_eq!(decode_all(&mut framing), vec![]);
+
+        let mut data = vec![0x0C, 0x00];
+        data.extend(encode(&framing, &[0x01, 0x02]));
+        framing.received(&data);
+        assert_eq!(decode_all(&mut framing), vec![Ok(vec![0x01, 0x02])]);
+    }
+
+    #[test]
+    fn cobs_oversized_complete_frame() {
+        let mut framing = Cobs::new(8);
+
+        let mut data = encode(&framing, &[0x01; 10]);
+        data.extend(encode(&framing, &[0x02; 8]));
+        framing.received(&data);
+
+        assert_eq!(
+            decode_all(&mut framing),
+            vec![
+                Err(FrameError::TooLarge { size: 11, max: 8 }),
+                Err(FrameError::TooLarge { size: 9, max: 8 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn cobs_invalid_frame() {
+        let mut framing = Cobs::default();
+
+        framing.received(&[0x05, 0x01, 0x00, 0x02, 0x02, 0x00]);
+
+        assert_eq!(
+            decode_all(&mut framing),
+            vec![Err(FrameError::InvalidCobs), Ok(vec![0x02])]
+        );
+    }
+
+    #[test]
+    fn length_prefixed_round_trip() {
+        for width in [1, 2, 4] {
+            let mut framing = LengthPrefixed::new(width);
+
+            let payloads = [vec![], vec![0x00], vec![0x01, 0x02, 0x03]];
+            for payload in &payloads {
+                let frame = encode(&framing, payload);
+                assert_eq!(frame.len(), width + payload.len());
+
+                for byte in &frame {
+                    framing.received(&[*byte]);
+                }
+            }
+
+            let decoded: Vec<_> = payloads.iter().cloned().map(Ok).collect();
+            assert_eq!(decode_all(&mut framing), decoded);
+        }
+    }
+
+    #[test]
+    fn length_prefixed_max_size() {
+        let mut framing = LengthPrefixed::new(1);
+
+        let frame = encode(&framing, &[0xAA; 255]);
+        assert_eq!(frame[0], 0xFF);
+
+        framing.received(&frame);
+        assert_eq!(decode_all(&mut framing), vec![Ok(vec![0xAA; 255])]);
+
+        let mut framing = LengthPrefixed::new(2);
+        let frame = encode(&framing, &[0xAA; 0x1234]);
+        assert_eq!(frame[..2], [0x34, 0x12]);
+    }
+
+    #[test]
+    fn length_prefixed_overflow() {
+        let mut out = Vec::new();
+
+        assert_eq!(
+            LengthPrefixed::new(1).encode(&[0; 256], &mut out),
+            Err(FrameError::TooLarge {
+                size: 256,
+                max: 255
+            })
+        );
+        assert_eq!(
+            LengthPrefixed::new(2).encode(&[0; 0x10000], &mut out),
+            Err(FrameError::TooLarge {
+                size: 0x10000,
+                max: 0xFFFF
+            })
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn length_prefixed_oversized_frame() {
+        let mut framing = LengthPrefixed::new(2).with_max_frame_size(4);
+
+        framing.received(&[0x05, 0x00, 0x01, 0x02]);
+        assert_eq!(
+            decode_all(&mut framing),
+            vec![Err(FrameError::TooLarge { size: 5, max: 4 })]
+        );
+
+        framing.received(&[0x01, 0x00, 0x03]);
+        assert_eq!(decode_all(&mut framing), vec![Ok(vec![0x03])]);
+    }
+}
@@ -49,6 +49,11 @@ pub use channel::*;
 pub mod channels;
 pub use channels::Channels;
 
+pub mod bridge;
+pub use bridge::{Bridge, BridgeAddress};
+
+pub mod framing;
+
 mod rtt;
 pub use rtt::*;
 
@@ -65,9 +70,9 @@ pub enum Error {
     )]
     ControlBlockNotFound,
 
-    /// Multiple control blocks found in target memory. The data contains the control block addresses (up to 5).
+    /// Multiple control blocks found in target memory. The data contains the control block addresses.
     #[error("Multiple control blocks found in target memory.")]
-    MultipleControlBlocksFound(Vec<u32>),
+    MultipleControlBlocksFound(Vec<u64>),
 
     /// The control block has been corrupted. The data contains a detailed error.
     #[error("Control block corrupted: {0}")]
@@ -84,4 +89,21 @@ pub enum Error {
     /// Wraps errors propagated up from reading memory on the target.
     #[error("Unexpected error while reading {0} from target memory. Please report this as a bug.")]
     MemoryRead(String),
+
+    /// An error occurred on a socket of an RTT [`Bridge`].
+    #[error("RTT bridge socket error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A frame could not be encoded or written, see [`framing`].
+    #[error("RTT framing error: {0}")]
+    Framing(#[from] framing::FrameError),
+}
+
+impl Error {
+    /// Check if the error was caused by losing the connection to the probe or the target.
+    ///
+    /// See [`probe_rs::Error::is_connection_lost`].
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(self, Error::Probe(err) if err.is_connection_lost())
+    }
 }
@@ -5,11 +5,14 @@ use scroll::{Pread, LE};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::ops::Range;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// The RTT interface.
 ///
-/// Use [`Rtt::attach`] or [`Rtt::attach_region`] to attach to a probe-rs [`Core`] and detect the channels, as they were
-///     configured on the target.
+/// Use [`Rtt::attach_elf`], [`Rtt::attach`] or [`Rtt::attach_region`] to attach to a probe-rs [`Core`] and detect the channels, as they were
+///     configured on the target. If the target has more than one control block, e.g. one per core on a multi-core chip,
+///     use [`Rtt::find_control_blocks`] to get all of them and attach to each with [`ScanRegion::Exact`].
 /// The timing of when this is called is really important, or else unexpected results can be expected.
 ///
 /// ## Examples of how timing between host and target effects the results
@@ -36,7 +39,7 @@ use std::ops::Range;
 ///         * RTT Channel names are correct, but no data, or corrupted data, will be reported from RTT, because the buffer sizes are incorrect.
 #[derive(Debug)]
 pub struct Rtt {
-    ptr: u32,
+    ptr: u64,
     up_channels: Channels<UpChannel>,
     down_channels: Channels<DownChannel>,
 }
@@ -53,6 +56,9 @@ pub struct Rtt {
 //     RttChannel up_channels[max_up_channels]; // Array of up (target to host) channels.
 //     RttChannel down_channels[max_down_channels]; // array of down (host to target) channels.
 // }
+//
+// The layout of the header is the same for 32-bit and 64-bit targets, the size of the channels
+// depends on the pointer width of the target.
 
 impl Rtt {
     const RTT_ID: [u8; 16] = *b"SEGGER RTT\0\0\0\0\0\0";
@@ -66,20 +72,25 @@ impl Rtt {
     const O_MAX_DOWN_CHANNELS: usize = 20;
     const O_CHANNEL_ARRAYS: usize = 24;
 
+    // Delay between attempts to attach in `attach_elf`
+    const ATTACH_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
     fn from(
         core: &mut Core,
         memory_map: &[MemoryRegion],
         // Pointer from which to scan
-        ptr: u32,
+        ptr: u64,
         // Memory contents read in advance, starting from ptr
         mem_in: Option<&[u8]>,
+        // Width of pointers on the target in bytes
+        pointer_width: usize,
     ) -> Result<Option<Rtt>, Error> {
         let mut mem = match mem_in {
             Some(mem) => Cow::Borrowed(mem),
             None => {
                 // If memory wasn't passed in, read the minimum header size
                 let mut mem = vec![0u8; Self::MIN_SIZE];
-                core.read(ptr.into(), &mut mem)?;
+                core.read(ptr, &mut mem)?;
                 Cow::Owned(mem)
             }
         };
@@ -107,13 +118,14 @@ impl Rtt {
             )));
         }
 
-        let cb_len = Self::O_CHANNEL_ARRAYS + (max_up_channels + max_down_channels) * Channel::SIZE;
+        let channel_size = Channel::size(pointer_width);
+        let cb_len = Self::O_CHANNEL_ARRAYS + (max_up_channels + max_down_channels) * channel_size;
 
         if let Cow::Owned(mem) = &mut mem {
             // If memory wasn't passed in, read the rest of the control block
             mem.resize(cb_len, 0);
             core.read(
-                (ptr + Self::MIN_SIZE as u32).into(),
+                ptr + Self::MIN_SIZE as u64,
                 &mut mem[Self::MIN_SIZE..cb_len],
             )?;
         }
@@ -128,11 +140,16 @@ impl Rtt {
         let mut down_channels = BTreeMap::new();
 
         for i in 0..max_up_channels {
-            let offset = Self::O_CHANNEL_ARRAYS + i * Channel::SIZE;
-
-            if let Some(chan) =
-                Channel::from(core, i, memory_map, ptr + offset as u32, &mem[offset..])?
-            {
+            let offset = Self::O_CHANNEL_ARRAYS + i * channel_size;
+
+            if let Some(chan) = Channel::from(
+                core,
+                i,
+                memory_map,
+                ptr + offset as u64,
+                &mem[offset..],
+                pointer_width,
+            )? {
                 up_channels.insert(i, UpChannel(chan));
             } else {
                 tracing::warn!("Buffer for up channel {} not initialized", i);
@@ -141,11 +158,16 @@ impl Rtt {
 
         for i in 0..max_down_channels {
             let offset =
-                Self::O_CHANNEL_ARRAYS + (max_up_channels * Channel::SIZE) + i * Channel::SIZE;
-
-            if let Some(chan) =
-                Channel::from(core, i, memory_map, ptr + offset as u32, &mem[offset..])?
-            {
+                Self::O_CHANNEL_ARRAYS + (max_up_channels * channel_size) + i * channel_size;
+
+            if let Some(chan) = Channel::from(
+                core,
+                i,
+                memory_map,
+                ptr + offset as u64,
+                &mem[offset..],
+                pointer_width,
+            )? {
                 down_channels.insert(i, DownChannel(chan));
             } else {
                 tracing::warn!("Buffer for down channel {} not initialized", i);
@@ -167,21 +189,101 @@ impl Rtt {
         Self::attach_region(core, memory_map, &Default::default())
     }
 
+    /// Attaches to the RTT control block at the address of the `_SEGGER_RTT` symbol in the
+    /// firmware's ELF file, or scans the target RAM if the symbol is not present.
+    ///
+    /// Until the firmware has initialized RTT, which may take a while after a reset, the attempt
+    /// is repeated for up to `timeout`. A `timeout` of zero attempts to attach only once.
+    ///
+    /// `core` can be e.g. an owned `Core` or a shared `Rc<Core>`.
+    pub fn attach_elf(
+        core: &mut Core,
+        memory_map: &[MemoryRegion],
+        elf: &[u8],
+        timeout: Duration,
+    ) -> Result<Rtt, Error> {
+        let region = match find_rtt_symbol(elf) {
+            Some(address) => ScanRegion::Exact(address),
+            None => {
+                tracing::warn!(
+                    "No RTT control block symbol in the ELF file, scanning RAM. Does your firmware use RTT?"
+                );
+                ScanRegion::Ram
+            }
+        };
+
+        let start = Instant::now();
+        loop {
+            match Self::attach_region(core, memory_map, &region) {
+                Err(Error::ControlBlockNotFound) if start.elapsed() < timeout => {
+                    tracing::debug!("RTT control block not initialized yet, retrying");
+                    thread::sleep(Self::ATTACH_RETRY_INTERVAL);
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Attempts to detect an RTT control block in the specified RAM region(s) and returns an
     /// instance if a valid control block was found.
     ///
+    /// Fails with [`Error::MultipleControlBlocksFound`] if there is more than one control block in
+    /// the region. Use [`Rtt::find_control_blocks`] to choose between them.
+    ///
     /// `core` can be e.g. an owned `Core` or a shared `Rc<Core>`.
     pub fn attach_region(
         core: &mut Core,
         memory_map: &[MemoryRegion],
         region: &ScanRegion,
     ) -> Result<Rtt, Error> {
-        let ranges: Vec<Range<u32>> = match region {
+        let mut instances = Self::scan(core, memory_map, region)?;
+
+        if instances.is_empty() {
+            return Err(Error::ControlBlockNotFound);
+        }
+
+        if instances.len() > 1 {
+            return Err(Error::MultipleControlBlocksFound(
+                instances.into_iter().map(|i| i.ptr).collect(),
+            ));
+        }
+
+        Ok(instances.remove(0))
+    }
+
+    /// Returns the addresses of all valid RTT control blocks in the specified RAM region(s).
+    ///
+    /// Each of the control blocks can be attached to with [`ScanRegion::Exact`], e.g. using the
+    /// core which owns it on a multi-core chip.
+    pub fn find_control_blocks(
+        core: &mut Core,
+        memory_map: &[MemoryRegion],
+        region: &ScanRegion,
+    ) -> Result<Vec<u64>, Error> {
+        let instances = Self::scan(core, memory_map, region)?;
+
+        if instances.is_empty() {
+            return Err(Error::ControlBlockNotFound);
+        }
+
+        Ok(instances.into_iter().map(|i| i.ptr).collect())
+    }
+
+    /// Parses all valid control blocks in the specified RAM region(s).
+    fn scan(
+        core: &mut Core,
+        memory_map: &[MemoryRegion],
+        region: &ScanRegion,
+    ) -> Result<Vec<Rtt>, Error> {
+        let pointer_width = if core.is_64_bit() { 8 } else { 4 };
+
+        let ranges: Vec<Range<u64>> = match region {
             ScanRegion::Exact(addr) => {
                 tracing::debug!("Scanning at exact address: 0x{:X}", addr);
 
-                return Rtt::from(core, memory_map, *addr, None)?
-                    .ok_or(Error::ControlBlockNotFound);
+                return Ok(Rtt::from(core, memory_map, *addr, None, pointer_width)?
+                    .into_iter()
+                    .collect());
             }
             ScanRegion::Ram => {
                 tracing::debug!("Scanning RAM");
@@ -189,10 +291,7 @@ impl Rtt {
                 memory_map
                     .iter()
                     .filter_map(|r| match r {
-                        MemoryRegion::Ram(r) => Some(Range {
-                            start: r.range.start as u32,
-                            end: r.range.end as u32,
-                        }),
+                        MemoryRegion::Ram(r) => Some(r.range.clone()),
                         _ => None,
                     })
                     .collect()
@@ -208,46 +307,35 @@ impl Rtt {
         let mut instances: Vec<Rtt> = Vec::new();
 
         for range in ranges.iter() {
-            if range.len() < Self::MIN_SIZE {
+            let range_len = range.end.saturating_sub(range.start) as usize;
+
+            if range_len < Self::MIN_SIZE {
                 continue;
             }
 
-            mem.resize(range.len(), 0);
+            mem.resize(range_len, 0);
             {
-                core.read(range.start.into(), mem.as_mut())?;
+                core.read(range.start, mem.as_mut())?;
             }
 
             for offset in 0..(mem.len() - Self::MIN_SIZE) {
                 if let Ok(Some(rtt)) = Rtt::from(
                     core,
                     memory_map,
-                    range.start + offset as u32,
+                    range.start + offset as u64,
                     Some(&mem[offset..]),
+                    pointer_width,
                 ) {
                     instances.push(rtt);
-
-                    if instances.len() >= 5 {
-                        break;
-                    }
                 }
             }
         }
 
-        if instances.is_empty() {
-            return Err(Error::ControlBlockNotFound);
-        }
-
-        if instances.len() > 1 {
-            return Err(Error::MultipleControlBlocksFound(
-                instances.into_iter().map(|i| i.ptr).collect(),
-            ));
-        }
-
-        Ok(instances.remove(0))
+        Ok(instances)
     }
 
     /// Returns the memory address of the control block in target memory.
-    pub fn ptr(&self) -> u32 {
+    pub fn ptr(&self) -> u64 {
         self.ptr
     }
 
@@ -271,12 +359,12 @@ pub enum ScanRegion {
 
     /// Limit scanning to these memory addresses in target memory. It is up to the user to ensure
     /// that reading from this range will not read from undefined memory.
-    Range(Range<u32>),
+    Range(Range<u64>),
 
     /// Tries to find the control block starting at this exact address. It is up to the user to
     /// ensure that reading the necessary bytes after the pointer will no read from undefined
     /// memory.
-    Exact(u32),
+    Exact(u64),
 }
 
 impl Default for ScanRegion {
@@ -284,3 +372,230 @@ impl Default for ScanRegion {
         ScanRegion::Ram
     }
 }
+
+/// Name of the RTT control block symbol in the SEGGER RTT implementation and `rtt-target`.
+const RTT_SYMBOL: &str = "_SEGGER_RTT";
+
+/// Returns the address of the RTT control block from the `_SEGGER_RTT` symbol in an ELF file, or
+/// `None` if the file can't be parsed or doesn't contain the symbol.
+///
+/// Use it with [`ScanRegion::Exact`] to attach without scanning the target memory.
+pub fn find_rtt_symbol(elf: &[u8]) -> Option<u64> {
+    let binary = match goblin::elf::Elf::parse(elf) {
+        Ok(binary) => binary,
+        Err(err) => {
+            tracing::debug!("Failed to parse ELF file: {}", err);
+            return None;
+        }
+    };
+
+    binary
+        .syms
+        .iter()
+        .find_map(|sym| match binary.strtab.get_at(sym.st_name) {
+            Some(RTT_SYMBOL) => Some(sym.st_value),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::{RttChannel, UpChannel};
+    use probe_rs::config::RamRegion;
+    use probe_rs::{FakeProbe, Permissions, Probe, Session};
+
+    /// The memory of the fake probe, which is mapped at address 0.
+    const MEMORY: Range<u64> = 0..0x8000;
+
+    pub(crate) fn fake_session() -> Session {
+        Probe::from_specific_probe(Box::new(FakeProbe::new()))
+            .attach("stm32wb55ccux", Permissions::default())
+            .expect("Failed to attach with 'fake' probe.")
+    }
+
+    pub(crate) fn memory_map() -> Vec<MemoryRegion> {
+        vec![MemoryRegion::Ram(RamRegion {
+            name: None,
+            range: MEMORY,
+            is_boot_memory: false,
+            cores: vec![],
+        })]
+    }
+
+    fn push_pointer(mem: &mut Vec<u8>, pointer: u64, pointer_width: usize) {
+        mem.extend_from_slice(&pointer.to_le_bytes()[..pointer_width]);
+    }
+
+    /// Writes a control block with one up channel named "Terminal" and one unnamed down channel
+    /// to `ptr`. The buffers and the name follow the control block.
+    pub(crate) fn write_control_block(core: &mut Core, ptr: u64, pointer_width: usize) {
+        let name_ptr = ptr + 0x100;
+        let up_buffer_ptr = ptr + 0x200;
+        let down_buffer_ptr = ptr + 0x300;
+
+        let mut mem = Vec::new();
+        mem.extend_from_slice(&Rtt::RTT_ID);
+        mem.extend_from_slice(&1u32.to_le_bytes());
+        mem.extend_from_slice(&1u32.to_le_bytes());
+
+        // Up channel with 5 bytes of data
+        push_pointer(&mut mem, name_ptr, pointer_width);
+        push_pointer(&mut mem, up_buffer_ptr, pointer_width);
+        for value in [64u32, 5, 0, 2] {
+            mem.extend_from_slice(&value.to_le_bytes());
+        }
+
+        // Down channel without a name
+        push_pointer(&mut mem, 0, pointer_width);
+        push_pointer(&mut mem, down_buffer_ptr, pointer_width);
+        for value in [32u32, 0, 0, 0] {
+            mem.extend_from_slice(&value.to_le_bytes());
+        }
+
+        core.write_8(ptr, &mem).unwrap();
+        core.write_8(name_ptr, b"Terminal\0").unwrap();
+        core.write_8(up_buffer_ptr, b"hello").unwrap();
+    }
+
+    fn check_channels(core: &mut Core, rtt: &mut Rtt, ptr: u64, pointer_width: usize) {
+        assert_eq!(rtt.ptr(), ptr);
+        assert_eq!(rtt.up_channels().len(), 1);
+        assert_eq!(rtt.down_channels().len(), 1);
+
+        let up: UpChannel = rtt.up_channels().take(0).unwrap();
+        assert_eq!(up.name(), Some("Terminal"));
+        assert_eq!(up.buffer_size(), 64);
+        assert_eq!(up.mode(core).unwrap(), ChannelMode::BlockIfFull);
+
+        let mut buf = [0u8; 16];
+        assert_eq!(up.read(core, &mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"hello");
+        // The read offset follows the write offset of the up channel.
+        let read_offset = ptr + 24 + 2 * pointer_width as u64 + 8;
+        assert_eq!(core.read_word_32(read_offset).unwrap(), 5);
+
+        let down = rtt.down_channels().take(0).unwrap();
+        assert_eq!(down.name(), None);
+        assert_eq!(down.buffer_size(), 32);
+
+        assert_eq!(down.write(core, b"hi").unwrap(), 2);
+        let mut data = [0u8; 2];
+        core.read_8(ptr + 0x300, &mut data).unwrap();
+        assert_eq!(&data, b"hi");
+        let write_offset = read_offset - 4 + Channel::size(pointer_width) as u64;
+        assert_eq!(core.read_word_32(write_offset).unwrap(), 2);
+    }
+
+    #[test]
+    fn control_block_32_bit() {
+        let mut session = fake_session();
+        let mut core = session.core(0).unwrap();
+        write_control_block(&mut core, 0x1000, 4);
+
+        let mut rtt =
+            Rtt::attach_region(&mut core, &memory_map(), &ScanRegion::Exact(0x1000)).unwrap();
+        check_channels(&mut core, &mut rtt, 0x1000, 4);
+    }
+
+    #[test]
+    fn control_block_64_bit() {
+        let mut session = fake_session();
+        let mut core = session.core(0).unwrap();
+        write_control_block(&mut core, 0x1000, 8);
+
+        let mut rtt = Rtt::from(&mut core, &memory_map(), 0x1000, None, 8)
+            .unwrap()
+            .unwrap();
+        check_channels(&mut core, &mut rtt, 0x1000, 8);
+    }
+
+    #[test]
+    fn control_block_not_found() {
+        let mut session = fake_session();
+        let mut core = session.core(0).unwrap();
+
+        assert!(matches!(
+            Rtt::attach_region(&mut core, &memory_map(), &ScanRegion::Exact(0x1000)),
+            Err(Error::ControlBlockNotFound)
+        ));
+    }
+
+    /// An ELF file with the `_SEGGER_RTT` symbol at 0x2000.
+    fn elf_file() -> Vec<u8> {
+        std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/segger_rtt.elf"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn rtt_symbol() {
+        assert_eq!(find_rtt_symbol(&elf_file()), Some(0x2000));
+        assert_eq!(find_rtt_symbol(b"not an ELF file"), None);
+    }
+
+    #[test]
+    fn attach_elf_uses_rtt_symbol() {
+        let mut session = fake_session();
+        let mut core = session.core(0).unwrap();
+        write_control_block(&mut core, 0x1000, 4);
+        write_control_block(&mut core, 0x2000, 4);
+
+        // Scanning the RAM would find both control blocks
+        let mut rtt =
+            Rtt::attach_elf(&mut core, &memory_map(), &elf_file(), Duration::ZERO).unwrap();
+        check_channels(&mut core, &mut rtt, 0x2000, 4);
+    }
+
+    #[test]
+    fn attach_elf_without_rtt_symbol() {
+        let mut session = fake_session();
+        let mut core = session.core(0).unwrap();
+        write_control_block(&mut core, 0x1000, 4);
+
+        let mut rtt =
+            Rtt::attach_elf(&mut core, &memory_map(), b"not an ELF file", Duration::ZERO).unwrap();
+        check_channels(&mut core, &mut rtt, 0x1000, 4);
+    }
+
+    #[test]
+    fn attach_elf_timeout() {
+        let mut session = fake_session();
+        let mut core = session.core(0).unwrap();
+
+        let timeout = Duration::from_millis(50);
+        let start = Instant::now();
+        assert!(matches!(
+            Rtt::attach_elf(&mut core, &memory_map(), &elf_file(), timeout),
+            Err(Error::ControlBlockNotFound)
+        ));
+        assert!(start.elapsed() >= timeout);
+    }
+
+    #[test]
+    fn multiple_control_blocks() {
+        let mut session = fake_session();
+        let mut core = session.core(0).unwrap();
+        write_control_block(&mut core, 0x1000, 4);
+        write_control_block(&mut core, 0x2000, 4);
+
+        let region = ScanRegion::Range(0x1000..0x3000);
+        match Rtt::attach_region(&mut core, &memory_map(), &region) {
+            Err(Error::MultipleControlBlocksFound(addresses)) => {
+                assert_eq!(addresses, [0x1000, 0x2000])
+            }
+            other => panic!("Expected multiple control blocks, got {other:?}"),
+        }
+
+        let addresses = Rtt::find_control_blocks(&mut core, &memory_map(), &region).unwrap();
+        assert_eq!(addresses, [0x1000, 0x2000]);
+
+        for address in addresses {
+            let mut rtt =
+                Rtt::attach_region(&mut core, &memory_map(), &ScanRegion::Exact(address)).unwrap();
+            check_channels(&mut core, &mut rtt, address, 4);
+        }
+    }
+}
@@ -0,0 +1,10 @@
+# Minimal ELF file with an RTT control block at 0x2000, used by the `Rtt::attach_elf` tests.
+#
+# Built with `as segger_rtt.s -o segger_rtt.o && ld -e 0 --section-start=.bss=0x2000 segger_rtt.o -o segger_rtt.elf`.
+
+    .section .bss
+    .globl _SEGGER_RTT
+    .type _SEGGER_RTT, @object
+    .size _SEGGER_RTT, 0x78
+_SEGGER_RTT:
+    .zero 0x78
@@ -1,12 +1,22 @@
 use probe_rs::Permissions;
-use probe_rs::{config::TargetSelector, DebugProbeInfo, Probe};
-use probe_rs_rtt::{Channels, Rtt, RttChannel, ScanRegion};
+use probe_rs::{config::TargetSelector, Core, DebugProbeInfo, Probe, Session};
+use probe_rs_rtt::{
+    Bridge, BridgeAddress, Channels, DownChannel, Rtt, RttChannel, ScanRegion, UpChannel,
+};
 
 use clap::Parser;
 use std::io::prelude::*;
 use std::io::{stdin, stdout};
+use std::path::PathBuf;
 use std::sync::mpsc::{channel, Receiver};
 use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the probe to come back in a single reconnection attempt.
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The number of reconnection attempts before giving up.
+const RECONNECT_ATTEMPTS: usize = 30;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 enum ProbeInfo {
@@ -40,7 +50,7 @@ fn parse_scan_region(
         .split("..")
         .map(|p| {
             if p.starts_with("0x") || p.starts_with("0X") {
-                u32::from_str_radix(&p[2..], 16)
+                u64::from_str_radix(&p[2..], 16)
             } else {
                 p.parse()
             }
@@ -98,6 +108,33 @@ struct Opts {
         value_parser = parse_scan_region,
         help = "Memory region to scan for control block. You can specify either an exact starting address '0x1000' or a range such as '0x0000..0x1000'. Both decimal and hex are accepted.")]
     scan_region: ScanRegion,
+
+    #[clap(
+        long,
+        conflicts_with = "scan_region",
+        help = "ELF file of the firmware. The control block is located using its '_SEGGER_RTT' symbol instead of scanning memory."
+    )]
+    elf: Option<PathBuf>,
+
+    #[clap(
+        long,
+        default_value = "1000",
+        requires = "elf",
+        help = "Time in milliseconds to wait for the firmware to initialize RTT, when using '--elf'."
+    )]
+    timeout: u64,
+
+    #[clap(
+        long,
+        help = "Reconnect to the target if the connection to the probe or the target is lost, instead of exiting."
+    )]
+    reconnect: bool,
+
+    #[clap(
+        long,
+        help = "Expose all RTT channels as sockets instead of using the terminal. Channel N listens on the given TCP address with the port incremented by N, e.g. '127.0.0.1:19021', on a free port for every channel with port 0, or on the given Unix socket path with '.N' appended, e.g. 'unix:/tmp/rtt'."
+    )]
+    bridge: Option<BridgeAddress>,
 }
 
 fn main() {
@@ -171,7 +208,23 @@ fn run() -> i32 {
 
     eprintln!("Attaching to RTT...");
 
-    let mut rtt = match Rtt::attach_region(&mut core, &memory_map, &opts.scan_region) {
+    let attach_result = match &opts.elf {
+        Some(path) => match std::fs::read(path) {
+            Ok(elf) => Rtt::attach_elf(
+                &mut core,
+                &memory_map,
+                &elf,
+                Duration::from_millis(opts.timeout),
+            ),
+            Err(err) => {
+                eprintln!("Error reading {}: {err}", path.display());
+                return 1;
+            }
+        },
+        None => Rtt::attach_region(&mut core, &memory_map, &opts.scan_region),
+    };
+
+    let mut rtt = match attach_result {
         Ok(rtt) => rtt,
         Err(err) => {
             eprintln!("Error attaching to RTT: {err}");
@@ -189,6 +242,11 @@ fn run() -> i32 {
         return 0;
     }
 
+    if let Some(address) = &opts.bridge {
+        drop(core);
+        return run_bridge(&mut session, &mut rtt, address, opts.reconnect);
+    }
+
     let up_channel = if let Some(up) = opts.up {
         let chan = rtt.up_channels().take(up);
 
@@ -219,50 +277,180 @@ fn run() -> i32 {
 
     eprintln!("Found control block at 0x{:08x}", rtt.ptr());
 
+    drop(core);
+
     let mut up_buf = [0u8; 1024];
     let mut down_buf = vec![];
 
     loop {
-        if let Some(up_channel) = up_channel.as_ref() {
-            let count = match up_channel.read(&mut core, up_buf.as_mut()) {
-                Ok(count) => count,
-                Err(err) => {
-                    eprintln!("\nError reading from RTT: {err}");
+        let result = match session.core(0) {
+            Ok(mut core) => transfer(
+                &mut core,
+                up_channel.as_ref(),
+                down_channel.as_ref(),
+                &mut up_buf,
+                &mut down_buf,
+            ),
+            Err(err) => Err(err.into()),
+        };
+
+        let count = match result {
+            Ok(count) => count,
+            Err(err) if opts.reconnect && err.is_connection_lost() => {
+                if let Err(err) = reconnect(&mut session, rtt.ptr()) {
+                    eprintln!("\nError reconnecting to RTT: {err}");
                     return 1;
                 }
-            };
+                continue;
+            }
+            Err(err) => {
+                eprintln!("\nError transferring RTT data: {err}");
+                return 1;
+            }
+        };
 
-            match stdout().write_all(&up_buf[..count]) {
-                Ok(_) => {
-                    stdout().flush().ok();
-                }
-                Err(err) => {
-                    eprintln!("Error writing to stdout: {err}");
-                    return 1;
-                }
+        match stdout().write_all(&up_buf[..count]) {
+            Ok(_) => {
+                stdout().flush().ok();
+            }
+            Err(err) => {
+                eprintln!("Error writing to stdout: {err}");
+                return 1;
             }
         }
 
-        if let (Some(down_channel), Some(stdin)) = (down_channel.as_ref(), &stdin) {
+        if let Some(stdin) = &stdin {
             if let Ok(bytes) = stdin.try_recv() {
                 down_buf.extend_from_slice(bytes.as_slice());
             }
+        }
+    }
+}
+
+/// Forward all RTT channels to sockets at `address` until an error occurs.
+fn run_bridge(
+    session: &mut Session,
+    rtt: &mut Rtt,
+    address: &BridgeAddress,
+    reconnect_on_loss: bool,
+) -> i32 {
+    let ptr = rtt.ptr();
+
+    let mut bridge = match Bridge::new(rtt, address) {
+        Ok(bridge) => bridge,
+        Err(err) => {
+            eprintln!("Error creating RTT bridge: {err}");
+            return 1;
+        }
+    };
+
+    eprintln!("Found control block at 0x{ptr:08x}");
 
-            if !down_buf.is_empty() {
-                let count = match down_channel.write(&mut core, down_buf.as_mut()) {
-                    Ok(count) => count,
-                    Err(err) => {
-                        eprintln!("\nError writing to RTT: {err}");
-                        return 1;
-                    }
-                };
-
-                if count > 0 {
-                    down_buf.drain(..count);
+    for (number, address) in bridge.addresses() {
+        eprintln!("RTT channel {number} is available on {address}");
+    }
+
+    loop {
+        let result = match session.core(0) {
+            Ok(mut core) => bridge.poll(&mut core),
+            Err(err) => Err(err.into()),
+        };
+
+        match result {
+            // Don't keep the probe busy while there's nothing to transfer
+            Ok(0) => thread::sleep(Duration::from_millis(10)),
+            Ok(_) => {}
+            Err(err) if reconnect_on_loss && err.is_connection_lost() => {
+                if let Err(err) = reconnect(session, ptr) {
+                    eprintln!("\nError reconnecting to RTT: {err}");
+                    return 1;
                 }
             }
+            Err(err) => {
+                eprintln!("\nError transferring RTT data: {err}");
+                return 1;
+            }
+        }
+    }
+}
+
+/// Read from the up channel into `up_buf`, and write pending data from `down_buf` to the down channel.
+///
+/// Returns the number of bytes read from the up channel.
+fn transfer(
+    core: &mut Core,
+    up_channel: Option<&UpChannel>,
+    down_channel: Option<&DownChannel>,
+    up_buf: &mut [u8],
+    down_buf: &mut Vec<u8>,
+) -> Result<usize, probe_rs_rtt::Error> {
+    let count = match up_channel {
+        Some(up_channel) => up_channel.read(core, up_buf)?,
+        None => 0,
+    };
+
+    if let Some(down_channel) = down_channel {
+        if !down_buf.is_empty() {
+            let count = down_channel.write(core, down_buf)?;
+            down_buf.drain(..count);
+        }
+    }
+
+    Ok(count)
+}
+
+/// Reconnect to the target after the connection was lost, and wait for the
+/// RTT control block at `ptr` to be initialized again.
+///
+/// The channels stay valid, as long as the firmware on the target is not changed.
+/// Gives up after [RECONNECT_ATTEMPTS] failed attempts, or if the session can not be reconnected at all.
+fn reconnect(session: &mut Session, ptr: u64) -> Result<(), probe_rs_rtt::Error> {
+    if !session.can_reconnect() {
+        return Err(probe_rs::Error::UnableToOpenProbe(
+            "The probe was not opened using a selector, and can not be reopened",
+        )
+        .into());
+    }
+
+    eprintln!("\nConnection to the target lost, waiting for it to come back...");
+
+    let mut attempt = 1;
+    loop {
+        match session.reconnect(RECONNECT_TIMEOUT) {
+            Ok(()) => break,
+            // Opening the probe or attaching to the target failed for a reason that won't go away.
+            Err(err @ probe_rs::Error::UnableToOpenProbe(_)) => return Err(err.into()),
+            Err(err) if attempt < RECONNECT_ATTEMPTS => {
+                eprintln!(
+                    "Failed to reconnect (attempt {attempt}/{RECONNECT_ATTEMPTS}), retrying: {err}"
+                );
+                attempt += 1;
+            }
+            Err(err) => return Err(err.into()),
         }
     }
+
+    let memory_map = session.target().memory_map.clone();
+
+    // The target might have been reset, so wait for the firmware to initialize RTT again.
+    let start = Instant::now();
+    loop {
+        let mut core = session.core(0)?;
+
+        match Rtt::attach_region(&mut core, &memory_map, &ScanRegion::Exact(ptr)) {
+            Ok(_) => break,
+            Err(probe_rs_rtt::Error::ControlBlockNotFound)
+                if start.elapsed() < RECONNECT_TIMEOUT =>
+            {
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    eprintln!("Reconnected.");
+
+    Ok(())
 }
 
 fn list_probes(mut stream: impl std::io::Write, probes: &[DebugProbeInfo]) {
@@ -11,7 +11,9 @@ use probe_rs::config::{
     RawFlashAlgorithm,
 };
 use probe_rs::{Architecture, CoreType};
-use probe_rs_target::{ArmCoreAccessOptions, CoreAccessOptions, RiscvCoreAccessOptions};
+use probe_rs_target::{
+    ArmCoreAccessOptions, CoreAccessOptions, RiscvCoreAccessOptions, XtensaCoreAccessOptions,
+};
 use tokio::runtime::Builder;
 
 pub(crate) enum Kind<'a, T>
@@ -145,6 +147,7 @@ where
             cores,
             memory_map: get_mem_map(&device),
             flash_algorithms: flash_algorithm_names,
+            jtag: None,
         });
     }
 
@@ -163,11 +166,15 @@ fn create_core(processor: &Processor) -> Result<ProbeCore> {
         core_access_options: match core_type.architecture() {
             Architecture::Arm => CoreAccessOptions::Arm(ArmCoreAccessOptions {
                 ap: processor.ap,
+                ap_address: None,
                 psel: 0,
+                targetsel: None,
                 debug_base: None,
                 cti_base: None,
+                jtag_tap: None,
             }),
-            Architecture::Riscv => CoreAccessOptions::Riscv(RiscvCoreAccessOptions {}),
+            Architecture::Riscv => CoreAccessOptions::Riscv(RiscvCoreAccessOptions::default()),
+            Architecture::Xtensa => CoreAccessOptions::Xtensa(XtensaCoreAccessOptions::default()),
         },
     })
 }
@@ -188,9 +188,12 @@ fn cmd_elf(
                     core_type: CoreType::Armv6m,
                     core_access_options: CoreAccessOptions::Arm(ArmCoreAccessOptions {
                         ap: 0,
+                        ap_address: None,
                         psel: 0,
+                        targetsel: None,
                         debug_base: None,
                         cti_base: None,
+                        jtag_tap: None,
                     }),
                 }],
                 part: None,
@@ -210,6 +213,7 @@ fn cmd_elf(
                     }),
                 ],
                 flash_algorithms: vec![algorithm_name],
+                jtag: None,
             }],
             flash_algorithms: vec![algorithm],
             source: BuiltIn,